@@ -0,0 +1,147 @@
+// flow dashboard: polls /dashboard/api/summary and renders it.
+
+const TOKEN_KEY = "flow.dashboard.token";
+const POLL_MS = 5000;
+
+const $ = (id) => document.getElementById(id);
+
+function escapeHtml(value) {
+  return String(value ?? "").replace(/[&<>"']/g, (ch) => `&#${ch.charCodeAt(0)};`);
+}
+
+function ago(ms) {
+  if (!ms) return "";
+  const secs = Math.max(0, Math.round((Date.now() - ms) / 1000));
+  if (secs < 60) return `${secs}s ago`;
+  if (secs < 3600) return `${Math.round(secs / 60)}m ago`;
+  if (secs < 86400) return `${Math.round(secs / 3600)}h ago`;
+  return new Date(ms).toLocaleString();
+}
+
+function duration(ms) {
+  if (ms < 1000) return `${ms}ms`;
+  if (ms < 60000) return `${(ms / 1000).toFixed(1)}s`;
+  return `${Math.floor(ms / 60000)}m${Math.round((ms % 60000) / 1000)}s`;
+}
+
+function runStatus(run) {
+  if (!run) return '<span class="empty">-</span>';
+  const cls = run.success ? "ok" : "fail";
+  const label = run.success ? "ok" : `failed (${run.status ?? "?"})`;
+  return `<span class="${cls}">${escapeHtml(run.name)} ${label}</span> ${ago(run.started_ms)}`;
+}
+
+function rows(target, items, columns, emptyText) {
+  const el = $(target);
+  if (!items.length) {
+    el.innerHTML = `<tr><td class="empty" colspan="${columns}">${emptyText}</td></tr>`;
+    return;
+  }
+  el.innerHTML = items.join("");
+}
+
+function render(data) {
+  const select = $("project");
+  const current = select.value;
+  select.innerHTML =
+    '<option value="">All projects</option>' +
+    data.projects
+      .map((p) => `<option value="${escapeHtml(p.name)}">${escapeHtml(p.name)}</option>`)
+      .join("");
+  select.value = current;
+
+  rows(
+    "projects",
+    data.projects.map(
+      (p) => `<tr>
+        <td title="${escapeHtml(p.root)}">${escapeHtml(p.name)}</td>
+        <td>${p.running || ""}</td>
+        <td>${runStatus(p.last_run)}</td>
+        <td>${runStatus(p.last_deploy)}</td>
+      </tr>`,
+    ),
+    4,
+    "No projects",
+  );
+
+  rows(
+    "running",
+    data.running.map(
+      (t) => `<tr>
+        <td>${escapeHtml(t.project)}</td>
+        <td>${escapeHtml(t.task)}</td>
+        <td>${t.pid}</td>
+        <td>${ago(t.started_ms)}</td>
+        <td><code>${escapeHtml(t.command)}</code></td>
+      </tr>`,
+    ),
+    5,
+    "Nothing running",
+  );
+
+  rows(
+    "runs",
+    data.runs.map(
+      (r) => `<tr>
+        <td>${escapeHtml(r.kind)}</td>
+        <td>${escapeHtml(r.project)}</td>
+        <td>${escapeHtml(r.name)}</td>
+        <td class="${r.success ? "ok" : "fail"}">${r.success ? "ok" : `exit ${r.status ?? "?"}`}</td>
+        <td>${duration(r.duration_ms)}</td>
+        <td>${ago(r.started_ms)}</td>
+      </tr>`,
+    ),
+    6,
+    "No runs yet",
+  );
+
+  const logs = $("logs");
+  logs.innerHTML = data.logs.length
+    ? data.logs
+        .map(
+          (l) => `<div class="log ${l.type === "error" ? "error" : ""}"><span class="meta">${new Date(
+            l.timestamp,
+          ).toLocaleTimeString()} ${escapeHtml(l.project)}/${escapeHtml(l.service)}</span> ${escapeHtml(
+            l.content,
+          )}${l.resolved_stack || l.stack ? `\n${escapeHtml(l.resolved_stack || l.stack)}` : ""}</div>`,
+        )
+        .join("")
+    : '<p class="empty">No logs</p>';
+
+  const scope = data.scope ? ` · token for ${data.scope}` : "";
+  $("status").textContent = `updated ${new Date(data.generated_ms).toLocaleTimeString()}${scope}`;
+}
+
+async function refresh() {
+  const params = new URLSearchParams();
+  const project = $("project").value;
+  if (project) params.set("project", project);
+  const headers = {};
+  const token = localStorage.getItem(TOKEN_KEY);
+  if (token) headers.Authorization = `Bearer ${token}`;
+
+  try {
+    const resp = await fetch(`api/summary?${params}`, { headers });
+    if (resp.status === 401) {
+      $("status").textContent = "Token rejected; enter a valid token or clear it";
+      return;
+    }
+    if (!resp.ok) throw new Error(`HTTP ${resp.status}`);
+    render(await resp.json());
+  } catch (err) {
+    $("status").textContent = `Failed to load: ${err.message}`;
+  }
+}
+
+$("token").value = localStorage.getItem(TOKEN_KEY) || "";
+$("token-form").addEventListener("submit", (event) => {
+  event.preventDefault();
+  const token = $("token").value.trim();
+  if (token) localStorage.setItem(TOKEN_KEY, token);
+  else localStorage.removeItem(TOKEN_KEY);
+  refresh();
+});
+$("project").addEventListener("change", refresh);
+
+refresh();
+setInterval(refresh, POLL_MS);
@@ -0,0 +1,67 @@
+use std::hint::black_box;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use flow_ffi_host_boundary::{
+    DylibBoundary, flow_host_add_u64, flow_host_noop, rust_fn_add, rust_inline_add,
+};
+
+fn boundary(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ffi_host_boundary");
+
+    group.bench_function("rust_inline_add", |b| {
+        let mut acc = 0_u64;
+        b.iter(|| {
+            acc = rust_inline_add(black_box(acc), black_box(7));
+            acc
+        })
+    });
+    group.bench_function("rust_fn_add", |b| {
+        let mut acc = 0_u64;
+        b.iter(|| {
+            acc = rust_fn_add(black_box(acc), black_box(7));
+            acc
+        })
+    });
+    group.bench_function("rust_extern_add", |b| {
+        let mut acc = 0_u64;
+        b.iter(|| {
+            acc = flow_host_add_u64(black_box(acc), black_box(7));
+            acc
+        })
+    });
+    group.bench_function("rust_extern_noop", |b| {
+        let mut acc = 0_u64;
+        b.iter(|| {
+            acc = flow_host_noop(black_box(acc));
+            acc
+        })
+    });
+
+    match DylibBoundary::locate().map(|path| DylibBoundary::open(&path)) {
+        Some(Ok(dylib)) => {
+            let add = dylib.add;
+            let noop = dylib.noop;
+            group.bench_function("rust_dlopen_add", |b| {
+                let mut acc = 0_u64;
+                b.iter(|| {
+                    acc = black_box(add)(black_box(acc), black_box(7));
+                    acc
+                })
+            });
+            group.bench_function("rust_dlopen_noop", |b| {
+                let mut acc = 0_u64;
+                b.iter(|| {
+                    acc = black_box(noop)(black_box(acc));
+                    acc
+                })
+            });
+        }
+        Some(Err(err)) => eprintln!("skipping dlopen benches: {err}"),
+        None => eprintln!("skipping dlopen benches: cdylib not found (set FLOW_FFI_DYLIB)"),
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, boundary);
+criterion_main!(benches);
@@ -1,6 +1,9 @@
 use std::hint::black_box;
 
-use flow_ffi_host_boundary::{flow_host_add_u64, flow_host_noop, monotonic_now_ns, rust_fn_add, rust_inline_add};
+use flow_ffi_host_boundary::{
+    DylibBoundary, flow_host_add_u64, flow_host_noop, monotonic_now_ns, rust_fn_add,
+    rust_inline_add,
+};
 
 #[derive(Debug)]
 struct BenchResult {
@@ -57,6 +60,26 @@ fn bench_noop(iterations: u64) -> BenchResult {
     finish("rust_extern_noop", iterations, start, acc)
 }
 
+fn bench_dylib_add(dylib: &DylibBoundary, iterations: u64) -> BenchResult {
+    let add = black_box(dylib.add);
+    let mut acc = black_box(0_u64);
+    let start = monotonic_now_ns();
+    for i in 0..iterations {
+        acc = black_box(add(black_box(acc), black_box(i)));
+    }
+    finish("rust_dlopen_add", iterations, start, acc)
+}
+
+fn bench_dylib_noop(dylib: &DylibBoundary, iterations: u64) -> BenchResult {
+    let noop = black_box(dylib.noop);
+    let mut acc = black_box(0_u64);
+    let start = monotonic_now_ns();
+    for _ in 0..iterations {
+        acc = black_box(noop(black_box(acc)));
+    }
+    finish("rust_dlopen_noop", iterations, start, acc)
+}
+
 fn parse_iters() -> u64 {
     let mut args = std::env::args().skip(1);
     while let Some(arg) = args.next() {
@@ -80,17 +103,47 @@ fn print_result(result: &BenchResult) {
     );
 }
 
+/// One JSON object on stdout, for scripts/bench-ffi-regress.py.
+fn print_json(iterations: u64, results: &[BenchResult]) {
+    let ns_per_op = results
+        .iter()
+        .map(|r| format!("\"{}\":{:.4}", r.label, r.ns_per_op))
+        .collect::<Vec<_>>()
+        .join(",");
+    println!(
+        "{{\"iterations\":{},\"ns_per_op\":{{{}}}}}",
+        iterations, ns_per_op
+    );
+}
+
 fn main() {
     let iterations = parse_iters();
-    println!("rust_boundary_bench iterations={}", iterations);
+    let json = std::env::args().any(|arg| arg == "--json");
+    if !json {
+        println!("rust_boundary_bench iterations={}", iterations);
+    }
 
-    let inline = bench_inline_add(iterations);
-    let fn_call = bench_fn_add(iterations);
-    let extern_call = bench_extern_add(iterations);
-    let noop = bench_noop(iterations);
+    let mut results = vec![
+        bench_inline_add(iterations),
+        bench_fn_add(iterations),
+        bench_extern_add(iterations),
+        bench_noop(iterations),
+    ];
 
-    print_result(&inline);
-    print_result(&fn_call);
-    print_result(&extern_call);
-    print_result(&noop);
+    match DylibBoundary::locate().map(|path| DylibBoundary::open(&path)) {
+        Some(Ok(dylib)) => {
+            results.push(bench_dylib_add(&dylib, iterations));
+            results.push(bench_dylib_noop(&dylib, iterations));
+        }
+        Some(Err(err)) => eprintln!("skipping dlopen benches: {err}"),
+        None => eprintln!("skipping dlopen benches: cdylib not found (set FLOW_FFI_DYLIB)"),
+    }
+
+    if json {
+        print_json(iterations, &results);
+    } else {
+        for result in &results {
+            print_result(result);
+        }
+    }
 }
@@ -1,4 +1,5 @@
-use std::hint::black_box;
+use std::ffi::{CString, c_void};
+use std::path::{Path, PathBuf};
 
 #[unsafe(no_mangle)]
 #[inline(never)]
@@ -48,3 +49,64 @@ pub fn monotonic_now_ns() -> u64 {
             .saturating_add(ts.tv_nsec as u64)
     }
 }
+
+type AddFn = extern "C" fn(u64, u64) -> u64;
+type NoopFn = extern "C" fn(u64) -> u64;
+
+/// Host functions resolved at runtime from the cdylib build of this crate (`dlopen`),
+/// to measure calls that go through a dynamically loaded symbol.
+pub struct DylibBoundary {
+    handle: *mut c_void,
+    pub add: AddFn,
+    pub noop: NoopFn,
+}
+
+impl DylibBoundary {
+    /// Default location: `FLOW_FFI_DYLIB`, else next to the running executable (or its
+    /// parent, for `target/release/deps` bench binaries).
+    pub fn locate() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("FLOW_FFI_DYLIB") {
+            return Some(PathBuf::from(path));
+        }
+        let file = if cfg!(target_os = "macos") {
+            "libflow_ffi_host_boundary.dylib"
+        } else {
+            "libflow_ffi_host_boundary.so"
+        };
+        let exe = std::env::current_exe().ok()?;
+        exe.ancestors()
+            .skip(1)
+            .take(2)
+            .map(|dir| dir.join(file))
+            .find(|path| path.is_file())
+    }
+
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let c_path = CString::new(path.to_string_lossy().as_bytes()).map_err(|e| e.to_string())?;
+        unsafe {
+            let handle = libc::dlopen(c_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL);
+            if handle.is_null() {
+                return Err(format!("dlopen failed for {}", path.display()));
+            }
+            let add = libc::dlsym(handle, c"flow_host_add_u64".as_ptr());
+            let noop = libc::dlsym(handle, c"flow_host_noop".as_ptr());
+            if add.is_null() || noop.is_null() {
+                libc::dlclose(handle);
+                return Err(format!("missing flow_host_* symbols in {}", path.display()));
+            }
+            Ok(Self {
+                handle,
+                add: std::mem::transmute::<*mut c_void, AddFn>(add),
+                noop: std::mem::transmute::<*mut c_void, NoopFn>(noop),
+            })
+        }
+    }
+}
+
+impl Drop for DylibBoundary {
+    fn drop(&mut self) {
+        unsafe {
+            libc::dlclose(self.handle);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! Secret scan throughput over a large synthetic diff.
+//!
+//! `cargo bench -p flow_commit_scan --bench scan_diff [-- <added lines>]` (default 50k lines).
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use flow_commit_scan::{scan_text, scan_unified_diff};
+
+const RUNS: usize = 5;
+
+/// Lines typical of a monorepo commit: code, config, and the odd secret-looking value.
+const LINES: &[&str] = &[
+    "    let total = items.iter().map(|item| item.price * item.quantity).sum::<u64>();",
+    "export function formatDate(date: Date): string { return date.toISOString().slice(0, 10); }",
+    "  \"version\": \"1.4.2\",",
+    "    // Retry the request with exponential backoff before giving up.",
+    "const API_URL = process.env.API_URL ?? \"https://api.example.com/v1\";",
+    "    if response.status_code != 200 { return Err(anyhow!(\"bad status\")); }",
+    "import { useEffect, useState } from \"react\";",
+    "password = \"correct-horse-battery\"",
+    "",
+    "    assert_eq!(parse(\"2GB\").unwrap(), 2 << 30);",
+];
+
+fn synthetic_diff(added_lines: usize) -> String {
+    let per_file = 500;
+    let mut diff = String::new();
+    for file in 0..added_lines.div_ceil(per_file) {
+        let ext = ["rs", "ts", "json"][file % 3];
+        diff.push_str(&format!(
+            "diff --git a/src/mod{file}.{ext} b/src/mod{file}.{ext}\n--- a/src/mod{file}.{ext}\n+++ b/src/mod{file}.{ext}\n@@ -0,0 +1,{per_file} @@\n"
+        ));
+        for line in 0..per_file.min(added_lines - file * per_file) {
+            diff.push('+');
+            diff.push_str(LINES[(file + line) % LINES.len()]);
+            diff.push('\n');
+        }
+    }
+    diff
+}
+
+fn best_of<T>(runs: usize, mut f: impl FnMut() -> T) -> (Duration, T) {
+    let mut best = Duration::MAX;
+    let mut last = None;
+    for _ in 0..runs {
+        let started = Instant::now();
+        let out = black_box(f());
+        best = best.min(started.elapsed());
+        last = Some(out);
+    }
+    (best, last.expect("at least one run"))
+}
+
+fn report(name: &str, lines: usize, elapsed: Duration, findings: usize) {
+    let per_sec = lines as f64 / elapsed.as_secs_f64();
+    println!(
+        "{name:<18} {lines:>7} lines  {:>8.2} ms  {:>10.0} lines/s  {findings} findings",
+        elapsed.as_secs_f64() * 1000.0,
+        per_sec
+    );
+}
+
+fn main() {
+    let added_lines = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse::<usize>().ok())
+        .unwrap_or(50_000);
+    let diff = synthetic_diff(added_lines);
+    let text: String = diff
+        .lines()
+        .filter_map(|line| line.strip_prefix('+'))
+        .filter(|line| !line.starts_with("++"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // Compile the patterns outside the timed runs.
+    let _ = scan_text("warmup", "warmup");
+
+    let (elapsed, findings) = best_of(RUNS, || scan_unified_diff(&diff, &[]));
+    report("scan_unified_diff", added_lines, elapsed, findings.len());
+    let (elapsed, findings) = best_of(RUNS, || scan_text(&text, "bench"));
+    report("scan_text", added_lines, elapsed, findings.len());
+}
@@ -0,0 +1,444 @@
+//! Value-level secret checks for JSON, YAML, and TOML files.
+//!
+//! The generic assignment regexes misfire on config files (`password_env: DB_PASSWORD`,
+//! `token_url = "https://..."`) and never see values in YAML block scalars or TOML
+//! multi-line strings. For these files the line scanner drops the generic patterns and this
+//! module checks each key/value pair instead: a value is reported when its key names a
+//! secret and the value itself is long, free of spaces, and varied enough to be one.
+
+use std::collections::BTreeSet;
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use super::{
+    is_placeholder_match, looks_like_identifier_reference, looks_like_secret_lookup,
+    should_ignore_secret_scan_line,
+};
+
+pub(crate) const PATTERN: &str = "Config Secret Value";
+
+const MIN_SECRET_LEN: usize = 16;
+
+/// Substrings of (snake_cased) key names that hold secrets.
+const SECRET_KEY_WORDS: &[&str] = &[
+    "secret",
+    "token",
+    "password",
+    "passwd",
+    "api_key",
+    "apikey",
+    "private_key",
+    "credential",
+    "access_key",
+    "auth_key",
+    "signing_key",
+    "encryption_key",
+];
+
+/// Key suffixes that name something about a secret rather than the secret itself.
+const NON_SECRET_KEY_SUFFIXES: &[&str] = &[
+    "_url",
+    "_uri",
+    "_endpoint",
+    "_path",
+    "_file",
+    "_dir",
+    "_env",
+    "_var",
+    "_name",
+    "_id",
+    "_type",
+    "_header",
+    "_ttl",
+    "_expiry",
+    "_expires_in",
+    "_length",
+    "_size",
+    "_count",
+    "_version",
+];
+
+/// Value prefixes for references, templates, encrypted blobs, and paths.
+const NON_SECRET_VALUE_PREFIXES: &[&str] = &[
+    "${", "{{", "$(", "<", "ENC[", "vault:", "arn:", "/", "./", "../", "~/",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Format {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    pub(crate) fn from_path(path: &str) -> Option<Self> {
+        let name = path.rsplit('/').next().unwrap_or(path).to_ascii_lowercase();
+        match name.rsplit_once('.')?.1 {
+            "json" | "jsonc" | "json5" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// A secret-looking value. `line..=last_line` (1-based) is the lines it spans;
+/// `start..end` is its byte range on `line`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ValueSpan {
+    pub line: usize,
+    pub last_line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A string value assigned to a key.
+struct Entry {
+    key: String,
+    /// Line holding the key, where ignore markers are looked up.
+    key_line: usize,
+    /// Value text; block and multi-line values have their whitespace removed.
+    value: String,
+    span: ValueSpan,
+}
+
+impl Entry {
+    fn inline(key: &str, key_line: usize, line: &str, start: usize, end: usize) -> Self {
+        Self {
+            key: key.to_string(),
+            key_line,
+            value: line[start..end].to_string(),
+            span: ValueSpan {
+                line: key_line,
+                last_line: key_line,
+                start,
+                end,
+            },
+        }
+    }
+
+    /// A value spread over `lines[first..=last]` (0-based).
+    fn block(key: &str, key_line: usize, lines: &[&str], first: usize, last: usize) -> Self {
+        let value = lines[first..=last]
+            .iter()
+            .flat_map(|line| line.split_whitespace())
+            .map(|part| part.trim_end_matches('\\'))
+            .collect();
+        let text = lines[first];
+        let start = text.len() - text.trim_start().len();
+        Self {
+            key: key.to_string(),
+            key_line,
+            value,
+            span: ValueSpan {
+                line: first + 1,
+                last_line: last + 1,
+                start,
+                end: text.trim_end().len(),
+            },
+        }
+    }
+}
+
+/// Secret-looking values in a config file's lines.
+pub(crate) fn scan(format: Format, lines: &[&str]) -> Vec<ValueSpan> {
+    let entries = match format {
+        Format::Json => json_entries(lines),
+        Format::Yaml => yaml_entries(lines),
+        Format::Toml => toml_entries(lines),
+    };
+    entries
+        .into_iter()
+        .filter(|entry| {
+            key_names_secret(&entry.key)
+                && value_looks_secret(&entry.value)
+                && !ignored(lines, entry.key_line)
+        })
+        .map(|entry| entry.span)
+        .collect()
+}
+
+fn ignored(lines: &[&str], key_line: usize) -> bool {
+    should_ignore_secret_scan_line(lines[key_line - 1])
+        || (key_line >= 2
+            && lines[key_line - 2]
+                .to_lowercase()
+                .contains("flow:secret:ignore-next"))
+}
+
+fn key_names_secret(key: &str) -> bool {
+    let key = snake_case(key);
+    let key = key.rsplit('.').next().unwrap_or(&key);
+    SECRET_KEY_WORDS.iter().any(|word| key.contains(word))
+        && !NON_SECRET_KEY_SUFFIXES
+            .iter()
+            .any(|suffix| key.ends_with(suffix))
+}
+
+/// `clientSecret` / `api-key` / `API Token` -> `client_secret` / `api_key` / `api_token`.
+fn snake_case(key: &str) -> String {
+    let mut out = String::with_capacity(key.len() + 4);
+    let mut prev_lower = false;
+    for c in key.chars() {
+        if c.is_ascii_uppercase() && prev_lower {
+            out.push('_');
+        }
+        prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        match c {
+            '-' | ' ' => out.push('_'),
+            c => out.push(c.to_ascii_lowercase()),
+        }
+    }
+    out
+}
+
+fn value_looks_secret(value: &str) -> bool {
+    let v = value.trim();
+    if v.len() < MIN_SECRET_LEN || v.chars().any(char::is_whitespace) || v.contains("://") {
+        return false;
+    }
+    if NON_SECRET_VALUE_PREFIXES
+        .iter()
+        .any(|prefix| v.starts_with(prefix))
+    {
+        return false;
+    }
+    if is_placeholder_match(v) || looks_like_identifier_reference(v) || looks_like_secret_lookup(v)
+    {
+        return false;
+    }
+    let has_letter = v.chars().any(|c| c.is_ascii_alphabetic());
+    let has_digit = v.chars().any(|c| c.is_ascii_digit());
+    let distinct: BTreeSet<char> = v.chars().collect();
+    has_letter && has_digit && distinct.len() >= 8
+}
+
+fn unquote(key: &str) -> &str {
+    key.trim_matches(|c| c == '"' || c == '\'')
+}
+
+fn json_entries(lines: &[&str]) -> Vec<Entry> {
+    static PAIR: OnceLock<Regex> = OnceLock::new();
+    let pair = PAIR.get_or_init(|| {
+        Regex::new(r#""((?:[^"\\]|\\.)*)"\s*:\s*"((?:[^"\\]|\\.)*)""#).expect("json pair regex")
+    });
+    let mut entries = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        for caps in pair.captures_iter(line) {
+            let (Some(key), Some(value)) = (caps.get(1), caps.get(2)) else {
+                continue;
+            };
+            entries.push(Entry::inline(
+                key.as_str(),
+                idx + 1,
+                line,
+                value.start(),
+                value.end(),
+            ));
+        }
+    }
+    entries
+}
+
+fn toml_entries(lines: &[&str]) -> Vec<Entry> {
+    static MULTILINE: OnceLock<Regex> = OnceLock::new();
+    static PAIR: OnceLock<Regex> = OnceLock::new();
+    let multiline = MULTILINE.get_or_init(|| {
+        Regex::new(r#"^\s*([A-Za-z0-9_.-]+|"[^"]*"|'[^']*')\s*=\s*("""|''')"#)
+            .expect("toml multiline regex")
+    });
+    let pair = PAIR.get_or_init(|| {
+        Regex::new(
+            r#"(?:^|[{,])\s*([A-Za-z0-9_.-]+|"[^"]*"|'[^']*')\s*=\s*(?:"((?:[^"\\]|\\.)*)"|'([^']*)')"#,
+        )
+        .expect("toml pair regex")
+    });
+
+    let mut entries = Vec::new();
+    let mut idx = 0;
+    while idx < lines.len() {
+        let line = lines[idx];
+        if let Some(caps) = multiline.captures(line) {
+            let key = unquote(&caps[1]);
+            let delim = caps.get(2).expect("delimiter group");
+            let rest = &line[delim.end()..];
+            if let Some(close) = rest.find(delim.as_str()) {
+                let start = delim.end();
+                entries.push(Entry::inline(key, idx + 1, line, start, start + close));
+                idx += 1;
+                continue;
+            }
+            let Some(close_idx) =
+                (idx + 1..lines.len()).find(|&i| lines[i].contains(delim.as_str()))
+            else {
+                break;
+            };
+            // Body lines, excluding the closing delimiter's line when it holds nothing else.
+            let last = if lines[close_idx].trim() == delim.as_str() {
+                close_idx - 1
+            } else {
+                close_idx
+            };
+            let first = if rest.trim().is_empty() { idx + 1 } else { idx };
+            if first <= last {
+                let mut body: Vec<&str> = lines[first..=last].to_vec();
+                if first == idx {
+                    body[0] = rest;
+                }
+                if let Some(end) = body.last_mut() {
+                    let text = *end;
+                    *end = text.split(delim.as_str()).next().unwrap_or(text);
+                }
+                let mut entry = Entry::block(key, idx + 1, &body, 0, body.len() - 1);
+                entry.span.line = first + 1;
+                entry.span.last_line = last + 1;
+                if first == idx {
+                    entry.span.start = delim.end();
+                    entry.span.end = line.trim_end().len();
+                }
+                entries.push(entry);
+            }
+            idx = close_idx + 1;
+            continue;
+        }
+        for caps in pair.captures_iter(line) {
+            let key = unquote(&caps[1]);
+            let Some(value) = caps.get(2).or_else(|| caps.get(3)) else {
+                continue;
+            };
+            entries.push(Entry::inline(
+                key,
+                idx + 1,
+                line,
+                value.start(),
+                value.end(),
+            ));
+        }
+        idx += 1;
+    }
+    entries
+}
+
+fn yaml_entries(lines: &[&str]) -> Vec<Entry> {
+    static PAIR: OnceLock<Regex> = OnceLock::new();
+    static BLOCK: OnceLock<Regex> = OnceLock::new();
+    let pair = PAIR.get_or_init(|| {
+        Regex::new(r#"^(\s*(?:-\s+)?)("[^"]*"|'[^']*'|[A-Za-z0-9_.\-]+)\s*:(?:\s+(.*))?$"#)
+            .expect("yaml pair regex")
+    });
+    let block =
+        BLOCK.get_or_init(|| Regex::new(r"^[|>][-+0-9]*\s*(?:#.*)?$").expect("yaml block regex"));
+
+    let mut entries = Vec::new();
+    let mut idx = 0;
+    while idx < lines.len() {
+        let line = lines[idx];
+        idx += 1;
+        let Some(caps) = pair.captures(line) else {
+            continue;
+        };
+        let key = unquote(&caps[2]);
+        let key_column = caps[1].len();
+        let Some(value) = caps
+            .get(3)
+            .filter(|value| !value.as_str().trim().is_empty())
+        else {
+            continue;
+        };
+        let raw = value.as_str().trim_end();
+
+        if block.is_match(raw) {
+            let body_end = (idx..lines.len())
+                .find(|&i| {
+                    let next = lines[i];
+                    !next.trim().is_empty() && next.len() - next.trim_start().len() <= key_column
+                })
+                .unwrap_or(lines.len());
+            let body: Vec<usize> = (idx..body_end)
+                .filter(|&i| !lines[i].trim().is_empty())
+                .collect();
+            if let (Some(&first), Some(&last)) = (body.first(), body.last()) {
+                entries.push(Entry::block(key, idx, lines, first, last));
+            }
+            idx = body_end;
+            continue;
+        }
+
+        let start = value.start();
+        let (start, end) = match raw.chars().next() {
+            Some(quote @ ('"' | '\'')) => match raw[1..].find(quote) {
+                Some(close) => (start + 1, start + 1 + close),
+                None => continue,
+            },
+            Some('!' | '&' | '*' | '[' | '{') | None => continue,
+            Some(_) => {
+                let plain = raw.split(" #").next().unwrap_or(raw).trim_end();
+                (start, start + plain.len())
+            }
+        };
+        entries.push(Entry::inline(key, idx, line, start, end));
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(format: Format, content: &str) -> Vec<(usize, usize, String)> {
+        let lines: Vec<&str> = content.lines().collect();
+        scan(format, &lines)
+            .into_iter()
+            .map(|span| {
+                (
+                    span.line,
+                    span.last_line,
+                    lines[span.line - 1][span.start..span.end].to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn yaml_block_scalars_and_plain_values() {
+        let content = "\
+service:
+  api_token: |
+    c2stbGl2ZS1aZjR0UTlw
+    bTNrVjhyTjJ4WTdoRDVq
+  password_env: DB_PASSWORD
+  token_url: https://auth.example.com/oauth/token
+  clientSecret: 9fQ2xLm7Rt4Vb8Nz1Kp3 # rotated monthly
+  # flow:secret:ignore-next
+  webhook_secret: 'Wq7Zr3Lx9Mb2Nc5Vt8Kp'
+  name: worker
+";
+        assert_eq!(
+            spans(Format::Yaml, content),
+            vec![
+                (3, 4, "c2stbGl2ZS1aZjR0UTlw".to_string()),
+                (7, 7, "9fQ2xLm7Rt4Vb8Nz1Kp3".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn json_and_toml_values() {
+        let json = r#"{"apiKey": "Ak93Lq0Zr7Tx2Vm5Pb8W", "api_key_env": "OPENAI_API_KEY",
+  "token": "${DEPLOY_TOKEN}", "password": "hunter2"}"#;
+        assert_eq!(
+            spans(Format::Json, json),
+            vec![(1, 1, "Ak93Lq0Zr7Tx2Vm5Pb8W".to_string())]
+        );
+
+        let toml = "[deploy]\nsigning_key = \"\"\"\nMC4CAQAwBQYDK2Vw\nBCIEIHz9Lq2Wm8Rt\n\"\"\"\nsecret_name = \"prod-db-password-2024\"\nauth = { token = 'Tk4Nz8Qw2Lp6Rv0Xm3Bj' }\n";
+        assert_eq!(
+            spans(Format::Toml, toml),
+            vec![
+                (3, 4, "MC4CAQAwBQYDK2Vw".to_string()),
+                (7, 7, "Tk4Nz8Qw2Lp6Rv0Xm3Bj".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,337 @@
+//! Inline image support on top of the cell renderer.
+//!
+//! The native renderer only knows about cells, so images are emitted out-of-band with the
+//! kitty graphics protocol or the iTerm2 inline image protocol after each frame. Terminals
+//! without either get a half-block (`▀`) approximation for raw RGBA images.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::{Color, Error, Result};
+
+const KITTY_CHUNK: usize = 4096;
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Cell rectangle in terminal coordinates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub const fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+/// Image data that can be placed into a buffer.
+#[derive(Clone, Debug)]
+pub enum Image {
+    /// Encoded image file bytes (PNG for kitty; any format iTerm2 understands).
+    Encoded(Vec<u8>),
+    /// Raw 8-bit RGBA pixels, row-major.
+    Rgba {
+        width: u32,
+        height: u32,
+        pixels: Vec<u8>,
+    },
+}
+
+impl Image {
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        std::fs::read(path).map(Image::Encoded).map_err(|err| {
+            Error::new(format!(
+                "opentui: failed to read image {}: {err}",
+                path.display()
+            ))
+        })
+    }
+
+    pub fn from_rgba(width: u32, height: u32, pixels: Vec<u8>) -> Result<Self> {
+        if pixels.len() != (width as usize) * (height as usize) * 4 {
+            return Err(Error::new(format!(
+                "opentui: expected {}x{} RGBA pixels ({} bytes), got {} bytes",
+                width,
+                height,
+                width as usize * height as usize * 4,
+                pixels.len()
+            )));
+        }
+        Ok(Image::Rgba {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    fn is_png(&self) -> bool {
+        matches!(self, Image::Encoded(bytes) if bytes.starts_with(PNG_SIGNATURE))
+    }
+}
+
+/// Inline graphics protocol supported by the attached terminal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphicsProtocol {
+    Kitty,
+    Iterm2,
+    None,
+}
+
+impl GraphicsProtocol {
+    /// Detect from the environment. `OPENTUI_GRAPHICS=kitty|iterm2|none` overrides detection.
+    pub fn detect() -> Self {
+        let var = |key: &str| std::env::var(key).ok();
+        Self::from_env(
+            var("OPENTUI_GRAPHICS").as_deref(),
+            var("TERM").as_deref(),
+            var("TERM_PROGRAM").as_deref(),
+            var("KITTY_WINDOW_ID").is_some(),
+        )
+    }
+
+    fn from_env(
+        override_value: Option<&str>,
+        term: Option<&str>,
+        term_program: Option<&str>,
+        kitty_window: bool,
+    ) -> Self {
+        match override_value.map(str::to_ascii_lowercase).as_deref() {
+            Some("kitty") => return GraphicsProtocol::Kitty,
+            Some("iterm2" | "iterm") => return GraphicsProtocol::Iterm2,
+            Some("none" | "off") => return GraphicsProtocol::None,
+            _ => {}
+        }
+        if kitty_window || term == Some("xterm-kitty") || term_program == Some("ghostty") {
+            return GraphicsProtocol::Kitty;
+        }
+        match term_program {
+            Some("iTerm.app" | "WezTerm") => GraphicsProtocol::Iterm2,
+            _ => GraphicsProtocol::None,
+        }
+    }
+}
+
+/// An image queued for output after the next render.
+pub(crate) struct Placement {
+    pub image: Image,
+    pub rect: Rect,
+}
+
+/// Write queued placements to the terminal.
+pub(crate) fn flush_placements(protocol: GraphicsProtocol, placements: &[Placement]) -> Result<()> {
+    if placements.is_empty() {
+        return Ok(());
+    }
+    let mut out = Vec::new();
+    for placement in placements {
+        let escape = match protocol {
+            GraphicsProtocol::Kitty => kitty_escape(&placement.image, placement.rect),
+            GraphicsProtocol::Iterm2 => iterm2_escape(&placement.image, placement.rect),
+            GraphicsProtocol::None => None,
+        };
+        if let Some(escape) = escape {
+            // Save cursor, move to the rect origin, draw, restore.
+            out.extend_from_slice(
+                format!(
+                    "\x1b7\x1b[{};{}H",
+                    placement.rect.y + 1,
+                    placement.rect.x + 1
+                )
+                .as_bytes(),
+            );
+            out.extend_from_slice(escape.as_bytes());
+            out.extend_from_slice(b"\x1b8");
+        }
+    }
+    let mut stdout = io::stdout().lock();
+    stdout
+        .write_all(&out)
+        .and_then(|_| stdout.flush())
+        .map_err(|err| Error::new(format!("opentui: failed to write image: {err}")))
+}
+
+/// Whether `image` can be shown with `protocol` (otherwise the caller falls back to cells).
+pub(crate) fn supports(protocol: GraphicsProtocol, image: &Image) -> bool {
+    match protocol {
+        GraphicsProtocol::Kitty => matches!(image, Image::Rgba { .. }) || image.is_png(),
+        GraphicsProtocol::Iterm2 => matches!(image, Image::Encoded(_)),
+        GraphicsProtocol::None => false,
+    }
+}
+
+fn kitty_escape(image: &Image, rect: Rect) -> Option<String> {
+    let (format, payload) = match image {
+        Image::Encoded(bytes) if image.is_png() => ("f=100".to_string(), bytes.as_slice()),
+        Image::Encoded(_) => return None,
+        Image::Rgba {
+            width,
+            height,
+            pixels,
+        } => (format!("f=32,s={width},v={height}"), pixels.as_slice()),
+    };
+    // A stable id per image lets re-renders replace the previous placement instead of stacking.
+    let id = image_id(payload);
+    let encoded = base64_encode(payload);
+    let chunks: Vec<&str> = encoded
+        .as_bytes()
+        .chunks(KITTY_CHUNK)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+    let mut out = String::new();
+    for (idx, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(idx + 1 < chunks.len());
+        if idx == 0 {
+            out.push_str(&format!(
+                "\x1b_Ga=T,{format},i={id},p=1,c={},r={},C=1,q=2,m={more};{chunk}\x1b\\",
+                rect.width, rect.height
+            ));
+        } else {
+            out.push_str(&format!("\x1b_Gm={more};{chunk}\x1b\\"));
+        }
+    }
+    Some(out)
+}
+
+fn iterm2_escape(image: &Image, rect: Rect) -> Option<String> {
+    let Image::Encoded(bytes) = image else {
+        return None;
+    };
+    Some(format!(
+        "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=1:{}\x07",
+        bytes.len(),
+        rect.width,
+        rect.height,
+        base64_encode(bytes)
+    ))
+}
+
+/// Downsample RGBA pixels to `rect` cells, two pixel rows per cell (top = fg, bottom = bg).
+pub(crate) fn half_block_cells(
+    width: u32,
+    height: u32,
+    pixels: &[u8],
+    cols: u32,
+    rows: u32,
+) -> Vec<(Color, Color)> {
+    let sample = |cx: u32, py: u32| -> Color {
+        if width == 0 || height == 0 {
+            return Color::default();
+        }
+        let x = (cx as u64 * width as u64 / cols.max(1) as u64).min(width as u64 - 1);
+        let y = (py as u64 * height as u64 / (rows.max(1) as u64 * 2)).min(height as u64 - 1);
+        let idx = ((y * width as u64 + x) * 4) as usize;
+        match pixels.get(idx..idx + 4) {
+            Some(px) => Color::rgba(
+                px[0] as f32 / 255.0,
+                px[1] as f32 / 255.0,
+                px[2] as f32 / 255.0,
+                px[3] as f32 / 255.0,
+            ),
+            None => Color::default(),
+        }
+    };
+    let mut cells = Vec::with_capacity((cols * rows) as usize);
+    for row in 0..rows {
+        for col in 0..cols {
+            cells.push((sample(col, row * 2), sample(col, row * 2 + 1)));
+        }
+    }
+    cells
+}
+
+fn image_id(bytes: &[u8]) -> u32 {
+    // FNV-1a; kitty ids must be non-zero.
+    let hash = bytes.iter().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ *byte as u32).wrapping_mul(0x01000193)
+    });
+    hash.max(1)
+}
+
+fn base64_encode(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+        out.push(ALPHABET[(n >> 18) as usize & 63] as char);
+        out.push(ALPHABET[(n >> 12) as usize & 63] as char);
+        out.push(if chunk.len() > 1 {
+            ALPHABET[(n >> 6) as usize & 63] as char
+        } else {
+            '='
+        });
+        out.push(if chunk.len() > 2 {
+            ALPHABET[n as usize & 63] as char
+        } else {
+            '='
+        });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_matches_rfc_vectors() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn detects_protocol_from_env() {
+        let detect = GraphicsProtocol::from_env;
+        assert_eq!(
+            detect(None, Some("xterm-kitty"), None, false),
+            GraphicsProtocol::Kitty
+        );
+        assert_eq!(
+            detect(None, None, Some("iTerm.app"), false),
+            GraphicsProtocol::Iterm2
+        );
+        assert_eq!(
+            detect(Some("none"), Some("xterm-kitty"), None, true),
+            GraphicsProtocol::None
+        );
+        assert_eq!(
+            detect(None, Some("xterm-256color"), None, false),
+            GraphicsProtocol::None
+        );
+    }
+
+    #[test]
+    fn kitty_escape_chunks_large_payloads() {
+        let image = Image::from_rgba(64, 64, vec![255; 64 * 64 * 4]).unwrap();
+        let escape = kitty_escape(&image, Rect::new(0, 0, 8, 4)).unwrap();
+        assert!(escape.starts_with("\x1b_Ga=T,f=32,s=64,v=64,"));
+        assert!(escape.contains("c=8,r=4"));
+        assert!(escape.contains("\x1b_Gm=1;"));
+        assert!(escape.contains("\x1b_Gm=0;"));
+    }
+
+    #[test]
+    fn half_blocks_sample_top_and_bottom_rows() {
+        // 1x2 image: red on top, blue below.
+        let pixels = vec![255, 0, 0, 255, 0, 0, 255, 255];
+        let cells = half_block_cells(1, 2, &pixels, 1, 1);
+        assert_eq!(cells.len(), 1);
+        assert_eq!(cells[0].0.r, 1.0);
+        assert_eq!(cells[0].1.b, 1.0);
+    }
+}
@@ -1,7 +1,18 @@
 use std::ffi::{CStr, CString};
 use std::fmt;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+
+mod graphics;
+mod render_loop;
+mod snapshot;
+mod text;
+
+use graphics::Placement;
+pub use graphics::{GraphicsProtocol, Image, Rect};
+pub use render_loop::{DrawCommand, DrawHandle, RenderLoop};
+use snapshot::Capture;
+pub use text::{display_width, ellipsize_to_width, pad_to_width, truncate_to_width, wrap_to_width};
 
 #[derive(Debug)]
 pub struct Error {
@@ -63,6 +74,7 @@ pub const BORDER_SIMPLE: [u32; 11] = [
 type RendererPtr = *mut std::ffi::c_void;
 type BufferPtr = *mut std::ffi::c_void;
 
+type FnGetVersion = unsafe extern "C" fn() -> *const libc::c_char;
 type FnCreateRenderer = unsafe extern "C" fn(u32, u32, bool) -> RendererPtr;
 type FnDestroyRenderer = unsafe extern "C" fn(RendererPtr);
 type FnSetupTerminal = unsafe extern "C" fn(RendererPtr, bool);
@@ -99,22 +111,72 @@ struct Inner {
     lib: *mut std::ffi::c_void,
     fns: Fns,
     path: String,
+    capabilities: Capabilities,
 }
 
+/// Entry points of the loaded libopentui. The `Option` ones are missing from older builds; the
+/// wrappers below emulate or skip them instead of refusing to load.
 struct Fns {
     create_renderer: FnCreateRenderer,
     destroy_renderer: FnDestroyRenderer,
     setup_terminal: FnSetupTerminal,
-    suspend_renderer: FnSuspendRenderer,
+    suspend_renderer: Option<FnSuspendRenderer>,
     render: FnRender,
-    clear_terminal: FnClearTerminal,
-    resize_renderer: FnResizeRenderer,
+    clear_terminal: Option<FnClearTerminal>,
+    resize_renderer: Option<FnResizeRenderer>,
     get_next_buffer: FnGetNextBuffer,
-    get_current_buffer: FnGetCurrentBuffer,
+    get_current_buffer: Option<FnGetCurrentBuffer>,
     buffer_clear: FnBufferClear,
     buffer_draw_text: FnBufferDrawText,
-    buffer_fill_rect: FnBufferFillRect,
-    buffer_draw_box: FnBufferDrawBox,
+    buffer_fill_rect: Option<FnBufferFillRect>,
+    buffer_draw_box: Option<FnBufferDrawBox>,
+}
+
+/// What the loaded libopentui supports, from `OpenTui::capabilities`.
+///
+/// A `false` feature is degraded rather than an error: `fill_rect` and `draw_box` are drawn with
+/// text cells, `current_buffer` returns the next buffer, and `suspend`, `clear_terminal`, and
+/// `resize` do nothing.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// From `getVersion`, when the library exports it.
+    pub version: Option<String>,
+    pub suspend: bool,
+    pub clear_terminal: bool,
+    pub resize: bool,
+    pub current_buffer: bool,
+    pub fill_rect: bool,
+    pub draw_box: bool,
+}
+
+impl Capabilities {
+    fn from_fns(fns: &Fns, version: Option<String>) -> Self {
+        Self {
+            version,
+            suspend: fns.suspend_renderer.is_some(),
+            clear_terminal: fns.clear_terminal.is_some(),
+            resize: fns.resize_renderer.is_some(),
+            current_buffer: fns.get_current_buffer.is_some(),
+            fill_rect: fns.buffer_fill_rect.is_some(),
+            draw_box: fns.buffer_draw_box.is_some(),
+        }
+    }
+
+    /// Symbols of degraded features, e.g. for a one-line "old libopentui" warning.
+    pub fn missing(&self) -> Vec<&'static str> {
+        [
+            (self.suspend, "suspendRenderer"),
+            (self.clear_terminal, "clearTerminal"),
+            (self.resize, "resizeRenderer"),
+            (self.current_buffer, "getCurrentBuffer"),
+            (self.fill_rect, "bufferFillRect"),
+            (self.draw_box, "bufferDrawBox"),
+        ]
+        .into_iter()
+        .filter(|(supported, _)| !supported)
+        .map(|(_, symbol)| symbol)
+        .collect()
+    }
 }
 
 impl Drop for Inner {
@@ -128,27 +190,36 @@ impl Drop for Inner {
 }
 
 impl OpenTui {
+    /// Load libopentui. Only the symbols needed to create a renderer and draw text are required;
+    /// anything newer is optional and reported by `capabilities`.
     pub fn load() -> Result<Self> {
         let (lib, path) = load_library()?;
-        let fns = unsafe {
-            Fns {
-                create_renderer: load_symbol(lib, "createRenderer")?,
-                destroy_renderer: load_symbol(lib, "destroyRenderer")?,
-                setup_terminal: load_symbol(lib, "setupTerminal")?,
-                suspend_renderer: load_symbol(lib, "suspendRenderer")?,
-                render: load_symbol(lib, "render")?,
-                clear_terminal: load_symbol(lib, "clearTerminal")?,
-                resize_renderer: load_symbol(lib, "resizeRenderer")?,
-                get_next_buffer: load_symbol(lib, "getNextBuffer")?,
-                get_current_buffer: load_symbol(lib, "getCurrentBuffer")?,
-                buffer_clear: load_symbol(lib, "bufferClear")?,
-                buffer_draw_text: load_symbol(lib, "bufferDrawText")?,
-                buffer_fill_rect: load_symbol(lib, "bufferFillRect")?,
-                buffer_draw_box: load_symbol(lib, "bufferDrawBox")?,
+        let loaded = unsafe { load_fns(lib) };
+        let fns = match loaded {
+            Ok(fns) => fns,
+            Err(err) => {
+                let _ = unsafe { dlclose(lib) };
+                return Err(err);
             }
         };
+        let version = unsafe { load_optional_symbol::<FnGetVersion>(lib, "getVersion") }.and_then(
+            |get_version| {
+                let ptr = unsafe { get_version() };
+                (!ptr.is_null()).then(|| {
+                    unsafe { CStr::from_ptr(ptr) }
+                        .to_string_lossy()
+                        .into_owned()
+                })
+            },
+        );
+        let capabilities = Capabilities::from_fns(&fns, version);
         Ok(Self {
-            inner: Arc::new(Inner { lib, fns, path }),
+            inner: Arc::new(Inner {
+                lib,
+                fns,
+                path,
+                capabilities,
+            }),
         })
     }
 
@@ -156,82 +227,207 @@ impl OpenTui {
         &self.inner.path
     }
 
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.inner.capabilities
+    }
+
+    /// Create a renderer. With `testing`, frames are also captured in memory for
+    /// `Renderer::snapshot` and no graphics protocol is used.
     pub fn create_renderer(&self, width: u32, height: u32, testing: bool) -> Result<Renderer> {
         let ptr = unsafe { (self.inner.fns.create_renderer)(width, height, testing) };
         if ptr.is_null() {
             return Err(Error::new("opentui: createRenderer returned null"));
         }
+        let graphics = if testing {
+            GraphicsProtocol::None
+        } else {
+            GraphicsProtocol::detect()
+        };
         Ok(Renderer {
-            inner: self.inner.clone(),
+            inner: Some(self.inner.clone()),
             ptr,
+            graphics,
+            images: Arc::new(Mutex::new(Vec::new())),
+            capture: testing.then(|| Arc::new(Mutex::new(Capture::new(width, height)))),
         })
     }
 }
 
 pub struct Renderer {
-    inner: Arc<Inner>,
+    /// `None` for a headless renderer.
+    inner: Option<Arc<Inner>>,
     ptr: RendererPtr,
+    graphics: GraphicsProtocol,
+    images: Arc<Mutex<Vec<Placement>>>,
+    capture: Option<Arc<Mutex<Capture>>>,
 }
 
 impl Renderer {
+    /// A renderer that draws only into memory, without loading libopentui or touching the
+    /// terminal. Read frames back with `snapshot`.
+    pub fn headless(width: u32, height: u32) -> Self {
+        Self {
+            inner: None,
+            ptr: std::ptr::null_mut(),
+            graphics: GraphicsProtocol::None,
+            images: Arc::new(Mutex::new(Vec::new())),
+            capture: Some(Arc::new(Mutex::new(Capture::new(width, height)))),
+        }
+    }
+
     pub fn setup_terminal(&self, use_alternate_screen: bool) {
-        unsafe { (self.inner.fns.setup_terminal)(self.ptr, use_alternate_screen) };
+        if let Some(inner) = &self.inner {
+            unsafe { (inner.fns.setup_terminal)(self.ptr, use_alternate_screen) };
+        }
     }
 
     pub fn suspend(&self) {
-        unsafe { (self.inner.fns.suspend_renderer)(self.ptr) };
+        if let Some(suspend_renderer) = self.fns().and_then(|fns| fns.suspend_renderer) {
+            unsafe { suspend_renderer(self.ptr) };
+        }
     }
 
     pub fn clear_terminal(&self) {
-        unsafe { (self.inner.fns.clear_terminal)(self.ptr) };
+        if let Some(clear_terminal) = self.fns().and_then(|fns| fns.clear_terminal) {
+            unsafe { clear_terminal(self.ptr) };
+        }
     }
 
     pub fn resize(&self, width: u32, height: u32) {
-        unsafe { (self.inner.fns.resize_renderer)(self.ptr, width, height) };
+        if let Some(resize_renderer) = self.fns().and_then(|fns| fns.resize_renderer) {
+            unsafe { resize_renderer(self.ptr, width, height) };
+        }
+        with_capture(&self.capture, |capture| capture.resize(width, height));
     }
 
+    /// Render the next buffer, then emit any images queued with `Buffer::draw_image`.
     pub fn render(&self, force: bool) {
-        unsafe { (self.inner.fns.render)(self.ptr, force) };
+        if let Some(inner) = &self.inner {
+            unsafe { (inner.fns.render)(self.ptr, force) };
+        }
+        with_capture(&self.capture, Capture::render);
+        let placements = match self.images.lock() {
+            Ok(mut queue) => std::mem::take(&mut *queue),
+            Err(_) => return,
+        };
+        let _ = graphics::flush_placements(self.graphics, &placements);
+    }
+
+    pub fn graphics_protocol(&self) -> GraphicsProtocol {
+        self.graphics
+    }
+
+    pub fn set_graphics_protocol(&mut self, protocol: GraphicsProtocol) {
+        self.graphics = protocol;
     }
 
     pub fn next_buffer(&self) -> Buffer {
-        let ptr = unsafe { (self.inner.fns.get_next_buffer)(self.ptr) };
-        Buffer {
-            inner: self.inner.clone(),
-            ptr,
-        }
+        let ptr = match &self.inner {
+            Some(inner) => unsafe { (inner.fns.get_next_buffer)(self.ptr) },
+            None => std::ptr::null_mut(),
+        };
+        self.buffer(ptr, false)
     }
 
+    /// The buffer on screen, or the next buffer when libopentui has no `getCurrentBuffer`.
     pub fn current_buffer(&self) -> Buffer {
-        let ptr = unsafe { (self.inner.fns.get_current_buffer)(self.ptr) };
+        if self.inner.is_none() {
+            return self.buffer(std::ptr::null_mut(), true);
+        }
+        let Some(get_current_buffer) = self.fns().and_then(|fns| fns.get_current_buffer) else {
+            return self.next_buffer();
+        };
+        let ptr = unsafe { get_current_buffer(self.ptr) };
+        self.buffer(ptr, true)
+    }
+
+    /// The last rendered frame as text, one line per row with trailing spaces trimmed.
+    /// Empty unless the renderer was created with `testing` or `headless`.
+    pub fn snapshot(&self) -> String {
+        self.read_capture(|capture| capture.current.plain())
+    }
+
+    /// The last rendered frame with 24-bit colors and attributes as ANSI SGR escapes.
+    pub fn snapshot_styled(&self) -> String {
+        self.read_capture(|capture| capture.current.styled())
+    }
+
+    fn read_capture(&self, read: impl FnOnce(&Capture) -> String) -> String {
+        match &self.capture {
+            Some(capture) => match capture.lock() {
+                Ok(capture) => read(&capture),
+                Err(poisoned) => read(&poisoned.into_inner()),
+            },
+            None => String::new(),
+        }
+    }
+
+    fn fns(&self) -> Option<&Fns> {
+        self.inner.as_deref().map(|inner| &inner.fns)
+    }
+
+    fn buffer(&self, ptr: BufferPtr, on_screen: bool) -> Buffer {
         Buffer {
             inner: self.inner.clone(),
             ptr,
+            graphics: self.graphics,
+            images: self.images.clone(),
+            capture: self.capture.clone(),
+            on_screen,
         }
     }
 }
 
 impl Drop for Renderer {
     fn drop(&mut self) {
-        unsafe {
-            (self.inner.fns.destroy_renderer)(self.ptr);
+        if let Some(inner) = &self.inner {
+            unsafe { (inner.fns.destroy_renderer)(self.ptr) };
+        }
+    }
+}
+
+fn with_capture(capture: &Option<Arc<Mutex<Capture>>>, change: impl FnOnce(&mut Capture)) {
+    if let Some(capture) = capture {
+        match capture.lock() {
+            Ok(mut capture) => change(&mut capture),
+            Err(poisoned) => change(&mut poisoned.into_inner()),
         }
     }
 }
 
 pub struct Buffer {
-    inner: Arc<Inner>,
+    /// `None` when drawing into a headless renderer.
+    inner: Option<Arc<Inner>>,
     ptr: BufferPtr,
+    graphics: GraphicsProtocol,
+    images: Arc<Mutex<Vec<Placement>>>,
+    capture: Option<Arc<Mutex<Capture>>>,
+    /// Whether this is the current (on-screen) buffer rather than the next one.
+    on_screen: bool,
 }
 
 impl Buffer {
     pub fn clear(&self, bg: Color) {
-        unsafe { (self.inner.fns.buffer_clear)(self.ptr, &bg as *const Color as *const f32) };
+        self.capture(|screen| screen.clear(bg));
+        if let Some(inner) = &self.inner {
+            unsafe { (inner.fns.buffer_clear)(self.ptr, &bg as *const Color as *const f32) };
+        }
     }
 
     pub fn fill_rect(&self, x: u32, y: u32, width: u32, height: u32, bg: Color) {
+        self.capture(|screen| screen.fill_rect(x, y, width, height, bg));
+        let Some(fns) = self.fns() else {
+            return;
+        };
+        let Some(buffer_fill_rect) = fns.buffer_fill_rect else {
+            let row = " ".repeat(width as usize);
+            for dy in 0..height {
+                self.native_draw_text(&row, x, y + dy, bg, Some(bg), ATTR_NONE);
+            }
+            return;
+        };
         unsafe {
-            (self.inner.fns.buffer_fill_rect)(
+            buffer_fill_rect(
                 self.ptr,
                 x,
                 y,
@@ -243,12 +439,28 @@ impl Buffer {
     }
 
     pub fn draw_text(&self, text: &str, x: u32, y: u32, fg: Color, bg: Option<Color>, attr: u32) {
+        self.capture(|screen| screen.draw_text(text, x, y, fg, bg, attr));
+        self.native_draw_text(text, x, y, fg, bg, attr);
+    }
+
+    fn native_draw_text(
+        &self,
+        text: &str,
+        x: u32,
+        y: u32,
+        fg: Color,
+        bg: Option<Color>,
+        attr: u32,
+    ) {
+        let Some(fns) = self.fns() else {
+            return;
+        };
         let bg_ptr = match bg {
             Some(color) => &color as *const Color as *const f32,
             None => std::ptr::null(),
         };
         unsafe {
-            (self.inner.fns.buffer_draw_text)(
+            (fns.buffer_draw_text)(
                 self.ptr,
                 text.as_ptr(),
                 text.len(),
@@ -273,12 +485,29 @@ impl Buffer {
         background: Color,
         title: Option<&str>,
     ) {
+        self.capture(|screen| {
+            for (dy, row) in box_rows(width, height, border_chars, title)
+                .iter()
+                .enumerate()
+            {
+                draw_clipped_row(row, x, y + dy as i32, |text, col, row_y| {
+                    screen.draw_text(text, col, row_y, border, Some(background), ATTR_NONE)
+                });
+            }
+        });
+        let Some(fns) = self.fns() else {
+            return;
+        };
+        let Some(buffer_draw_box) = fns.buffer_draw_box else {
+            self.draw_box_cells(x, y, width, height, border_chars, border, background, title);
+            return;
+        };
         let (title_ptr, title_len) = match title {
             Some(value) => (value.as_ptr(), value.len() as u32),
             None => (std::ptr::null(), 0),
         };
         unsafe {
-            (self.inner.fns.buffer_draw_box)(
+            buffer_draw_box(
                 self.ptr,
                 x,
                 y,
@@ -295,6 +524,156 @@ impl Buffer {
     }
 }
 
+impl Buffer {
+    /// `draw_box` for libopentui builds without `bufferDrawBox`: background, border, and title
+    /// drawn as text. Boxes starting off-screen are clipped to the visible part.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_box_cells(
+        &self,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        border_chars: &[u32; 11],
+        border: Color,
+        background: Color,
+        title: Option<&str>,
+    ) {
+        for (dy, row) in box_rows(width, height, border_chars, title)
+            .iter()
+            .enumerate()
+        {
+            draw_clipped_row(row, x, y + dy as i32, |text, col, row_y| {
+                self.native_draw_text(text, col, row_y, border, Some(background), ATTR_NONE)
+            });
+        }
+    }
+
+    fn fns(&self) -> Option<&Fns> {
+        self.inner.as_deref().map(|inner| &inner.fns)
+    }
+
+    /// Mirror a draw into the captured next or current frame, in testing mode.
+    fn capture(&self, draw: impl FnOnce(&mut snapshot::Screen)) {
+        let on_screen = self.on_screen;
+        with_capture(&self.capture, |capture| {
+            draw(if on_screen {
+                &mut capture.current
+            } else {
+                &mut capture.next
+            })
+        });
+    }
+
+    /// Draw an image into `rect`.
+    ///
+    /// With kitty or iTerm2 graphics the cells are reserved and the image is emitted after the
+    /// next `Renderer::render`. Otherwise RGBA images fall back to half-block cells; encoded
+    /// images need a graphics-capable terminal.
+    pub fn draw_image(&self, image: &Image, rect: Rect) -> Result<()> {
+        if rect.width == 0 || rect.height == 0 {
+            return Ok(());
+        }
+        if graphics::supports(self.graphics, image) {
+            self.fill_rect(rect.x, rect.y, rect.width, rect.height, Color::default());
+            let mut queue = self
+                .images
+                .lock()
+                .map_err(|_| Error::new("opentui: image queue poisoned"))?;
+            queue.push(Placement {
+                image: image.clone(),
+                rect,
+            });
+            return Ok(());
+        }
+        let Image::Rgba {
+            width,
+            height,
+            pixels,
+        } = image
+        else {
+            return Err(Error::new(
+                "opentui: terminal has no inline image support (use an RGBA image for the cell fallback)",
+            ));
+        };
+        let cells = graphics::half_block_cells(*width, *height, pixels, rect.width, rect.height);
+        for (idx, (top, bottom)) in cells.into_iter().enumerate() {
+            let col = idx as u32 % rect.width;
+            let row = idx as u32 / rect.width;
+            self.draw_text(
+                "\u{2580}",
+                rect.x + col,
+                rect.y + row,
+                top,
+                Some(bottom),
+                ATTR_NONE,
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Draw one box row at `(x, y)`, skipping rows above and columns left of the buffer.
+fn draw_clipped_row(row: &str, x: i32, y: i32, mut draw: impl FnMut(&str, u32, u32)) {
+    if y < 0 {
+        return;
+    }
+    // Skip the off-screen columns of a box that starts left of the buffer.
+    let skip = x.min(0).unsigned_abs() as usize;
+    let visible: String = row.chars().skip(skip).collect();
+    draw(&visible, x.max(0) as u32, y as u32);
+}
+
+/// Text rows of a box, using opentui's border order: top-left, top-right, bottom-left,
+/// bottom-right, horizontal, vertical, then the junction characters (unused here).
+fn box_rows(width: u32, height: u32, border_chars: &[u32; 11], title: Option<&str>) -> Vec<String> {
+    if width < 2 || height < 2 {
+        return Vec::new();
+    }
+    let ch = |idx: usize| char::from_u32(border_chars[idx]).unwrap_or(' ');
+    let inner = width as usize - 2;
+    let mut top: String = std::iter::repeat_n(ch(4), inner).collect();
+    if let Some(title) = title.filter(|title| !title.is_empty()) {
+        let title = format!(" {} ", ellipsize_to_width(title, inner.saturating_sub(4)));
+        let title_width = display_width(&title);
+        if inner > title_width {
+            let rest: String = std::iter::repeat_n(ch(4), inner - 1 - title_width).collect();
+            top = format!("{}{title}{rest}", ch(4));
+        }
+    }
+    let middle = format!("{}{}{}", ch(5), " ".repeat(inner), ch(5));
+    let mut rows = Vec::with_capacity(height as usize);
+    rows.push(format!("{}{top}{}", ch(0), ch(1)));
+    rows.extend(std::iter::repeat_n(middle, height as usize - 2));
+    rows.push(format!(
+        "{}{}{}",
+        ch(2),
+        std::iter::repeat_n(ch(4), inner).collect::<String>(),
+        ch(3)
+    ));
+    rows
+}
+
+unsafe fn load_fns(lib: *mut std::ffi::c_void) -> Result<Fns> {
+    unsafe {
+        Ok(Fns {
+            create_renderer: load_symbol(lib, "createRenderer")?,
+            destroy_renderer: load_symbol(lib, "destroyRenderer")?,
+            setup_terminal: load_symbol(lib, "setupTerminal")?,
+            suspend_renderer: load_optional_symbol(lib, "suspendRenderer"),
+            render: load_symbol(lib, "render")?,
+            clear_terminal: load_optional_symbol(lib, "clearTerminal"),
+            resize_renderer: load_optional_symbol(lib, "resizeRenderer"),
+            get_next_buffer: load_symbol(lib, "getNextBuffer")?,
+            get_current_buffer: load_optional_symbol(lib, "getCurrentBuffer"),
+            buffer_clear: load_symbol(lib, "bufferClear")?,
+            buffer_draw_text: load_symbol(lib, "bufferDrawText")?,
+            buffer_fill_rect: load_optional_symbol(lib, "bufferFillRect"),
+            buffer_draw_box: load_optional_symbol(lib, "bufferDrawBox"),
+        })
+    }
+}
+
 fn load_library() -> Result<(*mut std::ffi::c_void, String)> {
     let mut errors = Vec::new();
     for path in candidate_paths() {
@@ -390,12 +769,20 @@ fn path_to_cstring(path: &Path) -> Result<CString> {
 }
 
 unsafe fn load_symbol<T>(lib: *mut std::ffi::c_void, symbol: &str) -> Result<T> {
-    let name = CString::new(symbol).map_err(|_| Error::new("opentui: invalid symbol"))?;
+    unsafe { load_optional_symbol(lib, symbol) }.ok_or_else(|| {
+        Error::new(format!(
+            "opentui: missing symbol {symbol} (libopentui is too old)"
+        ))
+    })
+}
+
+unsafe fn load_optional_symbol<T>(lib: *mut std::ffi::c_void, symbol: &str) -> Option<T> {
+    let name = CString::new(symbol).ok()?;
     let ptr = unsafe { dlsym(lib, name.as_ptr()) };
     if ptr.is_null() {
-        return Err(Error::new(format!("opentui: missing symbol {symbol}")));
+        return None;
     }
-    Ok(unsafe { std::mem::transmute_copy(&ptr) })
+    Some(unsafe { std::mem::transmute_copy(&ptr) })
 }
 
 fn dl_error_string() -> String {
@@ -414,3 +801,68 @@ unsafe extern "C" {
     fn dlclose(handle: *mut std::ffi::c_void) -> libc::c_int;
     fn dlerror() -> *const libc::c_char;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn box_rows_draw_border_and_title() {
+        assert_eq!(
+            box_rows(10, 3, &BORDER_SIMPLE, Some("hi")),
+            ["+- hi ---+", "|        |", "+--------+"]
+        );
+        assert_eq!(
+            box_rows(4, 2, &BORDER_SIMPLE, Some("too long")),
+            ["+--+", "+--+"]
+        );
+        assert!(box_rows(1, 5, &BORDER_SIMPLE, None).is_empty());
+    }
+
+    #[test]
+    fn capabilities_list_missing_symbols() {
+        let caps = Capabilities {
+            version: Some("0.1.30".to_string()),
+            suspend: true,
+            clear_terminal: true,
+            resize: true,
+            current_buffer: true,
+            fill_rect: false,
+            draw_box: false,
+        };
+        assert_eq!(caps.missing(), ["bufferFillRect", "bufferDrawBox"]);
+        assert_eq!(Capabilities::default().missing().len(), 6);
+    }
+
+    #[test]
+    fn headless_renderer_snapshots_rendered_frames() {
+        let renderer = Renderer::headless(12, 4);
+        let buffer = renderer.next_buffer();
+        buffer.clear(Color::default());
+        buffer.draw_box(
+            0,
+            0,
+            10,
+            3,
+            &BORDER_SIMPLE,
+            0,
+            Color::rgb(1.0, 1.0, 1.0),
+            Color::default(),
+            Some("hi"),
+        );
+        buffer.draw_text("ok", 2, 1, Color::rgb(1.0, 1.0, 1.0), None, ATTR_BOLD);
+        assert_eq!(renderer.snapshot(), "\n\n\n");
+
+        renderer.render(false);
+        assert_eq!(renderer.snapshot(), "+- hi ---+\n| ok     |\n+--------+\n");
+        assert!(
+            renderer
+                .snapshot_styled()
+                .contains("\x1b[0;1;38;2;255;255;255;49mok")
+        );
+
+        renderer.resize(4, 1);
+        renderer.render(false);
+        assert_eq!(renderer.snapshot(), "");
+    }
+}
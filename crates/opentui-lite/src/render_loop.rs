@@ -0,0 +1,351 @@
+//! Background render loop with a thread-safe draw queue.
+//!
+//! `Renderer::spawn_loop` moves the renderer onto its own thread. Application code pushes
+//! `DrawCommand`s through a cloneable `DrawHandle` from any thread (or async task) and never
+//! holds a `Buffer`. The loop wakes when work is queued, waits out the rest of the frame
+//! interval, then applies everything queued so far to the next buffer and renders once, so
+//! bursts of updates coalesce into at most `fps` frames per second.
+
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::{Buffer, Color, Image, Rect, Renderer};
+
+/// One drawing operation, applied to the next buffer on the render thread.
+pub enum DrawCommand {
+    Clear(Color),
+    FillRect {
+        rect: Rect,
+        bg: Color,
+    },
+    Text {
+        text: String,
+        x: u32,
+        y: u32,
+        fg: Color,
+        bg: Option<Color>,
+        attr: u32,
+    },
+    Box {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        border_chars: [u32; 11],
+        packed_options: u32,
+        border: Color,
+        background: Color,
+        title: Option<String>,
+    },
+    Image {
+        image: Image,
+        rect: Rect,
+    },
+    /// Anything else the `Buffer` API offers.
+    Custom(Box<dyn FnOnce(&Buffer) + Send>),
+}
+
+impl DrawCommand {
+    fn apply(self, buffer: &Buffer) {
+        match self {
+            DrawCommand::Clear(bg) => buffer.clear(bg),
+            DrawCommand::FillRect { rect, bg } => {
+                buffer.fill_rect(rect.x, rect.y, rect.width, rect.height, bg)
+            }
+            DrawCommand::Text {
+                text,
+                x,
+                y,
+                fg,
+                bg,
+                attr,
+            } => buffer.draw_text(&text, x, y, fg, bg, attr),
+            DrawCommand::Box {
+                x,
+                y,
+                width,
+                height,
+                border_chars,
+                packed_options,
+                border,
+                background,
+                title,
+            } => buffer.draw_box(
+                x,
+                y,
+                width,
+                height,
+                &border_chars,
+                packed_options,
+                border,
+                background,
+                title.as_deref(),
+            ),
+            DrawCommand::Image { image, rect } => {
+                let _ = buffer.draw_image(&image, rect);
+            }
+            DrawCommand::Custom(draw) => draw(buffer),
+        }
+    }
+}
+
+/// Cloneable, thread-safe handle for queueing work on a `RenderLoop`.
+#[derive(Clone)]
+pub struct DrawHandle {
+    shared: Arc<Shared>,
+}
+
+impl DrawHandle {
+    /// Queue a command for the next frame.
+    pub fn push(&self, command: DrawCommand) {
+        self.update(|state| state.pending.push(command));
+    }
+
+    /// Replace everything queued so far with a complete frame; stale frames are skipped.
+    pub fn submit_frame(&self, commands: Vec<DrawCommand>) {
+        self.update(|state| state.pending = commands);
+    }
+
+    /// Render on the next tick even if nothing was queued; `force` redraws every cell.
+    pub fn request_render(&self, force: bool) {
+        self.update(|state| state.force |= force);
+    }
+
+    /// Resize the renderer before the next frame.
+    pub fn resize(&self, width: u32, height: u32) {
+        self.update(|state| state.resize = Some((width, height)));
+    }
+
+    fn update(&self, change: impl FnOnce(&mut State)) {
+        let mut state = self.shared.lock();
+        change(&mut state);
+        state.dirty = true;
+        self.shared.wake.notify_one();
+    }
+}
+
+/// A renderer running on its own thread. Dropping it stops the loop.
+pub struct RenderLoop {
+    handle: DrawHandle,
+    join: Option<JoinHandle<SendRenderer>>,
+}
+
+impl RenderLoop {
+    pub fn handle(&self) -> DrawHandle {
+        self.handle.clone()
+    }
+
+    /// Render whatever is still queued, stop the thread, and hand the renderer back.
+    pub fn stop(mut self) -> Renderer {
+        self.shutdown().expect("render loop already stopped").0
+    }
+
+    fn shutdown(&mut self) -> Option<SendRenderer> {
+        let join = self.join.take()?;
+        {
+            let mut state = self.handle.shared.lock();
+            state.stop = true;
+            self.handle.shared.wake.notify_one();
+        }
+        join.join().ok()
+    }
+}
+
+impl Drop for RenderLoop {
+    fn drop(&mut self) {
+        let _ = self.shutdown();
+    }
+}
+
+impl Renderer {
+    /// Move the renderer onto a background thread that renders at most `fps` frames per
+    /// second. Draw through the returned loop's `DrawHandle`.
+    pub fn spawn_loop(self, fps: u32) -> RenderLoop {
+        let shared = Arc::new(Shared::default());
+        let interval = Duration::from_secs_f64(1.0 / f64::from(fps.max(1)));
+        let renderer = SendRenderer(self);
+        let thread_shared = shared.clone();
+        let join = thread::spawn(move || run_loop(renderer, &thread_shared, interval));
+        RenderLoop {
+            handle: DrawHandle { shared },
+            join: Some(join),
+        }
+    }
+}
+
+/// Owns the renderer on the loop thread.
+struct SendRenderer(Renderer);
+
+// SAFETY: the native renderer is only ever used by one thread at a time. It moves into the
+// loop thread whole and comes back through `JoinHandle::join`; `Inner` holds a library
+// handle and function pointers that are never mutated.
+unsafe impl Send for SendRenderer {}
+
+/// Where a frame goes; the render thread draws into a `Renderer`, tests record frames.
+trait FrameTarget {
+    fn draw_frame(&mut self, frame: Frame);
+}
+
+struct Frame {
+    commands: Vec<DrawCommand>,
+    resize: Option<(u32, u32)>,
+    force: bool,
+}
+
+impl FrameTarget for SendRenderer {
+    fn draw_frame(&mut self, frame: Frame) {
+        let renderer = &self.0;
+        if let Some((width, height)) = frame.resize {
+            renderer.resize(width, height);
+        }
+        let buffer = renderer.next_buffer();
+        for command in frame.commands {
+            command.apply(&buffer);
+        }
+        renderer.render(frame.force);
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<State>,
+    wake: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[derive(Default)]
+struct State {
+    pending: Vec<DrawCommand>,
+    resize: Option<(u32, u32)>,
+    force: bool,
+    dirty: bool,
+    stop: bool,
+}
+
+impl State {
+    fn take_frame(&mut self) -> Frame {
+        self.dirty = false;
+        Frame {
+            commands: std::mem::take(&mut self.pending),
+            resize: self.resize.take(),
+            force: std::mem::take(&mut self.force),
+        }
+    }
+}
+
+fn run_loop<T: FrameTarget>(mut target: T, shared: &Shared, interval: Duration) -> T {
+    let mut last_frame: Option<Instant> = None;
+    loop {
+        let mut state = shared.lock();
+        while !state.dirty && !state.stop {
+            state = shared
+                .wake
+                .wait(state)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        if state.stop {
+            if state.dirty {
+                target.draw_frame(state.take_frame());
+            }
+            return target;
+        }
+
+        // Let more commands pile up until the frame is due.
+        if let Some(due) = last_frame.map(|at| at + interval)
+            && let Some(wait) = due.checked_duration_since(Instant::now())
+        {
+            drop(state);
+            thread::sleep(wait);
+            state = shared.lock();
+        }
+        let frame = state.take_frame();
+        drop(state);
+        target.draw_frame(frame);
+        last_frame = Some(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Command count, resize and force flag of a drawn frame.
+    type Recorded = (usize, Option<(u32, u32)>, bool);
+
+    #[derive(Default)]
+    struct Recorder {
+        frames: Vec<Recorded>,
+    }
+
+    impl FrameTarget for Recorder {
+        fn draw_frame(&mut self, frame: Frame) {
+            self.frames
+                .push((frame.commands.len(), frame.resize, frame.force));
+        }
+    }
+
+    fn text(label: &str) -> DrawCommand {
+        DrawCommand::Text {
+            text: label.to_string(),
+            x: 0,
+            y: 0,
+            fg: Color::rgb(1.0, 1.0, 1.0),
+            bg: None,
+            attr: 0,
+        }
+    }
+
+    fn spawn(interval: Duration) -> (DrawHandle, JoinHandle<Recorder>) {
+        let shared = Arc::new(Shared::default());
+        let thread_shared = shared.clone();
+        let join = thread::spawn(move || run_loop(Recorder::default(), &thread_shared, interval));
+        (DrawHandle { shared }, join)
+    }
+
+    fn stop(handle: &DrawHandle, join: JoinHandle<Recorder>) -> Recorder {
+        {
+            let mut state = handle.shared.lock();
+            state.stop = true;
+            handle.shared.wake.notify_one();
+        }
+        join.join().unwrap()
+    }
+
+    #[test]
+    fn bursts_within_a_frame_interval_coalesce() {
+        let (handle, join) = spawn(Duration::from_millis(200));
+        handle.push(text("first"));
+        thread::sleep(Duration::from_millis(50));
+        for label in ["a", "b", "c"] {
+            handle.push(text(label));
+        }
+        handle.resize(80, 24);
+        thread::sleep(Duration::from_millis(400));
+        let recorder = stop(&handle, join);
+        assert_eq!(
+            recorder.frames,
+            vec![(1, None, false), (3, Some((80, 24)), false)]
+        );
+    }
+
+    #[test]
+    fn submit_frame_replaces_stale_commands_and_stop_flushes() {
+        let (handle, join) = spawn(Duration::from_millis(200));
+        handle.push(text("frame 1"));
+        thread::sleep(Duration::from_millis(50));
+        handle.push(text("stale"));
+        handle.push(text("stale"));
+        handle.submit_frame(vec![text("frame 2")]);
+        handle.request_render(true);
+        let recorder = stop(&handle, join);
+        assert_eq!(recorder.frames, vec![(1, None, false), (1, None, true)]);
+    }
+}
@@ -0,0 +1,257 @@
+//! In-memory screen for testing renderers.
+//!
+//! Renderers created with `testing = true` (or `Renderer::headless`) mirror every `Buffer` draw
+//! into a cell grid, and `Renderer::render` keeps the drawn frame. `Renderer::snapshot` reads it
+//! back as plain text and `Renderer::snapshot_styled` with ANSI SGR escapes, so UIs can be
+//! snapshot-tested without a TTY. Wide glyphs take two cells; `draw_box` options other than the
+//! border characters and title are not modelled.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::text::grapheme_width;
+use crate::{
+    ATTR_BLINK, ATTR_BOLD, ATTR_DIM, ATTR_HIDDEN, ATTR_INVERSE, ATTR_ITALIC, ATTR_NONE,
+    ATTR_STRIKETHROUGH, ATTR_UNDERLINE, Color,
+};
+
+#[derive(Clone, Debug)]
+struct Cell {
+    /// Grapheme shown in the cell; empty for the right half of a wide glyph.
+    text: String,
+    fg: Color,
+    bg: Color,
+    attr: u32,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            text: " ".to_string(),
+            fg: Color::rgb(1.0, 1.0, 1.0),
+            bg: Color::default(),
+            attr: ATTR_NONE,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Screen {
+    width: u32,
+    height: u32,
+    cells: Vec<Cell>,
+}
+
+impl Screen {
+    pub(crate) fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![Cell::default(); width as usize * height as usize],
+        }
+    }
+
+    pub(crate) fn clear(&mut self, bg: Color) {
+        for cell in &mut self.cells {
+            *cell = Cell {
+                bg,
+                ..Cell::default()
+            };
+        }
+    }
+
+    pub(crate) fn fill_rect(&mut self, x: u32, y: u32, width: u32, height: u32, bg: Color) {
+        let row = " ".repeat(width as usize);
+        for dy in 0..height {
+            self.draw_text(&row, x, y + dy, bg, Some(bg), ATTR_NONE);
+        }
+    }
+
+    /// Write `text` from `(x, y)`, clipped at the right edge. `bg: None` keeps each cell's
+    /// background.
+    pub(crate) fn draw_text(
+        &mut self,
+        text: &str,
+        x: u32,
+        y: u32,
+        fg: Color,
+        bg: Option<Color>,
+        attr: u32,
+    ) {
+        if y >= self.height {
+            return;
+        }
+        let mut col = x;
+        for grapheme in text.graphemes(true) {
+            let width = grapheme_width(grapheme) as u32;
+            if width == 0 {
+                continue;
+            }
+            if col + width > self.width {
+                break;
+            }
+            for offset in 0..width {
+                let idx = self.index(col + offset, y);
+                let cell = &mut self.cells[idx];
+                cell.text = if offset == 0 {
+                    grapheme.to_string()
+                } else {
+                    String::new()
+                };
+                cell.fg = fg;
+                if let Some(bg) = bg {
+                    cell.bg = bg;
+                }
+                cell.attr = attr;
+            }
+            col += width;
+        }
+    }
+
+    /// Rows trimmed of trailing spaces, one per line.
+    pub(crate) fn plain(&self) -> String {
+        self.rows()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.text.as_str())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Full-width rows with an SGR sequence wherever the style changes, reset at each row end.
+    pub(crate) fn styled(&self) -> String {
+        self.rows()
+            .map(|row| {
+                let mut out = String::new();
+                let mut style = None;
+                for cell in row {
+                    let cell_style = (cell.fg, cell.bg, cell.attr);
+                    if style
+                        .is_none_or(|current: (Color, Color, u32)| !same_style(current, cell_style))
+                    {
+                        out.push_str(&sgr(cell.fg, cell.bg, cell.attr));
+                        style = Some(cell_style);
+                    }
+                    out.push_str(&cell.text);
+                }
+                if style.is_some() {
+                    out.push_str("\x1b[0m");
+                }
+                out
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &[Cell]> {
+        self.cells.chunks(self.width.max(1) as usize)
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+}
+
+/// The next buffer being drawn and the last rendered frame.
+#[derive(Debug, Default)]
+pub(crate) struct Capture {
+    pub(crate) next: Screen,
+    pub(crate) current: Screen,
+}
+
+impl Capture {
+    pub(crate) fn new(width: u32, height: u32) -> Self {
+        Self {
+            next: Screen::new(width, height),
+            current: Screen::new(width, height),
+        }
+    }
+
+    pub(crate) fn render(&mut self) {
+        self.current = self.next.clone();
+    }
+
+    pub(crate) fn resize(&mut self, width: u32, height: u32) {
+        *self = Self::new(width, height);
+    }
+}
+
+fn same_style(a: (Color, Color, u32), b: (Color, Color, u32)) -> bool {
+    rgb(a.0) == rgb(b.0) && bg_code(a.1) == bg_code(b.1) && a.2 == b.2
+}
+
+fn rgb(color: Color) -> (u8, u8, u8) {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    (channel(color.r), channel(color.g), channel(color.b))
+}
+
+/// Transparent backgrounds use the terminal default.
+fn bg_code(color: Color) -> Option<(u8, u8, u8)> {
+    (color.a > 0.0).then(|| rgb(color))
+}
+
+fn sgr(fg: Color, bg: Color, attr: u32) -> String {
+    let mut codes = vec!["0".to_string()];
+    for (flag, code) in [
+        (ATTR_BOLD, "1"),
+        (ATTR_DIM, "2"),
+        (ATTR_ITALIC, "3"),
+        (ATTR_UNDERLINE, "4"),
+        (ATTR_BLINK, "5"),
+        (ATTR_INVERSE, "7"),
+        (ATTR_HIDDEN, "8"),
+        (ATTR_STRIKETHROUGH, "9"),
+    ] {
+        if attr & flag != 0 {
+            codes.push(code.to_string());
+        }
+    }
+    let (r, g, b) = rgb(fg);
+    codes.push(format!("38;2;{r};{g};{b}"));
+    match bg_code(bg) {
+        Some((r, g, b)) => codes.push(format!("48;2;{r};{g};{b}")),
+        None => codes.push("49".to_string()),
+    }
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_wide_glyphs_and_clips_at_the_edge() {
+        let mut screen = Screen::new(8, 2);
+        screen.draw_text("a日本語", 0, 0, Color::rgb(1.0, 1.0, 1.0), None, ATTR_NONE);
+        screen.draw_text(
+            "overflowing",
+            3,
+            1,
+            Color::rgb(1.0, 1.0, 1.0),
+            None,
+            ATTR_NONE,
+        );
+        assert_eq!(screen.plain(), "a日本語\n   overf");
+    }
+
+    #[test]
+    fn styled_rows_emit_sgr_on_style_changes() {
+        let mut screen = Screen::new(4, 1);
+        screen.clear(Color::default());
+        screen.draw_text(
+            "ok",
+            0,
+            0,
+            Color::rgb(0.0, 1.0, 0.0),
+            Some(Color::rgb(0.0, 0.0, 0.0)),
+            ATTR_BOLD,
+        );
+        assert_eq!(
+            screen.styled(),
+            "\x1b[0;1;38;2;0;255;0;48;2;0;0;0mok\x1b[0;38;2;255;255;255;49m  \x1b[0m"
+        );
+    }
+}
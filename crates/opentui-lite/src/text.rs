@@ -0,0 +1,171 @@
+//! Terminal cell width measurement for text passed to `Buffer::draw_text`.
+//!
+//! Widths are computed per grapheme cluster: combining marks ride on their base character,
+//! East Asian wide/fullwidth characters take two cells, and emoji sequences (ZWJ joins,
+//! VS16 presentation, flags) are treated as a single two-cell glyph, matching how modern
+//! terminals render them. Control characters take no cells.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
+
+const ELLIPSIS: char = '\u{2026}';
+
+/// Number of terminal cells `text` occupies.
+pub fn display_width(text: &str) -> usize {
+    text.graphemes(true).map(grapheme_width).sum()
+}
+
+/// Longest prefix of `text` that fits in `max` cells, never splitting a grapheme cluster.
+pub fn truncate_to_width(text: &str, max: usize) -> &str {
+    let mut width = 0;
+    for (idx, grapheme) in text.grapheme_indices(true) {
+        width += grapheme_width(grapheme);
+        if width > max {
+            return &text[..idx];
+        }
+    }
+    text
+}
+
+/// Like [`truncate_to_width`], but ends clipped text with `…` so the cut is visible.
+pub fn ellipsize_to_width(text: &str, max: usize) -> String {
+    if display_width(text) <= max {
+        return text.to_string();
+    }
+    if max == 0 {
+        return String::new();
+    }
+    let mut out = truncate_to_width(text, max - 1).to_string();
+    out.push(ELLIPSIS);
+    out
+}
+
+/// Right-pad `text` with spaces to exactly `width` cells, truncating if it is wider.
+///
+/// A wide glyph that would straddle the edge is dropped and replaced by padding.
+pub fn pad_to_width(text: &str, width: usize) -> String {
+    let clipped = truncate_to_width(text, width);
+    let mut out = clipped.to_string();
+    out.extend(std::iter::repeat_n(' ', width - display_width(clipped)));
+    out
+}
+
+/// Word-wrap `text` into lines of at most `max` cells.
+///
+/// Breaks at whitespace where possible and splits words longer than a line at grapheme
+/// boundaries. Embedded newlines start a new line; leading indentation is kept.
+pub fn wrap_to_width(text: &str, max: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    if max == 0 {
+        return lines;
+    }
+    for paragraph in text.split('\n') {
+        let paragraph = paragraph.strip_suffix('\r').unwrap_or(paragraph);
+        let mut line = String::new();
+        let mut line_width = 0;
+        for segment in paragraph.split_word_bounds() {
+            let width = display_width(segment);
+            if segment.chars().all(char::is_whitespace) {
+                if line_width + width <= max {
+                    line.push_str(segment);
+                    line_width += width;
+                } else {
+                    // The break point: drop the whitespace and continue on the next line.
+                    push_line(&mut lines, &mut line);
+                    line_width = 0;
+                }
+                continue;
+            }
+            if line_width + width > max && line_width > 0 {
+                push_line(&mut lines, &mut line);
+                line_width = 0;
+            }
+            if width <= max {
+                line.push_str(segment);
+                line_width += width;
+                continue;
+            }
+            for grapheme in segment.graphemes(true) {
+                let width = grapheme_width(grapheme);
+                if line_width + width > max && line_width > 0 {
+                    push_line(&mut lines, &mut line);
+                    line_width = 0;
+                }
+                line.push_str(grapheme);
+                line_width += width;
+            }
+        }
+        push_line(&mut lines, &mut line);
+    }
+    lines
+}
+
+fn push_line(lines: &mut Vec<String>, line: &mut String) {
+    lines.push(line.trim_end().to_string());
+    line.clear();
+}
+
+pub(crate) fn grapheme_width(grapheme: &str) -> usize {
+    let mut chars = grapheme.chars();
+    let Some(first) = chars.next() else {
+        return 0;
+    };
+    if first.is_control() {
+        return 0;
+    }
+    let is_sequence = chars.next().is_some();
+    if is_sequence && (grapheme.contains(['\u{200D}', '\u{FE0F}']) || is_regional_indicator(first))
+    {
+        return 2;
+    }
+    first.width().unwrap_or(0)
+}
+
+fn is_regional_indicator(ch: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&ch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_wide_combining_and_emoji_text() {
+        assert_eq!(display_width("build"), 5);
+        assert_eq!(display_width("ビルド"), 6);
+        assert_eq!(display_width("cafe\u{301}"), 4);
+        assert_eq!(display_width("🚀 deploy"), 9);
+        assert_eq!(display_width("👩\u{200D}💻"), 2);
+        assert_eq!(display_width("❤\u{FE0F}"), 2);
+        assert_eq!(display_width("🇯🇵"), 2);
+        assert_eq!(display_width("a\tb"), 2);
+    }
+
+    #[test]
+    fn truncates_and_pads_on_cell_boundaries() {
+        assert_eq!(truncate_to_width("ビルド", 3), "ビ");
+        assert_eq!(truncate_to_width("cafe\u{301}s", 4), "cafe\u{301}");
+        assert_eq!(ellipsize_to_width("deploy-prod", 8), "deploy-…");
+        assert_eq!(ellipsize_to_width("ビルド", 4), "ビ…");
+        assert_eq!(ellipsize_to_width("dev", 3), "dev");
+        assert_eq!(pad_to_width("ビルド", 5), "ビル ");
+        assert_eq!(pad_to_width("ok", 4), "ok  ");
+    }
+
+    #[test]
+    fn wraps_at_words_and_splits_long_words() {
+        assert_eq!(
+            wrap_to_width("run the dev server", 8),
+            vec!["run the", "dev", "server"]
+        );
+        assert_eq!(
+            wrap_to_width("ビルドテスト", 5),
+            vec!["ビル", "ドテ", "スト"]
+        );
+        assert_eq!(
+            wrap_to_width("  indented\nnext", 20),
+            vec!["  indented", "next"]
+        );
+        assert!(wrap_to_width("anything", 0).is_empty());
+    }
+}
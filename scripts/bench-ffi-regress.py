@@ -0,0 +1,141 @@
+#!/usr/bin/env python3
+"""Record FFI host-boundary ns/op into a JSON history and fail on regressions.
+
+Runs bench/ffi_host_boundary's rust_boundary_bench (inline Rust, extern "C", and
+dlopen'd calls) and compares each metric against the last passing run in
+.flow/bench/ffi_host_boundary.jsonl. Only passing runs are appended, so re-running
+after a regression still compares against the pre-regression numbers.
+"""
+from __future__ import annotations
+
+import argparse
+import json
+import os
+import subprocess
+import time
+from pathlib import Path
+from typing import Dict
+
+
+def run(cmd: list[str], cwd: Path, env: Dict[str, str] | None = None) -> subprocess.CompletedProcess:
+    merged = os.environ.copy()
+    if env:
+        merged.update(env)
+    return subprocess.run(cmd, cwd=str(cwd), text=True, capture_output=True, env=merged, check=False)
+
+
+def git_rev(root: Path) -> str:
+    proc = run(["git", "rev-parse", "--short", "HEAD"], cwd=root)
+    return proc.stdout.strip() if proc.returncode == 0 else ""
+
+
+def load_previous(history: Path) -> dict | None:
+    if not history.exists():
+        return None
+    last = None
+    for line in history.read_text(encoding="utf-8").splitlines():
+        line = line.strip()
+        if not line:
+            continue
+        try:
+            last = json.loads(line)
+        except json.JSONDecodeError:
+            continue
+    return last
+
+
+def main() -> int:
+    parser = argparse.ArgumentParser(description="Track FFI boundary ns/op and fail on regressions.")
+    parser.add_argument("--iters", type=int, default=10_000_000)
+    parser.add_argument("--runs", type=int, default=3, help="Repeat runs; the fastest per metric is kept.")
+    parser.add_argument(
+        "--threshold",
+        type=float,
+        default=float(os.environ.get("FLOW_FFI_REGRESSION_PCT", "15")),
+        help="Allowed slowdown in percent vs the last passing run (default: 15, or FLOW_FFI_REGRESSION_PCT).",
+    )
+    parser.add_argument(
+        "--min-delta-ns",
+        type=float,
+        default=0.1,
+        help="Ignore slowdowns smaller than this many ns/op (sub-ns ops are noisy).",
+    )
+    parser.add_argument("--history-dir", default=".flow/bench")
+    parser.add_argument("--no-record", action="store_true", help="Compare only; do not append to history.")
+    args = parser.parse_args()
+
+    if args.iters <= 0 or args.runs <= 0:
+        raise SystemExit("--iters and --runs must be > 0")
+
+    root = Path(__file__).resolve().parents[1]
+    manifest = root / "bench" / "ffi_host_boundary" / "Cargo.toml"
+    history_dir = Path(args.history_dir)
+    if not history_dir.is_absolute():
+        history_dir = root / history_dir
+    history = history_dir / "ffi_host_boundary.jsonl"
+
+    build = run(["cargo", "build", "--manifest-path", str(manifest), "--release"], cwd=root)
+    if build.returncode != 0:
+        print(build.stdout)
+        print(build.stderr)
+        raise SystemExit("failed to build ffi host boundary bench")
+
+    binary = manifest.parent / "target" / "release" / "rust_boundary_bench"
+    best: Dict[str, float] = {}
+    for _ in range(args.runs):
+        proc = run([str(binary), "--iters", str(args.iters), "--json"], cwd=root)
+        if proc.returncode != 0:
+            print(proc.stdout)
+            print(proc.stderr)
+            raise SystemExit("rust_boundary_bench failed")
+        if proc.stderr.strip():
+            print(proc.stderr.strip())
+        payload = json.loads(proc.stdout.strip().splitlines()[-1])
+        for label, value in payload["ns_per_op"].items():
+            best[label] = min(value, best.get(label, value))
+
+    previous = load_previous(history)
+    prev_metrics: Dict[str, float] = (previous or {}).get("ns_per_op", {})
+
+    regressions: list[str] = []
+    print(f"{'metric':<20} {'ns/op':>10} {'prev':>10} {'change':>9}")
+    for label, value in sorted(best.items()):
+        prev = prev_metrics.get(label)
+        if prev is None or prev <= 0:
+            print(f"{label:<20} {value:>10.4f} {'-':>10} {'-':>9}")
+            continue
+        change = (value - prev) / prev * 100.0
+        print(f"{label:<20} {value:>10.4f} {prev:>10.4f} {change:>+8.1f}%")
+        if change > args.threshold and value - prev >= args.min_delta_ns:
+            regressions.append(f"{label}: {prev:.4f} -> {value:.4f} ns/op ({change:+.1f}%)")
+
+    if regressions:
+        print(f"FFI boundary regressions (> {args.threshold:.1f}% vs last passing run):")
+        for regression in regressions:
+            print(f"  - {regression}")
+        print("Not recorded; the baseline stays at the last passing run.")
+        return 1
+
+    if not args.no_record:
+        history_dir.mkdir(parents=True, exist_ok=True)
+        record = {
+            "timestamp": int(time.time()),
+            "git_rev": git_rev(root),
+            "iters": args.iters,
+            "runs": args.runs,
+            "ns_per_op": best,
+        }
+        with history.open("a", encoding="utf-8") as handle:
+            handle.write(json.dumps(record, sort_keys=True) + "\n")
+        print(f"recorded: {history}")
+
+    if previous is None:
+        print("No previous run; baseline recorded.")
+        return 0
+
+    print(f"FFI boundary within {args.threshold:.1f}% of last passing run.")
+    return 0
+
+
+if __name__ == "__main__":
+    raise SystemExit(main())
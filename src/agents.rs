@@ -14,6 +14,7 @@ use ignore::WalkBuilder;
 use serde_json::Value;
 use shell_words;
 
+use crate::ai_guard;
 use crate::cli::{AgentsAction, AgentsCommand};
 use crate::config;
 use crate::discover;
@@ -952,7 +953,7 @@ pub fn run_flow_agent_capture(prompt: &str) -> Result<String> {
         bail!("No prompt provided for flow agent.");
     }
 
-    let full_prompt = build_flow_prompt(prompt)?;
+    let full_prompt = ai_guard::guard_prompt(&build_flow_prompt(prompt)?)?;
     invoke_gen_capture(&gen_loc, &full_prompt)
 }
 
@@ -969,7 +970,7 @@ pub fn run_flow_agent_capture_streaming(prompt: &str) -> Result<String> {
         bail!("No prompt provided for flow agent.");
     }
 
-    let full_prompt = build_flow_prompt(prompt)?;
+    let full_prompt = ai_guard::guard_prompt(&build_flow_prompt(prompt)?)?;
     invoke_gen_capture_streaming(&gen_loc, &full_prompt)
 }
 
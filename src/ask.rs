@@ -2,13 +2,14 @@
 
 use std::collections::HashSet;
 use std::io::{self, IsTerminal, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
 use anyhow::{Context, Result, bail};
 use clap::CommandFactory;
 
 use crate::ai_server;
+use crate::ask_index::{self, ChunkKind, SearchHit};
 use crate::cli::Cli;
 use crate::discover::{self, DiscoveredTask};
 use crate::{config, external_cli, opentui_prompt};
@@ -24,6 +25,14 @@ pub struct AskOpts {
     pub model: Option<String>,
     /// AI server URL override.
     pub url: Option<String>,
+    /// Search the local embeddings index before asking the AI server.
+    pub use_index: bool,
+    /// Re-embed the whole project index.
+    pub reindex: bool,
+    /// LM Studio embedding model override.
+    pub embed_model: Option<String>,
+    /// LM Studio port for embeddings.
+    pub lmstudio_port: Option<u16>,
 }
 
 enum AskSelection {
@@ -52,10 +61,16 @@ struct AskExternalCli {
 pub fn run(opts: AskOpts) -> Result<()> {
     let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
     let discovery = discover::discover_tasks(&root)?;
-    run_with_tasks(opts, discovery.tasks)
+    let project_config = discovery.root_config.clone();
+    run_with_tasks(opts, &root, project_config.as_deref(), discovery.tasks)
 }
 
-fn run_with_tasks(opts: AskOpts, tasks: Vec<DiscoveredTask>) -> Result<()> {
+fn run_with_tasks(
+    opts: AskOpts,
+    root: &Path,
+    project_config: Option<&Path>,
+    tasks: Vec<DiscoveredTask>,
+) -> Result<()> {
     let query_display = opts.args.join(" ");
     let routing = load_routing_context();
 
@@ -77,6 +92,12 @@ fn run_with_tasks(opts: AskOpts, tasks: Vec<DiscoveredTask>) -> Result<()> {
         return Ok(());
     }
 
+    if opts.use_index
+        && let Some(hit) = search_project_index(&opts, root, project_config, &tasks, &query_display)
+    {
+        return suggest_index_hit(&hit, &tasks);
+    }
+
     let commands = flow_command_candidates();
     let valid_subcommands = valid_subcommand_set(&commands);
     let prompt = build_prompt(&query_display, &tasks, &commands, &routing);
@@ -99,6 +120,70 @@ fn run_with_tasks(opts: AskOpts, tasks: Vec<DiscoveredTask>) -> Result<()> {
     Ok(())
 }
 
+/// Best match from the project's embeddings index, or `None` when LM Studio is unavailable,
+/// the index can't be updated, or nothing is similar enough.
+fn search_project_index(
+    opts: &AskOpts,
+    root: &Path,
+    project_config: Option<&Path>,
+    tasks: &[DiscoveredTask],
+    query: &str,
+) -> Option<SearchHit> {
+    let project_root = project_config.and_then(Path::parent).unwrap_or(root);
+    let project_name = project_config
+        .and_then(|path| config::load(path).ok())
+        .and_then(|cfg| cfg.project_name);
+    let index_opts = ask_index::IndexOptions {
+        model: opts.embed_model.as_deref(),
+        port: opts.lmstudio_port,
+        rebuild: opts.reindex,
+    };
+    match ask_index::search(
+        project_root,
+        project_name.as_deref(),
+        tasks,
+        query,
+        &index_opts,
+    ) {
+        Ok(hit) => hit,
+        Err(err) => {
+            eprintln!("WARN project search unavailable: {err:#}");
+            None
+        }
+    }
+}
+
+fn suggest_index_hit(hit: &SearchHit, tasks: &[DiscoveredTask]) -> Result<()> {
+    match hit.kind {
+        ChunkKind::Task => {
+            let matched = find_task(&hit.title, tasks)?;
+            println!("Project search match ({:.2})", hit.score);
+            suggest_task(matched, &[])
+        }
+        ChunkKind::Doc => {
+            println!("From {} ({:.2}):", hit.title, hit.score);
+            print_snippet(&hit.content);
+            Ok(())
+        }
+        ChunkKind::LogError => {
+            println!("Recent {} ({:.2}):", hit.title, hit.score);
+            print_snippet(&hit.content);
+            Ok(())
+        }
+    }
+}
+
+fn print_snippet(content: &str) {
+    const MAX_LINES: usize = 16;
+    let lines: Vec<&str> = content.lines().collect();
+    for line in lines.iter().take(MAX_LINES) {
+        println!("  {line}");
+    }
+    if lines.len() > MAX_LINES {
+        println!("  ...");
+    }
+}
+
 fn suggest_task(task: &DiscoveredTask, args: &[String]) -> Result<()> {
     let command = if args.is_empty() {
         format!("f {}", task.task.name)
@@ -699,8 +784,34 @@ mod tests {
                 shortcuts: Vec::new(),
                 interactive: false,
                 confirm_on_match: false,
+                confirm: None,
+                protected: false,
                 on_cancel: None,
                 output_file: None,
+                host: None,
+                remote_dir: None,
+                remote_env: Vec::new(),
+                inputs: Vec::new(),
+                outputs: Vec::new(),
+                port: None,
+                matrix: Default::default(),
+                matrix_parallel: false,
+                env_files: None,
+                retries: 0,
+                retry_backoff: None,
+                retry_exponential: false,
+                prompt: Vec::new(),
+                cwd: None,
+                umask: None,
+                nice: None,
+                max_memory: None,
+                cpu: None,
+                memory: None,
+                git_note: false,
+                parser: None,
+                before: Vec::new(),
+                after: Vec::new(),
+                on_failure: Vec::new(),
             },
             config_path: PathBuf::from("flow.toml"),
             relative_dir: String::new(),
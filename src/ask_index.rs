@@ -0,0 +1,514 @@
+//! Local embeddings index behind `f ask`.
+//!
+//! Task descriptions, markdown docs, and recent log errors for a project are split into
+//! chunks, embedded with an LM Studio embedding model, and stored in flow.db. Each `f ask`
+//! re-reads the sources and only embeds chunks whose content (or model) changed, so the
+//! index stays current without a separate indexing step. Nothing leaves the machine.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use ignore::WalkBuilder;
+use rusqlite::{Connection, params};
+
+use crate::discover::DiscoveredTask;
+use crate::{db, lmstudio, log_store, running};
+
+/// Cosine similarity a hit needs before it is shown instead of asking the AI server.
+const MIN_SCORE: f32 = 0.55;
+const EMBED_BATCH: usize = 32;
+const MAX_DOC_FILES: usize = 300;
+const MAX_DOC_BYTES: u64 = 256 * 1024;
+const MAX_CHUNK_CHARS: usize = 1500;
+const MAX_LOG_ERRORS: usize = 50;
+const LOG_WINDOW_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkKind {
+    Task,
+    Doc,
+    LogError,
+}
+
+impl ChunkKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ChunkKind::Task => "task",
+            ChunkKind::Doc => "doc",
+            ChunkKind::LogError => "log",
+        }
+    }
+
+    fn parse(raw: &str) -> Self {
+        match raw {
+            "task" => ChunkKind::Task,
+            "log" => ChunkKind::LogError,
+            _ => ChunkKind::Doc,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Chunk {
+    /// Stable identity within the project (`task:<dir>:<name>`, `doc:<path>#<n>`, `log:<hash>`).
+    key: String,
+    kind: ChunkKind,
+    /// Task name, `path › heading`, or log service.
+    title: String,
+    content: String,
+}
+
+impl Chunk {
+    fn content_hash(&self) -> String {
+        blake3::hash(format!("{}\n{}", self.title, self.content).as_bytes())
+            .to_hex()
+            .to_string()
+    }
+}
+
+/// Best match for a query.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub kind: ChunkKind,
+    pub title: String,
+    pub content: String,
+    pub score: f32,
+}
+
+pub struct IndexOptions<'a> {
+    pub model: Option<&'a str>,
+    pub port: Option<u16>,
+    /// Drop the project's index and embed everything again.
+    pub rebuild: bool,
+}
+
+/// Bring the project's index up to date and return the best hit for `query`, if it clears
+/// the similarity threshold. Returns `Ok(None)` when LM Studio is not running.
+pub fn search(
+    project_root: &Path,
+    project_name: Option<&str>,
+    tasks: &[DiscoveredTask],
+    query: &str,
+    opts: &IndexOptions<'_>,
+) -> Result<Option<SearchHit>> {
+    if !lmstudio::is_available(opts.port) {
+        return Ok(None);
+    }
+    let model = opts.model.unwrap_or(lmstudio::DEFAULT_EMBEDDING_MODEL);
+    let root_key = project_root.display().to_string();
+    let conn = open_index_db()?;
+    if opts.rebuild {
+        conn.execute(
+            "DELETE FROM ask_index WHERE project_root = ?1",
+            params![root_key],
+        )?;
+    }
+
+    let mut chunks = task_chunks(tasks);
+    chunks.extend(doc_chunks(project_root));
+    if let Some(name) = project_name {
+        chunks.extend(log_error_chunks(name));
+    }
+    update_index(&conn, &root_key, &chunks, model, opts.port)?;
+
+    let query_embedding = lmstudio::embed(&[query.to_string()], Some(model), opts.port)?
+        .pop()
+        .unwrap_or_default();
+    let best = load_entries(&conn, &root_key)?
+        .into_iter()
+        .map(|(hit, embedding)| SearchHit {
+            score: cosine(&query_embedding, &embedding),
+            ..hit
+        })
+        .max_by(|a, b| a.score.total_cmp(&b.score));
+    Ok(best.filter(|hit| hit.score >= MIN_SCORE))
+}
+
+fn open_index_db() -> Result<Connection> {
+    let conn = db::open_db()?;
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS ask_index (
+            project_root TEXT NOT NULL,
+            key TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            title TEXT NOT NULL,
+            content TEXT NOT NULL,
+            content_hash TEXT NOT NULL,
+            model TEXT NOT NULL,
+            embedding BLOB NOT NULL,
+            updated_ms INTEGER NOT NULL,
+            PRIMARY KEY (project_root, key)
+        );
+        "#,
+    )
+    .context("failed to create ask_index schema")?;
+    Ok(conn)
+}
+
+fn update_index(
+    conn: &Connection,
+    root_key: &str,
+    chunks: &[Chunk],
+    model: &str,
+    port: Option<u16>,
+) -> Result<()> {
+    let mut existing: HashMap<String, (String, String)> = HashMap::new();
+    {
+        let mut stmt =
+            conn.prepare("SELECT key, content_hash, model FROM ask_index WHERE project_root = ?1")?;
+        let rows = stmt.query_map(params![root_key], |row| {
+            Ok((row.get::<_, String>(0)?, (row.get(1)?, row.get(2)?)))
+        })?;
+        for row in rows {
+            let (key, value) = row?;
+            existing.insert(key, value);
+        }
+    }
+
+    let (to_embed, stale) = plan_update(&existing, chunks, model);
+    for key in &stale {
+        conn.execute(
+            "DELETE FROM ask_index WHERE project_root = ?1 AND key = ?2",
+            params![root_key, key],
+        )?;
+    }
+    if to_embed.is_empty() {
+        return Ok(());
+    }
+
+    eprintln!(
+        "Indexing {} chunk(s) for project search with {model}...",
+        to_embed.len()
+    );
+    for batch in to_embed.chunks(EMBED_BATCH) {
+        let inputs: Vec<String> = batch
+            .iter()
+            .map(|&idx| format!("{}\n{}", chunks[idx].title, chunks[idx].content))
+            .collect();
+        let embeddings = lmstudio::embed(&inputs, Some(model), port)?;
+        for (&idx, embedding) in batch.iter().zip(embeddings) {
+            let chunk = &chunks[idx];
+            conn.execute(
+                r#"
+                INSERT OR REPLACE INTO ask_index
+                    (project_root, key, kind, title, content, content_hash, model, embedding, updated_ms)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                "#,
+                params![
+                    root_key,
+                    chunk.key,
+                    chunk.kind.as_str(),
+                    chunk.title,
+                    chunk.content,
+                    chunk.content_hash(),
+                    model,
+                    encode_embedding(&embedding),
+                    running::now_ms() as i64,
+                ],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Indices of chunks that need (re-)embedding, and stored keys that no longer exist.
+fn plan_update(
+    existing: &HashMap<String, (String, String)>,
+    chunks: &[Chunk],
+    model: &str,
+) -> (Vec<usize>, Vec<String>) {
+    let to_embed = chunks
+        .iter()
+        .enumerate()
+        .filter(|(_, chunk)| {
+            existing.get(&chunk.key).is_none_or(|(hash, stored_model)| {
+                *hash != chunk.content_hash() || stored_model != model
+            })
+        })
+        .map(|(idx, _)| idx)
+        .collect();
+    let live: HashSet<&str> = chunks.iter().map(|chunk| chunk.key.as_str()).collect();
+    let stale = existing
+        .keys()
+        .filter(|key| !live.contains(key.as_str()))
+        .cloned()
+        .collect();
+    (to_embed, stale)
+}
+
+fn load_entries(conn: &Connection, root_key: &str) -> Result<Vec<(SearchHit, Vec<f32>)>> {
+    let mut stmt = conn
+        .prepare("SELECT kind, title, content, embedding FROM ask_index WHERE project_root = ?1")?;
+    let rows = stmt.query_map(params![root_key], |row| {
+        let kind: String = row.get(0)?;
+        let embedding: Vec<u8> = row.get(3)?;
+        Ok((
+            SearchHit {
+                kind: ChunkKind::parse(&kind),
+                title: row.get(1)?,
+                content: row.get(2)?,
+                score: 0.0,
+            },
+            decode_embedding(&embedding),
+        ))
+    })?;
+    let mut entries = Vec::new();
+    for row in rows {
+        entries.push(row?);
+    }
+    Ok(entries)
+}
+
+fn task_chunks(tasks: &[DiscoveredTask]) -> Vec<Chunk> {
+    tasks
+        .iter()
+        .map(|task| {
+            let mut content = String::new();
+            if let Some(description) = &task.task.description {
+                content.push_str(description.trim());
+                content.push('\n');
+            }
+            content.push_str(&format!("command: {}", task.task.command.trim()));
+            if !task.relative_dir.is_empty() {
+                content.push_str(&format!("\ndirectory: {}", task.relative_dir));
+            }
+            Chunk {
+                key: format!("task:{}:{}", task.relative_dir, task.task.name),
+                kind: ChunkKind::Task,
+                title: task.task.name.clone(),
+                content,
+            }
+        })
+        .collect()
+}
+
+fn doc_chunks(root: &Path) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let mut files = 0;
+    for entry in WalkBuilder::new(root).build().flatten() {
+        let path = entry.path();
+        let is_markdown = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("mdx"));
+        if !is_markdown || !entry.file_type().is_some_and(|kind| kind.is_file()) {
+            continue;
+        }
+        if entry.metadata().map(|meta| meta.len()).unwrap_or(0) > MAX_DOC_BYTES {
+            continue;
+        }
+        let Ok(contents) = fs::read_to_string(path) else {
+            continue;
+        };
+        let rel = path
+            .strip_prefix(root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string();
+        for (idx, (heading, body)) in split_markdown(&contents).into_iter().enumerate() {
+            let title = match heading {
+                Some(heading) => format!("{rel} › {heading}"),
+                None => rel.clone(),
+            };
+            chunks.push(Chunk {
+                key: format!("doc:{rel}#{idx}"),
+                kind: ChunkKind::Doc,
+                title,
+                content: body,
+            });
+        }
+        files += 1;
+        if files >= MAX_DOC_FILES {
+            break;
+        }
+    }
+    chunks
+}
+
+/// Split markdown into `(heading, body)` sections, breaking long sections at paragraph
+/// boundaries so each stays under `MAX_CHUNK_CHARS`. Empty sections are dropped.
+fn split_markdown(contents: &str) -> Vec<(Option<String>, String)> {
+    let mut sections: Vec<(Option<String>, String)> = vec![(None, String::new())];
+    let mut in_fence = false;
+    for line in contents.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        }
+        if !in_fence && line.starts_with('#') {
+            let heading = line.trim_start_matches('#').trim().to_string();
+            sections.push((Some(heading), String::new()));
+            continue;
+        }
+        let body = &mut sections.last_mut().expect("sections is never empty").1;
+        body.push_str(line);
+        body.push('\n');
+    }
+
+    let mut out = Vec::new();
+    for (heading, body) in sections {
+        let mut current = String::new();
+        for paragraph in body.split("\n\n") {
+            if !current.is_empty() && current.len() + paragraph.len() > MAX_CHUNK_CHARS {
+                out.push((heading.clone(), current.trim().to_string()));
+                current.clear();
+            }
+            current.push_str(paragraph);
+            current.push_str("\n\n");
+        }
+        if !current.trim().is_empty() {
+            out.push((heading, current.trim().to_string()));
+        }
+    }
+    out
+}
+
+fn log_error_chunks(project: &str) -> Vec<Chunk> {
+    let Ok(conn) = log_store::open_log_db() else {
+        return Vec::new();
+    };
+    let query = log_store::LogQuery {
+        project: Some(project.to_string()),
+        since: Some(running::now_ms() as i64 - LOG_WINDOW_MS),
+        limit: 2000,
+        ..Default::default()
+    };
+    let Ok(entries) = log_store::query_logs(&conn, &query) else {
+        return Vec::new();
+    };
+
+    let mut seen = HashSet::new();
+    let mut chunks = Vec::new();
+    for stored in entries {
+        let entry = stored.entry;
+        if entry.log_type != "error" && !looks_like_error(&entry.content) {
+            continue;
+        }
+        let mut content = entry.content.trim().to_string();
+        if let Some(stack) = &entry.stack {
+            for line in stack.lines().take(5) {
+                content.push('\n');
+                content.push_str(line);
+            }
+        }
+        let key = blake3::hash(format!("{}\n{}", entry.service, entry.content).as_bytes())
+            .to_hex()
+            .to_string();
+        if !seen.insert(key.clone()) {
+            continue;
+        }
+        chunks.push(Chunk {
+            key: format!("log:{key}"),
+            kind: ChunkKind::LogError,
+            title: format!("error in {}", entry.service),
+            content,
+        });
+        if chunks.len() >= MAX_LOG_ERRORS {
+            break;
+        }
+    }
+    chunks
+}
+
+fn looks_like_error(line: &str) -> bool {
+    let lower = line.to_ascii_lowercase();
+    ["error", "panic", "exception", "failed", "fatal"]
+        .iter()
+        .any(|needle| lower.contains(needle))
+}
+
+fn encode_embedding(values: &[f32]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+fn decode_embedding(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let mut dot = 0.0f32;
+    let mut norm_a = 0.0f32;
+    let mut norm_b = 0.0f32;
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(key: &str, content: &str) -> Chunk {
+        Chunk {
+            key: key.to_string(),
+            kind: ChunkKind::Doc,
+            title: "README.md".to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn splits_markdown_by_heading_and_ignores_fenced_comments() {
+        let doc = "Intro text.\n\n# Setup\nInstall deps.\n```sh\n# not a heading\nf setup\n```\n\n## Migrations\nRun `f db migrate`.\n\n# Empty\n";
+        let sections = split_markdown(doc);
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections[0], (None, "Intro text.".to_string()));
+        assert_eq!(sections[1].0.as_deref(), Some("Setup"));
+        assert!(sections[1].1.contains("# not a heading\nf setup"));
+        assert_eq!(
+            sections[2],
+            (
+                Some("Migrations".to_string()),
+                "Run `f db migrate`.".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn plans_incremental_updates_by_hash_and_model() {
+        let chunks = vec![chunk("doc:a#0", "same"), chunk("doc:b#0", "changed")];
+        let mut existing = HashMap::new();
+        existing.insert(
+            "doc:a#0".to_string(),
+            (chunks[0].content_hash(), "m1".to_string()),
+        );
+        existing.insert("doc:b#0".to_string(), ("old".to_string(), "m1".to_string()));
+        existing.insert(
+            "doc:gone#0".to_string(),
+            ("x".to_string(), "m1".to_string()),
+        );
+
+        let (to_embed, stale) = plan_update(&existing, &chunks, "m1");
+        assert_eq!(to_embed, vec![1]);
+        assert_eq!(stale, vec!["doc:gone#0".to_string()]);
+
+        let (to_embed, _) = plan_update(&existing, &chunks, "m2");
+        assert_eq!(to_embed, vec![0, 1]);
+    }
+
+    #[test]
+    fn embeddings_round_trip_and_rank_by_cosine() {
+        let vector = vec![0.5f32, -1.25, 3.0];
+        assert_eq!(decode_embedding(&encode_embedding(&vector)), vector);
+        assert!((cosine(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert_eq!(cosine(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+        assert_eq!(cosine(&[1.0], &[1.0, 2.0]), 0.0);
+    }
+}
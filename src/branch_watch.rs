@@ -0,0 +1,356 @@
+//! Branch-change resync for registered projects.
+//!
+//! The supervisor watches each registered project's `.git/HEAD`. When the checked-out
+//! branch changes it runs the project's `[flow] on_branch_change` tasks, re-syncs skills
+//! when `[skills]` is configured, and records the switch so `f tasks` (the palette) can
+//! show which branch the environment was last prepared for.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use notify::RecursiveMode;
+use notify_debouncer_mini::new_debouncer;
+use rusqlite::{Connection, OptionalExtension, params};
+
+use crate::{config, db, deploy_watch, projects, running, skills};
+
+/// How often the registered project list is re-read to pick up new projects.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+struct WatchedProject {
+    name: String,
+    root: PathBuf,
+    config_path: PathBuf,
+    git_dir: PathBuf,
+    branch: Option<String>,
+}
+
+/// What the last branch switch did, as shown in the palette header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchContext {
+    pub branch: String,
+    pub previous: String,
+    pub changed_ms: i64,
+    pub resync: String,
+}
+
+/// Start the watcher thread. Failures are logged; the supervisor keeps running.
+pub fn spawn() -> thread::JoinHandle<()> {
+    thread::spawn(|| {
+        if let Err(err) = run() {
+            eprintln!("WARN branch watcher stopped: {err:#}");
+        }
+    })
+}
+
+fn run() -> Result<()> {
+    let (event_tx, event_rx) = mpsc::channel();
+    let mut debouncer = new_debouncer(Duration::from_millis(500), event_tx)
+        .context("failed to initialize branch watcher")?;
+    let mut watched: HashMap<PathBuf, WatchedProject> = HashMap::new();
+    let mut last_refresh: Option<Instant> = None;
+
+    loop {
+        if last_refresh.is_none_or(|at| at.elapsed() >= REFRESH_INTERVAL) {
+            for project in new_projects(&watched) {
+                match debouncer
+                    .watcher()
+                    .watch(&project.git_dir, RecursiveMode::NonRecursive)
+                {
+                    Ok(()) => {
+                        watched.insert(project.git_dir.clone(), project);
+                    }
+                    Err(err) => {
+                        tracing::warn!(?err, git_dir = %project.git_dir.display(), "cannot watch");
+                    }
+                }
+            }
+            last_refresh = Some(Instant::now());
+        }
+
+        match event_rx.recv_timeout(Duration::from_secs(5)) {
+            Ok(Ok(events)) => {
+                // Git replaces HEAD via rename; the event path may not match the watched
+                // path exactly (symlinks), so re-read every project's HEAD instead.
+                if events
+                    .iter()
+                    .any(|event| event.path.file_name().is_some_and(|name| name == "HEAD"))
+                {
+                    for project in watched.values_mut() {
+                        check_branch(project);
+                    }
+                }
+            }
+            Ok(Err(err)) => tracing::warn!(?err, "branch watcher error"),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+    }
+}
+
+/// Registered projects with a git checkout that are not watched yet.
+fn new_projects(watched: &HashMap<PathBuf, WatchedProject>) -> Vec<WatchedProject> {
+    let entries = match projects::list_projects() {
+        Ok(entries) => entries,
+        Err(err) => {
+            tracing::warn!(?err, "failed to list projects for branch watcher");
+            return Vec::new();
+        }
+    };
+    entries
+        .into_iter()
+        .filter_map(|entry| {
+            let git_dir = resolve_git_dir(&entry.project_root)?;
+            if watched.contains_key(&git_dir) {
+                return None;
+            }
+            let branch = read_branch(&git_dir);
+            Some(WatchedProject {
+                name: entry.name,
+                root: entry.project_root,
+                config_path: entry.config_path,
+                git_dir,
+                branch,
+            })
+        })
+        .collect()
+}
+
+fn check_branch(project: &mut WatchedProject) {
+    let Some(current) = read_branch(&project.git_dir) else {
+        return;
+    };
+    if project.branch.as_deref() == Some(current.as_str()) {
+        return;
+    }
+    let previous = project.branch.replace(current.clone());
+    if let Some(previous) = previous {
+        on_branch_change(project, &previous, &current);
+    }
+}
+
+fn on_branch_change(project: &WatchedProject, previous: &str, current: &str) {
+    println!(
+        "branch watcher: {} switched {} -> {}",
+        project.name, previous, current
+    );
+    let resync = match config::load(&project.config_path) {
+        Ok(cfg) => resync(project, &cfg),
+        Err(err) => format!("config error: {err}"),
+    };
+    if let Err(err) = record_context(&project.root, current, previous, &resync) {
+        tracing::warn!(?err, project = %project.name, "failed to record branch change");
+    }
+}
+
+/// Run `on_branch_change` tasks (stopping at the first failure) and re-sync skills.
+fn resync(project: &WatchedProject, cfg: &config::Config) -> String {
+    let mut notes = Vec::new();
+    for task in &cfg.flow.on_branch_change {
+        let status = std::env::current_exe().and_then(|exe| {
+            Command::new(exe)
+                .arg("run")
+                .arg("--config")
+                .arg(&project.config_path)
+                .arg(task)
+                .current_dir(&project.root)
+                .stdin(Stdio::null())
+                .status()
+        });
+        match status {
+            Ok(status) if status.success() => notes.push(format!("{task} ok")),
+            Ok(status) => {
+                let code = status
+                    .code()
+                    .map(|code| format!("exit {code}"))
+                    .unwrap_or_else(|| "signal".to_string());
+                notes.push(format!("{task} failed ({code})"));
+                break;
+            }
+            Err(err) => {
+                notes.push(format!("{task} failed ({err})"));
+                break;
+            }
+        }
+    }
+    if cfg.skills.is_some() {
+        match skills::enforce_skills_from_config(&project.root, cfg) {
+            Ok(_) => notes.push("skills synced".to_string()),
+            Err(err) => notes.push(format!("skills sync failed ({err})")),
+        }
+    }
+    if notes.is_empty() {
+        "nothing to resync".to_string()
+    } else {
+        notes.join(", ")
+    }
+}
+
+/// The git directory for a checkout, following `.git` files used by worktrees.
+fn resolve_git_dir(root: &Path) -> Option<PathBuf> {
+    let dot_git = root.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let contents = fs::read_to_string(&dot_git).ok()?;
+    let target = contents.trim().strip_prefix("gitdir:")?.trim();
+    let path = root.join(target);
+    path.is_dir().then_some(path)
+}
+
+fn read_branch(git_dir: &Path) -> Option<String> {
+    parse_head(&fs::read_to_string(git_dir.join("HEAD")).ok()?)
+}
+
+/// Branch name from HEAD contents, or `detached@<short sha>`.
+fn parse_head(contents: &str) -> Option<String> {
+    let contents = contents.trim();
+    if let Some(reference) = contents.strip_prefix("ref:") {
+        let reference = reference.trim();
+        let name = reference.strip_prefix("refs/heads/").unwrap_or(reference);
+        return (!name.is_empty()).then(|| name.to_string());
+    }
+    let sha = contents.get(..7)?;
+    sha.chars()
+        .all(|ch| ch.is_ascii_hexdigit())
+        .then(|| format!("detached@{sha}"))
+}
+
+fn create_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS branch_context (
+            project_root TEXT PRIMARY KEY,
+            branch TEXT NOT NULL,
+            previous TEXT NOT NULL,
+            changed_ms INTEGER NOT NULL,
+            resync TEXT NOT NULL
+        );
+        "#,
+    )
+    .context("failed to create branch_context schema")?;
+    Ok(())
+}
+
+fn record_context(root: &Path, branch: &str, previous: &str, resync: &str) -> Result<()> {
+    let conn = db::open_db()?;
+    create_schema(&conn)?;
+    conn.execute(
+        r#"
+        INSERT INTO branch_context (project_root, branch, previous, changed_ms, resync)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        ON CONFLICT(project_root) DO UPDATE SET
+            branch = excluded.branch,
+            previous = excluded.previous,
+            changed_ms = excluded.changed_ms,
+            resync = excluded.resync
+        "#,
+        params![
+            root.to_string_lossy(),
+            branch,
+            previous,
+            running::now_ms() as i64,
+            resync
+        ],
+    )
+    .context("failed to record branch change")?;
+    Ok(())
+}
+
+fn load_context(conn: &Connection, root: &Path) -> Result<Option<BranchContext>> {
+    create_schema(conn)?;
+    conn.query_row(
+        "SELECT branch, previous, changed_ms, resync FROM branch_context WHERE project_root = ?1",
+        [root.to_string_lossy()],
+        |row| {
+            Ok(BranchContext {
+                branch: row.get(0)?,
+                previous: row.get(1)?,
+                changed_ms: row.get(2)?,
+                resync: row.get(3)?,
+            })
+        },
+    )
+    .optional()
+    .context("failed to read branch context")
+}
+
+/// One-line summary of the checkout for the palette: the current branch, plus the last
+/// recorded switch when it is still the checked-out branch.
+pub fn palette_header(root: &Path) -> Option<String> {
+    let branch = read_branch(&resolve_git_dir(root)?)?;
+    let context = db::open_db()
+        .ok()
+        .and_then(|conn| load_context(&conn, root).ok().flatten());
+    Some(header_line(
+        &branch,
+        context.as_ref(),
+        running::now_ms() as i64,
+    ))
+}
+
+fn header_line(branch: &str, context: Option<&BranchContext>, now_ms: i64) -> String {
+    match context {
+        Some(context) if context.branch == branch => format!(
+            "branch: {} (from {} {}; {})",
+            branch,
+            context.previous,
+            deploy_watch::format_age((now_ms - context.changed_ms).max(0)),
+            context.resync
+        ),
+        _ => format!("branch: {branch}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_branches_worktrees_and_detached_heads() {
+        assert_eq!(
+            parse_head("ref: refs/heads/feature/login\n").as_deref(),
+            Some("feature/login")
+        );
+        assert_eq!(
+            parse_head("4b825dc642cb6eb9a060e54bf8d69288fbee4904\n").as_deref(),
+            Some("detached@4b825dc")
+        );
+        assert_eq!(parse_head("ref: "), None);
+        assert_eq!(parse_head("garbage"), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("main");
+        fs::create_dir_all(main.join(".git/worktrees/wt")).unwrap();
+        assert_eq!(resolve_git_dir(&main), Some(main.join(".git")));
+        let worktree = dir.path().join("wt");
+        fs::create_dir_all(&worktree).unwrap();
+        fs::write(worktree.join(".git"), "gitdir: ../main/.git/worktrees/wt\n").unwrap();
+        assert_eq!(
+            resolve_git_dir(&worktree),
+            Some(worktree.join("../main/.git/worktrees/wt"))
+        );
+        assert_eq!(resolve_git_dir(dir.path()), None);
+    }
+
+    #[test]
+    fn header_mentions_resync_only_for_the_current_branch() {
+        let context = BranchContext {
+            branch: "feature".to_string(),
+            previous: "main".to_string(),
+            changed_ms: 1_000,
+            resync: "setup ok".to_string(),
+        };
+        let header = header_line("feature", Some(&context), 121_000);
+        assert!(header.starts_with("branch: feature (from main "));
+        assert!(header.ends_with("; setup ok)"));
+        assert_eq!(header_line("main", Some(&context), 121_000), "branch: main");
+        assert_eq!(header_line("main", None, 0), "branch: main");
+    }
+}
@@ -2,6 +2,7 @@ use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::{net::IpAddr, path::PathBuf};
 
 use crate::commit::ReviewModelArg;
+use crate::tail::TailSource;
 
 /// Command line interface for the flow daemon / CLI hybrid.
 #[derive(Parser, Debug)]
@@ -19,6 +20,15 @@ pub struct Cli {
     /// Output all commands in machine-readable JSON format for external tools.
     #[arg(long, global = true)]
     pub help_full: bool,
+
+    /// Run in a registered project (see `f projects`) instead of the current directory.
+    /// Consumed before parsing by `project_selector`, so it must precede the command.
+    #[arg(long, value_name = "NAME")]
+    pub project: Option<String>,
+
+    /// Run as if flow was started in PATH (like `git -C`); must precede the command.
+    #[arg(short = 'C', value_name = "PATH")]
+    pub directory: Option<PathBuf>,
 }
 
 /// Returns version string with relative build time (e.g., "0.1.0 (built 5m ago)")
@@ -173,7 +183,7 @@ pub enum Commands {
     Rerun(RerunOpts),
     #[command(
         about = "List running flow processes for the current project.",
-        long_about = "Lists flow-started processes tracked for this project. Use --all to see processes across all projects."
+        long_about = "Lists flow-started processes tracked for this project. Use --all to see processes across all projects. `f ps scan` finds dev servers started outside flow, and `f ps adopt` attaches one to a task so logs, kill, and port tracking cover it."
     )]
     Ps(ProcessOpts),
     #[command(
@@ -186,6 +196,11 @@ pub enum Commands {
         long_about = "Tail the log output of a running task. Use -f to follow in real-time."
     )]
     Logs(TaskLogsOpts),
+    #[command(
+        about = "Follow task logs, the deployed journal, and proxy 5xx in one stream.",
+        long_about = "Merges new output of the project's tasks, `journalctl` of the [host] service over SSH, and proxied requests that returned 5xx into one time-ordered, per-source colored stream. Filter with --source, --task, and --grep."
+    )]
+    Tail(TailOpts),
     #[command(
         about = "Quick traces for AI + task runs from jazz2 state.",
         long_about = "Print recent AI agent events and Flow task runs stored in the shared jazz2 state. Use --follow to stream.",
@@ -197,16 +212,36 @@ pub enum Commands {
         long_about = "Shows the latest recorded task failure, lists recent failures, or copies a formatted repair payload to the clipboard without manual terminal selection."
     )]
     Failure(FailureCommand),
+    #[command(
+        about = "Suggest fixes for the last failed task.",
+        long_about = "Matches the last failed task's output against known error signatures (missing binary, port in use, lockfile conflict, module not found) and prints a targeted suggestion. With --fix, runs the task mapped under [diagnose.fixes]."
+    )]
+    Diagnose(DiagnoseCommand),
+    #[command(
+        about = "Inspect unified run history across tasks, deploys, and commits.",
+        long_about = "Lists recorded task runs, deploys, and commit-gate executions with duration, exit status, and log links. Nested runs (e.g. tasks started by a deploy) link back to their parent."
+    )]
+    Runs(RunsCommand),
+    #[command(
+        about = "Search tasks, flow.toml, run output, stored logs, and docs in one place.",
+        long_about = "Searches task names and descriptions, flow.toml lines, recorded runs and their log files, the log store, and markdown under docs/ and .ai/docs/ for the current project. Every whitespace-separated term must match (case-insensitive). Results are ranked by match quality, then by recency.\n\nExamples:\n  f find deploy\n  f find \"connection refused\" --source logs --source runs\n  f find migrate --all-projects"
+    )]
+    Find(FindOpts),
     #[command(
         about = "Manage anonymous usage analytics preferences and local queue.",
         long_about = "Inspect, enable/disable, export, or purge local anonymous usage analytics events."
     )]
     Analytics(AnalyticsCommand),
     #[command(
-        about = "List registered projects.",
-        long_about = "Shows all projects that have been registered (projects with a 'name' field in flow.toml)."
+        about = "List registered projects or report their resource usage.",
+        long_about = "Shows all projects that have been registered (projects with a 'name' field in flow.toml).\n\nExamples:\n  f projects\n  f projects usage --since 7d"
     )]
-    Projects,
+    Projects(ProjectsCommand),
+    #[command(
+        about = "Generate shell completions (zsh, bash, fish).",
+        long_about = "Prints a completion script for the given shell. Besides the static commands and flags, the script completes task names for `f run` and `f <task>`, registered project names for `--project`, and configured deploy targets, all read live from the local flow.toml and project registry.\n\nExamples:\n  f completions zsh > ~/.zfunc/_f\n  f completions bash >> ~/.bashrc\n  f completions fish > ~/.config/fish/completions/f.fish"
+    )]
+    Completions(CompletionsOpts),
     #[command(
         about = "Fuzzy search AI sessions across all projects and copy context.",
         long_about = "Browse AI sessions (Claude, Codex, Cursor) across all projects. On selection, copies the session context since last checkpoint to clipboard for passing to another session.",
@@ -301,6 +336,12 @@ pub enum Commands {
         hide = true
     )]
     CommitWithCheck(CommitOpts),
+    /// Check a commit message file (hidden; run by the Flow commit-msg hook).
+    #[command(hide = true, name = "commit-msg-hook")]
+    CommitMsgHook(CommitMsgHookOpts),
+    /// Scan staged changes for secrets (hidden; run by the Flow pre-commit hook).
+    #[command(hide = true, name = "pre-commit-hook")]
+    PreCommitHook,
     #[command(
         about = "Undo the last undoable action (commit, push).",
         long_about = "Reverts the last recorded action. For commits, resets with --soft to keep changes staged. For pushes, force pushes the previous state.",
@@ -502,6 +543,11 @@ pub enum Commands {
         long_about = "Pushes the current branch to a private mirror remote (typically on GitHub) when invoked without a subcommand. Also manages Flow-owned global Git push hooks and internal push-policy plumbing used by those hooks."
     )]
     Push(PushCommand),
+    #[command(
+        about = "Install or inspect Flow's per-repo git hooks.",
+        long_about = "Installs Flow's pre-commit (staged secret scan), commit-msg, and pre-push (push policy) hooks for the current repo, alongside husky, lefthook, or an existing core.hooksPath. Existing hooks are chained, never overwritten, and `uninstall` restores them."
+    )]
+    Hooks(HooksCommand),
     #[command(
         about = "Show JJ workflow status optimized for stacked home-branch work.",
         long_about = "Displays the current JJ workspace, home branch, intake branch, trunk relation, leaf branches, and the working-copy summary. This is intended to replace a raw `jj st` for repos that use a persistent home branch plus review/codex workspaces. Use `--compact` when the repo has too many leaves/workspaces for the full listing to be a good first read.",
@@ -555,6 +601,11 @@ pub enum Commands {
         long_about = "Clone repositories into ~/repos/<owner>/<repo> with SSH URLs and optional upstream setup for forks."
     )]
     Repos(ReposCommand),
+    #[command(
+        about = "Import repos from remote sources into flow.",
+        long_about = "Batch-onboard repos: list a GitHub org, clone the selected repos into ~/repos/<org>/<repo>, run `f setup --no-ai` in each, and register them as flow projects."
+    )]
+    Discover(DiscoverCommand),
     #[command(
         about = "Browse git repos under ~/code.",
         long_about = "Fuzzy search git repositories under ~/code and open the selected path. Also includes helpers to migrate AI sessions when paths move."
@@ -655,6 +706,31 @@ pub struct AnalyticsCommand {
     pub action: Option<AnalyticsAction>,
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct ProjectsCommand {
+    #[command(subcommand)]
+    pub action: Option<ProjectsAction>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ProjectsAction {
+    /// Show CPU time, peak memory, and runtime of flow-managed processes per project.
+    Usage(ProjectsUsageOpts),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ProjectsUsageOpts {
+    /// Lookback window (e.g. 24h, 7d, 30d).
+    #[arg(long, default_value = "7d")]
+    pub since: String,
+    /// Only report this project.
+    #[arg(long)]
+    pub project: Option<String>,
+    /// Emit JSON instead of a table.
+    #[arg(long)]
+    pub json: bool,
+}
+
 #[derive(Subcommand, Debug, Clone)]
 pub enum AnalyticsAction {
     /// Show analytics status and queue metadata.
@@ -706,12 +782,58 @@ pub enum ProxyAction {
     Trace(ProxyTraceOpts),
     /// Show the last request details.
     Last(ProxyLastOpts),
+    /// Export recent traces as an HTTP Archive (HAR) for browser devtools.
+    Export(ProxyExportOpts),
     /// Add a new proxy target.
     Add(ProxyAddOpts),
     /// List configured proxy targets.
     List,
     /// Stop the proxy server.
     Stop,
+    /// Manage /etc/hosts entries so *.localhost project domains resolve locally.
+    Domains(ProxyDomainsCommand),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ProxyDomainsCommand {
+    #[command(subcommand)]
+    pub action: Option<ProxyDomainsAction>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ProxyDomainsAction {
+    /// List flow-managed domains in the hosts file.
+    #[command(alias = "ls")]
+    List,
+    /// Point domains at loopback (defaults to the current project's task domains).
+    Add(ProxyDomainsAddOpts),
+    /// Remove flow-managed domains.
+    #[command(alias = "rm")]
+    Remove(ProxyDomainsRemoveOpts),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ProxyDomainsAddOpts {
+    /// Domains to add (e.g. api.myapp.localhost).
+    pub hosts: Vec<String>,
+
+    /// Skip the confirmation prompt.
+    #[arg(short, long)]
+    pub yes: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ProxyDomainsRemoveOpts {
+    /// Domains to remove.
+    pub hosts: Vec<String>,
+
+    /// Remove every flow-managed domain.
+    #[arg(long)]
+    pub all: bool,
+
+    /// Skip the confirmation prompt.
+    #[arg(short, long)]
+    pub yes: bool,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -763,6 +885,25 @@ pub struct ProxyLastOpts {
     pub body: bool,
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct ProxyExportOpts {
+    /// Output path for the HAR file.
+    #[arg(long, value_name = "PATH")]
+    pub har: PathBuf,
+
+    /// Number of most recent requests to export.
+    #[arg(long, default_value = "500")]
+    pub last: usize,
+
+    /// Filter by target name.
+    #[arg(long)]
+    pub target: Option<String>,
+
+    /// Export only errors (status >= 400).
+    #[arg(long)]
+    pub errors: bool,
+}
+
 #[derive(Args, Debug, Clone)]
 pub struct ProxyAddOpts {
     /// Target address (e.g., "localhost:3000").
@@ -949,6 +1090,28 @@ pub struct FailureLastOpts {
     pub json: bool,
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct DiagnoseCommand {
+    #[command(subcommand)]
+    pub action: Option<DiagnoseAction>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum DiagnoseAction {
+    /// Analyze the latest recorded task failure.
+    Last(DiagnoseLastOpts),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DiagnoseLastOpts {
+    /// Emit the diagnosis as JSON.
+    #[arg(long)]
+    pub json: bool,
+    /// Run the fix task mapped under [diagnose.fixes] for the matched signature.
+    #[arg(long)]
+    pub fix: bool,
+}
+
 #[derive(Args, Debug, Clone)]
 pub struct FailureListOpts {
     /// Maximum number of recent failures to return.
@@ -981,6 +1144,112 @@ pub enum FailureCopyFormat {
     Json,
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct RunsCommand {
+    #[command(subcommand)]
+    pub action: Option<RunsAction>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum RunsAction {
+    /// List recent runs (most recent first).
+    List(RunsListOpts),
+    /// Show a single run and the runs it started.
+    Show(RunsShowOpts),
+    /// Show the run summaries attached to a commit as git notes.
+    Note(RunsNoteOpts),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct RunsListOpts {
+    /// Only show runs of this kind (task, deploy, commit).
+    #[arg(long)]
+    pub kind: Option<String>,
+    /// Only show runs for this project name.
+    #[arg(long)]
+    pub project: Option<String>,
+    /// Only show failed runs.
+    #[arg(long)]
+    pub failed: bool,
+    /// Maximum number of runs to return.
+    #[arg(long, default_value_t = 20)]
+    pub limit: usize,
+    /// Emit runs as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct FindOpts {
+    /// Text to search for; every whitespace-separated term must match.
+    #[arg(required = true, num_args = 1.., value_name = "QUERY")]
+    pub query: Vec<String>,
+    /// Search every registered project instead of just the current one.
+    #[arg(long)]
+    pub all_projects: bool,
+    /// Only search these sources (repeatable).
+    #[arg(long, value_enum)]
+    pub source: Vec<FindSource>,
+    /// Maximum number of results.
+    #[arg(long, default_value_t = 20)]
+    pub limit: usize,
+    /// Emit results as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindSource {
+    Tasks,
+    Config,
+    Runs,
+    Logs,
+    Docs,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CompletionsOpts {
+    /// Shell to generate completions for.
+    #[arg(value_enum, required_unless_present = "list")]
+    pub shell: Option<CompletionShell>,
+    /// Print dynamic completion candidates (used by the generated scripts).
+    #[arg(long, value_enum, hide = true)]
+    pub list: Option<CompletionList>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionShell {
+    Zsh,
+    Bash,
+    Fish,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionList {
+    Tasks,
+    Projects,
+    DeployTargets,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct RunsShowOpts {
+    /// Run id (or unique prefix) from `f runs list`.
+    pub id: String,
+    /// Emit the run as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct RunsNoteOpts {
+    /// Commit to read notes from.
+    #[arg(default_value = "HEAD")]
+    pub commit: String,
+    /// Emit the notes as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
 #[derive(Args, Debug, Clone)]
 pub struct TasksCommand {
     #[command(subcommand)]
@@ -1001,6 +1270,15 @@ pub enum TasksAction {
     RunAi(TasksRunAiOpts),
     /// Manage the AI task daemon.
     Daemon(TasksDaemonCommand),
+    /// Drop cached runs for tasks that declare `inputs`.
+    ClearCache(TasksClearCacheOpts),
+    /// Show how tasks, entry points, services, and gates connect.
+    Graph(TasksGraphOpts),
+    /// Show failure rates, durations, streaks, and flaky tasks from the run history.
+    Stats(TasksStatsOpts),
+    /// Serve a JSON-RPC task API (list, run, cancel, progress and diagnostic events) for
+    /// editor integrations, over stdio or a Unix socket.
+    Serve(TasksServeOpts),
 }
 
 #[derive(Args, Debug, Clone)]
@@ -1013,6 +1291,57 @@ pub struct TasksListOpts {
     pub dupes: bool,
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct TasksClearCacheOpts {
+    /// Path to the project flow config (flow.toml).
+    #[arg(long, default_value = "flow.toml")]
+    pub config: PathBuf,
+    /// Only clear this task (defaults to every task in the project).
+    pub task: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct TasksGraphOpts {
+    /// Path to the project flow config (flow.toml).
+    #[arg(long, default_value = "flow.toml")]
+    pub config: PathBuf,
+    /// Output format (defaults to the TUI on a terminal, Mermaid otherwise).
+    #[arg(long, value_enum)]
+    pub format: Option<TaskGraphFormat>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct TasksServeOpts {
+    /// Path to the project flow config (flow.toml).
+    #[arg(long, default_value = "flow.toml")]
+    pub config: PathBuf,
+    /// Listen on this Unix socket instead of speaking over stdin/stdout.
+    #[arg(long)]
+    pub socket: Option<PathBuf>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct TasksStatsOpts {
+    /// Path to the project flow config (flow.toml).
+    #[arg(long, default_value = "flow.toml")]
+    pub config: PathBuf,
+    /// Only show this task.
+    pub task: Option<String>,
+    /// Look back this many days.
+    #[arg(long, default_value_t = 30)]
+    pub days: u32,
+    /// Output as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskGraphFormat {
+    Dot,
+    Mermaid,
+    Tui,
+}
+
 #[derive(Args, Debug, Clone)]
 pub struct TasksDupesOpts {
     /// Path to the project flow config (flow.toml).
@@ -1160,6 +1489,15 @@ pub struct TaskRunOpts {
     /// Hub port to delegate tasks to.
     #[arg(long, default_value_t = 9050)]
     pub hub_port: u16,
+    /// Queue priority for delegated runs (higher runs first).
+    #[arg(long, default_value_t = 0, allow_hyphen_values = true)]
+    pub priority: i32,
+    /// Print the environment flow resolves for the task (env files, flow vars) and exit.
+    #[arg(long)]
+    pub print_env: bool,
+    /// Run `confirm` and `protected` tasks without asking.
+    #[arg(long, short = 'y')]
+    pub yes: bool,
     /// Name of the task to execute.
     #[arg(value_name = "TASK")]
     pub name: String,
@@ -1202,7 +1540,10 @@ pub struct TaskActivateOpts {
 }
 
 #[derive(Args, Debug, Clone)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct ProcessOpts {
+    #[command(subcommand)]
+    pub action: Option<PsAction>,
     /// Path to the project flow config (flow.toml).
     #[arg(long, default_value = "flow.toml")]
     pub config: PathBuf,
@@ -1211,6 +1552,42 @@ pub struct ProcessOpts {
     pub all: bool,
 }
 
+#[derive(Subcommand, Debug, Clone)]
+pub enum PsAction {
+    #[command(
+        about = "Attach a process started outside flow to a task",
+        long_about = "Registers an already-running process under one of the project's tasks so `f ps`, `f kill`, `f logs`, and port tracking treat it like a flow-started run. Logs are available when the process writes stdout to a file."
+    )]
+    Adopt(PsAdoptOpts),
+    #[command(
+        about = "Find running processes that look like this project's tasks",
+        long_about = "Matches the system process table against the project's task commands (and working directory, when it can be read) and lists likely candidates for `f ps adopt`."
+    )]
+    Scan(PsScanOpts),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct PsAdoptOpts {
+    /// PID of the running process.
+    pub pid: u32,
+    /// Task to attach the process to.
+    #[arg(long, short)]
+    pub task: String,
+    /// Path to the project flow config (flow.toml).
+    #[arg(long, default_value = "flow.toml")]
+    pub config: PathBuf,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct PsScanOpts {
+    /// Path to the project flow config (flow.toml).
+    #[arg(long, default_value = "flow.toml")]
+    pub config: PathBuf,
+    /// Adopt every candidate that matches exactly one task.
+    #[arg(long)]
+    pub adopt: bool,
+}
+
 #[derive(Args, Debug, Clone)]
 pub struct KillOpts {
     /// Path to the project flow config (flow.toml).
@@ -1234,7 +1611,10 @@ pub struct KillOpts {
 }
 
 #[derive(Args, Debug, Clone)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct TaskLogsOpts {
+    #[command(subcommand)]
+    pub action: Option<LogsAction>,
     /// Path to the project flow config (flow.toml).
     #[arg(long, default_value = "flow.toml")]
     pub config: PathBuf,
@@ -1262,10 +1642,136 @@ pub struct TaskLogsOpts {
     /// Hub task ID to fetch logs for (from delegated tasks).
     #[arg(long)]
     pub task_id: Option<String>,
+    /// Proxy trace ID (`x-flow-trace-id`): show the proxied request and the task log
+    /// lines that mention it.
+    #[arg(long, value_name = "ID")]
+    pub trace: Option<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct TailOpts {
+    /// Path to the project flow config (flow.toml).
+    #[arg(long, default_value = "flow.toml")]
+    pub config: PathBuf,
+    /// Look up the project by registered name instead of config path.
+    #[arg(long, short)]
+    pub project: Option<String>,
+    /// Only these sources (repeat or separate with commas).
+    #[arg(long = "source", short, value_enum, value_delimiter = ',')]
+    pub sources: Vec<TailSource>,
+    /// Only this task's log.
+    #[arg(long, short)]
+    pub task: Option<String>,
+    /// Only lines containing this text (case-insensitive).
+    #[arg(long, short)]
+    pub grep: Option<String>,
+    /// Print one JSON object per line instead of colored text.
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum LogsAction {
+    #[command(
+        about = "Bundle logs, run records, and proxy traces for a bug report",
+        long_about = "Write stored logs, run records (with a redacted tail of each run's log), and proxy traces from the given window into one gzipped JSONL archive."
+    )]
+    Export(LogsExportOpts),
+    #[command(about = "Load a bundle created by `f logs export`")]
+    Import(LogsImportOpts),
+    #[command(
+        about = "Manage per-project log server ingest tokens",
+        long_about = "Create, list, and revoke tokens for the log server's /logs/ingest endpoint. Entries sent with a token are scoped to its project and limited by its rate limit and daily byte quota; once a project has a token, unauthenticated ingest for it is rejected."
+    )]
+    Token(LogsTokenCommand),
+    #[command(
+        about = "Show stored error logs grouped by cluster, with source-mapped stack traces",
+        long_about = "List error entries from the log store, grouped into clusters of similar messages (numbers, UUIDs, hex values, and paths ignored) with counts and first/last seen times; pass --all to list every entry. When a project has `[logs] source_maps = true`, stacks from minified JS bundles are shown with the original file and line; pass --raw to see the stack as it was captured."
+    )]
+    Errors(LogsErrorsOpts),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct LogsExportOpts {
+    /// Lookback window (e.g. 30m, 1h, 2d).
+    #[arg(long, default_value = "1h")]
+    pub since: String,
+    /// Only include logs and runs for this project (default: all projects).
+    #[arg(long, short)]
+    pub project: Option<String>,
+    /// Output path (default: ./flow-logs-<project>-<timestamp>.jsonl.gz).
+    #[arg(long, short)]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct LogsErrorsOpts {
+    /// Only show errors for this project (default: all projects).
+    #[arg(long, short)]
+    pub project: Option<String>,
+    /// Lookback window (e.g. 30m, 1h, 2d).
+    #[arg(long)]
+    pub since: Option<String>,
+    /// Number of error clusters (or entries with --all) to show.
+    #[arg(long, short = 'n', default_value_t = 20)]
+    pub limit: usize,
+    /// Show the stack as captured instead of the source-mapped one.
+    #[arg(long)]
+    pub raw: bool,
+    /// List every error entry instead of grouping similar ones.
+    #[arg(long)]
+    pub all: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct LogsImportOpts {
+    /// Bundle file (.jsonl.gz or .jsonl).
+    #[arg(value_name = "FILE")]
+    pub path: PathBuf,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct LogsTokenCommand {
+    #[command(subcommand)]
+    pub action: LogsTokenAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum LogsTokenAction {
+    #[command(about = "Create an ingest token for a project (printed once)")]
+    Create(LogsTokenCreateOpts),
+    #[command(about = "List active ingest tokens")]
+    List,
+    #[command(about = "Revoke an ingest token")]
+    Revoke(LogsTokenRevokeOpts),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct LogsTokenCreateOpts {
+    /// Project the token ingests logs for.
+    #[arg(value_name = "PROJECT")]
+    pub project: String,
+    /// Maximum log entries per minute.
+    #[arg(long, default_value_t = crate::log_tokens::DEFAULT_RATE_PER_MIN)]
+    pub rate: u32,
+    /// Maximum bytes of log content per day (e.g. 512KB, 64MB, 1GB).
+    #[arg(long, default_value = crate::log_tokens::DEFAULT_DAILY_QUOTA)]
+    pub quota: String,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct LogsTokenRevokeOpts {
+    /// Token id (or unique prefix) from `f logs token list`.
+    #[arg(value_name = "ID")]
+    pub id: String,
 }
 
 #[derive(Args, Debug, Clone, Default)]
-pub struct DoctorOpts {}
+pub struct DoctorOpts {
+    /// Apply the suggested flow.toml upgrades (legacy key renames, baseline sections).
+    #[arg(long)]
+    pub fix: bool,
+}
 
 #[derive(Args, Debug, Clone)]
 pub struct HealthOpts {}
@@ -1376,9 +1882,16 @@ pub enum ShellAction {
 #[derive(Args, Debug, Clone)]
 pub struct NewOpts {
     /// Template name (e.g., web, docs). If omitted, shows fuzzy picker.
+    /// With --template, this is the project name or path instead.
     pub template: Option<String>,
     /// Destination path. Plain names go to ~/code/ (e.g., "zerg" → ~/code/zerg). Use ./ for cwd.
     pub path: Option<String>,
+    /// Template to create the project from: a name in ~/new, a directory, or a git URL.
+    #[arg(long = "template", short = 't', value_name = "TEMPLATE")]
+    pub template_source: Option<String>,
+    /// Don't run the project's setup task after creating it.
+    #[arg(long)]
+    pub no_setup: bool,
     /// Show what would change without writing.
     #[arg(long)]
     pub dry_run: bool,
@@ -1445,6 +1958,116 @@ pub enum HubAction {
     Start,
     #[command(about = "Stop the hub daemon if it was started by flow")]
     Stop,
+    #[command(
+        about = "Show delegated runs waiting for or holding a hub slot",
+        long_about = "Show delegated runs waiting for or holding a hub slot. Limits come from [hub] max_concurrent / max_per_project in ~/.config/flow/flow.toml."
+    )]
+    Queue(HubQueueOpts),
+    /// Dispatch [notify] rules for a delegated run (run by the command's exit trap).
+    #[command(hide = true)]
+    Notify(HubNotifyOpts),
+    #[command(
+        about = "Keep the hub daemon running at login (launchd on macOS, systemd --user on Linux)"
+    )]
+    Install(HubInstallOpts),
+    #[command(about = "Remove the hub login service installed by `f hub install`")]
+    Uninstall,
+    #[command(about = "Show the hub login service, daemon health, and log files")]
+    Status,
+    /// Run the hub daemon in the foreground with rotated logs (used by the login service).
+    #[command(hide = true)]
+    Run(HubRunOpts),
+    #[command(about = "Open the browser dashboard (projects, running tasks, runs, logs, deploys)")]
+    Dashboard(HubDashboardOpts),
+    #[command(
+        about = "Archive flow state (project registry, runs, logs, local env, deploy config) into a tarball"
+    )]
+    Backup(HubBackupOpts),
+    #[command(about = "Restore flow state from an `f hub backup` archive")]
+    Restore(HubRestoreOpts),
+}
+
+#[derive(Args, Debug, Clone, PartialEq, Eq)]
+pub struct HubBackupOpts {
+    /// Archive to write; `.tar.zst`, `.tar.gz`, or `.tar` picks the compression.
+    #[arg(long, short, default_value = "flow-backup.tar.zst")]
+    pub out: PathBuf,
+    /// Only keep logs from the last N days.
+    #[arg(long, value_name = "N")]
+    pub log_days: Option<u32>,
+    /// Leave logs out of the archive.
+    #[arg(long, conflicts_with = "log_days")]
+    pub no_logs: bool,
+    /// Leave the local env backend (secret values) out of the archive.
+    #[arg(long)]
+    pub no_env: bool,
+}
+
+#[derive(Args, Debug, Clone, PartialEq, Eq)]
+pub struct HubRestoreOpts {
+    /// Archive written by `f hub backup`.
+    pub archive: PathBuf,
+    /// Rewrite project paths starting with OLD to NEW (repeatable); the old $HOME is remapped automatically.
+    #[arg(long, value_name = "OLD=NEW")]
+    pub remap: Vec<String>,
+    /// Restore without asking for confirmation.
+    #[arg(long, short)]
+    pub yes: bool,
+}
+
+#[derive(Args, Debug, Clone, PartialEq, Eq)]
+pub struct HubDashboardOpts {
+    /// Print the dashboard URL instead of opening a browser.
+    #[arg(long)]
+    pub no_open: bool,
+}
+
+#[derive(Args, Debug, Clone, PartialEq, Eq)]
+pub struct HubInstallOpts {
+    /// Print the generated plist/unit instead of installing it.
+    #[arg(long)]
+    pub dry_run: bool,
+    #[command(flatten)]
+    pub logs: HubRunOpts,
+}
+
+#[derive(Args, Debug, Clone, PartialEq, Eq)]
+pub struct HubRunOpts {
+    /// Rotate a hub log file once it reaches this many megabytes.
+    #[arg(long, default_value_t = 10)]
+    pub max_log_mb: u64,
+    /// Rotated log files to keep per stream.
+    #[arg(long, default_value_t = 5)]
+    pub keep_logs: usize,
+}
+
+#[derive(Args, Debug, Clone, PartialEq, Eq)]
+pub struct HubNotifyOpts {
+    /// Task that finished.
+    #[arg(long)]
+    pub task: String,
+    /// Exit status of the task command.
+    #[arg(long)]
+    pub status: i32,
+    /// flow.toml the task came from.
+    #[arg(long)]
+    pub config: PathBuf,
+    /// Working directory of the run.
+    #[arg(long)]
+    pub cwd: PathBuf,
+}
+
+#[derive(Args, Debug, Clone, PartialEq, Eq)]
+pub struct HubQueueOpts {
+    /// Output as JSON.
+    #[arg(long)]
+    pub json: bool,
+    /// Drop every queued and running entry (frees stuck slots).
+    #[arg(long)]
+    pub clear: bool,
+    /// Release a slot (run by the delegated command on exit).
+    #[arg(long, hide = true)]
+    pub release: Option<i64>,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -1479,6 +2102,11 @@ pub enum SecretsAction {
     List(SecretsListOpts),
     #[command(about = "Fetch secrets for a specific environment")]
     Pull(SecretsPullOpts),
+    #[command(
+        about = "Move staged secrets into the env store and read them from the environment",
+        long_about = "For each secret the commit scan finds in staged changes: store the value in the env store under a suggested key, rewrite the line to read it from the environment (.env, JS/TS, Rust, Python), and re-stage the file."
+    )]
+    Quarantine(SecretsQuarantineOpts),
 }
 
 #[derive(Args, Debug, Clone)]
@@ -1488,6 +2116,33 @@ pub struct SecretsListOpts {
     pub config: PathBuf,
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct SecretsQuarantineOpts {
+    /// Only quarantine findings in these repo-relative files or directories.
+    #[arg(value_name = "PATH")]
+    pub paths: Vec<String>,
+
+    /// Env store key to use instead of the suggested one (single finding only).
+    #[arg(long)]
+    pub key: Option<String>,
+
+    /// Project environment to store secrets in.
+    #[arg(short, long, default_value = "dev")]
+    pub environment: String,
+
+    /// Store secrets as personal env vars instead of project ones.
+    #[arg(long)]
+    pub personal: bool,
+
+    /// Show the planned keys and rewritten lines without changing anything.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Don't ask before moving each secret.
+    #[arg(short, long)]
+    pub yes: bool,
+}
+
 #[derive(Args, Debug, Clone)]
 pub struct SecretsPullOpts {
     /// Path to the project flow config (flow.toml).
@@ -1612,6 +2267,9 @@ pub struct SetupOpts {
     /// Optional setup target (e.g., deploy).
     #[arg(value_enum, value_name = "TARGET")]
     pub target: Option<SetupTarget>,
+    /// Never call AI; use detected defaults only (also FLOW_NO_AI=1).
+    #[arg(long)]
+    pub no_ai: bool,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -1661,6 +2319,22 @@ pub struct AskOpts {
     /// AI server URL (defaults to AI_SERVER_URL or http://127.0.0.1:7331).
     #[arg(long)]
     pub url: Option<String>,
+
+    /// Skip the local embeddings search over tasks, docs, and log errors.
+    #[arg(long)]
+    pub no_index: bool,
+
+    /// Re-embed the whole project instead of only changed chunks.
+    #[arg(long, conflicts_with = "no_index")]
+    pub reindex: bool,
+
+    /// LM Studio embedding model for the project index (default: text-embedding-nomic-embed-text-v1.5).
+    #[arg(long)]
+    pub embed_model: Option<String>,
+
+    /// LM Studio port used for embeddings.
+    #[arg(long, default_value_t = 1234)]
+    pub lmstudio_port: u16,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -1727,6 +2401,12 @@ pub struct BranchAiOpts {
     pub switch: bool,
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct CommitMsgHookOpts {
+    /// Commit message file passed by git (usually .git/COMMIT_EDITMSG).
+    pub path: PathBuf,
+}
+
 #[derive(Args, Debug, Clone)]
 pub struct CommitOpts {
     /// Skip pushing after commit.
@@ -1796,6 +2476,60 @@ pub struct CommitOpts {
     /// Skip test requirements only.
     #[arg(long)]
     pub skip_tests: bool,
+    /// Check detected secrets against provider APIs to flag which are still live.
+    #[arg(long)]
+    pub verify_secrets: bool,
+    #[command(subcommand)]
+    pub action: Option<CommitAction>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum CommitAction {
+    /// Check or configure commit signing against the [commit.signing] policy.
+    Signing(CommitSigningCommand),
+    /// Split the staged change into a series of smaller commits.
+    Split(CommitSplitOpts),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CommitSplitOpts {
+    /// Print the proposed series without committing.
+    #[arg(long)]
+    pub dry: bool,
+    /// Name each proposed commit with the AI commit-message tool.
+    #[arg(long)]
+    pub ai_names: bool,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CommitSigningCommand {
+    #[command(subcommand)]
+    pub action: Option<CommitSigningAction>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum CommitSigningAction {
+    /// Show git's signing setup and how it compares to the policy (default).
+    Status,
+    /// Pick a signing key and write the git config that signs commits with it.
+    Setup(CommitSigningSetupOpts),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CommitSigningSetupOpts {
+    /// Signature format: ssh or gpg (defaults to the policy, then ssh).
+    #[arg(long)]
+    pub format: Option<String>,
+    /// Key to sign with: SSH public key path or GPG key id (defaults to the policy, then the
+    /// first key found).
+    #[arg(long)]
+    pub key: Option<String>,
+    /// Write to the global git config instead of this repo's.
+    #[arg(long)]
+    pub global: bool,
+    /// Print the git config that would be written without changing anything.
+    #[arg(long)]
+    pub dry_run: bool,
 }
 
 impl CommitOpts {
@@ -1826,6 +2560,7 @@ impl CommitOpts {
             || self.skip_quality
             || self.skip_docs
             || self.skip_tests
+            || self.verify_secrets
         {
             return None;
         }
@@ -2191,6 +2926,16 @@ pub struct StatusOpts {
     /// Show a focused status summary using Flow's JJ overview model.
     #[arg(long)]
     pub compact: bool,
+    /// Show the project dashboard (running tasks, ports, last commit gate, deploy health,
+    /// .env drift, proxy error rate) instead of JJ status.
+    #[arg(short, long, conflicts_with_all = ["raw", "compact"])]
+    pub project: bool,
+    /// Print the project dashboard as JSON.
+    #[arg(long, requires = "project")]
+    pub json: bool,
+    /// Refresh the project dashboard every 2 seconds.
+    #[arg(short, long, requires = "project", conflicts_with = "json")]
+    pub watch: bool,
 }
 
 #[derive(Args, Debug, Clone, Default)]
@@ -3318,15 +4063,31 @@ pub struct PushHooksCommand {
 
 #[derive(Subcommand, Debug, Clone)]
 pub enum PushHooksAction {
-    /// Install the global Flow pre-push hook via core.hooksPath.
+    /// Install the global Flow pre-push and commit-msg hooks via core.hooksPath.
     Install {
-        /// Overwrite an existing global hooks path or non-Flow pre-push hook.
+        /// Overwrite an existing global hooks path or non-Flow hooks.
         #[arg(long)]
         force: bool,
     },
-    /// Remove the Flow-managed global pre-push hook.
+    /// Remove the Flow-managed global hooks.
     Uninstall,
-    /// Show Flow pre-push hook status.
+    /// Show Flow hook status.
+    Status,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct HooksCommand {
+    #[command(subcommand)]
+    pub action: Option<HooksAction>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum HooksAction {
+    /// Install Flow's pre-commit, commit-msg, and pre-push hooks, chaining existing hooks.
+    Install,
+    /// Remove Flow's hooks and restore the hooks they chained.
+    Uninstall,
+    /// Show which manager runs the repo's hooks and the state of each Flow hook.
     Status,
 }
 
@@ -3402,7 +4163,7 @@ pub enum EnvAction {
         #[arg(short, long, default_value = "production")]
         environment: String,
     },
-    /// Apply env vars from cloud to the configured Cloudflare worker.
+    /// Apply env vars from cloud to the configured Cloudflare worker or Railway service.
     Apply,
     /// Bootstrap Cloudflare secrets from flow.toml (interactive).
     Bootstrap,
@@ -3473,11 +4234,69 @@ pub enum EnvAction {
     },
     /// Show configured env keys from flow.toml.
     Keys,
+    /// Render a .env template with values from the env store.
+    Render {
+        /// Template file (supports ${KEY}, ${KEY:-default}, and empty values).
+        #[arg(short, long, default_value = ".env.template")]
+        template: PathBuf,
+        /// Output file.
+        #[arg(short, long, default_value = ".env")]
+        out: PathBuf,
+        /// Fetch from personal env vars instead of project.
+        #[arg(long)]
+        personal: bool,
+        /// Environment to fetch from (dev, staging, production).
+        #[arg(short, long, default_value = "production")]
+        environment: String,
+        /// Show the key-level diff without writing the output file.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Check that .env.example and the env store agree on required keys.
+    Lint {
+        /// Example file listing required keys.
+        #[arg(long, default_value = ".env.example")]
+        example: PathBuf,
+        /// Check personal env vars instead of project.
+        #[arg(long)]
+        personal: bool,
+        /// Environment to check (dev, staging, production).
+        #[arg(short, long, default_value = "production")]
+        environment: String,
+    },
     /// Manage service tokens for host deployments.
     Token {
         #[command(subcommand)]
         action: TokenAction,
     },
+    /// Show when an env var changed, on which machine, and its previous values.
+    History {
+        /// Key to inspect.
+        key: String,
+        /// Use the project env store instead of personal.
+        #[arg(long)]
+        project: bool,
+        /// Environment for --project (dev, staging, production).
+        #[arg(short, long, default_value = "production")]
+        environment: String,
+        /// Print values in full instead of masked.
+        #[arg(long)]
+        reveal: bool,
+    },
+    /// Restore a previous value of an env var from its history.
+    Rollback {
+        /// Key to roll back.
+        key: String,
+        /// How many changes to go back (1 = the value before the current one).
+        #[arg(long, default_value_t = 1)]
+        steps: usize,
+        /// Use the project env store instead of personal.
+        #[arg(long)]
+        project: bool,
+        /// Environment for --project (dev, staging, production).
+        #[arg(short, long, default_value = "production")]
+        environment: String,
+    },
 }
 
 #[derive(Subcommand, Debug, Clone)]
@@ -3757,6 +4576,48 @@ pub enum ConfigAction {
         #[arg(long)]
         json: bool,
     },
+    /// Read a value from flow.toml by dotted key (e.g. `tasks.dev.command`).
+    Get(ConfigGetOpts),
+    /// Set a value in flow.toml, preserving comments and formatting (e.g. `host.port 8080`).
+    Set(ConfigSetOpts),
+    /// Show flow.toml with `${env:VAR}` / `${envstore:KEY}` references resolved and masked.
+    Resolve(ConfigResolveOpts),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ConfigGetOpts {
+    /// Dotted key path; `[[tasks]]` entries are selected by name (e.g. `tasks.dev.command`).
+    #[arg(value_name = "KEY")]
+    pub key: String,
+    /// Path to the project flow config (flow.toml).
+    #[arg(long, default_value = "flow.toml")]
+    pub config: PathBuf,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ConfigSetOpts {
+    /// Dotted key path; missing tables are created (e.g. `host.port`).
+    #[arg(value_name = "KEY")]
+    pub key: String,
+    /// TOML value (`8080`, `true`, `["a", "b"]`); anything else is stored as a string.
+    #[arg(value_name = "VALUE", allow_hyphen_values = true)]
+    pub value: String,
+    /// Store the value as a string even if it parses as another TOML type.
+    #[arg(long)]
+    pub string: bool,
+    /// Path to the project flow config (flow.toml).
+    #[arg(long, default_value = "flow.toml")]
+    pub config: PathBuf,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ConfigResolveOpts {
+    /// Only show this dotted key (e.g. `tasks.deploy.command`).
+    #[arg(value_name = "KEY")]
+    pub key: Option<String>,
+    /// Path to the project flow config (flow.toml).
+    #[arg(long, default_value = "flow.toml")]
+    pub config: PathBuf,
 }
 
 #[derive(Subcommand, Debug, Clone)]
@@ -3931,11 +4792,16 @@ pub enum SkillsAction {
         /// Skill name.
         name: String,
     },
-    /// Install a curated skill from the registry.
+    /// Install a skill from the registry or a git source.
     Install {
-        /// Skill name to install.
+        /// Skill name, `gh:org/repo/skill@ref`, or `<git-url>[#path][@ref]`.
         name: String,
     },
+    /// Re-fetch git-installed skills and upgrade them when their source changed.
+    Update {
+        /// Only update this skill (default: all git-installed skills).
+        name: Option<String>,
+    },
     /// Publish a local skill to the shared registry.
     Publish {
         /// Skill name to publish.
@@ -3948,6 +4814,18 @@ pub enum SkillsAction {
     },
     /// Sync flow.toml tasks as skills.
     Sync,
+    /// Report drift between flow.toml tasks and generated task skills.
+    Status {
+        /// Show a diff for each drifted skill.
+        #[arg(long)]
+        diff: bool,
+        /// Re-sync stale and missing skills and remove orphans; keeps local edits.
+        #[arg(long)]
+        sync: bool,
+        /// With --sync, also overwrite locally edited skills.
+        #[arg(long, requires = "sync")]
+        force: bool,
+    },
     /// Force Codex app-server to rescan skills from disk for this cwd.
     Reload,
     /// Fetch dependency skills via seq scraper integration.
@@ -4308,6 +5186,52 @@ pub struct ReposCommand {
     pub action: Option<ReposAction>,
 }
 
+#[derive(Args, Debug, Clone)]
+pub struct DiscoverCommand {
+    #[command(subcommand)]
+    pub action: DiscoverAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum DiscoverAction {
+    /// Clone, set up, and register repos from a GitHub org (or user).
+    Github(DiscoverGithubOpts),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DiscoverGithubOpts {
+    /// GitHub org or user whose repos to list.
+    #[arg(long)]
+    pub org: String,
+    /// Root directory for clones (default: ~/repos).
+    #[arg(long, default_value = "~/repos")]
+    pub root: String,
+    /// Import every repo whose name contains this text (case-insensitive) without a picker.
+    #[arg(long = "match", value_name = "TEXT")]
+    pub pattern: Option<String>,
+    /// Import every listed repo without a picker.
+    #[arg(long)]
+    pub all: bool,
+    /// Include archived repos.
+    #[arg(long)]
+    pub include_archived: bool,
+    /// Include forks.
+    #[arg(long)]
+    pub include_forks: bool,
+    /// Maximum number of repos to list.
+    #[arg(long, default_value_t = 1000)]
+    pub limit: usize,
+    /// Perform full clones (skip shallow clone + background history fetch).
+    #[arg(long)]
+    pub full: bool,
+    /// Skip `f setup --no-ai` in freshly cloned repos.
+    #[arg(long)]
+    pub no_setup: bool,
+    /// List the repos that would be imported without cloning anything.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
 #[derive(Args, Debug, Clone)]
 pub struct CodeCommand {
     #[command(subcommand)]
@@ -5176,6 +6100,14 @@ pub enum DeployAction {
         /// Run setup script even if already deployed.
         #[arg(long)]
         setup: bool,
+        /// Create the service user, set up dest, install [host] packages, and open the
+        /// firewall before deploying (prints the plan first).
+        #[arg(long)]
+        provision: bool,
+        /// Skip confirmation for destructive remote steps (rsync --delete) and the
+        /// provisioning plan.
+        #[arg(long, short)]
+        yes: bool,
     },
     /// Deploy to Cloudflare Workers.
     #[command(alias = "cf")]
@@ -5217,7 +6149,11 @@ pub enum DeployAction {
     /// Restart the deployed service.
     Restart,
     /// Stop the deployed service.
-    Stop,
+    Stop {
+        /// Skip the confirmation prompt.
+        #[arg(long, short)]
+        yes: bool,
+    },
     /// SSH into the host (for host deployments).
     Shell,
     /// Configure host for deployment.
@@ -5237,6 +6173,31 @@ pub enum DeployAction {
         #[arg(long, default_value_t = 200)]
         status: u16,
     },
+    /// Show health of deployed services across all registered projects.
+    Watch(DeployWatchOpts),
+    /// List deploy audit logs, or replay the remote commands of one deploy.
+    Audit(DeployAuditOpts),
+    /// Report where the host's systemd unit, nginx site, or .env keys diverge from [host].
+    Drift,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DeployAuditOpts {
+    /// Deploy id (or unique prefix) from `.flow/deploys/`; lists recent deploys when omitted.
+    pub id: Option<String>,
+    /// Maximum number of deploys to list.
+    #[arg(long, default_value_t = 20)]
+    pub limit: usize,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DeployWatchOpts {
+    /// Check every service now before printing status.
+    #[arg(long)]
+    pub poll: bool,
+    /// Emit status as JSON.
+    #[arg(long)]
+    pub json: bool,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -5745,7 +6706,9 @@ mod tests {
                 assert!(json);
                 assert_eq!(
                     query,
-                    vec!["https://linear.app/example-workspace/project/example-project-v1-1234567890ab/overview"]
+                    vec![
+                        "https://linear.app/example-workspace/project/example-project-v1-1234567890ab/overview"
+                    ]
                 );
             }
             other => panic!("unexpected parsed command: {other:?}"),
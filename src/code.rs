@@ -11,9 +11,9 @@ use serde_json::Value;
 
 use crate::cli::{
     CodeAction, CodeCommand, CodeMigrateOpts, CodeMoveSessionsOpts, CodeNewOpts, MigrateAction,
-    MigrateCommand, NewOpts,
+    MigrateCommand, NewOpts, TaskRunOpts,
 };
-use crate::config;
+use crate::{config, init, projects, tasks};
 
 const DEFAULT_CODE_ROOT: &str = "~/code";
 const DEFAULT_TEMPLATE_ROOT: &str = "~/new";
@@ -84,13 +84,61 @@ fn fuzzy_select_template() -> Result<Option<String>> {
     Ok(Some(selected))
 }
 
+/// Where a project template comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplateSource {
+    /// A directory: a name under ~/new, or a path.
+    Dir(PathBuf),
+    /// A git URL, cloned shallowly.
+    Git(String),
+}
+
+fn resolve_template_source(template: &str, template_root: &Path) -> TemplateSource {
+    let template = template.trim();
+    let is_git = ["https://", "http://", "ssh://", "git://", "git@", "file://"]
+        .iter()
+        .any(|prefix| template.starts_with(prefix))
+        || template.ends_with(".git");
+    if is_git {
+        return TemplateSource::Git(template.to_string());
+    }
+    if template.contains('/') || template.starts_with('~') || template.starts_with('.') {
+        let expanded = config::expand_path(template);
+        let path = if expanded.is_absolute() {
+            expanded
+        } else {
+            std::env::current_dir()
+                .unwrap_or_else(|_| PathBuf::from("."))
+                .join(expanded)
+        };
+        return TemplateSource::Dir(path);
+    }
+    TemplateSource::Dir(template_root.join(template))
+}
+
 /// Create a new project from a template at a specific path.
-/// Usage: f new [template] [path]
+/// Usage: f new [template] [path], or f new <name> --template <template>
 pub fn new_from_template(opts: NewOpts) -> Result<()> {
     let template_root = config::expand_path(DEFAULT_TEMPLATE_ROOT);
 
+    // With --template, the first positional is the project rather than the template.
+    let (template, path) = match opts.template_source {
+        Some(source) => {
+            if opts.path.is_some() {
+                bail!(
+                    "pass the project as a single argument with --template: f new <name> --template <template>"
+                );
+            }
+            if opts.template.is_none() {
+                bail!("missing project name: f new <name> --template <template>");
+            }
+            (Some(source), opts.template)
+        }
+        None => (opts.template, opts.path),
+    };
+
     // Get template name (fuzzy select if not provided)
-    let template_name = match opts.template {
+    let template_name = match template {
         Some(t) => t,
         None => match fuzzy_select_template()? {
             Some(t) => t,
@@ -98,16 +146,17 @@ pub fn new_from_template(opts: NewOpts) -> Result<()> {
         },
     };
 
-    let template_dir = template_root.join(template_name.trim());
-
-    if !template_dir.exists() {
-        bail!("Template not found: {}", template_dir.display());
-    }
-    if !template_dir.is_dir() {
-        bail!(
-            "Template path is not a directory: {}",
-            template_dir.display()
-        );
+    let source = resolve_template_source(&template_name, &template_root);
+    if let TemplateSource::Dir(template_dir) = &source {
+        if !template_dir.exists() {
+            bail!("Template not found: {}", template_dir.display());
+        }
+        if !template_dir.is_dir() {
+            bail!(
+                "Template path is not a directory: {}",
+                template_dir.display()
+            );
+        }
     }
 
     // Resolve target path:
@@ -115,8 +164,17 @@ pub fn new_from_template(opts: NewOpts) -> Result<()> {
     // - Starts with ./ or ../: relative to cwd
     // - Starts with ~ or /: absolute path
     // - Otherwise: relative to ~/code/
-    let target = match opts.path {
-        None => std::env::current_dir()?.join(&template_name),
+    let target = match path {
+        None => {
+            let name = template_name
+                .trim()
+                .trim_end_matches('/')
+                .rsplit(['/', ':'])
+                .next()
+                .unwrap_or_default()
+                .trim_end_matches(".git");
+            std::env::current_dir()?.join(name)
+        }
         Some(p) => {
             let trimmed = p.trim();
             if trimmed.starts_with("./")
@@ -140,13 +198,27 @@ pub fn new_from_template(opts: NewOpts) -> Result<()> {
     if target.exists() {
         bail!("Destination already exists: {}", target.display());
     }
+    let project_name = target
+        .file_name()
+        .and_then(|n| n.to_str())
+        .context("destination has no directory name")?
+        .to_string();
 
     if opts.dry_run {
-        println!(
-            "Would copy template {} -> {}",
-            template_dir.display(),
-            target.display()
-        );
+        match &source {
+            TemplateSource::Dir(dir) => println!(
+                "Would copy template {} -> {}",
+                dir.display(),
+                target.display()
+            ),
+            TemplateSource::Git(url) => {
+                println!("Would clone template {} -> {}", url, target.display())
+            }
+        }
+        println!("Would initialize git, write flow.toml if missing, and register '{project_name}'");
+        if !opts.no_setup {
+            println!("Would run the setup task if flow.toml defines one");
+        }
         return Ok(());
     }
 
@@ -159,8 +231,72 @@ pub fn new_from_template(opts: NewOpts) -> Result<()> {
         }
     }
 
-    copy_dir_all(&template_dir, &target)?;
+    match &source {
+        TemplateSource::Dir(dir) => copy_dir_all(dir, &target)?,
+        TemplateSource::Git(url) => clone_template(url, &target)?,
+    }
     println!("Created {}", target.display());
+
+    // The new project starts its own history rather than the template's.
+    let template_git = target.join(".git");
+    if template_git.exists() {
+        fs::remove_dir_all(&template_git)
+            .with_context(|| format!("failed to remove {}", template_git.display()))?;
+    }
+    let status = Command::new("git")
+        .args(["init", "-q"])
+        .current_dir(&target)
+        .status()
+        .context("failed to run git init")?;
+    if !status.success() {
+        bail!("git init failed in {}", target.display());
+    }
+
+    let config_path = target.join("flow.toml");
+    if !config_path.exists() {
+        fs::write(
+            &config_path,
+            format!(
+                "name = \"{project_name}\"\n{}",
+                init::render_template(&target)
+            ),
+        )
+        .with_context(|| format!("failed to write {}", config_path.display()))?;
+        println!("Wrote {}", config_path.display());
+    }
+    let cfg = config::load(&config_path)
+        .with_context(|| format!("failed to load {}", config_path.display()))?;
+    let registered_name = cfg.project_name.as_deref().unwrap_or(&project_name);
+    projects::register_project(registered_name, &config_path)?;
+    println!("Registered project '{registered_name}'");
+
+    let has_setup =
+        tasks::find_task(&cfg, "setup").is_some_and(|task| !task.command.trim().is_empty());
+    if has_setup && !opts.no_setup {
+        tasks::run(TaskRunOpts {
+            config: config_path,
+            delegate_to_hub: false,
+            hub_host: std::net::IpAddr::from([127, 0, 0, 1]),
+            hub_port: 9050,
+            priority: 0,
+            print_env: false,
+            yes: false,
+            name: "setup".to_string(),
+            args: Vec::new(),
+        })?;
+    }
+    Ok(())
+}
+
+fn clone_template(url: &str, target: &Path) -> Result<()> {
+    let status = Command::new("git")
+        .args(["clone", "-q", "--depth", "1", url])
+        .arg(target)
+        .status()
+        .context("failed to run git clone")?;
+    if !status.success() {
+        bail!("failed to clone template {}", url);
+    }
     Ok(())
 }
 
@@ -1731,6 +1867,28 @@ mod tests {
     use super::*;
     use tempfile::tempdir;
 
+    #[test]
+    fn resolve_template_source_detects_git_urls_and_paths() {
+        let root = Path::new("/home/me/new");
+        assert_eq!(
+            resolve_template_source("rust-cli", root),
+            TemplateSource::Dir(root.join("rust-cli"))
+        );
+        assert_eq!(
+            resolve_template_source("/tmp/starter", root),
+            TemplateSource::Dir(PathBuf::from("/tmp/starter"))
+        );
+        for url in [
+            "https://github.com/me/rust-cli",
+            "git@github.com:me/rust-cli.git",
+        ] {
+            assert_eq!(
+                resolve_template_source(url, root),
+                TemplateSource::Git(url.to_string())
+            );
+        }
+    }
+
     #[test]
     fn rewrite_path_prefix_rewrites_exact_and_nested_paths() {
         let from = "~/code/org/linsa/linsa-mac";
@@ -13,7 +13,6 @@ use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result, anyhow, bail};
 use clap::ValueEnum;
-use flow_commit_scan::scan_diff_for_secrets;
 use regex::Regex;
 use reqwest::StatusCode;
 use reqwest::blocking::Client;
@@ -27,7 +26,11 @@ use url::Url;
 use uuid::Uuid;
 
 use crate::ai;
-use crate::cli::{CommitQueueAction, CommitQueueCommand, DaemonAction, PrOpts};
+use crate::cli::{
+    CommitMsgHookOpts, CommitQueueAction, CommitQueueCommand, DaemonAction, PrOpts,
+    SecretsQuarantineOpts, TaskRunOpts,
+};
+use crate::commit_signing;
 use crate::config;
 use crate::daemon;
 use crate::env as flow_env;
@@ -35,11 +38,16 @@ use crate::features;
 use crate::git_guard;
 use crate::gitignore_policy;
 use crate::hub;
+use crate::hub_events::{self, HubEvent};
 use crate::notify;
 use crate::pr_preview;
+use crate::runs;
+use crate::secret_quarantine;
+use crate::secret_verify;
 use crate::setup;
 use crate::skills;
 use crate::supervisor;
+use crate::tasks;
 use crate::todo;
 use crate::undo;
 use crate::vcs;
@@ -295,6 +303,22 @@ fn check_sensitive_files(repo_root: &Path) -> Vec<String> {
     sensitive
 }
 
+/// Gates on the staged content that every commit must pass: internal and generated
+/// files, the gitignore policy, sensitive files, and the secret scan.
+pub(crate) fn enforce_staged_gates(repo_root: &Path) -> Result<()> {
+    ensure_no_internal_staged(repo_root)?;
+    ensure_no_unwanted_staged(repo_root)?;
+    gitignore_policy::enforce_staged_policy(repo_root)?;
+
+    // Check for sensitive files before proceeding
+    let sensitive_files = check_sensitive_files(repo_root);
+    warn_sensitive_files(&sensitive_files)?;
+
+    // Scan diff content for hardcoded secrets
+    let secret_findings = scan_staged_secrets(repo_root);
+    warn_secrets_in_diff(repo_root, &secret_findings)
+}
+
 /// Warn about sensitive files and optionally abort.
 fn warn_sensitive_files(files: &[String]) -> Result<()> {
     if files.is_empty() {
@@ -318,6 +342,73 @@ fn warn_sensitive_files(files: &[String]) -> Result<()> {
     bail!("Refusing to commit sensitive files. Set FLOW_ALLOW_SENSITIVE_COMMIT=1 to override.")
 }
 
+/// `[commit.secret_scan] ignore_paths` from the repo and global flow.toml.
+pub(crate) fn secret_scan_ignore_paths(repo_root: &Path) -> Vec<String> {
+    let mut paths: Vec<String> = [
+        load_local_commit_config(repo_root),
+        load_global_commit_config(),
+    ]
+    .into_iter()
+    .flatten()
+    .filter_map(|commit| commit.secret_scan)
+    .flat_map(|scan| scan.ignore_paths)
+    .collect();
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// Whether findings should be checked against provider APIs: `FLOW_VERIFY_SECRETS=1`
+/// (set by `f commit --verify-secrets`) or `[commit.secret_scan] verify = true`.
+fn secret_scan_verify_enabled(repo_root: &Path) -> bool {
+    env::var("FLOW_VERIFY_SECRETS").ok().as_deref() == Some("1")
+        || [
+            load_local_commit_config(repo_root),
+            load_global_commit_config(),
+        ]
+        .into_iter()
+        .flatten()
+        .filter_map(|commit| commit.secret_scan)
+        .any(|scan| scan.verify)
+}
+
+/// Scan the staged tree for secrets, honoring configured ignore paths.
+/// Each finding is published as a `secret.found` hub event (without the matched text).
+/// Binary files can't be scanned, so they are listed instead.
+fn scan_staged_secrets(repo_root: &Path) -> Vec<(String, usize, String, String)> {
+    let report =
+        flow_commit_scan::scan_staged_report(repo_root, &secret_scan_ignore_paths(repo_root));
+    if !report.binary_files.is_empty() {
+        let mut shown = report.binary_files[..report.binary_files.len().min(3)].join(", ");
+        if report.binary_files.len() > 3 {
+            shown.push_str(&format!(", +{} more", report.binary_files.len() - 3));
+        }
+        eprintln!(
+            "ℹ️  Secret scan skipped {} binary file(s): {}",
+            report.binary_files.len(),
+            shown
+        );
+    }
+    let findings: Vec<_> = report
+        .findings
+        .into_iter()
+        .map(flow_commit_scan::Finding::into_tuple)
+        .collect();
+    for (path, line, pattern, _) in &findings {
+        hub_events::publish(HubEvent::new(
+            hub_events::SECRET_FOUND,
+            None,
+            json!({
+                "repo_root": repo_root.display().to_string(),
+                "path": path,
+                "line": line,
+                "pattern": pattern,
+            }),
+        ));
+    }
+    findings
+}
+
 /// Warn about secrets found in diff and optionally abort.
 fn warn_secrets_in_diff(
     repo_root: &Path,
@@ -337,12 +428,20 @@ fn warn_secrets_in_diff(
     println!();
     print_secret_findings("🔐 Potential secrets detected in staged changes:", findings);
     println!();
+    if secret_scan_verify_enabled(repo_root) {
+        secret_verify::print_verified(repo_root, findings);
+        println!();
+    }
+    println!(
+        "If these are real secrets, run `f secrets quarantine` to move them into the env store and read them from the environment."
+    );
     println!("If these are false positives (examples, placeholders, tests), you can:");
     println!("   - Set FLOW_ALLOW_SECRET_COMMIT=1 to override for this commit");
     println!(
         "   - Mark the line with '# flow:secret:ignore' (or add it on the line above to ignore the next line)"
     );
     println!("   - Use placeholder values like 'xxx' for example secrets");
+    println!("   - Add fixture paths to [commit.secret_scan] ignore_paths in flow.toml");
     println!("   - Re-stage files if you recently edited them: git add <file>");
     println!();
 
@@ -374,11 +473,38 @@ fn warn_secrets_in_diff(
         ensure_no_internal_staged(repo_root)?;
         ensure_no_unwanted_staged(repo_root)?;
         gitignore_policy::enforce_staged_policy(repo_root)?;
-        *findings = scan_diff_for_secrets(repo_root);
+        *findings = scan_staged_secrets(repo_root);
         Ok(())
     };
 
-    if interactive && agent_enabled {
+    if interactive
+        && prompt_yes_no_default_yes(
+            "Quarantine these secrets into the env store and rewrite the lines to read them from the environment?",
+        )?
+    {
+        let opts = SecretsQuarantineOpts {
+            paths: Vec::new(),
+            key: None,
+            environment: "dev".to_string(),
+            personal: false,
+            dry_run: false,
+            yes: true,
+        };
+        if let Err(err) = secret_quarantine::quarantine_staged(repo_root, &opts) {
+            eprintln!("⚠ Failed to quarantine secrets: {err:#}");
+        }
+        current_findings = scan_staged_secrets(repo_root);
+        if current_findings.is_empty() {
+            if prompt_yes_no_default_yes(
+                "Secret scan is clean after quarantine. Continue with commit?",
+            )? {
+                return Ok(());
+            }
+            bail!("Commit aborted after quarantine. Review changes and retry.");
+        }
+    }
+
+    if interactive && agent_enabled && !current_findings.is_empty() {
         let task = build_fix_f_commit_task(&current_findings);
         eprintln!(
             "ℹ️  fix-f-commit Hive agent '{}' is retired; continuing with manual/Codex handoff.",
@@ -467,7 +593,7 @@ fn should_run_sync_for_secret_fixes(repo_root: &Path) -> Result<bool> {
     ensure_no_unwanted_staged(repo_root)?;
     gitignore_policy::enforce_staged_policy(repo_root)?;
 
-    Ok(!scan_diff_for_secrets(repo_root).is_empty())
+    Ok(!scan_staged_secrets(repo_root).is_empty())
 }
 
 fn run_fix_f_commit_ai(repo_root: &Path, task: &str) -> Result<()> {
@@ -520,6 +646,117 @@ fn print_secret_findings(header: &str, findings: &[(String, usize, String, Strin
     }
 }
 
+/// `[commit.message] require_ticket` from the repo flow.toml, falling back to the global one.
+fn commit_message_ticket_pattern(repo_root: &Path) -> Option<String> {
+    [
+        load_local_commit_config(repo_root),
+        load_global_commit_config(),
+    ]
+    .into_iter()
+    .flatten()
+    .filter_map(|commit| commit.message)
+    .find_map(|message| message.require_ticket)
+    .filter(|pattern| !pattern.trim().is_empty())
+}
+
+/// Messages git writes itself (merges, reverts, autosquash markers) skip the ticket policy.
+fn is_generated_commit_message(message: &str) -> bool {
+    let message = message.trim_start();
+    ["Merge ", "Revert \"", "fixup! ", "squash! ", "amend! "]
+        .iter()
+        .any(|prefix| message.starts_with(prefix))
+}
+
+/// Check a commit message against the secret patterns and `[commit.message]` policy.
+pub fn check_commit_message(repo_root: &Path, message: &str) -> Result<()> {
+    let findings: Vec<_> = flow_commit_scan::scan_text(message, "commit message")
+        .into_iter()
+        .map(flow_commit_scan::Finding::into_tuple)
+        .collect();
+    if !findings.is_empty() {
+        if env::var("FLOW_ALLOW_SECRET_COMMIT").ok().as_deref() == Some("1") {
+            println!(
+                "\n⚠️  Warning: Potential secrets detected in the commit message but FLOW_ALLOW_SECRET_COMMIT=1, continuing..."
+            );
+        } else {
+            println!();
+            print_secret_findings(
+                "🔐 Potential secrets detected in the commit message:",
+                &findings,
+            );
+            println!();
+            println!(
+                "Commit messages are pushed with the commit; remove the value from the message."
+            );
+            println!(
+                "If this is a false positive, set FLOW_ALLOW_SECRET_COMMIT=1 for this commit."
+            );
+            bail!("Refusing to commit a message that contains potential secrets.");
+        }
+    }
+
+    if let Some(pattern) = commit_message_ticket_pattern(repo_root)
+        && !is_generated_commit_message(message)
+    {
+        let ticket = Regex::new(&pattern).with_context(|| {
+            format!("invalid [commit.message] require_ticket pattern '{pattern}'")
+        })?;
+        if !ticket.is_match(message) {
+            bail!(
+                "Commit message must reference a ticket matching `{}` ([commit.message] require_ticket).",
+                pattern
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Drop git's comment lines and anything below the `--verbose` scissors line.
+fn strip_commit_message_comments(raw: &str) -> String {
+    raw.lines()
+        .take_while(|line| !(line.starts_with("# -") && line.contains(">8")))
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `f pre-commit-hook`: run by the Flow-managed pre-commit hook (`f hooks install`).
+pub fn run_pre_commit_hook() -> Result<()> {
+    let repo_root = git_root_or_cwd();
+    let findings = scan_staged_secrets(&repo_root);
+    if findings.is_empty() {
+        return Ok(());
+    }
+    if env::var("FLOW_ALLOW_SECRET_COMMIT").ok().as_deref() == Some("1") {
+        println!(
+            "⚠️  Warning: Potential secrets detected but FLOW_ALLOW_SECRET_COMMIT=1, continuing..."
+        );
+        return Ok(());
+    }
+    print_secret_findings(
+        "🔐 Potential secrets detected in staged changes:",
+        &findings,
+    );
+    println!(
+        "Run `f secrets quarantine` to move them into the env store, set FLOW_ALLOW_SECRET_COMMIT=1 to override, or mark the line with '# flow:secret:ignore'."
+    );
+    bail!("commit blocked by Flow secret scan")
+}
+
+/// `f commit-msg-hook <file>`: run by the Flow-managed commit-msg hook.
+pub fn run_commit_msg_hook(opts: CommitMsgHookOpts) -> Result<()> {
+    let raw = fs::read_to_string(&opts.path)
+        .with_context(|| format!("failed to read {}", opts.path.display()))?;
+    let message = strip_commit_message_comments(&raw);
+    if message.trim().is_empty() {
+        // git aborts empty messages on its own.
+        return Ok(());
+    }
+    let repo_root = git_root_or_cwd();
+    check_commit_message(&repo_root, &message)?;
+    commit_signing::enforce(&repo_root)
+}
+
 fn has_unstaged_changes(repo_root: &Path, file: &str) -> bool {
     let output = Command::new("git")
         .args(["diff", "--name-only", "--", file])
@@ -724,7 +961,7 @@ fn load_ts_commit_config() -> Option<config::TsCommitConfig> {
         .and_then(|flow| flow.commit)
 }
 
-fn load_local_commit_config(repo_root: &Path) -> Option<config::CommitConfig> {
+pub(crate) fn load_local_commit_config(repo_root: &Path) -> Option<config::CommitConfig> {
     let local = repo_root.join("flow.toml");
     if !local.exists() {
         return None;
@@ -732,7 +969,7 @@ fn load_local_commit_config(repo_root: &Path) -> Option<config::CommitConfig> {
     config::load(&local).ok().and_then(|cfg| cfg.commit)
 }
 
-fn load_global_commit_config() -> Option<config::CommitConfig> {
+pub(crate) fn load_global_commit_config() -> Option<config::CommitConfig> {
     let global = config::default_config_path();
     if !global.exists() {
         return None;
@@ -2008,17 +2245,7 @@ pub fn run_sync(
 
     stage_changes_for_commit(&repo_root, stage_paths)?;
     debug!(paths = stage_paths.len(), "staged changes");
-    ensure_no_internal_staged(&repo_root)?;
-    ensure_no_unwanted_staged(&repo_root)?;
-    gitignore_policy::enforce_staged_policy(&repo_root)?;
-
-    // Check for sensitive files before proceeding
-    let sensitive_files = check_sensitive_files(&repo_root);
-    warn_sensitive_files(&sensitive_files)?;
-
-    // Scan diff content for hardcoded secrets
-    let secret_findings = scan_diff_for_secrets(&repo_root);
-    warn_secrets_in_diff(&repo_root, &secret_findings)?;
+    enforce_staged_gates(&repo_root)?;
 
     // Check for files with large diffs
     let large_diffs = check_large_diffs(&repo_root);
@@ -2085,6 +2312,9 @@ pub fn run_sync(
     println!("{}", message);
     println!("────────────────────────────────────────\n");
 
+    check_commit_message(&repo_root, &message)?;
+    commit_signing::enforce(&repo_root)?;
+
     // Commit
     let paragraphs = split_paragraphs(&message);
     debug!(
@@ -2233,7 +2463,7 @@ pub fn run_fast(
     warn_sensitive_files(&sensitive_files)?;
 
     // Scan diff content for hardcoded secrets
-    let secret_findings = scan_diff_for_secrets(&cwd);
+    let secret_findings = scan_staged_secrets(&cwd);
     warn_secrets_in_diff(&repo_root, &secret_findings)?;
 
     // Ensure we actually have changes
@@ -2270,6 +2500,9 @@ pub fn run_fast(
     ensure_no_unwanted_staged(&repo_root)?;
     gitignore_policy::enforce_staged_policy(&repo_root)?;
 
+    check_commit_message(&repo_root, &full_message)?;
+    commit_signing::enforce(&repo_root)?;
+
     // Commit
     git_run(&["commit", "-m", &full_message])?;
     println!("✓ Committed");
@@ -3819,6 +4052,67 @@ fn run_pre_commit_test_gate(
     Ok(())
 }
 
+fn run_verify_task_gate(repo_root: &Path, gate_overrides: CommitGateOverrides) -> Result<()> {
+    if gate_overrides.skip_quality {
+        return Ok(());
+    }
+    let config_path = repo_root.join("flow.toml");
+    let cfg = config::load_or_default(&config_path);
+    let Some(verify) = cfg.commit.and_then(|commit| commit.verify) else {
+        return Ok(());
+    };
+    let mode = verify
+        .mode
+        .unwrap_or_else(|| "warn".to_string())
+        .to_ascii_lowercase();
+    let task_names: Vec<String> = verify
+        .tasks
+        .into_iter()
+        .filter(|name| !name.trim().is_empty())
+        .collect();
+    if mode == "off" || task_names.is_empty() {
+        return Ok(());
+    }
+
+    let mut failed = Vec::new();
+    for name in &task_names {
+        println!();
+        println!("Running verify task '{}'...", name);
+        let result = tasks::run(TaskRunOpts {
+            config: config_path.clone(),
+            delegate_to_hub: false,
+            hub_host: IpAddr::from([127, 0, 0, 1]),
+            hub_port: 9050,
+            priority: 0,
+            print_env: false,
+            yes: false,
+            name: name.clone(),
+            args: Vec::new(),
+        });
+        if let Err(err) = result {
+            eprintln!("  verify: task '{}' failed: {:#}", name, err);
+            failed.push(name.as_str());
+        }
+    }
+
+    if failed.is_empty() {
+        println!(
+            "✓ Verify gate passed ({} task{})",
+            task_names.len(),
+            if task_names.len() == 1 { "" } else { "s" }
+        );
+        return Ok(());
+    }
+    if mode == "block" {
+        bail!(
+            "Commit blocked by verify gate ({}); fix and retry, or use --skip-quality",
+            failed.join(", ")
+        );
+    }
+    eprintln!("  verify: warning only (mode=warn)");
+    Ok(())
+}
+
 fn is_doc_gate_failure(message: &str) -> bool {
     let m = message.to_ascii_lowercase();
     m.contains("doc") || m.contains("documentation")
@@ -3955,7 +4249,7 @@ pub fn run_with_check_sync(
     warn_sensitive_files(&sensitive_files)?;
 
     // Scan diff content for hardcoded secrets
-    let secret_findings = scan_diff_for_secrets(&repo_root);
+    let secret_findings = scan_staged_secrets(&repo_root);
     warn_secrets_in_diff(&repo_root, &secret_findings)?;
 
     // Check for files with large diffs
@@ -3971,14 +4265,30 @@ pub fn run_with_check_sync(
     }
     let changed_files = changed_files_from_diff(&diff);
 
-    // Enforce required workflow skills before review.
-    let skill_gate_report = run_required_skill_gate(&repo_root, gate_overrides)?;
+    // Record the gate sequence as one run so failures show up in `f runs`.
+    let gate_run = runs::RunTracker::start(
+        runs::RunKind::Commit,
+        "commit-gate",
+        repo_root.file_name().and_then(|name| name.to_str()),
+        Some(&repo_root),
+    );
+    let gate_result = (|| -> Result<_> {
+        // Enforce required workflow skills before review.
+        let skill_gate_report = run_required_skill_gate(&repo_root, gate_overrides)?;
+
+        // Fast feedback loop: run impacted tests with Bun before AI review.
+        run_pre_commit_test_gate(&repo_root, &changed_files, gate_overrides)?;
 
-    // Fast feedback loop: run impacted tests with Bun before AI review.
-    run_pre_commit_test_gate(&repo_root, &changed_files, gate_overrides)?;
+        // Project verify tasks (e.g. lockfile drift) from [commit.verify].
+        run_verify_task_gate(&repo_root, gate_overrides)?;
 
-    // Enforce project invariants (forbidden patterns, dep policy, file size).
-    let invariant_report = run_invariant_gate(&repo_root, &diff, &changed_files, gate_overrides)?;
+        // Enforce project invariants (forbidden patterns, dep policy, file size).
+        let invariant_report =
+            run_invariant_gate(&repo_root, &diff, &changed_files, gate_overrides)?;
+        Ok((skill_gate_report, invariant_report))
+    })();
+    gate_run.finish_result(&gate_result);
+    let (skill_gate_report, invariant_report) = gate_result?;
 
     // Get AI session context since last checkpoint (if enabled)
     let session_context = if include_context {
@@ -4403,6 +4713,8 @@ pub fn run_with_check_sync(
     ensure_no_internal_staged(&repo_root)?;
     ensure_no_unwanted_staged(&repo_root)?;
     gitignore_policy::enforce_staged_policy(&repo_root)?;
+    check_commit_message(&repo_root, &full_message)?;
+    commit_signing::enforce(&repo_root)?;
 
     // Commit
     let paragraphs = split_paragraphs(&full_message);
@@ -6558,7 +6870,7 @@ fn ensure_git_repo() -> Result<()> {
     Ok(())
 }
 
-fn git_root_or_cwd() -> std::path::PathBuf {
+pub(crate) fn git_root_or_cwd() -> std::path::PathBuf {
     match git_capture(&["rev-parse", "--show-toplevel"]) {
         Ok(root) => std::path::PathBuf::from(root.trim()),
         Err(_) => std::env::current_dir().unwrap_or_default(),
@@ -12141,6 +12453,24 @@ fn build_deterministic_commit_message(diff: &str) -> String {
     }
 }
 
+/// AI commit message for one slice of a larger change (used by `f commit split`).
+pub(crate) fn generate_message_for_diff(
+    repo_root: &Path,
+    diff: &str,
+    status: &str,
+) -> Result<String> {
+    let commit_message_override = resolve_commit_message_override(repo_root);
+    let (diff_for_prompt, truncated) = truncate_diff(diff);
+    generate_commit_message_with_fallbacks(
+        repo_root,
+        None,
+        commit_message_override.as_ref(),
+        &diff_for_prompt,
+        status,
+        truncated,
+    )
+}
+
 fn generate_commit_message_with_fallbacks(
     repo_root: &Path,
     review_selection: Option<&ReviewSelection>,
@@ -16340,4 +16670,26 @@ mod tests {
         assert!(body.contains(&review_plan_path.display().to_string()));
         assert!(kit_system_path.ends_with("example-org-example-repo-pr-2922-kit-system.md"));
     }
+
+    #[test]
+    fn commit_message_check_enforces_ticket_and_secrets() {
+        let temp = tempdir().expect("tempdir");
+        fs::write(
+            temp.path().join("flow.toml"),
+            "[commit.message]\nrequire_ticket = \"PROJ-\\\\d+\"\n",
+        )
+        .expect("write flow.toml");
+
+        assert!(check_commit_message(temp.path(), "PROJ-42: fix login").is_ok());
+        let err = check_commit_message(temp.path(), "fix login").expect_err("needs ticket");
+        assert!(err.to_string().contains("PROJ-\\d+"));
+        assert!(check_commit_message(temp.path(), "Merge branch 'main'").is_ok());
+
+        let token = format!("ghp_{}", "a".repeat(36));
+        let message = format!("PROJ-1: rotate token {token}");
+        assert!(check_commit_message(temp.path(), &message).is_err());
+
+        let raw = "PROJ-7: tidy\n# Please enter the commit message\n# ------------------------ >8 ------------------------\ndiff --git a/x b/x\n";
+        assert_eq!(strip_commit_message_comments(raw), "PROJ-7: tidy");
+    }
 }
@@ -0,0 +1,538 @@
+//! `[commit.signing]`: require signed commits and help configure git to produce them.
+//!
+//! Enforcement checks the git configuration a commit will be made with (`commit.gpgsign`,
+//! `gpg.format`, `user.signingkey`) rather than signing itself, so plain `git commit` through
+//! the Flow commit-msg hook is held to the same policy as `f commit`.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+
+use crate::cli::{CommitSigningAction, CommitSigningCommand, CommitSigningSetupOpts};
+use crate::commit;
+use crate::config::CommitSigningConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigningFormat {
+    Ssh,
+    Gpg,
+}
+
+impl SigningFormat {
+    fn parse(raw: &str) -> Result<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "ssh" => Ok(Self::Ssh),
+            "gpg" | "openpgp" => Ok(Self::Gpg),
+            other => bail!("unknown [commit.signing] format '{other}' (use \"ssh\" or \"gpg\")"),
+        }
+    }
+
+    /// Value git expects in `gpg.format`.
+    fn git_value(self) -> &'static str {
+        match self {
+            Self::Ssh => "ssh",
+            Self::Gpg => "openpgp",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Ssh => "ssh",
+            Self::Gpg => "gpg",
+        }
+    }
+}
+
+/// The policy in effect: repo flow.toml first, then the global config.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SigningPolicy {
+    pub require: bool,
+    pub format: Option<SigningFormat>,
+    pub key: Option<String>,
+}
+
+impl SigningPolicy {
+    pub fn load(repo_root: &Path) -> Result<Option<Self>> {
+        let config = [
+            commit::load_local_commit_config(repo_root),
+            commit::load_global_commit_config(),
+        ]
+        .into_iter()
+        .flatten()
+        .find_map(|commit| commit.signing);
+        config.map(|config| Self::from_config(&config)).transpose()
+    }
+
+    fn from_config(config: &CommitSigningConfig) -> Result<Self> {
+        Ok(Self {
+            require: config.require.unwrap_or(false),
+            format: config
+                .format
+                .as_deref()
+                .filter(|raw| !raw.trim().is_empty())
+                .map(SigningFormat::parse)
+                .transpose()?,
+            key: config
+                .key
+                .as_deref()
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(str::to_string),
+        })
+    }
+}
+
+/// What git will do for the next commit in a repo.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitSigning {
+    pub gpgsign: bool,
+    /// `gpg.format`; unset means openpgp.
+    pub format: Option<String>,
+    pub key: Option<String>,
+    /// Whether the configured key can sign here (file or secret key present).
+    pub key_usable: bool,
+}
+
+impl GitSigning {
+    pub fn inspect(repo_root: &Path) -> Self {
+        let get = |key: &str| git_config_get(repo_root, key);
+        let format = get("gpg.format");
+        let key = get("user.signingkey");
+        let key_usable = match (&key, format.as_deref()) {
+            (Some(key), Some("ssh")) => ssh_key_material(key).is_some(),
+            (Some(key), None | Some("openpgp")) => gpg_secret_key_exists(key),
+            _ => false,
+        };
+        Self {
+            gpgsign: get("commit.gpgsign").is_some_and(|value| value == "true"),
+            format,
+            key,
+            key_usable,
+        }
+    }
+
+    fn format(&self) -> Option<SigningFormat> {
+        SigningFormat::parse(self.format.as_deref().unwrap_or("openpgp")).ok()
+    }
+}
+
+/// Everything in `git` that falls short of `policy`.
+pub fn problems(policy: &SigningPolicy, git: &GitSigning) -> Vec<String> {
+    let mut problems = Vec::new();
+    if !git.gpgsign {
+        problems.push("commit.gpgsign is not true, so commits are not signed".to_string());
+    }
+    if let Some(wanted) = policy.format
+        && git.format() != Some(wanted)
+    {
+        problems.push(format!(
+            "gpg.format is {}, policy wants {}",
+            git.format.as_deref().unwrap_or("openpgp (unset)"),
+            wanted.git_value()
+        ));
+    }
+    match &git.key {
+        None => problems.push("user.signingkey is not set".to_string()),
+        Some(key) => {
+            if !git.key_usable {
+                problems.push(format!(
+                    "signing key {key} is not available on this machine"
+                ));
+            }
+            if let Some(wanted) = &policy.key
+                && !same_key(wanted, key, policy.format.or(git.format()))
+            {
+                problems.push(format!("user.signingkey is {key}, policy prefers {wanted}"));
+            }
+        }
+    }
+    problems
+}
+
+fn same_key(a: &str, b: &str, format: Option<SigningFormat>) -> bool {
+    match format {
+        Some(SigningFormat::Ssh) => match (ssh_key_material(a), ssh_key_material(b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => expand_home(a) == expand_home(b),
+        },
+        _ => {
+            let normalize = |id: &str| {
+                id.trim()
+                    .trim_start_matches("0x")
+                    .trim_end_matches('!')
+                    .replace(' ', "")
+                    .to_ascii_uppercase()
+            };
+            let (a, b) = (normalize(a), normalize(b));
+            // A long key id is the tail of the fingerprint.
+            !a.is_empty() && !b.is_empty() && (a.ends_with(&b) || b.ends_with(&a))
+        }
+    }
+}
+
+/// `<type> <base64>` of an SSH signing key given as a path, `key::` literal, or bare literal.
+fn ssh_key_material(key: &str) -> Option<String> {
+    let key = key.trim();
+    let literal = key.strip_prefix("key::").unwrap_or(key);
+    let text = if literal.starts_with("ssh-") || literal.starts_with("ecdsa-") {
+        literal.to_string()
+    } else {
+        fs::read_to_string(expand_home(key)).ok()?
+    };
+    let mut fields = text.split_whitespace();
+    Some(format!("{} {}", fields.next()?, fields.next()?))
+}
+
+fn gpg_secret_key_exists(key: &str) -> bool {
+    Command::new("gpg")
+        .args(["--batch", "--list-secret-keys", key])
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// Block the commit when `[commit.signing] require = true` and git won't sign it properly.
+pub fn enforce(repo_root: &Path) -> Result<()> {
+    let Some(policy) = SigningPolicy::load(repo_root)? else {
+        return Ok(());
+    };
+    if !policy.require {
+        return Ok(());
+    }
+    let problems = problems(&policy, &GitSigning::inspect(repo_root));
+    if problems.is_empty() {
+        return Ok(());
+    }
+    println!("\n🔏 [commit.signing] requires signed commits:");
+    for problem in &problems {
+        println!("   - {problem}");
+    }
+    println!("Run `f commit signing setup` to configure git.");
+    bail!("commit blocked by [commit.signing] policy")
+}
+
+pub fn run(cmd: CommitSigningCommand) -> Result<()> {
+    match cmd.action {
+        Some(CommitSigningAction::Setup(opts)) => setup(opts),
+        Some(CommitSigningAction::Status) | None => status(),
+    }
+}
+
+fn status() -> Result<()> {
+    let repo_root = commit::git_root_or_cwd();
+    let policy = SigningPolicy::load(&repo_root)?;
+    let git = GitSigning::inspect(&repo_root);
+    println!(
+        "commit.gpgsign: {}",
+        if git.gpgsign { "true" } else { "false" }
+    );
+    println!(
+        "gpg.format:     {}",
+        git.format.as_deref().unwrap_or("openpgp (unset)")
+    );
+    println!(
+        "signing key:    {}{}",
+        git.key.as_deref().unwrap_or("(unset)"),
+        if git.key.is_some() && !git.key_usable {
+            " (not available here)"
+        } else {
+            ""
+        }
+    );
+    let Some(policy) = policy else {
+        println!("\nNo [commit.signing] policy in flow.toml.");
+        return Ok(());
+    };
+    let problems = problems(&policy, &git);
+    println!(
+        "\n[commit.signing]: require = {}{}{}",
+        policy.require,
+        policy
+            .format
+            .map(|format| format!(", format = {}", format.label()))
+            .unwrap_or_default(),
+        policy
+            .key
+            .as_deref()
+            .map(|key| format!(", key = {key}"))
+            .unwrap_or_default()
+    );
+    if problems.is_empty() {
+        println!("✅ git is set up to sign commits as the policy asks");
+    } else {
+        for problem in &problems {
+            println!("⚠️  {problem}");
+        }
+        println!("Run `f commit signing setup` to fix.");
+    }
+    Ok(())
+}
+
+/// `f commit signing setup`: pick a key and write the git config that signs with it.
+fn setup(opts: CommitSigningSetupOpts) -> Result<()> {
+    let repo_root = commit::git_root_or_cwd();
+    let policy = SigningPolicy::load(&repo_root)?.unwrap_or_default();
+    let format = match opts.format.as_deref() {
+        Some(raw) => SigningFormat::parse(raw)?,
+        None => policy.format.unwrap_or(SigningFormat::Ssh),
+    };
+    let key = match opts.key.clone().or_else(|| policy.key.clone()) {
+        Some(key) => key,
+        None => detect_key(format)?,
+    };
+    match format {
+        SigningFormat::Ssh if ssh_key_material(&key).is_none() => {
+            bail!("SSH key {key} not found; create one with `ssh-keygen -t ed25519`")
+        }
+        SigningFormat::Gpg if !gpg_secret_key_exists(&key) => {
+            bail!("no GPG secret key matches {key}; see `gpg --list-secret-keys`")
+        }
+        _ => {}
+    }
+
+    let scope = if opts.global { "--global" } else { "--local" };
+    let mut settings = vec![
+        ("gpg.format", format.git_value().to_string()),
+        ("user.signingkey", key.clone()),
+        ("commit.gpgsign", "true".to_string()),
+        ("tag.gpgsign", "true".to_string()),
+    ];
+    let mut allowed_signers = None;
+    if format == SigningFormat::Ssh {
+        let path = git_config_get(&repo_root, "gpg.ssh.allowedSignersFile")
+            .map(|path| expand_home(&path))
+            .unwrap_or_else(|| home_dir().join(".config/git/allowed_signers"));
+        settings.push((
+            "gpg.ssh.allowedSignersFile",
+            path.to_string_lossy().into_owned(),
+        ));
+        allowed_signers = Some(path);
+    }
+
+    println!(
+        "Configuring git ({}) to sign with {} key {}:",
+        scope.trim_start_matches('-'),
+        format.label(),
+        key
+    );
+    for (name, value) in &settings {
+        println!("  git config {scope} {name} {value}");
+    }
+    let email = git_config_get(&repo_root, "user.email");
+    if let (Some(path), Some(email), Some(material)) =
+        (&allowed_signers, &email, ssh_key_material(&key))
+    {
+        println!(
+            "  add \"{email} {material}\" to {} (lets `git log --show-signature` verify)",
+            path.display()
+        );
+    }
+    if opts.dry_run {
+        println!("Dry run: nothing changed.");
+        return Ok(());
+    }
+
+    for (name, value) in &settings {
+        let status = Command::new("git")
+            .current_dir(&repo_root)
+            .args(["config", scope, name, value])
+            .status()
+            .context("failed to run git config")?;
+        if !status.success() {
+            bail!("git config {scope} {name} failed");
+        }
+    }
+    if let (Some(path), Some(email), Some(material)) =
+        (&allowed_signers, &email, ssh_key_material(&key))
+    {
+        add_allowed_signer(path, email, &material)?;
+    }
+
+    let problems = problems(
+        &SigningPolicy {
+            require: true,
+            ..policy
+        },
+        &GitSigning::inspect(&repo_root),
+    );
+    if problems.is_empty() {
+        println!(
+            "✅ Commits in this {} are now signed.",
+            scope_label(opts.global)
+        );
+    } else {
+        for problem in &problems {
+            println!("⚠️  {problem}");
+        }
+    }
+    Ok(())
+}
+
+fn scope_label(global: bool) -> &'static str {
+    if global { "account" } else { "repo" }
+}
+
+/// First SSH public key in ~/.ssh, or the first GPG secret key.
+fn detect_key(format: SigningFormat) -> Result<String> {
+    match format {
+        SigningFormat::Ssh => ["id_ed25519.pub", "id_ecdsa.pub", "id_rsa.pub"]
+            .iter()
+            .map(|name| home_dir().join(".ssh").join(name))
+            .find(|path| path.exists())
+            .map(|path| path.to_string_lossy().into_owned())
+            .context("no SSH public key in ~/.ssh; create one with `ssh-keygen -t ed25519` or pass --key"),
+        SigningFormat::Gpg => {
+            let output = Command::new("gpg")
+                .args(["--batch", "--with-colons", "--list-secret-keys"])
+                .output()
+                .context("failed to run gpg; install GnuPG or use --format ssh")?;
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .find(|line| line.starts_with("fpr:"))
+                .and_then(|line| line.split(':').nth(9))
+                .filter(|fpr| !fpr.is_empty())
+                .map(str::to_string)
+                .context("no GPG secret keys found; create one with `gpg --full-generate-key` or pass --key")
+        }
+    }
+}
+
+fn add_allowed_signer(path: &Path, email: &str, material: &str) -> Result<()> {
+    let existing = fs::read_to_string(path).unwrap_or_default();
+    if existing.lines().any(|line| line.contains(material)) {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    if !existing.is_empty() && !existing.ends_with('\n') {
+        writeln!(file)?;
+    }
+    writeln!(file, "{email} namespaces=\"git\" {material}")?;
+    Ok(())
+}
+
+/// `f doctor` lines; `None` when neither a policy nor git signing is configured.
+pub fn doctor_report() -> Option<Vec<String>> {
+    let repo_root = commit::git_root_or_cwd();
+    let git = GitSigning::inspect(&repo_root);
+    let policy = match SigningPolicy::load(&repo_root) {
+        Ok(policy) => policy,
+        Err(err) => return Some(vec![format!("⚠️  [commit.signing]: {err:#}")]),
+    };
+    if policy.is_none() && !git.gpgsign {
+        return None;
+    }
+    let policy = policy.unwrap_or_default();
+    let mut lines = vec![format!(
+        "Commit signing ({}):",
+        if policy.require {
+            "required"
+        } else {
+            "optional"
+        }
+    )];
+    let problems = problems(&policy, &git);
+    if problems.is_empty() {
+        lines.push(format!(
+            "✅ signing with {} key {}",
+            git.format().map(SigningFormat::label).unwrap_or("gpg"),
+            git.key.as_deref().unwrap_or_default()
+        ));
+    } else {
+        lines.extend(problems.iter().map(|problem| format!("⚠️  {problem}")));
+        lines.push("   Fix with `f commit signing setup`.".to_string());
+    }
+    Some(lines)
+}
+
+fn git_config_get(repo_root: &Path, key: &str) -> Option<String> {
+    let output = Command::new("git")
+        .current_dir(repo_root)
+        .args(["config", "--get", key])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => home_dir().join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+fn home_dir() -> PathBuf {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(format: Option<&str>, key: Option<&str>) -> GitSigning {
+        GitSigning {
+            gpgsign: true,
+            format: format.map(str::to_string),
+            key: key.map(str::to_string),
+            key_usable: true,
+        }
+    }
+
+    #[test]
+    fn reports_policy_mismatches() {
+        let policy = SigningPolicy {
+            require: true,
+            format: Some(SigningFormat::Ssh),
+            key: Some("key::ssh-ed25519 AAAAC3Nza me@laptop".to_string()),
+        };
+        let ok = git(Some("ssh"), Some("ssh-ed25519 AAAAC3Nza other-comment"));
+        assert!(problems(&policy, &ok).is_empty());
+
+        let unsigned = GitSigning {
+            gpgsign: false,
+            ..git(None, Some("ABCD1234"))
+        };
+        let found = problems(&policy, &unsigned);
+        assert_eq!(found.len(), 3, "{found:?}");
+        assert!(found[0].contains("commit.gpgsign"));
+        assert!(found[1].contains("policy wants ssh"));
+        assert!(found[2].contains("policy prefers"));
+
+        let missing_key = git(Some("ssh"), None);
+        assert_eq!(
+            problems(&policy, &missing_key),
+            ["user.signingkey is not set"]
+        );
+    }
+
+    #[test]
+    fn matches_gpg_key_ids_against_fingerprints() {
+        let gpg = Some(SigningFormat::Gpg);
+        assert!(same_key(
+            "0x3AA5C34371567BD2",
+            "4AEE18F83AFDEB23 3AA5C34371567BD2"
+                .replace(' ', "")
+                .as_str(),
+            gpg
+        ));
+        assert!(same_key("3aa5c34371567bd2!", "3AA5C34371567BD2", gpg));
+        assert!(!same_key("3AA5C34371567BD2", "0000000071567BD2FF", gpg));
+        assert!(SigningFormat::parse("x509").is_err());
+        assert_eq!(SigningFormat::parse("OpenPGP").unwrap(), SigningFormat::Gpg);
+    }
+}
@@ -0,0 +1,1039 @@
+//! `f commit split`: turn one large staged change into a series of smaller commits.
+//!
+//! The staged diff is parsed into hunks and clustered by affinity: hunks in the same file
+//! stay together, files that share a directory (or a module stem for flat source roots)
+//! are grouped, and a symbol defined in one group pulls in every hunk that uses it. The
+//! proposed series is shown in a TUI where hunks can be moved between commits and
+//! subjects edited. Applying resets the index to HEAD and replays each commit with
+//! `git apply --cached`; if anything fails the original index is restored.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result, bail};
+use crossterm::{
+    event::{self, Event as CEvent, KeyCode, KeyEvent},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use ratatui::{
+    Terminal,
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+};
+use regex::Regex;
+
+use crate::cli::CommitSplitOpts;
+use crate::commit;
+use crate::commit_signing;
+
+/// Identifiers shorter than this are too generic to link hunks together.
+const MIN_SYMBOL_LEN: usize = 4;
+
+#[derive(Debug, Clone)]
+struct FilePatch {
+    path: String,
+    /// `diff --git` line through `+++`, plus the body of binary patches.
+    header: Vec<String>,
+    hunks: Vec<Hunk>,
+    /// New, deleted, binary, or mode-only files can't be split below the file level.
+    whole: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Hunk {
+    header: String,
+    lines: Vec<String>,
+}
+
+impl Hunk {
+    fn added(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().filter_map(|line| line.strip_prefix('+'))
+    }
+
+    /// Function context git printed after the `@@ ... @@` range, if any.
+    fn context(&self) -> Option<&str> {
+        let rest = self.header.strip_prefix("@@")?;
+        let (_, context) = rest.split_once("@@")?;
+        let context = context.trim();
+        (!context.is_empty()).then_some(context)
+    }
+}
+
+/// A hunk, or a whole file when it can't be split.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Unit {
+    file: usize,
+    hunk: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ProposedCommit {
+    message: String,
+    units: Vec<usize>,
+}
+
+struct StagedChange {
+    files: Vec<FilePatch>,
+    units: Vec<Unit>,
+}
+
+pub fn run(opts: CommitSplitOpts) -> Result<()> {
+    let CommitSplitOpts { dry, ai_names } = opts;
+    let repo_root = git_output(None, &["rev-parse", "--show-toplevel"])
+        .map(|root| std::path::PathBuf::from(root.trim()))
+        .context("f commit split must run inside a git repository")?;
+    if git_output(Some(&repo_root), &["rev-parse", "--verify", "-q", "HEAD"]).is_err() {
+        bail!("f commit split needs an existing commit to split onto");
+    }
+    // The commits below are made with plain `git commit`, so run `f commit`'s staged
+    // gates (secret scan included) on the whole index before it is split.
+    if !dry {
+        commit::enforce_staged_gates(&repo_root)?;
+    }
+
+    let diff = git_output(
+        Some(&repo_root),
+        &[
+            "-c",
+            "core.quotePath=false",
+            "diff",
+            "--cached",
+            "--no-color",
+            "--no-ext-diff",
+            "--no-renames",
+            "--binary",
+            "--src-prefix=a/",
+            "--dst-prefix=b/",
+        ],
+    )?;
+    let change = parse_staged(&diff);
+    if change.units.is_empty() {
+        bail!("No staged changes to split");
+    }
+
+    let mut plan: Vec<ProposedCommit> = cluster(&change)
+        .into_iter()
+        .map(|units| ProposedCommit {
+            message: default_message(&change, &units),
+            units,
+        })
+        .collect();
+    if ai_names {
+        name_with_ai(&repo_root, &change, &mut plan);
+    }
+
+    if dry {
+        print_plan(&change, &plan);
+        return Ok(());
+    }
+    if plan.len() == 1 {
+        print_plan(&change, &plan);
+        println!("\nEverything staged belongs together; use f commit instead.");
+        return Ok(());
+    }
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        print_plan(&change, &plan);
+        bail!("f commit split needs a terminal to confirm the series (use --dry to preview)");
+    }
+
+    let Some(plan) = review_plan(&change, plan)? else {
+        println!("Split cancelled; staged changes are unchanged.");
+        return Ok(());
+    };
+    for proposed in &plan {
+        commit::check_commit_message(&repo_root, &proposed.message)?;
+    }
+    commit_signing::enforce(&repo_root)?;
+    apply_plan(&repo_root, &change, &plan)?;
+    println!("Created {} commits:", plan.len());
+    let log = git_output(
+        Some(&repo_root),
+        &["log", "--oneline", &format!("-{}", plan.len())],
+    )?;
+    for line in log.lines().rev() {
+        println!("  {line}");
+    }
+    Ok(())
+}
+
+fn parse_staged(diff: &str) -> StagedChange {
+    let mut files: Vec<FilePatch> = Vec::new();
+    for line in diff.lines() {
+        if line.starts_with("diff --git ") {
+            files.push(FilePatch {
+                path: path_from_diff_line(line),
+                header: vec![line.to_string()],
+                hunks: Vec::new(),
+                whole: false,
+            });
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+        if line.starts_with("@@") {
+            file.hunks.push(Hunk {
+                header: line.to_string(),
+                lines: Vec::new(),
+            });
+        } else if let Some(hunk) = file.hunks.last_mut() {
+            hunk.lines.push(line.to_string());
+        } else {
+            if line.starts_with("new file mode")
+                || line.starts_with("deleted file mode")
+                || line.starts_with("GIT binary patch")
+                || line.starts_with("Binary files")
+            {
+                file.whole = true;
+            }
+            if let Some(path) = line.strip_prefix("+++ b/") {
+                file.path = path.trim_end_matches('\t').to_string();
+            }
+            file.header.push(line.to_string());
+        }
+    }
+
+    let mut units = Vec::new();
+    for (idx, file) in files.iter_mut().enumerate() {
+        if file.hunks.is_empty() {
+            file.whole = true;
+        }
+        if file.whole {
+            units.push(Unit {
+                file: idx,
+                hunk: None,
+            });
+        } else {
+            units.extend((0..file.hunks.len()).map(|hunk| Unit {
+                file: idx,
+                hunk: Some(hunk),
+            }));
+        }
+    }
+    StagedChange { files, units }
+}
+
+fn path_from_diff_line(line: &str) -> String {
+    line.rsplit_once(" b/")
+        .map(|(_, path)| path.to_string())
+        .unwrap_or_else(|| line.trim_start_matches("diff --git ").to_string())
+}
+
+/// Group units into commits, ordered manifests, source, tests, then docs.
+fn cluster(change: &StagedChange) -> Vec<Vec<usize>> {
+    let mut sets = DisjointSets::new(change.units.len());
+
+    let mut by_key: HashMap<String, usize> = HashMap::new();
+    for (idx, unit) in change.units.iter().enumerate() {
+        let key = affinity_key(&change.files[unit.file].path);
+        match by_key.get(&key) {
+            Some(&first) => sets.union(first, idx),
+            None => {
+                by_key.insert(key, idx);
+            }
+        }
+    }
+
+    let mut definitions: HashMap<String, usize> = HashMap::new();
+    for (idx, unit) in change.units.iter().enumerate() {
+        for symbol in defined_symbols(unit_added(change, unit)) {
+            definitions.entry(symbol).or_insert(idx);
+        }
+    }
+    for (idx, unit) in change.units.iter().enumerate() {
+        for line in unit_added(change, unit) {
+            for ident in identifier_regex().find_iter(line) {
+                if let Some(&definer) = definitions.get(ident.as_str()) {
+                    sets.union(definer, idx);
+                }
+            }
+        }
+    }
+
+    let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for idx in 0..change.units.len() {
+        groups.entry(sets.find(idx)).or_default().push(idx);
+    }
+    let mut groups: Vec<Vec<usize>> = groups.into_values().collect();
+    groups.sort_by_key(|units| {
+        let first = &change.files[change.units[units[0]].file].path;
+        let rank = units
+            .iter()
+            .map(|&idx| path_rank(&change.files[change.units[idx].file].path))
+            .min()
+            .unwrap_or(1);
+        (rank, first.clone())
+    });
+    groups
+}
+
+/// Files sharing a key land in the same commit: the parent directory, or the file stem
+/// when the file sits directly in the repo root or a top-level dir such as `src/`.
+fn affinity_key(path: &str) -> String {
+    let path = Path::new(path);
+    let parent = path.parent().unwrap_or(Path::new(""));
+    if parent.components().count() >= 2 {
+        return parent.to_string_lossy().into_owned();
+    }
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    // `Cargo.toml` and `Cargo.lock` belong together, as do `foo.rs` and `foo/`.
+    format!("{}:{}", parent.display(), stem)
+}
+
+fn path_rank(path: &str) -> u8 {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    const MANIFESTS: &[&str] = &[
+        "Cargo.toml",
+        "Cargo.lock",
+        "package.json",
+        "bun.lock",
+        "bun.lockb",
+        "pnpm-lock.yaml",
+        "package-lock.json",
+        "yarn.lock",
+        "go.mod",
+        "go.sum",
+        "pyproject.toml",
+    ];
+    if MANIFESTS.contains(&name) {
+        0
+    } else if path.ends_with(".md") || path.starts_with("docs/") {
+        3
+    } else if path
+        .split('/')
+        .any(|part| part == "tests" || part == "test" || part == "__tests__")
+        || name.contains("_test.")
+        || name.contains(".test.")
+        || name.contains(".spec.")
+    {
+        2
+    } else {
+        1
+    }
+}
+
+fn unit_added<'a>(change: &'a StagedChange, unit: &Unit) -> Vec<&'a str> {
+    let file = &change.files[unit.file];
+    match unit.hunk {
+        Some(hunk) => file.hunks[hunk].added().collect(),
+        None => file.hunks.iter().flat_map(Hunk::added).collect(),
+    }
+}
+
+fn defined_symbols<'a>(lines: impl IntoIterator<Item = &'a str>) -> HashSet<String> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| {
+        Regex::new(
+            r"\b(?:fn|struct|enum|trait|type|const|static|mod|class|def|function|interface)\s+([A-Za-z_][A-Za-z0-9_]*)",
+        )
+        .expect("valid definition regex")
+    });
+    lines
+        .into_iter()
+        .flat_map(|line| re.captures_iter(line))
+        .map(|caps| caps[1].to_string())
+        .filter(|name| name.len() >= MIN_SYMBOL_LEN)
+        .collect()
+}
+
+fn identifier_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"[A-Za-z_][A-Za-z0-9_]*").expect("valid identifier regex"))
+}
+
+struct DisjointSets {
+    parent: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+        }
+    }
+
+    fn find(&mut self, idx: usize) -> usize {
+        let mut root = idx;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        self.parent[idx] = root;
+        root
+    }
+
+    /// Merge two sets, keeping the smaller index as root so groups sort stably.
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent[a.max(b)] = a.min(b);
+        }
+    }
+}
+
+/// The patch for a set of units, in original file and hunk order.
+fn build_patch(change: &StagedChange, units: &[usize]) -> String {
+    let mut selected: BTreeMap<usize, Vec<Option<usize>>> = BTreeMap::new();
+    for &idx in units {
+        let unit = change.units[idx];
+        selected.entry(unit.file).or_default().push(unit.hunk);
+    }
+    let mut patch = String::new();
+    for (file_idx, mut hunks) in selected {
+        let file = &change.files[file_idx];
+        hunks.sort();
+        for line in &file.header {
+            patch.push_str(line);
+            patch.push('\n');
+        }
+        let chosen: Vec<&Hunk> = if file.whole {
+            file.hunks.iter().collect()
+        } else {
+            hunks
+                .into_iter()
+                .flatten()
+                .map(|hunk| &file.hunks[hunk])
+                .collect()
+        };
+        for hunk in chosen {
+            patch.push_str(&hunk.header);
+            patch.push('\n');
+            for line in &hunk.lines {
+                patch.push_str(line);
+                patch.push('\n');
+            }
+        }
+    }
+    patch
+}
+
+fn unit_paths<'a>(change: &'a StagedChange, units: &[usize]) -> Vec<&'a str> {
+    let mut paths: Vec<&str> = units
+        .iter()
+        .map(|&idx| change.files[change.units[idx].file].path.as_str())
+        .collect();
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+fn default_message(change: &StagedChange, units: &[usize]) -> String {
+    let paths = unit_paths(change, units);
+    let subject = match paths.as_slice() {
+        [single] => {
+            let header = &change.files[change.units[units[0]].file].header;
+            if header.iter().any(|line| line.starts_with("new file mode")) {
+                format!("Add {single}")
+            } else if header
+                .iter()
+                .any(|line| line.starts_with("deleted file mode"))
+            {
+                format!("Remove {single}")
+            } else {
+                format!("Update {single}")
+            }
+        }
+        _ => match common_dir(&paths) {
+            Some(dir) => format!("Update {dir} ({} files)", paths.len()),
+            None => format!("Update {} files", paths.len()),
+        },
+    };
+    if paths.len() == 1 {
+        return subject;
+    }
+    let mut body: Vec<String> = paths
+        .iter()
+        .take(5)
+        .map(|path| format!("- {path}"))
+        .collect();
+    if paths.len() > 5 {
+        body.push(format!("- and {} more files", paths.len() - 5));
+    }
+    format!("{subject}\n\n{}", body.join("\n"))
+}
+
+fn common_dir(paths: &[&str]) -> Option<String> {
+    let mut common: Vec<&str> = paths.first()?.split('/').collect();
+    common.pop();
+    for path in &paths[1..] {
+        let parts: Vec<&str> = path.split('/').collect();
+        let shared = common
+            .iter()
+            .zip(&parts[..parts.len() - 1])
+            .take_while(|(a, b)| a == b)
+            .count();
+        common.truncate(shared);
+    }
+    (!common.is_empty()).then(|| common.join("/"))
+}
+
+fn name_with_ai(repo_root: &Path, change: &StagedChange, plan: &mut [ProposedCommit]) {
+    let total = plan.len();
+    for (idx, proposed) in plan.iter_mut().enumerate() {
+        print!("Naming commit {}/{}... ", idx + 1, total);
+        std::io::stdout().flush().ok();
+        let patch = build_patch(change, &proposed.units);
+        let status = unit_paths(change, &proposed.units).join("\n");
+        match commit::generate_message_for_diff(repo_root, &patch, &status) {
+            Ok(message) => {
+                println!("done");
+                proposed.message = message;
+            }
+            Err(err) => println!("kept default ({err})"),
+        }
+    }
+}
+
+fn subject(message: &str) -> &str {
+    message.lines().next().unwrap_or("")
+}
+
+fn unit_label(change: &StagedChange, idx: usize) -> String {
+    let unit = change.units[idx];
+    let file = &change.files[unit.file];
+    match unit.hunk {
+        None => file.path.clone(),
+        Some(hunk) => {
+            let hunk = &file.hunks[hunk];
+            let start = hunk
+                .header
+                .split_whitespace()
+                .find_map(|part| part.strip_prefix('+'))
+                .and_then(|range| range.split(',').next())
+                .unwrap_or("?");
+            match hunk.context() {
+                Some(context) => format!("{}:{} {}", file.path, start, context),
+                None => format!("{}:{}", file.path, start),
+            }
+        }
+    }
+}
+
+fn print_plan(change: &StagedChange, plan: &[ProposedCommit]) {
+    println!(
+        "Proposed {} commit(s) from {} staged hunk(s):",
+        plan.len(),
+        change.units.len()
+    );
+    for (idx, proposed) in plan.iter().enumerate() {
+        println!("\n{}. {}", idx + 1, subject(&proposed.message));
+        for &unit in &proposed.units {
+            println!("     {}", unit_label(change, unit));
+        }
+    }
+}
+
+/// Reset the index to HEAD and replay each commit. Restores the original index on failure.
+fn apply_plan(repo_root: &Path, change: &StagedChange, plan: &[ProposedCommit]) -> Result<()> {
+    let original_tree = git_output(Some(repo_root), &["write-tree"])?;
+    let original_tree = original_tree.trim();
+    git_output(Some(repo_root), &["read-tree", "HEAD"])?;
+
+    for (idx, proposed) in plan.iter().enumerate() {
+        let patch = build_patch(change, &proposed.units);
+        let result = git_with_stdin(
+            repo_root,
+            &["apply", "--cached", "--whitespace=nowarn", "-"],
+            &patch,
+        )
+        .and_then(|_| git_with_stdin(repo_root, &["commit", "-q", "-F", "-"], &proposed.message));
+        if let Err(err) = result {
+            git_output(Some(repo_root), &["read-tree", original_tree]).ok();
+            bail!(
+                "commit {}/{} failed: {err:#}\n{} commit(s) were created; everything not yet committed is staged again",
+                idx + 1,
+                plan.len(),
+                idx
+            );
+        }
+    }
+
+    let final_tree = git_output(Some(repo_root), &["write-tree"])?;
+    if final_tree.trim() != original_tree {
+        git_output(Some(repo_root), &["read-tree", original_tree])?;
+        println!(
+            "⚠ The split commits don't add up to the staged tree; the difference is staged again."
+        );
+    }
+    Ok(())
+}
+
+fn git_output(workdir: Option<&Path>, args: &[&str]) -> Result<String> {
+    let mut cmd = Command::new("git");
+    if let Some(workdir) = workdir {
+        cmd.current_dir(workdir);
+    }
+    let output = cmd
+        .args(args)
+        .output()
+        .with_context(|| format!("failed to run git {}", args.join(" ")))?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn git_with_stdin(repo_root: &Path, args: &[&str], input: &str) -> Result<()> {
+    let mut child = Command::new("git")
+        .current_dir(repo_root)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run git {}", args.join(" ")))?;
+    child
+        .stdin
+        .take()
+        .context("git stdin unavailable")?
+        .write_all(input.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Review TUI
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Row {
+    Commit(usize),
+    Unit(usize, usize),
+}
+
+struct SplitApp<'a> {
+    change: &'a StagedChange,
+    plan: Vec<ProposedCommit>,
+    selected: usize,
+    editing: Option<String>,
+    result: Option<Vec<ProposedCommit>>,
+}
+
+impl SplitApp<'_> {
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        for (commit_idx, proposed) in self.plan.iter().enumerate() {
+            rows.push(Row::Commit(commit_idx));
+            rows.extend((0..proposed.units.len()).map(|pos| Row::Unit(commit_idx, pos)));
+        }
+        rows
+    }
+
+    fn current(&self) -> Option<Row> {
+        self.rows().get(self.selected).copied()
+    }
+
+    fn current_commit(&self) -> Option<usize> {
+        match self.current()? {
+            Row::Commit(idx) | Row::Unit(idx, _) => Some(idx),
+        }
+    }
+
+    fn select(&mut self, row: Row) {
+        if let Some(pos) = self.rows().iter().position(|candidate| *candidate == row) {
+            self.selected = pos;
+        }
+    }
+
+    /// Move the selected hunk into commit `to` (appending a new commit when `to` is past
+    /// the end) and drop commits left empty. Messages that were still the generated
+    /// default are regenerated; edited or AI-written ones are kept.
+    fn move_unit(&mut self, to: usize) {
+        let Some(Row::Unit(from, pos)) = self.current() else {
+            return;
+        };
+        if to == from {
+            return;
+        }
+        if to >= self.plan.len() {
+            self.plan.push(ProposedCommit {
+                message: String::new(),
+                units: Vec::new(),
+            });
+        }
+        let mut to = to.min(self.plan.len() - 1);
+        let unit = self.plan[from].units[pos];
+        self.edit_units(from, |units| units.retain(|u| *u != unit));
+        self.edit_units(to, |units| {
+            units.push(unit);
+            units.sort();
+        });
+        let target = self.plan[to].units.iter().position(|u| *u == unit);
+        if self.plan[from].units.is_empty() {
+            self.plan.remove(from);
+            if to > from {
+                to -= 1;
+            }
+        }
+        if let Some(pos) = target {
+            self.select(Row::Unit(to, pos));
+        }
+    }
+
+    fn edit_units(&mut self, idx: usize, edit: impl FnOnce(&mut Vec<usize>)) {
+        let proposed = &mut self.plan[idx];
+        let was_default = proposed.message.is_empty()
+            || proposed.message == default_message(self.change, &proposed.units);
+        edit(&mut proposed.units);
+        if was_default && !proposed.units.is_empty() {
+            proposed.message = default_message(self.change, &proposed.units);
+        }
+    }
+
+    fn swap_commit(&mut self, up: bool) {
+        let Some(idx) = self.current_commit() else {
+            return;
+        };
+        let other = if up {
+            idx.checked_sub(1)
+        } else {
+            (idx + 1 < self.plan.len()).then_some(idx + 1)
+        };
+        if let Some(other) = other {
+            self.plan.swap(idx, other);
+            self.select(Row::Commit(other));
+        }
+    }
+}
+
+fn review_plan(
+    change: &StagedChange,
+    plan: Vec<ProposedCommit>,
+) -> Result<Option<Vec<ProposedCommit>>> {
+    let mut app = SplitApp {
+        change,
+        plan,
+        selected: 0,
+        editing: None,
+        result: None,
+    };
+
+    enable_raw_mode().context("failed to enable raw mode")?;
+    let mut stdout = std::io::stdout();
+    execute!(stdout, EnterAlternateScreen).context("failed to enter alternate screen")?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).context("failed to create terminal backend")?;
+
+    let app_result = run_app(&mut terminal, &mut app);
+
+    disable_raw_mode().ok();
+    let _ = terminal.show_cursor();
+    drop(terminal);
+    let mut stdout = std::io::stdout();
+    execute!(stdout, LeaveAlternateScreen).ok();
+
+    app_result
+}
+
+fn run_app<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut SplitApp<'_>,
+) -> Result<Option<Vec<ProposedCommit>>> {
+    loop {
+        terminal
+            .draw(|f| draw_ui(f, app))
+            .map_err(|err| anyhow::anyhow!("failed to draw commit split UI: {err}"))?;
+
+        if event::poll(std::time::Duration::from_millis(200))?
+            && let CEvent::Key(key) = event::read()?
+            && handle_key(app, key)
+        {
+            return Ok(app.result.take());
+        }
+    }
+}
+
+fn handle_key(app: &mut SplitApp<'_>, key: KeyEvent) -> bool {
+    if let Some(input) = app.editing.as_mut() {
+        match key.code {
+            KeyCode::Enter => {
+                let subject = input.trim().to_string();
+                app.editing = None;
+                if let Some(idx) = app.current_commit()
+                    && !subject.is_empty()
+                {
+                    let message = &mut app.plan[idx].message;
+                    *message = match message.split_once('\n') {
+                        Some((_, body)) => format!("{subject}\n{body}"),
+                        None => subject,
+                    };
+                }
+            }
+            KeyCode::Esc => app.editing = None,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(ch) if !ch.is_control() => input.push(ch),
+            _ => {}
+        }
+        return false;
+    }
+
+    let row_count = app.rows().len();
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => return true,
+        KeyCode::Up | KeyCode::Char('k') => app.selected = app.selected.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => {
+            app.selected = (app.selected + 1).min(row_count.saturating_sub(1));
+        }
+        KeyCode::Char('[') => {
+            if let Some(Row::Unit(from, _)) = app.current()
+                && from > 0
+            {
+                app.move_unit(from - 1);
+            }
+        }
+        KeyCode::Char(']') => {
+            if let Some(Row::Unit(from, _)) = app.current() {
+                app.move_unit(from + 1);
+            }
+        }
+        KeyCode::Char('n') => {
+            let end = app.plan.len();
+            app.move_unit(end);
+        }
+        KeyCode::Char('K') => app.swap_commit(true),
+        KeyCode::Char('J') => app.swap_commit(false),
+        KeyCode::Char('e') => {
+            if let Some(idx) = app.current_commit() {
+                app.editing = Some(subject(&app.plan[idx].message).to_string());
+            }
+        }
+        KeyCode::Enter | KeyCode::Char('a') => {
+            app.result = Some(app.plan.clone());
+            return true;
+        }
+        _ => {}
+    }
+    false
+}
+
+fn draw_ui(f: &mut ratatui::Frame<'_>, app: &SplitApp<'_>) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(3),
+                Constraint::Min(1),
+                Constraint::Length(3),
+            ]
+            .as_ref(),
+        )
+        .split(f.area());
+
+    let title = format!(
+        "Split {} hunk(s) into {} commit(s)",
+        app.change.units.len(),
+        app.plan.len()
+    );
+    let header = Paragraph::new(Line::from(title))
+        .block(Block::default().borders(Borders::ALL).title("flow"))
+        .alignment(ratatui::layout::Alignment::Center);
+    f.render_widget(header, chunks[0]);
+
+    let body = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(chunks[1]);
+
+    let rows = app.rows();
+    let items = rows
+        .iter()
+        .map(|row| match *row {
+            Row::Commit(idx) => ListItem::new(Line::from(vec![
+                Span::styled(format!("{}. ", idx + 1), Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    subject(&app.plan[idx].message).to_string(),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+            ])),
+            Row::Unit(idx, pos) => ListItem::new(Line::from(format!(
+                "     {}",
+                unit_label(app.change, app.plan[idx].units[pos])
+            ))),
+        })
+        .collect::<Vec<_>>();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title("Commits"))
+        .highlight_style(
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        );
+    let mut state = ListState::default();
+    state.select(Some(app.selected));
+    f.render_stateful_widget(list, body[0], &mut state);
+
+    let preview_lines: Vec<Line> = match app.current() {
+        Some(Row::Unit(idx, pos)) => {
+            let patch = build_patch(app.change, &[app.plan[idx].units[pos]]);
+            patch
+                .lines()
+                .map(|line| {
+                    let color = match line.chars().next() {
+                        Some('+') => Color::Green,
+                        Some('-') => Color::Red,
+                        Some('@') => Color::Cyan,
+                        _ => Color::Reset,
+                    };
+                    Line::from(Span::styled(line.to_string(), Style::default().fg(color)))
+                })
+                .collect()
+        }
+        Some(Row::Commit(idx)) => app.plan[idx]
+            .message
+            .lines()
+            .map(|line| Line::from(line.to_string()))
+            .collect(),
+        None => Vec::new(),
+    };
+    let preview = Paragraph::new(preview_lines)
+        .block(Block::default().borders(Borders::ALL).title("Preview"))
+        .wrap(Wrap { trim: false });
+    f.render_widget(preview, body[1]);
+
+    let footer = match &app.editing {
+        Some(input) => Paragraph::new(format!("subject> {input}")),
+        None => Paragraph::new(
+            "j/k move  [/] hunk to prev/next commit  n new commit  J/K reorder  e edit subject  enter apply  q cancel",
+        ),
+    };
+    f.render_widget(
+        footer.block(Block::default().borders(Borders::ALL)),
+        chunks[2],
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/src/proxy/auth.rs b/src/proxy/auth.rs
+new file mode 100644
+index 0000000..1111111
+--- /dev/null
++++ b/src/proxy/auth.rs
+@@ -0,0 +1,3 @@
++pub fn inject_header(headers: &mut Headers) {
++    headers.push(token());
++}
+diff --git a/src/proxy/server.rs b/src/proxy/server.rs
+index 2222222..3333333 100644
+--- a/src/proxy/server.rs
++++ b/src/proxy/server.rs
+@@ -10,3 +10,4 @@ fn handle(req: Request) {
+     let mut headers = collect(req);
++    inject_header(&mut headers);
+     forward(headers)
+ }
+diff --git a/src/tasks.rs b/src/tasks.rs
+index 4444444..5555555 100644
+--- a/src/tasks.rs
++++ b/src/tasks.rs
+@@ -1,2 +1,2 @@
+-use std::fs;
++use std::fs::{self, File};
+ use std::io;
+@@ -40,2 +40,3 @@ fn run_task() {
+     let status = run();
++    log(status);
+diff --git a/docs/commands/tasks.md b/docs/commands/tasks.md
+index 6666666..7777777 100644
+--- a/docs/commands/tasks.md
++++ b/docs/commands/tasks.md
+@@ -1,1 +1,2 @@
+ # f tasks
++Tasks now log their status.
+";
+
+    #[test]
+    fn parses_hunks_and_rebuilds_partial_patches() {
+        let change = parse_staged(DIFF);
+        assert_eq!(change.files.len(), 4);
+        assert!(change.files[0].whole);
+        assert_eq!(change.units.len(), 5);
+        assert_eq!(change.files[2].path, "src/tasks.rs");
+        assert_eq!(change.files[2].hunks[1].context(), Some("fn run_task() {"));
+        assert_eq!(unit_label(&change, 3), "src/tasks.rs:40 fn run_task() {");
+
+        let patch = build_patch(&change, &[3]);
+        assert!(patch.starts_with("diff --git a/src/tasks.rs b/src/tasks.rs\n"));
+        assert!(patch.contains("+    log(status);\n"));
+        assert!(!patch.contains("use std::fs"));
+        assert_eq!(build_patch(&change, &(0..5).collect::<Vec<_>>()), DIFF);
+    }
+
+    #[test]
+    fn clusters_by_directory_stem_and_symbol() {
+        let change = parse_staged(DIFF);
+        let groups = cluster(&change);
+        // proxy files share a directory; tasks.rs hunks share a file; docs go last.
+        assert_eq!(groups, vec![vec![0, 1], vec![2, 3], vec![4]]);
+
+        assert_eq!(affinity_key("src/tasks.rs"), "src:tasks");
+        assert_eq!(affinity_key("Cargo.toml"), affinity_key("Cargo.lock"));
+        assert_eq!(affinity_key("src/proxy/auth.rs"), "src/proxy");
+        assert!(defined_symbols(["pub fn inject_header(x: u8) {"]).contains("inject_header"));
+        assert!(defined_symbols(["fn run() {"]).is_empty());
+    }
+
+    #[test]
+    fn moving_hunks_updates_the_series() {
+        let change = parse_staged(DIFF);
+        let plan = cluster(&change)
+            .into_iter()
+            .map(|units| ProposedCommit {
+                message: default_message(&change, &units),
+                units,
+            })
+            .collect();
+        let mut app = SplitApp {
+            change: &change,
+            plan,
+            selected: 0,
+            editing: None,
+            result: None,
+        };
+        assert_eq!(subject(&app.plan[0].message), "Update src/proxy (2 files)");
+        assert_eq!(
+            subject(&app.plan[2].message),
+            "Update docs/commands/tasks.md"
+        );
+
+        // Move the docs hunk into the tasks commit; the emptied commit disappears.
+        app.select(Row::Unit(2, 0));
+        app.move_unit(1);
+        assert_eq!(app.plan.len(), 2);
+        assert_eq!(app.plan[1].units, vec![2, 3, 4]);
+        assert_eq!(app.current(), Some(Row::Unit(1, 2)));
+
+        // Split the second tasks.rs hunk into its own commit at the end.
+        app.select(Row::Unit(1, 1));
+        app.move_unit(app.plan.len());
+        assert_eq!(app.plan.len(), 3);
+        assert_eq!(app.plan[2].units, vec![3]);
+        assert_eq!(subject(&app.plan[2].message), "Update src/tasks.rs");
+    }
+}
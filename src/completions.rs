@@ -0,0 +1,204 @@
+//! Shell completion scripts with dynamic task, project, and deploy target candidates.
+//!
+//! `f completions <shell>` prints the clap-generated script followed by a small hook that calls
+//! back into `f completions --list <kind>` so task names come from the live flow.toml.
+
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use clap::CommandFactory;
+use clap_complete::{Shell, generate};
+
+use crate::cli::{Cli, CompletionList, CompletionShell, CompletionsOpts};
+use crate::config::{self, Config};
+use crate::{project_snapshot, projects};
+
+pub fn run(opts: CompletionsOpts) -> Result<()> {
+    if let Some(list) = opts.list {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        for candidate in candidates(list, &cwd) {
+            println!("{candidate}");
+        }
+        return Ok(());
+    }
+
+    let Some(shell) = opts.shell else {
+        bail!("specify a shell: zsh, bash, or fish");
+    };
+    let bin = bin_name();
+    let mut out = Vec::new();
+    let clap_shell = match shell {
+        CompletionShell::Zsh => Shell::Zsh,
+        CompletionShell::Bash => Shell::Bash,
+        CompletionShell::Fish => Shell::Fish,
+    };
+    generate(clap_shell, &mut Cli::command(), &bin, &mut out);
+    let mut script = String::from_utf8(out).context("completion script is not valid UTF-8")?;
+    script.push_str(&dynamic_hook(shell, &bin));
+    print!("{script}");
+    Ok(())
+}
+
+/// Dynamic candidates for the given kind. Errors resolve to an empty list so completion never
+/// prints noise into the prompt.
+fn candidates(list: CompletionList, cwd: &Path) -> Vec<String> {
+    match list {
+        CompletionList::Tasks => local_config(cwd)
+            .map(|cfg| cfg.tasks.into_iter().map(|task| task.name).collect())
+            .unwrap_or_default(),
+        CompletionList::Projects => {
+            let mut names: Vec<String> = projects::list_projects()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|entry| entry.name)
+                .collect();
+            names.sort();
+            names.dedup();
+            names
+        }
+        CompletionList::DeployTargets => local_config(cwd)
+            .map(|cfg| deploy_targets(&cfg))
+            .unwrap_or_default(),
+    }
+}
+
+fn local_config(cwd: &Path) -> Option<Config> {
+    let path = project_snapshot::find_flow_toml_upwards(cwd)?;
+    config::load(&path).ok()
+}
+
+/// Deploy subcommands that the project flow.toml actually configures.
+fn deploy_targets(cfg: &Config) -> Vec<String> {
+    [
+        ("host", cfg.host.is_some()),
+        ("cloudflare", cfg.cloudflare.is_some()),
+        ("railway", cfg.railway.is_some()),
+        ("web", cfg.web.is_some()),
+    ]
+    .into_iter()
+    .filter(|(_, configured)| *configured)
+    .map(|(name, _)| name.to_string())
+    .collect()
+}
+
+/// Binary name the script is generated for (`f` or `flow`).
+fn bin_name() -> String {
+    std::env::args_os()
+        .next()
+        .and_then(|arg| {
+            Path::new(&arg)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .filter(|name| name == "flow")
+        .unwrap_or_else(|| "f".to_string())
+}
+
+fn dynamic_hook(shell: CompletionShell, bin: &str) -> String {
+    let template = match shell {
+        CompletionShell::Zsh => ZSH_HOOK,
+        CompletionShell::Bash => BASH_HOOK,
+        CompletionShell::Fish => FISH_HOOK,
+    };
+    template.replace("{bin}", bin)
+}
+
+const ZSH_HOOK: &str = r#"
+# flow: dynamic task, project, and deploy target completion
+_{bin}_flow_dynamic() {
+    local -a items
+    if [[ ${words[CURRENT-1]} == --project ]]; then
+        items=(${(f)"$({bin} completions --list projects 2>/dev/null)"})
+        _describe -t projects 'project' items
+        return
+    fi
+    if (( CURRENT == 3 )) && [[ ${words[2]} == run ]]; then
+        items=(${(f)"$({bin} completions --list tasks 2>/dev/null)"})
+        items=("${(@)items//:/\\:}")
+        _describe -t tasks 'task' items
+        return
+    fi
+    if (( CURRENT == 2 )); then
+        items=(${(f)"$({bin} completions --list tasks 2>/dev/null)"})
+        items=("${(@)items//:/\\:}")
+        _describe -t tasks 'task' items
+    fi
+    if (( CURRENT == 3 )) && [[ ${words[2]} == deploy ]]; then
+        items=(${(f)"$({bin} completions --list deploy-targets 2>/dev/null)"})
+        _describe -t deploy-targets 'configured deploy target' items
+    fi
+    _{bin} "$@"
+}
+compdef _{bin}_flow_dynamic {bin}
+"#;
+
+const BASH_HOOK: &str = r#"
+# flow: dynamic task, project, and deploy target completion
+_{bin}_flow_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local prev="${COMP_WORDS[COMP_CWORD-1]}"
+    if [[ "$prev" == "--project" ]]; then
+        COMPREPLY=($(compgen -W "$({bin} completions --list projects 2>/dev/null)" -- "$cur"))
+        return 0
+    fi
+    if [[ $COMP_CWORD -eq 2 && "${COMP_WORDS[1]}" == "run" ]]; then
+        COMPREPLY=($(compgen -W "$({bin} completions --list tasks 2>/dev/null)" -- "$cur"))
+        return 0
+    fi
+    _{bin} "$@"
+    if [[ $COMP_CWORD -eq 1 ]]; then
+        COMPREPLY+=($(compgen -W "$({bin} completions --list tasks 2>/dev/null)" -- "$cur"))
+    elif [[ $COMP_CWORD -eq 2 && "${COMP_WORDS[1]}" == "deploy" ]]; then
+        COMPREPLY=($(compgen -W "$({bin} completions --list deploy-targets 2>/dev/null)" -- "$cur") "${COMPREPLY[@]}")
+    fi
+}
+complete -F _{bin}_flow_dynamic -o nosort -o bashdefault -o default {bin}
+"#;
+
+const FISH_HOOK: &str = r#"
+# flow: dynamic task, project, and deploy target completion
+complete -c {bin} -n "__fish_use_subcommand" -f -a "({bin} completions --list tasks 2>/dev/null)" -d "task"
+complete -c {bin} -n "__fish_seen_subcommand_from run" -f -a "({bin} completions --list tasks 2>/dev/null)" -d "task"
+complete -c {bin} -n "__fish_seen_subcommand_from deploy" -f -a "({bin} completions --list deploy-targets 2>/dev/null)" -d "configured deploy target"
+complete -c {bin} -l project -x -a "({bin} completions --list projects 2>/dev/null)"
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deploy_targets_follow_configured_sections() {
+        let mut cfg = Config::default();
+        assert!(deploy_targets(&cfg).is_empty());
+        cfg.host = Some(Default::default());
+        cfg.web = Some(Default::default());
+        assert_eq!(deploy_targets(&cfg), vec!["host", "web"]);
+    }
+
+    #[test]
+    fn task_candidates_come_from_nearest_flow_toml() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(
+            dir.path().join("flow.toml"),
+            "version = 1\n\n[[tasks]]\nname = \"web:dev\"\ncommand = \"pnpm dev\"\n",
+        )
+        .expect("write flow.toml");
+        let nested = dir.path().join("apps/web");
+        std::fs::create_dir_all(&nested).expect("create nested dir");
+        assert_eq!(candidates(CompletionList::Tasks, &nested), vec!["web:dev"]);
+    }
+
+    #[test]
+    fn hooks_reference_binary_name() {
+        for shell in [
+            CompletionShell::Zsh,
+            CompletionShell::Bash,
+            CompletionShell::Fish,
+        ] {
+            let hook = dynamic_hook(shell, "flow");
+            assert!(hook.contains("flow completions --list tasks"));
+            assert!(!hook.contains("{bin}"));
+        }
+    }
+}
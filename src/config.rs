@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fs,
     path::{Path, PathBuf},
     process::Command,
@@ -40,6 +40,10 @@ pub struct Config {
         alias = "env-space-scope"
     )]
     pub env_space_kind: Option<String>,
+    /// Default dotenv files loaded for every task (e.g. `[".env", ".env.local"]`); tasks can
+    /// override this with their own `env_files`.
+    #[serde(default, alias = "env-files")]
+    pub env_files: Option<Vec<String>>,
     /// Flow-specific settings (primary_task, etc.)
     #[serde(default)]
     pub flow: FlowSettings,
@@ -88,6 +92,18 @@ pub struct Config {
     pub stream: Option<StreamConfig>,
     #[serde(default, rename = "server-hub")]
     pub server_hub: Option<ServerHubConfig>,
+    /// Concurrency limits for runs delegated to the hub (`[hub]`).
+    #[serde(default)]
+    pub hub: Option<HubConfig>,
+    /// Notification rules for task, deploy, and service events (`[[notify.rules]]`).
+    #[serde(default)]
+    pub notify: Option<NotifyConfig>,
+    /// Fix tasks offered when a failed task matches a known error signature (`[diagnose]`).
+    #[serde(default)]
+    pub diagnose: Option<DiagnoseConfig>,
+    /// Log store settings such as source-map resolution of JS stack traces (`[logs]`).
+    #[serde(default)]
+    pub logs: Option<LogsConfig>,
     /// Background daemons that flow can manage (start/stop/status).
     #[serde(default, alias = "daemon")]
     pub daemons: Vec<DaemonConfig>,
@@ -155,6 +171,28 @@ pub struct Config {
     /// Commit explanation config (AI-generated markdown summaries).
     #[serde(default, rename = "explain-commits", alias = "explain_commits")]
     pub explain_commits: Option<ExplainCommitsConfig>,
+    /// AI provider integration settings (prompt guard, etc.).
+    #[serde(default)]
+    pub ai: Option<AiConfig>,
+}
+
+/// AI provider integration settings.
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct AiConfig {
+    /// Secret scanning applied to prompts before they leave the machine.
+    #[serde(default)]
+    pub guard: Option<AiGuardConfig>,
+}
+
+/// Prompt guard settings (`[ai.guard]`).
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct AiGuardConfig {
+    /// What to do when a prompt contains secrets: "warn", "redact" (default), "block", or "off".
+    #[serde(default)]
+    pub mode: Option<String>,
+    /// Secret pattern names to ignore (e.g. "Generic Secret Assignment").
+    #[serde(default, alias = "allow-patterns")]
+    pub allow_patterns: Vec<String>,
 }
 
 /// Commit explanation config — AI-generated markdown summaries per commit.
@@ -345,6 +383,23 @@ pub struct CommitConfig {
         alias = "skillGate"
     )]
     pub skill_gate: Option<SkillGateConfig>,
+    /// Tasks that must pass before commit (e.g. the lockfile drift check `f setup` generates).
+    #[serde(default)]
+    pub verify: Option<CommitVerifyConfig>,
+    /// Staged-diff secret scan settings.
+    #[serde(
+        default,
+        rename = "secret_scan",
+        alias = "secret-scan",
+        alias = "secretScan"
+    )]
+    pub secret_scan: Option<SecretScanConfig>,
+    /// Commit message policy, enforced by `f commit` and the Flow commit-msg hook.
+    #[serde(default)]
+    pub message: Option<CommitMessageConfig>,
+    /// Commit signing policy, enforced by `f commit` and the Flow commit-msg hook.
+    #[serde(default)]
+    pub signing: Option<CommitSigningConfig>,
     /// Push gate for review todos: "warn" (default) | "block" | "off"
     #[serde(
         default,
@@ -424,6 +479,61 @@ pub struct SkillGateConfig {
     pub min_version: Option<HashMap<String, u32>>,
 }
 
+/// `[commit.verify]`: flow.toml tasks run before review; a failing task fails the gate.
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct CommitVerifyConfig {
+    /// Gate mode: "warn" (default) | "block" | "off"
+    #[serde(default)]
+    pub mode: Option<String>,
+    /// Task names, run in order.
+    #[serde(default)]
+    pub tasks: Vec<String>,
+}
+
+/// Secret scan configuration for `f commit`.
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct SecretScanConfig {
+    /// Glob patterns whose staged changes are never scanned (e.g. "**/fixtures/**", "*.snap").
+    #[serde(
+        default,
+        rename = "ignore_paths",
+        alias = "ignore-paths",
+        alias = "ignorePaths"
+    )]
+    pub ignore_paths: Vec<String>,
+    /// Check detected tokens against the provider's API to flag which are still live.
+    #[serde(default)]
+    pub verify: bool,
+}
+
+/// `[commit.message]`: rules every commit message must satisfy.
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct CommitMessageConfig {
+    /// Regex a commit message must match somewhere (e.g. "PROJ-\\d+").
+    /// Merge, revert, and fixup/squash messages are exempt.
+    #[serde(
+        default,
+        rename = "require_ticket",
+        alias = "require-ticket",
+        alias = "requireTicket"
+    )]
+    pub require_ticket: Option<String>,
+}
+
+/// `[commit.signing]`: require git to sign commits, optionally with a specific key.
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct CommitSigningConfig {
+    /// Refuse to commit unless git is configured to sign (`commit.gpgsign`) with a usable key.
+    #[serde(default)]
+    pub require: Option<bool>,
+    /// Signature format: "ssh" or "gpg".
+    #[serde(default)]
+    pub format: Option<String>,
+    /// Preferred key: an SSH public key path or literal, or a GPG key id/fingerprint.
+    #[serde(default)]
+    pub key: Option<String>,
+}
+
 /// Project invariants for AI-driven enforcement at commit time.
 ///
 /// Defines machine-parseable rules that flow checks against staged changes.
@@ -609,6 +719,17 @@ pub struct TsEnvConfig {
         alias = "global-keys"
     )]
     pub global_keys: Vec<String>,
+    /// Previous values kept per key for `f env rollback` (default 5).
+    #[serde(
+        default,
+        rename = "history_limit",
+        alias = "historyLimit",
+        alias = "history-limit"
+    )]
+    pub history_limit: Option<usize>,
+    /// Rotation policy: `f doctor` warns about keys unchanged for longer than this (e.g. "90d").
+    #[serde(default, rename = "max_age", alias = "maxAge", alias = "max-age")]
+    pub max_age: Option<String>,
 }
 
 /// Agents settings from TypeScript config.
@@ -737,6 +858,16 @@ pub struct TsReviewConfig {
     pub model: Option<String>,
 }
 
+impl Config {
+    /// Tool versions pinned in `[deps.versions]`.
+    pub fn pinned_dependency_versions(&self) -> BTreeMap<String, String> {
+        match self.dependencies.get("versions") {
+            Some(DependencySpec::Versions(versions)) => versions.clone(),
+            _ => BTreeMap::new(),
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
@@ -744,6 +875,7 @@ impl Default for Config {
             project_name: None,
             env_space: None,
             env_space_kind: None,
+            env_files: None,
             flow: FlowSettings::default(),
             lifecycle: None,
             codex: None,
@@ -764,6 +896,10 @@ impl Default for Config {
             watchers: Vec::new(),
             stream: None,
             server_hub: None,
+            hub: None,
+            notify: None,
+            diagnose: None,
+            logs: None,
             daemons: Vec::new(),
             push_policy: None,
             host: None,
@@ -783,6 +919,7 @@ impl Default for Config {
             proxy: None,
             proxies: Vec::new(),
             explain_commits: None,
+            ai: None,
         }
     }
 }
@@ -799,6 +936,9 @@ pub struct FlowSettings {
     /// Task to run when invoking `f deploy` with no subcommand.
     #[serde(default, rename = "deploy_task", alias = "deploy-task")]
     pub deploy_task: Option<String>,
+    /// Tasks the supervisor runs after the checked-out branch changes (e.g. `["setup"]`).
+    #[serde(default, rename = "on_branch_change", alias = "on-branch-change")]
+    pub on_branch_change: Vec<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
@@ -924,9 +1064,12 @@ pub struct SkillsConfig {
         alias = "syncTasks"
     )]
     pub sync_tasks: bool,
-    /// Skills to install from the registry when missing.
+    /// Skills to install when missing: registry names, `gh:org/repo/skill@ref`, or git URLs.
     #[serde(default)]
     pub install: Vec<String>,
+    /// Expected `sha256:<hex>` checksums for git-installed skills, keyed by skill name.
+    #[serde(default)]
+    pub checksums: BTreeMap<String, String>,
     /// Codex-specific skills behavior.
     #[serde(default)]
     pub codex: Option<SkillsCodexConfig>,
@@ -1240,6 +1383,16 @@ pub struct SetupConfig {
     /// Server setup defaults (used by f setup release).
     #[serde(default)]
     pub server: Option<SetupServerConfig>,
+    /// Installer used for missing `[deps]` (`[setup.installer]`).
+    #[serde(default)]
+    pub installer: Option<SetupInstallerConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct SetupInstallerConfig {
+    /// Preferred backend: "mise", "asdf", "apt", "brew", or "auto" (default).
+    #[serde(default)]
+    pub backend: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
@@ -1447,6 +1600,10 @@ pub struct ServerConfig {
     pub env: HashMap<String, String>,
     /// Whether this server should be started automatically with the daemon.
     pub autostart: bool,
+    /// Globs whose changes make the supervisor restart this server.
+    pub restart_on: Vec<String>,
+    /// Minimum time between file-triggered restarts.
+    pub restart_cooldown: Option<String>,
 }
 
 impl ServerConfig {
@@ -1473,6 +1630,8 @@ impl ServerConfig {
             retry: Some(3),
             ready_delay: None,
             ready_output: None,
+            restart_on: self.restart_on.clone(),
+            restart_cooldown: self.restart_cooldown.clone(),
         }
     }
 }
@@ -1497,6 +1656,10 @@ impl<'de> Deserialize<'de> for ServerConfig {
             env: HashMap<String, String>,
             #[serde(default = "default_autostart")]
             autostart: bool,
+            #[serde(default, alias = "restart-on")]
+            restart_on: Vec<String>,
+            #[serde(default, alias = "restart-cooldown")]
+            restart_cooldown: Option<String>,
         }
 
         let raw = RawServerConfig::deserialize(deserializer)?;
@@ -1540,6 +1703,8 @@ impl<'de> Deserialize<'de> for ServerConfig {
             working_dir: raw.working_dir.map(|dir| expand_path(&dir)),
             env: raw.env,
             autostart: raw.autostart,
+            restart_on: raw.restart_on,
+            restart_cooldown: raw.restart_cooldown,
         })
     }
 }
@@ -1581,12 +1746,115 @@ pub struct TaskConfig {
     /// Require confirmation when matched via LM Studio (for destructive tasks).
     #[serde(default, alias = "confirm-on-match")]
     pub confirm_on_match: bool,
+    /// Question asked before every run (e.g. "This wipes the local DB. Continue?"); anything
+    /// but "y" cancels. Runs without a TTY go ahead unless the task is also `protected`.
+    #[serde(default)]
+    pub confirm: Option<String>,
+    /// Never run without an explicit yes: asked on a TTY, refused without one unless `--yes`.
+    #[serde(default)]
+    pub protected: bool,
     /// Command to run when the task is cancelled (Ctrl+C).
     #[serde(default, alias = "on-cancel")]
     pub on_cancel: Option<String>,
     /// Optional file path to save combined task output (relative to project root unless absolute).
     #[serde(default, alias = "output-file")]
     pub output_file: Option<String>,
+    /// Run the command on a remote host over SSH ("user@host[:port]", or "deploy" for the
+    /// deploy host configured via `f deploy setup`).
+    #[serde(default)]
+    pub host: Option<String>,
+    /// Remote working directory (defaults to `[host].dest` when targeting the deploy host).
+    #[serde(default, alias = "remote-dir")]
+    pub remote_dir: Option<String>,
+    /// Local environment variable names forwarded to the remote command.
+    #[serde(default, alias = "remote-env")]
+    pub remote_env: Vec<String>,
+    /// Globs (relative to the project root) whose contents key the task cache. When set, a
+    /// run whose inputs are unchanged since the last successful run is skipped.
+    #[serde(default)]
+    pub inputs: Vec<String>,
+    /// Files or directories produced by the task; cached and restored on a cache hit.
+    #[serde(default)]
+    pub outputs: Vec<String>,
+    /// Port the task serves on. While it runs, a local `f proxy` routes
+    /// `<task>.<project>.localhost` to it.
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Expand into one run per combination of values; `{{matrix.<key>}}` in the command is
+    /// replaced with the run's value (e.g. `matrix.node = ["18", "20", "22"]`).
+    #[serde(default, deserialize_with = "deserialize_matrix")]
+    pub matrix: BTreeMap<String, Vec<String>>,
+    /// Run matrix combinations concurrently instead of one after another.
+    #[serde(default, alias = "matrix-parallel")]
+    pub matrix_parallel: bool,
+    /// Dotenv files (relative to the flow.toml directory) loaded into the task's environment;
+    /// later files override earlier ones and the process environment overrides both. Falls
+    /// back to the project-wide `env_files` when unset.
+    #[serde(default, alias = "env-files")]
+    pub env_files: Option<Vec<String>>,
+    /// Re-run the command up to this many times after a failed attempt.
+    #[serde(default)]
+    pub retries: u32,
+    /// Delay before each retry (e.g. "5s", "500ms"); defaults to 5s.
+    #[serde(default, alias = "retry-backoff")]
+    pub retry_backoff: Option<String>,
+    /// Double the delay after every failed retry.
+    #[serde(default, alias = "retry-exponential")]
+    pub retry_exponential: bool,
+    /// Values asked for before the run; `{{name}}` in the command is replaced with the answer.
+    #[serde(default, alias = "prompts")]
+    pub prompt: Vec<TaskPromptConfig>,
+    /// Directory the command runs in, relative to the flow.toml directory unless absolute.
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// File mode creation mask for the command, in octal (e.g. "022").
+    #[serde(default)]
+    pub umask: Option<String>,
+    /// Scheduling niceness for the command (-20..=19; higher yields more CPU to other work).
+    #[serde(default)]
+    pub nice: Option<i32>,
+    /// Address-space limit for the command and its children (e.g. "2GB"; Unix only).
+    #[serde(default, alias = "max-memory")]
+    pub max_memory: Option<String>,
+    /// CPU cores a hub-delegated run reserves while it runs (default 1).
+    #[serde(default)]
+    pub cpu: Option<u32>,
+    /// Memory a hub-delegated run reserves while it runs (e.g. "8GB").
+    #[serde(default)]
+    pub memory: Option<String>,
+    /// After a local run on a clean tree, attach a pass/fail summary to HEAD as a git note
+    /// under `refs/notes/flow`.
+    #[serde(default, alias = "git-note")]
+    pub git_note: bool,
+    /// Extract test results from the output: "cargo-test", "jest", "junit-xml" (or
+    /// "junit-xml:<report path>"), or a regex with a `name` group.
+    #[serde(default)]
+    pub parser: Option<String>,
+    /// Tasks run right before this one on every run, unlike task `dependencies` (which are
+    /// prerequisites and may be served from the task cache). Skipped when this task is cached.
+    #[serde(default)]
+    pub before: Vec<String>,
+    /// Tasks run after this one whether it succeeded or failed; a failing `after` task fails
+    /// the run.
+    #[serde(default)]
+    pub after: Vec<String>,
+    /// Tasks run when this task (or one of its `before` tasks) fails, before `after` tasks.
+    /// Their own failures are only reported.
+    #[serde(default, alias = "on-failure")]
+    pub on_failure: Vec<String>,
+}
+
+/// `prompt = [{ name = "migration_name", message = "Migration name?" }]`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TaskPromptConfig {
+    /// Placeholder name used as `{{name}}` in the command and as `name=value` on the CLI.
+    pub name: String,
+    /// Question shown on a TTY (defaults to the name).
+    #[serde(default)]
+    pub message: Option<String>,
+    /// Answer used when nothing is entered, and for runs without a TTY.
+    #[serde(default)]
+    pub default: Option<String>,
 }
 
 /// Definition of a dependency that can be referenced by automation tasks.
@@ -1599,6 +1867,21 @@ pub enum DependencySpec {
     Multiple(Vec<String>),
     /// Flox package descriptor that should be added to the local env manifest.
     Flox(FloxInstallSpec),
+    /// Command with a minimum version (`node = { bin = "node", min = "20.0" }`).
+    Tool(ToolDependency),
+    /// `[deps.versions]`: tool versions pinned for `f setup` installers (e.g. `node = "22"`).
+    Versions(BTreeMap<String, String>),
+}
+
+/// `[deps]` entry that requires a minimum version of a command.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ToolDependency {
+    /// Binary that must be on PATH.
+    pub bin: String,
+    /// Minimum version, compared against `<bin> --version` output.
+    #[serde(default)]
+    pub min: Option<String>,
 }
 
 fn deserialize_shortcuts<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
@@ -1621,6 +1904,40 @@ where
     Ok(shortcuts)
 }
 
+/// Matrix values may be strings, numbers, or booleans (`matrix.node = [18, 20]`).
+fn deserialize_matrix<'de, D>(
+    deserializer: D,
+) -> std::result::Result<BTreeMap<String, Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum MatrixValue {
+        Str(String),
+        Int(i64),
+        Float(f64),
+        Bool(bool),
+    }
+
+    let raw = BTreeMap::<String, Vec<MatrixValue>>::deserialize(deserializer)?;
+    Ok(raw
+        .into_iter()
+        .map(|(key, values)| {
+            let values = values
+                .into_iter()
+                .map(|value| match value {
+                    MatrixValue::Str(s) => s,
+                    MatrixValue::Int(i) => i.to_string(),
+                    MatrixValue::Float(f) => f.to_string(),
+                    MatrixValue::Bool(b) => b.to_string(),
+                })
+                .collect();
+            (key, values)
+        })
+        .collect())
+}
+
 /// Storage configuration describing remote environments providers.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct StorageConfig {
@@ -1703,6 +2020,109 @@ pub struct RemoteServerConfig {
     pub sync_paths: Vec<PathBuf>,
 }
 
+/// Queueing limits for tasks delegated to the hub daemon.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct HubConfig {
+    /// Maximum delegated runs in flight across all projects (default 2).
+    #[serde(default, alias = "max-concurrent")]
+    pub max_concurrent: Option<usize>,
+    /// Maximum delegated runs in flight per project (default 1).
+    #[serde(default, alias = "max-per-project")]
+    pub max_per_project: Option<usize>,
+    /// CPU cores delegated runs may reserve together (default: all cores).
+    #[serde(default)]
+    pub cpu: Option<u32>,
+    /// Memory delegated runs may reserve together, e.g. "6GB" (default: total RAM).
+    #[serde(default)]
+    pub memory: Option<String>,
+}
+
+/// `[notify]`: where to send alerts when tasks, deploys, or services change state.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct NotifyConfig {
+    #[serde(default, alias = "rule")]
+    pub rules: Vec<NotifyRule>,
+}
+
+/// Event a notification rule listens for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyEvent {
+    TaskFailure,
+    TaskSuccess,
+    DeploySuccess,
+    DeployFailure,
+    ServiceDown,
+    ServiceRecovered,
+}
+
+impl NotifyEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NotifyEvent::TaskFailure => "task_failure",
+            NotifyEvent::TaskSuccess => "task_success",
+            NotifyEvent::DeploySuccess => "deploy_success",
+            NotifyEvent::DeployFailure => "deploy_failure",
+            NotifyEvent::ServiceDown => "service_down",
+            NotifyEvent::ServiceRecovered => "service_recovered",
+        }
+    }
+}
+
+/// One `[[notify.rules]]` entry; every configured channel receives the alert.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NotifyRule {
+    /// Event that triggers the rule.
+    pub on: NotifyEvent,
+    /// Only fire for these task/service names (all when empty).
+    #[serde(default, alias = "task", alias = "services")]
+    pub tasks: Vec<String>,
+    /// Post to macOS Notification Center.
+    #[serde(default)]
+    pub macos: bool,
+    /// Slack incoming webhook URL, or `$ENV_VAR` holding it.
+    #[serde(default, alias = "slack-webhook", alias = "slack")]
+    pub slack_webhook: Option<String>,
+    /// Shell command to run; receives `FLOW_NOTIFY_*` environment variables.
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+/// `[diagnose]`: tasks that fix the failures `f diagnose` recognizes.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct DiagnoseConfig {
+    /// Signature name (e.g. `module_not_found`) to the task that fixes it.
+    #[serde(default)]
+    pub fixes: HashMap<String, String>,
+}
+
+/// `[logs]`: how the log store treats ingested entries.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LogsConfig {
+    /// Resolve minified JS stack traces through source maps in the build output.
+    #[serde(default)]
+    pub source_maps: bool,
+    /// Directories (relative to the project root) searched for `*.map` files.
+    #[serde(default = "default_source_map_dirs")]
+    pub source_map_dirs: Vec<String>,
+}
+
+impl Default for LogsConfig {
+    fn default() -> Self {
+        Self {
+            source_maps: false,
+            source_map_dirs: default_source_map_dirs(),
+        }
+    }
+}
+
+fn default_source_map_dirs() -> Vec<String> {
+    ["dist", "build", ".next", "out", ".output", "public"]
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ServerHubConfig {
@@ -1898,6 +2318,13 @@ pub struct DaemonConfig {
     /// Description of what this daemon does.
     #[serde(default)]
     pub description: Option<String>,
+    /// Globs (relative to `working_dir`, else the flow.toml directory) whose changes make the
+    /// supervisor restart this daemon.
+    #[serde(default, alias = "restart-on")]
+    pub restart_on: Vec<String>,
+    /// Minimum time between file-triggered restarts (e.g. "10s"); defaults to 5s.
+    #[serde(default, alias = "restart-cooldown")]
+    pub restart_cooldown: Option<String>,
 }
 
 impl DaemonConfig {
@@ -1936,7 +2363,16 @@ impl DependencySpec {
         match self {
             DependencySpec::Single(cmd) => buffer.push(cmd.clone()),
             DependencySpec::Multiple(cmds) => buffer.extend(cmds.iter().cloned()),
-            DependencySpec::Flox(_) => {}
+            DependencySpec::Tool(tool) => buffer.push(tool.bin.clone()),
+            DependencySpec::Flox(_) | DependencySpec::Versions(_) => {}
+        }
+    }
+
+    /// Minimum version required for this dependency's command, if any.
+    pub fn min_version(&self) -> Option<&str> {
+        match self {
+            DependencySpec::Tool(tool) => tool.min.as_deref(),
+            _ => None,
         }
     }
 }
@@ -2184,6 +2620,21 @@ pub fn preferred_env_backend() -> Option<String> {
     Some(trimmed.to_ascii_lowercase())
 }
 
+/// Previous env values kept per key, from `flow.env.historyLimit` in config.ts.
+pub fn env_history_limit() -> Option<usize> {
+    load_ts_config()?.flow?.env?.history_limit
+}
+
+/// Env key rotation max age (e.g. "90d"), from `flow.env.maxAge` in config.ts.
+pub fn env_max_age() -> Option<String> {
+    let max_age = load_ts_config()?.flow?.env?.max_age?;
+    let trimmed = max_age.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    Some(trimmed.to_string())
+}
+
 /// Env vars to inject into every task from the personal env store.
 /// Defaults to AI server connection vars unless overridden in config.ts.
 pub fn global_env_keys() -> Vec<String> {
@@ -2545,6 +2996,9 @@ fn merge_config(base: &mut Config, other: Config) {
     if base.flow.deploy_task.is_none() {
         base.flow.deploy_task = other.flow.deploy_task;
     }
+    if base.flow.on_branch_change.is_empty() {
+        base.flow.on_branch_change = other.flow.on_branch_change;
+    }
     if base.codex.is_none() {
         base.codex = other.codex;
     } else if let (Some(base_codex), Some(other_codex)) = (base.codex.as_mut(), other.codex) {
@@ -2566,6 +3020,9 @@ fn merge_config(base: &mut Config, other: Config) {
                 base_server.host = other_server.host;
             }
         }
+        if base_setup.installer.is_none() {
+            base_setup.installer = other_setup.installer;
+        }
     }
     if base.task_resolution.is_none() {
         base.task_resolution = other.task_resolution;
@@ -2588,6 +3045,9 @@ fn merge_config(base: &mut Config, other: Config) {
     if base.everruns.is_none() {
         base.everruns = other.everruns;
     }
+    if base.ai.is_none() {
+        base.ai = other.ai;
+    }
     base.options.merge(other.options);
     base.servers.extend(other.servers);
     base.remote_servers.extend(other.remote_servers);
@@ -2598,6 +3058,23 @@ fn merge_config(base: &mut Config, other: Config) {
     base.invariants = base.invariants.take().or(other.invariants);
     base.storage = base.storage.take().or(other.storage);
     base.server_hub = base.server_hub.take().or(other.server_hub);
+    base.hub = base.hub.take().or(other.hub);
+    base.env_files = base.env_files.take().or(other.env_files);
+    match (&mut base.notify, other.notify) {
+        (Some(base_notify), Some(other_notify)) => base_notify.rules.extend(other_notify.rules),
+        (None, Some(other_notify)) => base.notify = Some(other_notify),
+        _ => {}
+    }
+    match (&mut base.diagnose, other.diagnose) {
+        (Some(base_diagnose), Some(other_diagnose)) => {
+            for (signature, task) in other_diagnose.fixes {
+                base_diagnose.fixes.entry(signature).or_insert(task);
+            }
+        }
+        (None, Some(other_diagnose)) => base.diagnose = Some(other_diagnose),
+        _ => {}
+    }
+    base.logs = base.logs.take().or(other.logs);
     for (key, value) in other.aliases {
         base.aliases.entry(key).or_insert(value);
     }
@@ -3244,6 +3721,61 @@ dependencies = ["fast", "toolkit"]
         }
     }
 
+    #[test]
+    fn parses_tool_dependency_with_min_version() {
+        let contents = r#"
+[deps]
+node = { bin = "node", min = "20.0" }
+versions = { go = "1.22" }
+"#;
+
+        let cfg: Config = toml::from_str(contents).expect("tool dependency should parse");
+        let node = cfg.dependencies.get("node").expect("node dep");
+        assert_eq!(node.min_version(), Some("20.0"));
+        let mut commands = Vec::new();
+        node.extend_commands(&mut commands);
+        assert_eq!(commands, vec!["node".to_string()]);
+        assert_eq!(
+            cfg.pinned_dependency_versions()
+                .get("go")
+                .map(String::as_str),
+            Some("1.22")
+        );
+    }
+
+    #[test]
+    fn parses_pinned_dependency_versions() {
+        let contents = r#"
+[deps]
+node = "node"
+
+[deps.versions]
+node = "22"
+pnpm = "9"
+
+[setup.installer]
+backend = "mise"
+"#;
+
+        let cfg: Config = toml::from_str(contents).expect("config with versions should parse");
+        let versions = cfg.pinned_dependency_versions();
+        assert_eq!(versions.get("node").map(String::as_str), Some("22"));
+        assert_eq!(versions.get("pnpm").map(String::as_str), Some("9"));
+
+        let mut commands = Vec::new();
+        for spec in cfg.dependencies.values() {
+            spec.extend_commands(&mut commands);
+        }
+        assert_eq!(commands, vec!["node".to_string()]);
+        assert_eq!(
+            cfg.setup
+                .and_then(|setup| setup.installer)
+                .and_then(|installer| installer.backend)
+                .as_deref(),
+            Some("mise")
+        );
+    }
+
     #[test]
     fn parses_flox_dependencies_and_config() {
         let contents = r#"
@@ -3469,6 +4001,22 @@ quick-default = true
         assert_eq!(commit.quick_default, Some(true));
     }
 
+    #[test]
+    fn commit_secret_scan_config_parses() {
+        let toml = r#"
+[commit.secret_scan]
+ignore_paths = ["**/fixtures/**", "*.snap"]
+verify = true
+"#;
+        let cfg: Config = toml::from_str(toml).expect("commit.secret_scan should parse");
+        let scan = cfg
+            .commit
+            .and_then(|commit| commit.secret_scan)
+            .expect("secret scan config expected");
+        assert_eq!(scan.ignore_paths, vec!["**/fixtures/**", "*.snap"]);
+        assert!(scan.verify);
+    }
+
     #[test]
     fn commit_skill_gate_config_parses() {
         let toml = r#"
@@ -0,0 +1,424 @@
+//! Format-preserving flow.toml edits built on `toml_edit`.
+//!
+//! Keys are dotted paths (`host.port`, `tasks.dev.command`). A segment that lands on an array
+//! of tables (`[[tasks]]`) selects the entry whose `name` matches, or an index (`tasks.0`);
+//! plain arrays take an index. Quote segments that contain dots: `env."api.url"`. Comments,
+//! ordering, and inline tables are left as they were.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow, bail};
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Table, TableLike, Value};
+
+use crate::cli::{ConfigGetOpts, ConfigSetOpts};
+use crate::config::Config;
+
+/// Read and parse a TOML file for editing.
+pub fn load(path: &Path) -> Result<DocumentMut> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    parse(&contents).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// Parse TOML text for editing.
+pub fn parse(contents: &str) -> Result<DocumentMut> {
+    Ok(contents.parse::<DocumentMut>()?)
+}
+
+/// Write an edited document back to disk.
+pub fn save(path: &Path, doc: &DocumentMut) -> Result<()> {
+    fs::write(path, doc.to_string()).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Split a dotted key path, honoring double-quoted segments.
+fn parse_path(path: &str) -> Result<Vec<String>> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut was_quoted = false;
+    for ch in path.trim().chars() {
+        match ch {
+            '"' => {
+                quoted = !quoted;
+                was_quoted = true;
+            }
+            '.' if !quoted => {
+                if current.is_empty() && !was_quoted {
+                    bail!("invalid key path '{}': empty segment", path);
+                }
+                segments.push(std::mem::take(&mut current));
+                was_quoted = false;
+            }
+            _ => current.push(ch),
+        }
+    }
+    if quoted {
+        bail!("invalid key path '{}': unterminated quote", path);
+    }
+    if current.is_empty() && !was_quoted {
+        bail!("invalid key path '{}': empty segment", path);
+    }
+    segments.push(current);
+    Ok(segments)
+}
+
+fn find_entry(tables: &ArrayOfTables, selector: &str) -> Option<usize> {
+    tables
+        .iter()
+        .position(|table| table.get("name").and_then(Item::as_str) == Some(selector))
+        .or_else(|| {
+            selector
+                .parse::<usize>()
+                .ok()
+                .filter(|idx| *idx < tables.len())
+        })
+}
+
+/// Walk `segments` from `root` to a table (or inline table). With `create`, missing keys are
+/// added as implicit tables; array entries are never created.
+fn table_at<'a>(
+    root: &'a mut dyn TableLike,
+    segments: &[String],
+    create: bool,
+) -> Result<Option<&'a mut dyn TableLike>> {
+    let mut current = root;
+    let mut idx = 0;
+    while idx < segments.len() {
+        let key = segments[idx].as_str();
+        if !current.contains_key(key) {
+            if !create {
+                return Ok(None);
+            }
+            let mut table = Table::new();
+            table.set_implicit(true);
+            current.insert(key, Item::Table(table));
+        }
+        let here = segments[..=idx].join(".");
+        let item = current.get_mut(key).expect("key present");
+        current = match item {
+            Item::ArrayOfTables(tables) => {
+                idx += 1;
+                let Some(selector) = segments.get(idx) else {
+                    bail!("'{here}' is an array of tables; select an entry by name or index");
+                };
+                let Some(entry) = find_entry(tables, selector) else {
+                    if create {
+                        bail!("no '{here}' entry named '{selector}'");
+                    }
+                    return Ok(None);
+                };
+                tables.get_mut(entry).expect("entry index in range") as &mut dyn TableLike
+            }
+            Item::Value(Value::Array(array)) => {
+                idx += 1;
+                let selector = segments
+                    .get(idx)
+                    .and_then(|segment| segment.parse::<usize>().ok());
+                match selector.and_then(|i| array.get_mut(i)) {
+                    Some(Value::InlineTable(table)) => table as &mut dyn TableLike,
+                    Some(_) => bail!("'{here}' entries are not tables"),
+                    None if create => bail!("'{here}' needs a valid index"),
+                    None => return Ok(None),
+                }
+            }
+            other => other
+                .as_table_like_mut()
+                .ok_or_else(|| anyhow!("'{here}' is not a table"))?,
+        };
+        idx += 1;
+    }
+    Ok(Some(current))
+}
+
+/// Look up a value, table, or array entry by key path.
+pub fn get(doc: &DocumentMut, path: &str) -> Result<Option<Item>> {
+    let mut current = doc.as_item().clone();
+    for segment in parse_path(path)? {
+        let next = match &current {
+            Item::ArrayOfTables(tables) => find_entry(tables, &segment)
+                .and_then(|idx| tables.get(idx))
+                .map(|table| Item::Table(table.clone())),
+            Item::Value(Value::Array(array)) => segment
+                .parse::<usize>()
+                .ok()
+                .and_then(|idx| array.get(idx))
+                .map(|value| Item::Value(value.clone())),
+            item => item
+                .as_table_like()
+                .and_then(|table| table.get(&segment))
+                .cloned(),
+        };
+        match next {
+            Some(item) => current = item,
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(current))
+}
+
+/// Set a value, creating intermediate tables. An existing value keeps its trailing comment.
+pub fn set(doc: &mut DocumentMut, path: &str, value: Value) -> Result<()> {
+    let segments = parse_path(path)?;
+    let (key, parents) = segments.split_last().expect("path has a segment");
+    let table = table_at(doc.as_table_mut(), parents, true)?.expect("created");
+    insert_value(table, key, value, path)
+}
+
+/// Insert into `table`, carrying over the decor (and so the trailing comment) of a value
+/// being replaced.
+fn insert_value(table: &mut dyn TableLike, key: &str, mut value: Value, path: &str) -> Result<()> {
+    match table.get(key) {
+        Some(Item::Value(old)) => *value.decor_mut() = old.decor().clone(),
+        Some(Item::None) | None => {}
+        Some(_) => bail!("'{}' is a table; set one of its keys instead", path),
+    }
+    table.insert(key, Item::Value(value));
+    Ok(())
+}
+
+/// Set a value only when the key is missing. Returns whether the document changed.
+pub fn set_if_missing(doc: &mut DocumentMut, path: &str, value: Value) -> Result<bool> {
+    if get(doc, path)?.is_some() {
+        return Ok(false);
+    }
+    set(doc, path, value)?;
+    Ok(true)
+}
+
+/// Make sure a table exists at `path` (rendered with its own header).
+pub fn ensure_table(doc: &mut DocumentMut, path: &str) -> Result<()> {
+    let segments = parse_path(path)?;
+    let (key, parents) = segments.split_last().expect("path has a segment");
+    let parent = table_at(doc.as_table_mut(), parents, true)?.expect("created");
+    match parent.get_mut(key) {
+        None => {
+            parent.insert(key, Item::Table(Table::new()));
+        }
+        Some(Item::Table(table)) => table.set_implicit(false),
+        Some(item) if item.is_table_like() => {}
+        Some(_) => bail!("'{}' is not a table", path),
+    }
+    Ok(())
+}
+
+/// Write several keys of the table at `path` in one go, creating it (with its own header) when
+/// missing. `None` removes the key; keys not listed are left alone, as are comments and an
+/// inline-table spelling of the table.
+pub fn upsert_table(
+    doc: &mut DocumentMut,
+    path: &str,
+    entries: Vec<(&str, Option<Value>)>,
+) -> Result<()> {
+    let segments = parse_path(path)?;
+    if table_at(doc.as_table_mut(), &segments, false)?.is_none() {
+        ensure_table(doc, path)?;
+    }
+    let table = table_at(doc.as_table_mut(), &segments, false)?.expect("ensured");
+    for (key, value) in entries {
+        match value {
+            Some(value) => insert_value(table, key, value, &format!("{path}.{key}"))?,
+            None => {
+                table.remove(key);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Remove a key. Returns the removed item, if any.
+pub fn remove(doc: &mut DocumentMut, path: &str) -> Result<Option<Item>> {
+    let segments = parse_path(path)?;
+    let (key, parents) = segments.split_last().expect("path has a segment");
+    Ok(table_at(doc.as_table_mut(), parents, false)?.and_then(|table| table.remove(key)))
+}
+
+/// Parse a CLI value as TOML (`8080`, `true`, `["a", "b"]`, `"quoted"`), falling back to a
+/// plain string.
+pub fn parse_value(raw: &str) -> Value {
+    let trimmed = raw.trim();
+    match trimmed.parse::<Value>() {
+        Ok(mut value) => {
+            value.decor_mut().clear();
+            value
+        }
+        Err(_) => Value::from(raw),
+    }
+}
+
+/// Render an item for `f config get`: strings print raw, other values as TOML, and tables as
+/// a TOML snippet under their key.
+pub fn render(key: &str, item: &Item) -> String {
+    match item {
+        Item::Value(Value::String(value)) => value.value().clone(),
+        Item::Value(value) => {
+            let mut value = value.clone();
+            value.decor_mut().clear();
+            value.to_string()
+        }
+        other => {
+            let mut doc = DocumentMut::new();
+            doc.insert(key, other.clone());
+            doc.to_string().trim().to_string()
+        }
+    }
+}
+
+pub fn run_get(opts: ConfigGetOpts) -> Result<()> {
+    let doc = load(&opts.config)?;
+    let Some(item) = get(&doc, &opts.key)? else {
+        bail!("'{}' is not set in {}", opts.key, opts.config.display());
+    };
+    let leaf = parse_path(&opts.key)?.pop().unwrap_or_default();
+    println!("{}", render(&leaf, &item));
+    Ok(())
+}
+
+pub fn run_set(opts: ConfigSetOpts) -> Result<()> {
+    let mut doc = load(&opts.config)?;
+    let value = if opts.string {
+        Value::from(opts.value.as_str())
+    } else {
+        parse_value(&opts.value)
+    };
+    set(&mut doc, &opts.key, value)?;
+
+    let updated = doc.to_string();
+    toml::from_str::<Config>(&updated).with_context(|| {
+        format!(
+            "refusing to write {}: setting '{}' makes it invalid",
+            opts.config.display(),
+            opts.key
+        )
+    })?;
+    fs::write(&opts.config, updated)
+        .with_context(|| format!("failed to write {}", opts.config.display()))?;
+    let item = get(&doc, &opts.key)?.unwrap_or(Item::None);
+    println!("{} = {}", opts.key, render(&opts.key, &item));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"# project config
+name = "demo"
+
+[host]
+dest = "/opt/demo" # deploy target
+port = 3000
+
+[[tasks]]
+name = "dev"
+command = "bun dev" # local server
+
+[[tasks]]
+name = "test"
+command = "bun test"
+"#;
+
+    #[test]
+    fn get_resolves_tables_and_task_entries() {
+        let doc: DocumentMut = SAMPLE.parse().unwrap();
+        let port = get(&doc, "host.port").unwrap().unwrap();
+        assert_eq!(render("port", &port), "3000");
+        let command = get(&doc, "tasks.dev.command").unwrap().unwrap();
+        assert_eq!(render("command", &command), "bun dev");
+        let by_index = get(&doc, "tasks.1.name").unwrap().unwrap();
+        assert_eq!(render("name", &by_index), "test");
+        assert!(get(&doc, "tasks.build.command").unwrap().is_none());
+    }
+
+    #[test]
+    fn set_preserves_comments_and_creates_tables() {
+        let mut doc: DocumentMut = SAMPLE.parse().unwrap();
+        set(&mut doc, "host.port", parse_value("8080")).unwrap();
+        set(&mut doc, "tasks.dev.command", parse_value("bun run dev")).unwrap();
+        set(&mut doc, "web.path", parse_value("web")).unwrap();
+        let out = doc.to_string();
+        assert!(out.starts_with("# project config\n"));
+        assert!(out.contains("dest = \"/opt/demo\" # deploy target\nport = 8080\n"));
+        assert!(out.contains("command = \"bun run dev\" # local server\n"));
+        assert!(out.contains("[web]\npath = \"web\"\n"));
+        assert!(set(&mut doc, "tasks.build.command", parse_value("x")).is_err());
+    }
+
+    #[test]
+    fn parse_value_and_quoted_paths() {
+        assert_eq!(parse_value("true").as_bool(), Some(true));
+        assert_eq!(parse_value("[1, 2]").as_array().map(|a| a.len()), Some(2));
+        assert_eq!(parse_value("bun dev").as_str(), Some("bun dev"));
+        assert_eq!(
+            parse_path(r#"env."api.url".value"#).unwrap(),
+            vec!["env", "api.url", "value"]
+        );
+        assert!(parse_path("host..port").is_err());
+    }
+
+    #[test]
+    fn upsert_table_edits_inline_tables_in_place() {
+        let mut doc =
+            parse("name = \"demo\"\nhost = { dest = \"/opt/demo\", sudo = true }\n").unwrap();
+        upsert_table(
+            &mut doc,
+            "host",
+            vec![
+                ("dest", Some("/srv/demo".into())),
+                ("port", Some(8080i64.into())),
+            ],
+        )
+        .unwrap();
+        let out = doc.to_string();
+        assert!(!out.contains("[host]"), "{out}");
+        assert!(out.contains("dest = \"/srv/demo\""));
+        assert!(out.contains("sudo = true"));
+        assert!(out.contains("port = 8080"));
+        assert!(toml::from_str::<toml::Table>(&out).is_ok());
+    }
+
+    #[test]
+    fn upsert_table_keeps_comments_and_nested_tables() {
+        let input = r#"[host] # prod box
+dest = "/opt/demo" # deploy target
+ssl = true
+
+[host.env]
+RUST_LOG = "info"
+
+[[tasks]]
+name = "dev"
+command = "bun dev"
+"#;
+        let mut doc = parse(input).unwrap();
+        upsert_table(
+            &mut doc,
+            "host",
+            vec![("dest", Some("/srv/demo".into())), ("ssl", None)],
+        )
+        .unwrap();
+        upsert_table(
+            &mut doc,
+            "tasks.dev",
+            vec![("command", Some("bun run dev".into()))],
+        )
+        .unwrap();
+        let out = doc.to_string();
+        assert!(out.starts_with("[host] # prod box\ndest = \"/srv/demo\" # deploy target\n"));
+        assert!(!out.contains("ssl"));
+        assert!(out.contains("[host.env]\nRUST_LOG = \"info\"\n"));
+        assert!(out.contains("[[tasks]]\nname = \"dev\"\ncommand = \"bun run dev\"\n"));
+    }
+
+    #[test]
+    fn upsert_table_appends_missing_table_with_header() {
+        let mut doc = parse(SAMPLE).unwrap();
+        upsert_table(&mut doc, "web", vec![("path", Some("web".into()))]).unwrap();
+        upsert_table(&mut doc, "a.b.c", vec![("x", Some(1i64.into()))]).unwrap();
+        let out = doc.to_string();
+        assert!(out.starts_with(SAMPLE), "{out}");
+        assert!(out.contains("[web]\npath = \"web\"\n"));
+        assert!(out.contains("[a.b.c]\nx = 1\n"));
+        assert_eq!(render("x", &get(&doc, "a.b.c.x").unwrap().unwrap()), "1");
+    }
+}
@@ -0,0 +1,402 @@
+//! `${env:VAR}` and `${envstore:KEY}` references in flow.toml values.
+//!
+//! Config is loaded with references left as written; they are resolved only when a task or
+//! deploy runs, so resolved values never end up in cached config, task history, or logs.
+//! `$${env:VAR}` is a literal `${env:VAR}`.
+//!
+//! Task commands are not rewritten with the values themselves: each reference becomes a shell
+//! expansion of a variable that is exported to the task's process (`${envstore:API_TOKEN}` →
+//! `${API_TOKEN}`), so secrets stay out of argv and the printed command.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result, bail};
+use regex::{Captures, Regex};
+use toml_edit::{Item, Value};
+
+use crate::cli::ConfigResolveOpts;
+use crate::config_edit;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Source {
+    /// The environment flow was started with.
+    Env,
+    /// The personal env store (`f env set`).
+    EnvStore,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Reference {
+    pub source: Source,
+    pub key: String,
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let source = match self.source {
+            Source::Env => "env",
+            Source::EnvStore => "envstore",
+        };
+        write!(f, "${{{source}:{}}}", self.key)
+    }
+}
+
+/// References resolved against the environment and the env store.
+#[derive(Debug, Default, Clone)]
+pub struct Resolved {
+    values: HashMap<Reference, String>,
+    missing: BTreeSet<Reference>,
+}
+
+impl Resolved {
+    /// Look every reference up, reading the env store once for all store keys.
+    pub fn lookup(references: &BTreeSet<Reference>) -> Result<Self> {
+        let store_keys: Vec<String> = references
+            .iter()
+            .filter(|reference| reference.source == Source::EnvStore)
+            .map(|reference| reference.key.clone())
+            .collect();
+        let store = if store_keys.is_empty() {
+            HashMap::new()
+        } else {
+            crate::env::fetch_personal_env_vars(&store_keys)
+                .context("failed to read ${envstore:...} values from the env store")?
+        };
+        Ok(Self::from_sources(
+            references,
+            |reference| match reference.source {
+                Source::Env => std::env::var(&reference.key).ok(),
+                Source::EnvStore => store.get(&reference.key).cloned(),
+            },
+        ))
+    }
+
+    fn from_sources(
+        references: &BTreeSet<Reference>,
+        get: impl Fn(&Reference) -> Option<String>,
+    ) -> Self {
+        let mut resolved = Self::default();
+        for reference in references {
+            match get(reference) {
+                Some(value) => {
+                    resolved.values.insert(reference.clone(), value);
+                }
+                None => {
+                    resolved.missing.insert(reference.clone());
+                }
+            }
+        }
+        resolved
+    }
+
+    pub fn get(&self, reference: &Reference) -> Option<&str> {
+        self.values.get(reference).map(String::as_str)
+    }
+
+    /// Fail naming every reference without a value.
+    pub fn ensure_complete(&self) -> Result<()> {
+        if self.missing.is_empty() {
+            return Ok(());
+        }
+        let names: Vec<String> = self.missing.iter().map(ToString::to_string).collect();
+        let mut hints = Vec::new();
+        if self.missing.iter().any(|r| r.source == Source::Env) {
+            hints.push("export env vars before running flow");
+        }
+        if self.missing.iter().any(|r| r.source == Source::EnvStore) {
+            hints.push("add store keys with `f env set KEY=value`");
+        }
+        bail!(
+            "missing values for {} ({})",
+            names.join(", "),
+            hints.join("; ")
+        );
+    }
+}
+
+/// A task command with references turned into shell variables, plus the variables to export.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShellCommand {
+    pub command: String,
+    /// `${envstore:KEY}` values; the process doesn't have these yet.
+    pub env: Vec<(String, String)>,
+    /// `${env:VAR}` values, already in flow's environment; SSH runs pass them along.
+    pub inherited: Vec<(String, String)>,
+}
+
+fn reference_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"\$?\$\{(env|envstore):([^}]*)\}").expect("valid interpolation regex")
+    })
+}
+
+fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_match(caps: &Captures<'_>) -> Result<Option<Reference>> {
+    if caps[0].starts_with("$$") {
+        return Ok(None);
+    }
+    let key = caps[2].trim();
+    if !is_valid_key(key) {
+        bail!(
+            "invalid reference '{}': expected a variable name like ${{{}:API_TOKEN}}",
+            &caps[0],
+            &caps[1]
+        );
+    }
+    let source = if &caps[1] == "env" {
+        Source::Env
+    } else {
+        Source::EnvStore
+    };
+    Ok(Some(Reference {
+        source,
+        key: key.to_string(),
+    }))
+}
+
+/// References in `value`, skipping `$$`-escaped ones.
+pub fn references(value: &str) -> Result<BTreeSet<Reference>> {
+    let mut found = BTreeSet::new();
+    for caps in reference_regex().captures_iter(value) {
+        if let Some(reference) = parse_match(&caps)? {
+            found.insert(reference);
+        }
+    }
+    Ok(found)
+}
+
+/// Replace each reference with `replace(reference)` and unescape `$${`.
+fn expand(value: &str, mut replace: impl FnMut(&Reference) -> String) -> String {
+    reference_regex()
+        .replace_all(value, |caps: &Captures<'_>| match parse_match(caps) {
+            Ok(Some(reference)) => replace(&reference),
+            _ => caps[0].strip_prefix('$').unwrap_or(&caps[0]).to_string(),
+        })
+        .into_owned()
+}
+
+/// Resolve `value` now; for config values that are handed to another tool (deploy env vars).
+pub fn interpolate(value: &str) -> Result<String> {
+    let refs = references(value)?;
+    if refs.is_empty() && !value.contains("$${") {
+        return Ok(value.to_string());
+    }
+    let resolved = Resolved::lookup(&refs)?;
+    resolved.ensure_complete()?;
+    Ok(expand(value, |reference| {
+        resolved.get(reference).unwrap_or_default().to_string()
+    }))
+}
+
+/// Prepare a task command for `sh -c`: references become `${KEY}` and store values are
+/// returned for the caller to export. `${env:KEY}` only needs checking, since the task
+/// inherits flow's environment.
+pub fn prepare_shell_command(command: &str) -> Result<ShellCommand> {
+    let refs = references(command)?;
+    if refs.is_empty() && !command.contains("$${") {
+        return Ok(ShellCommand {
+            command: command.to_string(),
+            ..Default::default()
+        });
+    }
+    let resolved = Resolved::lookup(&refs)?;
+    resolved.ensure_complete()?;
+    Ok(shell_command(command, &refs, &resolved))
+}
+
+fn shell_command(command: &str, refs: &BTreeSet<Reference>, resolved: &Resolved) -> ShellCommand {
+    let values = |source: Source| -> Vec<(String, String)> {
+        refs.iter()
+            .filter(|reference| reference.source == source)
+            .filter_map(|reference| {
+                let value = resolved.get(reference)?;
+                Some((reference.key.clone(), value.to_string()))
+            })
+            .collect()
+    };
+    ShellCommand {
+        command: expand(command, |reference| format!("${{{}}}", reference.key)),
+        env: values(Source::EnvStore),
+        inherited: values(Source::Env),
+    }
+}
+
+/// `f config resolve [KEY]`: print flow.toml (or one key) with references resolved and masked.
+pub fn run_resolve(opts: ConfigResolveOpts) -> Result<()> {
+    let (path, _) = crate::tasks::load_project_config(opts.config.clone())?;
+    let mut doc = config_edit::load(&path)?;
+    let mut selected = match opts.key.as_deref() {
+        Some(key) => Some(
+            config_edit::get(&doc, key)?
+                .with_context(|| format!("{} has no key '{}'", path.display(), key))?,
+        ),
+        None => None,
+    };
+    let item = match selected.as_mut() {
+        Some(item) => item,
+        None => doc.as_item_mut(),
+    };
+
+    let mut refs = BTreeSet::new();
+    visit_strings(item, &mut |value| {
+        refs.extend(references(value)?);
+        Ok(None)
+    })?;
+    let resolved = Resolved::lookup(&refs)?;
+    visit_strings(item, &mut |value| {
+        if references(value)?.is_empty() && !value.contains("$${") {
+            return Ok(None);
+        }
+        Ok(Some(expand(value, |reference| {
+            match resolved.get(reference) {
+                Some(_) => "********".to_string(),
+                None => format!("<missing {reference}>"),
+            }
+        })))
+    })?;
+
+    match (opts.key.as_deref(), selected.as_ref()) {
+        (Some(key), Some(item)) => println!("{}", config_edit::render(key, item)),
+        _ => print!("{doc}"),
+    }
+
+    if refs.is_empty() {
+        return Ok(());
+    }
+    let width = refs
+        .iter()
+        .map(|reference| reference.to_string().len())
+        .max()
+        .unwrap_or(0);
+    println!();
+    println!("References:");
+    for reference in &refs {
+        let state = match (reference.source, resolved.get(reference).is_some()) {
+            (_, false) => "missing",
+            (Source::Env, true) => "set (environment)",
+            (Source::EnvStore, true) => "set (env store)",
+        };
+        println!("  {:<width$}  {state}", reference.to_string());
+    }
+    resolved.ensure_complete()
+}
+
+/// Call `f` on every string in `item`, replacing it when `f` returns a new value.
+fn visit_strings(item: &mut Item, f: &mut dyn FnMut(&str) -> Result<Option<String>>) -> Result<()> {
+    match item {
+        Item::Value(value) => visit_value(value, f),
+        Item::Table(table) => {
+            for (_, child) in table.iter_mut() {
+                visit_strings(child, f)?;
+            }
+            Ok(())
+        }
+        Item::ArrayOfTables(tables) => {
+            for table in tables.iter_mut() {
+                for (_, child) in table.iter_mut() {
+                    visit_strings(child, f)?;
+                }
+            }
+            Ok(())
+        }
+        Item::None => Ok(()),
+    }
+}
+
+fn visit_value(value: &mut Value, f: &mut dyn FnMut(&str) -> Result<Option<String>>) -> Result<()> {
+    match value {
+        Value::String(text) => {
+            if let Some(replaced) = f(text.value())? {
+                let decor = text.decor().clone();
+                let mut formatted = toml_edit::Formatted::new(replaced);
+                *formatted.decor_mut() = decor;
+                *text = formatted;
+            }
+            Ok(())
+        }
+        Value::Array(array) => {
+            for child in array.iter_mut() {
+                visit_value(child, f)?;
+            }
+            Ok(())
+        }
+        Value::InlineTable(table) => {
+            for (_, child) in table.iter_mut() {
+                visit_value(child, f)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference(source: Source, key: &str) -> Reference {
+        Reference {
+            source,
+            key: key.to_string(),
+        }
+    }
+
+    #[test]
+    fn finds_references_and_rejects_bad_names() {
+        let refs = references(
+            "curl -H \"Authorization: ${envstore:API_TOKEN}\" ${env:API_URL}/v1 $${env:LITERAL} ${HOME}",
+        )
+        .unwrap();
+        assert_eq!(
+            refs.into_iter().collect::<Vec<_>>(),
+            [
+                reference(Source::Env, "API_URL"),
+                reference(Source::EnvStore, "API_TOKEN"),
+            ]
+        );
+        assert!(references("${envstore:api-token}").is_err());
+        assert!(references("${env:}").is_err());
+    }
+
+    #[test]
+    fn shell_commands_export_store_values_instead_of_inlining_them() {
+        let command = "curl -H \"Authorization: ${envstore:API_TOKEN}\" ${env:API_URL} $${env:X}";
+        let refs = references(command).unwrap();
+        let resolved = Resolved::from_sources(&refs, |reference| match reference.key.as_str() {
+            "API_TOKEN" => Some("s3cret".to_string()),
+            "API_URL" => Some("https://api.example.com".to_string()),
+            _ => None,
+        });
+        resolved.ensure_complete().unwrap();
+        let prepared = shell_command(command, &refs, &resolved);
+        assert_eq!(
+            prepared.command,
+            "curl -H \"Authorization: ${API_TOKEN}\" ${API_URL} ${env:X}"
+        );
+        assert_eq!(
+            prepared.env,
+            [("API_TOKEN".to_string(), "s3cret".to_string())]
+        );
+        assert_eq!(
+            prepared.inherited,
+            [("API_URL".to_string(), "https://api.example.com".to_string())]
+        );
+
+        let missing = Resolved::from_sources(&refs, |_| None);
+        let err = missing.ensure_complete().unwrap_err().to_string();
+        assert!(
+            err.contains("${env:API_URL}, ${envstore:API_TOKEN}"),
+            "{err}"
+        );
+    }
+}
@@ -346,7 +346,8 @@ struct SpawnedDaemon {
     stdout_log: PathBuf,
 }
 
-fn spawn_daemon_process(daemon: &DaemonConfig, binary: &Path) -> Result<SpawnedDaemon> {
+/// Command line, working directory and environment for a daemon, without stdio wiring.
+pub(crate) fn daemon_command(daemon: &DaemonConfig, binary: &Path) -> Command {
     let mut cmd = Command::new(binary);
 
     if let Some(subcommand) = &daemon.command {
@@ -373,6 +374,11 @@ fn spawn_daemon_process(daemon: &DaemonConfig, binary: &Path) -> Result<SpawnedD
     for (key, value) in &daemon.env {
         cmd.env(key, value);
     }
+    cmd
+}
+
+fn spawn_daemon_process(daemon: &DaemonConfig, binary: &Path) -> Result<SpawnedDaemon> {
+    let mut cmd = daemon_command(daemon, binary);
 
     let (stdout_log, stderr_log) = daemon_log_paths(&daemon.name)?;
     let stdout_file = OpenOptions::new()
@@ -455,7 +461,10 @@ fn wait_for_daemon_ready(daemon: &DaemonConfig, stdout_log: &Path) -> Result<()>
     Ok(())
 }
 /// Find a daemon config by name from merged configs.
-fn find_daemon_config_with_path(name: &str, config_path: Option<&Path>) -> Result<DaemonConfig> {
+pub(crate) fn find_daemon_config_with_path(
+    name: &str,
+    config_path: Option<&Path>,
+) -> Result<DaemonConfig> {
     let config = load_merged_config_with_path(config_path)?;
     let aliases = match name {
         "codexd" => ["codexd", "jd"],
@@ -520,7 +529,7 @@ fn resolve_flow_toml_path() -> Option<PathBuf> {
 }
 
 /// Find a binary on PATH or as an absolute path.
-fn find_binary(name: &str) -> Result<PathBuf> {
+pub(crate) fn find_binary(name: &str) -> Result<PathBuf> {
     // If it's an absolute path, use it directly
     let path = Path::new(name);
     if path.is_absolute() && path.exists() {
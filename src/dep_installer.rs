@@ -0,0 +1,441 @@
+//! Installer backends for missing project dependencies (`f setup`).
+//!
+//! Backends are tried in order: the one pinned by `[setup.installer] backend`, then mise, asdf,
+//! apt (Linux), and Homebrew as the fallback. Versions from `[deps.versions]` are pinned per
+//! project where the backend supports it (mise/asdf write them into the project directory).
+//! `[deps]` entries with a `min` version are checked against `--version` output.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result, bail};
+
+use crate::config::Config;
+
+/// A package manager that can install missing tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Backend {
+    Mise,
+    Asdf,
+    Apt,
+    Brew,
+}
+
+impl Backend {
+    const AUTO_ORDER: [Backend; 4] = [Backend::Mise, Backend::Asdf, Backend::Apt, Backend::Brew];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Mise => "mise",
+            Backend::Asdf => "asdf",
+            Backend::Apt => "apt",
+            Backend::Brew => "brew",
+        }
+    }
+
+    fn parse(raw: &str) -> Result<Option<Self>> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "" | "auto" => Ok(None),
+            "mise" => Ok(Some(Backend::Mise)),
+            "asdf" => Ok(Some(Backend::Asdf)),
+            "apt" | "apt-get" => Ok(Some(Backend::Apt)),
+            "brew" | "homebrew" => Ok(Some(Backend::Brew)),
+            other => bail!("unknown [setup.installer] backend '{other}' (mise, asdf, apt, brew)"),
+        }
+    }
+
+    fn available(self) -> bool {
+        let bin = match self {
+            Backend::Mise => "mise",
+            Backend::Asdf => "asdf",
+            Backend::Apt => {
+                if !cfg!(target_os = "linux") {
+                    return false;
+                }
+                "apt-get"
+            }
+            Backend::Brew => "brew",
+        };
+        which::which(bin).is_ok()
+    }
+
+    /// Package/plugin name that provides `command`.
+    fn package_for(self, command: &str) -> Option<&'static str> {
+        let tool = tool_for_command(command)?;
+        match self {
+            Backend::Mise => Some(tool),
+            Backend::Asdf => Some(match tool {
+                "node" => "nodejs",
+                "go" => "golang",
+                other => other,
+            }),
+            Backend::Apt => match tool {
+                "node" => Some("nodejs"),
+                "python" => Some("python3"),
+                "go" => Some("golang-go"),
+                "rust" => Some("cargo"),
+                "yarn" => Some("yarnpkg"),
+                _ => None,
+            },
+            Backend::Brew => Some(tool),
+        }
+    }
+
+    /// Commands that install `package`, pinned to `version` when given.
+    fn install_commands(self, package: &str, version: Option<&str>) -> Vec<Vec<String>> {
+        let owned = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        match self {
+            Backend::Mise => {
+                let spec = format!("{package}@{}", version.unwrap_or("latest"));
+                vec![owned(&["mise", "use", &spec])]
+            }
+            Backend::Asdf => {
+                let version = version
+                    .map(|v| {
+                        if v.matches('.').count() >= 2 {
+                            v.to_string()
+                        } else {
+                            format!("latest:{v}")
+                        }
+                    })
+                    .unwrap_or_else(|| "latest".to_string());
+                vec![
+                    owned(&["asdf", "plugin", "add", package]),
+                    owned(&["asdf", "install", package, &version]),
+                    owned(&["asdf", "set", package, &version]),
+                ]
+            }
+            Backend::Apt => vec![owned(&["sudo", "apt-get", "install", "-y", package])],
+            Backend::Brew => {
+                let formula = match version {
+                    Some(v) if matches!(package, "node" | "python" | "go") => {
+                        let major_minor: Vec<&str> = v.split('.').take(2).collect();
+                        let pin = if package == "node" {
+                            major_minor[0].to_string()
+                        } else {
+                            major_minor.join(".")
+                        };
+                        format!("{package}@{pin}")
+                    }
+                    _ => package.to_string(),
+                };
+                vec![owned(&["brew", "install", &formula])]
+            }
+        }
+    }
+
+    /// Whether the backend honors `[deps.versions]` pins.
+    fn pins_versions(self) -> bool {
+        !matches!(self, Backend::Apt)
+    }
+}
+
+/// Tool name (mise/brew naming) for a command on PATH.
+fn tool_for_command(command: &str) -> Option<&'static str> {
+    match command {
+        "pnpm" => Some("pnpm"),
+        "yarn" => Some("yarn"),
+        "bun" => Some("bun"),
+        "node" | "npm" | "npx" => Some("node"),
+        "python" | "python3" | "pip" | "pip3" => Some("python"),
+        "go" => Some("go"),
+        "rustc" | "cargo" => Some("rust"),
+        "wasm-pack" => Some("wasm-pack"),
+        "deno" => Some("deno"),
+        "uv" => Some("uv"),
+        _ => None,
+    }
+}
+
+/// Backend pinned by `[setup.installer] backend`, if any.
+pub fn preferred_backend(cfg: &Config) -> Option<&str> {
+    cfg.setup
+        .as_ref()
+        .and_then(|setup| setup.installer.as_ref())
+        .and_then(|installer| installer.backend.as_deref())
+}
+
+/// Backends to try, most preferred first.
+fn candidate_backends(preferred: Option<&str>) -> Result<Vec<Backend>> {
+    let mut order = Vec::new();
+    if let Some(backend) = preferred.map(Backend::parse).transpose()?.flatten() {
+        order.push(backend);
+    }
+    for backend in Backend::AUTO_ORDER {
+        if !order.contains(&backend) {
+            order.push(backend);
+        }
+    }
+    Ok(order.into_iter().filter(|b| b.available()).collect())
+}
+
+/// Install `missing` commands with the first available backend that knows each one.
+pub fn install_missing(
+    project_root: &Path,
+    missing: &BTreeSet<String>,
+    versions: &BTreeMap<String, String>,
+    preferred: Option<&str>,
+) -> Result<()> {
+    let backends = candidate_backends(preferred)?;
+    if backends.is_empty() {
+        println!("No installer found (mise, asdf, apt, brew). Install missing deps manually.");
+        return Ok(());
+    }
+
+    // Several commands can map to one package (node/npm); install each package once.
+    let mut installed = BTreeSet::new();
+    for command in missing {
+        let Some((backend, package)) = backends
+            .iter()
+            .find_map(|b| b.package_for(command).map(|pkg| (*b, pkg)))
+        else {
+            println!(
+                "  - No installer mapping for '{}'; install it manually.",
+                command
+            );
+            continue;
+        };
+        if !installed.insert((backend, package)) {
+            continue;
+        }
+
+        let tool = tool_for_command(command).unwrap_or(package);
+        let version = versions
+            .get(tool)
+            .or_else(|| versions.get(command.as_str()))
+            .map(String::as_str);
+        if version.is_some() && !backend.pins_versions() {
+            println!(
+                "  - {} cannot pin versions; installing the distro {}",
+                backend.name(),
+                package
+            );
+        }
+
+        println!(
+            "Installing {}{} with {}",
+            package,
+            version.map(|v| format!("@{v}")).unwrap_or_default(),
+            backend.name()
+        );
+        for args in backend.install_commands(package, version) {
+            // `asdf plugin add` fails when the plugin exists; that's fine.
+            let tolerate_failure = args.get(1).map(String::as_str) == Some("plugin");
+            let status = Command::new(&args[0])
+                .args(&args[1..])
+                .current_dir(project_root)
+                .stdin(Stdio::inherit())
+                .stdout(Stdio::inherit())
+                .stderr(Stdio::inherit())
+                .status()
+                .with_context(|| format!("failed to run {}", args.join(" ")))?;
+            if !status.success() && !tolerate_failure {
+                println!("  - `{}` failed; install it manually.", args.join(" "));
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Result of checking one `[deps]` command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DepState {
+    /// Present and new enough (version is `None` when no minimum was requested).
+    Ok(Option<String>),
+    Missing,
+    Outdated {
+        found: String,
+        min: String,
+    },
+    /// Present, but `--version` output had no recognizable version.
+    Unknown {
+        min: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct DepCheck {
+    pub command: String,
+    pub min: Option<String>,
+    pub state: DepState,
+}
+
+/// Check presence (and minimum versions) of every `[deps]` command.
+pub fn check_dependencies(cfg: &Config) -> Vec<DepCheck> {
+    let mut requirements: BTreeMap<String, Option<String>> = BTreeMap::new();
+    for spec in cfg.dependencies.values() {
+        let mut commands = Vec::new();
+        spec.extend_commands(&mut commands);
+        for command in commands {
+            let min = spec.min_version().map(str::to_string);
+            let entry = requirements.entry(command).or_default();
+            if min.is_some() {
+                *entry = min;
+            }
+        }
+    }
+
+    requirements
+        .into_iter()
+        .map(|(command, min)| {
+            let state = if which::which(&command).is_err() {
+                DepState::Missing
+            } else if let Some(min) = min.clone() {
+                match detect_version(&command) {
+                    Some(found) if version_at_least(&found, &min) => DepState::Ok(Some(found)),
+                    Some(found) => DepState::Outdated { found, min },
+                    None => DepState::Unknown { min },
+                }
+            } else {
+                DepState::Ok(None)
+            };
+            DepCheck {
+                command,
+                min,
+                state,
+            }
+        })
+        .collect()
+}
+
+/// Version reported by `<command> --version` (or `go version`).
+pub fn detect_version(command: &str) -> Option<String> {
+    let arg = if command == "go" {
+        "version"
+    } else {
+        "--version"
+    };
+    let output = Command::new(command)
+        .arg(arg)
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    extract_version(&text)
+}
+
+/// First dotted version number in `text` (e.g. "v20.11.1" -> "20.11.1").
+fn extract_version(text: &str) -> Option<String> {
+    let re = regex::Regex::new(r"\d+(?:\.\d+)+|\d+").ok()?;
+    // Prefer a dotted version; fall back to a bare number.
+    re.find_iter(text)
+        .map(|m| m.as_str())
+        .find(|v| v.contains('.'))
+        .or_else(|| re.find(text).map(|m| m.as_str()))
+        .map(str::to_string)
+}
+
+fn version_parts(version: &str) -> Vec<u64> {
+    version
+        .trim()
+        .trim_start_matches('v')
+        .split('.')
+        .map(|part| {
+            part.chars()
+                .take_while(char::is_ascii_digit)
+                .collect::<String>()
+                .parse()
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Compare dotted versions numerically, padding missing components with zero.
+fn version_at_least(found: &str, min: &str) -> bool {
+    let found = version_parts(found);
+    let min = version_parts(min);
+    let len = found.len().max(min.len());
+    let pad = |parts: &[u64]| -> Vec<u64> {
+        (0..len)
+            .map(|idx| parts.get(idx).copied().unwrap_or(0))
+            .collect()
+    };
+    pad(&found) >= pad(&min)
+}
+
+/// Command that would install `command` at `min` with the preferred available backend.
+pub fn installer_hint(command: &str, min: Option<&str>, preferred: Option<&str>) -> String {
+    let backends = candidate_backends(preferred).unwrap_or_default();
+    backends
+        .iter()
+        .find_map(|backend| {
+            let package = backend.package_for(command)?;
+            let commands = backend.install_commands(package, min);
+            commands.last().map(|args| args.join(" "))
+        })
+        .unwrap_or_else(|| match min {
+            Some(min) => format!("install {command} >= {min} manually"),
+            None => format!("install {command} manually"),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_commands_to_backend_packages() {
+        assert_eq!(Backend::Mise.package_for("npm"), Some("node"));
+        assert_eq!(Backend::Asdf.package_for("node"), Some("nodejs"));
+        assert_eq!(Backend::Apt.package_for("python3"), Some("python3"));
+        assert_eq!(Backend::Apt.package_for("bun"), None);
+        assert_eq!(Backend::Brew.package_for("cargo"), Some("rust"));
+        assert_eq!(Backend::Brew.package_for("unknown-tool"), None);
+    }
+
+    #[test]
+    fn pins_versions_per_backend() {
+        assert_eq!(
+            Backend::Mise.install_commands("node", Some("22")),
+            vec![vec!["mise", "use", "node@22"]]
+        );
+        assert_eq!(
+            Backend::Asdf.install_commands("nodejs", Some("22"))[1],
+            vec!["asdf", "install", "nodejs", "latest:22"]
+        );
+        assert_eq!(
+            Backend::Brew.install_commands("python", Some("3.12.1")),
+            vec![vec!["brew", "install", "python@3.12"]]
+        );
+        assert_eq!(
+            Backend::Brew.install_commands("pnpm", Some("9")),
+            vec![vec!["brew", "install", "pnpm"]]
+        );
+    }
+
+    #[test]
+    fn compares_versions_numerically() {
+        assert!(version_at_least("20.11.1", "20.0"));
+        assert!(version_at_least("v22", "20.0"));
+        assert!(!version_at_least("18.19.0", "20"));
+        assert!(!version_at_least("1.9.0", "1.10"));
+        assert!(version_at_least("1.10.0", "1.10"));
+    }
+
+    #[test]
+    fn extracts_versions_from_tool_output() {
+        assert_eq!(extract_version("v20.11.1").as_deref(), Some("20.11.1"));
+        assert_eq!(
+            extract_version("go version go1.22.3 darwin/arm64").as_deref(),
+            Some("1.22.3")
+        );
+        assert_eq!(
+            extract_version("Python 3.12.1\n").as_deref(),
+            Some("3.12.1")
+        );
+        assert_eq!(extract_version("no digits here"), None);
+    }
+
+    #[test]
+    fn parses_backend_names() {
+        assert_eq!(Backend::parse("Mise").unwrap(), Some(Backend::Mise));
+        assert_eq!(Backend::parse("auto").unwrap(), None);
+        assert!(Backend::parse("nix").is_err());
+    }
+}
@@ -10,7 +10,7 @@ use std::fs;
 use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context, Result, bail};
 use reqwest::blocking::Client;
@@ -19,12 +19,22 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::cli::{DeployAction, DeployCommand, EnvAction, TaskRunOpts};
-use crate::config::Config;
+use crate::config::{Config, NotifyEvent};
+use crate::config_edit;
+use crate::deploy_audit;
+use crate::deploy_provision::ProvisionPlan;
 use crate::deploy_setup::{
     CloudflareSetupDefaults, CloudflareSetupResult, discover_wrangler_configs, run_cloudflare_setup,
 };
+use crate::deploy_watch;
+use crate::deploy_workers::{self, WorkerConfig};
 use crate::env::parse_env_file;
+use crate::env_template::{EnvTemplate, render_env_vars};
+use crate::line_diff;
+use crate::notify_rules;
+use crate::plugins;
 use crate::release;
+use crate::runs;
 use crate::services;
 use crate::tasks;
 
@@ -113,6 +123,8 @@ pub struct HostConfig {
     /// Specific env keys to fetch when env_source = "cloud".
     #[serde(default)]
     pub env_keys: Vec<String>,
+    /// .env template rendered at deploy time (same syntax as `f env render`).
+    pub env_template: Option<String>,
     /// Fetch from project-scoped env vars instead of personal (default).
     #[serde(default)]
     pub env_project: bool,
@@ -125,6 +137,34 @@ pub struct HostConfig {
     /// Enable SSL via Let's Encrypt.
     #[serde(default)]
     pub ssl: bool,
+    /// Log in as the configured user and escalate with sudo for systemd, nginx, and
+    /// certbot steps (NOPASSWD is detected; otherwise the password is asked once).
+    #[serde(default)]
+    pub sudo: bool,
+    /// Destructive operations ("rsync --delete", "systemctl stop") that run without the
+    /// interactive confirmation.
+    #[serde(default, alias = "allow-destructive")]
+    pub allow_destructive: Vec<String>,
+    /// How a deploy replaces the running release: "restart" (default) or "swap" to bring
+    /// the new release up on `alt_port` and flip nginx to it.
+    pub strategy: Option<String>,
+    /// Second port used by the "swap" strategy (defaults to `port + 1`).
+    pub alt_port: Option<u16>,
+    /// Path a new release must answer before traffic moves to it (defaults to "/").
+    pub health_path: Option<String>,
+    /// Seconds to wait for the new release to become healthy (defaults to 30).
+    pub health_timeout: Option<u64>,
+    /// System user the service runs as; created by `f deploy host --provision`.
+    pub user: Option<String>,
+    /// apt packages installed by `f deploy host --provision`.
+    #[serde(default)]
+    pub packages: Vec<String>,
+    /// Background workers and scheduled jobs deployed next to the service.
+    #[serde(default)]
+    pub workers: Vec<WorkerConfig>,
+    /// SSH connection ("user@host[:port]") for this project; defaults to the host from
+    /// `f deploy set-host`.
+    pub ssh: Option<String>,
 }
 
 /// Cloudflare deployment config from flow.toml [cloudflare] section.
@@ -205,6 +245,25 @@ pub struct WebConfig {
     pub environment: Option<String>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HostStrategy {
+    Restart,
+    Swap,
+}
+
+fn host_strategy(cfg: &HostConfig) -> Result<HostStrategy> {
+    match cfg
+        .strategy
+        .as_deref()
+        .map(str::to_ascii_lowercase)
+        .as_deref()
+    {
+        None | Some("restart") => Ok(HostStrategy::Restart),
+        Some("swap") => Ok(HostStrategy::Swap),
+        Some(other) => bail!("unknown [host] strategy '{other}' (use \"restart\" or \"swap\")"),
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum EnvApplyMode {
     Always,
@@ -241,16 +300,37 @@ pub struct RailwayConfig {
     pub start: Option<String>,
     /// Path to .env file.
     pub env_file: Option<String>,
+    /// Env source for variables ("cloud", "flow", or "local"); takes precedence over env_file.
+    pub env_source: Option<String>,
+    /// Keys to sync from the env store (defaults to every key in the environment).
+    #[serde(default)]
+    pub env_keys: Vec<String>,
+    /// Personal env store key holding the Railway API token (defaults to RAILWAY_API_TOKEN).
+    pub token_key: Option<String>,
 }
 
 /// Get the deploy config file path.
-fn deploy_config_path() -> PathBuf {
+pub(crate) fn deploy_config_path() -> PathBuf {
     dirs::config_dir()
         .unwrap_or_else(|| PathBuf::from("."))
         .join("flow")
         .join("deploy.json")
 }
 
+/// SSH connection for a project: `[host] ssh` when set, otherwise the global deploy host.
+pub fn host_connection(host_cfg: Option<&HostConfig>) -> Result<Option<HostConnection>> {
+    let ssh = host_cfg
+        .and_then(|host| host.ssh.as_deref())
+        .map(str::trim)
+        .filter(|ssh| !ssh.is_empty());
+    match ssh {
+        Some(ssh) => HostConnection::parse(ssh)
+            .map(Some)
+            .with_context(|| format!("invalid [host] ssh '{ssh}'")),
+        None => Ok(load_deploy_config()?.host),
+    }
+}
+
 /// Load global deploy config.
 pub fn load_deploy_config() -> Result<DeployConfig> {
     let path = deploy_config_path();
@@ -315,11 +395,84 @@ pub fn run(cmd: DeployCommand) -> Result<()> {
         Some(DeployAction::Shell) => open_shell(),
         Some(DeployAction::SetHost { connection }) => set_host(&connection),
         Some(DeployAction::ShowHost) => show_host(),
+        Some(DeployAction::Watch(opts)) => deploy_watch::run(opts),
         action => {
             let ctx = load_deploy_project_context()?;
-            run_with_project_context(action, ctx)
+            let Some(label) = deploy_run_label(action.as_ref()) else {
+                return run_with_project_context(action, ctx);
+            };
+            let tracker = start_deploy_run(label, &ctx);
+            let notify = DeployNotifyTarget::new(&ctx);
+            let result = run_with_project_context(action, ctx);
+            tracker.finish_result(&result);
+            notify.send(label, &result);
+            result
+        }
+    }
+}
+
+/// Name recorded in run history for actions that actually deploy something.
+fn deploy_run_label(action: Option<&DeployAction>) -> Option<&'static str> {
+    match action {
+        None => Some("deploy"),
+        Some(DeployAction::Host { .. }) => Some("deploy:host"),
+        Some(DeployAction::Cloudflare { .. }) => Some("deploy:cloudflare"),
+        Some(DeployAction::Web) => Some("deploy:web"),
+        Some(DeployAction::Railway) => Some("deploy:railway"),
+        Some(DeployAction::Restart) => Some("deploy:restart"),
+        _ => None,
+    }
+}
+
+/// Start a deploy run record and export its id so task runs started by the deploy link to it.
+fn start_deploy_run(label: &str, ctx: &DeployProjectContext) -> runs::RunTracker {
+    let project = ctx
+        .flow_config
+        .as_ref()
+        .and_then(|cfg| cfg.project_name.as_deref());
+    runs::RunTracker::start(
+        runs::RunKind::Deploy,
+        label,
+        project,
+        Some(&ctx.project_root),
+    )
+    .enter()
+}
+
+/// Where `[notify]` deploy alerts go, captured before the project context is consumed.
+struct DeployNotifyTarget {
+    config_path: PathBuf,
+    project_root: PathBuf,
+    project: String,
+}
+
+impl DeployNotifyTarget {
+    fn new(ctx: &DeployProjectContext) -> Self {
+        Self {
+            config_path: ctx.config_path.clone(),
+            project_root: ctx.project_root.clone(),
+            project: notify_rules::project_label(ctx.flow_config.as_ref(), &ctx.project_root),
         }
     }
+
+    /// Dispatch `[notify]` rules and `post-deploy` plugin hooks.
+    fn send(self, label: &str, result: &Result<()>) {
+        plugins::run_post_deploy(&self.project_root, &self.project, label, result);
+        let (event, detail) = match result {
+            Ok(()) => (NotifyEvent::DeploySuccess, String::new()),
+            Err(err) => (NotifyEvent::DeployFailure, format!("{err:#}")),
+        };
+        notify_rules::dispatch_from(
+            &self.config_path,
+            &self.project_root,
+            &notify_rules::Notification {
+                event,
+                project: self.project,
+                subject: label.to_string(),
+                detail,
+            },
+        );
+    }
 }
 
 fn run_with_project_context(action: Option<DeployAction>, ctx: DeployProjectContext) -> Result<()> {
@@ -340,6 +493,9 @@ fn run_with_project_context(action: Option<DeployAction>, ctx: DeployProjectCont
                             delegate_to_hub: false,
                             hub_host: std::net::IpAddr::from([127, 0, 0, 1]),
                             hub_port: 9050,
+                            priority: 0,
+                            print_env: false,
+                            yes: false,
                             name: task_name.to_string(),
                             args: Vec::new(),
                         });
@@ -360,6 +516,9 @@ fn run_with_project_context(action: Option<DeployAction>, ctx: DeployProjectCont
                         delegate_to_hub: false,
                         hub_host: std::net::IpAddr::from([127, 0, 0, 1]),
                         hub_port: 9050,
+                        priority: 0,
+                        print_env: false,
+                        yes: false,
                         name: "deploy".to_string(),
                         args: Vec::new(),
                     });
@@ -384,7 +543,16 @@ fn run_with_project_context(action: Option<DeployAction>, ctx: DeployProjectCont
         Some(DeployAction::Host {
             remote_build,
             setup,
-        }) => deploy_host(&project_root, flow_config.as_ref(), remote_build, setup),
+            provision,
+            yes,
+        }) => deploy_host(
+            &project_root,
+            flow_config.as_ref(),
+            remote_build,
+            setup,
+            provision,
+            yes,
+        ),
         Some(DeployAction::Cloudflare { secrets, dev }) => {
             deploy_cloudflare(&project_root, flow_config.as_ref(), secrets, dev)
         }
@@ -406,7 +574,9 @@ fn run_with_project_context(action: Option<DeployAction>, ctx: DeployProjectCont
             lines,
         ),
         Some(DeployAction::Restart) => restart_service(&project_root, flow_config.as_ref()),
-        Some(DeployAction::Stop) => stop_service(&project_root, flow_config.as_ref()),
+        Some(DeployAction::Stop { yes }) => stop_service(&project_root, flow_config.as_ref(), yes),
+        Some(DeployAction::Audit(opts)) => deploy_audit::run(&project_root, opts),
+        Some(DeployAction::Drift) => check_drift(&project_root, flow_config.as_ref()),
         Some(DeployAction::Health { url, status }) => {
             check_health(&project_root, flow_config.as_ref(), url, status)
         }
@@ -414,7 +584,8 @@ fn run_with_project_context(action: Option<DeployAction>, ctx: DeployProjectCont
         | Some(DeployAction::Release(_))
         | Some(DeployAction::Shell)
         | Some(DeployAction::SetHost { .. })
-        | Some(DeployAction::ShowHost) => unreachable!("handled before project context load"),
+        | Some(DeployAction::ShowHost)
+        | Some(DeployAction::Watch(_)) => unreachable!("handled before project context load"),
     }
 }
 
@@ -426,9 +597,19 @@ pub fn run_prod(cmd: DeployCommand) -> Result<()> {
         Some(DeployAction::Shell) => open_shell(),
         Some(DeployAction::SetHost { connection }) => set_host(&connection),
         Some(DeployAction::ShowHost) => show_host(),
+        Some(DeployAction::Watch(opts)) => deploy_watch::run(opts),
         action => {
             let ctx = load_deploy_project_context()?;
-            run_prod_with_project_context(action, ctx)
+            let Some(label) = deploy_run_label(action.as_ref()) else {
+                return run_prod_with_project_context(action, ctx);
+            };
+            let label = if action.is_none() { "prod" } else { label };
+            let tracker = start_deploy_run(label, &ctx);
+            let notify = DeployNotifyTarget::new(&ctx);
+            let result = run_prod_with_project_context(action, ctx);
+            tracker.finish_result(&result);
+            notify.send(label, &result);
+            result
         }
     }
 }
@@ -455,6 +636,9 @@ fn run_prod_with_project_context(
                     delegate_to_hub: false,
                     hub_host: std::net::IpAddr::from([127, 0, 0, 1]),
                     hub_port: 9050,
+                    priority: 0,
+                    print_env: false,
+                    yes: false,
                     name: "deploy-prod".to_string(),
                     args: Vec::new(),
                 });
@@ -466,6 +650,9 @@ fn run_prod_with_project_context(
                     delegate_to_hub: false,
                     hub_host: std::net::IpAddr::from([127, 0, 0, 1]),
                     hub_port: 9050,
+                    priority: 0,
+                    print_env: false,
+                    yes: false,
                     name: "prod".to_string(),
                     args: Vec::new(),
                 });
@@ -478,7 +665,7 @@ fn run_prod_with_project_context(
             {
                 if cfg.host.is_some() {
                     println!("Detected [host] config, deploying to Linux host...");
-                    return deploy_host(&project_root, Some(cfg), false, false);
+                    return deploy_host(&project_root, Some(cfg), false, false, false, false);
                 }
 
                 if cfg.cloudflare.is_some() {
@@ -518,7 +705,16 @@ fn run_prod_with_project_context(
         Some(DeployAction::Host {
             remote_build,
             setup,
-        }) => deploy_host(&project_root, flow_config.as_ref(), remote_build, setup),
+            provision,
+            yes,
+        }) => deploy_host(
+            &project_root,
+            flow_config.as_ref(),
+            remote_build,
+            setup,
+            provision,
+            yes,
+        ),
         Some(DeployAction::Cloudflare { secrets, dev }) => {
             if let Some(cfg) = flow_config.as_ref() {
                 if let Err(err) = ensure_prod_cloudflare_routes(&project_root, cfg) {
@@ -545,7 +741,9 @@ fn run_prod_with_project_context(
             lines,
         ),
         Some(DeployAction::Restart) => restart_service(&project_root, flow_config.as_ref()),
-        Some(DeployAction::Stop) => stop_service(&project_root, flow_config.as_ref()),
+        Some(DeployAction::Stop { yes }) => stop_service(&project_root, flow_config.as_ref(), yes),
+        Some(DeployAction::Audit(opts)) => deploy_audit::run(&project_root, opts),
+        Some(DeployAction::Drift) => check_drift(&project_root, flow_config.as_ref()),
         Some(DeployAction::Health { url, status }) => {
             check_health(&project_root, flow_config.as_ref(), url, status)
         }
@@ -553,7 +751,8 @@ fn run_prod_with_project_context(
         | Some(DeployAction::Release(_))
         | Some(DeployAction::Shell)
         | Some(DeployAction::SetHost { .. })
-        | Some(DeployAction::ShowHost) => unreachable!("handled before project context load"),
+        | Some(DeployAction::ShowHost)
+        | Some(DeployAction::Watch(_)) => unreachable!("handled before project context load"),
     }
 }
 
@@ -746,7 +945,7 @@ fn auto_deploy(project_root: &Path, config: Option<&Config>) -> Result<()> {
     // Check which platform configs exist
     if config.host.is_some() {
         println!("Detected [host] config, deploying to Linux host...");
-        return deploy_host(project_root, Some(config), false, false);
+        return deploy_host(project_root, Some(config), false, false, false, false);
     }
 
     if config.cloudflare.is_some() {
@@ -819,6 +1018,9 @@ fn deploy_web(project_root: &Path, config: Option<&Config>) -> Result<()> {
             delegate_to_hub: false,
             hub_host: std::net::IpAddr::from([127, 0, 0, 1]),
             hub_port: 9050,
+            priority: 0,
+            print_env: false,
+            yes: false,
             name: "deploy-web".to_string(),
             args: Vec::new(),
         });
@@ -831,6 +1033,9 @@ fn deploy_web(project_root: &Path, config: Option<&Config>) -> Result<()> {
             delegate_to_hub: false,
             hub_host: std::net::IpAddr::from([127, 0, 0, 1]),
             hub_port: 9050,
+            priority: 0,
+            print_env: false,
+            yes: false,
             name: "deploy".to_string(),
             args: Vec::new(),
         });
@@ -879,16 +1084,14 @@ fn deploy_host(
     config: Option<&Config>,
     _remote_build: bool,
     force_setup: bool,
+    provision: bool,
+    assume_yes: bool,
 ) -> Result<()> {
-    let deploy_config = load_deploy_config()?;
-    let conn = deploy_config
-        .host
-        .as_ref()
-        .context("No host configured. Run: f deploy set-host user@host:port")?;
-
     let host_cfg = config
         .and_then(|c| c.host.as_ref())
         .context("No [host] section in flow.toml")?;
+    let conn = &host_connection(Some(host_cfg))?
+        .context("No host configured. Run: f deploy set-host user@host:port")?;
 
     let dest = host_cfg.dest.as_deref().unwrap_or("/opt/app");
     let service_name = host_cfg
@@ -896,11 +1099,44 @@ fn deploy_host(
         .as_deref()
         .unwrap_or_else(|| project_root.file_name().unwrap().to_str().unwrap());
 
+    let swap = match host_strategy(host_cfg)? {
+        HostStrategy::Swap => Some(SwapPlan::from_config(service_name, host_cfg)?),
+        HostStrategy::Restart => None,
+    };
+    deploy_workers::validate(&host_cfg.workers)?;
+
     println!("Deploying to {}:{}", conn.ssh_target(), dest);
 
+    let audit = deploy_audit::begin(
+        project_root,
+        "deploy:host",
+        &conn.ssh_target(),
+        &host_cfg.allow_destructive,
+        assume_yes,
+    )?;
+
+    // One authenticated connection for every step below.
+    let _master = SshMaster::open(conn)?;
+    let privilege = Privilege::detect(conn, Some(host_cfg))?;
+
+    if provision {
+        provision_host(conn, &privilege, host_cfg, dest, assume_yes)?;
+    }
+
     // 1. Sync files via rsync
     println!("\n==> Syncing files...");
-    rsync_upload(project_root, conn, dest)?;
+    rsync_upload(project_root, conn, &privilege, dest)?;
+    if let Some(user) = &host_cfg.user {
+        // rsync keeps the login user's group; give the service user read access again.
+        ssh_run_privileged(
+            conn,
+            &privilege,
+            &format!(
+                "chgrp -R {user} {dest} && chmod -R g+rX,o= {dest}",
+                dest = shell_words::quote(dest)
+            ),
+        )?;
+    }
 
     // 2. Handle env vars
     let use_cloud = is_cloud_source(host_cfg.env_source.as_deref());
@@ -935,7 +1171,25 @@ fn deploy_host(
     } else if use_cloud || use_flow {
         // Deploy-time fetch mode: fetch now and copy to host
         let env_name = host_cfg.environment.as_deref().unwrap_or("production");
-        let keys = &host_cfg.env_keys;
+        let template = match &host_cfg.env_template {
+            Some(path) => {
+                let template_path = project_root.join(path);
+                let content = fs::read_to_string(&template_path).with_context(|| {
+                    format!("failed to read env template {}", template_path.display())
+                })?;
+                Some(EnvTemplate::parse(&content))
+            }
+            None => None,
+        };
+        let mut keys = host_cfg.env_keys.clone();
+        if let Some(template) = &template {
+            for key in template.referenced_keys() {
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+        let keys = &keys;
         let use_project = host_cfg.env_project;
 
         if !keys.is_empty() {
@@ -965,32 +1219,28 @@ fn deploy_host(
             };
 
             match result {
-                Ok(mut vars) if !vars.is_empty() => {
-                    if !keys.is_empty() {
-                        let key_set: HashSet<_> = keys.iter().collect();
-                        vars.retain(|k, _| key_set.contains(k));
-                    }
-
-                    // Generate .env content
-                    let mut content = String::new();
-                    content.push_str(&format!(
-                        "# Source: {} {} (fetched at deploy)\n",
-                        source_label, source
-                    ));
-                    let mut sorted_keys: Vec<_> = vars.keys().collect();
-                    sorted_keys.sort();
-                    for key in sorted_keys {
-                        let value = &vars[key];
-                        let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
-                        content.push_str(&format!("{}=\"{}\"\n", key, escaped));
-                    }
+                Ok(mut vars) if !vars.is_empty() || template.is_some() => {
+                    let key_set: HashSet<_> = keys.iter().collect();
+                    vars.retain(|k, _| key_set.contains(k));
+
+                    let header = format!("Source: {} {} (fetched at deploy)", source_label, source);
+                    let (content, count) = match &template {
+                        Some(template) => {
+                            let rendered = template.render(&vars)?;
+                            (
+                                format!("# {}\n{}", header, rendered.content),
+                                rendered.entries.len(),
+                            )
+                        }
+                        None => (render_env_vars(&[header], &vars), vars.len()),
+                    };
 
                     // Write to temp file and scp
                     let temp_env =
                         std::env::temp_dir().join(format!(".env.{}", std::process::id()));
                     fs::write(&temp_env, &content)?;
                     let remote_env = format!("{}/.env", dest);
-                    println!("==> Copying {} env vars to remote...", vars.len());
+                    println!("==> Copying {} env vars to remote...", count);
                     scp_file(&temp_env, conn, &remote_env)?;
                     let _ = fs::remove_file(&temp_env);
                 }
@@ -1022,22 +1272,60 @@ fn deploy_host(
     // 4. Create/update systemd service
     if let Some(run_cmd) = &host_cfg.run {
         println!("==> Configuring systemd service: {}", service_name);
-        create_systemd_service(conn, service_name, dest, run_cmd, host_cfg)?;
+        create_systemd_service(
+            conn,
+            &privilege,
+            service_name,
+            dest,
+            run_cmd,
+            host_cfg,
+            swap.is_some(),
+        )?;
     }
+    if !host_cfg.workers.is_empty() {
+        println!("==> Configuring {} worker unit(s)", host_cfg.workers.len());
+        create_worker_units(conn, &privilege, service_name, dest, host_cfg)?;
+    }
+    remove_stale_worker_units(conn, &privilege, service_name, host_cfg)?;
 
     // 5. Configure nginx if domain specified
     if let Some(domain) = &host_cfg.domain {
         if let Some(port) = host_cfg.port {
+            // A swap deploy keeps nginx on the live release until the new one is healthy.
+            let port = match &swap {
+                Some(_) => nginx_upstream_port(conn, domain)?.unwrap_or(port),
+                None => port,
+            };
             println!("==> Configuring nginx for {}", domain);
-            setup_nginx(conn, domain, port, host_cfg.ssl)?;
+            setup_nginx(conn, &privilege, domain, port, host_cfg.ssl)?;
         }
     }
 
-    // 6. Restart service
-    println!("==> Starting service...");
-    ssh_run(conn, &format!("systemctl restart {}", service_name))?;
+    // 6. Restart service, or bring the new release up next to the old one
+    if let Some(plan) = &swap {
+        swap_release(conn, &privilege, plan)?;
+    } else {
+        println!("==> Starting service...");
+        ssh_run_privileged(
+            conn,
+            &privilege,
+            &format!("systemctl restart {}", service_name),
+        )?;
+    }
+    // Long-running workers pick up the new release; timers re-arm with their schedule.
+    let workers = worker_controls(service_name, host_cfg);
+    if !workers.is_empty() {
+        println!("==> Restarting workers...");
+        ssh_run_privileged(
+            conn,
+            &privilege,
+            &format!("systemctl restart {}", workers.join(" ")),
+        )?;
+    }
 
     println!("\n✓ Deployed successfully!");
+    println!("  Audit: f deploy audit {}", audit.id);
+    audit.finish(true);
     if let Some(domain) = &host_cfg.domain {
         let scheme = if host_cfg.ssl { "https" } else { "http" };
         println!("  URL: {}://{}", scheme, domain);
@@ -1050,6 +1338,258 @@ fn deploy_host(
     Ok(())
 }
 
+/// Print the provisioning plan, confirm it, and run each step as root.
+fn provision_host(
+    conn: &HostConnection,
+    privilege: &Privilege,
+    host_cfg: &HostConfig,
+    dest: &str,
+    assume_yes: bool,
+) -> Result<()> {
+    if matches!(privilege, Privilege::Direct) && conn.user != "root" {
+        bail!("--provision needs root: log in as root or set [host] sudo = true");
+    }
+    let plan = ProvisionPlan::new(host_cfg, dest, &conn.user)?;
+    println!();
+    plan.print(&conn.ssh_target());
+    if !assume_yes && std::io::stdin().is_terminal() && !prompt_yes_no("Apply this plan?", false)? {
+        bail!("Provisioning cancelled");
+    }
+    for step in &plan.steps {
+        println!("==> {}", step.summary);
+        ssh_run_privileged(conn, privilege, &step.command)?;
+    }
+    Ok(())
+}
+
+/// Ports, unit names, and health check for a "swap" host deploy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SwapPlan {
+    service: String,
+    domain: String,
+    port: u16,
+    alt_port: u16,
+    health_path: String,
+    health_timeout: u64,
+}
+
+/// Time nginx gets to finish requests already proxied to the old release.
+const SWAP_DRAIN_SECS: u64 = 5;
+
+impl SwapPlan {
+    fn from_config(service: &str, cfg: &HostConfig) -> Result<Self> {
+        let domain = cfg
+            .domain
+            .clone()
+            .context("strategy = \"swap\" needs [host] domain (nginx switches between ports)")?;
+        let port = cfg.port.context("strategy = \"swap\" needs [host] port")?;
+        let alt_port = match cfg.alt_port {
+            Some(alt) => alt,
+            None => port
+                .checked_add(1)
+                .context("[host] port has no room for alt_port")?,
+        };
+        if alt_port == port {
+            bail!("[host] alt_port must differ from port ({port})");
+        }
+        let health_path = cfg.health_path.as_deref().unwrap_or("/");
+        let health_path = if health_path.starts_with('/') {
+            health_path.to_string()
+        } else {
+            format!("/{health_path}")
+        };
+        Ok(Self {
+            service: service.to_string(),
+            domain,
+            port,
+            alt_port,
+            health_path,
+            health_timeout: cfg.health_timeout.unwrap_or(30).max(1),
+        })
+    }
+
+    /// The idle port, given the one nginx currently proxies to.
+    fn next_port(&self, serving: Option<u16>) -> u16 {
+        if serving == Some(self.alt_port) {
+            self.port
+        } else {
+            self.alt_port
+        }
+    }
+
+    fn other_port(&self, port: u16) -> u16 {
+        if port == self.port {
+            self.alt_port
+        } else {
+            self.port
+        }
+    }
+
+    /// Instance of the `{service}@.service` template that listens on `port`.
+    fn unit(&self, port: u16) -> String {
+        format!("{}@{}", self.service, port)
+    }
+}
+
+/// Start the new release on the idle port, wait for it to pass its health check, point
+/// nginx at it, then stop the old release. If nginx rejects the switch or the site is
+/// unhealthy afterwards, nginx is pointed back at the old release and the new one stopped.
+fn swap_release(conn: &HostConnection, privilege: &Privilege, plan: &SwapPlan) -> Result<()> {
+    let serving = nginx_upstream_port(conn, &plan.domain)?;
+    let next = plan.next_port(serving);
+    let new_unit = plan.unit(next);
+    let stop_new = || {
+        let _ = ssh_run_privileged(conn, privilege, &format!("systemctl stop {new_unit}"));
+    };
+
+    println!("==> Starting {} on port {}...", new_unit, next);
+    ssh_run_privileged(conn, privilege, &format!("systemctl restart {new_unit}"))?;
+
+    let direct_url = format!("http://127.0.0.1:{}{}", next, plan.health_path);
+    println!("==> Waiting for {}...", direct_url);
+    if !remote_health_check(conn, &[direct_url.as_str()], plan.health_timeout)? {
+        stop_new();
+        bail!(
+            "{} did not become healthy within {}s; the previous release is still serving",
+            new_unit,
+            plan.health_timeout
+        );
+    }
+
+    println!("==> Switching nginx to port {}...", next);
+    let host_header = format!("Host: {}", plan.domain);
+    let proxied_url = format!("http://127.0.0.1{}", plan.health_path);
+    let switched = point_nginx_at(conn, privilege, &plan.domain, next).and_then(|()| {
+        if remote_health_check(conn, &["-H", &host_header, &proxied_url], 10)? {
+            Ok(())
+        } else {
+            bail!(
+                "{} is not healthy through nginx after the switch",
+                plan.domain
+            )
+        }
+    });
+    if let Err(err) = switched {
+        if let Some(previous) = serving {
+            eprintln!("⚠ Switch failed, pointing nginx back at port {}", previous);
+            if let Err(revert_err) = point_nginx_at(conn, privilege, &plan.domain, previous) {
+                eprintln!("⚠ Failed to revert nginx: {revert_err:#}");
+            }
+        }
+        stop_new();
+        return Err(err.context("zero-downtime switch failed; reverted to the previous release"));
+    }
+
+    // Stop both the other instance and a pre-swap single service, whichever exists.
+    println!("==> Stopping previous release...");
+    let old_unit = plan.unit(plan.other_port(next));
+    ssh_run_privileged(
+        conn,
+        privilege,
+        &format!(
+            "systemctl enable {new_unit} && sleep {SWAP_DRAIN_SECS} && {{ \
+            systemctl disable --now {old_unit} 2>/dev/null; \
+            systemctl disable --now {} 2>/dev/null; true; }}",
+            plan.service
+        ),
+    )?;
+    Ok(())
+}
+
+/// Poll `curl <args>` on the host once a second until it succeeds or `timeout_secs` pass.
+fn remote_health_check(
+    conn: &HostConnection,
+    curl_args: &[&str],
+    timeout_secs: u64,
+) -> Result<bool> {
+    let args = shell_words::join(curl_args);
+    ssh_succeeds(
+        conn,
+        &format!(
+            "for _ in $(seq 1 {timeout_secs}); do \
+            curl -fsS -o /dev/null --max-time 2 {args} && exit 0; sleep 1; done; exit 1"
+        ),
+    )
+}
+
+fn nginx_site_path(domain: &str) -> String {
+    format!("/etc/nginx/sites-available/{domain}")
+}
+
+/// Port the nginx site for `domain` currently proxies to, if the site exists.
+fn nginx_upstream_port(conn: &HostConnection, domain: &str) -> Result<Option<u16>> {
+    let output = ssh_capture(
+        conn,
+        &format!(
+            "grep -o 'proxy_pass http://127.0.0.1:[0-9]*' {} 2>/dev/null | head -n 1",
+            nginx_site_path(domain)
+        ),
+    )?;
+    Ok(parse_upstream_port(&output))
+}
+
+fn parse_upstream_port(output: &str) -> Option<u16> {
+    output
+        .trim()
+        .strip_prefix("proxy_pass http://127.0.0.1:")?
+        .parse()
+        .ok()
+}
+
+/// Rewrite the site's upstream port in place (keeping certbot's edits) and reload nginx.
+fn point_nginx_at(
+    conn: &HostConnection,
+    privilege: &Privilege,
+    domain: &str,
+    port: u16,
+) -> Result<()> {
+    ssh_run_privileged(
+        conn,
+        privilege,
+        &format!(
+            "sed -i 's#proxy_pass http://127.0.0.1:[0-9]*;#proxy_pass http://127.0.0.1:{port};#' {} && \
+            nginx -t && systemctl reload nginx",
+            nginx_site_path(domain)
+        ),
+    )
+}
+
+/// Unit that runs the live release: the serving `{service}@{port}` instance for swap
+/// deploys, otherwise the service itself.
+fn live_service_unit(
+    conn: &HostConnection,
+    host_cfg: Option<&HostConfig>,
+    service: &str,
+) -> Result<String> {
+    if let Some(cfg) = host_cfg
+        && host_strategy(cfg)? == HostStrategy::Swap
+        && let Some(domain) = cfg.domain.as_deref()
+        && let Some(port) = nginx_upstream_port(conn, domain)?
+    {
+        return Ok(format!("{service}@{port}"));
+    }
+    Ok(service.to_string())
+}
+
+/// The unit of each `[[host.workers]]` entry that deploys and restarts act on (the timer of
+/// scheduled jobs).
+fn worker_controls(service: &str, host_cfg: &HostConfig) -> Vec<String> {
+    deploy_workers::worker_units(service, &host_cfg.workers)
+        .iter()
+        .map(|units| units.control().to_string())
+        .collect()
+}
+
+/// `-u` arguments selecting the logs of every worker unit.
+fn worker_journal_units(service: &str, host_cfg: Option<&HostConfig>) -> String {
+    host_cfg
+        .map(|cfg| deploy_workers::worker_units(service, &cfg.workers))
+        .unwrap_or_default()
+        .iter()
+        .map(|units| format!(" -u {}", units.service))
+        .collect()
+}
+
 /// Deploy to Cloudflare Workers.
 fn deploy_cloudflare(
     project_root: &Path,
@@ -1094,7 +1634,9 @@ fn deploy_cloudflare(
             for key in &keys {
                 if let Some(value) = cf_cfg.env_defaults.get(key) {
                     if !value.trim().is_empty() {
-                        cloud_vars.insert(key.clone(), value.clone());
+                        let value = crate::config_interp::interpolate(value)
+                            .with_context(|| format!("[cloudflare] env_defaults.{key}"))?;
+                        cloud_vars.insert(key.clone(), value);
                     }
                 }
             }
@@ -1173,6 +1715,7 @@ fn deploy_cloudflare(
     if use_env_store && !cloud_vars.is_empty() {
         deploy_cmd.envs(&cloud_vars);
     }
+    runs::set_run_env(&mut deploy_cmd);
 
     let status = deploy_cmd.status()?;
 
@@ -1602,10 +2145,7 @@ fn deploy_railway(project_root: &Path, config: Option<&Config>) -> Result<()> {
         .and_then(|c| c.railway.as_ref())
         .unwrap_or(&default_rail);
 
-    // Check railway CLI
-    if which::which("railway").is_err() {
-        bail!("Railway CLI not found. Install: npm install -g @railway/cli");
-    }
+    ensure_railway_cli()?;
 
     // Link project if specified
     if let (Some(project), Some(env)) = (&rail_cfg.project, &rail_cfg.environment) {
@@ -1619,12 +2159,24 @@ fn deploy_railway(project_root: &Path, config: Option<&Config>) -> Result<()> {
         }
     }
 
-    // Set env vars from file
-    if let Some(env_file) = &rail_cfg.env_file {
+    // Set env vars from the env store, or from file
+    let source = rail_cfg.env_source.as_deref();
+    if is_cloud_source(source) || is_flow_source(source) {
+        let vars = fetch_railway_env_vars(rail_cfg)?;
+        if vars.is_empty() {
+            eprintln!(
+                "⚠ No env vars found in env store for environment '{}'",
+                railway_environment(rail_cfg)
+            );
+        } else {
+            println!("==> Syncing {} env var(s) from env store...", vars.len());
+            set_railway_variables(project_root, &vars)?;
+        }
+    } else if let Some(env_file) = &rail_cfg.env_file {
         let env_path = project_root.join(env_file);
         if env_path.exists() {
             println!("==> Setting environment variables...");
-            set_railway_env(&env_path)?;
+            set_railway_env(project_root, &env_path)?;
         }
     }
 
@@ -1646,81 +2198,360 @@ fn deploy_railway(project_root: &Path, config: Option<&Config>) -> Result<()> {
     Ok(())
 }
 
-/// Show deployment status.
-fn show_status(_project_root: &Path, config: Option<&Config>) -> Result<()> {
-    let deploy_config = load_deploy_config()?;
+const RAILWAY_GRAPHQL_URL: &str = "https://backboard.railway.com/graphql/v2";
+const DEFAULT_RAILWAY_TOKEN_KEY: &str = "RAILWAY_API_TOKEN";
 
-    println!("Deployment Status\n");
+const RAILWAY_PROJECT_QUERY: &str = "query project($id: String!) { project(id: $id) { name services { edges { node { id name } } } environments { edges { node { id name } } } } }";
+const RAILWAY_DEPLOYMENTS_QUERY: &str = "query deployments($input: DeploymentListInput!) { deployments(input: $input, first: 1) { edges { node { id status createdAt staticUrl } } } }";
 
-    // Host status
-    if let Some(conn) = &deploy_config.host {
-        println!("Host: {}@{}:{}", conn.user, conn.host, conn.port);
-        if let Some(cfg) = config.and_then(|c| c.host.as_ref()) {
-            if let Some(service) = &cfg.service {
-                let output = ssh_capture(
-                    conn,
-                    &format!(
-                        "systemctl is-active {} 2>/dev/null || echo inactive",
-                        service
-                    ),
-                )?;
-                println!("  Service '{}': {}", service, output.trim());
-            }
-        }
-    } else {
-        println!("Host: not configured");
+fn ensure_railway_cli() -> Result<()> {
+    if which::which("railway").is_err() {
+        bail!("Railway CLI not found. Install: npm install -g @railway/cli");
     }
-
     Ok(())
 }
 
-/// Show deployment logs.
-fn show_logs(
-    project_root: &Path,
-    config: Option<&Config>,
-    follow: bool,
-    since_deploy: bool,
-    all: bool,
-    lines: usize,
-) -> Result<()> {
-    if let Some(cf_cfg) = config.and_then(|c| c.cloudflare.as_ref()) {
-        return show_cloudflare_logs(project_root, cf_cfg, follow, lines);
-    }
+fn railway_environment(rail_cfg: &RailwayConfig) -> &str {
+    rail_cfg.environment.as_deref().unwrap_or("production")
+}
 
-    let deploy_config = load_deploy_config()?;
-    let conn = deploy_config.host.as_ref().context("No host configured")?;
+/// Sync env store vars to the linked Railway service (`f env apply`).
+pub fn apply_railway_env(project_root: &Path, config: Option<&Config>) -> Result<()> {
+    let rail_cfg = config
+        .and_then(|c| c.railway.as_ref())
+        .context("No [railway] section in flow.toml")?;
+    let source = rail_cfg.env_source.as_deref();
+    if !is_cloud_source(source) && !is_flow_source(source) {
+        bail!("railway.env_source must be set to \"cloud\", \"flow\", or \"local\" to apply envs");
+    }
+    ensure_railway_cli()?;
 
-    let service = config
-        .and_then(|c| c.host.as_ref())
-        .and_then(|h| h.service.as_ref())
-        .context("No service name in [host] config")?;
+    let vars = fetch_railway_env_vars(rail_cfg)?;
+    if vars.is_empty() {
+        bail!(
+            "No env vars found in env store for environment '{}'",
+            railway_environment(rail_cfg)
+        );
+    }
+    println!("==> Applying {} env var(s) from env store...", vars.len());
+    set_railway_variables(project_root, &vars)
+}
 
-    let use_since_deploy = since_deploy && !all;
-    let since_flag = if use_since_deploy {
-        let state = load_deploy_log_state(project_root);
-        if let Some(ts) = state.last_deploy_unix {
-            format!("--since '@{}'", ts)
-        } else {
-            String::new()
-        }
+fn fetch_railway_env_vars(rail_cfg: &RailwayConfig) -> Result<HashMap<String, String>> {
+    let fetch =
+        || crate::env::fetch_project_env_vars(railway_environment(rail_cfg), &rail_cfg.env_keys);
+    if rail_cfg.env_source.as_deref() == Some("local") {
+        with_local_env_backend(fetch)
     } else {
-        String::new()
-    };
-
-    let follow_flag = if follow { "-f" } else { "" };
-    let cmd = format!(
-        "journalctl -u {} -n {} {} {} --no-pager",
-        service, lines, follow_flag, since_flag
-    );
-
-    ssh_run(conn, &cmd)?;
-    Ok(())
+        fetch()
+    }
 }
 
-fn show_cloudflare_logs(
-    project_root: &Path,
-    cf_cfg: &CloudflareConfig,
-    follow: bool,
+/// `railway variables set KEY=VALUE` for each var, in key order.
+fn set_railway_variables(project_root: &Path, vars: &HashMap<String, String>) -> Result<()> {
+    let mut keys: Vec<_> = vars.keys().collect();
+    keys.sort();
+    for key in keys {
+        let status = Command::new("railway")
+            .args(["variables", "set", &format!("{}={}", key, vars[key])])
+            .current_dir(project_root)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+        if !status.success() {
+            bail!("Failed to set Railway variable {}", key);
+        }
+    }
+    Ok(())
+}
+
+/// Railway API token from the process env, else the personal env store.
+fn railway_api_token(rail_cfg: &RailwayConfig) -> Result<String> {
+    let key = rail_cfg
+        .token_key
+        .as_deref()
+        .unwrap_or(DEFAULT_RAILWAY_TOKEN_KEY);
+    if let Ok(token) = std::env::var(key)
+        && !token.trim().is_empty()
+    {
+        return Ok(token);
+    }
+    let vars = crate::env::fetch_personal_env_vars(&[key.to_string()])
+        .with_context(|| format!("failed to read {} from the env store", key))?;
+    vars.get(key)
+        .filter(|token| !token.trim().is_empty())
+        .cloned()
+        .with_context(|| {
+            format!(
+                "Railway API token missing. Store one with `f env set --personal {}=<token>`.",
+                key
+            )
+        })
+}
+
+fn railway_graphql(client: &Client, token: &str, query: &str, variables: Value) -> Result<Value> {
+    let resp = client
+        .post(RAILWAY_GRAPHQL_URL)
+        .bearer_auth(token)
+        .json(&serde_json::json!({ "query": query, "variables": variables }))
+        .send()
+        .context("failed to query the Railway API")?;
+    let status = resp.status();
+    let json: Value = resp
+        .json()
+        .context("failed to parse Railway API response")?;
+    railway_api_check(&json)?;
+    if !status.is_success() {
+        bail!("Railway API returned {}", status);
+    }
+    Ok(json["data"].clone())
+}
+
+fn railway_api_check(json: &Value) -> Result<()> {
+    let Some(errors) = json["errors"]
+        .as_array()
+        .filter(|errors| !errors.is_empty())
+    else {
+        return Ok(());
+    };
+    let messages: Vec<&str> = errors
+        .iter()
+        .filter_map(|err| err["message"].as_str())
+        .collect();
+    bail!("Railway API error: {}", messages.join("; "))
+}
+
+/// `(id, name)` of each node in a GraphQL connection (`{ edges: [{ node: { .. } }] }`).
+fn railway_nodes(connection: &Value) -> Vec<(String, String)> {
+    connection["edges"]
+        .as_array()
+        .map(|edges| {
+            edges
+                .iter()
+                .filter_map(|edge| {
+                    let node = &edge["node"];
+                    Some((
+                        node["id"].as_str()?.to_string(),
+                        node["name"].as_str()?.to_string(),
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Latest deployment of each service (or just `[railway] service`) in the configured environment.
+fn show_railway_status(rail_cfg: &RailwayConfig) -> Result<()> {
+    let project = rail_cfg
+        .project
+        .as_deref()
+        .context("Set [railway] project to show Railway status")?;
+    let token = railway_api_token(rail_cfg)?;
+    let client = Client::builder()
+        .timeout(Duration::from_secs(20))
+        .build()
+        .context("failed to build Railway API client")?;
+
+    let data = railway_graphql(
+        &client,
+        &token,
+        RAILWAY_PROJECT_QUERY,
+        serde_json::json!({ "id": project }),
+    )?;
+    let project_json = &data["project"];
+    let environment = railway_environment(rail_cfg);
+    let environment_id = railway_nodes(&project_json["environments"])
+        .into_iter()
+        .find(|(_, name)| name == environment)
+        .map(|(id, _)| id)
+        .with_context(|| format!("Railway environment '{}' not found", environment))?;
+    let mut services = railway_nodes(&project_json["services"]);
+    if let Some(service) = rail_cfg.service.as_deref() {
+        services.retain(|(_, name)| name == service);
+        if services.is_empty() {
+            bail!("Railway service '{}' not found", service);
+        }
+    }
+
+    println!(
+        "Railway: {} ({})",
+        project_json["name"].as_str().unwrap_or(project),
+        environment
+    );
+    for (service_id, name) in services {
+        let data = railway_graphql(
+            &client,
+            &token,
+            RAILWAY_DEPLOYMENTS_QUERY,
+            serde_json::json!({
+                "input": {
+                    "projectId": project,
+                    "serviceId": service_id,
+                    "environmentId": environment_id,
+                }
+            }),
+        )?;
+        let deployment = &data["deployments"]["edges"][0]["node"];
+        let Some(status) = deployment["status"].as_str() else {
+            println!("  Service '{}': no deployments", name);
+            continue;
+        };
+        let created = deployment["createdAt"].as_str().unwrap_or("?");
+        match deployment["staticUrl"]
+            .as_str()
+            .filter(|url| !url.is_empty())
+        {
+            Some(url) => println!(
+                "  Service '{}': {} (deployed {}) https://{}",
+                name, status, created, url
+            ),
+            None => println!("  Service '{}': {} (deployed {})", name, status, created),
+        }
+    }
+    Ok(())
+}
+
+/// Show deployment status.
+fn show_status(_project_root: &Path, config: Option<&Config>) -> Result<()> {
+    let conn = host_connection(config.and_then(|c| c.host.as_ref()))?;
+
+    println!("Deployment Status\n");
+
+    // Host status
+    if let Some(conn) = &conn {
+        println!("Host: {}@{}:{}", conn.user, conn.host, conn.port);
+        if let Some(cfg) = config.and_then(|c| c.host.as_ref()) {
+            if let Some(service) = &cfg.service {
+                let unit = live_service_unit(conn, Some(cfg), service)?;
+                let output = ssh_capture(
+                    conn,
+                    &format!("systemctl is-active {} 2>/dev/null || echo inactive", unit),
+                )?;
+                println!("  Service '{}': {}", unit, output.trim());
+                for units in deploy_workers::worker_units(service, &cfg.workers) {
+                    let output = ssh_capture(
+                        conn,
+                        &format!(
+                            "systemctl is-active {} 2>/dev/null || echo inactive",
+                            units.control()
+                        ),
+                    )?;
+                    match &units.timer {
+                        Some(timer) => println!("  Job '{}': {}", timer, output.trim()),
+                        None => println!("  Worker '{}': {}", units.service, output.trim()),
+                    }
+                }
+            }
+        }
+    } else if config.and_then(|c| c.railway.as_ref()).is_none() {
+        println!("Host: not configured");
+    }
+
+    if let Some(rail_cfg) = config.and_then(|c| c.railway.as_ref()) {
+        if conn.is_some() {
+            println!();
+        }
+        show_railway_status(rail_cfg)?;
+    }
+
+    Ok(())
+}
+
+/// Show deployment logs.
+fn show_logs(
+    project_root: &Path,
+    config: Option<&Config>,
+    follow: bool,
+    since_deploy: bool,
+    all: bool,
+    lines: usize,
+) -> Result<()> {
+    if let Some(cf_cfg) = config.and_then(|c| c.cloudflare.as_ref()) {
+        return show_cloudflare_logs(project_root, cf_cfg, follow, lines);
+    }
+    if let Some(rail_cfg) = config
+        .filter(|c| c.host.is_none())
+        .and_then(|c| c.railway.as_ref())
+    {
+        return show_railway_logs(project_root, rail_cfg, follow, lines);
+    }
+
+    let conn =
+        &host_connection(config.and_then(|c| c.host.as_ref()))?.context("No host configured")?;
+
+    let service = config
+        .and_then(|c| c.host.as_ref())
+        .and_then(|h| h.service.as_ref())
+        .context("No service name in [host] config")?;
+
+    let use_since_deploy = since_deploy && !all;
+    let since_flag = if use_since_deploy {
+        let state = load_deploy_log_state(project_root);
+        if let Some(ts) = state.last_deploy_unix {
+            format!("--since '@{}'", ts)
+        } else {
+            String::new()
+        }
+    } else {
+        String::new()
+    };
+
+    // Swap deploys alternate between two template instances; show both.
+    let host_cfg = config.and_then(|c| c.host.as_ref());
+    let unit = match host_cfg.map(host_strategy).transpose()? {
+        Some(HostStrategy::Swap) => format!("'{}@*'", service),
+        _ => service.to_string(),
+    };
+    let follow_flag = if follow { "-f" } else { "" };
+    let cmd = format!(
+        "journalctl -u {}{} -n {} {} {} --no-pager",
+        unit,
+        worker_journal_units(service, host_cfg),
+        lines,
+        follow_flag,
+        since_flag
+    );
+
+    let privilege = Privilege::detect(conn, host_cfg)?;
+    ssh_run_privileged(conn, &privilege, &cmd)?;
+    Ok(())
+}
+
+/// `ssh … journalctl -f` for the `[host]` service, used by `f tail`: new entries only, each
+/// starting with its unix timestamp (`-o short-unix`). Returns the service name with the
+/// command, or `None` without a service or a host from `f deploy set-host`. The command runs
+/// without a terminal, so hosts with `sudo = true` need NOPASSWD.
+pub(crate) fn journal_follow_command(config: &Config) -> Result<Option<(String, Command)>> {
+    let Some(host_cfg) = config.host.as_ref() else {
+        return Ok(None);
+    };
+    let Some(service) = host_cfg.service.as_deref() else {
+        return Ok(None);
+    };
+    let Some(conn) = &host_connection(Some(host_cfg))? else {
+        return Ok(None);
+    };
+    let unit = match host_strategy(host_cfg)? {
+        HostStrategy::Swap => format!("'{}@*'", service),
+        HostStrategy::Restart => service.to_string(),
+    };
+    let mut remote = format!(
+        "journalctl -u {}{} -f -n 0 -o short-unix --no-pager",
+        unit,
+        worker_journal_units(service, Some(host_cfg))
+    );
+    if host_cfg.sudo && conn.user != "root" {
+        remote = format!("sudo -n {}", remote);
+    }
+    let mut cmd = ssh_command(conn);
+    cmd.arg(conn.ssh_target()).arg(remote);
+    Ok(Some((service.to_string(), cmd)))
+}
+
+fn show_cloudflare_logs(
+    project_root: &Path,
+    cf_cfg: &CloudflareConfig,
+    follow: bool,
     lines: usize,
 ) -> Result<()> {
     let worker_path = cf_cfg
@@ -1754,32 +2585,114 @@ fn show_cloudflare_logs(
     Ok(())
 }
 
+fn show_railway_logs(
+    project_root: &Path,
+    rail_cfg: &RailwayConfig,
+    follow: bool,
+    lines: usize,
+) -> Result<()> {
+    ensure_railway_cli()?;
+
+    if !follow {
+        eprintln!("Note: railway logs streams the latest deployment until you stop it (Ctrl+C).");
+        let _ = lines;
+    }
+
+    let mut cmd = Command::new("railway");
+    cmd.arg("logs");
+    if let Some(service) = rail_cfg.service.as_deref() {
+        cmd.args(["--service", service]);
+    }
+    if let Some(env) = rail_cfg.environment.as_deref() {
+        cmd.args(["--environment", env]);
+    }
+
+    let status = cmd
+        .current_dir(project_root)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()?;
+
+    if !status.success() {
+        bail!("Railway log stream failed");
+    }
+
+    Ok(())
+}
+
 /// Restart the deployed service.
-fn restart_service(_project_root: &Path, config: Option<&Config>) -> Result<()> {
-    let deploy_config = load_deploy_config()?;
-    let conn = deploy_config.host.as_ref().context("No host configured")?;
+fn restart_service(project_root: &Path, config: Option<&Config>) -> Result<()> {
+    let conn =
+        &host_connection(config.and_then(|c| c.host.as_ref()))?.context("No host configured")?;
     let service = config
         .and_then(|c| c.host.as_ref())
         .and_then(|h| h.service.as_ref())
         .context("No service name")?;
 
-    println!("Restarting {}...", service);
-    ssh_run(conn, &format!("systemctl restart {}", service))?;
+    let host_cfg = config.and_then(|c| c.host.as_ref());
+    let audit = deploy_audit::begin(
+        project_root,
+        "deploy:restart",
+        &conn.ssh_target(),
+        host_cfg
+            .map(|h| h.allow_destructive.as_slice())
+            .unwrap_or_default(),
+        false,
+    )?;
+    let privilege = Privilege::detect(conn, host_cfg)?;
+    let mut units = vec![live_service_unit(conn, host_cfg, service)?];
+    if let Some(cfg) = host_cfg {
+        units.extend(worker_controls(service, cfg));
+    }
+    let units = units.join(" ");
+    println!("Restarting {}...", units);
+    ssh_run_privileged(conn, &privilege, &format!("systemctl restart {}", units))?;
+    audit.finish(true);
     println!("✓ Restarted");
     Ok(())
 }
 
 /// Stop the deployed service.
-fn stop_service(_project_root: &Path, config: Option<&Config>) -> Result<()> {
-    let deploy_config = load_deploy_config()?;
-    let conn = deploy_config.host.as_ref().context("No host configured")?;
+fn stop_service(project_root: &Path, config: Option<&Config>, assume_yes: bool) -> Result<()> {
+    let conn =
+        &host_connection(config.and_then(|c| c.host.as_ref()))?.context("No host configured")?;
     let service = config
         .and_then(|c| c.host.as_ref())
         .and_then(|h| h.service.as_ref())
         .context("No service name")?;
 
-    println!("Stopping {}...", service);
-    ssh_run(conn, &format!("systemctl stop {}", service))?;
+    let host_cfg = config.and_then(|c| c.host.as_ref());
+    let audit = deploy_audit::begin(
+        project_root,
+        "deploy:stop",
+        &conn.ssh_target(),
+        host_cfg
+            .map(|h| h.allow_destructive.as_slice())
+            .unwrap_or_default(),
+        assume_yes,
+    )?;
+    // Timers are stopped before their jobs so a job can't fire in between.
+    let mut units = vec![live_service_unit(conn, host_cfg, service)?];
+    for worker in host_cfg
+        .map(|cfg| deploy_workers::worker_units(service, &cfg.workers))
+        .unwrap_or_default()
+    {
+        units.extend(worker.timer);
+        units.push(worker.service);
+    }
+    let units = units.join(" ");
+    deploy_audit::confirm_destructive(
+        "systemctl stop",
+        &format!(
+            "This stops {} on {} until the next deploy or restart.",
+            units, conn.host
+        ),
+    )?;
+    let privilege = Privilege::detect(conn, host_cfg)?;
+    println!("Stopping {}...", units);
+    ssh_run_privileged(conn, &privilege, &format!("systemctl stop {}", units))?;
+    audit.finish(true);
     println!("✓ Stopped");
     Ok(())
 }
@@ -1790,8 +2703,8 @@ fn open_shell() -> Result<()> {
     let conn = deploy_config.host.as_ref().context("No host configured")?;
 
     println!("Connecting to {}...", conn.ssh_target());
-    let status = Command::new("ssh")
-        .args(["-p", &conn.port.to_string(), &conn.ssh_target()])
+    let status = ssh_command(conn)
+        .arg(conn.ssh_target())
         .stdin(Stdio::inherit())
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
@@ -1831,22 +2744,159 @@ fn show_host() -> Result<()> {
 // SSH/rsync helpers
 // ─────────────────────────────────────────────────────────────
 
+/// Seconds an idle shared connection stays open after its last use.
+const SSH_CONTROL_PERSIST_SECS: u32 = 120;
+
+/// Directory for ControlMaster sockets. Kept short: socket paths are limited to ~104 bytes.
+fn ssh_control_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("flow")
+        .join("ssh")
+}
+
+/// Options shared by ssh, scp, and rsync so every step reuses one authenticated connection.
+fn ssh_options() -> Vec<String> {
+    let dir = ssh_control_dir();
+    if fs::create_dir_all(&dir).is_ok() {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(&dir, fs::Permissions::from_mode(0o700));
+    }
+    vec![
+        "-o".to_string(),
+        "StrictHostKeyChecking=accept-new".to_string(),
+        "-o".to_string(),
+        "ControlMaster=auto".to_string(),
+        "-o".to_string(),
+        format!("ControlPath={}", dir.join("%C").display()),
+        "-o".to_string(),
+        format!("ControlPersist={SSH_CONTROL_PERSIST_SECS}"),
+    ]
+}
+
+/// `ssh -p <port> <options>` with the shared connection options.
+fn ssh_command(conn: &HostConnection) -> Command {
+    let mut cmd = Command::new("ssh");
+    cmd.args(["-p", &conn.port.to_string()]).args(ssh_options());
+    cmd
+}
+
+/// A ControlMaster connection opened once per deploy. Authentication happens here, and
+/// every later ssh/scp/rsync step multiplexes over it. Dropping it closes the master
+/// unless it was already running before this deploy.
+struct SshMaster {
+    conn: HostConnection,
+    owned: bool,
+}
+
+impl SshMaster {
+    fn open(conn: &HostConnection) -> Result<Self> {
+        let running = ssh_command(conn)
+            .args(["-O", "check", &conn.ssh_target()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false);
+        if !running {
+            let started = Instant::now();
+            let status = ssh_command(conn)
+                .args(["-N", "-f", &conn.ssh_target()])
+                .stdin(Stdio::inherit())
+                .stdout(Stdio::inherit())
+                .stderr(Stdio::inherit())
+                .status()
+                .context("Failed to run SSH")?;
+            deploy_audit::record_command(
+                "ssh",
+                "ssh -N -f (open shared connection)",
+                started,
+                status.code(),
+            );
+            if !status.success() {
+                bail!("SSH connection to {} failed", conn.ssh_target());
+            }
+        }
+        Ok(Self {
+            conn: conn.clone(),
+            owned: !running,
+        })
+    }
+}
+
+impl Drop for SshMaster {
+    fn drop(&mut self) {
+        if !self.owned {
+            return;
+        }
+        let _ = ssh_command(&self.conn)
+            .args(["-O", "exit", &self.conn.ssh_target()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+}
+
+/// How steps that need root (systemd, /etc, certbot) run on the host.
+enum Privilege {
+    /// Logged in as root, or `[host] sudo` is off: run commands as-is.
+    Direct,
+    /// `sudo -n` works (NOPASSWD).
+    Sudo,
+    /// Password asked once locally and fed to `sudo -k -S` on each step. `-k` makes sudo
+    /// read it even when credentials are cached, so it never reaches the command's stdin.
+    SudoPassword(String),
+}
+
+impl Privilege {
+    /// Resolve how to escalate; prompts for the sudo password when NOPASSWD is not set up.
+    fn detect(conn: &HostConnection, host_cfg: Option<&HostConfig>) -> Result<Self> {
+        let wants_sudo = host_cfg.map(|cfg| cfg.sudo).unwrap_or(false);
+        if !wants_sudo || conn.user == "root" {
+            return Ok(Privilege::Direct);
+        }
+        if ssh_succeeds(conn, "sudo -n true")? {
+            return Ok(Privilege::Sudo);
+        }
+        let password = prompt_secret(&format!("sudo password for {}: ", conn.ssh_target()))?;
+        let privilege = Privilege::SudoPassword(password);
+        if ssh_run_with_input(conn, &privilege.wrap("true"), privilege.input()).is_err() {
+            bail!("sudo on {} rejected the password", conn.ssh_target());
+        }
+        Ok(privilege)
+    }
+
+    /// Remote command line that runs `cmd` as root.
+    fn wrap(&self, cmd: &str) -> String {
+        let quoted = shell_words::quote(cmd);
+        match self {
+            Privilege::Direct => cmd.to_string(),
+            Privilege::Sudo => format!("sudo -n sh -c {quoted}"),
+            Privilege::SudoPassword(_) => format!("sudo -k -S -p '' sh -c {quoted}"),
+        }
+    }
+
+    /// Bytes to send on the remote command's stdin.
+    fn input(&self) -> Option<String> {
+        match self {
+            Privilege::SudoPassword(password) => Some(format!("{password}\n")),
+            _ => None,
+        }
+    }
+}
+
 /// Run SSH command with inherited stdio.
 fn ssh_run(conn: &HostConnection, cmd: &str) -> Result<()> {
-    let status = Command::new("ssh")
-        .args([
-            "-p",
-            &conn.port.to_string(),
-            "-o",
-            "StrictHostKeyChecking=accept-new",
-            &conn.ssh_target(),
-            cmd,
-        ])
+    let started = Instant::now();
+    let status = ssh_command(conn)
+        .arg(conn.ssh_target())
+        .arg(cmd)
         .stdin(Stdio::inherit())
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .status()
         .context("Failed to run SSH")?;
+    deploy_audit::record_command("ssh", cmd, started, status.code());
 
     if !status.success() {
         bail!("SSH command failed: {}", cmd);
@@ -1854,50 +2904,130 @@ fn ssh_run(conn: &HostConnection, cmd: &str) -> Result<()> {
     Ok(())
 }
 
+/// Run an SSH command as root according to `privilege`.
+fn ssh_run_privileged(conn: &HostConnection, privilege: &Privilege, cmd: &str) -> Result<()> {
+    ssh_run_with_input(conn, &privilege.wrap(cmd), privilege.input())
+        .with_context(|| format!("SSH command failed: {}", cmd))
+}
+
+/// Run SSH command with output inherited and `input` (if any) written to its stdin.
+fn ssh_run_with_input(conn: &HostConnection, cmd: &str, input: Option<String>) -> Result<()> {
+    let Some(input) = input else {
+        return ssh_run(conn, cmd);
+    };
+    let started = Instant::now();
+    let mut child = ssh_command(conn)
+        .arg(conn.ssh_target())
+        .arg(cmd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .context("Failed to run SSH")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes())?;
+    }
+    let status = child.wait().context("Failed to run SSH")?;
+    deploy_audit::record_command("ssh", cmd, started, status.code());
+    if !status.success() {
+        bail!("SSH command failed");
+    }
+    Ok(())
+}
+
+/// Run SSH command quietly and report whether it exited successfully.
+fn ssh_succeeds(conn: &HostConnection, cmd: &str) -> Result<bool> {
+    let started = Instant::now();
+    let status = ssh_command(conn)
+        .arg(conn.ssh_target())
+        .arg(cmd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .context("Failed to run SSH")?;
+    deploy_audit::record_command("ssh", cmd, started, status.code());
+    Ok(status.success())
+}
+
 /// Run SSH command and capture output.
 fn ssh_capture(conn: &HostConnection, cmd: &str) -> Result<String> {
-    let output = Command::new("ssh")
-        .args([
-            "-p",
-            &conn.port.to_string(),
-            "-o",
-            "StrictHostKeyChecking=accept-new",
-            &conn.ssh_target(),
-            cmd,
-        ])
+    let started = Instant::now();
+    let output = ssh_command(conn)
+        .arg(conn.ssh_target())
+        .arg(cmd)
         .output()
         .context("Failed to run SSH")?;
+    deploy_audit::record_command("ssh", cmd, started, output.status.code());
 
     Ok(String::from_utf8_lossy(&output.stdout).to_string())
 }
 
 /// Sync directory via rsync.
-fn rsync_upload(local: &Path, conn: &HostConnection, remote_dest: &str) -> Result<()> {
+fn rsync_upload(
+    local: &Path,
+    conn: &HostConnection,
+    privilege: &Privilege,
+    remote_dest: &str,
+) -> Result<()> {
     let remote = format!("{}:{}", conn.ssh_target(), remote_dest);
-    let ssh_cmd = format!("ssh -p {}", conn.port);
+    let mut ssh_parts = vec!["ssh".to_string(), "-p".to_string(), conn.port.to_string()];
+    ssh_parts.extend(ssh_options());
+    let ssh_cmd = shell_words::join(ssh_parts);
+
+    // Create remote directory first; under sudo it is handed to the login user so
+    // rsync and scp can write to it without root.
+    match privilege {
+        Privilege::Direct => ssh_run(conn, &format!("mkdir -p {}", remote_dest))?,
+        _ => ssh_run_privileged(
+            conn,
+            privilege,
+            &format!(
+                "mkdir -p {dest} && chown {user} {dest}",
+                dest = remote_dest,
+                user = conn.user
+            ),
+        )?,
+    }
 
-    // Create remote directory first
-    ssh_run(conn, &format!("mkdir -p {}", remote_dest))?;
+    deploy_audit::confirm_destructive(
+        "rsync --delete",
+        &format!(
+            "Files in {} that are not in {} will be deleted.",
+            remote,
+            local.display()
+        ),
+    )?;
 
+    let local_arg = format!("{}/", local.display());
+    let args = [
+        "-avz",
+        "--delete",
+        "--exclude=target/",
+        "--exclude=.git/",
+        "--exclude=node_modules/",
+        "--exclude=.env",
+        "--exclude=*.log",
+        "--exclude=.flow/",
+        "-e",
+        &ssh_cmd,
+        &local_arg,
+        &remote,
+    ];
+    let started = Instant::now();
     let status = Command::new("rsync")
-        .args([
-            "-avz",
-            "--delete",
-            "--exclude=target/",
-            "--exclude=.git/",
-            "--exclude=node_modules/",
-            "--exclude=.env",
-            "--exclude=*.log",
-            "-e",
-            &ssh_cmd,
-            &format!("{}/", local.display()),
-            &remote,
-        ])
+        .args(args)
         .stdin(Stdio::inherit())
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .status()
         .context("Failed to run rsync")?;
+    deploy_audit::record_command(
+        "rsync",
+        &format!("rsync {}", shell_words::join(args)),
+        started,
+        status.code(),
+    );
 
     if !status.success() {
         bail!("rsync failed");
@@ -1908,15 +3038,20 @@ fn rsync_upload(local: &Path, conn: &HostConnection, remote_dest: &str) -> Resul
 /// Copy file via scp.
 fn scp_file(local: &Path, conn: &HostConnection, remote: &str) -> Result<()> {
     let dest = format!("{}:{}", conn.ssh_target(), remote);
+    let started = Instant::now();
     let status = Command::new("scp")
-        .args([
-            "-P",
-            &conn.port.to_string(),
-            &local.display().to_string(),
-            &dest,
-        ])
+        .args(["-P", &conn.port.to_string()])
+        .args(ssh_options())
+        .arg(local)
+        .arg(&dest)
         .status()
         .context("Failed to run scp")?;
+    deploy_audit::record_command(
+        "scp",
+        &format!("scp {} {}", local.display(), dest),
+        started,
+        status.code(),
+    );
 
     if !status.success() {
         bail!("scp failed");
@@ -1924,88 +3059,214 @@ fn scp_file(local: &Path, conn: &HostConnection, remote: &str) -> Result<()> {
     Ok(())
 }
 
-/// Install the env-fetch script on the host.
-/// This script fetches env vars from cloud using a service token on startup.
-fn install_env_fetch_script(
-    conn: &HostConnection,
+/// Install the env-fetch script on the host.
+/// This script fetches env vars from cloud using a service token on startup.
+fn install_env_fetch_script(
+    conn: &HostConnection,
+    dest: &str,
+    service_token: &str,
+    api_base: &str,
+    project_name: &str,
+    environment: &str,
+    keys: &[String],
+) -> Result<()> {
+    let script = env_fetch_script(dest, api_base, project_name, environment, keys);
+
+    // Write the script and its JSON parser to temp files and copy them
+    for (name, content) in [
+        ("fetch-env.sh", script.as_str()),
+        ("env-json.awk", ENV_JSON_AWK),
+    ] {
+        let temp = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        fs::write(&temp, content)?;
+        let copied = scp_file(&temp, conn, &format!("{}/{}", dest, name));
+        let _ = fs::remove_file(&temp);
+        copied?;
+    }
+
+    // Make executable
+    ssh_run(conn, &format!("chmod +x {}/fetch-env.sh", dest))?;
+
+    // Store the service token securely
+    let temp_token = std::env::temp_dir().join(format!(".cloud-token-{}", std::process::id()));
+    fs::write(&temp_token, service_token)?;
+    scp_file(&temp_token, conn, &format!("{}/.cloud-token", dest))?;
+    let _ = fs::remove_file(&temp_token);
+
+    // Secure the token file (only readable by its owner and root)
+    ssh_run(conn, &format!("chmod 600 {}/.cloud-token", dest))?;
+
+    Ok(())
+}
+
+/// Seconds `fetch-env.sh` keeps retrying an unreachable cloud before failing the service
+/// start; below systemd's default 90s start timeout. Overridable on the host with
+/// `FLOW_ENV_FETCH_MAX_WAIT`.
+const ENV_FETCH_MAX_WAIT_SECS: u32 = 60;
+
+/// The `fetch-env.sh` run as `ExecStartPre`. It retries network errors and 5xx responses with
+/// exponential backoff, fails fast on 401/403/404, and only replaces `.env` after a response
+/// parsed. Failures go to stderr, so they show up in the service's journal.
+fn env_fetch_script(
     dest: &str,
-    service_token: &str,
     api_base: &str,
     project_name: &str,
     environment: &str,
     keys: &[String],
-) -> Result<()> {
+) -> String {
     // Build the keys query parameter
     let keys_param = if keys.is_empty() {
         String::new()
     } else {
         format!("&keys={}", keys.join(","))
     };
-
-    // Create the fetch script
-    // The script fetches env vars from cloud API and writes to .env
     let api_base = api_base.trim_end_matches('/');
-    let script = format!(
+    format!(
         r##"#!/bin/bash
 # Auto-generated by flow - fetches env vars from cloud on startup
 # This token can ONLY read env vars for project: {project_name}
 
-set -e
+set -u
 
 TOKEN_FILE="{dest}/.cloud-token"
 ENV_FILE="{dest}/.env"
+PARSER="{dest}/env-json.awk"
 API_URL="{api_base}/api/env/{project_name}?environment={environment}{keys_param}"
+MAX_WAIT="${{FLOW_ENV_FETCH_MAX_WAIT:-{ENV_FETCH_MAX_WAIT_SECS}}}"
 
-if [ ! -f "$TOKEN_FILE" ]; then
-    echo "ERROR: Service token not found at $TOKEN_FILE" >&2
+fail() {{
+    echo "flow fetch-env ({project_name}): $*" >&2
     exit 1
-fi
+}}
 
+[ -f "$TOKEN_FILE" ] || fail "service token not found at $TOKEN_FILE"
+[ -f "$PARSER" ] || fail "env parser not found at $PARSER; redeploy with f deploy host"
 TOKEN=$(cat "$TOKEN_FILE")
 
-# Fetch env vars from cloud
-RESPONSE=$(curl -sf -H "Authorization: Bearer $TOKEN" "$API_URL")
-
-if [ $? -ne 0 ]; then
-    echo "ERROR: Failed to fetch env vars from cloud" >&2
-    exit 1
-fi
+BODY=$(mktemp) || fail "mktemp failed"
+ERRORS=$(mktemp) || fail "mktemp failed"
+trap 'rm -f "$BODY" "$ERRORS" "$ENV_FILE.tmp"' EXIT
+
+# Fetch env vars from cloud, backing off 1s, 2s, 4s, ... (capped at 30s) until MAX_WAIT
+# seconds have passed since the script started
+attempt=1
+delay=1
+while :; do
+    STATUS=$(curl -sS -o "$BODY" -w '%{{http_code}}' --connect-timeout 5 --max-time 10 \
+        -H "Authorization: Bearer $TOKEN" "$API_URL" 2>"$ERRORS") || STATUS="000"
+    case "$STATUS" in
+        2??) break ;;
+        401|403|404) fail "cloud rejected the request (HTTP $STATUS); check the service token and environment '{environment}'" ;;
+    esac
+    if [ "$STATUS" = "000" ]; then
+        REASON=$(head -n 1 "$ERRORS")
+    else
+        REASON="HTTP $STATUS"
+    fi
+    if [ $((SECONDS + delay)) -gt "$MAX_WAIT" ]; then
+        fail "cloud unreachable at {api_base} after $attempt attempts over ${{SECONDS}}s ($REASON); not starting"
+    fi
+    echo "flow fetch-env ({project_name}): attempt $attempt failed ($REASON); retrying in ${{delay}}s" >&2
+    sleep "$delay"
+    attempt=$((attempt + 1))
+    delay=$((delay * 2))
+    [ "$delay" -gt 30 ] && delay=30
+done
 
 # Parse JSON and write to .env file
-echo "# Environment: {environment} (fetched from cloud)" > "$ENV_FILE"
-echo "$RESPONSE" | python3 -c "
-import json, sys
-data = json.load(sys.stdin)
-for k, v in sorted(data.get('env', {{}}).items()):
-    escaped = v.replace('\\', '\\\\').replace('\"', '\\\"')
-    print(f'{{{{k}}}}=\"{{{{escaped}}}}\"')
-" >> "$ENV_FILE"
-
-chmod 600 "$ENV_FILE"
+{{
+    echo "# Environment: {environment} (fetched from cloud)"
+    LC_ALL=C awk -f "$PARSER" "$BODY"
+}} > "$ENV_FILE.tmp" || fail "cloud response had no env object"
+
+chmod 600 "$ENV_FILE.tmp"
+mv "$ENV_FILE.tmp" "$ENV_FILE"
 echo "Fetched env vars for {project_name} ({environment})"
 "##
-    );
+    )
+}
+
+/// Shipped next to `fetch-env.sh` so hosts need only awk, not python3 or jq. Prints the string
+/// entries of the top-level `"env"` object as `KEY="value"` lines and exits non-zero when there
+/// is no such object.
+const ENV_JSON_AWK: &str = r#"# Auto-generated by flow - reads {"env": {...}} from the env API
+function next_char() {
+    while (i <= n && index(" \t\r\n", substr(json, i, 1))) i++
+    return substr(json, i, 1)
+}
 
-    // Write script to temp file and copy
-    let temp_script = std::env::temp_dir().join(format!("fetch-env-{}.sh", std::process::id()));
-    fs::write(&temp_script, &script)?;
-    scp_file(&temp_script, conn, &format!("{}/fetch-env.sh", dest))?;
-    let _ = fs::remove_file(&temp_script);
+function hex_value(h,    v, k) {
+    v = 0
+    for (k = 1; k <= length(h); k++) v = v * 16 + index("0123456789abcdef", tolower(substr(h, k, 1))) - 1
+    return v
+}
 
-    // Make executable
-    ssh_run(conn, &format!("chmod +x {}/fetch-env.sh", dest))?;
+function utf8(cp) {
+    if (cp < 128) return sprintf("%c", cp)
+    if (cp < 2048) return sprintf("%c%c", 192 + int(cp / 64), 128 + cp % 64)
+    return sprintf("%c%c%c", 224 + int(cp / 4096), 128 + int(cp / 64) % 64, 128 + cp % 64)
+}
 
-    // Store the service token securely
-    let temp_token = std::env::temp_dir().join(format!(".cloud-token-{}", std::process::id()));
-    fs::write(&temp_token, service_token)?;
-    scp_file(&temp_token, conn, &format!("{}/.cloud-token", dest))?;
-    let _ = fs::remove_file(&temp_token);
+function read_string(    out, c) {
+    out = ""
+    i++
+    while (i <= n) {
+        c = substr(json, i, 1)
+        if (c == "\"") { i++; return out }
+        if (c == "\\") {
+            i++
+            c = substr(json, i, 1)
+            if (c == "n") out = out "\n"
+            else if (c == "t") out = out "\t"
+            else if (c == "r") out = out "\r"
+            else if (c == "b") out = out "\b"
+            else if (c == "f") out = out "\f"
+            else if (c == "u") { out = out utf8(hex_value(substr(json, i + 1, 4))); i += 4 }
+            else out = out c
+        } else out = out c
+        i++
+    }
+    return out
+}
 
-    // Secure the token file (only readable by root)
-    ssh_run(conn, &format!("chmod 600 {}/.cloud-token", dest))?;
+function quote(v,    out, k, c) {
+    out = ""
+    for (k = 1; k <= length(v); k++) {
+        c = substr(v, k, 1)
+        if (c == "\\" || c == "\"") out = out "\\"
+        out = out c
+    }
+    return "\"" out "\""
+}
 
-    Ok(())
+{ json = json $0 "\n" }
+
+END {
+    n = length(json); i = 1; depth = 0; env_depth = 0; found = 0; key = ""; after_colon = 0
+    while (i <= n) {
+        c = substr(json, i, 1)
+        if (c == "\"") {
+            s = read_string()
+            if (!after_colon && next_char() == ":") { key = s; continue }
+            if (after_colon && env_depth && depth == env_depth && key ~ /^[A-Za-z_][A-Za-z0-9_]*$/) print key "=" quote(s)
+            after_colon = 0
+            continue
+        }
+        if (c == ":") after_colon = 1
+        else if (c == "{" || c == "[") {
+            depth++
+            if (c == "{" && after_colon && depth == 2 && key == "env") { env_depth = 2; found = 1 }
+            after_colon = 0
+        } else if (c == "}" || c == "]") {
+            if (depth == env_depth) env_depth = 0
+            depth--
+            after_colon = 0
+        } else if (c == ",") after_colon = 0
+        i++
+    }
+    if (!found) exit 1
 }
+"#;
 
 /// Check if systemd service exists.
 fn service_exists(conn: &HostConnection, name: &str) -> Result<bool> {
@@ -2022,30 +3283,180 @@ fn service_exists(conn: &HostConnection, name: &str) -> Result<bool> {
 /// Create systemd service file.
 fn create_systemd_service(
     conn: &HostConnection,
+    privilege: &Privilege,
     name: &str,
     workdir: &str,
     exec_start: &str,
     config: &HostConfig,
+    template: bool,
 ) -> Result<()> {
-    let exec_start = normalize_exec_start(workdir, exec_start);
+    let (unit_file, service) = render_systemd_unit(name, workdir, exec_start, config, template);
+    let written = install_unit(conn, privilege, &format!("{unit_file}.service"), &service)?;
+    // Template instances are enabled by the swap once they are serving.
+    if written && !template {
+        ssh_run_privileged(conn, privilege, &format!("systemctl enable {}", name))?;
+    }
+    Ok(())
+}
 
-    // Determine if we're using cloud with service token (fetch on startup)
-    let use_cloud = is_cloud_source(config.env_source.as_deref());
-    let has_service_token = config.service_token.is_some();
+/// Install the `[[host.workers]]` units and enable each worker's service or timer.
+/// Disable and delete the units of workers that were removed from `[[host.workers]]`.
+fn remove_stale_worker_units(
+    conn: &HostConnection,
+    privilege: &Privilege,
+    service: &str,
+    config: &HostConfig,
+) -> Result<()> {
+    let installed = ssh_capture(
+        conn,
+        &deploy_workers::list_installed_command(service, "/etc/systemd/system"),
+    )?;
+    let stale = deploy_workers::stale_units(service, &config.workers, &installed);
+    if stale.is_empty() {
+        return Ok(());
+    }
+    println!("==> Removing {} stale worker unit(s)", stale.len());
+    let paths: Vec<String> = stale.iter().map(|file| unit_path(file)).collect();
+    ssh_run_privileged(
+        conn,
+        privilege,
+        &format!(
+            "systemctl disable --now {} ; rm -f {} && systemctl daemon-reload",
+            stale.join(" "),
+            paths.join(" ")
+        ),
+    )
+}
+fn create_worker_units(
+    conn: &HostConnection,
+    privilege: &Privilege,
+    service: &str,
+    workdir: &str,
+    config: &HostConfig,
+) -> Result<()> {
+    let env = UnitEnv::from_config(workdir, config);
+    for worker in &config.workers {
+        for (file, contents) in deploy_workers::render(service, workdir, worker, &env) {
+            install_unit(conn, privilege, &file, &contents)?;
+        }
+    }
+    let controls = worker_controls(service, config);
+    if !controls.is_empty() {
+        ssh_run_privileged(
+            conn,
+            privilege,
+            &format!("systemctl enable {}", controls.join(" ")),
+        )?;
+    }
+    Ok(())
+}
 
-    let env_file_line = if use_cloud || config.env_file.is_some() {
-        format!("EnvironmentFile={}/.env", workdir)
-    } else {
-        String::new()
+/// Write a unit file (e.g. "app.service") unless the installed one already matches, printing
+/// what changed. Returns whether it was written; systemd is reloaded when it was.
+fn install_unit(
+    conn: &HostConnection,
+    privilege: &Privilege,
+    file: &str,
+    contents: &str,
+) -> Result<bool> {
+    let current = remote_unit(conn, file)?;
+    match unit_drift(contents, current.as_deref()) {
+        UnitDrift::InSync => {
+            println!("    {file} unchanged, skipping rewrite");
+            return Ok(false);
+        }
+        UnitDrift::Differs(diff) => {
+            println!("    {file} changes:");
+            print!("{}", indent_diff(&diff));
+        }
+        UnitDrift::Missing => {}
+    }
+
+    let escaped = contents.replace('\"', "\\\"").replace('$', "\\$");
+    let cmd = format!(
+        "echo \"{}\" > {} && systemctl daemon-reload",
+        escaped,
+        unit_path(file)
+    );
+    ssh_run_privileged(conn, privilege, &cmd)?;
+    Ok(true)
+}
+
+fn unit_path(file: &str) -> String {
+    format!("/etc/systemd/system/{file}")
+}
+
+/// Contents of the installed unit file (e.g. "app.service"), or None when it doesn't exist.
+fn remote_unit(conn: &HostConnection, unit_file: &str) -> Result<Option<String>> {
+    let path = unit_path(unit_file);
+    let output = ssh_capture(
+        conn,
+        &format!("test -f {path} && cat {path} || echo __flow_missing__"),
+    )?;
+    Ok((output.trim() != "__flow_missing__").then_some(output))
+}
+
+/// How an installed unit compares to the one flow.toml renders.
+#[derive(Debug, PartialEq, Eq)]
+enum UnitDrift {
+    Missing,
+    InSync,
+    /// Line diff from the installed unit to the rendered one.
+    Differs(String),
+}
+
+fn unit_drift(expected: &str, installed: Option<&str>) -> UnitDrift {
+    let Some(installed) = installed else {
+        return UnitDrift::Missing;
+    };
+    // `echo` adds a trailing newline, so compare line by line without trailing blanks.
+    let normalize = |text: &str| {
+        text.lines()
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim_end()
+            .to_string()
     };
+    let (installed, expected) = (normalize(installed), normalize(expected));
+    if installed == expected {
+        UnitDrift::InSync
+    } else {
+        UnitDrift::Differs(line_diff::render_line_diff(&installed, &expected))
+    }
+}
+
+/// Only the changed lines of a `line_diff::render_line_diff`, indented for deploy output.
+fn indent_diff(diff: &str) -> String {
+    diff.lines()
+        .filter(|line| line.starts_with('-') || line.starts_with('+'))
+        .map(|line| format!("      {line}\n"))
+        .collect()
+}
 
-    // Add ExecStartPre to fetch env vars if using service token
-    let exec_start_pre = if use_cloud && has_service_token {
-        format!("ExecStartPre={}/fetch-env.sh", workdir)
+/// Unit file name (without `.service`) and contents for the `[host]` service.
+fn render_systemd_unit(
+    name: &str,
+    workdir: &str,
+    exec_start: &str,
+    config: &HostConfig,
+    template: bool,
+) -> (String, String) {
+    let exec_start = normalize_exec_start(workdir, exec_start);
+    // Template instances take their port from the instance name (`{name}@3001`); it is
+    // passed on the command line so an EnvironmentFile PORT cannot override it.
+    let (exec_start, unit_file) = if template {
+        (with_instance_port(&exec_start), format!("{name}@"))
     } else {
-        String::new()
+        (exec_start, name.to_string())
     };
 
+    let UnitEnv {
+        user_line,
+        exec_start_pre,
+        env_file_line,
+    } = UnitEnv::from_config(workdir, config);
+
     let service = format!(
         r#"[Unit]
 Description={name}
@@ -2054,6 +3465,7 @@ After=network.target
 [Service]
 Type=simple
 WorkingDirectory={workdir}
+{user_line}
 {exec_start_pre}
 ExecStart={exec_start}
 Restart=always
@@ -2064,18 +3476,51 @@ RestartSec=5
 WantedBy=multi-user.target
 "#
     );
+    (unit_file, service)
+}
 
-    let escaped = service.replace('\"', "\\\"").replace('$', "\\$");
-    let cmd = format!(
-        "echo \"{}\" > /etc/systemd/system/{}.service && systemctl daemon-reload && systemctl enable {}",
-        escaped, name, name
-    );
+/// `[Service]` lines the main unit and worker units share: user, env fetch, env file.
+#[derive(Debug, Clone, Default)]
+pub struct UnitEnv {
+    pub user_line: String,
+    pub exec_start_pre: String,
+    pub env_file_line: String,
+}
 
-    ssh_run(conn, &cmd)?;
-    Ok(())
+impl UnitEnv {
+    pub fn from_config(workdir: &str, config: &HostConfig) -> Self {
+        // Determine if we're using cloud with service token (fetch on startup)
+        let use_cloud = is_cloud_source(config.env_source.as_deref());
+        let has_service_token = config.service_token.is_some();
+
+        let env_file_line = if use_cloud || config.env_file.is_some() {
+            format!("EnvironmentFile={}/.env", workdir)
+        } else {
+            String::new()
+        };
+
+        // Add ExecStartPre to fetch env vars if using service token; with a service user it
+        // runs as root ("+"), since the user cannot write to the release directory.
+        let exec_start_pre = match (use_cloud && has_service_token, &config.user) {
+            (false, _) => String::new(),
+            (true, Some(_)) => format!("ExecStartPre=+{}/fetch-env.sh", workdir),
+            (true, None) => format!("ExecStartPre={}/fetch-env.sh", workdir),
+        };
+
+        let user_line = match &config.user {
+            Some(user) => format!("User={user}\nGroup={user}"),
+            None => String::new(),
+        };
+
+        Self {
+            user_line,
+            exec_start_pre,
+            env_file_line,
+        }
+    }
 }
 
-fn normalize_exec_start(workdir: &str, exec_start: &str) -> String {
+pub fn normalize_exec_start(workdir: &str, exec_start: &str) -> String {
     let trimmed = exec_start.trim();
     if trimmed.is_empty() {
         return String::new();
@@ -2104,8 +3549,21 @@ fn normalize_exec_start(workdir: &str, exec_start: &str) -> String {
     shell_words::join(env_parts)
 }
 
+fn with_instance_port(exec_start: &str) -> String {
+    let command = exec_start
+        .strip_prefix("/usr/bin/env ")
+        .unwrap_or(exec_start);
+    format!("/usr/bin/env PORT=%i {command}")
+}
+
 /// Set up nginx reverse proxy.
-fn setup_nginx(conn: &HostConnection, domain: &str, port: u16, ssl: bool) -> Result<()> {
+fn setup_nginx(
+    conn: &HostConnection,
+    privilege: &Privilege,
+    domain: &str,
+    port: u16,
+    ssl: bool,
+) -> Result<()> {
     let config = format!(
         r#"server {{
     listen 80;
@@ -2134,7 +3592,7 @@ fn setup_nginx(conn: &HostConnection, domain: &str, port: u16, ssl: bool) -> Res
         escaped, domain, domain
     );
 
-    ssh_run(conn, &cmd)?;
+    ssh_run_privileged(conn, privilege, &cmd)?;
 
     // Set up SSL if requested
     if ssl {
@@ -2143,7 +3601,7 @@ fn setup_nginx(conn: &HostConnection, domain: &str, port: u16, ssl: bool) -> Res
             "certbot --nginx -d {} --non-interactive --agree-tos -m admin@{} || true",
             domain, domain
         );
-        ssh_run(conn, &ssl_cmd)?;
+        ssh_run_privileged(conn, privilege, &ssl_cmd)?;
     }
 
     Ok(())
@@ -2189,101 +3647,29 @@ fn update_flow_toml_cloudflare(
     project_root: &Path,
     setup: &CloudflareSetupResult,
 ) -> Result<()> {
-    let contents = fs::read_to_string(flow_path)?;
-    let mut lines: Vec<String> = contents.lines().map(|line| line.to_string()).collect();
-    let had_trailing_newline = contents.ends_with('\n');
-
     let worker_path = relative_dir(project_root, &setup.worker_path);
     let env_file = setup
         .env_file
         .as_ref()
         .map(|path| relative_path(project_root, path));
-    let environment = setup.environment.clone();
-
-    if let Some(start) = lines.iter().position(|line| line.trim() == "[cloudflare]") {
-        let end = find_section_end(&lines, start + 1);
-        let mut section_lines = Vec::new();
-        for line in &lines[start + 1..end] {
-            if !is_cloudflare_key_line(line) {
-                section_lines.push(line.clone());
-            }
-        }
-
-        let mut updates = Vec::new();
-        if let Some(path) = worker_path {
-            updates.push(format!("path = \"{}\"", path));
-        }
-        if let Some(env_file) = env_file {
-            updates.push(format!("env_file = \"{}\"", env_file));
-        }
-        if let Some(environment) = environment {
-            updates.push(format!("environment = \"{}\"", environment));
-        }
 
-        if !updates.is_empty() {
-            let needs_blank = section_lines
-                .last()
-                .map(|line| !line.trim().is_empty())
-                .unwrap_or(false);
-            if needs_blank {
-                section_lines.push(String::new());
+    let mut doc = config_edit::load(flow_path)?;
+    config_edit::ensure_table(&mut doc, "cloudflare")?;
+    for (key, value) in [
+        ("path", worker_path),
+        ("env_file", env_file),
+        ("environment", setup.environment.clone()),
+    ] {
+        let path = format!("cloudflare.{key}");
+        match value {
+            Some(value) => config_edit::set(&mut doc, &path, value.into())?,
+            None => {
+                config_edit::remove(&mut doc, &path)?;
             }
-            section_lines.extend(updates);
-        }
-
-        let mut updated = Vec::new();
-        updated.extend_from_slice(&lines[..start + 1]);
-        updated.extend(section_lines);
-        updated.extend_from_slice(&lines[end..]);
-        lines = updated;
-    } else {
-        if !lines.is_empty()
-            && !lines
-                .last()
-                .map(|line| line.trim().is_empty())
-                .unwrap_or(false)
-        {
-            lines.push(String::new());
-        }
-        lines.push("[cloudflare]".to_string());
-        if let Some(path) = worker_path {
-            lines.push(format!("path = \"{}\"", path));
-        }
-        if let Some(env_file) = env_file {
-            lines.push(format!("env_file = \"{}\"", env_file));
-        }
-        if let Some(environment) = environment {
-            lines.push(format!("environment = \"{}\"", environment));
-        }
-    }
-
-    let mut updated = lines.join("\n");
-    if had_trailing_newline {
-        updated.push('\n');
-    }
-    fs::write(flow_path, updated)?;
-    Ok(())
-}
-
-fn find_section_end(lines: &[String], start: usize) -> usize {
-    for (idx, line) in lines.iter().enumerate().skip(start) {
-        let trimmed = line.trim();
-        if trimmed.starts_with('[') && trimmed.ends_with(']') {
-            return idx;
         }
     }
-    lines.len()
-}
-
-fn is_cloudflare_key_line(line: &str) -> bool {
-    let trimmed = line.trim();
-    if trimmed.starts_with('#') || trimmed.starts_with(';') {
-        return false;
-    }
-    let Some((key, _)) = trimmed.split_once('=') else {
-        return false;
-    };
-    matches!(key.trim(), "path" | "env_file" | "environment" | "env")
+    config_edit::remove(&mut doc, "cloudflare.env")?;
+    config_edit::save(flow_path, &doc)
 }
 
 fn relative_path(project_root: &Path, path: &Path) -> String {
@@ -2406,58 +3792,15 @@ fn ensure_web_path(flow_path: &Path, web_path: &str) -> Result<bool> {
 }
 
 fn ensure_web_key(flow_path: &Path, key: &str, value: &str) -> Result<bool> {
-    let contents = fs::read_to_string(flow_path)?;
-    let mut lines: Vec<String> = contents.lines().map(|line| line.to_string()).collect();
-    let had_trailing_newline = contents.ends_with('\n');
-
-    let mut changed = false;
-    if let Some(start) = lines.iter().position(|line| line.trim() == "[web]") {
-        let end = find_section_end(&lines, start + 1);
-        let mut section_lines = lines[start + 1..end].to_vec();
-        if !section_has_key(&section_lines, key) {
-            section_lines.push(format!("{key} = \"{}\"", value.trim()));
-            changed = true;
-        }
-
-        let mut updated = Vec::new();
-        updated.extend_from_slice(&lines[..start + 1]);
-        updated.extend(section_lines);
-        updated.extend_from_slice(&lines[end..]);
-        lines = updated;
-    } else {
-        if !lines.is_empty()
-            && !lines
-                .last()
-                .map(|line| line.trim().is_empty())
-                .unwrap_or(false)
-        {
-            lines.push(String::new());
-        }
-        lines.push("[web]".to_string());
-        lines.push(format!("{key} = \"{}\"", value.trim()));
-        changed = true;
-    }
-
+    let mut doc = config_edit::load(flow_path)?;
+    let changed =
+        config_edit::set_if_missing(&mut doc, &format!("web.{key}"), value.trim().into())?;
     if changed {
-        let mut updated = lines.join("\n");
-        if had_trailing_newline {
-            updated.push('\n');
-        }
-        fs::write(flow_path, updated)?;
+        config_edit::save(flow_path, &doc)?;
     }
-
     Ok(changed)
 }
 
-fn section_has_key(lines: &[String], key: &str) -> bool {
-    let key_prefix = format!("{key} ");
-    let key_eq = format!("{key}=");
-    lines.iter().any(|line| {
-        let trimmed = line.trim();
-        trimmed.starts_with(&key_prefix) || trimmed.starts_with(&key_eq)
-    })
-}
-
 fn ensure_web_routes(project_root: &Path, web_cfg: &WebConfig) -> Result<bool> {
     let Some(route) = resolve_web_route(web_cfg) else {
         eprintln!("WARN web route not set. Add web.route or web.domain in flow.toml.");
@@ -2668,7 +4011,9 @@ fn apply_web_env(project_root: &Path, web_cfg: &WebConfig) -> Result<()> {
     for key in &keys {
         if let Some(value) = web_cfg.env_defaults.get(key) {
             if !value.trim().is_empty() {
-                vars.insert(key.clone(), value.clone());
+                let value = crate::config_interp::interpolate(value)
+                    .with_context(|| format!("[web] env_defaults.{key}"))?;
+                vars.insert(key.clone(), value);
             }
         }
     }
@@ -3096,8 +4441,9 @@ fn relative_dir(project_root: &Path, path: &Path) -> Option<String> {
 }
 
 /// Set Railway environment variables from env file.
-fn set_railway_env(env_file: &Path) -> Result<()> {
+fn set_railway_env(project_root: &Path, env_file: &Path) -> Result<()> {
     let content = fs::read_to_string(env_file)?;
+    let mut vars = HashMap::new();
     for line in content.lines() {
         let line = line.trim();
         if line.is_empty() || line.starts_with('#') {
@@ -3105,16 +4451,207 @@ fn set_railway_env(env_file: &Path) -> Result<()> {
         }
         if let Some((key, value)) = line.split_once('=') {
             let value = value.trim_matches('"').trim_matches('\'');
-            Command::new("railway")
-                .args(["variables", "set", &format!("{}={}", key, value)])
-                .stdout(Stdio::null())
-                .stderr(Stdio::null())
-                .status()?;
+            vars.insert(key.to_string(), value.to_string());
+        }
+    }
+    set_railway_variables(project_root, &vars)
+}
+
+/// `f deploy drift`: compare `[host]` in flow.toml with the systemd unit, nginx site, and
+/// `.env` keys actually on the host. Fails when anything diverges.
+fn check_drift(project_root: &Path, config: Option<&Config>) -> Result<()> {
+    let host_cfg = config
+        .and_then(|c| c.host.as_ref())
+        .context("No [host] section in flow.toml")?;
+    let conn = &host_connection(Some(host_cfg))?
+        .context("No host configured. Run: f deploy set-host user@host:port")?;
+    let dest = host_cfg.dest.as_deref().unwrap_or("/opt/app");
+    let service_name = host_cfg
+        .service
+        .as_deref()
+        .unwrap_or_else(|| project_root.file_name().unwrap().to_str().unwrap());
+    let swap = match host_strategy(host_cfg)? {
+        HostStrategy::Swap => Some(SwapPlan::from_config(service_name, host_cfg)?),
+        HostStrategy::Restart => None,
+    };
+    deploy_workers::validate(&host_cfg.workers)?;
+
+    println!(
+        "Checking {} against [host] in flow.toml\n",
+        conn.ssh_target()
+    );
+    let _master = SshMaster::open(conn)?;
+    let mut drifted = 0;
+    let mut report = |ok: bool, message: String| {
+        if ok {
+            println!("✓ {message}");
+        } else {
+            drifted += 1;
+            println!("✗ {message}");
+        }
+    };
+
+    if let Some(run_cmd) = &host_cfg.run {
+        let (unit_file, expected) =
+            render_systemd_unit(service_name, dest, run_cmd, host_cfg, swap.is_some());
+        let unit_file = format!("{unit_file}.service");
+        report_unit_drift(conn, &unit_file, &expected, &mut report)?;
+        let unit = live_service_unit(conn, Some(host_cfg), service_name)?;
+        let state = ssh_capture(conn, &format!("systemctl is-active {unit}"))?;
+        let state = state.trim();
+        report(state == "active", format!("{unit} is {state}"));
+    }
+
+    let env = UnitEnv::from_config(dest, host_cfg);
+    for worker in &host_cfg.workers {
+        for (file, expected) in deploy_workers::render(service_name, dest, worker, &env) {
+            report_unit_drift(conn, &file, &expected, &mut report)?;
+        }
+        let units = deploy_workers::WorkerUnits::new(service_name, worker);
+        let state = ssh_capture(conn, &format!("systemctl is-active {}", units.control()))?;
+        let state = state.trim();
+        report(state == "active", format!("{} is {state}", units.control()));
+    }
+
+    if let (Some(domain), Some(port)) = (&host_cfg.domain, host_cfg.port) {
+        match nginx_upstream_port(conn, domain)? {
+            None => report(false, format!("nginx site for {domain} is missing")),
+            Some(serving) => {
+                let expected = match &swap {
+                    Some(plan) => serving == plan.port || serving == plan.alt_port,
+                    None => serving == port,
+                };
+                report(
+                    expected,
+                    format!("nginx proxies {domain} to port {serving} (flow.toml: {port})"),
+                );
+            }
+        }
+        let enabled = ssh_succeeds(conn, &format!("test -e /etc/nginx/sites-enabled/{domain}"))?;
+        report(
+            enabled,
+            format!("nginx site for {domain} enabled: {enabled}"),
+        );
+        if host_cfg.ssl {
+            let tls = ssh_succeeds(
+                conn,
+                &format!("grep -q 'listen 443' {}", nginx_site_path(domain)),
+            )?;
+            report(tls, format!("nginx serves {domain} over TLS: {tls}"));
+        }
+    }
+
+    if let Some(expected) = expected_env_keys(project_root, host_cfg)? {
+        // Only key names leave the host; values stay there.
+        let remote = ssh_capture(
+            conn,
+            &format!(
+                "sed -n 's/^\\(export \\)\\{{0,1\\}}\\([A-Za-z_][A-Za-z0-9_]*\\)=.*/\\2/p' {}/.env 2>/dev/null",
+                shell_words::quote(dest)
+            ),
+        )?;
+        let (missing, extra) = env_key_drift(&expected, &remote);
+        if missing.is_empty() && extra.is_empty() {
+            report(
+                true,
+                format!("{dest}/.env has the {} expected keys", expected.len()),
+            );
+        }
+        if !missing.is_empty() {
+            report(
+                false,
+                format!("{dest}/.env is missing {}", missing.join(", ")),
+            );
         }
+        if !extra.is_empty() {
+            report(
+                false,
+                format!("{dest}/.env has unexpected {}", extra.join(", ")),
+            );
+        }
+    }
+
+    if drifted > 0 {
+        bail!(
+            "{drifted} drift finding(s) on {}; `f deploy host` reapplies flow.toml",
+            conn.ssh_target()
+        );
+    }
+    println!("\nNo drift.");
+    Ok(())
+}
+
+/// Report whether the installed `unit_file` matches what flow.toml renders.
+fn report_unit_drift(
+    conn: &HostConnection,
+    unit_file: &str,
+    expected: &str,
+    report: &mut impl FnMut(bool, String),
+) -> Result<()> {
+    match unit_drift(expected, remote_unit(conn, unit_file)?.as_deref()) {
+        UnitDrift::InSync => report(true, format!("{unit_file} matches")),
+        UnitDrift::Missing => report(false, format!("{unit_file} is not installed")),
+        UnitDrift::Differs(diff) => report(
+            false,
+            format!(
+                "{unit_file} differs (- installed, + flow.toml):\n{}",
+                indent_diff(&diff).trim_end()
+            ),
+        ),
     }
     Ok(())
 }
 
+/// Env keys `[host]` writes to the remote `.env`, or None when flow doesn't manage it.
+fn expected_env_keys(project_root: &Path, host_cfg: &HostConfig) -> Result<Option<Vec<String>>> {
+    if is_cloud_source(host_cfg.env_source.as_deref())
+        || is_flow_source(host_cfg.env_source.as_deref())
+    {
+        let mut keys = host_cfg.env_keys.clone();
+        if let Some(path) = &host_cfg.env_template {
+            let template_path = project_root.join(path);
+            let content = fs::read_to_string(&template_path).with_context(|| {
+                format!("failed to read env template {}", template_path.display())
+            })?;
+            for key in EnvTemplate::parse(&content).referenced_keys() {
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+        return Ok((!keys.is_empty()).then_some(keys));
+    }
+    if let Some(env_file) = &host_cfg.env_file {
+        let local_env = project_root.join(env_file);
+        if local_env.exists() {
+            let content = fs::read_to_string(&local_env)
+                .with_context(|| format!("failed to read {}", local_env.display()))?;
+            return Ok(Some(parse_env_file(&content).into_keys().collect()));
+        }
+    }
+    Ok(None)
+}
+
+/// Expected keys absent from the remote key list, and remote keys flow.toml doesn't set.
+fn env_key_drift(expected: &[String], remote_keys: &str) -> (Vec<String>, Vec<String>) {
+    let remote: HashSet<&str> = remote_keys.lines().map(str::trim).collect();
+    let expected_set: HashSet<&str> = expected.iter().map(String::as_str).collect();
+    let mut missing: Vec<String> = expected
+        .iter()
+        .filter(|key| !remote.contains(key.as_str()))
+        .cloned()
+        .collect();
+    let mut extra: Vec<String> = remote
+        .into_iter()
+        .filter(|key| !key.is_empty() && !expected_set.contains(key))
+        .map(str::to_string)
+        .collect();
+    missing.sort();
+    missing.dedup();
+    extra.sort();
+    (missing, extra)
+}
+
 /// Check if deployment is healthy via HTTP.
 fn check_health(
     _project_root: &Path,
@@ -3193,3 +4730,182 @@ fn check_health(
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn railway_nodes_and_errors_are_read_from_graphql_responses() {
+        let project = serde_json::json!({
+            "services": { "edges": [
+                { "node": { "id": "svc-1", "name": "api" } },
+                { "node": { "id": "svc-2", "name": "worker" } }
+            ] }
+        });
+        assert_eq!(
+            railway_nodes(&project["services"]),
+            vec![
+                ("svc-1".to_string(), "api".to_string()),
+                ("svc-2".to_string(), "worker".to_string())
+            ]
+        );
+        assert!(railway_nodes(&project["environments"]).is_empty());
+
+        assert!(railway_api_check(&serde_json::json!({ "data": {} })).is_ok());
+        let err = railway_api_check(&serde_json::json!({
+            "errors": [{ "message": "Not Authorized" }]
+        }))
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Railway API error: Not Authorized");
+    }
+
+    #[test]
+    fn privilege_wraps_commands_for_sudo() {
+        let cmd = "echo 'x' > /etc/app && systemctl daemon-reload";
+        assert_eq!(Privilege::Direct.wrap(cmd), cmd);
+        assert_eq!(
+            Privilege::Sudo.wrap(cmd),
+            "sudo -n sh -c 'echo '\\''x'\\'' > /etc/app && systemctl daemon-reload'"
+        );
+        let password = Privilege::SudoPassword("hunter2".to_string());
+        assert!(password.wrap("true").starts_with("sudo -k -S -p '' sh -c "));
+        assert_eq!(password.input().as_deref(), Some("hunter2\n"));
+        assert!(Privilege::Sudo.input().is_none());
+    }
+
+    #[test]
+    fn swap_plan_alternates_ports_and_templates_the_unit() {
+        let cfg = HostConfig {
+            port: Some(3000),
+            domain: Some("app.example.com".to_string()),
+            strategy: Some("swap".to_string()),
+            health_path: Some("healthz".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(host_strategy(&cfg).unwrap(), HostStrategy::Swap);
+        let plan = SwapPlan::from_config("app", &cfg).unwrap();
+        assert_eq!(plan.alt_port, 3001);
+        assert_eq!(plan.health_path, "/healthz");
+        assert_eq!(plan.next_port(None), 3001);
+        assert_eq!(plan.next_port(Some(3000)), 3001);
+        assert_eq!(plan.next_port(Some(3001)), 3000);
+        assert_eq!(plan.unit(plan.other_port(3000)), "app@3001");
+
+        assert_eq!(
+            parse_upstream_port("proxy_pass http://127.0.0.1:3001\n"),
+            Some(3001)
+        );
+        assert_eq!(parse_upstream_port(""), None);
+        assert_eq!(
+            with_instance_port("/usr/bin/env bun run start"),
+            "/usr/bin/env PORT=%i bun run start"
+        );
+
+        let no_domain = HostConfig {
+            domain: None,
+            ..cfg.clone()
+        };
+        assert!(SwapPlan::from_config("app", &no_domain).is_err());
+        let unknown = HostConfig {
+            strategy: Some("blue-green".to_string()),
+            ..cfg
+        };
+        assert!(host_strategy(&unknown).is_err());
+    }
+
+    #[test]
+    fn unit_drift_ignores_trailing_whitespace_and_diffs_changes() {
+        let expected = "[Service]\nExecStart=/opt/app/server\nRestart=always\n";
+        assert_eq!(unit_drift(expected, None), UnitDrift::Missing);
+        assert_eq!(
+            unit_drift(
+                expected,
+                Some("[Service]  \nExecStart=/opt/app/server\nRestart=always\n\n")
+            ),
+            UnitDrift::InSync
+        );
+        let UnitDrift::Differs(diff) = unit_drift(
+            expected,
+            Some("[Service]\nExecStart=/opt/app/old\nRestart=always\n"),
+        ) else {
+            panic!("expected a diff");
+        };
+        assert_eq!(
+            indent_diff(&diff),
+            "      - ExecStart=/opt/app/old\n      + ExecStart=/opt/app/server\n"
+        );
+    }
+
+    #[test]
+    fn env_key_drift_reports_missing_and_extra_keys() {
+        let expected = vec!["DATABASE_URL".to_string(), "API_KEY".to_string()];
+        let (missing, extra) = env_key_drift(&expected, "DATABASE_URL\nDEBUG\n");
+        assert_eq!(missing, vec!["API_KEY".to_string()]);
+        assert_eq!(extra, vec!["DEBUG".to_string()]);
+    }
+
+    #[test]
+    fn ssh_options_share_one_control_master() {
+        let options = ssh_options();
+        assert!(options.contains(&"ControlMaster=auto".to_string()));
+        assert!(
+            options
+                .iter()
+                .any(|opt| opt.starts_with("ControlPath=") && opt.ends_with("%C"))
+        );
+    }
+
+    #[test]
+    fn env_fetch_script_retries_without_python() {
+        let script = env_fetch_script(
+            "/opt/app",
+            "https://myflow.sh/",
+            "app",
+            "production",
+            &["API_KEY".to_string(), "DB_URL".to_string()],
+        );
+        assert!(!script.contains("python3"));
+        assert!(script.contains(
+            r#"API_URL="https://myflow.sh/api/env/app?environment=production&keys=API_KEY,DB_URL""#
+        ));
+        assert!(script.contains(r#"MAX_WAIT="${FLOW_ENV_FETCH_MAX_WAIT:-60}""#));
+        assert!(script.contains("-w '%{http_code}'"));
+        assert!(script.contains(r#"PARSER="/opt/app/env-json.awk""#));
+    }
+
+    #[test]
+    fn env_json_awk_writes_dotenv_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let parser = dir.path().join("env-json.awk");
+        fs::write(&parser, ENV_JSON_AWK).unwrap();
+        let body = dir.path().join("body.json");
+        fs::write(
+            &body,
+            r#"{"project": "app", "env": {"API_KEY": "sk-\"1\"", "NESTED": {"X": "y"},
+                "MULTI": "a\nb\\c", "PORT": 3000, "bad-key": "z", "NAME": "café"}}"#,
+        )
+        .unwrap();
+        let output = Command::new("awk")
+            .env("LC_ALL", "C")
+            .arg("-f")
+            .arg(&parser)
+            .arg(&body)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            "API_KEY=\"sk-\\\"1\\\"\"\nMULTI=\"a\nb\\\\c\"\nNAME=\"café\"\n"
+        );
+
+        fs::write(&body, r#"{"error": "not found"}"#).unwrap();
+        let status = Command::new("awk")
+            .arg("-f")
+            .arg(&parser)
+            .arg(&body)
+            .status()
+            .unwrap();
+        assert!(!status.success());
+    }
+}
@@ -0,0 +1,412 @@
+//! Audit log of the remote commands a deploy runs.
+//!
+//! Each host deploy (and `f deploy restart` / `stop`) writes `.flow/deploys/<id>.log` in
+//! the project root: one JSON line per ssh/rsync/scp invocation with its start time,
+//! duration, and exit code. `f deploy audit <id>` replays it. Destructive operations ask
+//! for confirmation first when running interactively.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, bail};
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::cli::DeployAuditOpts;
+use crate::secret_redact;
+
+/// Remote operations that delete or stop things on the host.
+pub const DESTRUCTIVE_OPERATIONS: &[&str] = &["rsync --delete", "systemctl stop"];
+
+/// One line of a deploy audit file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuditEvent {
+    Start {
+        id: String,
+        label: String,
+        target: String,
+        ts: u64,
+    },
+    Command {
+        kind: String,
+        command: String,
+        ts: u64,
+        duration_ms: u64,
+        exit_code: Option<i32>,
+    },
+    Confirm {
+        operation: String,
+        approved: bool,
+        ts: u64,
+    },
+    Finish {
+        ok: bool,
+        ts: u64,
+    },
+}
+
+struct ActiveAudit {
+    file: File,
+    allowed: Vec<String>,
+    assume_yes: bool,
+}
+
+static ACTIVE: Mutex<Option<ActiveAudit>> = Mutex::new(None);
+
+/// An open deploy audit. Remote helpers record into it while it lives; call
+/// [`finish`](Self::finish) with the outcome, or drop it to record a failure.
+pub struct AuditSession {
+    pub id: String,
+    pub path: PathBuf,
+    finished: bool,
+}
+
+/// Where a project's deploy audit files live.
+pub fn audit_dir(project_root: &Path) -> PathBuf {
+    project_root.join(".flow").join("deploys")
+}
+
+/// Start auditing a deploy of `label` to `target` (`user@host`).
+/// `allowed` lists destructive operations pre-approved by `[host] allow_destructive`.
+pub fn begin(
+    project_root: &Path,
+    label: &str,
+    target: &str,
+    allowed: &[String],
+    assume_yes: bool,
+) -> Result<AuditSession> {
+    let dir = audit_dir(project_root);
+    fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let id = format!(
+        "{}-{}",
+        Local::now().format("%Y%m%d-%H%M%S"),
+        label.replace([':', '/', ' '], "-")
+    );
+    let path = dir.join(format!("{id}.log"));
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+
+    let mut active = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+    *active = Some(ActiveAudit {
+        file,
+        allowed: allowed.to_vec(),
+        assume_yes,
+    });
+    drop(active);
+
+    write_event(&AuditEvent::Start {
+        id: id.clone(),
+        label: label.to_string(),
+        target: target.to_string(),
+        ts: now_ms(),
+    });
+    Ok(AuditSession {
+        id,
+        path,
+        finished: false,
+    })
+}
+
+impl AuditSession {
+    pub fn finish(mut self, ok: bool) {
+        self.close(ok);
+    }
+
+    fn close(&mut self, ok: bool) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        write_event(&AuditEvent::Finish { ok, ts: now_ms() });
+        *ACTIVE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+impl Drop for AuditSession {
+    fn drop(&mut self) {
+        self.close(false);
+    }
+}
+
+/// Record a remote command that started at `started`. No-op outside an audit session.
+pub fn record_command(kind: &str, command: &str, started: Instant, exit_code: Option<i32>) {
+    let elapsed = started.elapsed();
+    write_event(&AuditEvent::Command {
+        kind: kind.to_string(),
+        command: secret_redact::redact_text(command),
+        ts: now_ms().saturating_sub(elapsed.as_millis() as u64),
+        duration_ms: elapsed.as_millis() as u64,
+        exit_code,
+    });
+}
+
+/// Ask before a destructive `operation` (one of [`DESTRUCTIVE_OPERATIONS`]).
+/// Proceeds without asking when stdin is not a terminal, `--yes` was passed, or the
+/// operation is allowlisted; a declined prompt aborts the deploy.
+pub fn confirm_destructive(operation: &str, detail: &str) -> Result<()> {
+    debug_assert!(DESTRUCTIVE_OPERATIONS.contains(&operation));
+    let (allowed, assume_yes) = {
+        let active = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+        match active.as_ref() {
+            Some(active) => (
+                active.allowed.iter().any(|allowed| allowed == operation),
+                active.assume_yes,
+            ),
+            None => (false, false),
+        }
+    };
+    if allowed || assume_yes || !std::io::stdin().is_terminal() {
+        return Ok(());
+    }
+
+    print!("⚠ {detail}\n  Run `{operation}` on the host? [y/N]: ");
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    let answer = input.trim().to_ascii_lowercase();
+    let approved = answer == "y" || answer == "yes";
+    write_event(&AuditEvent::Confirm {
+        operation: operation.to_string(),
+        approved,
+        ts: now_ms(),
+    });
+    if !approved {
+        bail!(
+            "Aborted before `{operation}`. Pass --yes or add it to [host] allow_destructive to skip this prompt."
+        );
+    }
+    Ok(())
+}
+
+fn write_event(event: &AuditEvent) {
+    let mut active = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(active) = active.as_mut() else {
+        return;
+    };
+    let Ok(mut line) = serde_json::to_string(event) else {
+        return;
+    };
+    line.push('\n');
+    if let Err(err) = active.file.write_all(line.as_bytes()) {
+        tracing::warn!(?err, "failed to write deploy audit");
+    }
+}
+
+/// `f deploy audit [id]`: list audits, or replay one.
+pub fn run(project_root: &Path, opts: DeployAuditOpts) -> Result<()> {
+    let dir = audit_dir(project_root);
+    let Some(id) = opts.id else {
+        return list(&dir, opts.limit);
+    };
+    let path = resolve(&dir, &id)?;
+    let events = read_events(&path)?;
+    print!("{}", render(&events));
+    Ok(())
+}
+
+fn list(dir: &Path, limit: usize) -> Result<()> {
+    let mut ids: Vec<String> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| name.strip_suffix(".log"))
+                    .map(str::to_string)
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    if ids.is_empty() {
+        println!("No deploy audits in {}.", dir.display());
+        return Ok(());
+    }
+    ids.sort_by(|a, b| b.cmp(a));
+    for id in ids.into_iter().take(limit.max(1)) {
+        println!("{id}");
+    }
+    println!("\nReplay one with: f deploy audit <id>");
+    Ok(())
+}
+
+/// Resolve an exact id, or a unique prefix (e.g. `20261015`), to its audit file.
+fn resolve(dir: &Path, id: &str) -> Result<PathBuf> {
+    let exact = dir.join(format!("{id}.log"));
+    if exact.exists() {
+        return Ok(exact);
+    }
+    let matches: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.starts_with(id) && name.ends_with(".log"))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    match matches.len() {
+        1 => Ok(matches.into_iter().next().unwrap()),
+        0 => bail!("No deploy audit '{}' in {}", id, dir.display()),
+        n => bail!("'{}' matches {} deploy audits; use a longer id", id, n),
+    }
+}
+
+fn read_events(path: &Path) -> Result<Vec<AuditEvent>> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut events = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(event) => events.push(event),
+            Err(err) => tracing::debug!(?err, "skipping malformed audit line"),
+        }
+    }
+    Ok(events)
+}
+
+fn render(events: &[AuditEvent]) -> String {
+    let mut out = String::new();
+    let mut started = None;
+    for event in events {
+        match event {
+            AuditEvent::Start {
+                id,
+                label,
+                target,
+                ts,
+            } => {
+                started = Some(*ts);
+                out.push_str(&format!(
+                    "Deploy {id} ({label}) to {target} at {}\n",
+                    format_ts(*ts, "%Y-%m-%d %H:%M:%S")
+                ));
+            }
+            AuditEvent::Command {
+                kind,
+                command,
+                ts,
+                duration_ms,
+                exit_code,
+            } => {
+                let exit = match exit_code {
+                    Some(code) => format!("exit {code}"),
+                    None => "no exit".to_string(),
+                };
+                out.push_str(&format!(
+                    "  {}  {:<5} {:>6.1}s  {:<7}  {}\n",
+                    format_ts(*ts, "%H:%M:%S"),
+                    kind,
+                    *duration_ms as f64 / 1000.0,
+                    exit,
+                    one_line(command)
+                ));
+            }
+            AuditEvent::Confirm {
+                operation,
+                approved,
+                ts,
+            } => {
+                let answer = if *approved { "approved" } else { "declined" };
+                out.push_str(&format!(
+                    "  {}  confirm  {operation}: {answer}\n",
+                    format_ts(*ts, "%H:%M:%S")
+                ));
+            }
+            AuditEvent::Finish { ok, ts } => {
+                let total = started
+                    .map(|start| format!(" in {:.1}s", ts.saturating_sub(start) as f64 / 1000.0))
+                    .unwrap_or_default();
+                let result = if *ok { "ok" } else { "failed" };
+                out.push_str(&format!("Result: {result}{total}\n"));
+            }
+        }
+    }
+    out
+}
+
+/// First line of a command, marking that multi-line payloads (unit files) were cut.
+fn one_line(command: &str) -> String {
+    let mut lines = command.lines();
+    let first = lines.next().unwrap_or_default();
+    if lines.next().is_some() {
+        format!("{first} …")
+    } else {
+        first.to_string()
+    }
+}
+
+fn format_ts(ms: u64, format: &str) -> String {
+    Local
+        .timestamp_millis_opt(ms as i64)
+        .single()
+        .map(|time| time.format(format).to_string())
+        .unwrap_or_else(|| ms.to_string())
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_records_commands_and_replays() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let session = begin(dir.path(), "deploy:host", "deploy@box", &[], true).unwrap();
+        let path = session.path.clone();
+        let started = Instant::now();
+        record_command("ssh", "mkdir -p /opt/app", started, Some(0));
+        record_command(
+            "rsync",
+            "rsync -avz --delete ./ deploy@box:/opt/app",
+            started,
+            Some(23),
+        );
+        confirm_destructive("systemctl stop", "Stop the service").unwrap();
+        session.finish(true);
+
+        // Outside a session nothing is written.
+        record_command("ssh", "true", Instant::now(), Some(0));
+
+        let events = read_events(&path).unwrap();
+        assert_eq!(events.len(), 4);
+        let replay = render(&events);
+        assert!(replay.contains("(deploy:host) to deploy@box"));
+        assert!(replay.contains("exit 0   mkdir -p /opt/app"));
+        assert!(replay.contains("exit 23"));
+        assert!(replay.contains("Result: ok"));
+
+        let id = path.file_stem().unwrap().to_str().unwrap();
+        assert!(id.ends_with("-deploy-host"));
+        assert_eq!(resolve(&audit_dir(dir.path()), id).unwrap(), path);
+    }
+
+    #[test]
+    fn one_line_marks_truncated_payloads() {
+        assert_eq!(one_line("echo hi"), "echo hi");
+        assert_eq!(
+            one_line("echo \"[Unit]\nDescription=x\""),
+            "echo \"[Unit] …"
+        );
+    }
+}
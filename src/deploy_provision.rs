@@ -0,0 +1,212 @@
+//! Host provisioning for `f deploy host --provision`.
+//!
+//! Prepares a fresh host before the first sync: a system user for the service, `dest` owned
+//! by it, apt packages from `[host] packages`, and a firewall rule for the port traffic arrives
+//! on. Every step is a root shell command that is safe to run again; the plan is printed
+//! before anything runs.
+
+use anyhow::{Context, Result, bail};
+
+use crate::deploy::HostConfig;
+
+/// One root command of the plan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvisionStep {
+    pub summary: String,
+    pub command: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvisionPlan {
+    pub steps: Vec<ProvisionStep>,
+}
+
+impl ProvisionPlan {
+    /// Build the plan for `cfg`. `login_user` is the SSH user; unless it is root it keeps
+    /// owning `dest` so rsync can write there, and the service user gets group access.
+    pub fn new(cfg: &HostConfig, dest: &str, login_user: &str) -> Result<Self> {
+        let user = cfg
+            .user
+            .as_deref()
+            .context("--provision needs [host] user = \"<name>\" for the service to run as")?;
+        validate_user(user)?;
+        for package in &cfg.packages {
+            validate_package(package)?;
+        }
+
+        let quoted_dest = shell_words::quote(dest);
+        let owner = if login_user == "root" {
+            user
+        } else {
+            login_user
+        };
+        let mut steps = vec![
+            ProvisionStep {
+                summary: format!("Create system user '{user}' (no login shell)"),
+                command: format!(
+                    "id -u {user} >/dev/null 2>&1 || useradd --system --no-create-home \
+                     --home-dir {quoted_dest} --shell /usr/sbin/nologin {user}"
+                ),
+            },
+            ProvisionStep {
+                summary: format!("Create {dest} owned by {owner}:{user}, closed to other users"),
+                command: format!(
+                    "mkdir -p {quoted_dest} && chown -R {owner}:{user} {quoted_dest} \
+                     && chmod -R u=rwX,g=rX,o= {quoted_dest}"
+                ),
+            },
+        ];
+
+        if !cfg.packages.is_empty() {
+            let packages = shell_words::join(&cfg.packages);
+            steps.push(ProvisionStep {
+                summary: format!("Install apt packages: {}", cfg.packages.join(", ")),
+                command: format!(
+                    "command -v apt-get >/dev/null 2>&1 || {{ echo 'apt-get not found; install {packages} manually' >&2; exit 1; }}; \
+                     DEBIAN_FRONTEND=noninteractive apt-get update -q \
+                     && DEBIAN_FRONTEND=noninteractive apt-get install -y -q {packages}"
+                ),
+            });
+        }
+
+        let ports = firewall_ports(cfg);
+        if !ports.is_empty() {
+            let list = ports
+                .iter()
+                .map(|port| format!("{port}/tcp"))
+                .collect::<Vec<_>>();
+            let ufw = list
+                .iter()
+                .map(|port| format!("ufw allow {port}"))
+                .collect::<Vec<_>>()
+                .join(" && ");
+            let firewalld = list
+                .iter()
+                .map(|port| format!("firewall-cmd --permanent --add-port={port}"))
+                .collect::<Vec<_>>()
+                .join(" && ");
+            steps.push(ProvisionStep {
+                summary: format!("Allow {} through ufw or firewalld", list.join(", ")),
+                command: format!(
+                    "if command -v ufw >/dev/null 2>&1; then {ufw}; \
+                     elif command -v firewall-cmd >/dev/null 2>&1 && firewall-cmd --state >/dev/null 2>&1; \
+                     then {firewalld} && firewall-cmd --reload; \
+                     else echo 'no ufw or firewalld found; skipping firewall rules' >&2; fi"
+                ),
+            });
+        }
+
+        Ok(Self { steps })
+    }
+
+    pub fn print(&self, target: &str) {
+        println!("Provisioning plan for {target}:");
+        for (idx, step) in self.steps.iter().enumerate() {
+            println!("  {}. {}", idx + 1, step.summary);
+            println!("     $ {}", step.command);
+        }
+    }
+}
+
+/// Ports opened in the firewall: 80 (and 443 with `ssl`) when nginx fronts the service,
+/// otherwise the service port itself.
+fn firewall_ports(cfg: &HostConfig) -> Vec<u16> {
+    if cfg.domain.is_some() {
+        if cfg.ssl { vec![80, 443] } else { vec![80] }
+    } else {
+        cfg.port.into_iter().collect()
+    }
+}
+
+fn validate_user(name: &str) -> Result<()> {
+    let mut chars = name.chars();
+    let valid = name.len() <= 32
+        && chars
+            .next()
+            .is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+    if !valid {
+        bail!("[host] user '{name}' is not a valid system user name (lowercase, digits, '_', '-')");
+    }
+    Ok(())
+}
+
+fn validate_package(name: &str) -> Result<()> {
+    let valid = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "+-.:=~".contains(c));
+    if !valid {
+        bail!("[host] packages: '{name}' is not a valid apt package name");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plans_user_dest_packages_and_firewall() {
+        let cfg = HostConfig {
+            user: Some("myapp".to_string()),
+            packages: vec!["nginx".to_string(), "libssl3".to_string()],
+            port: Some(3000),
+            domain: Some("myapp.example.com".to_string()),
+            ssl: true,
+            ..Default::default()
+        };
+        let plan = ProvisionPlan::new(&cfg, "/opt/myapp", "deploy").unwrap();
+        assert_eq!(plan.steps.len(), 4);
+        assert!(plan.steps[0].command.starts_with("id -u myapp"));
+        assert!(
+            plan.steps[1]
+                .command
+                .contains("chown -R deploy:myapp /opt/myapp")
+        );
+        assert!(
+            plan.steps[2]
+                .command
+                .ends_with("install -y -q nginx libssl3")
+        );
+        assert!(
+            plan.steps[3]
+                .command
+                .contains("ufw allow 80/tcp && ufw allow 443/tcp")
+        );
+
+        let direct = HostConfig {
+            domain: None,
+            packages: Vec::new(),
+            ..cfg.clone()
+        };
+        let plan = ProvisionPlan::new(&direct, "/opt/my app", "root").unwrap();
+        assert_eq!(plan.steps.len(), 3);
+        assert!(
+            plan.steps[1]
+                .command
+                .contains("chown -R myapp:myapp '/opt/my app'")
+        );
+        assert!(plan.steps[2].command.contains("ufw allow 3000/tcp;"));
+    }
+
+    #[test]
+    fn rejects_missing_or_unsafe_names() {
+        let cfg = HostConfig::default();
+        assert!(ProvisionPlan::new(&cfg, "/opt/app", "root").is_err());
+        let bad_user = HostConfig {
+            user: Some("My App".to_string()),
+            ..Default::default()
+        };
+        assert!(ProvisionPlan::new(&bad_user, "/opt/app", "root").is_err());
+        let bad_package = HostConfig {
+            user: Some("app".to_string()),
+            packages: vec!["curl; rm -rf /".to_string()],
+            ..Default::default()
+        };
+        assert!(ProvisionPlan::new(&bad_package, "/opt/app", "root").is_err());
+    }
+}
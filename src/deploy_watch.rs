@@ -0,0 +1,607 @@
+//! Watchdog for deployed services.
+//!
+//! The hub (`f hub run`) polls the `[host]` services of every registered project (HTTP when a
+//! domain is set, otherwise `systemctl is-active` over SSH to the project's host), records
+//! uptime history in flow.db, and raises alerts when a service goes down or recovers.
+//! `f deploy watch` shows the history.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use rusqlite::{Connection, params};
+use serde::Serialize;
+
+use crate::cli::DeployWatchOpts;
+use crate::config::NotifyEvent;
+use crate::deploy::{self, HostConfig, HostConnection};
+use crate::notify::{self, AlertKind};
+use crate::{config, db, notify_rules, projects, running};
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+const SSH_CONNECT_TIMEOUT_SECS: u32 = 10;
+const UPTIME_WINDOW_MS: i64 = 24 * 60 * 60 * 1000;
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+/// Checks older than this are deleted.
+const HISTORY_MS: i64 = 30 * UPTIME_WINDOW_MS;
+/// Most checks kept per service.
+const MAX_CHECKS_PER_SERVICE: i64 = 5000;
+
+/// How a service is checked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CheckMethod {
+    Http(String),
+    Systemd(String),
+}
+
+impl CheckMethod {
+    fn label(&self) -> &'static str {
+        match self {
+            CheckMethod::Http(_) => "http",
+            CheckMethod::Systemd(_) => "ssh",
+        }
+    }
+
+    fn target(&self) -> &str {
+        match self {
+            CheckMethod::Http(url) | CheckMethod::Systemd(url) => url,
+        }
+    }
+}
+
+/// A deployed service discovered from a registered project.
+#[derive(Debug, Clone)]
+struct WatchTarget {
+    project: String,
+    service: String,
+    method: CheckMethod,
+    /// flow.toml of the project, for its `[notify]` rules.
+    config_path: PathBuf,
+    /// SSH host of the project, for systemd checks.
+    conn: Option<HostConnection>,
+}
+
+/// Result of a single health check.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthCheck {
+    pub project: String,
+    pub service: String,
+    pub method: String,
+    pub target: String,
+    pub ok: bool,
+    pub detail: String,
+    pub latency_ms: i64,
+    pub checked_ms: i64,
+}
+
+/// Latest status plus recent uptime for one service.
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceStatus {
+    #[serde(flatten)]
+    pub last: HealthCheck,
+    /// Percentage of successful checks over the last 24 hours.
+    pub uptime_24h: Option<f64>,
+}
+
+/// Handle `f deploy watch`.
+pub fn run(opts: DeployWatchOpts) -> Result<()> {
+    let conn = open_watch_db()?;
+    if opts.poll {
+        let targets = discover_targets()?;
+        if targets.is_empty() && !opts.json {
+            println!("No deployed services found in registered projects.");
+            println!("Add [host] with `service` or `domain` to a project's flow.toml.");
+            return Ok(());
+        }
+        poll_once(&conn, &targets)?;
+    }
+
+    let statuses = latest_statuses(&conn, running::now_ms() as i64)?;
+    if opts.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&statuses).context("failed to encode watch JSON")?
+        );
+        return Ok(());
+    }
+    print_statuses(&statuses);
+    Ok(())
+}
+
+/// Start the hub's watcher. Errors are logged and retried on the next tick.
+pub fn spawn_watcher() -> thread::JoinHandle<()> {
+    thread::spawn(|| {
+        loop {
+            let started = Instant::now();
+            if let Err(err) = watch_once() {
+                eprintln!("WARN deploy watch poll failed: {err:#}");
+            }
+            if let Some(remaining) = POLL_INTERVAL.checked_sub(started.elapsed()) {
+                thread::sleep(remaining);
+            }
+        }
+    })
+}
+
+fn watch_once() -> Result<()> {
+    // Re-discover each round so newly registered projects are picked up.
+    let targets = discover_targets()?;
+    if targets.is_empty() {
+        return Ok(());
+    }
+    let conn = open_watch_db()?;
+    poll_once(&conn, &targets)?;
+    prune(&conn, running::now_ms() as i64)
+}
+
+/// Check every target once, record results, and notify on state transitions.
+fn poll_once(conn: &Connection, targets: &[WatchTarget]) -> Result<Vec<HealthCheck>> {
+    let mut results = Vec::with_capacity(targets.len());
+    for target in targets {
+        let previous = last_check(conn, &target.project, &target.service)?;
+        let check = check_target(target);
+        insert_check(conn, &check)?;
+        if let Some(message) = transition_message(previous.as_ref(), &check) {
+            let kind = if check.ok {
+                AlertKind::Success
+            } else {
+                AlertKind::Error
+            };
+            eprintln!("{message}");
+            if let Err(err) = notify::send_alert(&message, kind) {
+                tracing::debug!(?err, "failed to send deploy watch alert");
+            }
+            notify_service_transition(target, &check);
+        }
+        results.push(check);
+    }
+    Ok(results)
+}
+
+/// Fire the project's `[notify]` service rules for a state change.
+fn notify_service_transition(target: &WatchTarget, check: &HealthCheck) {
+    let event = if check.ok {
+        NotifyEvent::ServiceRecovered
+    } else {
+        NotifyEvent::ServiceDown
+    };
+    let cwd = target.config_path.parent().unwrap_or(Path::new("."));
+    notify_rules::dispatch_from(
+        &target.config_path,
+        cwd,
+        &notify_rules::Notification {
+            event,
+            project: target.project.clone(),
+            subject: target.service.clone(),
+            detail: check.detail.clone(),
+        },
+    );
+}
+
+/// Alert text when a service changes state. The first check only alerts when it is down.
+fn transition_message(previous: Option<&HealthCheck>, current: &HealthCheck) -> Option<String> {
+    let was_ok = previous.map(|p| p.ok);
+    match (was_ok, current.ok) {
+        (Some(true), false) | (None, false) => Some(format!(
+            "✗ {}/{} is down: {}",
+            current.project, current.service, current.detail
+        )),
+        (Some(false), true) => Some(format!(
+            "✓ {}/{} recovered",
+            current.project, current.service
+        )),
+        _ => None,
+    }
+}
+
+fn discover_targets() -> Result<Vec<WatchTarget>> {
+    let mut targets = Vec::new();
+    for entry in projects::list_projects()? {
+        if !entry.config_path.exists() {
+            continue;
+        }
+        let Ok(cfg) = config::load(&entry.config_path) else {
+            continue;
+        };
+        let Some(host) = cfg.host.as_ref() else {
+            continue;
+        };
+        if let Some(mut target) = target_for_host(&entry.name, host, &entry.config_path) {
+            if matches!(target.method, CheckMethod::Systemd(_)) {
+                target.conn = deploy::host_connection(Some(host)).unwrap_or_else(|err| {
+                    tracing::warn!(?err, project = %entry.name, "invalid deploy host");
+                    None
+                });
+            }
+            targets.push(target);
+        }
+    }
+    Ok(targets)
+}
+
+fn target_for_host(project: &str, host: &HostConfig, config_path: &Path) -> Option<WatchTarget> {
+    let service = host.service.clone().unwrap_or_else(|| project.to_string());
+    let method = if let Some(domain) = host.domain.as_deref() {
+        let scheme = if host.ssl { "https" } else { "http" };
+        CheckMethod::Http(format!("{scheme}://{domain}"))
+    } else {
+        CheckMethod::Systemd(host.service.clone()?)
+    };
+    Some(WatchTarget {
+        project: project.to_string(),
+        service,
+        method,
+        config_path: config_path.to_path_buf(),
+        conn: None,
+    })
+}
+
+fn check_target(target: &WatchTarget) -> HealthCheck {
+    let started = Instant::now();
+    let (ok, detail) = match &target.method {
+        CheckMethod::Http(url) => check_http(url),
+        CheckMethod::Systemd(unit) => match &target.conn {
+            Some(conn) => check_systemd(conn, unit),
+            None => (
+                false,
+                "no deploy host configured (f deploy set-host)".to_string(),
+            ),
+        },
+    };
+    HealthCheck {
+        project: target.project.clone(),
+        service: target.service.clone(),
+        method: target.method.label().to_string(),
+        target: target.method.target().to_string(),
+        ok,
+        detail,
+        latency_ms: started.elapsed().as_millis() as i64,
+        checked_ms: running::now_ms() as i64,
+    }
+}
+
+fn check_http(url: &str) -> (bool, String) {
+    let client = match crate::http_client::blocking_with_timeout(HTTP_TIMEOUT) {
+        Ok(client) => client,
+        Err(err) => return (false, err.to_string()),
+    };
+    match client.get(url).send() {
+        Ok(resp) => {
+            let status = resp.status();
+            (
+                status.is_success() || status.is_redirection(),
+                format!("HTTP {}", status.as_u16()),
+            )
+        }
+        Err(err) => (false, format!("unreachable: {err}")),
+    }
+}
+
+fn check_systemd(conn: &HostConnection, unit: &str) -> (bool, String) {
+    let output = Command::new("ssh")
+        .args([
+            "-p",
+            &conn.port.to_string(),
+            "-o",
+            "BatchMode=yes",
+            "-o",
+            &format!("ConnectTimeout={SSH_CONNECT_TIMEOUT_SECS}"),
+            "-o",
+            "StrictHostKeyChecking=accept-new",
+            &conn.ssh_target(),
+            &format!("systemctl is-active {unit} 2>/dev/null || true"),
+        ])
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            let state = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            let state = if state.is_empty() {
+                "unknown".to_string()
+            } else {
+                state
+            };
+            (state == "active", state)
+        }
+        Ok(output) => {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            (false, format!("ssh failed: {stderr}"))
+        }
+        Err(err) => (false, format!("ssh failed: {err}")),
+    }
+}
+
+fn print_statuses(statuses: &[ServiceStatus]) {
+    if statuses.is_empty() {
+        println!("No health checks recorded yet. Run `f deploy watch --poll`.");
+        return;
+    }
+    println!(
+        "{:<20} {:<20} {:<6} {:<6} {:>8}  {:<10} DETAIL",
+        "PROJECT", "SERVICE", "STATE", "CHECK", "UPTIME", "CHECKED"
+    );
+    let now = running::now_ms() as i64;
+    for status in statuses {
+        let last = &status.last;
+        println!(
+            "{:<20} {:<20} {:<6} {:<6} {:>8}  {:<10} {}",
+            last.project,
+            last.service,
+            if last.ok { "up" } else { "DOWN" },
+            last.method,
+            status
+                .uptime_24h
+                .map(|pct| format!("{pct:.1}%"))
+                .unwrap_or_else(|| "-".to_string()),
+            format_age(now.saturating_sub(last.checked_ms)),
+            last.detail
+        );
+    }
+}
+
+pub(crate) fn format_age(elapsed_ms: i64) -> String {
+    let secs = elapsed_ms / 1000;
+    if secs < 60 {
+        format!("{secs}s ago")
+    } else if secs < 3600 {
+        format!("{}m ago", secs / 60)
+    } else if secs < 86400 {
+        format!("{}h ago", secs / 3600)
+    } else {
+        format!("{}d ago", secs / 86400)
+    }
+}
+
+fn open_watch_db() -> Result<Connection> {
+    let conn = db::open_db()?;
+    init_schema(&conn)?;
+    Ok(conn)
+}
+
+/// Initialize the deploy health history schema.
+pub fn init_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS deploy_health_checks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project TEXT NOT NULL,
+            service TEXT NOT NULL,
+            method TEXT NOT NULL,
+            target TEXT NOT NULL,
+            ok INTEGER NOT NULL,
+            detail TEXT NOT NULL,
+            latency_ms INTEGER NOT NULL,
+            checked_ms INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_deploy_health_service
+            ON deploy_health_checks(project, service, checked_ms);
+        "#,
+    )
+    .context("failed to create deploy health schema")?;
+    Ok(())
+}
+
+fn insert_check(conn: &Connection, check: &HealthCheck) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT INTO deploy_health_checks
+            (project, service, method, target, ok, detail, latency_ms, checked_ms)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+        "#,
+        params![
+            check.project,
+            check.service,
+            check.method,
+            check.target,
+            check.ok,
+            check.detail,
+            check.latency_ms,
+            check.checked_ms,
+        ],
+    )
+    .context("failed to insert deploy health check")?;
+    Ok(())
+}
+
+/// Delete checks older than [`HISTORY_MS`] and all but the newest [`MAX_CHECKS_PER_SERVICE`]
+/// of each service.
+fn prune(conn: &Connection, now_ms: i64) -> Result<()> {
+    conn.execute(
+        "DELETE FROM deploy_health_checks WHERE checked_ms < ?1",
+        params![now_ms - HISTORY_MS],
+    )
+    .context("failed to prune deploy health checks")?;
+    conn.execute(
+        r#"
+        DELETE FROM deploy_health_checks
+        WHERE id IN (
+            SELECT id FROM (
+                SELECT id, ROW_NUMBER() OVER (
+                    PARTITION BY project, service ORDER BY checked_ms DESC, id DESC
+                ) AS rank
+                FROM deploy_health_checks
+            )
+            WHERE rank > ?1
+        )
+        "#,
+        params![MAX_CHECKS_PER_SERVICE],
+    )
+    .context("failed to prune deploy health checks")?;
+    Ok(())
+}
+
+fn row_to_check(row: &rusqlite::Row<'_>) -> rusqlite::Result<HealthCheck> {
+    Ok(HealthCheck {
+        project: row.get(0)?,
+        service: row.get(1)?,
+        method: row.get(2)?,
+        target: row.get(3)?,
+        ok: row.get(4)?,
+        detail: row.get(5)?,
+        latency_ms: row.get(6)?,
+        checked_ms: row.get(7)?,
+    })
+}
+
+fn last_check(conn: &Connection, project: &str, service: &str) -> Result<Option<HealthCheck>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT project, service, method, target, ok, detail, latency_ms, checked_ms
+        FROM deploy_health_checks
+        WHERE project = ?1 AND service = ?2
+        ORDER BY checked_ms DESC
+        LIMIT 1
+        "#,
+    )?;
+    let mut rows = stmt.query_map(params![project, service], row_to_check)?;
+    Ok(rows.next().transpose()?)
+}
+
+/// Latest check per service with uptime over the trailing 24 hours.
+pub fn latest_statuses(conn: &Connection, now_ms: i64) -> Result<Vec<ServiceStatus>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT h.project, h.service, h.method, h.target, h.ok, h.detail, h.latency_ms, h.checked_ms
+        FROM deploy_health_checks h
+        JOIN (
+            SELECT project, service, MAX(checked_ms) AS latest
+            FROM deploy_health_checks
+            GROUP BY project, service
+        ) m ON h.project = m.project AND h.service = m.service AND h.checked_ms = m.latest
+        ORDER BY h.project, h.service
+        "#,
+    )?;
+    let latest: Vec<HealthCheck> = stmt
+        .query_map([], row_to_check)?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut uptime_stmt = conn.prepare(
+        r#"
+        SELECT COUNT(*), COALESCE(SUM(ok), 0)
+        FROM deploy_health_checks
+        WHERE project = ?1 AND service = ?2 AND checked_ms >= ?3
+        "#,
+    )?;
+    let mut statuses = Vec::with_capacity(latest.len());
+    for last in latest {
+        let (total, up): (i64, i64) = uptime_stmt.query_row(
+            params![last.project, last.service, now_ms - UPTIME_WINDOW_MS],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let uptime_24h = (total > 0).then(|| up as f64 * 100.0 / total as f64);
+        statuses.push(ServiceStatus { last, uptime_24h });
+    }
+    Ok(statuses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(project: &str, ok: bool, checked_ms: i64) -> HealthCheck {
+        HealthCheck {
+            project: project.to_string(),
+            service: "api".to_string(),
+            method: "http".to_string(),
+            target: "https://api.example.com".to_string(),
+            ok,
+            detail: if ok { "HTTP 200" } else { "HTTP 502" }.to_string(),
+            latency_ms: 12,
+            checked_ms,
+        }
+    }
+
+    #[test]
+    fn latest_statuses_reports_last_state_and_uptime() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let now = 10 * UPTIME_WINDOW_MS;
+        insert_check(&conn, &check("web", true, now - 3000)).unwrap();
+        insert_check(&conn, &check("web", true, now - 2000)).unwrap();
+        insert_check(&conn, &check("web", true, now - 1500)).unwrap();
+        insert_check(&conn, &check("web", false, now - 1000)).unwrap();
+        // Outside the uptime window.
+        insert_check(&conn, &check("web", false, now - UPTIME_WINDOW_MS - 1)).unwrap();
+
+        let statuses = latest_statuses(&conn, now).unwrap();
+        assert_eq!(statuses.len(), 1);
+        assert!(!statuses[0].last.ok);
+        assert_eq!(statuses[0].uptime_24h, Some(75.0));
+    }
+
+    #[test]
+    fn prune_drops_old_checks_and_caps_each_service() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let now = 100 * UPTIME_WINDOW_MS;
+        insert_check(&conn, &check("old", true, now - HISTORY_MS - 1)).unwrap();
+        for i in 0..MAX_CHECKS_PER_SERVICE + 3 {
+            insert_check(&conn, &check("web", true, now - 10_000 + i)).unwrap();
+        }
+        insert_check(&conn, &check("api", false, now - 5)).unwrap();
+
+        prune(&conn, now).unwrap();
+        let count = |project: &str| -> i64 {
+            conn.query_row(
+                "SELECT COUNT(*) FROM deploy_health_checks WHERE project = ?1",
+                params![project],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!(count("old"), 0);
+        assert_eq!(count("web"), MAX_CHECKS_PER_SERVICE);
+        assert_eq!(count("api"), 1);
+        let oldest_web: i64 = conn
+            .query_row(
+                "SELECT MIN(checked_ms) FROM deploy_health_checks WHERE project = 'web'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(oldest_web, now - 10_000 + 3);
+    }
+
+    #[test]
+    fn transitions_only_alert_on_state_changes() {
+        let up = check("web", true, 1);
+        let down = check("web", false, 2);
+        assert!(transition_message(None, &up).is_none());
+        assert!(transition_message(None, &down).is_some());
+        assert!(
+            transition_message(Some(&up), &down)
+                .unwrap()
+                .contains("down")
+        );
+        assert!(
+            transition_message(Some(&down), &up)
+                .unwrap()
+                .contains("recovered")
+        );
+        assert!(transition_message(Some(&down), &down).is_none());
+    }
+
+    #[test]
+    fn host_targets_prefer_http_domain() {
+        let host = HostConfig {
+            service: Some("api".to_string()),
+            domain: Some("api.example.com".to_string()),
+            ssl: true,
+            ..Default::default()
+        };
+        let target = target_for_host("web", &host, Path::new("flow.toml")).unwrap();
+        assert_eq!(
+            target.method,
+            CheckMethod::Http("https://api.example.com".to_string())
+        );
+
+        let host = HostConfig {
+            service: Some("api".to_string()),
+            ..Default::default()
+        };
+        let target = target_for_host("web", &host, Path::new("flow.toml")).unwrap();
+        assert_eq!(target.method, CheckMethod::Systemd("api".to_string()));
+        assert!(target_for_host("web", &HostConfig::default(), Path::new("flow.toml")).is_none());
+    }
+}
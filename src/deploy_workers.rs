@@ -0,0 +1,272 @@
+//! Background workers for `[host]` deploys.
+//!
+//! Each `[[host.workers]]` entry becomes a systemd unit named `{service}-{name}` next to the
+//! main service. A worker without `schedule` is a long-running service restarted like the web
+//! process; one with `schedule` is a oneshot service fired by a `.timer` unit on that
+//! `OnCalendar` expression. `f deploy restart`, `stop`, `status`, `logs`, and `drift` cover
+//! the workers together with the main service.
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::deploy::{UnitEnv, normalize_exec_start};
+
+/// One `[[host.workers]]` entry.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct WorkerConfig {
+    /// Suffix of the unit name (`{service}-{name}`).
+    pub name: String,
+    /// Command to run, relative to `dest` like `[host] run`.
+    pub run: String,
+    /// systemd `OnCalendar` expression (e.g. "hourly", "*-*-* 03:00:00"); runs the command
+    /// as a job on that schedule instead of keeping it running.
+    pub schedule: Option<String>,
+}
+
+/// systemd unit names for one worker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerUnits {
+    pub name: String,
+    /// `{service}-{name}.service`
+    pub service: String,
+    /// `{service}-{name}.timer` for scheduled workers.
+    pub timer: Option<String>,
+}
+
+impl WorkerUnits {
+    pub fn new(service: &str, worker: &WorkerConfig) -> Self {
+        let unit = format!("{service}-{}", worker.name.trim());
+        Self {
+            name: worker.name.trim().to_string(),
+            service: format!("{unit}.service"),
+            timer: worker.schedule.as_ref().map(|_| format!("{unit}.timer")),
+        }
+    }
+
+    /// The unit enabled and restarted on deploy: the timer for scheduled jobs, otherwise the
+    /// service.
+    pub fn control(&self) -> &str {
+        self.timer.as_deref().unwrap_or(&self.service)
+    }
+}
+
+/// Unit names of every worker of `service`.
+pub fn worker_units(service: &str, workers: &[WorkerConfig]) -> Vec<WorkerUnits> {
+    workers
+        .iter()
+        .map(|worker| WorkerUnits::new(service, worker))
+        .collect()
+}
+
+pub fn validate(workers: &[WorkerConfig]) -> Result<()> {
+    let mut seen: Vec<&str> = Vec::new();
+    for worker in workers {
+        let name = worker.name.trim();
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+        {
+            bail!("[[host.workers]] name '{name}' must be letters, digits, '-' or '_'");
+        }
+        if seen.contains(&name) {
+            bail!("[[host.workers]] name '{name}' is used twice");
+        }
+        seen.push(name);
+        if worker.run.trim().is_empty() {
+            bail!("[[host.workers]] '{name}' needs a run command");
+        }
+        if worker.run.contains(['\n', '\r']) {
+            bail!("[[host.workers]] '{name}' run command must be a single line");
+        }
+        if let Some(schedule) = &worker.schedule
+            && (schedule.trim().is_empty() || schedule.contains('\n'))
+        {
+            bail!("[[host.workers]] '{name}' has an invalid schedule '{schedule}'");
+        }
+    }
+    Ok(())
+}
+
+/// Shell command listing the installed worker unit files of `service`, one path per line.
+/// Units are recognised by the `Description=` line [`render`] writes, so another service
+/// whose name merely starts with `{service}-` is left alone.
+pub fn list_installed_command(service: &str, unit_dir: &str) -> String {
+    format!(
+        "grep -lF -e 'Description={service} worker ' -e 'Description={service} job ' \
+         {unit_dir}/{service}-*.service {unit_dir}/{service}-*.timer 2>/dev/null || true"
+    )
+}
+
+/// Installed worker unit files (as listed by [`list_installed_command`]) that no longer
+/// belong to any configured worker.
+pub fn stale_units(service: &str, workers: &[WorkerConfig], installed: &str) -> Vec<String> {
+    let current: Vec<WorkerUnits> = worker_units(service, workers);
+    let mut stale: Vec<String> = installed
+        .lines()
+        .filter_map(|line| line.trim().rsplit('/').next())
+        .filter(|file| !file.is_empty())
+        .filter(|file| {
+            !current
+                .iter()
+                .any(|units| units.service == *file || units.timer.as_deref() == Some(*file))
+        })
+        .map(str::to_string)
+        .collect();
+    stale.sort();
+    stale.dedup();
+    stale
+}
+
+/// Unit file names and contents for `worker`: its service, then its timer when scheduled.
+/// `env` carries the working directory, user, and env file lines of the main service.
+pub fn render(
+    service: &str,
+    workdir: &str,
+    worker: &WorkerConfig,
+    env: &UnitEnv,
+) -> Vec<(String, String)> {
+    let units = WorkerUnits::new(service, worker);
+    let exec_start = normalize_exec_start(workdir, &worker.run);
+    let UnitEnv {
+        user_line,
+        exec_start_pre,
+        env_file_line,
+    } = env;
+    let name = &units.name;
+
+    let Some(schedule) = worker.schedule.as_deref().map(str::trim) else {
+        let unit = format!(
+            r#"[Unit]
+Description={service} worker {name}
+After=network.target
+
+[Service]
+Type=simple
+WorkingDirectory={workdir}
+{user_line}
+{exec_start_pre}
+ExecStart={exec_start}
+Restart=always
+RestartSec=5
+{env_file_line}
+
+[Install]
+WantedBy=multi-user.target
+"#
+        );
+        return vec![(units.service, unit)];
+    };
+
+    let job = format!(
+        r#"[Unit]
+Description={service} job {name}
+After=network.target
+
+[Service]
+Type=oneshot
+WorkingDirectory={workdir}
+{user_line}
+{exec_start_pre}
+ExecStart={exec_start}
+{env_file_line}
+"#
+    );
+    let timer = format!(
+        r#"[Unit]
+Description={service} job {name} schedule
+
+[Timer]
+OnCalendar={schedule}
+Persistent=true
+Unit={job_unit}
+
+[Install]
+WantedBy=timers.target
+"#,
+        job_unit = units.service
+    );
+    let timer_file = units.timer.clone().unwrap_or_default();
+    vec![(units.service, job), (timer_file, timer)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn worker(name: &str, run: &str, schedule: Option<&str>) -> WorkerConfig {
+        WorkerConfig {
+            name: name.to_string(),
+            run: run.to_string(),
+            schedule: schedule.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn renders_long_running_workers_and_scheduled_jobs() {
+        let env = UnitEnv {
+            user_line: "User=app\nGroup=app".to_string(),
+            exec_start_pre: String::new(),
+            env_file_line: "EnvironmentFile=/opt/app/.env".to_string(),
+        };
+
+        let queue = render(
+            "app",
+            "/opt/app",
+            &worker("queue", "bun run worker", None),
+            &env,
+        );
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue[0].0, "app-queue.service");
+        assert!(
+            queue[0]
+                .1
+                .contains("ExecStart=/usr/bin/env bun run worker\nRestart=always")
+        );
+        assert!(queue[0].1.contains("User=app"));
+
+        let cleanup = worker("cleanup", "./bin/cleanup --days 30", Some("daily"));
+        let units = WorkerUnits::new("app", &cleanup);
+        assert_eq!(units.control(), "app-cleanup.timer");
+        assert_eq!(units.timer.as_deref(), Some("app-cleanup.timer"));
+        let files = render("app", "/opt/app", &cleanup, &env);
+        assert_eq!(files[0].0, "app-cleanup.service");
+        assert!(files[0].1.contains("Type=oneshot"));
+        assert!(
+            files[0]
+                .1
+                .contains("ExecStart=/opt/app/./bin/cleanup --days 30")
+        );
+        assert!(!files[0].1.contains("Restart="));
+        assert_eq!(files[1].0, "app-cleanup.timer");
+        assert!(
+            files[1]
+                .1
+                .contains("OnCalendar=daily\nPersistent=true\nUnit=app-cleanup.service")
+        );
+    }
+
+    #[test]
+    fn rejects_bad_worker_definitions() {
+        assert!(validate(&[worker("queue", "bun run worker", None)]).is_ok());
+        assert!(validate(&[worker("my queue", "x", None)]).is_err());
+        assert!(validate(&[worker("q", "x", None), worker("q", "y", None)]).is_err());
+        assert!(validate(&[worker("q", "  ", None)]).is_err());
+        assert!(validate(&[worker("q", "x", Some(" "))]).is_err());
+        assert!(validate(&[worker("q", "x\nExecStartPost=/bin/sh", None)]).is_err());
+    }
+
+    #[test]
+    fn finds_units_of_removed_workers() {
+        let installed = "/etc/systemd/system/app-queue.service\n\
+                         /etc/systemd/system/app-old.service\n\
+                         /etc/systemd/system/app-report.service\n\
+                         /etc/systemd/system/app-report.timer\n";
+        let workers = [worker("queue", "bun run worker", None)];
+        assert_eq!(
+            stale_units("app", &workers, installed),
+            vec!["app-old.service", "app-report.service", "app-report.timer"]
+        );
+        assert!(stale_units("app", &workers, "").is_empty());
+    }
+}
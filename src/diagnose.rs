@@ -0,0 +1,407 @@
+//! Match failed task output against known error signatures.
+//!
+//! When a task exits non-zero, its output is checked for common, fixable causes
+//! (a missing binary, a port already in use, a lockfile conflict, a missing module)
+//! and a targeted suggestion is printed under the failure. `[diagnose.fixes]` maps a
+//! signature to a task that fixes it, which `f diagnose last --fix` runs.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result, bail};
+use regex::Regex;
+use serde::Serialize;
+
+use crate::cli::{DiagnoseAction, DiagnoseCommand, DiagnoseLastOpts, TaskRunOpts};
+use crate::{config, failure, tasks};
+
+/// A known class of task failure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Signature {
+    MissingBinary,
+    PortInUse,
+    LockfileConflict,
+    ModuleNotFound,
+}
+
+impl Signature {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Signature::MissingBinary => "missing_binary",
+            Signature::PortInUse => "port_in_use",
+            Signature::LockfileConflict => "lockfile_conflict",
+            Signature::ModuleNotFound => "module_not_found",
+        }
+    }
+}
+
+/// What a failure looks like and how to get past it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnosis {
+    pub signature: Signature,
+    pub summary: String,
+    pub suggestion: String,
+    /// Task from `[diagnose.fixes]` that fixes this failure.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix_task: Option<String>,
+}
+
+struct Pattern {
+    signature: Signature,
+    regex: Regex,
+}
+
+fn patterns() -> &'static [Pattern] {
+    static PATTERNS: OnceLock<Vec<Pattern>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        let table: &[(Signature, &str)] = &[
+            // sh/bash: `foo: command not found`, `sh: 1: foo: not found`; zsh: `command not found: foo`.
+            (
+                Signature::MissingBinary,
+                r"(?m)(?:^|\s)(?:[\w/.-]+: )?(?:line )?(?:\d+: )?(?P<name>[\w.+-]+): (?:command )?not found$",
+            ),
+            (
+                Signature::MissingBinary,
+                r"command not found: (?P<name>[\w.+-]+)",
+            ),
+            (
+                Signature::MissingBinary,
+                r"env: '?(?P<name>[\w.+-]+)'?: No such file or directory",
+            ),
+            (
+                Signature::PortInUse,
+                r"EADDRINUSE[^\n]*?:(?P<port>\d{2,5})\b",
+            ),
+            (
+                Signature::PortInUse,
+                r"(?i)(?:port|:)\s*(?P<port>\d{2,5})\s+(?:is\s+)?(?:already\s+in\s+use|in use)",
+            ),
+            (
+                Signature::PortInUse,
+                r"(?i)address already in use(?:[^\n]*?:(?P<port>\d{2,5})\b)?",
+            ),
+            (
+                Signature::LockfileConflict,
+                r"Unable to create '(?P<lock>[^']*index\.lock)': File exists",
+            ),
+            (
+                Signature::LockfileConflict,
+                r"Blocking waiting for file lock on (?P<lock>[^\n]+)",
+            ),
+            (
+                Signature::LockfileConflict,
+                r"(?i)(?P<lock>lockfile|bun\.lockb?|package-lock\.json|pnpm-lock\.yaml|yarn\.lock|Cargo\.lock)[^\n]*(?:frozen|out of date|not up to date|not in sync|needs to be updated|ERR_PNPM_OUTDATED_LOCKFILE)",
+            ),
+            (
+                Signature::LockfileConflict,
+                r"ERR_PNPM_OUTDATED_LOCKFILE|--frozen-lockfile|--locked was passed",
+            ),
+            (
+                Signature::ModuleNotFound,
+                r"Cannot find (?:module|package) '(?P<module>[^']+)'",
+            ),
+            (
+                Signature::ModuleNotFound,
+                r"No module named '?(?P<module>[\w.]+)'?",
+            ),
+            (
+                Signature::ModuleNotFound,
+                r"Module not found: (?:Error: )?Can't resolve '(?P<module>[^']+)'",
+            ),
+            (
+                Signature::ModuleNotFound,
+                r"no required module provides package (?P<module>\S+?);?\s",
+            ),
+        ];
+        table
+            .iter()
+            .map(|(signature, pattern)| Pattern {
+                signature: *signature,
+                regex: Regex::new(pattern).expect("diagnose pattern"),
+            })
+            .collect()
+    })
+}
+
+/// Diagnose `output`; each signature is reported at most once, in output order.
+pub fn analyze(output: &str) -> Vec<Diagnosis> {
+    let mut found: Vec<(usize, Diagnosis)> = Vec::new();
+    for pattern in patterns() {
+        if found
+            .iter()
+            .any(|(_, diagnosis)| diagnosis.signature == pattern.signature)
+        {
+            continue;
+        }
+        if let Some(caps) = pattern.regex.captures(output) {
+            let at = caps.get(0).map(|m| m.start()).unwrap_or(0);
+            let capture = |name: &str| caps.name(name).map(|m| m.as_str().trim().to_string());
+            found.push((at, describe(pattern.signature, capture, output)));
+        }
+    }
+    found.sort_by_key(|(at, _)| *at);
+    found.into_iter().map(|(_, diagnosis)| diagnosis).collect()
+}
+
+fn describe(
+    signature: Signature,
+    capture: impl Fn(&str) -> Option<String>,
+    output: &str,
+) -> Diagnosis {
+    let (summary, suggestion) = match signature {
+        Signature::MissingBinary => match capture("name") {
+            Some(name) => (
+                format!("`{name}` is not installed or not on PATH"),
+                format!(
+                    "Install {name} (e.g. add it to [deps] and run `f setup`), or check PATH in the task's shell."
+                ),
+            ),
+            None => (
+                "A required binary was not found".to_string(),
+                "Install the missing tool or fix PATH, then re-run the task.".to_string(),
+            ),
+        },
+        Signature::PortInUse => match capture("port") {
+            Some(port) => (
+                format!("Port {port} is already in use"),
+                format!(
+                    "Find the process with `lsof -nP -iTCP:{port} -sTCP:LISTEN` and stop it, or check `f ps` for a running task."
+                ),
+            ),
+            None => (
+                "The address is already in use".to_string(),
+                "Another process holds the port; check `f ps` or `lsof -nP -iTCP -sTCP:LISTEN`."
+                    .to_string(),
+            ),
+        },
+        Signature::LockfileConflict => lockfile_advice(capture("lock").as_deref(), output),
+        Signature::ModuleNotFound => {
+            let module = capture("module");
+            let summary = match &module {
+                Some(module) => format!("Module `{module}` could not be found"),
+                None => "A module could not be found".to_string(),
+            };
+            (summary, install_hint(output).to_string())
+        }
+    };
+    Diagnosis {
+        signature,
+        summary,
+        suggestion,
+        fix_task: None,
+    }
+}
+
+fn lockfile_advice(lock: Option<&str>, output: &str) -> (String, String) {
+    let lock = lock.unwrap_or_default();
+    if lock.ends_with("index.lock") {
+        return (
+            format!("Git index is locked ({lock})"),
+            format!(
+                "Make sure no other git process is running, then remove the stale lock: rm {lock}"
+            ),
+        );
+    }
+    if output.contains("Blocking waiting for file lock") {
+        return (
+            "Cargo is waiting on a lock held by another build".to_string(),
+            "Wait for the other cargo process (rust-analyzer, another task) or stop it."
+                .to_string(),
+        );
+    }
+    (
+        "The lockfile is out of sync with the manifest".to_string(),
+        format!(
+            "{} and commit the updated lockfile.",
+            install_hint(output).trim_end_matches('.')
+        ),
+    )
+}
+
+/// Best guess at the package manager command that restores dependencies.
+fn install_hint(output: &str) -> &'static str {
+    if output.contains("ModuleNotFoundError") || output.contains("No module named") {
+        "Install Python dependencies (e.g. `uv sync` or `pip install -r requirements.txt`)."
+    } else if output.contains("no required module provides package") {
+        "Run `go mod tidy` to add the missing module."
+    } else if output.contains("bun") {
+        "Run `bun install` to install dependencies."
+    } else if output.contains("pnpm") {
+        "Run `pnpm install` to install dependencies."
+    } else if output.contains("yarn") {
+        "Run `yarn install` to install dependencies."
+    } else if output.contains("Cargo.lock") || output.contains("cargo") {
+        "Run `cargo update -w` to refresh Cargo.lock."
+    } else {
+        "Install dependencies (e.g. `npm install`)."
+    }
+}
+
+/// Fill in `fix_task` from `[diagnose.fixes]`.
+pub fn attach_fixes(diagnoses: &mut [Diagnosis], fixes: &HashMap<String, String>) {
+    for diagnosis in diagnoses {
+        diagnosis.fix_task = fixes.get(diagnosis.signature.as_str()).cloned();
+    }
+}
+
+fn load_fixes(config_path: &Path) -> HashMap<String, String> {
+    if !config_path.exists() {
+        return HashMap::new();
+    }
+    config::load(config_path)
+        .ok()
+        .and_then(|cfg| cfg.diagnose)
+        .map(|diagnose| diagnose.fixes)
+        .unwrap_or_default()
+}
+
+/// Print suggestions for a failed task run; prints nothing when no signature matches.
+pub fn report_task_failure(output: &str, config_path: &Path) {
+    let mut diagnoses = analyze(output);
+    if diagnoses.is_empty() {
+        return;
+    }
+    attach_fixes(&mut diagnoses, &load_fixes(config_path));
+    eprintln!();
+    for diagnosis in &diagnoses {
+        eprint!("{}", render(diagnosis));
+    }
+    eprintln!("   Re-run analysis with `f diagnose last`.");
+}
+
+fn render(diagnosis: &Diagnosis) -> String {
+    let mut out = format!("💡 {}\n   {}\n", diagnosis.summary, diagnosis.suggestion);
+    if let Some(task) = &diagnosis.fix_task {
+        out.push_str(&format!(
+            "   Fix task: `f {task}` (or `f diagnose last --fix`)\n"
+        ));
+    }
+    out
+}
+
+pub fn run(cmd: DiagnoseCommand) -> Result<()> {
+    match cmd.action.unwrap_or(DiagnoseAction::Last(DiagnoseLastOpts {
+        json: false,
+        fix: false,
+    })) {
+        DiagnoseAction::Last(opts) => run_last(opts),
+    }
+}
+
+fn run_last(opts: DiagnoseLastOpts) -> Result<()> {
+    let record = failure::latest_record()?;
+    let config_path = PathBuf::from(&record.config);
+    let mut diagnoses = analyze(&record.output);
+    attach_fixes(&mut diagnoses, &load_fixes(&config_path));
+
+    if opts.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "task": record.task,
+                "status": record.status,
+                "workdir": record.workdir,
+                "diagnoses": diagnoses,
+            }))
+            .context("failed to encode diagnosis JSON")?
+        );
+    } else {
+        println!(
+            "Last failure: {} (exit status {})",
+            record.task, record.status
+        );
+        if diagnoses.is_empty() {
+            println!("No known error signature matched.");
+            println!("Inspect the output with `f failure last`.");
+        }
+        for diagnosis in &diagnoses {
+            print!("{}", render(diagnosis));
+        }
+    }
+
+    if !opts.fix {
+        return Ok(());
+    }
+    let Some(task) = diagnoses.iter().find_map(|d| d.fix_task.clone()) else {
+        bail!(
+            "No fix task configured for this failure. Map one under [diagnose.fixes] in flow.toml."
+        );
+    };
+    println!("Running fix task: {task}");
+    tasks::run(TaskRunOpts {
+        config: config_path,
+        delegate_to_hub: false,
+        hub_host: std::net::IpAddr::from([127, 0, 0, 1]),
+        hub_port: 9050,
+        priority: 0,
+        print_env: false,
+        yes: false,
+        name: task,
+        args: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signatures(output: &str) -> Vec<Signature> {
+        analyze(output).into_iter().map(|d| d.signature).collect()
+    }
+
+    #[test]
+    fn detects_missing_binary_in_shell_output() {
+        let diagnoses = analyze("bash: line 1: wrangler: command not found\n");
+        assert_eq!(diagnoses.len(), 1);
+        assert_eq!(diagnoses[0].signature, Signature::MissingBinary);
+        assert!(diagnoses[0].summary.contains("wrangler"));
+
+        assert_eq!(
+            signatures("zsh: command not found: rg\n"),
+            vec![Signature::MissingBinary]
+        );
+        assert_eq!(
+            signatures("sh: 1: tsc: not found\n"),
+            vec![Signature::MissingBinary]
+        );
+    }
+
+    #[test]
+    fn detects_port_in_use_with_port() {
+        let diagnoses =
+            analyze("Error: listen EADDRINUSE: address already in use :::3000\n    at Server");
+        assert_eq!(diagnoses.len(), 1);
+        assert_eq!(diagnoses[0].signature, Signature::PortInUse);
+        assert!(diagnoses[0].suggestion.contains("-iTCP:3000"));
+    }
+
+    #[test]
+    fn detects_lockfile_and_module_failures_in_output_order() {
+        let output = "error: lockfile had changes, but lockfile is frozen\n\
+                      Error: Cannot find module 'zod'\n";
+        let diagnoses = analyze(output);
+        assert_eq!(
+            diagnoses.iter().map(|d| d.signature).collect::<Vec<_>>(),
+            vec![Signature::LockfileConflict, Signature::ModuleNotFound]
+        );
+        assert!(diagnoses[1].summary.contains("zod"));
+
+        let git = analyze("fatal: Unable to create '/repo/.git/index.lock': File exists.\n");
+        assert!(git[0].suggestion.contains("rm /repo/.git/index.lock"));
+
+        let python = analyze("ModuleNotFoundError: No module named 'requests'\n");
+        assert!(python[0].suggestion.contains("uv sync"));
+    }
+
+    #[test]
+    fn attaches_configured_fix_tasks() {
+        let mut diagnoses = analyze("Cannot find module 'react'");
+        let fixes = HashMap::from([("module_not_found".to_string(), "install".to_string())]);
+        attach_fixes(&mut diagnoses, &fixes);
+        assert_eq!(diagnoses[0].fix_task.as_deref(), Some("install"));
+        assert!(render(&diagnoses[0]).contains("f install"));
+        assert!(analyze("all good\n").is_empty());
+    }
+}
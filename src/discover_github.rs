@@ -0,0 +1,312 @@
+//! `f discover github`: batch-onboard a GitHub org's repos.
+//!
+//! Lists repos with `gh repo list`, lets you pick some (fzf multi-select, `--match`, or
+//! `--all`), clones them the way `f repos clone` does, runs `f setup --no-ai` in each fresh
+//! clone, and registers every resulting flow.toml in the project DB.
+
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+
+use crate::cli::{DiscoverAction, DiscoverCommand, DiscoverGithubOpts, ReposCloneOpts};
+use crate::{config, projects, repos};
+
+pub fn run(cmd: DiscoverCommand) -> Result<()> {
+    match cmd.action {
+        DiscoverAction::Github(opts) => run_github(opts),
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GithubRepo {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    is_archived: bool,
+    #[serde(default)]
+    is_fork: bool,
+    #[serde(default)]
+    is_private: bool,
+    #[serde(default)]
+    pushed_at: Option<String>,
+}
+
+impl GithubRepo {
+    fn display(&self) -> String {
+        let mut tags = Vec::new();
+        if self.is_private {
+            tags.push("private");
+        }
+        if self.is_fork {
+            tags.push("fork");
+        }
+        if self.is_archived {
+            tags.push("archived");
+        }
+        let pushed = self
+            .pushed_at
+            .as_deref()
+            .and_then(|at| at.get(..10))
+            .unwrap_or("-");
+        let mut line = format!("{:<40} {pushed}", self.name);
+        if !tags.is_empty() {
+            line.push_str(&format!(" [{}]", tags.join(", ")));
+        }
+        if let Some(description) = self.description.as_deref().filter(|d| !d.is_empty()) {
+            line.push_str("  ");
+            line.push_str(description);
+        }
+        line
+    }
+}
+
+#[derive(Debug, Default)]
+struct Outcome {
+    cloned: Vec<String>,
+    existing: Vec<String>,
+    registered: Vec<String>,
+    failed: Vec<(String, String)>,
+}
+
+fn run_github(opts: DiscoverGithubOpts) -> Result<()> {
+    let repos = list_org_repos(&opts.org, opts.limit)?;
+    let candidates = filter_repos(repos, &opts);
+    if candidates.is_empty() {
+        println!("No repos in {} match.", opts.org);
+        return Ok(());
+    }
+
+    let selected = if opts.all || opts.pattern.is_some() {
+        candidates
+    } else if std::io::stdin().is_terminal() {
+        select_with_fzf(&candidates)?
+    } else {
+        bail!("no TTY to pick repos; pass --all or --match <text>");
+    };
+    if selected.is_empty() {
+        println!("Nothing selected.");
+        return Ok(());
+    }
+
+    let root = repos::normalize_root(&opts.root)?;
+    if opts.dry_run {
+        println!(
+            "Would import {} repo(s) from {} into {}:",
+            selected.len(),
+            opts.org,
+            root.join(&opts.org).display()
+        );
+        for repo in &selected {
+            println!("  {}", repo.name);
+        }
+        return Ok(());
+    }
+
+    let mut outcome = Outcome::default();
+    for (index, repo) in selected.iter().enumerate() {
+        println!(
+            "\n[{}/{}] {}/{}",
+            index + 1,
+            selected.len(),
+            opts.org,
+            repo.name
+        );
+        if let Err(err) = import_repo(&opts, repo, &mut outcome) {
+            eprintln!("✗ {}: {err:#}", repo.name);
+            outcome.failed.push((repo.name.clone(), format!("{err:#}")));
+        }
+    }
+
+    println!(
+        "\nImported {} from {}: {} cloned, {} already present, {} registered, {} failed.",
+        selected.len(),
+        opts.org,
+        outcome.cloned.len(),
+        outcome.existing.len(),
+        outcome.registered.len(),
+        outcome.failed.len()
+    );
+    for (name, err) in &outcome.failed {
+        println!("  ✗ {name}: {err}");
+    }
+    if !outcome.failed.is_empty() {
+        bail!("{} repo(s) failed to import", outcome.failed.len());
+    }
+    Ok(())
+}
+
+fn import_repo(opts: &DiscoverGithubOpts, repo: &GithubRepo, outcome: &mut Outcome) -> Result<()> {
+    let clone = repos::clone_repo(ReposCloneOpts {
+        url: format!("{}/{}", opts.org, repo.name),
+        root: opts.root.clone(),
+        full: opts.full,
+        no_upstream: false,
+        upstream_url: None,
+        no_home_branch_bootstrap: true,
+    })?;
+    if clone.already_cloned {
+        outcome.existing.push(repo.name.clone());
+    } else {
+        outcome.cloned.push(repo.name.clone());
+        if !opts.no_setup {
+            run_setup(&clone.path)?;
+        }
+    }
+
+    let flow_toml = clone.path.join("flow.toml");
+    if !flow_toml.exists() {
+        println!("No flow.toml in {}; not registered.", clone.path.display());
+        return Ok(());
+    }
+    let cfg = config::load_or_default(&flow_toml);
+    let name = cfg.project_name.unwrap_or_else(|| repo.name.clone());
+    projects::register_project(&name, &flow_toml)?;
+    println!("✓ registered project '{name}'");
+    outcome.registered.push(name);
+    Ok(())
+}
+
+/// `f setup --no-ai` in the fresh clone, as its own process so each repo starts clean.
+fn run_setup(repo_dir: &Path) -> Result<()> {
+    let exe = std::env::current_exe().context("failed to resolve flow binary")?;
+    let status = Command::new(exe)
+        .args(["setup", "--no-ai"])
+        .current_dir(repo_dir)
+        .stdin(Stdio::null())
+        .status()
+        .context("failed to run f setup")?;
+    if !status.success() {
+        bail!("f setup --no-ai failed in {}", repo_dir.display());
+    }
+    Ok(())
+}
+
+fn list_org_repos(org: &str, limit: usize) -> Result<Vec<GithubRepo>> {
+    let output = Command::new("gh")
+        .args([
+            "repo",
+            "list",
+            org,
+            "--limit",
+            &limit.to_string(),
+            "--json",
+            "name,description,isArchived,isFork,isPrivate,pushedAt",
+        ])
+        .output()
+        .context("failed to run gh; install the GitHub CLI and run `gh auth login`")?;
+    if !output.status.success() {
+        bail!(
+            "gh repo list {org} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let mut repos: Vec<GithubRepo> =
+        serde_json::from_slice(&output.stdout).context("failed to parse gh repo list output")?;
+    // Most recently pushed first, so the interesting repos top the picker.
+    repos.sort_by(|a, b| b.pushed_at.cmp(&a.pushed_at));
+    Ok(repos)
+}
+
+fn filter_repos(repos: Vec<GithubRepo>, opts: &DiscoverGithubOpts) -> Vec<GithubRepo> {
+    let pattern = opts.pattern.as_deref().map(str::to_ascii_lowercase);
+    repos
+        .into_iter()
+        .filter(|repo| opts.include_archived || !repo.is_archived)
+        .filter(|repo| opts.include_forks || !repo.is_fork)
+        .filter(|repo| {
+            pattern
+                .as_deref()
+                .is_none_or(|pattern| repo.name.to_ascii_lowercase().contains(pattern))
+        })
+        .collect()
+}
+
+fn select_with_fzf(repos: &[GithubRepo]) -> Result<Vec<GithubRepo>> {
+    let mut child = Command::new("fzf")
+        .args(["--multi", "--prompt", "import> "])
+        .args(["--header", "TAB to select repos, ENTER to import"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("failed to spawn fzf; pass --all or --match <text> instead")?;
+    {
+        let stdin = child.stdin.as_mut().context("failed to open fzf stdin")?;
+        for repo in repos {
+            writeln!(stdin, "{}", repo.display())?;
+        }
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+    let selection = String::from_utf8(output.stdout).context("fzf output was not valid UTF-8")?;
+    Ok(selection
+        .lines()
+        .filter_map(|line| repos.iter().find(|repo| repo.display() == line))
+        .cloned()
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo(name: &str, is_archived: bool, is_fork: bool) -> GithubRepo {
+        GithubRepo {
+            name: name.to_string(),
+            description: None,
+            is_archived,
+            is_fork,
+            is_private: false,
+            pushed_at: Some("2026-10-01T12:00:00Z".to_string()),
+        }
+    }
+
+    #[test]
+    fn filters_archived_forks_and_pattern() {
+        let repos = vec![
+            repo("api", false, false),
+            repo("api-legacy", true, false),
+            repo("web", false, false),
+            repo("api-fork", false, true),
+        ];
+        let mut opts = DiscoverGithubOpts {
+            org: "acme".to_string(),
+            root: "~/repos".to_string(),
+            pattern: Some("API".to_string()),
+            all: false,
+            include_archived: false,
+            include_forks: false,
+            limit: 1000,
+            full: false,
+            no_setup: false,
+            dry_run: false,
+        };
+        let names = |repos: Vec<GithubRepo>| -> Vec<String> {
+            repos.into_iter().map(|repo| repo.name).collect()
+        };
+        assert_eq!(names(filter_repos(repos.clone(), &opts)), ["api"]);
+
+        opts.include_archived = true;
+        opts.include_forks = true;
+        assert_eq!(
+            names(filter_repos(repos, &opts)),
+            ["api", "api-legacy", "api-fork"]
+        );
+    }
+
+    #[test]
+    fn parses_gh_repo_list_json() {
+        let json = r#"[{"name":"api","description":"","isArchived":false,"isFork":true,"isPrivate":true,"pushedAt":"2026-09-30T08:00:00Z"}]"#;
+        let repos: Vec<GithubRepo> = serde_json::from_str(json).unwrap();
+        assert_eq!(repos.len(), 1);
+        assert!(repos[0].is_fork && repos[0].is_private);
+        assert!(repos[0].display().starts_with("api "));
+        assert!(repos[0].display().contains("2026-09-30 [private, fork]"));
+    }
+}
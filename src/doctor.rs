@@ -10,7 +10,10 @@ use anyhow::{Context, Result, bail};
 use crossterm::{event, terminal};
 
 use crate::cli::DoctorOpts;
-use crate::vcs;
+use crate::{
+    commit_signing, config, dep_installer, env_history, flow_toml_advisor, git_hooks,
+    project_snapshot, time_window, vcs,
+};
 
 /// Ensure the lin watcher daemon is available, prompting to install a bundled
 /// copy if it is missing from PATH. Returns the resolved binary path.
@@ -33,7 +36,7 @@ pub fn ensure_lin_available_interactive() -> Result<PathBuf> {
     );
 }
 
-pub fn run(_opts: DoctorOpts) -> Result<()> {
+pub fn run(opts: DoctorOpts) -> Result<()> {
     println!("Running flow doctor checks...\n");
 
     let zerobrew_available = ensure_zerobrew_available_interactive()?;
@@ -50,10 +53,182 @@ pub fn run(_opts: DoctorOpts) -> Result<()> {
         ),
     }
 
+    check_project_dependencies();
+    check_flow_toml_upgrades(opts.fix);
+    check_env_rotation();
+    check_git_hooks();
+    check_commit_signing();
+
     println!("\n✅ flow doctor is done. Re-run it any time after changing shells or machines.");
     Ok(())
 }
 
+/// Report `[deps]` of the flow.toml above the cwd (missing or below `min`).
+fn check_project_dependencies() {
+    let Ok(cwd) = env::current_dir() else {
+        return;
+    };
+    let Some(path) = project_snapshot::find_flow_toml_upwards(&cwd) else {
+        return;
+    };
+    let cfg = match config::load(&path) {
+        Ok(cfg) => cfg,
+        Err(err) => {
+            println!("⚠️  Failed to load {}: {err:#}", path.display());
+            return;
+        }
+    };
+    if cfg.dependencies.is_empty() {
+        return;
+    }
+
+    println!("\nProject dependencies ({}):", path.display());
+    let preferred = dep_installer::preferred_backend(&cfg);
+    for check in dep_installer::check_dependencies(&cfg) {
+        let hint =
+            || dep_installer::installer_hint(&check.command, check.min.as_deref(), preferred);
+        match &check.state {
+            dep_installer::DepState::Ok(Some(found)) => println!("✅ {} {}", check.command, found),
+            dep_installer::DepState::Ok(None) => println!("✅ {} found on PATH", check.command),
+            dep_installer::DepState::Missing => {
+                println!("⚠️  {} is missing (try: {})", check.command, hint())
+            }
+            dep_installer::DepState::Outdated { found, min } => println!(
+                "⚠️  {} {} is older than required {} (try: {})",
+                check.command,
+                found,
+                min,
+                hint()
+            ),
+            dep_installer::DepState::Unknown { min } => println!(
+                "⚠️  Could not read {} version (need >= {})",
+                check.command, min
+            ),
+        }
+    }
+}
+
+/// Warn about env keys older than the `flow.env.maxAge` rotation policy in config.ts.
+fn check_env_rotation() {
+    let Some(max_age) = config::env_max_age() else {
+        return;
+    };
+    let max_age_ms = match time_window::parse_since(&max_age) {
+        Ok(ms) => ms,
+        Err(err) => {
+            println!("⚠️  Invalid flow.env.maxAge '{max_age}': {err:#}");
+            return;
+        }
+    };
+
+    println!("\nEnv rotation (max age {max_age}):");
+    let stale = match env_history::stale_keys(max_age_ms) {
+        Ok(stale) => stale,
+        Err(err) => {
+            println!("⚠️  Failed to read env history: {err:#}");
+            return;
+        }
+    };
+    if stale.is_empty() {
+        println!("✅ No recorded env keys are past their max age");
+        return;
+    }
+    let now = chrono::Utc::now();
+    for key in &stale {
+        println!(
+            "⚠️  {} ({}/{}) last changed {}d ago on {}; rotate it",
+            key.key,
+            key.store,
+            key.environment,
+            (now - key.changed_at).num_days(),
+            key.machine
+        );
+    }
+}
+
+/// Verify Flow's per-repo git hooks (`f hooks install`) when the cwd repo has them.
+fn check_git_hooks() {
+    let Some(lines) = git_hooks::doctor_report() else {
+        return;
+    };
+    println!();
+    for line in lines {
+        println!("{line}");
+    }
+}
+
+/// Validate git's signing setup against `[commit.signing]` when signing is in play.
+fn check_commit_signing() {
+    let Some(lines) = commit_signing::doctor_report() else {
+        return;
+    };
+    println!();
+    for line in lines {
+        println!("{line}");
+    }
+}
+
+/// Advise on (and with `fix`, apply) flow.toml upgrades for the project above the cwd.
+fn check_flow_toml_upgrades(fix: bool) {
+    let Ok(cwd) = env::current_dir() else {
+        return;
+    };
+    let Some(path) = project_snapshot::find_flow_toml_upwards(&cwd) else {
+        return;
+    };
+    println!("\nflow.toml upgrades ({}):", path.display());
+    let advice = match flow_toml_advisor::analyze_file(&path) {
+        Ok(advice) => advice,
+        Err(err) => {
+            println!("⚠️  {err:#}");
+            return;
+        }
+    };
+    if advice.is_clean() {
+        println!("✅ flow.toml is up to date");
+        return;
+    }
+
+    for rename in &advice.renames {
+        if rename.conflict {
+            println!(
+                "⚠️  {}: legacy `{}` is set alongside `{}`; remove one by hand",
+                rename.location, rename.from, rename.to
+            );
+        } else {
+            println!(
+                "⚠️  {}: legacy key `{}` should be `{}`",
+                rename.location, rename.from, rename.to
+            );
+        }
+    }
+    for section in &advice.missing_sections {
+        println!("⚠️  missing recommended section {section}");
+    }
+    for task in &advice.tasks_without_description {
+        println!("⚠️  task `{task}` has no description");
+    }
+    for (task, shortcut) in &advice.shortcut_suggestions {
+        println!("💡 task `{task}` could add shortcuts = [\"{shortcut}\"]");
+    }
+
+    if !advice.has_fixes() {
+        return;
+    }
+    println!("\n{}", advice.diff().trim_end());
+    if !fix {
+        println!(
+            "\nRun `f doctor --fix` to apply {} change(s) above.",
+            advice.fix_count()
+        );
+        return;
+    }
+    match flow_toml_advisor::apply(&path, &advice) {
+        Ok(()) => println!("✅ Applied {} flow.toml upgrade(s)", advice.fix_count()),
+        Err(err) => println!("⚠️  Failed to update {}: {err:#}", path.display()),
+    }
+}
+
 fn ensure_flox_available(zerobrew_available: bool) -> Result<()> {
     if which::which("flox").is_ok() {
         println!("✅ flox found on PATH");
@@ -266,7 +266,7 @@ fn run_doctor(paths: &DomainsPaths, engine: DomainsEngine) -> Result<()> {
     Ok(())
 }
 
-fn normalize_host(raw: &str) -> Result<String> {
+pub(crate) fn normalize_host(raw: &str) -> Result<String> {
     let mut host = raw.trim().to_ascii_lowercase();
     if let Some(stripped) = host.strip_prefix("http://") {
         host = stripped.to_string();
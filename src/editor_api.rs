@@ -0,0 +1,606 @@
+//! JSON-RPC task API for editor integrations (`f tasks serve`).
+//!
+//! Editors talk newline-delimited JSON-RPC 2.0 over stdio, or over a Unix socket with
+//! `--socket`. Requests list and run tasks; runs report back as notifications (`task/started`,
+//! `task/output`, `task/diagnostic`, `task/finished`) so an extension can show progress and
+//! problems without parsing terminal output itself. Each run is a `f run` child in its own
+//! process group, so `tasks/cancel` behaves like Ctrl+C in a terminal.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::cli::TasksServeOpts;
+use crate::project_snapshot::ProjectSnapshot;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+type Writer = Arc<Mutex<Box<dyn Write + Send>>>;
+
+pub fn run(opts: TasksServeOpts) -> Result<()> {
+    let config = absolute(&opts.config)?;
+    let Some(socket) = opts.socket else {
+        let writer: Writer = Arc::new(Mutex::new(Box::new(std::io::stdout())));
+        return serve_connection(std::io::stdin().lock(), writer, &config);
+    };
+
+    if let Some(parent) = socket.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    remove_stale_socket(&socket)?;
+    let listener = UnixListener::bind(&socket)
+        .with_context(|| format!("failed to bind {}", socket.display()))?;
+    eprintln!("Serving flow tasks on {}", socket.display());
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("warning: editor connection failed: {err}");
+                continue;
+            }
+        };
+        let config = config.clone();
+        thread::spawn(move || {
+            let writer: Writer = match stream.try_clone() {
+                Ok(clone) => Arc::new(Mutex::new(Box::new(clone))),
+                Err(err) => {
+                    eprintln!("warning: editor connection failed: {err}");
+                    return;
+                }
+            };
+            if let Err(err) = serve_connection(BufReader::new(stream), writer, &config) {
+                eprintln!("warning: editor connection closed: {err:#}");
+            }
+        });
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RunParams {
+    name: String,
+    #[serde(default)]
+    args: Vec<String>,
+    /// flow.toml to run against, for tasks found in nested configs by `tasks/list`.
+    #[serde(default)]
+    config: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CancelParams {
+    run_id: u64,
+}
+
+/// State shared by one connection's request loop and its run threads.
+struct Session {
+    writer: Writer,
+    config: PathBuf,
+    next_run: AtomicU64,
+    /// Process group ids of in-flight runs.
+    runs: Mutex<HashMap<u64, u32>>,
+    /// Threads that report each run's `task/finished`.
+    finishers: Mutex<Vec<thread::JoinHandle<()>>>,
+}
+
+impl Session {
+    fn send(&self, message: Value) {
+        let Ok(mut writer) = self.writer.lock() else {
+            return;
+        };
+        let _ = writeln!(writer, "{message}");
+        let _ = writer.flush();
+    }
+
+    fn notify(&self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    fn reply(&self, id: Option<Value>, result: Result<Value, (i64, String)>) {
+        let Some(id) = id else {
+            return;
+        };
+        let message = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            }),
+        };
+        self.send(message);
+    }
+
+    fn interrupt(pgid: u32) {
+        unsafe {
+            libc::kill(-(pgid as libc::pid_t), libc::SIGINT);
+        }
+    }
+
+    /// Wait for in-flight runs to report back, interrupting them first if asked.
+    fn drain(&self, interrupt: bool) {
+        if interrupt && let Ok(runs) = self.runs.lock() {
+            runs.values().copied().for_each(Self::interrupt);
+        }
+        let finishers = match self.finishers.lock() {
+            Ok(mut finishers) => std::mem::take(&mut *finishers),
+            Err(_) => return,
+        };
+        for finisher in finishers {
+            let _ = finisher.join();
+        }
+    }
+}
+
+fn serve_connection(reader: impl BufRead, writer: Writer, config: &Path) -> Result<()> {
+    let session = Arc::new(Session {
+        writer,
+        config: config.to_path_buf(),
+        next_run: AtomicU64::new(1),
+        runs: Mutex::new(HashMap::new()),
+        finishers: Mutex::new(Vec::new()),
+    });
+    for line in reader.lines() {
+        let line = line.context("failed to read editor request")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request: Request = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(err) => {
+                let code = if serde_json::from_str::<Value>(&line).is_ok() {
+                    INVALID_REQUEST
+                } else {
+                    PARSE_ERROR
+                };
+                session.reply(Some(Value::Null), Err((code, err.to_string())));
+                continue;
+            }
+        };
+        if request.method == "shutdown" {
+            session.drain(true);
+            session.reply(request.id, Ok(Value::Null));
+            return Ok(());
+        }
+        let result = handle(&session, &request.method, request.params);
+        session.reply(request.id, result);
+    }
+    // Input closed (e.g. a piped request): let started runs finish and report.
+    session.drain(false);
+    Ok(())
+}
+
+fn handle(session: &Arc<Session>, method: &str, params: Value) -> Result<Value, (i64, String)> {
+    match method {
+        "tasks/list" => list_tasks(&session.config).map_err(server_error),
+        "tasks/run" => {
+            let params: RunParams =
+                serde_json::from_value(params).map_err(|err| (INVALID_PARAMS, err.to_string()))?;
+            start_run(session, params).map_err(server_error)
+        }
+        "tasks/cancel" => {
+            let params: CancelParams =
+                serde_json::from_value(params).map_err(|err| (INVALID_PARAMS, err.to_string()))?;
+            let pgid = session
+                .runs
+                .lock()
+                .ok()
+                .and_then(|runs| runs.get(&params.run_id).copied());
+            let Some(pgid) = pgid else {
+                return Err((INVALID_PARAMS, format!("no run {}", params.run_id)));
+            };
+            // SIGINT, like Ctrl+C, so the task's on_cancel hook runs.
+            Session::interrupt(pgid);
+            Ok(json!({ "cancelled": true }))
+        }
+        other => Err((METHOD_NOT_FOUND, format!("unknown method '{other}'"))),
+    }
+}
+
+fn server_error(err: anyhow::Error) -> (i64, String) {
+    (SERVER_ERROR, format!("{err:#}"))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskInfo {
+    name: String,
+    description: Option<String>,
+    command: String,
+    config: PathBuf,
+    scope: String,
+}
+
+fn list_tasks(config: &Path) -> Result<Value> {
+    let snapshot = ProjectSnapshot::from_task_config(config, true)?;
+    let tasks: Vec<TaskInfo> = snapshot
+        .discovery
+        .tasks
+        .iter()
+        .map(|discovered| TaskInfo {
+            name: discovered.task.name.clone(),
+            description: discovered.task.description.clone(),
+            command: discovered.task.command.clone(),
+            config: discovered.config_path.clone(),
+            scope: discovered.scope.clone(),
+        })
+        .collect();
+    Ok(json!({ "root": snapshot.root, "tasks": tasks }))
+}
+
+fn start_run(session: &Arc<Session>, params: RunParams) -> Result<Value> {
+    let config = params.config.unwrap_or_else(|| session.config.clone());
+    let run_id = session.next_run.fetch_add(1, Ordering::SeqCst);
+    let flow_bin = std::env::current_exe().unwrap_or_else(|_| PathBuf::from("f"));
+    let workdir = config
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    let mut cmd = Command::new(flow_bin);
+    cmd.arg("run")
+        .arg("--config")
+        .arg(&config)
+        .arg(&params.name)
+        .args(&params.args)
+        .current_dir(&workdir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("failed to start task '{}'", params.name))?;
+    if let Ok(mut runs) = session.runs.lock() {
+        runs.insert(run_id, child.id());
+    }
+    session.notify(
+        "task/started",
+        json!({ "runId": run_id, "name": params.name, "pid": child.id() }),
+    );
+
+    let started = Instant::now();
+    let mut readers: Vec<(&'static str, Box<dyn Read + Send>)> = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        readers.push(("stdout", Box::new(stdout)));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        readers.push(("stderr", Box::new(stderr)));
+    }
+    let streams: Vec<_> = readers
+        .into_iter()
+        .map(|(name, stream)| {
+            let session = Arc::clone(session);
+            let workdir = workdir.clone();
+            thread::spawn(move || forward_output(&session, run_id, name, stream, &workdir))
+        })
+        .collect();
+
+    let name = params.name;
+    let finisher_session = Arc::clone(session);
+    let finisher = thread::spawn(move || {
+        let session = finisher_session;
+        let diagnostics: usize = streams.into_iter().filter_map(|h| h.join().ok()).sum();
+        let status = child.wait();
+        if let Ok(mut runs) = session.runs.lock() {
+            runs.remove(&run_id);
+        }
+        let exit_code = status.as_ref().ok().and_then(|status| status.code());
+        session.notify(
+            "task/finished",
+            json!({
+                "runId": run_id,
+                "name": name,
+                "success": status.is_ok_and(|status| status.success()),
+                "exitCode": exit_code,
+                "durationMs": started.elapsed().as_millis() as u64,
+                "diagnostics": diagnostics,
+            }),
+        );
+    });
+    if let Ok(mut finishers) = session.finishers.lock() {
+        finishers.push(finisher);
+    }
+
+    Ok(json!({ "runId": run_id }))
+}
+
+/// Relay one output stream line by line; returns how many diagnostics it reported.
+fn forward_output(
+    session: &Session,
+    run_id: u64,
+    stream_name: &str,
+    stream: Box<dyn Read + Send>,
+    workdir: &Path,
+) -> usize {
+    let mut parser = DiagnosticParser::default();
+    let mut count = 0;
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        session.notify(
+            "task/output",
+            json!({ "runId": run_id, "stream": stream_name, "line": line }),
+        );
+        if let Some(mut diagnostic) = parser.push(&line) {
+            let path = Path::new(&diagnostic.file);
+            if path.is_relative() {
+                diagnostic.file = workdir.join(path).to_string_lossy().into_owned();
+            }
+            count += 1;
+            session.notify(
+                "task/diagnostic",
+                json!({ "runId": run_id, "diagnostic": diagnostic }),
+            );
+        }
+    }
+    count
+}
+
+/// A compiler or linter message pointing at a source location.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: u32,
+    pub column: Option<u32>,
+    /// "error", "warning", or "note".
+    pub severity: String,
+    pub message: String,
+}
+
+/// Recognizes `file:line:col: error: message` (gcc, clang, go vet, eslint's unix format),
+/// `file(line,col): error TS1234: message` (tsc), and rustc's two-line
+/// `error[E0308]: message` / `--> file:line:col` form.
+#[derive(Debug, Default)]
+struct DiagnosticParser {
+    /// A rustc header waiting for its `-->` location line.
+    pending: Option<(String, String)>,
+}
+
+impl DiagnosticParser {
+    fn push(&mut self, line: &str) -> Option<Diagnostic> {
+        let trimmed = line.trim();
+        if let Some(location) = trimmed.strip_prefix("--> ") {
+            let (severity, message) = self.pending.take()?;
+            let (file, line, column) = split_location(location)?;
+            return Some(Diagnostic {
+                file,
+                line,
+                column,
+                severity,
+                message,
+            });
+        }
+        if let Some((severity, message)) = rustc_header(trimmed) {
+            self.pending = Some((severity, message));
+            return None;
+        }
+        tsc_diagnostic(trimmed).or_else(|| colon_diagnostic(trimmed))
+    }
+}
+
+fn severity_word(word: &str) -> Option<String> {
+    let word = word.trim().to_ascii_lowercase();
+    let word = word.split(['[', ' ']).next().unwrap_or_default();
+    matches!(word, "error" | "warning" | "note").then(|| word.to_string())
+}
+
+/// `error[E0308]: mismatched types` or `warning: unused variable`.
+fn rustc_header(line: &str) -> Option<(String, String)> {
+    let (head, message) = line.split_once(": ")?;
+    if head.contains(' ') || head.contains(':') {
+        return None;
+    }
+    let severity = severity_word(head)?;
+    Some((severity, message.trim().to_string()))
+}
+
+/// `src/app.ts(12,5): error TS2322: Type 'string' is not assignable to type 'number'.`
+fn tsc_diagnostic(line: &str) -> Option<Diagnostic> {
+    let (location, rest) = line.split_once("): ")?;
+    let (file, position) = location.rsplit_once('(')?;
+    let (line_no, column) = position.split_once(',')?;
+    let (head, message) = rest.split_once(": ")?;
+    Some(Diagnostic {
+        file: file.to_string(),
+        line: line_no.parse().ok()?,
+        column: column.parse().ok(),
+        severity: severity_word(head)?,
+        message: message.trim().to_string(),
+    })
+}
+
+/// `src/main.c:10:5: error: expected ';'`
+fn colon_diagnostic(line: &str) -> Option<Diagnostic> {
+    let (file, rest) = line.split_once(':')?;
+    if file.is_empty() || file.contains(' ') {
+        return None;
+    }
+    let (line_no, rest) = rest.split_once(':')?;
+    let line_no: u32 = line_no.trim().parse().ok()?;
+    let (column, rest) = match rest.split_once(':') {
+        Some((column, after)) if column.trim().parse::<u32>().is_ok() => {
+            (column.trim().parse().ok(), after)
+        }
+        _ => (None, rest),
+    };
+    let (head, message) = rest.split_once(':')?;
+    Some(Diagnostic {
+        file: file.to_string(),
+        line: line_no,
+        column,
+        severity: severity_word(head)?,
+        message: message.trim().to_string(),
+    })
+}
+
+/// `src/main.rs:4:9` (column optional).
+fn split_location(location: &str) -> Option<(String, u32, Option<u32>)> {
+    let mut parts = location.trim().rsplitn(3, ':');
+    let last = parts.next()?;
+    let middle = parts.next()?;
+    match (parts.next(), middle.parse::<u32>()) {
+        (Some(file), Ok(line)) => Some((file.to_string(), line, last.parse().ok())),
+        _ => Some((middle.to_string(), last.parse().ok()?, None)),
+    }
+}
+
+/// Remove a socket left behind by a server that is gone. Refuses to touch anything that is not
+/// a socket, or a socket another `f tasks serve` still answers on.
+fn remove_stale_socket(socket: &Path) -> Result<()> {
+    let Ok(metadata) = fs::symlink_metadata(socket) else {
+        return Ok(());
+    };
+    if !metadata.file_type().is_socket() {
+        bail!("{} exists and is not a socket", socket.display());
+    }
+    if UnixStream::connect(socket).is_ok() {
+        bail!("{} is already being served", socket.display());
+    }
+    fs::remove_file(socket)
+        .with_context(|| format!("failed to remove stale socket {}", socket.display()))
+}
+
+fn absolute(path: &Path) -> Result<PathBuf> {
+    if path.is_absolute() {
+        return Ok(path.to_path_buf());
+    }
+    Ok(std::env::current_dir()
+        .context("failed to read current directory")?
+        .join(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remove_stale_socket_only_removes_dead_sockets() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let file = dir.path().join("notes.txt");
+        fs::write(&file, "keep").unwrap();
+        assert!(remove_stale_socket(&file).is_err());
+        assert!(file.exists());
+
+        let live = dir.path().join("live.sock");
+        let listener = UnixListener::bind(&live).unwrap();
+        assert!(remove_stale_socket(&live).is_err());
+        assert!(live.exists());
+
+        drop(listener);
+        remove_stale_socket(&live).unwrap();
+        assert!(!live.exists());
+        remove_stale_socket(&live).unwrap();
+    }
+
+    #[test]
+    fn parses_common_diagnostic_formats() {
+        let mut parser = DiagnosticParser::default();
+        assert_eq!(parser.push("error[E0308]: mismatched types"), None);
+        assert_eq!(
+            parser.push("  --> src/main.rs:4:9"),
+            Some(Diagnostic {
+                file: "src/main.rs".to_string(),
+                line: 4,
+                column: Some(9),
+                severity: "error".to_string(),
+                message: "mismatched types".to_string(),
+            })
+        );
+
+        let tsc = parser
+            .push("src/app.ts(12,5): error TS2322: Type 'string' is not assignable.")
+            .unwrap();
+        assert_eq!(
+            (tsc.file.as_str(), tsc.line, tsc.column),
+            ("src/app.ts", 12, Some(5))
+        );
+        assert_eq!(tsc.message, "Type 'string' is not assignable.");
+
+        let gcc = parser
+            .push("main.c:10:5: warning: unused variable 'x'")
+            .unwrap();
+        assert_eq!(gcc.severity, "warning");
+        assert_eq!(gcc.column, Some(5));
+        assert_eq!(gcc.message, "unused variable 'x'");
+
+        assert_eq!(parser.push("   Compiling flow v0.1.0"), None);
+        assert_eq!(parser.push("http://localhost:3000: ready"), None);
+    }
+
+    #[test]
+    fn answers_requests_and_reports_unknown_methods() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("flow.toml");
+        fs::write(
+            &config,
+            "[[tasks]]\nname = \"build\"\ncommand = \"cargo build\"\ndescription = \"Build it\"\n",
+        )
+        .unwrap();
+
+        let output = Arc::new(Mutex::new(Vec::<u8>::new()));
+        struct Shared(Arc<Mutex<Vec<u8>>>);
+        impl Write for Shared {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let writer: Writer = Arc::new(Mutex::new(Box::new(Shared(Arc::clone(&output)))));
+        let input = concat!(
+            r#"{"jsonrpc":"2.0","id":1,"method":"tasks/list"}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","id":2,"method":"tasks/explode"}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","id":3,"method":"shutdown"}"#,
+            "\n",
+        );
+        serve_connection(input.as_bytes(), writer, &config).unwrap();
+
+        let output = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+        let replies: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(replies.len(), 3);
+        assert_eq!(replies[0]["result"]["tasks"][0]["name"], "build");
+        assert_eq!(replies[0]["result"]["tasks"][0]["description"], "Build it");
+        assert_eq!(replies[1]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(replies[2]["id"], 3);
+    }
+}
@@ -27,7 +27,9 @@ use crate::agent_setup;
 use crate::cli::{EnvAction, ProjectEnvAction, TokenAction};
 use crate::config;
 use crate::deploy;
+use crate::env_history;
 use crate::env_setup::{EnvSetupDefaults, run_env_setup};
+use crate::env_template::{self, EnvDiff};
 use crate::sealer_crypto::{get_sealer_id, new_x25519_private_key, seal, unseal};
 use crate::storage::{
     create_jazz_app_credentials, get_project_name as storage_project_name, sanitize_name,
@@ -801,6 +803,11 @@ fn local_env_enabled() -> bool {
         .unwrap_or(false)
 }
 
+/// Where the local env backend keeps its files (not created here).
+pub(crate) fn local_env_dir() -> PathBuf {
+    config::global_config_dir().join(LOCAL_ENV_DIR)
+}
+
 fn local_env_root() -> Result<PathBuf> {
     let base = config::ensure_global_config_dir()?;
     let path = base.join(LOCAL_ENV_DIR);
@@ -815,7 +822,7 @@ fn ensure_private_dir(path: &Path) -> Result<()> {
     Ok(())
 }
 
-fn write_private_file(path: &Path, content: &str) -> Result<()> {
+pub(crate) fn write_private_file(path: &Path, content: &str) -> Result<()> {
     if let Some(parent) = path.parent() {
         ensure_private_dir(parent)?;
     }
@@ -1038,7 +1045,7 @@ fn decrypt_project_env_value(
     Ok(Some(value))
 }
 
-fn sanitize_env_segment(value: &str) -> String {
+pub(crate) fn sanitize_env_segment(value: &str) -> String {
     let mut out = String::new();
     let mut last_sep = false;
     for ch in value.chars() {
@@ -1247,7 +1254,9 @@ fn set_local_env_var(
         vars.insert(key.to_string(), value.to_string());
     }
 
-    write_local_env_vars_raw(target, environment, &vars)
+    let path = write_local_env_vars_raw(target, environment, &vars)?;
+    record_env_history(target, environment, key, Some(value));
+    Ok(path)
 }
 
 fn delete_local_env_vars(
@@ -1261,7 +1270,9 @@ fn delete_local_env_vars(
 
     let mut vars = read_local_env_vars_raw(target, environment)?;
     for key in keys {
-        vars.remove(key);
+        if vars.remove(key).is_some() {
+            record_env_history(target, environment, key, None);
+        }
         if local_personal_keychain_supported(target) {
             delete_local_keychain_env_var(target, environment, key)?;
         }
@@ -1270,6 +1281,24 @@ fn delete_local_env_vars(
     write_local_env_vars_raw(target, environment, &vars)
 }
 
+/// Record a set (`Some`) or delete (`None`) in the local env history; failures only warn.
+fn record_env_history(target: &EnvTarget, environment: &str, key: &str, value: Option<&str>) {
+    let limit = config::env_history_limit().unwrap_or(env_history::DEFAULT_HISTORY_LIMIT);
+    let result = load_or_create_env_sealer_identity().and_then(|identity| {
+        env_history::record(
+            &env_target_label(target),
+            environment,
+            key,
+            value,
+            &identity.sealer_secret,
+            limit,
+        )
+    });
+    if let Err(err) = result {
+        eprintln!("⚠ Failed to record env history for {}: {:#}", key, err);
+    }
+}
+
 fn is_local_fallback_error(err: &anyhow::Error) -> bool {
     let msg = err.to_string().to_ascii_lowercase();
     msg.contains("not logged in")
@@ -2032,7 +2061,11 @@ pub fn run(action: Option<EnvAction>) -> Result<()> {
                 .ok_or_else(|| anyhow::anyhow!("flow.toml not found. Run `f init` first."))?;
             let project_root = flow_path.parent().map(|p| p.to_path_buf()).unwrap_or(cwd);
             let flow_config = config::load(&flow_path)?;
-            deploy::apply_cloudflare_env(&project_root, Some(&flow_config))?;
+            if flow_config.cloudflare.is_none() && flow_config.railway.is_some() {
+                deploy::apply_railway_env(&project_root, Some(&flow_config))?;
+            } else {
+                deploy::apply_cloudflare_env(&project_root, Some(&flow_config))?;
+            }
         }
         EnvAction::Bootstrap => {
             let cwd = std::env::current_dir()?;
@@ -2069,7 +2102,31 @@ pub fn run(action: Option<EnvAction>) -> Result<()> {
             keys,
             command,
         } => run_with_env(personal, &environment, &keys, &command)?,
+        EnvAction::Render {
+            template,
+            out,
+            personal,
+            environment,
+            dry_run,
+        } => render(&template, &out, personal, &environment, dry_run)?,
+        EnvAction::Lint {
+            example,
+            personal,
+            environment,
+        } => lint(&example, personal, &environment)?,
         EnvAction::Token { action } => run_token_action(action)?,
+        EnvAction::History {
+            key,
+            project,
+            environment,
+            reveal,
+        } => show_history(&key, project, &environment, reveal)?,
+        EnvAction::Rollback {
+            key,
+            steps,
+            project,
+            environment,
+        } => rollback(&key, steps, project, &environment)?,
     }
 
     Ok(())
@@ -2084,6 +2141,112 @@ fn run_token_action(action: TokenAction) -> Result<()> {
     Ok(())
 }
 
+/// Target and environment for `history` / `rollback`: personal unless `--project`.
+fn history_target(project: bool, environment: &str) -> Result<(EnvTarget, String)> {
+    if project {
+        Ok((resolve_env_target()?, environment.to_string()))
+    } else {
+        Ok((resolve_personal_target()?, "production".to_string()))
+    }
+}
+
+fn show_history(key: &str, project: bool, environment: &str, reveal: bool) -> Result<()> {
+    let (target, environment) = history_target(project, environment)?;
+    let label = env_target_label(&target);
+    let revisions = env_history::revisions(&label, &environment, key)?;
+    if revisions.is_empty() {
+        println!(
+            "No recorded changes for {} in {} ({}). History starts with the next set from this machine.",
+            key, label, environment
+        );
+        return Ok(());
+    }
+
+    if reveal {
+        require_env_read_unlock()?;
+    }
+    let identity = load_or_create_env_sealer_identity()?;
+    println!("{} ({}, {})", key, label, environment);
+    for (index, revision) in revisions.iter().enumerate() {
+        let shown = match revision.open(&identity.sealer_secret) {
+            Ok(Some(value)) if reveal => value,
+            Ok(Some(value)) if value.len() > 8 => format!("{}...", &value[..4]),
+            Ok(Some(_)) => "****".to_string(),
+            Ok(None) => "(deleted)".to_string(),
+            Err(_) => "(sealed on another machine)".to_string(),
+        };
+        println!(
+            "  {:>2}  {}  {:<24} {}",
+            index,
+            revision
+                .changed_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M"),
+            revision.machine,
+            shown
+        );
+    }
+
+    let latest = &revisions[0];
+    println!();
+    println!(
+        "Last changed {}d ago on {}",
+        (Utc::now() - latest.changed_at).num_days(),
+        latest.machine
+    );
+    if revisions.len() > 1 {
+        println!(
+            "Restore an older value with `f env rollback {} --steps <N>`.",
+            key
+        );
+    }
+    Ok(())
+}
+
+fn rollback(key: &str, steps: usize, project: bool, environment: &str) -> Result<()> {
+    if steps == 0 {
+        bail!("--steps must be at least 1");
+    }
+    let (target, environment) = history_target(project, environment)?;
+    let label = env_target_label(&target);
+    let revisions = env_history::revisions(&label, &environment, key)?;
+    let Some(revision) = revisions.get(steps) else {
+        bail!(
+            "{} has {} recorded previous value(s) in {} ({}); see `f env history {}`",
+            key,
+            revisions.len().saturating_sub(1),
+            label,
+            environment,
+            key
+        );
+    };
+
+    require_env_read_unlock()?;
+    let identity = load_or_create_env_sealer_identity()?;
+    let Some(value) = revision.open(&identity.sealer_secret)? else {
+        bail!(
+            "{} was deleted at that point; use `f env delete {}` instead",
+            key,
+            key
+        );
+    };
+    let when = revision
+        .changed_at
+        .with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M");
+
+    if project {
+        set_project_env_var_internal(key, &value, &environment, None)?;
+    } else {
+        set_personal_env_var(key, &value)?;
+    }
+    println!(
+        "✓ Rolled back {} to the value set {} on {}",
+        key, when, revision.machine
+    );
+    Ok(())
+}
+
 #[derive(Clone, Copy)]
 struct EnvTemplate {
     id: &'static str,
@@ -2305,6 +2468,9 @@ pub(crate) fn delete_personal_env_vars(keys: &[String]) -> Result<()> {
         bail!("API error {}: {}", status, body);
     }
 
+    for key in keys {
+        record_env_history(&target, "production", key, None);
+    }
     println!("✓ Deleted {} key(s)", keys.len());
     Ok(())
 }
@@ -2365,6 +2531,9 @@ fn delete_project_env_vars(keys: &[String], environment: &str) -> Result<()> {
             delete_project_cloud_env_entries(name, environment, keys, &api_url, token, &client)?;
         }
     }
+    for key in keys {
+        record_env_history(&target, environment, key, None);
+    }
 
     let target_label = match target {
         EnvTarget::Personal { space } => {
@@ -2480,6 +2649,115 @@ fn pull(environment: &str) -> Result<()> {
     Ok(())
 }
 
+/// Render a .env template from the env store and write it to `out`.
+fn render(
+    template_path: &Path,
+    out: &Path,
+    personal: bool,
+    environment: &str,
+    dry_run: bool,
+) -> Result<()> {
+    let content = fs::read_to_string(template_path)
+        .with_context(|| format!("failed to read template {}", template_path.display()))?;
+    let template = env_template::EnvTemplate::parse(&content);
+    let keys = template.referenced_keys();
+
+    let store = if keys.is_empty() {
+        HashMap::new()
+    } else if personal {
+        fetch_personal_env_vars(&keys)?
+    } else {
+        fetch_project_env_vars(environment, &keys)?
+    };
+    let rendered = template.render(&store)?;
+
+    let existing = fs::read_to_string(out).unwrap_or_default();
+    let diff = EnvDiff::between(&existing, &rendered.content);
+    if diff.is_empty() && out.exists() {
+        println!("✓ {} is up to date", out.display());
+        return Ok(());
+    }
+    for line in diff.lines() {
+        println!("  {}", line);
+    }
+
+    if dry_run {
+        println!("Dry run: {} not written.", out.display());
+        return Ok(());
+    }
+    write_private_file(out, &rendered.content)?;
+    println!(
+        "✓ Rendered {} env vars from {} to {}",
+        rendered.entries.len(),
+        template_path.display(),
+        out.display()
+    );
+    Ok(())
+}
+
+/// Compare the keys in an .env.example with the env store.
+fn lint(example: &Path, personal: bool, environment: &str) -> Result<()> {
+    let content = fs::read_to_string(example)
+        .with_context(|| format!("failed to read {}", example.display()))?;
+    let mut required: Vec<String> = env_template::EnvTemplate::parse(&content).keys();
+    required.sort();
+    required.dedup();
+    if required.is_empty() {
+        println!("No keys found in {}", example.display());
+        return Ok(());
+    }
+
+    // Personal stores hold vars for every project, so only check the required keys.
+    let store = if personal {
+        fetch_personal_env_vars(&required)?
+    } else {
+        fetch_project_env_vars(environment, &[])?
+    };
+
+    let missing: Vec<&String> = required
+        .iter()
+        .filter(|key| store.get(*key).is_none_or(|value| value.is_empty()))
+        .collect();
+    let mut undocumented: Vec<&String> = if personal {
+        Vec::new()
+    } else {
+        store.keys().filter(|key| !required.contains(key)).collect()
+    };
+    undocumented.sort();
+
+    let source = if personal {
+        "personal".to_string()
+    } else {
+        format!("project ({})", environment)
+    };
+    for key in &undocumented {
+        println!(
+            "⚠ {} is set in {} but not listed in {}",
+            key,
+            source,
+            example.display()
+        );
+    }
+    if missing.is_empty() {
+        println!(
+            "✓ {} required keys from {} are set in {}",
+            required.len(),
+            example.display(),
+            source
+        );
+        return Ok(());
+    }
+    for key in &missing {
+        println!("✗ {} is missing from {}", key, source);
+    }
+    bail!(
+        "{} of {} required keys missing; set them with `f env {}set`",
+        missing.len(),
+        required.len(),
+        if personal { "" } else { "project " }
+    );
+}
+
 /// Push local .env to cloud.
 fn push(environment: &str) -> Result<()> {
     let env_path = resolve_env_file_path()?;
@@ -3414,6 +3692,7 @@ pub(crate) fn set_personal_env_var(key: &str, value: &str) -> Result<()> {
         return Err(err);
     }
 
+    record_env_history(&target, environment, key, Some(value));
     println!("✓ Set personal env var: {}", key);
     Ok(())
 }
@@ -3550,6 +3829,8 @@ fn set_project_env_var_internal(
         },
     };
 
+    record_env_history(&target, environment, key, Some(value));
+
     let masked = if resolved_value.len() > 8 {
         format!("{}...", &resolved_value[..4])
     } else {
@@ -0,0 +1,307 @@
+//! Change history for env store keys (`f env history`, `f env rollback`).
+//!
+//! Every set or delete made through `f env` on this machine appends a revision to
+//! `env/history/<store>/<environment>.json` in the flow state dir. Values are sealed to this
+//! machine's env sealer identity, so the history never holds plaintext. Only the newest `historyLimit` previous values
+//! are kept per key. The newest revision doubles as the "last rotated" timestamp that
+//! `f doctor` checks against `maxAge`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use chrono::{DateTime, Utc};
+use rand::{TryRng, rngs::SysRng};
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::env::{sanitize_env_segment, write_private_file};
+use crate::sealer_crypto::{get_sealer_id, seal, unseal};
+
+/// Previous values kept per key when `historyLimit` is not set.
+pub const DEFAULT_HISTORY_LIMIT: usize = 5;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HistoryFile {
+    /// Revisions per key, newest first.
+    #[serde(default)]
+    keys: BTreeMap<String, Vec<Revision>>,
+}
+
+/// One change to a key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Revision {
+    #[serde(with = "rfc3339")]
+    pub changed_at: DateTime<Utc>,
+    /// Host that made the change.
+    pub machine: String,
+    /// Sealed value; `None` when the key was deleted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value: Option<SealedValue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SealedValue {
+    sealer_id: String,
+    nonce_material_b64: String,
+    ciphertext_b64: String,
+}
+
+impl Revision {
+    pub fn is_delete(&self) -> bool {
+        self.value.is_none()
+    }
+
+    /// Decrypt the value with this machine's sealer secret.
+    pub fn open(&self, sealer_secret: &str) -> Result<Option<String>> {
+        let Some(sealed) = &self.value else {
+            return Ok(None);
+        };
+        let sealer_id = get_sealer_id(sealer_secret)?;
+        if sealed.sealer_id != sealer_id {
+            bail!(
+                "value was sealed by another env sealer identity ({}); it can only be read there",
+                sealed.sealer_id
+            );
+        }
+        let ciphertext = STANDARD
+            .decode(sealed.ciphertext_b64.as_bytes())
+            .context("failed to decode env history ciphertext")?;
+        let nonce_material = STANDARD
+            .decode(sealed.nonce_material_b64.as_bytes())
+            .context("failed to decode env history nonce")?;
+        let plaintext = unseal(&ciphertext, sealer_secret, &sealer_id, &nonce_material)
+            .context("failed to decrypt env history value")?;
+        Ok(Some(
+            String::from_utf8(plaintext).context("env history value is not valid UTF-8")?,
+        ))
+    }
+}
+
+/// A key whose newest value is older than the rotation policy allows.
+#[derive(Debug, Clone)]
+pub struct StaleKey {
+    pub store: String,
+    pub environment: String,
+    pub key: String,
+    pub changed_at: DateTime<Utc>,
+    pub machine: String,
+}
+
+/// Append a revision for `key`; `value` is `None` for a delete.
+pub fn record(
+    store: &str,
+    environment: &str,
+    key: &str,
+    value: Option<&str>,
+    sealer_secret: &str,
+    limit: usize,
+) -> Result<()> {
+    let path = history_path(store, environment);
+    let mut file = load(&path)?;
+    let value = value
+        .map(|value| seal_value(value, sealer_secret))
+        .transpose()?;
+    let revision = Revision {
+        changed_at: Utc::now(),
+        machine: machine_name(),
+        value,
+    };
+    push_revision(
+        file.keys.entry(key.to_string()).or_default(),
+        revision,
+        limit,
+    );
+    let content = serde_json::to_string_pretty(&file)?;
+    write_private_file(&path, &content)
+}
+
+/// Revisions for `key`, newest first.
+pub fn revisions(store: &str, environment: &str, key: &str) -> Result<Vec<Revision>> {
+    let mut file = load(&history_path(store, environment))?;
+    Ok(file.keys.remove(key).unwrap_or_default())
+}
+
+/// Keys across every store whose newest revision is a set older than `max_age_ms`.
+pub fn stale_keys(max_age_ms: i64) -> Result<Vec<StaleKey>> {
+    let root = history_root();
+    let mut stale = Vec::new();
+    let Ok(stores) = fs::read_dir(&root) else {
+        return Ok(stale);
+    };
+    let now = Utc::now();
+    for store in stores.flatten() {
+        let Ok(files) = fs::read_dir(store.path()) else {
+            continue;
+        };
+        let store_name = store.file_name().to_string_lossy().to_string();
+        for entry in files.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(environment) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let file = load(&path)?;
+            for (key, revisions) in file.keys {
+                if let Some(latest) = revisions.first()
+                    && is_stale(latest, now, max_age_ms)
+                {
+                    stale.push(StaleKey {
+                        store: store_name.clone(),
+                        environment: environment.to_string(),
+                        key,
+                        changed_at: latest.changed_at,
+                        machine: latest.machine.clone(),
+                    });
+                }
+            }
+        }
+    }
+    stale.sort_by_key(|entry| entry.changed_at);
+    Ok(stale)
+}
+
+/// Keep the current revision plus at most `limit` previous ones.
+fn push_revision(revisions: &mut Vec<Revision>, revision: Revision, limit: usize) {
+    revisions.insert(0, revision);
+    revisions.truncate(limit + 1);
+}
+
+fn is_stale(latest: &Revision, now: DateTime<Utc>, max_age_ms: i64) -> bool {
+    !latest.is_delete() && (now - latest.changed_at).num_milliseconds() > max_age_ms
+}
+
+fn seal_value(value: &str, sealer_secret: &str) -> Result<SealedValue> {
+    let sealer_id = get_sealer_id(sealer_secret)?;
+    let mut nonce_material = [0u8; 32];
+    SysRng
+        .try_fill_bytes(&mut nonce_material)
+        .context("failed to generate env history nonce")?;
+    let ciphertext = seal(value.as_bytes(), sealer_secret, &sealer_id, &nonce_material)
+        .context("failed to seal env history value")?;
+    Ok(SealedValue {
+        sealer_id,
+        nonce_material_b64: STANDARD.encode(nonce_material),
+        ciphertext_b64: STANDARD.encode(ciphertext),
+    })
+}
+
+fn history_root() -> PathBuf {
+    config::global_state_dir().join("env").join("history")
+}
+
+fn history_path(store: &str, environment: &str) -> PathBuf {
+    history_root()
+        .join(sanitize_env_segment(store))
+        .join(format!("{}.json", sanitize_env_segment(environment)))
+}
+
+fn load(path: &Path) -> Result<HistoryFile> {
+    if !path.exists() {
+        return Ok(HistoryFile::default());
+    }
+    let content =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// Serialize revision timestamps as RFC 3339 strings.
+mod rfc3339 {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        time: &DateTime<Utc>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&time.to_rfc3339())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Utc>, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        DateTime::parse_from_rfc3339(&raw)
+            .map(|time| time.with_timezone(&Utc))
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+fn machine_name() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| {
+            Command::new("hostname")
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+        })
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn test_secret() -> String {
+        let key = crate::sealer_crypto::new_x25519_private_key();
+        format!("sealerSecret_z{}", bs58::encode(key).into_string())
+    }
+
+    fn revision(days_ago: i64, value: Option<SealedValue>) -> Revision {
+        Revision {
+            changed_at: Utc::now() - Duration::days(days_ago),
+            machine: "mbp".to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn sealed_values_round_trip_for_the_same_identity_only() {
+        let secret = test_secret();
+        let mut rev = revision(0, Some(seal_value("sk-live-123", &secret).unwrap()));
+        assert!(
+            !rev.value
+                .as_ref()
+                .unwrap()
+                .ciphertext_b64
+                .contains("sk-live")
+        );
+        assert_eq!(rev.open(&secret).unwrap().as_deref(), Some("sk-live-123"));
+        assert!(rev.open(&test_secret()).is_err());
+
+        rev.value = None;
+        assert!(rev.is_delete());
+        assert_eq!(rev.open(&secret).unwrap(), None);
+    }
+
+    #[test]
+    fn keeps_current_plus_limit_previous_values() {
+        let mut revisions = Vec::new();
+        for days in (0..5).rev() {
+            push_revision(&mut revisions, revision(days, None), 2);
+        }
+        assert_eq!(revisions.len(), 3);
+        assert!(revisions[0].changed_at > revisions[2].changed_at);
+    }
+
+    #[test]
+    fn only_live_keys_past_max_age_are_stale() {
+        let secret = test_secret();
+        let max_age = 90 * 86_400_000;
+        let now = Utc::now();
+        let live = Some(seal_value("v", &secret).unwrap());
+        assert!(is_stale(&revision(120, live.clone()), now, max_age));
+        assert!(!is_stale(&revision(10, live), now, max_age));
+        assert!(!is_stale(&revision(120, None), now, max_age));
+    }
+}
@@ -0,0 +1,375 @@
+//! `.env` templating shared by `f env render` and host deploys.
+//!
+//! A template is a regular dotenv file whose values may reference env store keys:
+//!
+//! ```text
+//! DATABASE_URL=                      # empty value = ${DATABASE_URL}
+//! API_BASE=https://${API_HOST:-localhost:3000}/v1
+//! LOG_LEVEL=${LOG_LEVEL-info}
+//! PRICE=$$5                          # literal `$`
+//! ```
+//!
+//! `${KEY:-default}` falls back when KEY is unset or empty, `${KEY-default}` only when
+//! unset. Keys defined earlier in the template win over the store, so later lines can
+//! build on them.
+
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::{Result, bail};
+
+use crate::env::parse_env_file;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplateLine {
+    /// Comments, blank lines, and anything that is not `KEY=value`.
+    Verbatim(String),
+    Entry {
+        key: String,
+        value: String,
+    },
+}
+
+/// A parsed `.env` template.
+#[derive(Debug, Clone)]
+pub struct EnvTemplate {
+    lines: Vec<TemplateLine>,
+}
+
+/// Result of rendering a template.
+#[derive(Debug, Clone)]
+pub struct RenderedEnv {
+    pub content: String,
+    /// Rendered entries in template order.
+    pub entries: Vec<(String, String)>,
+}
+
+impl EnvTemplate {
+    pub fn parse(content: &str) -> Self {
+        let lines = content
+            .lines()
+            .map(|line| {
+                let trimmed = line.trim();
+                if trimmed.is_empty() || trimmed.starts_with('#') {
+                    return TemplateLine::Verbatim(line.to_string());
+                }
+                let trimmed = trimmed.strip_prefix("export ").unwrap_or(trimmed);
+                let (key, value) = trimmed.split_once('=').unwrap_or((trimmed, ""));
+                let key = key.trim();
+                if !is_valid_key(key) {
+                    return TemplateLine::Verbatim(line.to_string());
+                }
+                let value = unquote(strip_inline_comment(value.trim()));
+                let value = if value.is_empty() {
+                    format!("${{{key}}}")
+                } else {
+                    value.to_string()
+                };
+                TemplateLine::Entry {
+                    key: key.to_string(),
+                    value,
+                }
+            })
+            .collect();
+        Self { lines }
+    }
+
+    /// Keys defined by the template, in order.
+    pub fn keys(&self) -> Vec<String> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                TemplateLine::Entry { key, .. } => Some(key.clone()),
+                TemplateLine::Verbatim(_) => None,
+            })
+            .collect()
+    }
+
+    /// Store keys the template reads (`${KEY}` references, sorted).
+    pub fn referenced_keys(&self) -> Vec<String> {
+        let mut keys = BTreeSet::new();
+        for line in &self.lines {
+            if let TemplateLine::Entry { value, .. } = line {
+                collect_references(value, &mut keys);
+            }
+        }
+        keys.into_iter().collect()
+    }
+
+    /// Expand every entry against `store`. Fails listing all references that have
+    /// neither a value nor a default.
+    pub fn render(&self, store: &HashMap<String, String>) -> Result<RenderedEnv> {
+        let mut local: HashMap<String, String> = HashMap::new();
+        let mut entries = Vec::new();
+        let mut missing = BTreeSet::new();
+        let mut content = String::new();
+
+        for line in &self.lines {
+            match line {
+                TemplateLine::Verbatim(raw) => {
+                    content.push_str(raw);
+                    content.push('\n');
+                }
+                TemplateLine::Entry { key, value } => {
+                    let lookup = |name: &str| local.get(name).or_else(|| store.get(name)).cloned();
+                    let rendered = expand(value, &lookup, &mut missing)?;
+                    content.push_str(&format_env_line(key, &rendered));
+                    content.push('\n');
+                    local.insert(key.clone(), rendered.clone());
+                    entries.push((key.clone(), rendered));
+                }
+            }
+        }
+
+        if !missing.is_empty() {
+            let missing: Vec<_> = missing.into_iter().collect();
+            bail!(
+                "missing values for: {} (set them in the env store or add a default, e.g. ${{{}:-value}})",
+                missing.join(", "),
+                missing[0]
+            );
+        }
+
+        Ok(RenderedEnv { content, entries })
+    }
+}
+
+/// `KEY="value"` with backslashes and quotes escaped.
+pub fn format_env_line(key: &str, value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("{key}=\"{escaped}\"")
+}
+
+/// Render a plain key/value map as a sorted .env file with an optional comment header.
+pub fn render_env_vars(header: &[String], vars: &HashMap<String, String>) -> String {
+    let mut content = String::new();
+    for line in header {
+        content.push_str("# ");
+        content.push_str(line);
+        content.push('\n');
+    }
+    let mut keys: Vec<_> = vars.keys().collect();
+    keys.sort();
+    for key in keys {
+        content.push_str(&format_env_line(key, &vars[key]));
+        content.push('\n');
+    }
+    content
+}
+
+/// Key-level changes between two .env files. Values are never included.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EnvDiff {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl EnvDiff {
+    pub fn between(old_content: &str, new_content: &str) -> Self {
+        let old = parse_env_file(old_content);
+        let new = parse_env_file(new_content);
+        let mut diff = Self::default();
+        for (key, value) in &new {
+            match old.get(key) {
+                None => diff.added.push(key.clone()),
+                Some(previous) if previous != value => diff.changed.push(key.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.removed = old
+            .keys()
+            .filter(|key| !new.contains_key(*key))
+            .cloned()
+            .collect();
+        diff.added.sort();
+        diff.changed.sort();
+        diff.removed.sort();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+
+    /// `+ KEY` / `~ KEY` / `- KEY` lines.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        lines.extend(self.added.iter().map(|key| format!("+ {key}")));
+        lines.extend(self.changed.iter().map(|key| format!("~ {key}")));
+        lines.extend(self.removed.iter().map(|key| format!("- {key}")));
+        lines
+    }
+}
+
+fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c == '_' || c.is_ascii_alphabetic())
+        && chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')))
+        .unwrap_or(value)
+}
+
+/// Drop a trailing ` # comment` from unquoted values.
+fn strip_inline_comment(value: &str) -> &str {
+    if value.starts_with('"') || value.starts_with('\'') {
+        return value;
+    }
+    match value.find(" #") {
+        Some(idx) => value[..idx].trim_end(),
+        None => value,
+    }
+}
+
+/// Split `${...}` starting at `start` (index of `$`), returning the inner text and the
+/// index just past the closing brace.
+fn split_reference(value: &str, start: usize) -> Result<(&str, usize)> {
+    let bytes = value.as_bytes();
+    let mut depth = 0usize;
+    let mut idx = start + 2;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'{' if idx > 0 && bytes[idx - 1] == b'$' => depth += 1,
+            b'}' if depth == 0 => return Ok((&value[start + 2..idx], idx + 1)),
+            b'}' => depth -= 1,
+            _ => {}
+        }
+        idx += 1;
+    }
+    bail!("unterminated reference in '{value}'")
+}
+
+/// `NAME`, `NAME:-default`, or `NAME-default`.
+fn parse_reference(inner: &str) -> (&str, Option<(&str, bool)>) {
+    let name_end = inner
+        .find(|c: char| c != '_' && !c.is_ascii_alphanumeric())
+        .unwrap_or(inner.len());
+    let (name, rest) = inner.split_at(name_end);
+    if let Some(default) = rest.strip_prefix(":-") {
+        (name, Some((default, true)))
+    } else if let Some(default) = rest.strip_prefix('-') {
+        (name, Some((default, false)))
+    } else {
+        (name, None)
+    }
+}
+
+fn expand(
+    value: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    missing: &mut BTreeSet<String>,
+) -> Result<String> {
+    let mut out = String::with_capacity(value.len());
+    let mut idx = 0;
+    while let Some(offset) = value[idx..].find('$') {
+        let start = idx + offset;
+        out.push_str(&value[idx..start]);
+        let rest = &value[start..];
+        if rest.starts_with("$$") {
+            out.push('$');
+            idx = start + 2;
+        } else if rest.starts_with("${") {
+            let (inner, end) = split_reference(value, start)?;
+            let (name, default) = parse_reference(inner);
+            if !is_valid_key(name) {
+                bail!("invalid reference '${{{inner}}}'");
+            }
+            let resolved = match (lookup(name), default) {
+                (Some(found), Some((_, true))) if found.is_empty() => None,
+                (Some(found), _) => Some(found),
+                (None, _) => None,
+            };
+            match (resolved, default) {
+                (Some(found), _) => out.push_str(&found),
+                (None, Some((default, _))) => out.push_str(&expand(default, lookup, missing)?),
+                (None, None) => {
+                    missing.insert(name.to_string());
+                }
+            }
+            idx = end;
+        } else {
+            out.push('$');
+            idx = start + 1;
+        }
+    }
+    out.push_str(&value[idx..]);
+    Ok(out)
+}
+
+fn collect_references(value: &str, keys: &mut BTreeSet<String>) {
+    let mut idx = 0;
+    while let Some(offset) = value[idx..].find('$') {
+        let start = idx + offset;
+        let rest = &value[start..];
+        if rest.starts_with("$$") {
+            idx = start + 2;
+        } else if rest.starts_with("${") {
+            let Ok((inner, end)) = split_reference(value, start) else {
+                return;
+            };
+            let (name, default) = parse_reference(inner);
+            if is_valid_key(name) {
+                keys.insert(name.to_string());
+            }
+            if let Some((default, _)) = default {
+                collect_references(default, keys);
+            }
+            idx = end;
+        } else {
+            idx = start + 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn renders_references_defaults_and_local_keys() {
+        let template = EnvTemplate::parse(
+            "# app\nDATABASE_URL=\nHOST=${API_HOST:-localhost}\nURL=https://${HOST}/v1\nLOG=${LOG_LEVEL-info}\nPRICE=$$5\n",
+        );
+        assert_eq!(
+            template.referenced_keys(),
+            vec!["API_HOST", "DATABASE_URL", "HOST", "LOG_LEVEL"]
+        );
+
+        let rendered = template
+            .render(&store(&[
+                ("DATABASE_URL", "postgres://db"),
+                ("API_HOST", ""),
+                ("HOST", "ignored.example.com"),
+            ]))
+            .unwrap();
+        assert_eq!(
+            rendered.content,
+            "# app\nDATABASE_URL=\"postgres://db\"\nHOST=\"localhost\"\nURL=\"https://localhost/v1\"\nLOG=\"info\"\nPRICE=\"$5\"\n"
+        );
+    }
+
+    #[test]
+    fn reports_all_missing_references() {
+        let template = EnvTemplate::parse("A=\nB=${C}/${D:-${E}}\n");
+        let err = template.render(&HashMap::new()).unwrap_err().to_string();
+        assert!(err.contains("missing values for: A, C, E"), "{err}");
+    }
+
+    #[test]
+    fn diffs_keys_without_values() {
+        let diff = EnvDiff::between("A=1\nB=2\nC=3\n", "A=\"1\"\nB=\"9\"\nD=\"4\"\n");
+        assert_eq!(diff.lines(), vec!["+ D", "~ B", "- C"]);
+    }
+}
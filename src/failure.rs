@@ -178,6 +178,11 @@ pub fn record_task_failure(
     }
 }
 
+/// The most recently recorded task failure.
+pub fn latest_record() -> Result<FailureRecord> {
+    latest_entry().map(|entry| entry.record)
+}
+
 fn run_last(opts: FailureLastOpts) -> Result<()> {
     let entry = latest_entry()?;
     if opts.json {
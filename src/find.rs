@@ -0,0 +1,607 @@
+//! `f find`: one query across the places flow keeps project knowledge.
+//!
+//! Sources are task definitions, flow.toml itself, recorded runs (name, detail, and log file),
+//! the log store, and markdown under `docs/` and `.ai/docs/`. Each source is scanned when the
+//! command runs rather than copied into a separate index, so results never go stale. Hits are
+//! scored by match quality and then boosted by recency.
+
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use ignore::WalkBuilder;
+use serde::Serialize;
+
+use crate::cli::{FindOpts, FindSource};
+use crate::config::{self, Config};
+use crate::deploy_watch::format_age;
+use crate::{log_store, project_snapshot, projects, running, runs};
+
+const ALL_SOURCES: [FindSource; 5] = [
+    FindSource::Tasks,
+    FindSource::Config,
+    FindSource::Runs,
+    FindSource::Logs,
+    FindSource::Docs,
+];
+const DOC_DIRS: [&str; 2] = ["docs", ".ai/docs"];
+/// Most recent runs whose name, detail, and log file are searched.
+const MAX_RUNS: usize = 500;
+/// Only the end of larger run logs is searched.
+const MAX_RUN_LOG_BYTES: u64 = 2 * 1024 * 1024;
+/// Log store entries fetched per project before ranking.
+const MAX_LOG_ENTRIES: usize = 200;
+/// Matching lines kept per file, so one noisy file can't crowd out everything else.
+const MAX_LINES_PER_FILE: usize = 3;
+const SNIPPET_CHARS: usize = 160;
+
+/// One search result.
+#[derive(Debug, Clone, Serialize)]
+pub struct Hit {
+    pub source: &'static str,
+    pub project: Option<String>,
+    pub title: String,
+    /// Absolute `path:line`, run id, or log entry id.
+    pub location: String,
+    pub snippet: String,
+    pub timestamp_ms: Option<i64>,
+    pub score: f64,
+}
+
+/// A project searched for tasks, config, and docs.
+#[derive(Debug, Clone)]
+struct Project {
+    label: String,
+    name: Option<String>,
+    root: PathBuf,
+    config_path: PathBuf,
+}
+
+pub fn run(opts: FindOpts) -> Result<()> {
+    let query = opts.query.join(" ");
+    let matcher = Matcher::new(&query);
+    if matcher.terms.is_empty() {
+        bail!("search query is empty");
+    }
+    let projects = search_scope(opts.all_projects)?;
+    let sources = if opts.source.is_empty() {
+        ALL_SOURCES.to_vec()
+    } else {
+        opts.source.clone()
+    };
+
+    let now_ms = running::now_ms() as i64;
+    let mut hits = Vec::new();
+    for source in sources {
+        let found = match source {
+            FindSource::Tasks => Ok(search_tasks(&matcher, &projects)),
+            FindSource::Config => Ok(search_config(&matcher, &projects)),
+            FindSource::Runs => search_runs(&matcher, &projects, opts.all_projects),
+            FindSource::Logs => search_log_store(&matcher, &projects, opts.all_projects),
+            FindSource::Docs => Ok(search_docs(&matcher, &projects)),
+        };
+        match found {
+            Ok(found) => hits.extend(found),
+            Err(err) => tracing::warn!(?err, source = source_label(source), "search failed"),
+        }
+    }
+    rank(&mut hits, now_ms);
+    hits.truncate(opts.limit.max(1));
+
+    if opts.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&hits).context("failed to encode results JSON")?
+        );
+        return Ok(());
+    }
+    if hits.is_empty() {
+        println!("No matches for '{query}'.");
+        return Ok(());
+    }
+    for hit in &hits {
+        let mut meta = Vec::new();
+        if opts.all_projects
+            && let Some(project) = hit.project.as_deref()
+        {
+            meta.push(project.to_string());
+        }
+        if let Some(ts) = hit.timestamp_ms {
+            meta.push(format_age(now_ms.saturating_sub(ts)));
+        }
+        if meta.is_empty() {
+            println!("{:<6} {}", hit.source, hit.title);
+        } else {
+            println!("{:<6} {}  ({})", hit.source, hit.title, meta.join(", "));
+        }
+        if !hit.snippet.is_empty() {
+            println!("       {}", hit.snippet);
+        }
+    }
+    Ok(())
+}
+
+fn source_label(source: FindSource) -> &'static str {
+    match source {
+        FindSource::Tasks => "task",
+        FindSource::Config => "config",
+        FindSource::Runs => "run",
+        FindSource::Logs => "log",
+        FindSource::Docs => "doc",
+    }
+}
+
+/// The current project, plus every registered one with `all_projects`.
+fn search_scope(all_projects: bool) -> Result<Vec<Project>> {
+    let cwd = std::env::current_dir().context("failed to read current directory")?;
+    let mut scope = Vec::new();
+    if let Some(config_path) = project_snapshot::find_flow_toml_upwards(&cwd) {
+        let name = config::load(&config_path)
+            .ok()
+            .and_then(|cfg| cfg.project_name);
+        scope.push(project(name, config_path));
+    } else if !all_projects {
+        bail!(
+            "no flow.toml found in {} or its parents (use --all-projects to search every registered project)",
+            cwd.display()
+        );
+    }
+    if all_projects {
+        for entry in projects::list_projects()? {
+            let root = entry
+                .project_root
+                .canonicalize()
+                .unwrap_or(entry.project_root);
+            if entry.config_path.exists() && !scope.iter().any(|p: &Project| p.root == root) {
+                scope.push(project(Some(entry.name), entry.config_path));
+            }
+        }
+    }
+    Ok(scope)
+}
+
+fn project(name: Option<String>, config_path: PathBuf) -> Project {
+    let root = config_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let root = root.canonicalize().unwrap_or(root);
+    let label = name.clone().unwrap_or_else(|| {
+        root.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| root.display().to_string())
+    });
+    Project {
+        label,
+        name,
+        root,
+        config_path,
+    }
+}
+
+fn search_tasks(matcher: &Matcher, projects: &[Project]) -> Vec<Hit> {
+    let mut hits = Vec::new();
+    for project in projects {
+        let cfg: Config = match config::load(&project.config_path) {
+            Ok(cfg) => cfg,
+            Err(err) => {
+                tracing::debug!(?err, path = %project.config_path.display(), "skipping config");
+                continue;
+            }
+        };
+        let modified = modified_ms(&project.config_path);
+        for task in &cfg.tasks {
+            let summary = task
+                .description
+                .clone()
+                .unwrap_or_else(|| task.command.trim().to_string());
+            let Some(score) = matcher.score_name(&task.name).or_else(|| {
+                matcher.score(&format!(
+                    "{} {} {}",
+                    task.shortcuts.join(" "),
+                    summary,
+                    task.command
+                ))
+            }) else {
+                continue;
+            };
+            hits.push(Hit {
+                source: "task",
+                project: Some(project.label.clone()),
+                title: task.name.clone(),
+                location: project.config_path.display().to_string(),
+                snippet: snippet(&summary),
+                timestamp_ms: modified,
+                score,
+            });
+        }
+    }
+    hits
+}
+
+fn search_config(matcher: &Matcher, projects: &[Project]) -> Vec<Hit> {
+    projects
+        .iter()
+        .flat_map(|project| file_hits(matcher, "config", project, &project.config_path))
+        .collect()
+}
+
+fn search_docs(matcher: &Matcher, projects: &[Project]) -> Vec<Hit> {
+    let mut hits = Vec::new();
+    for project in projects {
+        for dir in DOC_DIRS {
+            let dir = project.root.join(dir);
+            if !dir.is_dir() {
+                continue;
+            }
+            for entry in WalkBuilder::new(&dir)
+                .build()
+                .filter_map(|entry| entry.ok())
+            {
+                let path = entry.path();
+                let is_markdown = path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| matches!(ext, "md" | "mdx"));
+                if is_markdown && path.is_file() {
+                    hits.extend(file_hits(matcher, "doc", project, path));
+                }
+            }
+        }
+    }
+    hits
+}
+
+/// Matching lines of a text file, best first and at most `MAX_LINES_PER_FILE`. A match on the
+/// file name alone yields one hit for the file.
+fn file_hits(matcher: &Matcher, source: &'static str, project: &Project, path: &Path) -> Vec<Hit> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let relative = path.strip_prefix(&project.root).unwrap_or(path).display();
+    let modified = modified_ms(path);
+    let hit = |title: String, location: String, snippet: String, score: f64| Hit {
+        source,
+        project: Some(project.label.clone()),
+        title,
+        location,
+        snippet,
+        timestamp_ms: modified,
+        score,
+    };
+
+    let mut lines: Vec<(usize, &str, f64)> = content
+        .lines()
+        .enumerate()
+        .filter_map(|(idx, line)| Some((idx + 1, line, matcher.score(line)?)))
+        .collect();
+    lines.sort_by(|a, b| b.2.total_cmp(&a.2).then(a.0.cmp(&b.0)));
+    let mut hits: Vec<Hit> = lines
+        .into_iter()
+        .take(MAX_LINES_PER_FILE)
+        .map(|(line_no, line, score)| {
+            hit(
+                format!("{relative}:{line_no}"),
+                format!("{}:{line_no}", path.display()),
+                snippet(line),
+                score,
+            )
+        })
+        .collect();
+    if hits.is_empty() {
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if let Some(score) = matcher.score_name(&stem) {
+            let first_line = content.lines().find(|line| !line.trim().is_empty());
+            hits.push(hit(
+                relative.to_string(),
+                path.display().to_string(),
+                snippet(first_line.unwrap_or_default()),
+                score,
+            ));
+        }
+    }
+    hits
+}
+
+fn search_runs(matcher: &Matcher, projects: &[Project], all_projects: bool) -> Result<Vec<Hit>> {
+    let conn = runs::open_runs_db()?;
+    let records = if all_projects {
+        runs::recent_runs_under(&conn, None, MAX_RUNS)?
+    } else {
+        let mut records = Vec::new();
+        for project in projects {
+            records.extend(runs::recent_runs_under(
+                &conn,
+                Some(&project.root),
+                MAX_RUNS,
+            )?);
+        }
+        records
+    };
+
+    let mut hits = Vec::new();
+    for record in records {
+        let meta_score = matcher.score_name(&record.name).or_else(|| {
+            record
+                .detail
+                .as_deref()
+                .and_then(|detail| matcher.score(detail))
+        });
+        let log_match = record.log_path.as_deref().and_then(|log_path| {
+            let content = read_tail(Path::new(log_path), MAX_RUN_LOG_BYTES)?;
+            content
+                .lines()
+                .enumerate()
+                .filter_map(|(idx, line)| Some((idx, line, matcher.score(line)?)))
+                .max_by(|a, b| a.2.total_cmp(&b.2))
+                .map(|(idx, line, score)| (format!("{log_path}:{}", idx + 1), snippet(line), score))
+        });
+        let (location, snippet, score) = match (meta_score, log_match) {
+            (None, None) => continue,
+            (Some(score), None) => (
+                record.id.clone(),
+                record.detail.as_deref().map(snippet).unwrap_or_default(),
+                score,
+            ),
+            (None, Some(found)) => found,
+            (Some(score), Some((location, snippet, log_score))) => {
+                (location, snippet, score.max(log_score) + 0.5)
+            }
+        };
+        hits.push(Hit {
+            source: "run",
+            project: record.project.clone(),
+            title: format!(
+                "{} {} {} ({})",
+                record.kind.as_str(),
+                record.name,
+                if record.success { "ok" } else { "failed" },
+                record.id
+            ),
+            location,
+            snippet,
+            timestamp_ms: Some(record.started_ms),
+            score,
+        });
+    }
+    Ok(hits)
+}
+
+fn search_log_store(
+    matcher: &Matcher,
+    projects: &[Project],
+    all_projects: bool,
+) -> Result<Vec<Hit>> {
+    let conn = log_store::open_log_db()?;
+    // The longest term narrows the SQL scan; the matcher checks the rest.
+    let needle = matcher
+        .terms
+        .iter()
+        .max_by_key(|term| term.len())
+        .map(String::as_str)
+        .unwrap_or_default();
+    let entries = if all_projects {
+        log_store::search_logs(&conn, None, needle, MAX_LOG_ENTRIES)?
+    } else {
+        let mut entries = Vec::new();
+        for project in projects {
+            let name = project.name.as_deref().unwrap_or(&project.label);
+            entries.extend(log_store::search_logs(
+                &conn,
+                Some(name),
+                needle,
+                MAX_LOG_ENTRIES,
+            )?);
+        }
+        entries
+    };
+
+    Ok(entries
+        .into_iter()
+        .filter_map(|stored| {
+            let entry = &stored.entry;
+            let text = format!(
+                "{}\n{}",
+                entry.content,
+                entry.stack.as_deref().unwrap_or_default()
+            );
+            let score = matcher.score(&text)?;
+            let line = entry
+                .content
+                .lines()
+                .find(|line| matcher.score(line).is_some())
+                .or_else(|| entry.content.lines().next())
+                .unwrap_or_default();
+            Some(Hit {
+                source: "log",
+                project: Some(entry.project.clone()),
+                title: format!("{} {} #{}", entry.service, entry.log_type, stored.id),
+                location: format!("log #{}", stored.id),
+                snippet: snippet(line),
+                timestamp_ms: Some(entry.timestamp),
+                score,
+            })
+        })
+        .collect())
+}
+
+/// Sort by match quality weighted by recency, best first.
+fn rank(hits: &mut [Hit], now_ms: i64) {
+    for hit in hits.iter_mut() {
+        hit.score *= recency_boost(hit.timestamp_ms, now_ms);
+    }
+    hits.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(b.timestamp_ms.cmp(&a.timestamp_ms))
+    });
+}
+
+/// 2.0 for something from just now, falling towards 1.0 as it ages by days.
+fn recency_boost(timestamp_ms: Option<i64>, now_ms: i64) -> f64 {
+    let Some(timestamp_ms) = timestamp_ms else {
+        return 1.0;
+    };
+    let age_days = now_ms.saturating_sub(timestamp_ms).max(0) as f64 / 86_400_000.0;
+    1.0 + 1.0 / (1.0 + age_days)
+}
+
+/// Lowercased query; text matches when it contains every term.
+struct Matcher {
+    phrase: String,
+    terms: Vec<String>,
+}
+
+impl Matcher {
+    fn new(query: &str) -> Self {
+        let phrase = query
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+        let terms = phrase.split_whitespace().map(str::to_string).collect();
+        Self { phrase, terms }
+    }
+
+    /// 1 for containing every term, plus up to 1 for the exact phrase and up to 0.5 for terms
+    /// that start a word.
+    fn score(&self, text: &str) -> Option<f64> {
+        let text = text.to_lowercase();
+        if self.terms.is_empty() || !self.terms.iter().all(|term| text.contains(term.as_str())) {
+            return None;
+        }
+        let mut score = 1.0;
+        if self.terms.len() > 1 && text.contains(&self.phrase) {
+            score += 1.0;
+        }
+        let at_word_start = self
+            .terms
+            .iter()
+            .filter(|term| starts_word(&text, term))
+            .count();
+        score += 0.5 * at_word_start as f64 / self.terms.len() as f64;
+        Some(score)
+    }
+
+    /// Names outrank free text, most of all on an exact or prefix match.
+    fn score_name(&self, name: &str) -> Option<f64> {
+        let name = name.to_lowercase();
+        if name == self.phrase {
+            Some(6.0)
+        } else if name.starts_with(&self.phrase) {
+            Some(4.0)
+        } else {
+            self.score(&name).map(|score| score + 1.0)
+        }
+    }
+}
+
+fn starts_word(text: &str, term: &str) -> bool {
+    text.match_indices(term).any(|(idx, _)| {
+        text[..idx]
+            .chars()
+            .next_back()
+            .is_none_or(|ch| !ch.is_alphanumeric())
+    })
+}
+
+fn snippet(line: &str) -> String {
+    let line = line.trim();
+    if line.chars().count() <= SNIPPET_CHARS {
+        return line.to_string();
+    }
+    let cut: String = line.chars().take(SNIPPET_CHARS).collect();
+    format!("{cut}…")
+}
+
+fn modified_ms(path: &Path) -> Option<i64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    let since_epoch = modified.duration_since(std::time::UNIX_EPOCH).ok()?;
+    Some(since_epoch.as_millis() as i64)
+}
+
+/// The file's content, or just its last `max_bytes` when larger.
+fn read_tail(path: &Path, max_bytes: u64) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    if len > max_bytes {
+        file.seek(SeekFrom::Start(len - max_bytes)).ok()?;
+    }
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_outrank_text_and_every_term_must_match() {
+        let matcher = Matcher::new("  Deploy  API ");
+        assert_eq!(matcher.terms, vec!["deploy", "api"]);
+        assert_eq!(matcher.score("deploy the web app"), None);
+
+        let exact = matcher.score_name("deploy api").unwrap();
+        let prefix = matcher.score_name("Deploy API staging").unwrap();
+        let name = matcher.score_name("api-deploy").unwrap();
+        let text = matcher.score("deploy the api").unwrap();
+        let scattered = matcher.score("rapid redeploy").unwrap();
+        assert!(exact > prefix && prefix > name && name > text && text > scattered);
+    }
+
+    #[test]
+    fn recency_breaks_ties_between_equal_matches() {
+        let day = 86_400_000;
+        let hit = |title: &str, timestamp_ms| Hit {
+            source: "run",
+            project: None,
+            title: title.to_string(),
+            location: String::new(),
+            snippet: String::new(),
+            timestamp_ms,
+            score: 1.0,
+        };
+        let mut hits = vec![
+            hit("last month", Some(0)),
+            hit("undated", None),
+            hit("today", Some(30 * day)),
+        ];
+        rank(&mut hits, 30 * day);
+        let titles: Vec<_> = hits.iter().map(|hit| hit.title.as_str()).collect();
+        assert_eq!(titles, vec!["today", "last month", "undated"]);
+    }
+
+    #[test]
+    fn file_hits_keep_the_best_lines_per_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        let path = root.join("docs/migrations.md");
+        fs::write(
+            &path,
+            "# Migrations\nrun migrate\nmigrate up\nmigrate down\nwe remigrate\nnothing here\n",
+        )
+        .unwrap();
+        let project = project(Some("app".to_string()), root.join("flow.toml"));
+
+        let hits = file_hits(&Matcher::new("migrate"), "doc", &project, &path);
+        let titles: Vec<_> = hits.iter().map(|hit| hit.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec![
+                "docs/migrations.md:2",
+                "docs/migrations.md:3",
+                "docs/migrations.md:4"
+            ]
+        );
+
+        let hits = file_hits(&Matcher::new("migrations"), "doc", &project, &path);
+        assert_eq!(hits[0].title, "docs/migrations.md:1");
+        assert!(file_hits(&Matcher::new("rollback"), "doc", &project, &path).is_empty());
+    }
+}
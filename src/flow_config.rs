@@ -13,6 +13,7 @@ use which::which;
 
 use crate::cli::{ConfigAction, ConfigCommand};
 use crate::config::{self, TsFlowConfig};
+use crate::{config_edit, config_interp};
 
 const TS_CONFIG_LOADER: &str = r#"#!/usr/bin/env node
 import { pathToFileURL } from "node:url";
@@ -244,6 +245,9 @@ pub fn run(cmd: ConfigCommand) -> Result<()> {
                 print_eval_summary(&snapshot);
             }
         }
+        ConfigAction::Get(opts) => config_edit::run_get(opts)?,
+        ConfigAction::Set(opts) => config_edit::run_set(opts)?,
+        ConfigAction::Resolve(opts) => config_interp::run_resolve(opts)?,
     }
     Ok(())
 }
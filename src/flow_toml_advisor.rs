@@ -0,0 +1,448 @@
+//! flow.toml upgrade advisor behind `f doctor`.
+//!
+//! Reports legacy key spellings, missing Codex baseline sections (skills, commit gates), and
+//! tasks that are hard to discover. `f doctor --fix` writes the renames and baseline sections
+//! back after showing a diff; `f setup` only points here instead of rewriting the file.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use toml_edit::{DocumentMut, Item, TableLike};
+
+use crate::{config, config_edit, line_diff, setup, skills};
+
+/// Legacy top-level spellings the loader still accepts, with their canonical key.
+const ROOT_RENAMES: &[(&str, &str)] = &[
+    ("project_name", "name"),
+    ("project-name", "name"),
+    ("env-space", "env_space"),
+    ("env-space-kind", "env_space_kind"),
+    ("env-space-scope", "env_space_kind"),
+    ("env-files", "env_files"),
+    ("push-policy", "push_policy"),
+    ("pushPolicy", "push_policy"),
+    ("task-resolution", "task_resolution"),
+    ("taskResolution", "task_resolution"),
+    ("explain_commits", "explain-commits"),
+    ("production", "prod"),
+];
+
+const COMMIT_RENAMES: &[(&str, &str)] = &[
+    ("message_tool", "message-tool"),
+    ("messageTool", "message-tool"),
+    ("message_model", "message-model"),
+    ("messageModel", "message-model"),
+    ("review_fail_open", "review-fail-open"),
+    ("reviewFailOpen", "review-fail-open"),
+    ("message_fail_open", "message-fail-open"),
+    ("messageFailOpen", "message-fail-open"),
+    ("review_fallbacks", "review-fallbacks"),
+    ("reviewFallbacks", "review-fallbacks"),
+    ("message_fallbacks", "message-fallbacks"),
+    ("messageFallbacks", "message-fallbacks"),
+    ("queue-on-issues", "queue_on_issues"),
+    ("queueOnIssues", "queue_on_issues"),
+    ("quick_default", "quick-default"),
+    ("quickDefault", "quick-default"),
+    ("skill-gate", "skill_gate"),
+    ("skillGate", "skill_gate"),
+    ("secret-scan", "secret_scan"),
+    ("secretScan", "secret_scan"),
+    ("review_push_gate", "review-push-gate"),
+    ("reviewPushGate", "review-push-gate"),
+];
+
+const TASK_RENAMES: &[(&str, &str)] = &[
+    ("desc", "description"),
+    ("short", "shortcuts"),
+    ("shortcut", "shortcuts"),
+    ("delegate_to_hub", "delegate-to-hub"),
+    ("confirm-on-match", "confirm_on_match"),
+    ("on-cancel", "on_cancel"),
+    ("output-file", "output_file"),
+    ("remote-dir", "remote_dir"),
+    ("remote-env", "remote_env"),
+];
+
+/// A legacy key and the spelling it should use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyRename {
+    /// Where the key lives, e.g. "top level", "[commit]", or "task 'dev'".
+    pub location: String,
+    pub from: String,
+    pub to: String,
+    /// The canonical key is set too, so the legacy one is left for a manual fix.
+    pub conflict: bool,
+}
+
+#[derive(Debug)]
+pub struct Advice {
+    pub renames: Vec<KeyRename>,
+    /// Baseline section headers `--fix` would append, e.g. "[commit.skill_gate]".
+    pub missing_sections: Vec<String>,
+    pub tasks_without_description: Vec<String>,
+    /// (task, suggested shortcut) for multi-word task names without shortcuts.
+    pub shortcut_suggestions: Vec<(String, String)>,
+    current: String,
+    upgraded: String,
+}
+
+impl Advice {
+    /// Whether `--fix` would change the file.
+    pub fn has_fixes(&self) -> bool {
+        self.current != self.upgraded
+    }
+
+    pub fn is_clean(&self) -> bool {
+        !self.has_fixes()
+            && self.renames.is_empty()
+            && self.tasks_without_description.is_empty()
+            && self.shortcut_suggestions.is_empty()
+    }
+
+    /// The changed lines `--fix` would write, with a little context.
+    pub fn diff(&self) -> String {
+        compact_diff(
+            &line_diff::render_line_diff(&self.current, &self.upgraded),
+            2,
+        )
+    }
+
+    /// Number of changes `--fix` would apply (renames plus appended sections).
+    pub fn fix_count(&self) -> usize {
+        self.renames
+            .iter()
+            .filter(|rename| !rename.conflict)
+            .count()
+            + self.missing_sections.len()
+    }
+}
+
+/// Inspect flow.toml text. `project_root` decides whether the Bun testing gate is recommended.
+pub fn analyze(project_root: &Path, content: &str) -> Result<Advice> {
+    let mut current = content.to_string();
+    if !current.ends_with('\n') {
+        current.push('\n');
+    }
+    let mut doc = config_edit::parse(&current)?;
+    let renames = rename_legacy_keys(&mut doc)?;
+    let renamed = if renames.iter().any(|rename| !rename.conflict) {
+        doc.to_string()
+    } else {
+        current.clone()
+    };
+
+    let bun = setup::detect_bun_context(project_root, &renamed);
+    let upgraded = setup::ensure_codex_flow_baseline(&renamed, bun);
+    let missing_sections = upgraded
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with('[') && !setup::contains_toml_section(&renamed, line))
+        .map(str::to_string)
+        .collect();
+
+    let (tasks_without_description, shortcut_suggestions) = task_hints(&doc);
+    Ok(Advice {
+        renames,
+        missing_sections,
+        tasks_without_description,
+        shortcut_suggestions,
+        current,
+        upgraded,
+    })
+}
+
+/// Analyze the flow.toml at `path` (the project root is its directory).
+pub fn analyze_file(path: &Path) -> Result<Advice> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    analyze(project_root(path), &content)
+        .with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// Write the upgrades to `path` and reload Codex skills when the skills config asks for it.
+pub fn apply(path: &Path, advice: &Advice) -> Result<()> {
+    fs::write(path, &advice.upgraded)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    let cfg = config::load(path)?;
+    skills::maybe_reload_codex_skills(
+        project_root(path),
+        cfg.skills.as_ref(),
+        "doctor baseline upgrade",
+    );
+    Ok(())
+}
+
+fn project_root(path: &Path) -> &Path {
+    path.parent().unwrap_or_else(|| Path::new("."))
+}
+
+fn rename_legacy_keys(doc: &mut DocumentMut) -> Result<Vec<KeyRename>> {
+    let mut renames = Vec::new();
+    rename_keys(doc.as_table_mut(), "top level", ROOT_RENAMES, &mut renames);
+    if let Some(commit) = doc.get_mut("commit").and_then(Item::as_table_like_mut) {
+        rename_keys(commit, "[commit]", COMMIT_RENAMES, &mut renames);
+    }
+    if let Some(tasks) = doc.get_mut("tasks").and_then(Item::as_array_of_tables_mut) {
+        for task in tasks.iter_mut() {
+            let name = task
+                .get("name")
+                .and_then(Item::as_str)
+                .unwrap_or("?")
+                .to_string();
+            rename_keys(task, &format!("task '{name}'"), TASK_RENAMES, &mut renames);
+        }
+    }
+    move_jj_remote(doc, &mut renames)?;
+    Ok(renames)
+}
+
+fn rename_keys(
+    table: &mut dyn TableLike,
+    location: &str,
+    pairs: &[(&str, &str)],
+    renames: &mut Vec<KeyRename>,
+) {
+    for (from, to) in pairs {
+        if !table.contains_key(from) {
+            continue;
+        }
+        let conflict = table.contains_key(to);
+        if !conflict && let Some(item) = table.remove(from) {
+            table.insert(to, item);
+        }
+        renames.push(KeyRename {
+            location: location.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            conflict,
+        });
+    }
+}
+
+/// `[jj].remote` is only read as a fallback for `[git].remote`.
+fn move_jj_remote(doc: &mut DocumentMut, renames: &mut Vec<KeyRename>) -> Result<()> {
+    let Some(Item::Value(remote)) = config_edit::get(doc, "jj.remote")? else {
+        return Ok(());
+    };
+    let conflict = config_edit::get(doc, "git.remote")?.is_some();
+    if !conflict {
+        config_edit::remove(doc, "jj.remote")?;
+        config_edit::upsert_table(doc, "git", vec![("remote", Some(remote))])?;
+        if doc
+            .get("jj")
+            .and_then(Item::as_table_like)
+            .is_some_and(|jj| jj.is_empty())
+        {
+            doc.remove("jj");
+        }
+    }
+    renames.push(KeyRename {
+        location: "[jj]".to_string(),
+        from: "remote".to_string(),
+        to: "[git].remote".to_string(),
+        conflict,
+    });
+    Ok(())
+}
+
+fn task_hints(doc: &DocumentMut) -> (Vec<String>, Vec<(String, String)>) {
+    let Some(tasks) = doc.get("tasks").and_then(Item::as_array_of_tables) else {
+        return (Vec::new(), Vec::new());
+    };
+
+    let mut taken = BTreeSet::new();
+    for task in tasks.iter() {
+        if let Some(name) = task.get("name").and_then(Item::as_str) {
+            taken.insert(name.to_string());
+        }
+        match task.get("shortcuts") {
+            Some(Item::Value(toml_edit::Value::String(shortcut))) => {
+                taken.insert(shortcut.value().clone());
+            }
+            Some(Item::Value(toml_edit::Value::Array(shortcuts))) => {
+                taken.extend(
+                    shortcuts
+                        .iter()
+                        .filter_map(|v| v.as_str())
+                        .map(str::to_string),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    let mut undocumented = Vec::new();
+    let mut suggestions = Vec::new();
+    for task in tasks.iter() {
+        let Some(name) = task.get("name").and_then(Item::as_str) else {
+            continue;
+        };
+        let described = task
+            .get("description")
+            .and_then(Item::as_str)
+            .is_some_and(|desc| !desc.trim().is_empty());
+        if !described {
+            undocumented.push(name.to_string());
+        }
+        if task.contains_key("shortcuts") {
+            continue;
+        }
+        if let Some(shortcut) = suggest_shortcut(name)
+            && taken.insert(shortcut.clone())
+        {
+            suggestions.push((name.to_string(), shortcut));
+        }
+    }
+    (undocumented, suggestions)
+}
+
+/// Initials of a multi-word task name: "deploy-cli-release" -> "dcr".
+fn suggest_shortcut(name: &str) -> Option<String> {
+    let words: Vec<&str> = name
+        .split(['-', '_', ':', ' '])
+        .filter(|word| !word.is_empty())
+        .collect();
+    if words.len() < 2 {
+        return None;
+    }
+    words
+        .iter()
+        .map(|word| word.chars().next().filter(char::is_ascii_alphanumeric))
+        .collect::<Option<String>>()
+        .map(|initials| initials.to_ascii_lowercase())
+}
+
+/// Keep changed lines of a `render_line_diff` and `context` unchanged lines around them.
+fn compact_diff(diff: &str, context: usize) -> String {
+    let lines: Vec<&str> = diff.lines().collect();
+    let changed: Vec<bool> = lines.iter().map(|line| !line.starts_with("  ")).collect();
+    let keep: Vec<bool> = (0..lines.len())
+        .map(|i| {
+            let start = i.saturating_sub(context);
+            let end = (i + context + 1).min(lines.len());
+            changed[start..end].iter().any(|c| *c)
+        })
+        .collect();
+
+    let mut out = String::new();
+    let mut skipped = false;
+    for (line, keep) in lines.iter().zip(keep) {
+        if keep {
+            if skipped && !out.is_empty() {
+                out.push_str("  ...\n");
+            }
+            out.push_str(line);
+            out.push('\n');
+            skipped = false;
+        } else {
+            skipped = true;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn renames_legacy_keys_and_adds_baseline() {
+        let dir = tempdir().expect("tempdir");
+        let advice = analyze(
+            dir.path(),
+            r#"project_name = "demo"
+
+[jj]
+remote = "upstream"
+
+[commit]
+messageTool = "claude"
+
+[[tasks]]
+name = "deploy-cli-release"
+desc = "Ship the CLI"
+command = "./release.sh"
+
+[[tasks]]
+name = "lint"
+command = "cargo clippy"
+shortcuts = ["l"]
+"#,
+        )
+        .expect("analyze");
+
+        let renamed: Vec<(&str, &str)> = advice
+            .renames
+            .iter()
+            .map(|rename| (rename.from.as_str(), rename.to.as_str()))
+            .collect();
+        assert_eq!(
+            renamed,
+            vec![
+                ("project_name", "name"),
+                ("messageTool", "message-tool"),
+                ("desc", "description"),
+                ("remote", "[git].remote"),
+            ]
+        );
+        assert_eq!(advice.tasks_without_description, vec!["lint"]);
+        assert_eq!(
+            advice.shortcut_suggestions,
+            vec![("deploy-cli-release".to_string(), "dcr".to_string())]
+        );
+        assert!(
+            advice
+                .missing_sections
+                .contains(&"[commit.skill_gate]".to_string())
+        );
+        assert!(
+            !advice
+                .missing_sections
+                .contains(&"[commit.testing]".to_string())
+        );
+
+        let upgraded: toml::Value = toml::from_str(&advice.upgraded).expect("valid toml");
+        assert_eq!(upgraded["name"].as_str(), Some("demo"));
+        assert!(advice.upgraded.contains("message-tool = \"claude\""));
+        assert!(advice.upgraded.contains("[git]\nremote = \"upstream\""));
+        assert!(!advice.upgraded.contains("[jj]"));
+        assert!(advice.upgraded.contains("[skills.codex]"));
+        assert!(advice.diff().contains("+ [commit.skill_gate]"));
+    }
+
+    #[test]
+    fn conflicting_legacy_key_is_reported_not_rewritten() {
+        let dir = tempdir().expect("tempdir");
+        fs::write(dir.path().join("bun.lock"), "").expect("write bun.lock");
+        let advice = analyze(
+            dir.path(),
+            "name = \"demo\"\nproject-name = \"old\"\n\n[[tasks]]\nname = \"setup\"\ncommand = \"bun install\"\n",
+        )
+        .expect("analyze");
+
+        assert!(advice.renames[0].conflict);
+        assert_eq!(advice.fix_count(), advice.missing_sections.len());
+        assert!(advice.upgraded.contains("project-name = \"old\""));
+        assert!(
+            advice
+                .missing_sections
+                .contains(&"[commit.testing]".to_string())
+        );
+    }
+
+    #[test]
+    fn up_to_date_file_is_clean() {
+        let dir = tempdir().expect("tempdir");
+        let baseline = setup::ensure_codex_flow_baseline(
+            "[[tasks]]\nname = \"dev\"\ndescription = \"Run the app\"\ncommand = \"cargo run\"\n",
+            false,
+        );
+        let advice = analyze(dir.path(), &baseline).expect("analyze");
+        assert!(advice.is_clean(), "{advice:?}");
+        assert_eq!(advice.diff(), "");
+    }
+}
@@ -0,0 +1,609 @@
+//! Per-repo Flow git hooks: `f hooks install|uninstall|status`.
+//!
+//! Flow's pre-commit (staged secret scan), commit-msg (message checks), and pre-push (push
+//! policy) hooks are installed alongside whatever already runs the repo's hooks instead of
+//! replacing it:
+//!
+//! - husky: a marked block appended to `.husky/<hook>`.
+//! - lefthook: a marked block of `flow-*` commands in `lefthook-local.yml`.
+//! - otherwise: a Flow script in the hooks directory (repo-local `core.hooksPath` or
+//!   `.git/hooks`); an existing hook is moved to `<hook>.pre-flow` and run after Flow's check.
+
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+
+use crate::cli::{HooksAction, HooksCommand};
+use crate::push_hook::FLOW_HOOK_HELPERS;
+use crate::{config, push_policy};
+
+const FLOW_REPO_HOOK_MARKER: &str = "flow-repo-hook-v1";
+const BLOCK_BEGIN: &str = "# >>> flow-repo-hook-v1";
+const BLOCK_END: &str = "# <<< flow-repo-hook-v1";
+/// Suffix for a displaced hook that the Flow script chains to.
+const CHAINED_SUFFIX: &str = ".pre-flow";
+const LEFTHOOK_LOCAL: &str = "lefthook-local.yml";
+const LEFTHOOK_CONFIGS: [&str; 4] = [
+    "lefthook.yml",
+    "lefthook.yaml",
+    ".lefthook.yml",
+    ".lefthook.yaml",
+];
+
+pub const HOOK_NAMES: [&str; 3] = ["pre-commit", "commit-msg", "pre-push"];
+
+/// What runs the repo's hooks, and so where Flow's hooks go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookManager {
+    /// `.husky/` directory.
+    Husky(PathBuf),
+    /// Repo root holding the lefthook config.
+    Lefthook(PathBuf),
+    /// Hooks directory git runs directly.
+    HooksDir(PathBuf),
+}
+
+impl HookManager {
+    fn label(&self) -> &'static str {
+        match self {
+            HookManager::Husky(_) => "husky",
+            HookManager::Lefthook(_) => "lefthook",
+            HookManager::HooksDir(_) => "git hooks directory",
+        }
+    }
+
+    fn hook_path(&self, name: &str) -> PathBuf {
+        match self {
+            HookManager::Husky(dir) | HookManager::HooksDir(dir) => dir.join(name),
+            HookManager::Lefthook(root) => root.join(LEFTHOOK_LOCAL),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookState {
+    Installed,
+    /// Flow-managed but differs from what this flow version writes.
+    Outdated,
+    /// Flow-managed script git can't execute.
+    NotExecutable,
+    /// A hook exists but doesn't run Flow.
+    Foreign,
+    Missing,
+}
+
+impl HookState {
+    fn label(self) -> &'static str {
+        match self {
+            HookState::Installed => "installed",
+            HookState::Outdated => "outdated (re-run `f hooks install`)",
+            HookState::NotExecutable => "not executable (re-run `f hooks install`)",
+            HookState::Foreign => "not Flow-managed",
+            HookState::Missing => "missing",
+        }
+    }
+
+    fn is_flow(self) -> bool {
+        matches!(
+            self,
+            HookState::Installed | HookState::Outdated | HookState::NotExecutable
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HookStatus {
+    pub name: &'static str,
+    pub path: PathBuf,
+    pub state: HookState,
+    /// Displaced hook run after Flow's check.
+    pub chained: Option<PathBuf>,
+}
+
+pub fn run(cmd: HooksCommand) -> Result<()> {
+    let repo_root = repo_root()?;
+    match cmd.action.unwrap_or(HooksAction::Status) {
+        HooksAction::Install => install(&repo_root),
+        HooksAction::Uninstall => uninstall(&repo_root),
+        HooksAction::Status => print_status(&repo_root),
+    }
+}
+
+/// Find what manages the hooks of the repo at `repo_root`.
+pub fn detect_manager(repo_root: &Path) -> Result<HookManager> {
+    let local_hooks_path = git_config(repo_root, &["--local", "--get", "core.hooksPath"]);
+    let husky_dir = repo_root.join(".husky");
+    if husky_dir.is_dir()
+        && local_hooks_path
+            .as_deref()
+            .is_none_or(|path| path.trim_start_matches("./").starts_with(".husky"))
+    {
+        return Ok(HookManager::Husky(husky_dir));
+    }
+    if local_hooks_path.is_none()
+        && LEFTHOOK_CONFIGS
+            .iter()
+            .any(|name| repo_root.join(name).is_file())
+    {
+        return Ok(HookManager::Lefthook(repo_root.to_path_buf()));
+    }
+
+    let output = Command::new("git")
+        .current_dir(repo_root)
+        .args(["rev-parse", "--git-path", "hooks"])
+        .output()
+        .context("failed to resolve git hooks directory")?;
+    if !output.status.success() {
+        bail!("{} is not a git repository", repo_root.display());
+    }
+    let dir = config::expand_path(String::from_utf8_lossy(&output.stdout).trim());
+    Ok(HookManager::HooksDir(if dir.is_absolute() {
+        dir
+    } else {
+        repo_root.join(dir)
+    }))
+}
+
+/// State of each Flow hook in the repo at `repo_root`.
+pub fn inspect(repo_root: &Path) -> Result<(HookManager, Vec<HookStatus>)> {
+    let manager = detect_manager(repo_root)?;
+    let statuses = HOOK_NAMES
+        .iter()
+        .map(|&name| {
+            let path = manager.hook_path(name);
+            let chained = match &manager {
+                HookManager::HooksDir(dir) => Some(dir.join(format!("{name}{CHAINED_SUFFIX}")))
+                    .filter(|chained| chained.exists()),
+                _ => None,
+            };
+            let state = hook_state(&manager, name, &path);
+            HookStatus {
+                name,
+                path,
+                state,
+                chained,
+            }
+        })
+        .collect();
+    Ok((manager, statuses))
+}
+
+fn hook_state(manager: &HookManager, name: &str, path: &Path) -> HookState {
+    let Ok(content) = fs::read_to_string(path) else {
+        return HookState::Missing;
+    };
+    match manager {
+        HookManager::HooksDir(_) => {
+            if !content.contains(FLOW_REPO_HOOK_MARKER) {
+                HookState::Foreign
+            } else if content != render_hook_script(name) {
+                HookState::Outdated
+            } else if !is_executable(path) {
+                HookState::NotExecutable
+            } else {
+                HookState::Installed
+            }
+        }
+        HookManager::Husky(_) | HookManager::Lefthook(_) => {
+            let expected = match manager {
+                HookManager::Husky(_) => render_husky_block(name),
+                _ => render_lefthook_block(),
+            };
+            match extract_block(&content) {
+                Some(block) if block == expected => HookState::Installed,
+                Some(_) => HookState::Outdated,
+                None => HookState::Foreign,
+            }
+        }
+    }
+}
+
+fn install(repo_root: &Path) -> Result<()> {
+    let manager = detect_manager(repo_root)?;
+    match &manager {
+        HookManager::HooksDir(dir) => {
+            ensure_repo_local_hooks_dir(repo_root, dir)?;
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+            for name in HOOK_NAMES {
+                install_hook_script(dir, name)?;
+            }
+        }
+        HookManager::Husky(dir) => {
+            for name in HOOK_NAMES {
+                let path = dir.join(name);
+                let existing = fs::read_to_string(&path).unwrap_or_default();
+                write_hook_file(&path, &upsert_block(&existing, &render_husky_block(name)))?;
+                println!("Installed Flow {name} hook in {}", path.display());
+            }
+        }
+        HookManager::Lefthook(root) => {
+            let path = root.join(LEFTHOOK_LOCAL);
+            let existing = fs::read_to_string(&path).unwrap_or_default();
+            let (rest, _) = strip_block(&existing);
+            if let Some(name) = HOOK_NAMES.iter().find(|name| {
+                rest.lines()
+                    .any(|line| line.starts_with(&format!("{name}:")))
+            }) {
+                bail!(
+                    "{} already configures {name}; add Flow's commands to it by hand:\n\n{}",
+                    path.display(),
+                    render_lefthook_block()
+                );
+            }
+            fs::write(&path, upsert_block(&existing, &render_lefthook_block()))
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            println!("Installed Flow hooks in {}", path.display());
+            println!("Run `lefthook install` if lefthook's git hooks aren't installed yet.");
+        }
+    }
+    Ok(())
+}
+
+/// Write the Flow script for `name`, moving a non-Flow hook aside so the script chains to it.
+fn install_hook_script(dir: &Path, name: &str) -> Result<()> {
+    let path = dir.join(name);
+    let chained = dir.join(format!("{name}{CHAINED_SUFFIX}"));
+    if path.exists()
+        && hook_state(&HookManager::HooksDir(dir.to_path_buf()), name, &path) == HookState::Foreign
+    {
+        if chained.exists() {
+            bail!(
+                "Both {} and {} exist; remove one before installing Flow hooks.",
+                path.display(),
+                chained.display()
+            );
+        }
+        fs::rename(&path, &chained)
+            .with_context(|| format!("failed to move {} aside", path.display()))?;
+        println!("Chaining existing {name} hook via {}", chained.display());
+    }
+    write_hook_file(&path, &render_hook_script(name))?;
+    println!("Installed Flow {name} hook at {}", path.display());
+    Ok(())
+}
+
+fn uninstall(repo_root: &Path) -> Result<()> {
+    let manager = detect_manager(repo_root)?;
+    match &manager {
+        HookManager::HooksDir(dir) => {
+            for name in HOOK_NAMES {
+                let path = dir.join(name);
+                match hook_state(&manager, name, &path) {
+                    HookState::Missing => {
+                        println!("No Flow {name} hook found at {}", path.display());
+                        continue;
+                    }
+                    HookState::Foreign => {
+                        println!("Leaving non-Flow {name} hook at {}", path.display());
+                        continue;
+                    }
+                    _ => {}
+                }
+                fs::remove_file(&path)
+                    .with_context(|| format!("failed to remove {}", path.display()))?;
+                println!("Removed Flow {name} hook at {}", path.display());
+                let chained = dir.join(format!("{name}{CHAINED_SUFFIX}"));
+                if chained.exists() {
+                    fs::rename(&chained, &path)
+                        .with_context(|| format!("failed to restore {}", chained.display()))?;
+                    println!("Restored previous {name} hook");
+                }
+            }
+        }
+        HookManager::Husky(_) | HookManager::Lefthook(_) => {
+            let mut paths: Vec<PathBuf> = HOOK_NAMES
+                .iter()
+                .map(|name| manager.hook_path(name))
+                .collect();
+            paths.dedup();
+            for path in paths {
+                let Ok(existing) = fs::read_to_string(&path) else {
+                    continue;
+                };
+                let (rest, removed) = strip_block(&existing);
+                if !removed {
+                    continue;
+                }
+                if is_blank_script(&rest) {
+                    fs::remove_file(&path)
+                        .with_context(|| format!("failed to remove {}", path.display()))?;
+                } else {
+                    fs::write(&path, rest)
+                        .with_context(|| format!("failed to write {}", path.display()))?;
+                }
+                println!("Removed Flow hooks from {}", path.display());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_status(repo_root: &Path) -> Result<()> {
+    let (manager, statuses) = inspect(repo_root)?;
+    println!("Flow repo hook status ({})", repo_root.display());
+    println!("Hook manager: {}", manager.label());
+    for status in &statuses {
+        println!(
+            "{}: {} ({})",
+            status.name,
+            status.state.label(),
+            status.path.display()
+        );
+        if let Some(chained) = &status.chained {
+            println!("  chains {}", chained.display());
+        }
+    }
+    if let Some(global) = global_hooks_path(repo_root) {
+        println!("Global core.hooksPath: {}", global.display());
+    }
+    Ok(())
+}
+
+/// Hook integrity report for `f doctor`; `None` outside a repo with Flow hooks installed.
+pub fn doctor_report() -> Option<Vec<String>> {
+    let (manager, statuses) = inspect(&repo_root().ok()?).ok()?;
+    if !statuses.iter().any(|status| status.state.is_flow()) {
+        return None;
+    }
+    let mut lines = vec![format!("Git hooks ({}):", manager.label())];
+    for status in statuses {
+        let icon = if status.state == HookState::Installed {
+            "✅"
+        } else {
+            "⚠️ "
+        };
+        lines.push(format!(
+            "{icon} {} {} ({})",
+            status.name,
+            status.state.label(),
+            status.path.display()
+        ));
+    }
+    Some(lines)
+}
+
+/// Refuse to write into a hooks directory shared by every repo through global core.hooksPath.
+fn ensure_repo_local_hooks_dir(repo_root: &Path, dir: &Path) -> Result<()> {
+    if git_config(repo_root, &["--local", "--get", "core.hooksPath"]).is_some() {
+        return Ok(());
+    }
+    let Some(global) = global_hooks_path(repo_root) else {
+        return Ok(());
+    };
+    if normalize_path(&global) != normalize_path(dir) {
+        return Ok(());
+    }
+    let flow_global = push_policy::effective_global_hooks_path()
+        .is_ok_and(|path| normalize_path(&path) == normalize_path(dir));
+    bail!(
+        "Hooks for this repo come from global core.hooksPath {}, shared by every repo.{}\nSet a repo-local hooks path first (git config core.hooksPath .git/hooks) to install per-repo Flow hooks.",
+        dir.display(),
+        if flow_global {
+            " It already runs Flow's commit-msg and pre-push hooks (`f push hooks`)."
+        } else {
+            ""
+        }
+    )
+}
+
+fn global_hooks_path(repo_root: &Path) -> Option<PathBuf> {
+    git_config(repo_root, &["--global", "--get", "core.hooksPath"])
+        .map(|value| config::expand_path(&value))
+}
+
+fn git_config(repo_root: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .current_dir(repo_root)
+        .arg("config")
+        .args(args)
+        .output()
+        .ok()?;
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !value.is_empty()).then_some(value)
+}
+
+fn repo_root() -> Result<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+        .context("failed to resolve git repo root")?;
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || value.is_empty() {
+        bail!("not inside a git repository");
+    }
+    Ok(PathBuf::from(value))
+}
+
+/// The flow invocation each hook runs, in terms of the hook's shell arguments.
+fn hook_command(name: &str) -> &'static str {
+    match name {
+        "pre-commit" => "pre-commit-hook",
+        "commit-msg" => "commit-msg-hook \"$1\"",
+        _ => "push hook-eval --remote-name \"${1:-}\" --remote-url \"${2:-}\"",
+    }
+}
+
+fn render_hook_script(name: &str) -> String {
+    let command = hook_command(name);
+    // pre-push gets the ref list on stdin; keep a copy for the chained hook.
+    let (capture, redirect) = if name == "pre-push" {
+        (
+            r#"payload_file="$(mktemp "${TMPDIR:-/tmp}/flow-pre-push.XXXXXX")"
+trap 'rm -f "$payload_file"' EXIT
+cat >"$payload_file"
+
+"#,
+            r#" <"$payload_file""#,
+        )
+    } else {
+        ("", "")
+    };
+    format!(
+        r#"#!/usr/bin/env bash
+set -euo pipefail
+
+# {FLOW_REPO_HOOK_MARKER}
+
+{FLOW_HOOK_HELPERS}
+
+{capture}flow_bin="$(resolve_flow_bin)"
+"$flow_bin" {command}{redirect}
+
+chained="$(dirname "$0")/{name}{CHAINED_SUFFIX}"
+if [[ -x "$chained" ]]; then
+  "$chained" "$@"{redirect}
+fi
+"#
+    )
+}
+
+fn render_husky_block(name: &str) -> String {
+    format!(
+        "{BLOCK_BEGIN}\n\"${{FLOW_PUSH_FLOW_BIN:-f}}\" {}\n{BLOCK_END}",
+        hook_command(name)
+    )
+}
+
+fn render_lefthook_block() -> String {
+    format!(
+        r#"{BLOCK_BEGIN}
+pre-commit:
+  commands:
+    flow-pre-commit:
+      run: f pre-commit-hook
+commit-msg:
+  commands:
+    flow-commit-msg:
+      run: f commit-msg-hook {{1}}
+pre-push:
+  commands:
+    flow-pre-push:
+      run: f push hook-eval --remote-name {{1}} --remote-url {{2}}
+      use_stdin: true
+{BLOCK_END}"#
+    )
+}
+
+fn block_range(content: &str) -> Option<Range<usize>> {
+    let start = content.find(BLOCK_BEGIN)?;
+    let end = start + content[start..].find(BLOCK_END)? + BLOCK_END.len();
+    Some(start..end)
+}
+
+fn extract_block(content: &str) -> Option<&str> {
+    block_range(content).map(|range| &content[range])
+}
+
+/// `content` without the Flow block, and whether there was one.
+fn strip_block(content: &str) -> (String, bool) {
+    let Some(range) = block_range(content) else {
+        return (content.to_string(), false);
+    };
+    let before = content[..range.start].trim_end_matches('\n');
+    let after = content[range.end..].trim_start_matches('\n');
+    let mut rest = before.to_string();
+    if !before.is_empty() && !after.is_empty() {
+        rest.push('\n');
+    }
+    rest.push_str(after);
+    if !rest.is_empty() && !rest.ends_with('\n') {
+        rest.push('\n');
+    }
+    (rest, true)
+}
+
+/// Replace the Flow block in `content`, or append it after a blank line.
+fn upsert_block(content: &str, block: &str) -> String {
+    let (rest, _) = strip_block(content);
+    if rest.trim().is_empty() {
+        return format!("{block}\n");
+    }
+    format!("{}\n\n{block}\n", rest.trim_end_matches('\n'))
+}
+
+/// Nothing left but a shebang and blank lines.
+fn is_blank_script(content: &str) -> bool {
+    content
+        .lines()
+        .all(|line| line.trim().is_empty() || line.starts_with("#!"))
+}
+
+fn write_hook_file(path: &Path, content: &str) -> Result<()> {
+    fs::write(path, content).with_context(|| format!("failed to write {}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = fs::metadata(path)?.permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(path, perms)?;
+    }
+    Ok(())
+}
+
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::metadata(path).is_ok_and(|meta| meta.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        path.exists()
+    }
+}
+
+fn normalize_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_upsert_and_strip_keep_surrounding_script() {
+        let husky = "#!/usr/bin/env sh\nnpx lint-staged\n";
+        let block = render_husky_block("pre-commit");
+        let installed = upsert_block(husky, &block);
+        assert!(installed.starts_with(husky));
+        assert!(installed.contains("\"${FLOW_PUSH_FLOW_BIN:-f}\" pre-commit-hook"));
+        assert_eq!(upsert_block(&installed, &block), installed);
+        assert_eq!(extract_block(&installed), Some(block.as_str()));
+
+        let (rest, removed) = strip_block(&installed);
+        assert!(removed);
+        assert_eq!(rest, husky);
+        assert!(is_blank_script(&strip_block(&upsert_block("", &block)).0));
+    }
+
+    #[test]
+    fn install_chains_existing_hook_and_flags_outdated_script() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let existing = "#!/bin/sh\necho existing\n";
+        fs::write(dir.path().join("pre-commit"), existing).unwrap();
+
+        install_hook_script(dir.path(), "pre-commit").unwrap();
+        let manager = HookManager::HooksDir(dir.path().to_path_buf());
+        let path = dir.path().join("pre-commit");
+        assert_eq!(
+            hook_state(&manager, "pre-commit", &path),
+            HookState::Installed
+        );
+        let chained = dir.path().join("pre-commit.pre-flow");
+        assert_eq!(fs::read_to_string(&chained).unwrap(), existing);
+
+        // Re-installing updates the Flow script without touching the chained hook.
+        install_hook_script(dir.path(), "pre-commit").unwrap();
+        assert_eq!(fs::read_to_string(&chained).unwrap(), existing);
+
+        fs::write(&path, render_hook_script("pre-push")).unwrap();
+        assert_eq!(
+            hook_state(&manager, "pre-commit", &path),
+            HookState::Outdated
+        );
+    }
+}
@@ -23,7 +23,7 @@ pub fn run(_opts: HealthOpts) -> Result<()> {
     ensure_fish_flow_init()?;
     ensure_gitignore()?;
 
-    doctor::run(crate::cli::DoctorOpts {})?;
+    doctor::run(crate::cli::DoctorOpts::default())?;
     ensure_ai_server()?;
     ensure_unhash()?;
     ensure_rise_health()?;
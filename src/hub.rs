@@ -4,8 +4,8 @@ use anyhow::Result;
 use reqwest::blocking::Client;
 
 use crate::{
-    cli::{HubAction, HubCommand, HubOpts},
-    daemon, docs, supervisor,
+    cli::{HubAction, HubCommand, HubDashboardOpts, HubOpts, ServerOpts},
+    daemon, docs, hub_backup, hub_queue, hub_service, log_server, notify_rules, supervisor, web,
 };
 
 /// Flow acts as a thin launcher that makes sure the lin hub daemon is running.
@@ -37,9 +37,34 @@ pub fn run(cmd: HubCommand) -> Result<()> {
             docs::stop_docs_hub_daemon()?;
             Ok(())
         }
+        HubAction::Queue(queue_opts) => hub_queue::run(queue_opts),
+        HubAction::Notify(notify_opts) => notify_rules::run_hub_notify(notify_opts),
+        HubAction::Install(install_opts) => hub_service::install(install_opts),
+        HubAction::Uninstall => hub_service::uninstall(),
+        HubAction::Status => hub_service::status(opts.host, opts.port),
+        HubAction::Run(run_opts) => hub_service::run_foreground(run_opts),
+        HubAction::Dashboard(dashboard_opts) => open_dashboard(&opts, &dashboard_opts),
+        HubAction::Backup(backup_opts) => hub_backup::backup(backup_opts),
+        HubAction::Restore(restore_opts) => hub_backup::restore(restore_opts, &opts),
     }
 }
 
+/// Make sure the flow server is up, then open the dashboard it serves.
+fn open_dashboard(opts: &HubOpts, dashboard: &HubDashboardOpts) -> Result<()> {
+    log_server::run(ServerOpts {
+        host: opts.host.to_string(),
+        port: opts.port,
+        action: None,
+    })?;
+    let url = format!("{}/dashboard/", format_addr(opts.host, opts.port));
+    if dashboard.no_open {
+        println!("{url}");
+        return Ok(());
+    }
+    println!("Opening {url}");
+    web::open_in_browser(&url)
+}
+
 fn ensure_daemon(opts: &HubOpts) -> Result<()> {
     let host = opts.host;
     let port = opts.port;
@@ -54,6 +79,18 @@ fn ensure_daemon(opts: &HubOpts) -> Result<()> {
         return Ok(());
     }
 
+    // With a login service installed, the service manager owns the daemon process.
+    if hub_service::installed() {
+        hub_service::start()?;
+        if !opts.no_ui {
+            println!(
+                "Lin watcher daemon started via login service at {}",
+                format_addr(host, port)
+            );
+        }
+        return Ok(());
+    }
+
     supervisor::ensure_running(true, !opts.no_ui)?;
 
     let action = crate::cli::DaemonAction::Start {
@@ -70,6 +107,15 @@ fn ensure_daemon(opts: &HubOpts) -> Result<()> {
 }
 
 fn stop_daemon(opts: &HubOpts) -> Result<()> {
+    if hub_service::installed() {
+        hub_service::stop()?;
+        if !opts.no_ui {
+            println!(
+                "Lin hub stopped; the login service starts it again at next login or `f hub`."
+            );
+        }
+        return Ok(());
+    }
     let action = crate::cli::DaemonAction::Stop {
         name: "lin".to_string(),
     };
@@ -0,0 +1,540 @@
+//! `f hub backup` / `f hub restore`: move flow state to another machine.
+//!
+//! The archive is a tarball (zstd, gzip, or plain, chosen by extension) laid out as:
+//!
+//! ```text
+//! manifest.json        what was captured, the old $HOME, and registered projects
+//! flow.db              project registry, runs, and logs (copied with VACUUM INTO)
+//! history.jsonl        task invocation history
+//! env-local/           local env backend (`FLOW_ENV_BACKEND=local`)
+//! deploy.json          hosts from `f deploy setup`
+//! projects/<n>/.flow   per-project state (deploy audit log, ...)
+//! ```
+//!
+//! Restore moves anything it replaces aside as `<name>.pre-restore-<stamp>` and rewrites
+//! project paths from the old home directory (plus any `--remap OLD=NEW`) so the registry
+//! points at the new machine's checkouts.
+
+use std::fs;
+use std::io::IsTerminal;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use rusqlite::{Connection, params};
+use serde::{Deserialize, Serialize};
+
+use crate::cli::{HubBackupOpts, HubOpts, HubRestoreOpts};
+use crate::{db, deploy, env, history, hub, running};
+
+const MANIFEST_VERSION: u32 = 1;
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    created_at: String,
+    /// `$HOME` on the machine that made the backup; restore rewrites paths under it.
+    home: String,
+    /// Top-level entries present in the archive.
+    contents: Vec<String>,
+    projects: Vec<BackupProject>,
+    /// "all", "none", or "<n> days".
+    logs: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BackupProject {
+    name: String,
+    root: String,
+    /// Archive directory holding the project's `.flow`, when it had one.
+    flow_dir: Option<String>,
+}
+
+/// One file or directory restored to a fixed location.
+struct StatePath {
+    name: &'static str,
+    path: PathBuf,
+}
+
+fn state_paths() -> Vec<StatePath> {
+    vec![
+        StatePath {
+            name: "flow.db",
+            path: db::db_path(),
+        },
+        StatePath {
+            name: "history.jsonl",
+            path: history::history_path(),
+        },
+        StatePath {
+            name: "env-local",
+            path: env::local_env_dir(),
+        },
+        StatePath {
+            name: "deploy.json",
+            path: deploy::deploy_config_path(),
+        },
+    ]
+}
+
+pub fn backup(opts: HubBackupOpts) -> Result<()> {
+    let staging = tempfile::tempdir().context("failed to create staging directory")?;
+    let stage = staging.path();
+    let mut contents = Vec::new();
+
+    let db_path = db::db_path();
+    let mut projects = Vec::new();
+    if db_path.exists() {
+        let staged_db = stage.join("flow.db");
+        let conn = db::open_db()?;
+        conn.execute("VACUUM INTO ?1", params![staged_db.to_string_lossy()])
+            .context("failed to snapshot flow.db")?;
+        drop(conn);
+        let staged = Connection::open(&staged_db)?;
+        trim_logs(&staged, &opts)?;
+        projects = registered_projects(&staged)?;
+        contents.push("flow.db".to_string());
+    }
+
+    for state in state_paths() {
+        if state.name == "flow.db" || (state.name == "env-local" && opts.no_env) {
+            continue;
+        }
+        if !state.path.exists() {
+            continue;
+        }
+        copy_path(&state.path, &stage.join(state.name))?;
+        contents.push(state.name.to_string());
+    }
+
+    let mut entries = Vec::new();
+    for (idx, (name, root)) in projects.into_iter().enumerate() {
+        let flow_dir = Path::new(&root).join(".flow");
+        let archived = if flow_dir.is_dir() {
+            let dir = format!("projects/{idx}");
+            copy_path(&flow_dir, &stage.join(&dir).join(".flow"))?;
+            Some(dir)
+        } else {
+            None
+        };
+        entries.push(BackupProject {
+            name,
+            root,
+            flow_dir: archived,
+        });
+    }
+    if entries.iter().any(|project| project.flow_dir.is_some()) {
+        contents.push("projects".to_string());
+    }
+
+    let manifest = Manifest {
+        version: MANIFEST_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        home: home_dir().to_string_lossy().into_owned(),
+        contents,
+        projects: entries,
+        logs: if opts.no_logs {
+            "none".to_string()
+        } else if let Some(days) = opts.log_days {
+            format!("{days} days")
+        } else {
+            "all".to_string()
+        },
+    };
+    fs::write(
+        stage.join("manifest.json"),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+
+    run_tar(&opts.out, Compression::for_path(&opts.out), |cmd| {
+        cmd.arg("-c").arg("-C").arg(stage).arg(".");
+    })?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&opts.out, fs::Permissions::from_mode(0o600))?;
+    }
+
+    let size = fs::metadata(&opts.out).map(|m| m.len()).unwrap_or(0);
+    println!(
+        "Wrote {} ({:.1} MB): {}",
+        opts.out.display(),
+        size as f64 / 1_048_576.0,
+        manifest.contents.join(", ")
+    );
+    let with_state = manifest
+        .projects
+        .iter()
+        .filter(|project| project.flow_dir.is_some())
+        .count();
+    println!(
+        "{} registered project(s), {} with .flow state; logs: {}",
+        manifest.projects.len(),
+        with_state,
+        manifest.logs
+    );
+    if manifest.contents.iter().any(|name| name == "env-local") {
+        println!(
+            "The archive contains local env values; keep it private (or rerun with --no-env)."
+        );
+    }
+    Ok(())
+}
+
+pub fn restore(opts: HubRestoreOpts, hub_opts: &HubOpts) -> Result<()> {
+    if hub::hub_healthy(hub_opts.host, hub_opts.port) {
+        bail!("the hub daemon is running and holds flow.db open; stop it with `f hub stop` first");
+    }
+    if !opts.archive.exists() {
+        bail!("{} not found", opts.archive.display());
+    }
+    let remaps = opts
+        .remap
+        .iter()
+        .map(|raw| {
+            raw.split_once('=')
+                .map(|(from, to)| (from.to_string(), to.to_string()))
+                .with_context(|| format!("--remap expects OLD=NEW, got '{raw}'"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let staging = tempfile::tempdir().context("failed to create staging directory")?;
+    let stage = staging.path();
+    run_tar(&opts.archive, Compression::for_path(&opts.archive), |cmd| {
+        cmd.arg("-x").arg("-C").arg(stage);
+    })?;
+    let manifest: Manifest = serde_json::from_str(
+        &fs::read_to_string(stage.join("manifest.json"))
+            .context("archive has no manifest.json; is it from `f hub backup`?")?,
+    )?;
+    if manifest.version > MANIFEST_VERSION {
+        bail!(
+            "backup format {} is newer than this flow supports ({}); upgrade flow first",
+            manifest.version,
+            MANIFEST_VERSION
+        );
+    }
+
+    let new_home = home_dir().to_string_lossy().into_owned();
+    let mut rules = remaps;
+    if manifest.home != new_home {
+        rules.push((manifest.home.clone(), new_home));
+    }
+
+    println!(
+        "Backup from {} ({}): {}",
+        manifest.created_at,
+        manifest.home,
+        manifest.contents.join(", ")
+    );
+    let states: Vec<StatePath> = state_paths()
+        .into_iter()
+        .filter(|state| stage.join(state.name).exists())
+        .collect();
+    for state in &states {
+        let note = if state.path.exists() {
+            " (current copy kept as .pre-restore)"
+        } else {
+            ""
+        };
+        println!("  {} -> {}{}", state.name, state.path.display(), note);
+    }
+    let mut project_dirs = Vec::new();
+    for project in &manifest.projects {
+        let root = remap_path(&project.root, &rules);
+        let Some(dir) = project.flow_dir.as_deref() else {
+            continue;
+        };
+        if Path::new(&root).is_dir() {
+            println!("  {}/.flow -> {}/.flow", project.name, root);
+            project_dirs.push((
+                stage.join(dir).join(".flow"),
+                Path::new(&root).join(".flow"),
+            ));
+        } else {
+            println!(
+                "  {}/.flow skipped: {} does not exist here (clone it, or pass --remap)",
+                project.name, root
+            );
+        }
+    }
+
+    if !opts.yes {
+        if !std::io::stdin().is_terminal() {
+            bail!("restore replaces flow state; pass --yes to run non-interactively");
+        }
+        if !prompt_yes_no("Restore?")? {
+            println!("Aborted.");
+            return Ok(());
+        }
+    }
+
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+    for state in &states {
+        if state.name == "flow.db" {
+            for suffix in ["-wal", "-shm"] {
+                let sidecar = PathBuf::from(format!("{}{suffix}", state.path.display()));
+                move_aside(&sidecar, &stamp)?;
+            }
+        }
+        move_aside(&state.path, &stamp)?;
+        copy_path(&stage.join(state.name), &state.path)?;
+    }
+    for (from, to) in &project_dirs {
+        move_aside(to, &stamp)?;
+        copy_path(from, to)?;
+    }
+    if stage.join("flow.db").exists() && !rules.is_empty() {
+        let conn = db::open_db()?;
+        let moved = remap_registry(&conn, &rules)?;
+        if moved > 0 {
+            println!("Updated {moved} project path(s) in the registry.");
+        }
+    }
+    println!("✓ Restored flow state. Start the hub again with `f hub`.");
+    Ok(())
+}
+
+/// Drop logs per `--no-logs` / `--log-days` from the staged database.
+fn trim_logs(conn: &Connection, opts: &HubBackupOpts) -> Result<()> {
+    let has_logs: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'logs')",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap_or(false);
+    if !has_logs {
+        return Ok(());
+    }
+    let removed = if opts.no_logs {
+        conn.execute("DELETE FROM logs", [])?
+    } else if let Some(days) = opts.log_days {
+        let cutoff = running::now_ms() as i64 - i64::from(days) * DAY_MS;
+        conn.execute("DELETE FROM logs WHERE timestamp < ?1", params![cutoff])?
+    } else {
+        0
+    };
+    if removed > 0 {
+        conn.execute_batch("VACUUM")?;
+    }
+    Ok(())
+}
+
+fn registered_projects(conn: &Connection) -> Result<Vec<(String, String)>> {
+    let has_projects: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'projects')",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap_or(false);
+    if !has_projects {
+        return Ok(Vec::new());
+    }
+    let mut stmt = conn.prepare("SELECT name, project_root FROM projects ORDER BY name")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+/// Rewrite registry paths with `rules`; returns how many projects moved.
+fn remap_registry(conn: &Connection, rules: &[(String, String)]) -> Result<usize> {
+    let rows: Vec<(String, String, String)> = {
+        let mut stmt = conn.prepare("SELECT name, project_root, config_path FROM projects")?;
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<rusqlite::Result<_>>()?
+    };
+    let mut moved = 0;
+    for (name, root, config_path) in rows {
+        let new_root = remap_path(&root, rules);
+        if new_root == root {
+            continue;
+        }
+        conn.execute(
+            "UPDATE projects SET project_root = ?1, config_path = ?2 WHERE name = ?3",
+            params![new_root, remap_path(&config_path, rules), name],
+        )?;
+        moved += 1;
+    }
+    Ok(moved)
+}
+
+/// Apply the first rule whose prefix matches `path` at a path boundary.
+fn remap_path(path: &str, rules: &[(String, String)]) -> String {
+    for (from, to) in rules {
+        let from = from.trim_end_matches('/');
+        if let Some(rest) = path.strip_prefix(from)
+            && (rest.is_empty() || rest.starts_with('/'))
+        {
+            return format!("{}{rest}", to.trim_end_matches('/'));
+        }
+    }
+    path.to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    Zstd,
+    Gzip,
+    None,
+}
+
+impl Compression {
+    fn for_path(path: &Path) -> Self {
+        let name = path.to_string_lossy();
+        if name.ends_with(".zst") || name.ends_with(".tzst") {
+            Self::Zstd
+        } else if name.ends_with(".gz") || name.ends_with(".tgz") {
+            Self::Gzip
+        } else {
+            Self::None
+        }
+    }
+}
+
+fn run_tar(
+    archive: &Path,
+    compression: Compression,
+    mode: impl FnOnce(&mut Command),
+) -> Result<()> {
+    let mut cmd = Command::new("tar");
+    match compression {
+        Compression::Zstd => {
+            cmd.arg("--zstd");
+        }
+        Compression::Gzip => {
+            cmd.arg("-z");
+        }
+        Compression::None => {}
+    }
+    cmd.arg("-f").arg(archive);
+    mode(&mut cmd);
+    let output = cmd.output().context("failed to run tar")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if compression == Compression::Zstd && stderr.contains("zstd") {
+            bail!(
+                "tar could not use zstd ({}); install zstd or use a .tar.gz name",
+                stderr.trim()
+            );
+        }
+        bail!("tar failed: {}", stderr.trim());
+    }
+    Ok(())
+}
+
+fn move_aside(path: &Path, stamp: &str) -> Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let aside = PathBuf::from(format!("{}.pre-restore-{stamp}", path.display()));
+    fs::rename(path, &aside).with_context(|| format!("failed to move {} aside", path.display()))
+}
+
+fn copy_path(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            if entry.file_type()?.is_symlink() {
+                continue;
+            }
+            copy_path(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        // Keep private directories (env-local is 0700) private.
+        fs::set_permissions(to, fs::metadata(from)?.permissions())?;
+        Ok(())
+    } else {
+        fs::copy(from, to)
+            .map(|_| ())
+            .with_context(|| format!("failed to copy {}", from.display()))
+    }
+}
+
+fn home_dir() -> PathBuf {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn prompt_yes_no(message: &str) -> Result<bool> {
+    print!("{message} [y/N]: ");
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    let answer = input.trim().to_ascii_lowercase();
+    Ok(answer == "y" || answer == "yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remaps_paths_at_directory_boundaries() {
+        let rules = vec![
+            ("/srv/code".to_string(), "/work".to_string()),
+            ("/Users/me/".to_string(), "/home/me".to_string()),
+        ];
+        assert_eq!(remap_path("/srv/code/flow", &rules), "/work/flow");
+        assert_eq!(remap_path("/srv/codex/flow", &rules), "/srv/codex/flow");
+        assert_eq!(
+            remap_path("/Users/me/code/app/flow.toml", &rules),
+            "/home/me/code/app/flow.toml"
+        );
+        assert_eq!(remap_path("/Users/me", &rules), "/home/me");
+    }
+
+    #[test]
+    fn trims_old_logs_and_rewrites_registry() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::log_store::init_schema(&conn).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE projects (name TEXT PRIMARY KEY, project_root TEXT NOT NULL, \
+             config_path TEXT NOT NULL, updated_ms INTEGER NOT NULL);
+             INSERT INTO projects VALUES ('app', '/old/home/app', '/old/home/app/flow.toml', 0);
+             INSERT INTO projects VALUES ('tmp', '/tmp/tmp', '/tmp/tmp/flow.toml', 0);",
+        )
+        .unwrap();
+        let now = running::now_ms() as i64;
+        for timestamp in [now, now - 10 * DAY_MS] {
+            conn.execute(
+                "INSERT INTO logs (project, content, timestamp, log_type, service) \
+                 VALUES ('app', 'x', ?1, 'log', 'web')",
+                params![timestamp],
+            )
+            .unwrap();
+        }
+
+        let opts = HubBackupOpts {
+            out: PathBuf::from("flow-backup.tar.zst"),
+            log_days: Some(7),
+            no_logs: false,
+            no_env: false,
+        };
+        trim_logs(&conn, &opts).unwrap();
+        let remaining: i64 = conn
+            .query_row("SELECT COUNT(*) FROM logs", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(remaining, 1);
+
+        let rules = vec![("/old/home".to_string(), "/new/home".to_string())];
+        assert_eq!(remap_registry(&conn, &rules).unwrap(), 1);
+        let (root, config_path): (String, String) = conn
+            .query_row(
+                "SELECT project_root, config_path FROM projects WHERE name = 'app'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(root, "/new/home/app");
+        assert_eq!(config_path, "/new/home/app/flow.toml");
+    }
+}
@@ -0,0 +1,279 @@
+//! Browser dashboard served by the flow server at `/dashboard`.
+//!
+//! The page and its assets are embedded in the binary. It polls `/dashboard/api/summary` for
+//! projects, running tasks, recent runs, deploy status, and recent logs. Requests are
+//! authorized like `/logs/ingest`: a bearer token scopes the view to the token's project, and
+//! without one, projects that have an active token are left out.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use axum::{
+    Router,
+    extract::{Path as AxumPath, Query},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Json, Redirect, Response},
+    routing::get,
+};
+use rust_embed::RustEmbed;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::log_store::{self, LogQuery, StoredLogEntry};
+use crate::log_tokens::{self, Scope};
+use crate::runs::{self, RunKind, RunQuery, RunRecord};
+use crate::{log_server, running};
+
+const RECENT_RUNS: usize = 30;
+const RECENT_LOGS: usize = 100;
+
+#[derive(RustEmbed)]
+#[folder = "assets/dashboard/"]
+struct Assets;
+
+/// Dashboard routes, merged into the flow server's router.
+pub fn router<S: Clone + Send + Sync + 'static>() -> Router<S> {
+    Router::new()
+        .route(
+            "/dashboard",
+            get(|| async { Redirect::permanent("/dashboard/") }),
+        )
+        .route("/dashboard/", get(|| async { asset("index.html") }))
+        .route("/dashboard/api/summary", get(summary))
+        .route(
+            "/dashboard/{*path}",
+            get(|AxumPath(path): AxumPath<String>| async move { asset(&path) }),
+        )
+}
+
+fn asset(path: &str) -> Response {
+    match Assets::get(path) {
+        Some(file) => (
+            [(header::CONTENT_TYPE, content_type(path))],
+            file.data.into_owned(),
+        )
+            .into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+fn content_type(path: &str) -> &'static str {
+    match path.rsplit('.').next() {
+        Some("html") => "text/html; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        Some("json") => "application/json",
+        _ => "application/octet-stream",
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SummaryParams {
+    /// Narrow the view to one project.
+    project: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Summary {
+    generated_ms: i64,
+    /// Project the bearer token is scoped to, if one was sent.
+    scope: Option<String>,
+    projects: Vec<ProjectSummary>,
+    running: Vec<RunningTask>,
+    runs: Vec<RunRecord>,
+    logs: Vec<StoredLogEntry>,
+}
+
+#[derive(Debug, Serialize)]
+struct ProjectSummary {
+    name: String,
+    root: Option<String>,
+    running: usize,
+    last_run: Option<RunRecord>,
+    last_deploy: Option<RunRecord>,
+}
+
+#[derive(Debug, Serialize)]
+struct RunningTask {
+    project: Option<String>,
+    task: String,
+    command: String,
+    pid: u32,
+    started_ms: i64,
+}
+
+async fn summary(headers: HeaderMap, Query(params): Query<SummaryParams>) -> Response {
+    let bearer = log_server::bearer_token(&headers);
+    let result =
+        tokio::task::spawn_blocking(move || build_summary(bearer.as_deref(), params)).await;
+
+    match result {
+        Ok(Ok(Some(summary))) => (StatusCode::OK, Json(summary)).into_response(),
+        Ok(Ok(None)) => (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error": "invalid or revoked token" })),
+        )
+            .into_response(),
+        Ok(Err(err)) => {
+            tracing::error!(?err, "dashboard summary failed");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": err.to_string() })),
+            )
+                .into_response()
+        }
+        Err(err) => {
+            tracing::error!(?err, "dashboard summary task panicked");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "internal error" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Collect the dashboard data. `None` means the token was rejected.
+fn build_summary(bearer: Option<&str>, params: SummaryParams) -> Result<Option<Summary>> {
+    let log_conn = log_store::open_log_db()?;
+    log_tokens::init_schema(&log_conn)?;
+    let Some(scope) = log_tokens::scope(&log_conn, bearer)? else {
+        return Ok(None);
+    };
+    let selected = params.project.filter(|project| !project.is_empty());
+    let visible = |project: Option<&str>| {
+        scope.allows(project)
+            && selected
+                .as_deref()
+                .is_none_or(|selected| project == Some(selected))
+    };
+
+    let mut projects: BTreeMap<String, ProjectSummary> = BTreeMap::new();
+    for project in crate::projects::list_projects().unwrap_or_default() {
+        if visible(Some(&project.name)) {
+            summary_for(&mut projects, &project.name).root =
+                Some(project.project_root.display().to_string());
+        }
+    }
+
+    let mut running_tasks = Vec::new();
+    for process in running::load_running_processes()?
+        .projects
+        .into_values()
+        .flatten()
+    {
+        if !visible(process.project_name.as_deref()) || !running::process_alive(process.pid) {
+            continue;
+        }
+        if let Some(name) = process.project_name.as_deref() {
+            summary_for(&mut projects, name).running += 1;
+        }
+        running_tasks.push(RunningTask {
+            project: process.project_name,
+            task: process.task_name,
+            command: process.command,
+            pid: process.pid,
+            started_ms: process.started_at as i64,
+        });
+    }
+    running_tasks.sort_by_key(|task| std::cmp::Reverse(task.started_ms));
+
+    let runs_conn = runs::open_runs_db()?;
+    let project_filter = match &scope {
+        Scope::Token(project) => Some(project.clone()),
+        Scope::Open { .. } => selected.clone(),
+    };
+    let runs: Vec<RunRecord> = runs::query_runs(
+        &runs_conn,
+        &RunQuery {
+            project: project_filter.clone(),
+            limit: RECENT_RUNS * 4,
+            ..RunQuery::default()
+        },
+    )?
+    .into_iter()
+    .filter(|run| visible(run.project.as_deref()))
+    .collect();
+    let deploys = runs::query_runs(
+        &runs_conn,
+        &RunQuery {
+            kind: Some(RunKind::Deploy),
+            project: project_filter.clone(),
+            limit: RECENT_RUNS * 4,
+            ..RunQuery::default()
+        },
+    )?;
+    for run in &runs {
+        if let Some(name) = run.project.as_deref() {
+            let summary = summary_for(&mut projects, name);
+            if summary.last_run.is_none() {
+                summary.last_run = Some(run.clone());
+            }
+        }
+    }
+    for deploy in deploys {
+        let Some(name) = deploy.project.clone() else {
+            continue;
+        };
+        if !visible(Some(&name)) {
+            continue;
+        }
+        let summary = summary_for(&mut projects, &name);
+        if summary.last_deploy.is_none() {
+            summary.last_deploy = Some(deploy);
+        }
+    }
+
+    let logs = log_store::query_logs(
+        &log_conn,
+        &LogQuery {
+            project: project_filter,
+            limit: RECENT_LOGS * 4,
+            ..LogQuery::default()
+        },
+    )?
+    .into_iter()
+    .filter(|log| visible(Some(&log.entry.project)))
+    .take(RECENT_LOGS)
+    .collect();
+
+    Ok(Some(Summary {
+        generated_ms: running::now_ms() as i64,
+        scope: match scope {
+            Scope::Token(project) => Some(project),
+            Scope::Open { .. } => None,
+        },
+        projects: projects.into_values().collect(),
+        running: running_tasks,
+        runs: runs.into_iter().take(RECENT_RUNS).collect(),
+        logs,
+    }))
+}
+
+fn summary_for<'a>(
+    projects: &'a mut BTreeMap<String, ProjectSummary>,
+    name: &str,
+) -> &'a mut ProjectSummary {
+    projects
+        .entry(name.to_string())
+        .or_insert_with(|| ProjectSummary {
+            name: name.to_string(),
+            root: None,
+            running: 0,
+            last_run: None,
+            last_deploy: None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embeds_dashboard_assets() {
+        assert!(Assets::get("index.html").is_some());
+        assert!(Assets::get("app.js").is_some());
+        assert_eq!(content_type("app.js"), "text/javascript; charset=utf-8");
+    }
+}
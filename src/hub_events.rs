@@ -0,0 +1,372 @@
+//! Event bus served by the flow server at `/events`.
+//!
+//! Flow commands publish what they do (task, deploy, and commit runs starting and finishing,
+//! secret scan findings, supervised service health changes) with [`publish`], a short POST to
+//! the local flow server. The server numbers the events, keeps the latest ones in memory, and
+//! fans them out to `/events/stream` subscribers, so notifiers and dashboards can react to
+//! events instead of polling the runs and logs databases. Publishing never fails the caller;
+//! events are dropped when the server isn't running.
+
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use axum::{
+    Router,
+    extract::Query,
+    http::{HeaderMap, StatusCode},
+    response::{
+        IntoResponse, Json, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+    routing::get,
+};
+use futures::future;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+
+use crate::log_tokens::{self, Scope};
+use crate::{http_client, log_server, log_store, running};
+
+const EVENTS_URL: &str = "http://127.0.0.1:9050/events";
+const PUBLISH_TIMEOUT: Duration = Duration::from_millis(500);
+/// Events kept for `GET /events` and for replay to subscribers that pass `since`.
+const RECENT_CAPACITY: usize = 500;
+const DEFAULT_LIMIT: usize = 100;
+
+pub const SECRET_FOUND: &str = "secret.found";
+pub const SERVICE_HEALTH: &str = "service.health";
+
+/// One event on the bus.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HubEvent {
+    /// Assigned by the server and increasing for its lifetime; 0 until published.
+    #[serde(default)]
+    pub id: u64,
+    /// Dotted kind, e.g. `task.started`, `deploy.finished`, `secret.found`, `service.health`.
+    pub kind: String,
+    #[serde(default)]
+    pub ts_ms: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub data: Value,
+}
+
+impl HubEvent {
+    pub fn new(kind: impl Into<String>, project: Option<&str>, data: Value) -> Self {
+        Self {
+            id: 0,
+            kind: kind.into(),
+            ts_ms: running::now_ms() as i64,
+            project: project.map(|project| project.to_string()),
+            data,
+        }
+    }
+}
+
+/// Send `event` to the local flow server, waiting at most [`PUBLISH_TIMEOUT`].
+pub fn publish(event: HubEvent) {
+    if cfg!(test) {
+        return;
+    }
+    // Post from a plain thread so callers inside an async runtime can use the blocking client.
+    let _ = thread::spawn(move || {
+        let Ok(client) = http_client::blocking_with_timeout(PUBLISH_TIMEOUT) else {
+            return;
+        };
+        if let Err(err) = client.post(EVENTS_URL).json(&event).send() {
+            tracing::debug!(?err, kind = %event.kind, "hub event not delivered");
+        }
+    })
+    .join();
+}
+
+/// Server side of the bus. Modules running inside the flow server emit through [`bus`]
+/// directly.
+pub struct EventBus {
+    sender: broadcast::Sender<HubEvent>,
+    recent: Mutex<VecDeque<HubEvent>>,
+    next_id: AtomicU64,
+}
+
+pub fn bus() -> &'static EventBus {
+    static BUS: OnceLock<EventBus> = OnceLock::new();
+    BUS.get_or_init(EventBus::new)
+}
+
+impl EventBus {
+    fn new() -> Self {
+        let (sender, _) = broadcast::channel(256);
+        Self {
+            sender,
+            recent: Mutex::new(VecDeque::with_capacity(RECENT_CAPACITY)),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Number the event, remember it, and deliver it to current subscribers.
+    pub fn emit(&self, mut event: HubEvent) -> u64 {
+        let mut recent = self.lock_recent();
+        event.id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        if event.ts_ms == 0 {
+            event.ts_ms = running::now_ms() as i64;
+        }
+        let id = event.id;
+        if recent.len() == RECENT_CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back(event.clone());
+        // Sent under the lock so `subscribe` never sees an event both replayed and live.
+        let _ = self.sender.send(event);
+        id
+    }
+
+    /// The newest `limit` remembered events matching `filter`, oldest first.
+    fn recent(&self, filter: &EventFilter) -> Vec<HubEvent> {
+        let recent = self.lock_recent();
+        let mut events: Vec<HubEvent> = recent
+            .iter()
+            .rev()
+            .filter(|event| filter.matches(event))
+            .take(filter.limit.unwrap_or(DEFAULT_LIMIT))
+            .cloned()
+            .collect();
+        events.reverse();
+        events
+    }
+
+    /// Remembered events after `filter.since` plus a receiver for everything emitted later.
+    fn subscribe(&self, filter: &EventFilter) -> (Vec<HubEvent>, broadcast::Receiver<HubEvent>) {
+        let recent = self.lock_recent();
+        let backlog = match filter.since {
+            Some(_) => recent
+                .iter()
+                .filter(|event| filter.matches(event))
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        };
+        (backlog, self.sender.subscribe())
+    }
+
+    fn lock_recent(&self) -> MutexGuard<'_, VecDeque<HubEvent>> {
+        self.recent
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct EventFilter {
+    /// Comma-separated kinds; a trailing `*` matches a prefix (`task.*`, `*`).
+    kinds: Option<String>,
+    project: Option<String>,
+    /// Only events with a larger id.
+    since: Option<u64>,
+    limit: Option<usize>,
+}
+
+impl EventFilter {
+    fn matches(&self, event: &HubEvent) -> bool {
+        if self.since.is_some_and(|since| event.id <= since) {
+            return false;
+        }
+        if let Some(project) = &self.project
+            && event.project.as_deref() != Some(project.as_str())
+        {
+            return false;
+        }
+        self.kinds.as_deref().is_none_or(|kinds| {
+            kinds
+                .split(',')
+                .map(str::trim)
+                .filter(|pattern| !pattern.is_empty())
+                .any(|pattern| kind_matches(pattern, &event.kind))
+        })
+    }
+}
+
+fn kind_matches(pattern: &str, kind: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => kind.starts_with(prefix),
+        None => pattern == kind,
+    }
+}
+
+/// Event routes, merged into the flow server's router after its CORS layer: events carry repo
+/// paths and run details, so browsers on other origins must not read or post them. Requests are
+/// authorized like `/logs/ingest`: a bearer token scopes them to the token's project, and without
+/// one, projects that have an active token are left out.
+pub fn router<S: Clone + Send + Sync + 'static>() -> Router<S> {
+    Router::new()
+        .route("/events", get(recent_events).post(publish_event))
+        .route("/events/stream", get(event_stream))
+}
+
+/// Token scope of the request, or the 401 to send back.
+async fn request_scope(headers: &HeaderMap) -> Result<Scope, Response> {
+    let bearer = log_server::bearer_token(headers);
+    let result = tokio::task::spawn_blocking(move || {
+        let conn = log_store::open_log_db()?;
+        log_tokens::init_schema(&conn)?;
+        log_tokens::scope(&conn, bearer.as_deref())
+    })
+    .await;
+    match result {
+        Ok(Ok(Some(scope))) => Ok(scope),
+        Ok(Ok(None)) => Err((
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error": "invalid or revoked token" })),
+        )
+            .into_response()),
+        Ok(Err(err)) => {
+            tracing::error!(?err, "event auth failed");
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": err.to_string() })),
+            )
+                .into_response())
+        }
+        Err(err) => {
+            tracing::error!(?err, "event auth task panicked");
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "internal error" })),
+            )
+                .into_response())
+        }
+    }
+}
+
+/// GET /events - remembered events, filtered by `kinds`, `project`, `since`, and `limit`.
+async fn recent_events(headers: HeaderMap, Query(filter): Query<EventFilter>) -> Response {
+    let scope = match request_scope(&headers).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    let events: Vec<HubEvent> = bus()
+        .recent(&filter)
+        .into_iter()
+        .filter(|event| scope.allows(event.project.as_deref()))
+        .collect();
+    Json(json!({ "events": events })).into_response()
+}
+
+/// POST /events - publish an event; `id` is assigned and `ts_ms` defaults to now. With a token
+/// the event belongs to the token's project; without one it may not name a guarded project.
+async fn publish_event(headers: HeaderMap, Json(mut event): Json<HubEvent>) -> Response {
+    let scope = match request_scope(&headers).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    if let Scope::Token(project) = &scope {
+        event.project = Some(project.clone());
+    } else if !scope.allows(event.project.as_deref()) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error": "project requires a token" })),
+        )
+            .into_response();
+    }
+    if event.kind.trim().is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "kind is required" })),
+        )
+            .into_response();
+    }
+    let id = bus().emit(event);
+    (StatusCode::OK, Json(json!({ "id": id }))).into_response()
+}
+
+/// GET /events/stream - SSE of new events. `since` (or a reconnecting client's
+/// `Last-Event-ID`) first replays remembered events after that id.
+async fn event_stream(headers: HeaderMap, Query(mut filter): Query<EventFilter>) -> Response {
+    let scope = match request_scope(&headers).await {
+        Ok(scope) => scope,
+        Err(response) => return response,
+    };
+    if filter.since.is_none() {
+        filter.since = headers
+            .get("last-event-id")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok());
+    }
+    let (mut backlog, receiver) = bus().subscribe(&filter);
+    backlog.retain(|event| scope.allows(event.project.as_deref()));
+    let live = BroadcastStream::new(receiver).filter_map(move |result| {
+        future::ready(match result {
+            Ok(event) => (filter.matches(&event) && scope.allows(event.project.as_deref()))
+                .then(|| Ok::<_, Infallible>(sse_event(&event))),
+            Err(err) => {
+                tracing::warn!(?err, "event subscriber lagged; events dropped");
+                None
+            }
+        })
+    });
+    let stream = stream::iter(backlog.into_iter().map(|event| Ok(sse_event(&event)))).chain(live);
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+fn sse_event(event: &HubEvent) -> Event {
+    Event::default()
+        .id(event.id.to_string())
+        .data(serde_json::to_string(event).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(kinds: &str, since: Option<u64>) -> EventFilter {
+        EventFilter {
+            kinds: Some(kinds.to_string()),
+            since,
+            ..EventFilter::default()
+        }
+    }
+
+    #[test]
+    fn filters_by_kind_pattern_project_and_since() {
+        let mut event = HubEvent::new("deploy.finished", Some("api"), json!({}));
+        event.id = 7;
+        assert!(filter("task.*, deploy.*", None).matches(&event));
+        assert!(filter("*", Some(6)).matches(&event));
+        assert!(!filter("*", Some(7)).matches(&event));
+        assert!(!filter("deploy.started", None).matches(&event));
+        let other_project = EventFilter {
+            project: Some("web".to_string()),
+            ..EventFilter::default()
+        };
+        assert!(!other_project.matches(&event));
+    }
+
+    #[test]
+    fn emit_numbers_events_and_replays_after_since() {
+        let bus = EventBus::new();
+        for kind in ["task.started", "secret.found", "task.finished"] {
+            bus.emit(HubEvent::new(kind, None, Value::Null));
+        }
+        let kinds = |events: Vec<HubEvent>| -> Vec<(u64, String)> {
+            events.into_iter().map(|e| (e.id, e.kind)).collect()
+        };
+        assert_eq!(
+            kinds(bus.recent(&filter("task.*", None))),
+            vec![(1, "task.started".into()), (3, "task.finished".into())]
+        );
+
+        let (backlog, mut receiver) = bus.subscribe(&filter("*", Some(2)));
+        assert_eq!(kinds(backlog), vec![(3, "task.finished".into())]);
+        bus.emit(HubEvent::new(SERVICE_HEALTH, None, Value::Null));
+        assert_eq!(receiver.try_recv().unwrap().id, 4);
+    }
+}
@@ -0,0 +1,743 @@
+//! Client-side admission queue for tasks delegated to the hub.
+//!
+//! Delegated runs take a slot in flow.db before they are submitted. When the global or
+//! per-project limit (`[hub]` in the global flow.toml) is reached, `f run` waits in a FIFO
+//! queue ordered by `--priority`. The submitted command releases its slot on exit.
+//!
+//! Runs also reserve the `cpu` and `memory` their task declares. The head of the queue waits
+//! while those reservations, the system load average, or the memory the OS reports as
+//! available leave too little room, so two heavy builds don't start together.
+
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use rusqlite::{Connection, params};
+use serde::Serialize;
+
+use crate::cli::HubQueueOpts;
+use crate::{config, db, running, task_process};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Slots whose release never arrived (hub crash, killed shell) expire after this long.
+const RUNNING_LEASE_MS: i64 = 6 * 60 * 60 * 1000;
+const DEFAULT_MAX_CONCURRENT: usize = 2;
+const DEFAULT_MAX_PER_PROJECT: usize = 1;
+const DEFAULT_TASK_CPU: u32 = 1;
+
+/// A waiting or running delegated run.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueEntry {
+    pub id: i64,
+    pub project: String,
+    pub task: String,
+    pub priority: i32,
+    pub state: String,
+    pub pid: u32,
+    pub enqueued_ms: i64,
+    pub started_ms: Option<i64>,
+    pub cpu: u32,
+    pub memory_bytes: u64,
+}
+
+impl QueueEntry {
+    fn is_running(&self) -> bool {
+        self.state == "running"
+    }
+}
+
+/// Resources a delegated run reserves while it runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Weight {
+    pub cpu: u32,
+    pub memory_bytes: u64,
+}
+
+impl Weight {
+    /// Read `cpu` and `memory` from a task definition.
+    pub fn for_task(task: &config::TaskConfig) -> Result<Self> {
+        let memory_bytes = match task.memory.as_deref() {
+            Some(raw) => task_process::parse_memory(raw)
+                .with_context(|| format!("task '{}': invalid memory", task.name))?,
+            None => 0,
+        };
+        Ok(Self {
+            cpu: task.cpu.unwrap_or(DEFAULT_TASK_CPU).max(1),
+            memory_bytes,
+        })
+    }
+}
+
+/// Concurrency limits and resource budgets for delegated runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub global: usize,
+    pub per_project: usize,
+    pub cpu: u32,
+    pub memory_bytes: u64,
+}
+
+impl Limits {
+    /// Read `[hub]` from the global flow.toml.
+    pub fn load() -> Self {
+        let hub = config::load(config::default_config_path())
+            .ok()
+            .and_then(|cfg| cfg.hub)
+            .unwrap_or_default();
+        let memory_bytes = hub
+            .memory
+            .as_deref()
+            .and_then(|raw| match task_process::parse_memory(raw) {
+                Ok(bytes) => Some(bytes),
+                Err(err) => {
+                    tracing::warn!(?err, "ignoring invalid [hub] memory");
+                    None
+                }
+            })
+            .or_else(total_memory)
+            .unwrap_or(u64::MAX);
+        Self {
+            global: hub.max_concurrent.unwrap_or(DEFAULT_MAX_CONCURRENT).max(1),
+            per_project: hub
+                .max_per_project
+                .unwrap_or(DEFAULT_MAX_PER_PROJECT)
+                .max(1),
+            cpu: hub
+                .cpu
+                .unwrap_or_else(|| {
+                    thread::available_parallelism()
+                        .map(|n| n.get() as u32)
+                        .unwrap_or(DEFAULT_TASK_CPU)
+                })
+                .max(1),
+            memory_bytes,
+        }
+    }
+}
+
+/// Machine load sampled when making a scheduling decision.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SystemLoad {
+    /// One-minute load average.
+    pub load_avg: Option<f64>,
+    /// Memory the OS reports as available (Linux `MemAvailable`).
+    pub available_memory: Option<u64>,
+}
+
+impl SystemLoad {
+    pub fn sample() -> Self {
+        Self {
+            load_avg: load_average(),
+            available_memory: meminfo_field("MemAvailable"),
+        }
+    }
+}
+
+/// Why a queue entry is or isn't starting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decision {
+    Running,
+    Next,
+    SlotsFull,
+    ProjectFull,
+    NeedsCpu { need: u32, free: u32 },
+    NeedsMemory { need: u64, free: u64 },
+    Behind(i64),
+}
+
+impl Decision {
+    fn describe(self) -> String {
+        match self {
+            Decision::Running => "running".to_string(),
+            Decision::Next => "starts next".to_string(),
+            Decision::SlotsFull => "all slots busy".to_string(),
+            Decision::ProjectFull => "project at its limit".to_string(),
+            Decision::NeedsCpu { need, free } => format!("needs {need} cpu, {free} free"),
+            Decision::NeedsMemory { need, free } => format!(
+                "needs {}, {} free",
+                format_memory(need),
+                format_memory(free)
+            ),
+            Decision::Behind(id) => format!("behind #{id}"),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct QueueRow<'a> {
+    #[serde(flatten)]
+    entry: &'a QueueEntry,
+    decision: String,
+}
+
+pub fn run(opts: HubQueueOpts) -> Result<()> {
+    let conn = open_queue_db()?;
+    if let Some(id) = opts.release {
+        return release_with(&conn, id);
+    }
+    if opts.clear {
+        let removed = conn
+            .execute("DELETE FROM hub_queue", [])
+            .context("failed to clear hub queue")?;
+        println!("Cleared {removed} hub queue entr{}.", plural_y(removed));
+        return Ok(());
+    }
+
+    prune_stale(&conn)?;
+    let entries = load_entries(&conn)?;
+    let limits = Limits::load();
+    let load = SystemLoad::sample();
+    let decisions = schedule(&entries, limits, load);
+    if opts.json {
+        let rows: Vec<QueueRow> = decisions
+            .iter()
+            .filter_map(|(id, decision)| {
+                let entry = entries.iter().find(|e| e.id == *id)?;
+                Some(QueueRow {
+                    entry,
+                    decision: decision.describe(),
+                })
+            })
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&rows).context("failed to encode hub queue JSON")?
+        );
+        return Ok(());
+    }
+    print_entries(&entries, &decisions, limits, load);
+    Ok(())
+}
+
+/// Wait for a hub slot and mark it running. Returns the slot id to release after the run.
+pub fn acquire(project: &str, task: &str, priority: i32, weight: Weight) -> Result<i64> {
+    let conn = open_queue_db()?;
+    let limits = Limits::load();
+    conn.execute(
+        r#"
+        INSERT INTO hub_queue (project, task, priority, state, pid, enqueued_ms, cpu, memory_bytes)
+        VALUES (?1, ?2, ?3, 'waiting', ?4, ?5, ?6, ?7)
+        "#,
+        params![
+            project,
+            task,
+            priority,
+            std::process::id(),
+            running::now_ms() as i64,
+            weight.cpu,
+            weight.memory_bytes as i64
+        ],
+    )
+    .context("failed to enqueue hub run")?;
+    let id = conn.last_insert_rowid();
+
+    let mut last_status = None;
+    loop {
+        prune_stale(&conn)?;
+        let entries = load_entries(&conn)?;
+        let decisions = schedule(&entries, limits, SystemLoad::sample());
+        let decision = decisions
+            .iter()
+            .find(|(entry_id, _)| *entry_id == id)
+            .map(|(_, decision)| *decision);
+        if decision == Some(Decision::Next) {
+            conn.execute(
+                "UPDATE hub_queue SET state = 'running', started_ms = ?2 WHERE id = ?1",
+                params![id, running::now_ms() as i64],
+            )
+            .context("failed to start hub run")?;
+            return Ok(id);
+        }
+        let position = queue_position(&entries, id);
+        // Report by kind so fluctuating free memory doesn't reprint every poll.
+        let status = (position, decision.as_ref().map(std::mem::discriminant));
+        if last_status != Some(status) {
+            let in_flight = entries.iter().filter(|e| e.is_running()).count();
+            let reason = decision
+                .map(|decision| format!(", {}", decision.describe()))
+                .unwrap_or_default();
+            println!(
+                "Queued '{task}' for hub (position {}, {in_flight}/{} running{reason}). Inspect with `f hub queue`.",
+                position.unwrap_or(0),
+                limits.global
+            );
+            last_status = Some(status);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Free a slot taken by `acquire`.
+pub fn release(id: i64) -> Result<()> {
+    let conn = open_queue_db()?;
+    release_with(&conn, id)
+}
+
+fn release_with(conn: &Connection, id: i64) -> Result<()> {
+    conn.execute("DELETE FROM hub_queue WHERE id = ?1", params![id])
+        .context("failed to release hub queue slot")?;
+    Ok(())
+}
+
+/// Wrap a command so the hub releases its slot and dispatches `[notify]` rules when the
+/// command exits.
+pub fn wrap_command(command: &str, id: i64, task: &str, config_path: &Path, cwd: &Path) -> String {
+    let flow_bin = std::env::current_exe()
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| "f".to_string());
+    let flow_bin = shell_words::quote(&flow_bin);
+    let on_exit = format!(
+        "status=$?; {flow_bin} hub queue --release {id} >/dev/null 2>&1; \
+         {flow_bin} hub notify --task {} --status \"$status\" --config {} --cwd {} >/dev/null 2>&1",
+        shell_words::quote(task),
+        shell_words::quote(&config_path.to_string_lossy()),
+        shell_words::quote(&cwd.to_string_lossy()),
+    );
+    format!("trap {} EXIT\n{command}", shell_words::quote(&on_exit))
+}
+
+/// Decide what every entry is waiting on. Waiters are considered highest priority first,
+/// FIFO within a priority, and at most one starts per round. Entries for a project that is
+/// at its limit are skipped so other projects proceed, but a run that doesn't fit the free
+/// cpu or memory holds back everything behind it so heavy runs aren't starved by light ones.
+/// With nothing running the head always starts, even if it exceeds the budget.
+fn schedule(entries: &[QueueEntry], limits: Limits, load: SystemLoad) -> Vec<(i64, Decision)> {
+    let running: Vec<&QueueEntry> = entries.iter().filter(|e| e.is_running()).collect();
+    let reserved_cpu: u32 = running.iter().map(|e| e.cpu).sum();
+    let reserved_memory: u64 = running.iter().map(|e| e.memory_bytes).sum();
+    // The load average lags behind runs that just started, so count whichever is higher.
+    let busy_cpu = load
+        .load_avg
+        .map(|avg| avg.round() as u32)
+        .unwrap_or(0)
+        .max(reserved_cpu);
+    let free_cpu = limits.cpu.saturating_sub(busy_cpu);
+    let mut free_memory = limits.memory_bytes.saturating_sub(reserved_memory);
+    if let Some(available) = load.available_memory {
+        free_memory = free_memory.min(available);
+    }
+
+    let mut decisions: Vec<(i64, Decision)> =
+        running.iter().map(|e| (e.id, Decision::Running)).collect();
+    let mut ahead: Option<i64> = None;
+    for entry in waiting_in_order(entries) {
+        let project_running = running
+            .iter()
+            .filter(|r| r.project == entry.project)
+            .count();
+        let decision = if running.len() >= limits.global {
+            Decision::SlotsFull
+        } else if project_running >= limits.per_project {
+            Decision::ProjectFull
+        } else if let Some(id) = ahead {
+            Decision::Behind(id)
+        } else if !running.is_empty() && entry.cpu > free_cpu {
+            Decision::NeedsCpu {
+                need: entry.cpu,
+                free: free_cpu,
+            }
+        } else if !running.is_empty() && entry.memory_bytes > free_memory {
+            Decision::NeedsMemory {
+                need: entry.memory_bytes,
+                free: free_memory,
+            }
+        } else {
+            Decision::Next
+        };
+        if matches!(
+            decision,
+            Decision::Next | Decision::NeedsCpu { .. } | Decision::NeedsMemory { .. }
+        ) {
+            ahead = Some(entry.id);
+        }
+        decisions.push((entry.id, decision));
+    }
+    decisions
+}
+
+fn waiting_in_order(entries: &[QueueEntry]) -> Vec<&QueueEntry> {
+    let mut waiting: Vec<&QueueEntry> = entries.iter().filter(|e| !e.is_running()).collect();
+    waiting.sort_by(|a, b| b.priority.cmp(&a.priority).then(a.id.cmp(&b.id)));
+    waiting
+}
+
+fn queue_position(entries: &[QueueEntry], id: i64) -> Option<usize> {
+    waiting_in_order(entries)
+        .iter()
+        .position(|entry| entry.id == id)
+        .map(|idx| idx + 1)
+}
+
+/// Drop waiters whose `f run` process is gone and running slots past their lease.
+fn prune_stale(conn: &Connection) -> Result<()> {
+    let now = running::now_ms() as i64;
+    for entry in load_entries(conn)? {
+        let stale = if entry.is_running() {
+            entry
+                .started_ms
+                .is_some_and(|started| now - started > RUNNING_LEASE_MS)
+        } else {
+            !running::process_alive(entry.pid)
+        };
+        if stale {
+            release_with(conn, entry.id)?;
+        }
+    }
+    Ok(())
+}
+
+fn load_entries(conn: &Connection) -> Result<Vec<QueueEntry>> {
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT id, project, task, priority, state, pid, enqueued_ms, started_ms, cpu,
+                   memory_bytes
+            FROM hub_queue
+            ORDER BY id
+            "#,
+        )
+        .context("failed to query hub queue")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(QueueEntry {
+                id: row.get(0)?,
+                project: row.get(1)?,
+                task: row.get(2)?,
+                priority: row.get(3)?,
+                state: row.get(4)?,
+                pid: row.get(5)?,
+                enqueued_ms: row.get(6)?,
+                started_ms: row.get(7)?,
+                cpu: row.get(8)?,
+                memory_bytes: row.get::<_, i64>(9)?.max(0) as u64,
+            })
+        })
+        .context("failed to read hub queue")?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to read hub queue")
+}
+
+fn print_entries(
+    entries: &[QueueEntry],
+    decisions: &[(i64, Decision)],
+    limits: Limits,
+    load: SystemLoad,
+) {
+    let running: Vec<&QueueEntry> = entries.iter().filter(|e| e.is_running()).collect();
+    println!(
+        "Hub slots: {}/{} running (max {} per project)",
+        running.len(),
+        limits.global,
+        limits.per_project
+    );
+    let memory_budget = if limits.memory_bytes == u64::MAX {
+        "unlimited".to_string()
+    } else {
+        format_memory(limits.memory_bytes)
+    };
+    println!(
+        "Reserved: {}/{} cpu, {}/{} memory",
+        running.iter().map(|e| e.cpu).sum::<u32>(),
+        limits.cpu,
+        format_memory(running.iter().map(|e| e.memory_bytes).sum()),
+        memory_budget
+    );
+    if load.load_avg.is_some() || load.available_memory.is_some() {
+        let avg = load
+            .load_avg
+            .map(|avg| format!("{avg:.2}"))
+            .unwrap_or_else(|| "-".to_string());
+        let available = load
+            .available_memory
+            .map(format_memory)
+            .unwrap_or_else(|| "-".to_string());
+        println!("System: load {avg}, {available} available");
+    }
+    if entries.is_empty() {
+        println!("No delegated runs queued.");
+        return;
+    }
+    println!(
+        "{:<6} {:<8} {:>4} {:<20} {:<24} {:>4} {:>8} {:<6} DECISION",
+        "ID", "STATE", "PRIO", "PROJECT", "TASK", "CPU", "MEM", "SINCE"
+    );
+    let now = running::now_ms() as i64;
+    for (id, decision) in decisions {
+        let Some(entry) = entries.iter().find(|e| e.id == *id) else {
+            continue;
+        };
+        let since = entry.started_ms.unwrap_or(entry.enqueued_ms);
+        let memory = if entry.memory_bytes == 0 {
+            "-".to_string()
+        } else {
+            format_memory(entry.memory_bytes)
+        };
+        println!(
+            "{:<6} {:<8} {:>4} {:<20} {:<24} {:>4} {:>8} {:<6} {}",
+            entry.id,
+            entry.state,
+            entry.priority,
+            entry.project,
+            entry.task,
+            entry.cpu,
+            memory,
+            format_age(now.saturating_sub(since)),
+            decision.describe()
+        );
+    }
+}
+
+fn format_age(elapsed_ms: i64) -> String {
+    let secs = elapsed_ms / 1000;
+    if secs < 60 {
+        format!("{secs}s")
+    } else if secs < 3600 {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}h", secs / 3600)
+    }
+}
+
+fn format_memory(bytes: u64) -> String {
+    const GB: f64 = (1u64 << 30) as f64;
+    const MB: f64 = (1u64 << 20) as f64;
+    if bytes as f64 >= GB {
+        format!("{:.1}GB", bytes as f64 / GB)
+    } else {
+        format!("{:.0}MB", bytes as f64 / MB)
+    }
+}
+
+fn load_average() -> Option<f64> {
+    if cfg!(target_os = "macos") {
+        // `{ 1.23 1.45 1.67 }`
+        let output = std::process::Command::new("sysctl")
+            .args(["-n", "vm.loadavg"])
+            .output()
+            .ok()?;
+        return String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .find_map(|field| field.parse().ok());
+    }
+    std::fs::read_to_string("/proc/loadavg")
+        .ok()?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+fn total_memory() -> Option<u64> {
+    if cfg!(target_os = "macos") {
+        let output = std::process::Command::new("sysctl")
+            .args(["-n", "hw.memsize"])
+            .output()
+            .ok()?;
+        return String::from_utf8_lossy(&output.stdout).trim().parse().ok();
+    }
+    meminfo_field("MemTotal")
+}
+
+/// Read a `/proc/meminfo` field (reported in kB) as bytes.
+fn meminfo_field(name: &str) -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    parse_meminfo_field(&meminfo, name)
+}
+
+fn parse_meminfo_field(meminfo: &str, name: &str) -> Option<u64> {
+    meminfo.lines().find_map(|line| {
+        let rest = line.strip_prefix(name)?.strip_prefix(':')?;
+        let kb: u64 = rest.split_whitespace().next()?.parse().ok()?;
+        Some(kb * 1024)
+    })
+}
+
+fn plural_y(count: usize) -> &'static str {
+    if count == 1 { "y" } else { "ies" }
+}
+
+fn open_queue_db() -> Result<Connection> {
+    let conn = db::open_db()?;
+    init_schema(&conn)?;
+    Ok(conn)
+}
+
+/// Initialize the hub queue schema.
+pub fn init_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS hub_queue (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project TEXT NOT NULL,
+            task TEXT NOT NULL,
+            priority INTEGER NOT NULL DEFAULT 0,
+            state TEXT NOT NULL,
+            pid INTEGER NOT NULL,
+            enqueued_ms INTEGER NOT NULL,
+            started_ms INTEGER,
+            cpu INTEGER NOT NULL DEFAULT 1,
+            memory_bytes INTEGER NOT NULL DEFAULT 0
+        );
+        "#,
+    )
+    .context("failed to create hub queue schema")?;
+
+    let mut stmt = conn.prepare("PRAGMA table_info(hub_queue)")?;
+    let columns: Vec<String> = stmt
+        .query_map([], |row| row.get(1))?
+        .collect::<rusqlite::Result<_>>()?;
+    if !columns.iter().any(|c| c == "cpu") {
+        conn.execute(
+            "ALTER TABLE hub_queue ADD COLUMN cpu INTEGER NOT NULL DEFAULT 1",
+            [],
+        )
+        .context("failed to add cpu column")?;
+    }
+    if !columns.iter().any(|c| c == "memory_bytes") {
+        conn.execute(
+            "ALTER TABLE hub_queue ADD COLUMN memory_bytes INTEGER NOT NULL DEFAULT 0",
+            [],
+        )
+        .context("failed to add memory_bytes column")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: i64, project: &str, priority: i32, state: &str) -> QueueEntry {
+        QueueEntry {
+            id,
+            project: project.to_string(),
+            task: "build".to_string(),
+            priority,
+            state: state.to_string(),
+            pid: 1,
+            enqueued_ms: id,
+            started_ms: (state == "running").then_some(id),
+            cpu: 1,
+            memory_bytes: 0,
+        }
+    }
+
+    fn limits(global: usize, per_project: usize) -> Limits {
+        Limits {
+            global,
+            per_project,
+            cpu: 8,
+            memory_bytes: 8 << 30,
+        }
+    }
+
+    fn next_admitted(entries: &[QueueEntry], limits: Limits, load: SystemLoad) -> Option<i64> {
+        schedule(entries, limits, load)
+            .into_iter()
+            .find(|(_, decision)| *decision == Decision::Next)
+            .map(|(id, _)| id)
+    }
+
+    #[test]
+    fn admits_by_priority_then_fifo() {
+        let limits = limits(2, 2);
+        let entries = vec![
+            entry(1, "a", 0, "waiting"),
+            entry(2, "b", 5, "waiting"),
+            entry(3, "c", 5, "waiting"),
+        ];
+        assert_eq!(
+            next_admitted(&entries, limits, SystemLoad::default()),
+            Some(2)
+        );
+        assert_eq!(queue_position(&entries, 1), Some(3));
+    }
+
+    #[test]
+    fn respects_global_and_project_limits() {
+        let limits = limits(2, 1);
+        let entries = vec![
+            entry(1, "a", 0, "running"),
+            entry(2, "a", 9, "waiting"),
+            entry(3, "b", 0, "waiting"),
+        ];
+        // Project "a" is full, so the lower-priority run for "b" goes first.
+        assert_eq!(
+            next_admitted(&entries, limits, SystemLoad::default()),
+            Some(3)
+        );
+
+        let full = vec![
+            entry(1, "a", 0, "running"),
+            entry(2, "b", 0, "running"),
+            entry(3, "c", 0, "waiting"),
+        ];
+        assert_eq!(next_admitted(&full, limits, SystemLoad::default()), None);
+    }
+
+    #[test]
+    fn heavy_runs_wait_for_memory_and_load() {
+        let limits = limits(3, 3);
+        let mut cargo = entry(1, "api", 0, "running");
+        cargo.memory_bytes = 6 << 30;
+        let mut webpack = entry(2, "web", 5, "waiting");
+        webpack.memory_bytes = 4 << 30;
+        let small = entry(3, "docs", 0, "waiting");
+        let entries = vec![cargo, webpack, small];
+
+        // The heavy run holds its place instead of being overtaken by the small one.
+        let decisions = schedule(&entries, limits, SystemLoad::default());
+        assert_eq!(
+            decisions[1],
+            (
+                2,
+                Decision::NeedsMemory {
+                    need: 4 << 30,
+                    free: 2 << 30
+                }
+            )
+        );
+        assert_eq!(decisions[2], (3, Decision::Behind(2)));
+
+        // Outside load counts too, and the head always starts once nothing is running.
+        let busy = SystemLoad {
+            load_avg: Some(7.6),
+            available_memory: None,
+        };
+        let mut light = entries[1].clone();
+        light.memory_bytes = 0;
+        light.cpu = 2;
+        assert_eq!(
+            schedule(&[entries[0].clone(), light.clone()], limits, busy)[1].1,
+            Decision::NeedsCpu { need: 2, free: 0 }
+        );
+        assert_eq!(next_admitted(&[light], limits, busy), Some(2));
+    }
+
+    #[test]
+    fn reads_meminfo_fields_in_bytes() {
+        let meminfo = "MemTotal:       16318496 kB\nMemAvailable:    2048 kB\n";
+        assert_eq!(
+            parse_meminfo_field(meminfo, "MemAvailable"),
+            Some(2048 * 1024)
+        );
+        assert_eq!(parse_meminfo_field(meminfo, "SwapTotal"), None);
+    }
+
+    #[test]
+    fn wrapped_command_releases_slot_and_notifies_on_exit() {
+        let wrapped = wrap_command(
+            "cargo build",
+            42,
+            "build",
+            Path::new("/src/app/flow.toml"),
+            Path::new("/src/app"),
+        );
+        assert!(wrapped.starts_with("trap '"));
+        assert!(wrapped.contains("hub queue --release 42"));
+        assert!(wrapped.contains("hub notify --task build --status"));
+        assert!(wrapped.contains("--config /src/app/flow.toml --cwd /src/app"));
+        assert!(wrapped.ends_with("EXIT\ncargo build"));
+    }
+}
@@ -0,0 +1,544 @@
+//! Login service for the hub daemon.
+//!
+//! `f hub install` writes a launchd agent (macOS) or a systemd user unit (Linux) that runs
+//! `f hub run`. That wrapper starts the lin daemon in the foreground, so the service
+//! manager restarts it when it dies, and writes its stdout/stderr to the usual daemon log
+//! files, rotating them by size.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+
+use crate::cli::{HubInstallOpts, HubRunOpts};
+use crate::{config, daemon, deploy_watch, hub, project_usage};
+
+const HUB_DAEMON: &str = "lin";
+const LAUNCHD_LABEL: &str = "io.linsa.flow-hub";
+const SYSTEMD_UNIT: &str = "flow-hub.service";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ServiceManager {
+    Launchd,
+    Systemd,
+}
+
+impl ServiceManager {
+    fn current() -> Result<Self> {
+        if cfg!(target_os = "macos") {
+            Ok(Self::Launchd)
+        } else if cfg!(target_os = "linux") {
+            Ok(Self::Systemd)
+        } else {
+            bail!("hub login service is only supported on macOS (launchd) and Linux (systemd)")
+        }
+    }
+
+    fn unit_path(self) -> PathBuf {
+        match self {
+            Self::Launchd => {
+                config::expand_path(&format!("~/Library/LaunchAgents/{LAUNCHD_LABEL}.plist"))
+            }
+            Self::Systemd => {
+                let base = std::env::var_os("XDG_CONFIG_HOME")
+                    .map(PathBuf::from)
+                    .filter(|path| path.is_absolute())
+                    .unwrap_or_else(|| config::expand_path("~/.config"));
+                base.join("systemd/user").join(SYSTEMD_UNIT)
+            }
+        }
+    }
+}
+
+/// Whether `f hub install` has set up a login service.
+pub fn installed() -> bool {
+    ServiceManager::current().is_ok_and(|manager| manager.unit_path().exists())
+}
+
+pub fn install(opts: HubInstallOpts) -> Result<()> {
+    let manager = ServiceManager::current()?;
+    // Fail early if the hub daemon isn't configured; the service would just crash-loop.
+    daemon::find_daemon_config_with_path(HUB_DAEMON, None)?;
+
+    let args = service_program_args(&opts.logs)?;
+    let path_env = std::env::var("PATH").unwrap_or_default();
+    let log_dir = daemon::daemon_log_dir(HUB_DAEMON)?;
+    let contents = match manager {
+        ServiceManager::Launchd => render_plist(&args, &path_env, &log_dir.join("service.log")),
+        ServiceManager::Systemd => render_systemd_unit(&args, &path_env),
+    };
+    let unit_path = manager.unit_path();
+
+    if opts.dry_run {
+        println!("# {}", unit_path.display());
+        print!("{contents}");
+        return Ok(());
+    }
+
+    if let Some(parent) = unit_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    fs::write(&unit_path, contents)
+        .with_context(|| format!("failed to write {}", unit_path.display()))?;
+
+    // A hub started by hand would hold the port; hand it over to the service.
+    let _ = daemon::stop_daemon_with_path(HUB_DAEMON, None);
+
+    match manager {
+        ServiceManager::Launchd => {
+            let domain = launchd_domain();
+            let plist = unit_path.to_string_lossy();
+            let _ = Command::new("launchctl")
+                .args(["bootout", &domain, plist.as_ref()])
+                .output();
+            run_checked("launchctl", &["bootstrap", &domain, plist.as_ref()])?;
+            let _ = Command::new("launchctl")
+                .args(["enable", &launchd_target()])
+                .output();
+        }
+        ServiceManager::Systemd => {
+            run_checked("systemctl", &["--user", "daemon-reload"])?;
+            run_checked("systemctl", &["--user", "enable", "--now", SYSTEMD_UNIT])?;
+        }
+    }
+
+    println!("Installed hub service at {}", unit_path.display());
+    println!(
+        "Logs: {} (rotated at {} MB, keeping {})",
+        log_dir.display(),
+        opts.logs.max_log_mb,
+        opts.logs.keep_logs
+    );
+    if manager == ServiceManager::Systemd {
+        println!("To keep it running while logged out: loginctl enable-linger $USER");
+    }
+    Ok(())
+}
+
+pub fn uninstall() -> Result<()> {
+    let manager = ServiceManager::current()?;
+    let unit_path = manager.unit_path();
+    if !unit_path.exists() {
+        println!("Hub service is not installed.");
+        return Ok(());
+    }
+    match manager {
+        ServiceManager::Launchd => {
+            let _ = Command::new("launchctl")
+                .args(["bootout", &launchd_target()])
+                .output();
+        }
+        ServiceManager::Systemd => {
+            let _ = Command::new("systemctl")
+                .args(["--user", "disable", "--now", SYSTEMD_UNIT])
+                .output();
+        }
+    }
+    fs::remove_file(&unit_path)
+        .with_context(|| format!("failed to remove {}", unit_path.display()))?;
+    if manager == ServiceManager::Systemd {
+        let _ = Command::new("systemctl")
+            .args(["--user", "daemon-reload"])
+            .output();
+    }
+    println!("Removed hub service {}", unit_path.display());
+    Ok(())
+}
+
+/// Start the installed service (used by `f hub` when the daemon isn't responding).
+pub fn start() -> Result<()> {
+    match ServiceManager::current()? {
+        ServiceManager::Launchd => {
+            let domain = launchd_domain();
+            let plist = ServiceManager::Launchd.unit_path();
+            // Not loaded after `f hub stop`; bootstrap fails harmlessly when it already is.
+            let _ = Command::new("launchctl")
+                .args(["bootstrap", &domain, plist.to_string_lossy().as_ref()])
+                .output();
+            run_checked("launchctl", &["kickstart", &launchd_target()])
+        }
+        ServiceManager::Systemd => run_checked("systemctl", &["--user", "start", SYSTEMD_UNIT]),
+    }
+}
+
+/// Stop the installed service until the next login or `f hub`.
+pub fn stop() -> Result<()> {
+    match ServiceManager::current()? {
+        ServiceManager::Launchd => {
+            let _ = Command::new("launchctl")
+                .args(["bootout", &launchd_target()])
+                .output();
+            Ok(())
+        }
+        ServiceManager::Systemd => run_checked("systemctl", &["--user", "stop", SYSTEMD_UNIT]),
+    }
+}
+
+pub fn status(host: IpAddr, port: u16) -> Result<()> {
+    let manager = ServiceManager::current()?;
+    let unit_path = manager.unit_path();
+    if unit_path.exists() {
+        let state = match manager {
+            ServiceManager::Launchd => launchd_state(),
+            ServiceManager::Systemd => systemd_state(),
+        };
+        println!("Service: {state}");
+        println!("Unit:    {}", unit_path.display());
+    } else {
+        println!("Service: not installed (run `f hub install`)");
+    }
+
+    let health = if hub::hub_healthy(host, port) {
+        "ok"
+    } else {
+        "not responding"
+    };
+    println!("Health:  {health} ({host}:{port})");
+
+    let (stdout_log, stderr_log) = daemon::daemon_log_paths(HUB_DAEMON)?;
+    for (label, path) in [("Stdout:", stdout_log), ("Stderr:", stderr_log)] {
+        let size = fs::metadata(&path)
+            .map(|meta| format_size(meta.len()))
+            .unwrap_or_else(|_| "missing".to_string());
+        println!("{label}  {} ({size})", path.display());
+    }
+    Ok(())
+}
+
+/// Foreground entry point for the service: run lin with rotated stdout/stderr logs and exit
+/// with its status so the service manager can restart it.
+pub fn run_foreground(opts: HubRunOpts) -> Result<()> {
+    let daemon_cfg = daemon::find_daemon_config_with_path(HUB_DAEMON, None)?;
+    let binary = daemon::find_binary(&daemon_cfg.binary)?;
+    let (stdout_path, stderr_path) = daemon::daemon_log_paths(HUB_DAEMON)?;
+    let max_bytes = opts.max_log_mb.saturating_mul(1024 * 1024);
+
+    let mut child = daemon::daemon_command(&daemon_cfg, &binary)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to start {}", binary.display()))?;
+
+    let mut handles = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        let log = RotatingLog::open(&stdout_path, max_bytes, opts.keep_logs)?;
+        handles.push(copy_to_log(stdout, log));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        let log = RotatingLog::open(&stderr_path, max_bytes, opts.keep_logs)?;
+        handles.push(copy_to_log(stderr, log));
+    }
+
+    // These run for the life of this process; the service manager restarts them together.
+    project_usage::spawn_sampler();
+    deploy_watch::spawn_watcher();
+
+    let status = child.wait().context("failed to wait for hub daemon")?;
+    for handle in handles {
+        let _ = handle.join();
+    }
+    if !status.success() {
+        bail!("hub daemon exited with {status}");
+    }
+    Ok(())
+}
+
+fn copy_to_log<R: Read + Send + 'static>(
+    mut reader: R,
+    mut log: RotatingLog,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut chunk = [0u8; 8192];
+        loop {
+            match reader.read(&mut chunk) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if let Err(err) = log.write(&chunk[..n]) {
+                        eprintln!("WARN failed to write hub log: {err}");
+                        // Keep draining so the daemon never blocks on a full pipe.
+                        thread::sleep(Duration::from_millis(100));
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Append-only log file that is renamed to `<name>.1` (shifting older copies up to
+/// `<name>.<keep>`) once it would grow past `max_bytes`.
+struct RotatingLog {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+    keep: usize,
+}
+
+impl RotatingLog {
+    fn open(path: &Path, max_bytes: u64, keep: usize) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        let size = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            size,
+            max_bytes,
+            keep,
+        })
+    }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<()> {
+        if self.max_bytes > 0 && self.size > 0 && self.size + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(buf)?;
+        self.size += buf.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.keep == 0 {
+            self.file.set_len(0)?;
+        } else {
+            for idx in (1..self.keep).rev() {
+                let from = rotated_path(&self.path, idx);
+                if from.exists() {
+                    fs::rename(&from, rotated_path(&self.path, idx + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated_path(&self.path, 1))?;
+            self.file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path, idx: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{idx}"));
+    PathBuf::from(name)
+}
+
+fn service_program_args(logs: &HubRunOpts) -> Result<Vec<String>> {
+    let exe = std::env::current_exe().context("failed to resolve flow binary")?;
+    Ok(vec![
+        exe.to_string_lossy().into_owned(),
+        "hub".to_string(),
+        "run".to_string(),
+        "--max-log-mb".to_string(),
+        logs.max_log_mb.to_string(),
+        "--keep-logs".to_string(),
+        logs.keep_logs.to_string(),
+    ])
+}
+
+fn render_plist(args: &[String], path_env: &str, service_log: &Path) -> String {
+    let service_log = xml_escape(&service_log.to_string_lossy());
+    let mut buf = String::new();
+    buf.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    buf.push_str(
+        "<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n",
+    );
+    buf.push_str("<plist version=\"1.0\">\n<dict>\n");
+    buf.push_str("  <key>Label</key>\n");
+    buf.push_str(&format!("  <string>{LAUNCHD_LABEL}</string>\n"));
+    buf.push_str("  <key>ProgramArguments</key>\n  <array>\n");
+    for arg in args {
+        buf.push_str(&format!("    <string>{}</string>\n", xml_escape(arg)));
+    }
+    buf.push_str("  </array>\n");
+    buf.push_str("  <key>EnvironmentVariables</key>\n  <dict>\n");
+    buf.push_str("    <key>PATH</key>\n");
+    buf.push_str(&format!("    <string>{}</string>\n", xml_escape(path_env)));
+    buf.push_str("  </dict>\n");
+    buf.push_str("  <key>RunAtLoad</key>\n  <true/>\n");
+    buf.push_str("  <key>KeepAlive</key>\n  <true/>\n");
+    buf.push_str("  <key>ThrottleInterval</key>\n  <integer>10</integer>\n");
+    buf.push_str("  <key>StandardOutPath</key>\n");
+    buf.push_str(&format!("  <string>{service_log}</string>\n"));
+    buf.push_str("  <key>StandardErrorPath</key>\n");
+    buf.push_str(&format!("  <string>{service_log}</string>\n"));
+    buf.push_str("</dict>\n</plist>\n");
+    buf
+}
+
+fn render_systemd_unit(args: &[String], path_env: &str) -> String {
+    let exec = args
+        .iter()
+        .map(|arg| systemd_quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        "[Unit]\n\
+         Description=flow hub daemon ({HUB_DAEMON})\n\
+         After=network-online.target\n\
+         \n\
+         [Service]\n\
+         Type=simple\n\
+         ExecStart={exec}\n\
+         Environment={}\n\
+         Restart=always\n\
+         RestartSec=5\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        systemd_quote(&format!("PATH={path_env}"))
+    )
+}
+
+fn systemd_quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%");
+    format!("\"{escaped}\"")
+}
+
+fn xml_escape(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for ch in input.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+fn launchd_domain() -> String {
+    let uid = unsafe { libc::getuid() };
+    format!("gui/{uid}")
+}
+
+fn launchd_target() -> String {
+    format!("{}/{LAUNCHD_LABEL}", launchd_domain())
+}
+
+fn launchd_state() -> String {
+    let output = Command::new("launchctl")
+        .args(["print", &launchd_target()])
+        .output();
+    let output = match output {
+        Ok(output) if output.status.success() => output,
+        _ => return format!("launchd ({LAUNCHD_LABEL}) not loaded"),
+    };
+    let text = String::from_utf8_lossy(&output.stdout);
+    let field = |key: &str| {
+        text.lines()
+            .map(str::trim)
+            .find_map(|line| line.strip_prefix(key))
+            .map(|value| value.trim().to_string())
+    };
+    let state = field("state = ").unwrap_or_else(|| "loaded".to_string());
+    match field("pid = ") {
+        Some(pid) => format!("launchd ({LAUNCHD_LABEL}) {state}, pid {pid}"),
+        None => format!("launchd ({LAUNCHD_LABEL}) {state}"),
+    }
+}
+
+fn systemd_state() -> String {
+    let query = |verb: &str| {
+        Command::new("systemctl")
+            .args(["--user", verb, SYSTEMD_UNIT])
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .unwrap_or_else(|_| "unknown".to_string())
+    };
+    format!(
+        "systemd ({SYSTEMD_UNIT}) {}, {}",
+        query("is-active"),
+        query("is-enabled")
+    )
+}
+
+fn run_checked(program: &str, args: &[&str]) -> Result<()> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("failed to run {program}"))?;
+    if !output.status.success() {
+        bail!(
+            "{program} {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{bytes} B")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotates_logs_and_keeps_bounded_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stdout.log");
+        let mut log = RotatingLog::open(&path, 10, 2).unwrap();
+        for chunk in ["first-line", "second", "third-line", "fourth"] {
+            log.write(chunk.as_bytes()).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth");
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 1)).unwrap(),
+            "third-line"
+        );
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 2)).unwrap(),
+            "second"
+        );
+        assert!(!rotated_path(&path, 3).exists());
+    }
+
+    #[test]
+    fn renders_service_definitions() {
+        let args = vec![
+            "/Users/me/bin/f".to_string(),
+            "hub".to_string(),
+            "run".to_string(),
+        ];
+        let plist = render_plist(
+            &args,
+            "/opt/homebrew/bin:/usr/bin",
+            Path::new("/tmp/service.log"),
+        );
+        assert!(plist.contains("<string>io.linsa.flow-hub</string>"));
+        assert!(plist.contains("    <string>/Users/me/bin/f</string>\n    <string>hub</string>"));
+        assert!(plist.contains("<key>KeepAlive</key>\n  <true/>"));
+
+        let unit = render_systemd_unit(&args, "/usr/bin:/home/me/50%");
+        assert!(unit.contains("ExecStart=\"/Users/me/bin/f\" \"hub\" \"run\"\n"));
+        assert!(unit.contains("Environment=\"PATH=/usr/bin:/home/me/50%%\"\n"));
+        assert!(unit.contains("Restart=always\n"));
+    }
+}
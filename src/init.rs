@@ -6,6 +6,7 @@ use std::{
 use anyhow::{Context, Result, bail};
 
 use crate::cli::InitOpts;
+use crate::monorepo;
 
 const TEMPLATE: &str = r#"version = 1
 
@@ -59,10 +60,24 @@ pub(crate) fn write_template(path: &Path) -> Result<()> {
         }
     }
 
-    fs::write(path, TEMPLATE).with_context(|| format!("failed to write {}", path.display()))?;
+    let project_root = path.parent().unwrap_or_else(|| Path::new("."));
+    fs::write(path, render_template(project_root))
+        .with_context(|| format!("failed to write {}", path.display()))?;
     Ok(())
 }
 
+/// In a Cargo/pnpm workspace, replace the placeholder setup/dev tasks with per-package tasks.
+pub(crate) fn render_template(project_root: &Path) -> String {
+    let Some(workspace) = monorepo::detect(project_root) else {
+        return TEMPLATE.to_string();
+    };
+    let baseline = TEMPLATE
+        .find("[skills]")
+        .map(|idx| &TEMPLATE[idx..])
+        .unwrap_or_default();
+    format!("{}\n{baseline}", monorepo::render_flow_toml(&workspace))
+}
+
 pub fn run(opts: InitOpts) -> Result<()> {
     let target = resolve_path(opts.path);
     if target.exists() {
@@ -98,4 +113,26 @@ mod tests {
         assert!(TEMPLATE.contains("[commit.skill_gate]"));
         assert!(TEMPLATE.contains("quality-bun-feature-delivery = 2"));
     }
+
+    #[test]
+    fn workspace_template_uses_package_tasks() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        fs::write(
+            dir.path().join("pnpm-workspace.yaml"),
+            "packages:\n  - apps/*\n",
+        )
+        .expect("write pnpm-workspace.yaml");
+        fs::create_dir_all(dir.path().join("apps/web")).expect("create apps/web");
+        fs::write(
+            dir.path().join("apps/web/package.json"),
+            r#"{"name": "web", "scripts": {"dev": "vite"}}"#,
+        )
+        .expect("write package.json");
+
+        let rendered = render_template(dir.path());
+        assert!(rendered.contains("name = \"web:dev\""));
+        assert!(!rendered.contains("(fill me)"));
+        assert!(rendered.contains("[commit.skill_gate]"));
+        toml::from_str::<toml::Value>(&rendered).expect("rendered template is valid toml");
+    }
 }
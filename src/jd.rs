@@ -100,6 +100,8 @@ pub fn builtin_daemon_config() -> Result<config::DaemonConfig> {
         ready_delay: Some(100),
         ready_output: None,
         description: Some("Flow-managed jd query daemon for j/Codex session recovery".to_string()),
+        restart_on: Vec::new(),
+        restart_cooldown: None,
     })
 }
 
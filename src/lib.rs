@@ -4,6 +4,7 @@ pub mod agents;
 pub mod ai;
 pub mod ai_context;
 pub mod ai_everruns;
+pub mod ai_guard;
 pub mod ai_project_manifest;
 pub mod ai_server;
 pub mod ai_taskd;
@@ -12,8 +13,10 @@ pub mod ai_test;
 pub mod analytics;
 pub mod archive;
 pub mod ask;
+pub mod ask_index;
 pub mod auth;
 pub mod base_tool;
+pub mod branch_watch;
 pub mod branches;
 pub mod changes;
 pub mod cli;
@@ -26,33 +29,51 @@ pub mod codex_skill_eval;
 pub mod codex_telemetry;
 pub mod codex_text;
 pub mod commit;
+pub mod commit_signing;
+pub mod commit_split;
 pub mod commits;
+pub mod completions;
 pub mod config;
+pub mod config_edit;
+pub mod config_interp;
 pub mod daemon;
 pub mod daemon_snapshot;
 pub mod db;
+pub mod dep_installer;
 pub mod deploy;
+pub mod deploy_audit;
+pub mod deploy_provision;
 pub mod deploy_setup;
+pub mod deploy_watch;
+pub mod deploy_workers;
 pub mod deps;
+pub mod diagnose;
 pub mod discover;
+pub mod discover_github;
 pub mod docs;
 pub mod doctor;
 pub mod domains;
+pub mod editor_api;
 pub mod env;
+pub mod env_history;
 pub mod env_setup;
+pub mod env_template;
 pub mod explain_commits;
 pub mod ext;
 pub mod external_cli;
 pub mod failure;
 pub mod features;
+pub mod find;
 pub mod fish_install;
 pub mod fish_trace;
 pub mod fix;
 pub mod fixup;
 pub mod flow_config;
+pub mod flow_toml_advisor;
 pub mod flox;
 pub mod gh_release;
 pub mod git_guard;
+pub mod git_hooks;
 pub mod gitignore_policy;
 pub mod hash;
 pub mod health;
@@ -62,6 +83,11 @@ pub mod hive;
 pub mod home;
 pub mod http_client;
 pub mod hub;
+pub mod hub_backup;
+pub mod hub_dashboard;
+pub mod hub_events;
+pub mod hub_queue;
+pub mod hub_service;
 pub mod info;
 pub mod init;
 pub mod install;
@@ -73,11 +99,16 @@ pub mod jj;
 pub mod json_parse;
 pub mod latest;
 pub mod lifecycle;
+pub mod line_diff;
 pub mod lmstudio;
+pub mod log_bundle;
 pub mod log_server;
 pub mod log_store;
+pub mod log_tokens;
 pub mod macos;
+pub mod monorepo;
 pub mod notify;
+pub mod notify_rules;
 pub mod opentui_prompt;
 pub mod ops_overview;
 pub mod otp;
@@ -85,10 +116,14 @@ pub mod palette;
 pub mod parallel;
 #[cfg(test)]
 mod path_hygiene;
+pub mod plugins;
 pub mod pr_edit;
 pub mod pr_preview;
 pub mod processes;
+pub mod project_selector;
 pub mod project_snapshot;
+pub mod project_status;
+pub mod project_usage;
 pub mod projects;
 pub mod proxy;
 pub mod publish;
@@ -103,15 +138,22 @@ pub mod repo_capsule;
 pub mod repos;
 pub mod reviews_todo;
 pub mod rl_signals;
+pub mod run_notes;
 pub mod running;
+pub mod runs;
 pub mod runtime_assets;
 pub mod sealer_crypto;
+pub mod secret_quarantine;
 pub mod secret_redact;
+pub mod secret_verify;
 pub mod seq_client;
 pub mod seq_rpc;
+pub mod service_watch;
 pub mod services;
 pub mod setup;
+pub mod skill_source;
 pub mod skills;
+pub mod source_maps;
 pub mod ssh;
 pub mod ssh_keys;
 pub mod start;
@@ -120,9 +162,20 @@ pub mod storage;
 pub mod supervisor;
 pub mod sync;
 pub mod sync_plan;
+pub mod tail;
+pub mod task_cache;
 pub mod task_failure_agents;
+pub mod task_graph;
+pub mod task_log;
 pub mod task_match;
+pub mod task_matrix;
+pub mod task_parsers;
+pub mod task_process;
+pub mod task_prompt;
+pub mod task_retry;
+pub mod task_stats;
 pub mod tasks;
+pub mod time_window;
 pub mod todo;
 pub mod tools;
 #[path = "traces_stub.rs"]
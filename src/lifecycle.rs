@@ -116,6 +116,9 @@ fn run_task(config_path: &Path, task_name: &str, args: Vec<String>) -> Result<()
         delegate_to_hub: false,
         hub_host: IpAddr::from([127, 0, 0, 1]),
         hub_port: 9050,
+        priority: 0,
+        print_env: false,
+        yes: false,
         name: task_name.to_string(),
         args,
     })
@@ -1,4 +1,4 @@
-//! Simple LM Studio API client for task matching.
+//! Simple LM Studio API client for task matching and embeddings.
 
 use anyhow::{Context, Result};
 use reqwest::blocking::Client;
@@ -6,6 +6,8 @@ use serde::{Deserialize, Serialize};
 
 const DEFAULT_PORT: u16 = 1234;
 const DEFAULT_MODEL: &str = "qwen3-8b";
+/// Embedding model bundled with LM Studio.
+pub const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-nomic-embed-text-v1.5";
 
 #[derive(Debug, Serialize)]
 struct ChatRequest {
@@ -35,6 +37,24 @@ struct ResponseMessage {
     content: String,
 }
 
+#[derive(Debug, Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    #[serde(default)]
+    index: usize,
+    embedding: Vec<f32>,
+}
+
 /// Send a prompt to LM Studio and get a response.
 pub fn quick_prompt(prompt: &str, model: Option<&str>, port: Option<u16>) -> Result<String> {
     let prompt = prompt.trim();
@@ -85,8 +105,49 @@ pub fn quick_prompt(prompt: &str, model: Option<&str>, port: Option<u16>) -> Res
     Ok(text)
 }
 
+/// Embed `texts` with an LM Studio embedding model, one vector per input in order.
+pub fn embed(texts: &[String], model: Option<&str>, port: Option<u16>) -> Result<Vec<Vec<f32>>> {
+    let model = model.unwrap_or(DEFAULT_EMBEDDING_MODEL);
+    let port = port.unwrap_or(DEFAULT_PORT);
+
+    let client = Client::builder()
+        .timeout(std::time::Duration::from_secs(120))
+        .build()
+        .context("failed to create HTTP client")?;
+
+    let url = format!("http://localhost:{port}/v1/embeddings");
+    let resp = client
+        .post(&url)
+        .json(&EmbeddingRequest {
+            model,
+            input: texts,
+        })
+        .send()
+        .with_context(|| format!("failed to connect to LM Studio at localhost:{port}"))?;
+
+    if !resp.status().is_success() {
+        anyhow::bail!(
+            "LM Studio returned status {}: {}",
+            resp.status(),
+            resp.text().unwrap_or_default()
+        );
+    }
+
+    let mut parsed: EmbeddingResponse = resp
+        .json()
+        .context("failed to parse LM Studio embeddings response")?;
+    if parsed.data.len() != texts.len() {
+        anyhow::bail!(
+            "LM Studio returned {} embeddings for {} inputs",
+            parsed.data.len(),
+            texts.len()
+        );
+    }
+    parsed.data.sort_by_key(|item| item.index);
+    Ok(parsed.data.into_iter().map(|item| item.embedding).collect())
+}
+
 /// Check if LM Studio is running and accessible.
-#[allow(dead_code)]
 pub fn is_available(port: Option<u16>) -> bool {
     let port = port.unwrap_or(DEFAULT_PORT);
     let client = match Client::builder()
@@ -0,0 +1,397 @@
+//! `f logs export` / `f logs import`: shareable bug-report bundles.
+//!
+//! A bundle is gzipped JSON lines. The first line is a `meta` record, followed by stored logs,
+//! run records (with a redacted tail of each run's log file), proxy trace records, and the
+//! proxy's agent summary when one exists.
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::{LogsExportOpts, LogsImportOpts};
+use crate::log_store::{self, LogEntry, LogQuery};
+use crate::proxy::trace::{self, TraceBuffer};
+use crate::runs::{self, RunRecord};
+use crate::{config, running, secret_redact, time_window};
+
+const BUNDLE_VERSION: u32 = 1;
+const MAX_LOGS: usize = 100_000;
+const MAX_TRACES: usize = 10_000;
+const RUN_LOG_TAIL_LINES: usize = 200;
+
+/// One line of a bundle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum BundleRecord {
+    Meta {
+        version: u32,
+        created_ms: i64,
+        since_ms: i64,
+        project: Option<String>,
+        flow_version: String,
+    },
+    Log(LogEntry),
+    Run {
+        // Nested rather than flattened: `RunRecord` has its own `kind` field.
+        run: RunRecord,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        log_tail: Option<String>,
+    },
+    Trace(TraceExport),
+    ProxySummary {
+        summary: serde_json::Value,
+    },
+}
+
+/// Proxy trace record with wall-clock time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TraceExport {
+    timestamp_ms: i64,
+    req_id: u64,
+    trace_id: String,
+    method: String,
+    path: String,
+    status: u16,
+    latency_us: u32,
+    upstream_latency_us: u32,
+    bytes_in: u32,
+    bytes_out: u32,
+    target_idx: u8,
+    flags: String,
+}
+
+pub fn export(opts: LogsExportOpts) -> Result<()> {
+    let now_ms = running::now_ms() as i64;
+    let window_ms = time_window::parse_since(&opts.since).context("invalid --since")?;
+    let since_ms = now_ms.saturating_sub(window_ms);
+    let project = opts.project.as_deref();
+
+    let mut records = vec![BundleRecord::Meta {
+        version: BUNDLE_VERSION,
+        created_ms: now_ms,
+        since_ms,
+        project: opts.project.clone(),
+        flow_version: env!("CARGO_PKG_VERSION").to_string(),
+    }];
+
+    let log_conn = log_store::open_log_db()?;
+    let mut logs = log_store::query_logs(
+        &log_conn,
+        &LogQuery {
+            project: opts.project.clone(),
+            since: Some(since_ms),
+            limit: MAX_LOGS,
+            ..Default::default()
+        },
+    )?;
+    logs.reverse();
+    let log_count = logs.len();
+    records.extend(
+        logs.into_iter()
+            .map(|stored| BundleRecord::Log(stored.entry)),
+    );
+
+    let run_conn = runs::open_runs_db()?;
+    let run_records = runs::runs_since(&run_conn, project, since_ms)?;
+    let run_count = run_records.len();
+    records.extend(run_records.into_iter().map(|run| {
+        let log_tail = run.log_path.as_deref().and_then(read_log_tail);
+        BundleRecord::Run { run, log_tail }
+    }));
+
+    let trace_dir = trace::default_trace_dir();
+    let traces = read_traces(&trace_dir, since_ms, now_ms);
+    let trace_count = traces.len();
+    records.extend(traces.into_iter().map(BundleRecord::Trace));
+    if let Some(summary) = fs::read_to_string(trace_dir.join("trace-summary.json"))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+    {
+        records.push(BundleRecord::ProxySummary { summary });
+    }
+
+    let out = opts
+        .out
+        .unwrap_or_else(|| default_export_path(project, now_ms));
+    write_bundle(&out, &records)?;
+    println!(
+        "Exported {log_count} log(s), {run_count} run(s), {trace_count} proxy trace(s) since {} to {}",
+        opts.since,
+        out.display()
+    );
+    Ok(())
+}
+
+pub fn import(opts: LogsImportOpts) -> Result<()> {
+    let records = read_bundle(&opts.path)?;
+    let Some(BundleRecord::Meta {
+        version,
+        created_ms,
+        project,
+        ..
+    }) = records.first().cloned()
+    else {
+        bail!("{} is not a flow log bundle", opts.path.display());
+    };
+    if version > BUNDLE_VERSION {
+        bail!("bundle version {version} is newer than this flow supports ({BUNDLE_VERSION})");
+    }
+
+    let stem = bundle_stem(&opts.path);
+    let import_dir = config::global_state_dir().join("imports").join(&stem);
+    fs::create_dir_all(&import_dir)
+        .with_context(|| format!("failed to create {}", import_dir.display()))?;
+
+    let mut log_conn = log_store::open_log_db()?;
+    let run_conn = runs::open_runs_db()?;
+    let mut new_logs = Vec::new();
+    let mut skipped_logs = 0;
+    let mut run_count = 0;
+    let mut traces = Vec::new();
+
+    for record in records.into_iter().skip(1) {
+        match record {
+            BundleRecord::Log(entry) => {
+                if log_store::log_exists(&log_conn, &entry)? {
+                    skipped_logs += 1;
+                } else {
+                    new_logs.push(entry);
+                }
+            }
+            BundleRecord::Run { mut run, log_tail } => {
+                run.log_path = match log_tail {
+                    Some(tail) => {
+                        let path = import_dir.join(format!("run-{}.log", run.id));
+                        fs::write(&path, tail)
+                            .with_context(|| format!("failed to write {}", path.display()))?;
+                        Some(path.display().to_string())
+                    }
+                    None => None,
+                };
+                runs::insert_run(&run_conn, &run)?;
+                run_count += 1;
+            }
+            BundleRecord::Trace(trace) => traces.push(trace),
+            BundleRecord::ProxySummary { summary } => {
+                let path = import_dir.join("proxy-summary.json");
+                fs::write(&path, serde_json::to_string_pretty(&summary)?)
+                    .with_context(|| format!("failed to write {}", path.display()))?;
+            }
+            BundleRecord::Meta { .. } => {}
+        }
+    }
+
+    log_store::insert_logs(&mut log_conn, &new_logs)?;
+    if !traces.is_empty() {
+        let path = import_dir.join("traces.jsonl");
+        let mut lines = String::new();
+        for trace in &traces {
+            lines.push_str(&serde_json::to_string(trace)?);
+            lines.push('\n');
+        }
+        fs::write(&path, lines).with_context(|| format!("failed to write {}", path.display()))?;
+    }
+
+    println!(
+        "Imported bundle from {} (project: {}, exported {} ago)",
+        opts.path.display(),
+        project.as_deref().unwrap_or("all"),
+        format_age((running::now_ms() as i64).saturating_sub(created_ms))
+    );
+    println!(
+        "  {} log(s) added ({skipped_logs} already present), {run_count} run(s), {} proxy trace(s)",
+        new_logs.len(),
+        traces.len()
+    );
+    println!("  Run logs and traces: {}", import_dir.display());
+    println!("  Inspect with `f runs` and the log server API (/logs).");
+    Ok(())
+}
+
+fn write_bundle(path: &Path, records: &[BundleRecord]) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let file =
+        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    let mut writer = BufWriter::new(GzEncoder::new(file, Compression::default()));
+    for record in records {
+        serde_json::to_writer(&mut writer, record).context("failed to encode bundle record")?;
+        writer.write_all(b"\n")?;
+    }
+    writer
+        .into_inner()
+        .map_err(|err| err.into_error())?
+        .finish()
+        .with_context(|| format!("failed to finish {}", path.display()))?;
+    Ok(())
+}
+
+fn read_bundle(path: &Path) -> Result<Vec<BundleRecord>> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let reader: Box<dyn BufRead> = if path.extension().is_some_and(|ext| ext == "gz") {
+        Box::new(BufReader::new(GzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+    let mut records = Vec::new();
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line.with_context(|| format!("failed to read {}", path.display()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line)
+            .with_context(|| format!("invalid bundle record on line {}", idx + 1))?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Records from the most recent proxy trace buffer within the export window.
+fn read_traces(trace_dir: &Path, since_ms: i64, now_ms: i64) -> Vec<TraceExport> {
+    let Some(buffer) =
+        trace::latest_trace_file(trace_dir).and_then(|path| TraceBuffer::open(&path))
+    else {
+        return Vec::new();
+    };
+    // Trace timestamps are monotonic; anchor them to the wall clock via "now".
+    let now_ns = trace::now_ns();
+    let mut traces: Vec<TraceExport> = buffer
+        .recent(MAX_TRACES)
+        .into_iter()
+        .filter(|record| record.timestamp() != 0)
+        .map(|record| {
+            let age_ms = (now_ns.saturating_sub(record.timestamp()) / 1_000_000) as i64;
+            TraceExport {
+                timestamp_ms: now_ms.saturating_sub(age_ms),
+                req_id: record.req_id(),
+                trace_id: format!("{:032x}", record.trace_id()),
+                method: format!("{:?}", record.method()).to_uppercase(),
+                path: record.path().to_string(),
+                status: record.status(),
+                latency_us: record.latency_us(),
+                upstream_latency_us: record.upstream_latency_us(),
+                bytes_in: record.bytes_in(),
+                bytes_out: record.bytes_out(),
+                target_idx: record.target_idx(),
+                flags: trace::describe_flags(record.flags()),
+            }
+        })
+        .filter(|trace| trace.timestamp_ms >= since_ms)
+        .collect();
+    traces.reverse();
+    traces
+}
+
+fn read_log_tail(path: &str) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    let lines: Vec<&str> = content.lines().collect();
+    let start = lines.len().saturating_sub(RUN_LOG_TAIL_LINES);
+    Some(secret_redact::redact_text(&lines[start..].join("\n")))
+}
+
+fn default_export_path(project: Option<&str>, now_ms: i64) -> PathBuf {
+    let label: String = project
+        .unwrap_or("all")
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' {
+                ch
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    PathBuf::from(format!("flow-logs-{label}-{}.jsonl.gz", now_ms / 1000))
+}
+
+fn bundle_stem(path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "bundle".to_string());
+    name.trim_end_matches(".gz")
+        .trim_end_matches(".jsonl")
+        .to_string()
+}
+
+fn format_age(elapsed_ms: i64) -> String {
+    let secs = elapsed_ms / 1000;
+    if secs < 60 {
+        format!("{secs}s")
+    } else if secs < 3600 {
+        format!("{}m", secs / 60)
+    } else if secs < 86400 {
+        format!("{}h", secs / 3600)
+    } else {
+        format!("{}d", secs / 86400)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runs::RunKind;
+
+    #[test]
+    fn bundle_round_trips_through_gzip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.jsonl.gz");
+        let records = vec![
+            BundleRecord::Meta {
+                version: BUNDLE_VERSION,
+                created_ms: 10,
+                since_ms: 0,
+                project: Some("foo".to_string()),
+                flow_version: "test".to_string(),
+            },
+            BundleRecord::Log(LogEntry {
+                project: "foo".to_string(),
+                content: "deploy failed".to_string(),
+                timestamp: 5,
+                log_type: "error".to_string(),
+                service: "deploy".to_string(),
+                stack: None,
+                resolved_stack: None,
+                format: "text".to_string(),
+            }),
+            BundleRecord::Run {
+                run: RunRecord {
+                    id: "abc123".to_string(),
+                    kind: RunKind::Task,
+                    name: "deploy".to_string(),
+                    project: Some("foo".to_string()),
+                    project_root: None,
+                    parent_id: None,
+                    started_ms: 1,
+                    duration_ms: 2,
+                    status: Some(1),
+                    success: false,
+                    log_path: None,
+                    detail: None,
+                    results: None,
+                    code_rev: None,
+                },
+                log_tail: Some("error: boom".to_string()),
+            },
+        ];
+        write_bundle(&path, &records).unwrap();
+
+        let read = read_bundle(&path).unwrap();
+        assert_eq!(read.len(), 3);
+        assert!(matches!(&read[0], BundleRecord::Meta { project: Some(p), .. } if p == "foo"));
+        assert!(matches!(&read[1], BundleRecord::Log(entry) if entry.content == "deploy failed"));
+        assert!(matches!(
+            &read[2],
+            BundleRecord::Run { run, log_tail: Some(tail) } if run.id == "abc123" && tail == "error: boom"
+        ));
+        assert_eq!(bundle_stem(&path), "report");
+    }
+}
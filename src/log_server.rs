@@ -11,7 +11,7 @@ use anyhow::{Context, Result, bail};
 use axum::{
     Router,
     extract::{Json as AxumJson, Path as AxumPath, Query, State},
-    http::{Method, StatusCode},
+    http::{HeaderMap, Method, StatusCode, header},
     response::{
         IntoResponse, Json,
         sse::{Event, KeepAlive, Sse},
@@ -26,15 +26,19 @@ use tower_http::cors::{Any, CorsLayer};
 
 use crate::cli::{ServerAction, ServerOpts};
 use crate::log_store::{self, LogEntry, LogQuery};
+use crate::log_tokens::{self, IngestDecision, QuotaTracker};
 use crate::pr_edit::PrEditService;
+use crate::tail::{self, TailFilter};
 use crate::{
-    ai, config, daemon_snapshot, explain_commits, ops_overview, projects, skills, workflow,
+    ai, config, daemon_snapshot, explain_commits, hub_dashboard, hub_events, ops_overview,
+    processes, projects, skills, source_maps, workflow,
 };
 
 #[derive(Clone)]
 struct AppState {
     pr_edit: Arc<tokio::sync::RwLock<Option<Arc<PrEditService>>>>,
     pr_edit_error: Arc<tokio::sync::RwLock<Option<String>>>,
+    ingest_quotas: Arc<std::sync::Mutex<QuotaTracker>>,
 }
 
 /// Run the flow HTTP server for log ingestion.
@@ -121,6 +125,7 @@ fn ensure_server(host: &str, port: u16) -> Result<()> {
 fn run_foreground(host: &str, port: u16) -> Result<()> {
     // Initialize database and schema on startup
     let conn = log_store::open_log_db().context("failed to initialize log database")?;
+    log_tokens::init_schema(&conn)?;
     drop(conn);
 
     let addr: SocketAddr = format!("{}:{}", host, port)
@@ -158,6 +163,7 @@ fn run_foreground(host: &str, port: u16) -> Result<()> {
         let state = AppState {
             pr_edit,
             pr_edit_error,
+            ingest_quotas: Arc::new(std::sync::Mutex::new(QuotaTracker::default())),
         };
 
         let router = Router::new()
@@ -178,6 +184,7 @@ fn run_foreground(host: &str, port: u16) -> Result<()> {
             .route("/logs/ingest", post(logs_ingest))
             .route("/logs/query", get(logs_query))
             .route("/logs/errors/stream", get(logs_errors_stream))
+            .route("/logs/tail/stream", get(logs_tail_stream))
             .route("/pr-edit/status", get(pr_edit_status))
             .route("/pr-edit/rescan", post(pr_edit_rescan))
             // Flow projects + AI sessions
@@ -193,7 +200,10 @@ fn run_foreground(host: &str, port: u16) -> Result<()> {
                 "/projects/{name}/commit-explanations/{sha}",
                 get(project_commit_explanation_detail),
             )
+            .merge(hub_dashboard::router())
             .layer(cors)
+            // Added after the CORS layer so other origins can't read or publish events.
+            .merge(hub_events::router())
             .with_state(state);
 
         let listener = tokio::net::TcpListener::bind(addr)
@@ -677,28 +687,59 @@ enum IngestRequest {
     Batch(Vec<LogEntry>),
 }
 
-async fn logs_ingest(Json(payload): Json<IngestRequest>) -> impl IntoResponse {
+pub(crate) fn bearer_token(headers: &HeaderMap) -> Option<String> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let token = value.strip_prefix("Bearer ")?.trim();
+    (!token.is_empty()).then(|| token.to_string())
+}
+
+async fn logs_ingest(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<IngestRequest>,
+) -> impl IntoResponse {
+    let bearer = bearer_token(&headers);
     let result = tokio::task::spawn_blocking(move || {
         let mut conn = match log_store::open_log_db() {
             Ok(c) => c,
             Err(e) => return Err(e),
         };
 
-        match payload {
-            IngestRequest::Single(entry) => {
-                let id = log_store::insert_log(&conn, &entry)?;
-                Ok(json!({ "inserted": 1, "ids": [id] }))
-            }
-            IngestRequest::Batch(entries) => {
-                let ids = log_store::insert_logs(&mut conn, &entries)?;
-                Ok(json!({ "inserted": ids.len(), "ids": ids }))
-            }
+        let (single, mut entries) = match payload {
+            IngestRequest::Single(entry) => (true, vec![entry]),
+            IngestRequest::Batch(entries) => (false, entries),
+        };
+        let decision = {
+            let mut quotas = state
+                .ingest_quotas
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            log_tokens::authorize(
+                &conn,
+                &mut quotas,
+                bearer.as_deref(),
+                &mut entries,
+                crate::running::now_ms() as i64,
+            )?
+        };
+        if decision != IngestDecision::Allow {
+            return Ok(Err(decision));
+        }
+        source_maps::resolve_entries(&mut entries);
+
+        if single {
+            let id = log_store::insert_log(&conn, &entries[0])?;
+            Ok(Ok(json!({ "inserted": 1, "ids": [id] })))
+        } else {
+            let ids = log_store::insert_logs(&mut conn, &entries)?;
+            Ok(Ok(json!({ "inserted": ids.len(), "ids": ids })))
         }
     })
     .await;
 
     match result {
-        Ok(Ok(response)) => (StatusCode::OK, Json(response)).into_response(),
+        Ok(Ok(Ok(response))) => (StatusCode::OK, Json(response)).into_response(),
+        Ok(Ok(Err(decision))) => ingest_rejection(decision),
         Ok(Err(err)) => {
             tracing::error!(?err, "log ingest failed");
             (
@@ -718,6 +759,34 @@ async fn logs_ingest(Json(payload): Json<IngestRequest>) -> impl IntoResponse {
     }
 }
 
+fn ingest_rejection(decision: IngestDecision) -> axum::response::Response {
+    match decision {
+        IngestDecision::Allow => StatusCode::OK.into_response(),
+        IngestDecision::Unauthorized(message) => {
+            (StatusCode::UNAUTHORIZED, Json(json!({ "error": message }))).into_response()
+        }
+        IngestDecision::RateLimited {
+            message,
+            retry_after_secs,
+        } => (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_after_secs.to_string())],
+            Json(json!({ "error": message })),
+        )
+            .into_response(),
+        IngestDecision::QuotaExceeded(message) => (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(json!({ "error": message })),
+        )
+            .into_response(),
+        IngestDecision::TooLarge { message, limit } => (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(json!({ "error": message, "limit": limit })),
+        )
+            .into_response(),
+    }
+}
+
 async fn logs_query(Query(query): Query<LogQuery>) -> impl IntoResponse {
     let result = tokio::task::spawn_blocking(move || {
         let conn = log_store::open_log_db()?;
@@ -996,3 +1065,114 @@ async fn logs_errors_stream() -> Sse<impl Stream<Item = Result<Event, std::conve
 
     Sse::new(stream).keep_alive(KeepAlive::default())
 }
+
+#[derive(Debug, Deserialize, Default)]
+struct TailQuery {
+    /// Registered project name; must match the token's project when given.
+    project: Option<String>,
+    /// Comma-separated sources: task, journal, proxy.
+    source: Option<String>,
+    task: Option<String>,
+    grep: Option<String>,
+}
+
+/// Lines buffered per `/logs/tail/stream` client; later lines are dropped while it is full.
+const TAIL_STREAM_BUFFER: usize = 1024;
+
+/// SSE stream of `f tail` for a project, one JSON line per event.
+/// Requires a bearer token from `f logs token create`; the stream is scoped to its project.
+async fn logs_tail_stream(
+    headers: HeaderMap,
+    Query(query): Query<TailQuery>,
+) -> axum::response::Response {
+    let Some(bearer) = bearer_token(&headers) else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error": "missing bearer token" })),
+        )
+            .into_response();
+    };
+    let started = tokio::task::spawn_blocking(move || {
+        let conn = log_store::open_log_db()?;
+        log_tokens::init_schema(&conn)?;
+        let Some(token) = log_tokens::lookup(&conn, &bearer)? else {
+            return Ok(Err((
+                StatusCode::UNAUTHORIZED,
+                "invalid or revoked token".to_string(),
+            )));
+        };
+        if query
+            .project
+            .as_deref()
+            .is_some_and(|project| project != token.project)
+        {
+            return Ok(Err((
+                StatusCode::FORBIDDEN,
+                format!("token is scoped to project '{}'", token.project),
+            )));
+        }
+        let sources = tail::parse_sources(query.source.as_deref().unwrap_or_default())?;
+        let (project_root, config_path, project_name) =
+            processes::resolve_log_project(std::path::Path::new(""), Some(&token.project))?;
+        tail::spawn(
+            &project_root,
+            &config_path,
+            project_name,
+            TailFilter {
+                sources,
+                task: query.task,
+                grep: query.grep,
+            },
+        )
+        .map(Ok)
+    })
+    .await;
+    let stream = match started {
+        Ok(Ok(Ok(stream))) => stream,
+        Ok(Ok(Err((status, error)))) => {
+            return (status, Json(json!({ "error": error }))).into_response();
+        }
+        Ok(Err(err)) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": err.to_string() })),
+            )
+                .into_response();
+        }
+        Err(err) => {
+            tracing::error!(?err, "tail task panicked");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "internal error" })),
+            )
+                .into_response();
+        }
+    };
+
+    // Forward from the blocking merger until the client goes away; dropping the tail then
+    // stops its sources. A slow client loses lines rather than growing the buffer.
+    let (tx, rx) = tokio::sync::mpsc::channel(TAIL_STREAM_BUFFER);
+    tokio::task::spawn_blocking(move || {
+        loop {
+            match stream.lines.recv_timeout(Duration::from_millis(500)) {
+                Ok(line) => match tx.try_send(line) {
+                    Ok(()) | Err(tokio::sync::mpsc::error::TrySendError::Full(_)) => {}
+                    Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => break,
+                },
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout) if !tx.is_closed() => {}
+                Err(_) => break,
+            }
+        }
+    });
+    let events = stream::unfold(rx, |mut rx| async move {
+        let line = rx.recv().await?;
+        let data = serde_json::to_string(&line).unwrap_or_default();
+        Some((
+            Ok::<_, std::convert::Infallible>(Event::default().data(data)),
+            rx,
+        ))
+    });
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
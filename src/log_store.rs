@@ -1,5 +1,8 @@
+use std::sync::OnceLock;
+
 use anyhow::{Context, Result};
-use rusqlite::{Connection, params};
+use regex::Regex;
+use rusqlite::{Connection, OptionalExtension, params};
 use serde::{Deserialize, Serialize};
 
 use crate::db;
@@ -16,6 +19,9 @@ pub struct LogEntry {
     pub service: String, // task name or custom service
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stack: Option<String>,
+    /// `stack` with minified frames mapped back through the project's source maps.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_stack: Option<String>,
     #[serde(default = "default_format")]
     pub format: String, // "json" | "text"
 }
@@ -32,6 +38,20 @@ pub struct StoredLogEntry {
     pub entry: LogEntry,
 }
 
+/// Error entries that share a signature.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorCluster {
+    pub signature: String,
+    pub count: u64,
+    pub first_seen: i64,
+    pub last_seen: i64,
+    /// The most recent entry in the cluster.
+    pub latest: StoredLogEntry,
+}
+
+/// Signatures keep this much of the normalized message.
+const MAX_SIGNATURE_CHARS: usize = 240;
+
 /// Query parameters for filtering logs.
 #[derive(Debug, Clone, Deserialize)]
 pub struct LogQuery {
@@ -77,7 +97,9 @@ pub fn init_schema(conn: &Connection) -> Result<()> {
             log_type TEXT NOT NULL,
             service TEXT NOT NULL,
             stack TEXT,
-            format TEXT NOT NULL DEFAULT 'text'
+            resolved_stack TEXT,
+            format TEXT NOT NULL DEFAULT 'text',
+            signature TEXT
         );
         CREATE INDEX IF NOT EXISTS idx_logs_project ON logs(project);
         CREATE INDEX IF NOT EXISTS idx_logs_timestamp ON logs(timestamp);
@@ -86,16 +108,102 @@ pub fn init_schema(conn: &Connection) -> Result<()> {
         "#,
     )
     .context("failed to create logs schema")?;
+    ensure_columns(conn)?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_logs_signature ON logs(signature)",
+        [],
+    )
+    .context("failed to create logs signature index")?;
     Ok(())
 }
 
+/// Databases created before source-map resolution or error clustering lack the
+/// `resolved_stack` and `signature` columns.
+fn ensure_columns(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("PRAGMA table_info(logs)")?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    if !columns.iter().any(|name| name == "resolved_stack") {
+        conn.execute("ALTER TABLE logs ADD COLUMN resolved_stack TEXT", [])
+            .context("failed to add resolved_stack column")?;
+    }
+    if !columns.iter().any(|name| name == "signature") {
+        conn.execute("ALTER TABLE logs ADD COLUMN signature TEXT", [])
+            .context("failed to add signature column")?;
+        backfill_signatures(conn)?;
+    }
+    Ok(())
+}
+
+fn backfill_signatures(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT id, content FROM logs WHERE log_type = 'error'")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for (id, content) in rows {
+        conn.execute(
+            "UPDATE logs SET signature = ?1 WHERE id = ?2",
+            params![error_signature(&content), id],
+        )
+        .context("failed to backfill error signature")?;
+    }
+    Ok(())
+}
+
+/// Normalize an error message so repeats of the same failure share a key: UUIDs, hex
+/// values, paths, and numbers are replaced with placeholders and whitespace is collapsed.
+pub fn error_signature(content: &str) -> String {
+    static PATTERNS: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+    let patterns = PATTERNS.get_or_init(|| {
+        [
+            (
+                r"(?i)\b[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}\b",
+                "<uuid>",
+            ),
+            (r"(?i)\b0x[0-9a-f]+\b", "<hex>"),
+            (r"(?i)\b[0-9a-f]{16,}\b", "<hex>"),
+            (
+                r#"(?:[A-Za-z]:\\|~/|\.{0,2}/|\b[\w.-]+/)[^\s:'"()\[\]]*[^\s:'"()\[\].,;]"#,
+                "<path>",
+            ),
+            (r"\d+(?:\.\d+)*", "<n>"),
+        ]
+        .into_iter()
+        .map(|(pattern, placeholder)| {
+            (
+                Regex::new(pattern).expect("error signature pattern"),
+                placeholder,
+            )
+        })
+        .collect()
+    });
+    let mut normalized = content.trim().to_string();
+    for (re, placeholder) in patterns {
+        normalized = re.replace_all(&normalized, *placeholder).into_owned();
+    }
+    normalized
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(MAX_SIGNATURE_CHARS)
+        .collect()
+}
+
+fn signature_for(entry: &LogEntry) -> Option<String> {
+    (entry.log_type == "error").then(|| error_signature(&entry.content))
+}
+
 /// Insert a single log entry.
 pub fn insert_log(conn: &Connection, entry: &LogEntry) -> Result<i64> {
     let sanitized = sanitize_entry(entry);
     conn.execute(
         r#"
-        INSERT INTO logs (project, content, timestamp, log_type, service, stack, format)
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        INSERT INTO logs (project, content, timestamp, log_type, service, stack, resolved_stack, format, signature)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
         "#,
         params![
             sanitized.project,
@@ -104,7 +212,9 @@ pub fn insert_log(conn: &Connection, entry: &LogEntry) -> Result<i64> {
             sanitized.log_type,
             sanitized.service,
             sanitized.stack,
+            sanitized.resolved_stack,
             sanitized.format,
+            signature_for(&sanitized),
         ],
     )
     .context("failed to insert log")?;
@@ -120,8 +230,8 @@ pub fn insert_logs(conn: &mut Connection, entries: &[LogEntry]) -> Result<Vec<i6
         let sanitized = sanitize_entry(entry);
         tx.execute(
             r#"
-            INSERT INTO logs (project, content, timestamp, log_type, service, stack, format)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            INSERT INTO logs (project, content, timestamp, log_type, service, stack, resolved_stack, format, signature)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
             "#,
             params![
                 sanitized.project,
@@ -130,7 +240,9 @@ pub fn insert_logs(conn: &mut Connection, entries: &[LogEntry]) -> Result<Vec<i6
                 sanitized.log_type,
                 sanitized.service,
                 sanitized.stack,
+                sanitized.resolved_stack,
                 sanitized.format,
+                signature_for(&sanitized),
             ],
         )
         .context("failed to insert log")?;
@@ -144,7 +256,7 @@ pub fn insert_logs(conn: &mut Connection, entries: &[LogEntry]) -> Result<Vec<i6
 /// Query logs with filters.
 pub fn query_logs(conn: &Connection, query: &LogQuery) -> Result<Vec<StoredLogEntry>> {
     let mut sql = String::from(
-        "SELECT id, project, content, timestamp, log_type, service, stack, format FROM logs WHERE 1=1",
+        "SELECT id, project, content, timestamp, log_type, service, stack, resolved_stack, format FROM logs WHERE 1=1",
     );
     let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
 
@@ -176,22 +288,7 @@ pub fn query_logs(conn: &Connection, query: &LogQuery) -> Result<Vec<StoredLogEn
     let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
 
     let mut stmt = conn.prepare(&sql)?;
-    let rows = stmt.query_map(params_refs.as_slice(), |row| {
-        let content: String = row.get(2)?;
-        let stack: Option<String> = row.get(6)?;
-        Ok(StoredLogEntry {
-            id: row.get(0)?,
-            entry: LogEntry {
-                project: row.get(1)?,
-                content: secret_redact::redact_text(&content),
-                timestamp: row.get(3)?,
-                log_type: row.get(4)?,
-                service: row.get(5)?,
-                stack: stack.map(|value| secret_redact::redact_text(&value)),
-                format: row.get(7)?,
-            },
-        })
-    })?;
+    let rows = stmt.query_map(params_refs.as_slice(), stored_entry_from_row)?;
 
     let mut entries = Vec::new();
     for row in rows {
@@ -200,6 +297,143 @@ pub fn query_logs(conn: &Connection, query: &LogQuery) -> Result<Vec<StoredLogEn
     Ok(entries)
 }
 
+/// Entries whose content or stack contains `needle` (case-insensitive for ASCII), newest first.
+pub fn search_logs(
+    conn: &Connection,
+    project: Option<&str>,
+    needle: &str,
+    limit: usize,
+) -> Result<Vec<StoredLogEntry>> {
+    let pattern = format!(
+        "%{}%",
+        needle
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    );
+    let mut stmt = conn.prepare(
+        "SELECT id, project, content, timestamp, log_type, service, stack, resolved_stack, format \
+         FROM logs WHERE (?1 IS NULL OR project = ?1) \
+         AND (content LIKE ?2 ESCAPE '\\' OR stack LIKE ?2 ESCAPE '\\') \
+         ORDER BY timestamp DESC LIMIT ?3",
+    )?;
+    let rows = stmt.query_map(
+        params![project, pattern, limit.max(1) as i64],
+        stored_entry_from_row,
+    )?;
+    let mut entries = Vec::new();
+    for row in rows {
+        entries.push(row?);
+    }
+    Ok(entries)
+}
+
+/// Group error entries by signature, most recently seen first. `limit` caps the number of
+/// clusters, not entries.
+pub fn error_clusters(
+    conn: &Connection,
+    project: Option<&str>,
+    since: Option<i64>,
+    limit: usize,
+) -> Result<Vec<ErrorCluster>> {
+    let mut sql = String::from(
+        r#"
+        SELECT signature, COUNT(*), MIN(timestamp), MAX(timestamp), MAX(id)
+        FROM logs
+        WHERE log_type = 'error' AND signature IS NOT NULL
+        "#,
+    );
+    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+    if let Some(project) = project {
+        sql.push_str(" AND project = ?");
+        params_vec.push(Box::new(project.to_string()));
+    }
+    if let Some(since) = since {
+        sql.push_str(" AND timestamp >= ?");
+        params_vec.push(Box::new(since));
+    }
+    sql.push_str(" GROUP BY project, signature ORDER BY MAX(timestamp) DESC LIMIT ?");
+    params_vec.push(Box::new(limit as i64));
+    let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+
+    let mut stmt = conn.prepare(&sql)?;
+    let groups = stmt
+        .query_map(params_refs.as_slice(), |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, i64>(4)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to group error logs")?;
+
+    let mut clusters = Vec::with_capacity(groups.len());
+    for (signature, count, first_seen, last_seen, latest_id) in groups {
+        let latest = conn
+            .query_row(
+                "SELECT id, project, content, timestamp, log_type, service, stack, resolved_stack, format FROM logs WHERE id = ?1",
+                params![latest_id],
+                stored_entry_from_row,
+            )
+            .context("failed to load error cluster sample")?;
+        clusters.push(ErrorCluster {
+            signature,
+            count: count.max(0) as u64,
+            first_seen,
+            last_seen,
+            latest,
+        });
+    }
+    Ok(clusters)
+}
+
+fn stored_entry_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<StoredLogEntry> {
+    let content: String = row.get(2)?;
+    let stack: Option<String> = row.get(6)?;
+    let resolved_stack: Option<String> = row.get(7)?;
+    Ok(StoredLogEntry {
+        id: row.get(0)?,
+        entry: LogEntry {
+            project: row.get(1)?,
+            content: secret_redact::redact_text(&content),
+            timestamp: row.get(3)?,
+            log_type: row.get(4)?,
+            service: row.get(5)?,
+            stack: stack.map(|value| secret_redact::redact_text(&value)),
+            resolved_stack: resolved_stack.map(|value| secret_redact::redact_text(&value)),
+            format: row.get(8)?,
+        },
+    })
+}
+
+/// Whether an identical entry is already stored (used to make imports idempotent).
+pub fn log_exists(conn: &Connection, entry: &LogEntry) -> Result<bool> {
+    let sanitized = sanitize_entry(entry);
+    let found = conn
+        .query_row(
+            r#"
+            SELECT 1 FROM logs
+            WHERE project = ?1 AND timestamp = ?2 AND service = ?3 AND log_type = ?4
+              AND content = ?5
+            LIMIT 1
+            "#,
+            params![
+                sanitized.project,
+                sanitized.timestamp,
+                sanitized.service,
+                sanitized.log_type,
+                sanitized.content,
+            ],
+            |_| Ok(()),
+        )
+        .optional()
+        .context("failed to check for existing log")?;
+    Ok(found.is_some())
+}
+
 /// Get error logs for a project (convenience function).
 pub fn get_errors(conn: &Connection, project: &str, limit: usize) -> Result<Vec<StoredLogEntry>> {
     query_logs(
@@ -231,6 +465,10 @@ fn sanitize_entry(entry: &LogEntry) -> LogEntry {
             .stack
             .as_ref()
             .map(|value| secret_redact::redact_text(value)),
+        resolved_stack: entry
+            .resolved_stack
+            .as_ref()
+            .map(|value| secret_redact::redact_text(value)),
         format: entry.format.clone(),
     }
 }
@@ -251,6 +489,7 @@ mod tests {
             log_type: "log".to_string(),
             service: "web".to_string(),
             stack: None,
+            resolved_stack: None,
             format: "text".to_string(),
         };
 
@@ -282,6 +521,7 @@ mod tests {
             log_type: "log".to_string(),
             service: "api".to_string(),
             stack: None,
+            resolved_stack: None,
             format: "text".to_string(),
         };
 
@@ -292,6 +532,7 @@ mod tests {
             log_type: "error".to_string(),
             service: "api".to_string(),
             stack: Some("at main.rs:10".to_string()),
+            resolved_stack: None,
             format: "text".to_string(),
         };
 
@@ -302,4 +543,81 @@ mod tests {
         assert_eq!(errors.len(), 1);
         assert_eq!(errors[0].entry.log_type, "error");
     }
+
+    #[test]
+    fn clusters_errors_by_normalized_message() {
+        assert_eq!(
+            error_signature(
+                "panicked at src/worker.rs:42:9: job 7f3c2a10-1b2c-4d5e-8f90-a1b2c3d4e5f6 failed after 3 tries"
+            ),
+            "panicked at <path>:<n>:<n>: job <uuid> failed after <n> tries"
+        );
+
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let error = |content: &str, timestamp| LogEntry {
+            project: "test".to_string(),
+            content: content.to_string(),
+            timestamp,
+            log_type: "error".to_string(),
+            service: "api".to_string(),
+            stack: None,
+            resolved_stack: None,
+            format: "text".to_string(),
+        };
+        for i in 0..5 {
+            insert_log(
+                &conn,
+                &error(&format!("timeout after {i}ms on /tmp/job-{i}"), i),
+            )
+            .unwrap();
+        }
+        insert_log(&conn, &error("disk full", 2)).unwrap();
+
+        let clusters = error_clusters(&conn, Some("test"), None, 10).unwrap();
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].count, 5);
+        assert_eq!((clusters[0].first_seen, clusters[0].last_seen), (0, 4));
+        assert_eq!(
+            clusters[0].latest.entry.content,
+            "timeout after 4ms on /tmp/job-4"
+        );
+        assert_eq!(clusters[1].signature, "disk full");
+        assert_eq!(clusters[1].count, 1);
+    }
+
+    #[test]
+    fn search_matches_content_and_stack_literally() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let entry = |project: &str, content: &str, stack: Option<&str>| LogEntry {
+            project: project.to_string(),
+            content: content.to_string(),
+            timestamp: 1000,
+            log_type: "error".to_string(),
+            service: "api".to_string(),
+            stack: stack.map(str::to_string),
+            resolved_stack: None,
+            format: "text".to_string(),
+        };
+        insert_log(&conn, &entry("web", "Disk 100% full", None)).unwrap();
+        insert_log(
+            &conn,
+            &entry("web", "boom", Some("at checkout_cart (cart.ts:4)")),
+        )
+        .unwrap();
+        insert_log(&conn, &entry("other", "disk 100% full", None)).unwrap();
+
+        assert_eq!(search_logs(&conn, None, "disk 100%", 10).unwrap().len(), 2);
+        assert_eq!(
+            search_logs(&conn, Some("web"), "100% f", 10).unwrap().len(),
+            1
+        );
+        assert_eq!(
+            search_logs(&conn, Some("web"), "0_ful", 10).unwrap().len(),
+            0
+        );
+        let hits = search_logs(&conn, Some("web"), "checkout_cart", 10).unwrap();
+        assert_eq!(hits[0].entry.content, "boom");
+    }
 }
@@ -0,0 +1,523 @@
+//! Per-project ingest tokens for the log server.
+//!
+//! `f logs token create <project>` mints a token that clients send to `/logs/ingest` as
+//! `Authorization: Bearer <token>`. Entries ingested with a token are scoped to its project,
+//! and each token has its own rate limit (entries per minute) and daily byte quota so one
+//! runaway service cannot flood the shared log store. Only a SHA-256 hash of the token is
+//! stored; usage counters live in the server's memory.
+
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::{Context, Result, bail};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::cli::{LogsTokenAction, LogsTokenCommand, LogsTokenCreateOpts, LogsTokenRevokeOpts};
+use crate::log_store::{self, LogEntry};
+use crate::{proxy, running};
+
+/// Prefix that makes ingest tokens easy to spot (and redact) in config and shell history.
+pub const TOKEN_PREFIX: &str = "flog_";
+pub const DEFAULT_RATE_PER_MIN: u32 = 6000;
+pub const DEFAULT_DAILY_QUOTA: &str = "256MB";
+
+const MINUTE_MS: i64 = 60_000;
+const DAY_MS: i64 = 86_400_000;
+
+/// A stored ingest token (without the secret).
+#[derive(Debug, Clone, Serialize)]
+pub struct IngestToken {
+    pub id: String,
+    pub project: String,
+    pub rate_per_min: u32,
+    pub daily_bytes: u64,
+    pub created_ms: i64,
+}
+
+/// Initialize the log_tokens table schema.
+pub fn init_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS log_tokens (
+            id TEXT PRIMARY KEY,
+            token_hash TEXT NOT NULL UNIQUE,
+            project TEXT NOT NULL,
+            rate_per_min INTEGER NOT NULL,
+            daily_bytes INTEGER NOT NULL,
+            created_ms INTEGER NOT NULL,
+            revoked_ms INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_log_tokens_project ON log_tokens(project);
+        "#,
+    )
+    .context("failed to create log_tokens schema")?;
+    Ok(())
+}
+
+fn hash_token(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+/// Create a token for `project`. Returns the stored record and the secret, which is not
+/// recoverable afterwards.
+pub fn create(
+    conn: &Connection,
+    project: &str,
+    rate_per_min: u32,
+    daily_bytes: u64,
+) -> Result<(IngestToken, String)> {
+    let project = project.trim();
+    if project.is_empty() {
+        bail!("project name is required");
+    }
+    let secret = format!(
+        "{TOKEN_PREFIX}{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    );
+    let hash = hash_token(&secret);
+    let token = IngestToken {
+        id: hash[..12].to_string(),
+        project: project.to_string(),
+        rate_per_min,
+        daily_bytes,
+        created_ms: running::now_ms() as i64,
+    };
+    conn.execute(
+        r#"
+        INSERT INTO log_tokens (id, token_hash, project, rate_per_min, daily_bytes, created_ms)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        "#,
+        params![
+            token.id,
+            hash,
+            token.project,
+            token.rate_per_min,
+            token.daily_bytes as i64,
+            token.created_ms,
+        ],
+    )
+    .context("failed to store ingest token")?;
+    Ok((token, secret))
+}
+
+fn token_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<IngestToken> {
+    Ok(IngestToken {
+        id: row.get(0)?,
+        project: row.get(1)?,
+        rate_per_min: row.get(2)?,
+        daily_bytes: row.get::<_, i64>(3)?.max(0) as u64,
+        created_ms: row.get(4)?,
+    })
+}
+
+/// Look up an active token by its secret.
+pub fn lookup(conn: &Connection, secret: &str) -> Result<Option<IngestToken>> {
+    conn.query_row(
+        r#"
+        SELECT id, project, rate_per_min, daily_bytes, created_ms FROM log_tokens
+        WHERE token_hash = ?1 AND revoked_ms IS NULL
+        "#,
+        params![hash_token(secret.trim())],
+        token_from_row,
+    )
+    .optional()
+    .context("failed to look up ingest token")
+}
+
+/// Whether `project` has any active token (and therefore rejects unauthenticated ingest).
+pub fn project_requires_token(conn: &Connection, project: &str) -> Result<bool> {
+    let found = conn
+        .query_row(
+            "SELECT 1 FROM log_tokens WHERE project = ?1 AND revoked_ms IS NULL LIMIT 1",
+            params![project],
+            |_| Ok(()),
+        )
+        .optional()
+        .context("failed to check ingest tokens")?;
+    Ok(found.is_some())
+}
+
+/// All active tokens, newest first.
+pub fn list(conn: &Connection) -> Result<Vec<IngestToken>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, project, rate_per_min, daily_bytes, created_ms FROM log_tokens
+        WHERE revoked_ms IS NULL
+        ORDER BY created_ms DESC
+        "#,
+    )?;
+    let rows = stmt.query_map([], token_from_row)?;
+    let mut tokens = Vec::new();
+    for row in rows {
+        tokens.push(row?);
+    }
+    Ok(tokens)
+}
+
+/// Which projects a read request (dashboard, event bus) may see.
+#[derive(Debug)]
+pub enum Scope {
+    /// A valid token: only its project.
+    Token(String),
+    /// No token: everything except projects guarded by an active token.
+    Open { guarded: BTreeSet<String> },
+}
+
+impl Scope {
+    pub fn allows(&self, project: Option<&str>) -> bool {
+        match (self, project) {
+            (Scope::Token(scoped), project) => project == Some(scoped.as_str()),
+            (Scope::Open { guarded }, Some(project)) => !guarded.contains(project),
+            (Scope::Open { .. }, None) => true,
+        }
+    }
+}
+
+/// Scope of a request that sent `bearer`. `None` means the token is invalid or revoked.
+pub fn scope(conn: &Connection, bearer: Option<&str>) -> Result<Option<Scope>> {
+    Ok(match bearer {
+        Some(secret) => lookup(conn, secret)?.map(|token| Scope::Token(token.project)),
+        None => Some(Scope::Open {
+            guarded: list(conn)?.into_iter().map(|token| token.project).collect(),
+        }),
+    })
+}
+
+/// Revoke a token by id (or unique id prefix). Returns the revoked token.
+pub fn revoke(conn: &Connection, id: &str) -> Result<IngestToken> {
+    let id = id.trim();
+    if id.is_empty() {
+        bail!("token id is required");
+    }
+    let matches: Vec<IngestToken> = list(conn)?
+        .into_iter()
+        .filter(|token| token.id.starts_with(id))
+        .collect();
+    let token = match matches.as_slice() {
+        [token] => token.clone(),
+        [] => bail!("no active ingest token matches '{}'", id),
+        _ => bail!("'{}' matches {} tokens; use a longer id", id, matches.len()),
+    };
+    conn.execute(
+        "UPDATE log_tokens SET revoked_ms = ?1 WHERE id = ?2",
+        params![running::now_ms() as i64, token.id],
+    )
+    .context("failed to revoke ingest token")?;
+    Ok(token)
+}
+
+#[derive(Debug, Default)]
+struct Usage {
+    minute_start_ms: i64,
+    minute_entries: u64,
+    day_start_ms: i64,
+    day_bytes: u64,
+}
+
+/// In-memory per-token usage counters using fixed one-minute and UTC-day windows.
+#[derive(Debug, Default)]
+pub struct QuotaTracker {
+    usage: HashMap<String, Usage>,
+}
+
+/// Result of checking an ingest request against tokens and quotas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IngestDecision {
+    Allow,
+    Unauthorized(String),
+    RateLimited {
+        message: String,
+        retry_after_secs: u64,
+    },
+    QuotaExceeded(String),
+    /// The batch alone is over a limit, so retrying it can never succeed.
+    TooLarge {
+        message: String,
+        limit: u64,
+    },
+}
+
+impl QuotaTracker {
+    /// Charge `entries`/`bytes` to the token if both limits allow it; a rejected batch is not
+    /// counted.
+    pub fn charge(
+        &mut self,
+        token: &IngestToken,
+        entries: u64,
+        bytes: u64,
+        now_ms: i64,
+    ) -> IngestDecision {
+        if entries > token.rate_per_min as u64 {
+            return IngestDecision::TooLarge {
+                message: format!(
+                    "batch of {entries} entries is over the limit of {} entries/min for project '{}'; send smaller batches",
+                    token.rate_per_min, token.project
+                ),
+                limit: token.rate_per_min as u64,
+            };
+        }
+        if bytes > token.daily_bytes {
+            return IngestDecision::TooLarge {
+                message: format!(
+                    "batch of {bytes} bytes is over the daily quota of {} bytes for project '{}'",
+                    token.daily_bytes, token.project
+                ),
+                limit: token.daily_bytes,
+            };
+        }
+        let usage = self.usage.entry(token.id.clone()).or_default();
+        let minute_start = now_ms - now_ms.rem_euclid(MINUTE_MS);
+        if usage.minute_start_ms != minute_start {
+            usage.minute_start_ms = minute_start;
+            usage.minute_entries = 0;
+        }
+        let day_start = now_ms - now_ms.rem_euclid(DAY_MS);
+        if usage.day_start_ms != day_start {
+            usage.day_start_ms = day_start;
+            usage.day_bytes = 0;
+        }
+
+        if usage.minute_entries + entries > token.rate_per_min as u64 {
+            let retry_after_secs = ((minute_start + MINUTE_MS - now_ms) as u64).div_ceil(1000);
+            return IngestDecision::RateLimited {
+                message: format!(
+                    "rate limit of {} entries/min exceeded for project '{}'",
+                    token.rate_per_min, token.project
+                ),
+                retry_after_secs: retry_after_secs.max(1),
+            };
+        }
+        if usage.day_bytes + bytes > token.daily_bytes {
+            return IngestDecision::QuotaExceeded(format!(
+                "daily quota of {} bytes exhausted for project '{}'",
+                token.daily_bytes, token.project
+            ));
+        }
+        usage.minute_entries += entries;
+        usage.day_bytes += bytes;
+        IngestDecision::Allow
+    }
+}
+
+fn entry_bytes(entry: &LogEntry) -> u64 {
+    (entry.content.len() + entry.stack.as_ref().map_or(0, String::len)) as u64
+}
+
+/// Authorize an ingest request. With a bearer token, entries are rewritten to the token's
+/// project and charged against its limits. Without one, ingest is only allowed for projects
+/// that have no active token.
+pub fn authorize(
+    conn: &Connection,
+    tracker: &mut QuotaTracker,
+    bearer: Option<&str>,
+    entries: &mut [LogEntry],
+    now_ms: i64,
+) -> Result<IngestDecision> {
+    let Some(secret) = bearer else {
+        let projects: BTreeSet<&str> = entries.iter().map(|e| e.project.as_str()).collect();
+        for project in projects {
+            if project_requires_token(conn, project)? {
+                return Ok(IngestDecision::Unauthorized(format!(
+                    "project '{project}' requires an ingest token"
+                )));
+            }
+        }
+        return Ok(IngestDecision::Allow);
+    };
+
+    let Some(token) = lookup(conn, secret)? else {
+        return Ok(IngestDecision::Unauthorized(
+            "invalid or revoked ingest token".to_string(),
+        ));
+    };
+    for entry in entries.iter_mut() {
+        entry.project.clone_from(&token.project);
+    }
+    let bytes = entries.iter().map(entry_bytes).sum();
+    Ok(tracker.charge(&token, entries.len() as u64, bytes, now_ms))
+}
+
+pub fn run(cmd: LogsTokenCommand) -> Result<()> {
+    let conn = log_store::open_log_db()?;
+    init_schema(&conn)?;
+    match cmd.action {
+        LogsTokenAction::Create(opts) => create_command(&conn, opts),
+        LogsTokenAction::List => list_command(&conn),
+        LogsTokenAction::Revoke(opts) => revoke_command(&conn, opts),
+    }
+}
+
+fn create_command(conn: &Connection, opts: LogsTokenCreateOpts) -> Result<()> {
+    if opts.rate == 0 {
+        bail!("--rate must be greater than zero");
+    }
+    let daily_bytes = proxy::parse_size(&opts.quota) as u64;
+    if daily_bytes == 0 {
+        bail!(
+            "invalid --quota '{}' (expected e.g. 512KB, 64MB, 1GB)",
+            opts.quota
+        );
+    }
+    let (token, secret) = create(conn, &opts.project, opts.rate, daily_bytes)?;
+    println!("Created ingest token {} for {}", token.id, token.project);
+    println!(
+        "Limits: {} entries/min, {} bytes/day",
+        token.rate_per_min, token.daily_bytes
+    );
+    println!();
+    println!("  {secret}");
+    println!();
+    println!(
+        "This is the only time the token is shown. Send it as `Authorization: Bearer <token>`,"
+    );
+    println!("or export FLOW_LOG_TOKEN so `f` task runs forward logs with it.");
+    Ok(())
+}
+
+fn list_command(conn: &Connection) -> Result<()> {
+    let tokens = list(conn)?;
+    if tokens.is_empty() {
+        println!("No ingest tokens. Create one with `f logs token create <project>`.");
+        return Ok(());
+    }
+    println!(
+        "{:<12}  {:<24}  {:>10}  {:>12}",
+        "id", "project", "rate/min", "bytes/day"
+    );
+    for token in tokens {
+        println!(
+            "{:<12}  {:<24}  {:>10}  {:>12}",
+            token.id, token.project, token.rate_per_min, token.daily_bytes
+        );
+    }
+    Ok(())
+}
+
+fn revoke_command(conn: &Connection, opts: LogsTokenRevokeOpts) -> Result<()> {
+    let token = revoke(conn, &opts.id)?;
+    println!("Revoked ingest token {} for {}", token.id, token.project);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        log_store::init_schema(&conn).unwrap();
+        init_schema(&conn).unwrap();
+        conn
+    }
+
+    fn entry(project: &str, content: &str) -> LogEntry {
+        LogEntry {
+            project: project.to_string(),
+            content: content.to_string(),
+            timestamp: 1000,
+            log_type: "log".to_string(),
+            service: "web".to_string(),
+            stack: None,
+            resolved_stack: None,
+            format: "text".to_string(),
+        }
+    }
+
+    #[test]
+    fn token_scope_only_allows_its_project() {
+        let token = Scope::Token("web".to_string());
+        assert!(token.allows(Some("web")));
+        assert!(!token.allows(Some("api")));
+        assert!(!token.allows(None));
+
+        let open = Scope::Open {
+            guarded: BTreeSet::from(["web".to_string()]),
+        };
+        assert!(!open.allows(Some("web")));
+        assert!(open.allows(Some("api")));
+        assert!(open.allows(None));
+    }
+
+    #[test]
+    fn scope_follows_the_bearer_token() {
+        let conn = setup();
+        let (_, secret) = create(&conn, "web", 100, 1024).unwrap();
+
+        let scoped = scope(&conn, Some(&secret)).unwrap().unwrap();
+        assert!(scoped.allows(Some("web")));
+        assert!(!scoped.allows(Some("api")));
+        assert!(scope(&conn, Some("flow_log_bogus")).unwrap().is_none());
+        let open = scope(&conn, None).unwrap().unwrap();
+        assert!(!open.allows(Some("web")));
+        assert!(open.allows(Some("api")));
+    }
+
+    #[test]
+    fn token_scopes_entries_to_its_project() {
+        let conn = setup();
+        let (_, secret) = create(&conn, "api", 100, 1024).unwrap();
+        let mut tracker = QuotaTracker::default();
+        let mut entries = vec![entry("other", "hello")];
+
+        let decision = authorize(&conn, &mut tracker, Some(&secret), &mut entries, 0).unwrap();
+        assert_eq!(decision, IngestDecision::Allow);
+        assert_eq!(entries[0].project, "api");
+
+        let decision = authorize(&conn, &mut tracker, None, &mut [entry("api", "x")], 0).unwrap();
+        assert!(matches!(decision, IngestDecision::Unauthorized(_)));
+        let decision = authorize(&conn, &mut tracker, None, &mut [entry("web", "x")], 0).unwrap();
+        assert_eq!(decision, IngestDecision::Allow);
+    }
+
+    #[test]
+    fn revoked_tokens_are_rejected() {
+        let conn = setup();
+        let (token, secret) = create(&conn, "api", 100, 1024).unwrap();
+        revoke(&conn, &token.id[..6]).unwrap();
+        assert!(lookup(&conn, &secret).unwrap().is_none());
+        assert!(!project_requires_token(&conn, "api").unwrap());
+    }
+
+    #[test]
+    fn rate_limit_and_quota_are_enforced_per_window() {
+        let conn = setup();
+        let (token, _) = create(&conn, "api", 2, 10).unwrap();
+        let mut tracker = QuotaTracker::default();
+
+        assert_eq!(tracker.charge(&token, 2, 4, 1_000), IngestDecision::Allow);
+        assert!(matches!(
+            tracker.charge(&token, 1, 1, 2_000),
+            IngestDecision::RateLimited {
+                retry_after_secs: 58,
+                ..
+            }
+        ));
+        // Next minute: rate resets but the daily byte quota does not.
+        assert_eq!(tracker.charge(&token, 1, 6, 61_000), IngestDecision::Allow);
+        assert!(matches!(
+            tracker.charge(&token, 1, 1, 62_000),
+            IngestDecision::QuotaExceeded(_)
+        ));
+        assert_eq!(tracker.charge(&token, 1, 1, DAY_MS), IngestDecision::Allow);
+    }
+
+    #[test]
+    fn batches_over_a_limit_on_their_own_are_too_large() {
+        let conn = setup();
+        let (token, _) = create(&conn, "api", 2, 10).unwrap();
+        let mut tracker = QuotaTracker::default();
+
+        assert!(matches!(
+            tracker.charge(&token, 3, 1, 0),
+            IngestDecision::TooLarge { limit: 2, .. }
+        ));
+        assert!(matches!(
+            tracker.charge(&token, 1, 11, 0),
+            IngestDecision::TooLarge { limit: 10, .. }
+        ));
+        // Rejected batches are not counted.
+        assert_eq!(tracker.charge(&token, 2, 10, 0), IngestDecision::Allow);
+    }
+}
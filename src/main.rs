@@ -7,15 +7,18 @@ use clap::{CommandFactory, Parser, error::ErrorKind};
 use flowd::{
     agents, ai, ai_test, analytics, archive, auth, branches, changes,
     cli::{
-        Cli, Commands, InstallAction, ProxyAction, ProxyCommand, RerunOpts, ReviewAction,
-        ShellAction, ShellCommand, TaskRunOpts, TasksOpts, TraceAction,
+        Cli, Commands, CommitAction, InstallAction, LogsAction, ProjectsAction, ProxyAction,
+        ProxyCommand, RerunOpts, ReviewAction, ShellAction, ShellCommand, TaskRunOpts, TasksOpts,
+        TraceAction,
     },
-    code, commit, commits, daemon, deploy, deps, docs, doctor, domains, env, explain_commits, ext,
-    external_cli, failure, fish_install, fish_trace, fix, fixup, flow_config, git_guard,
+    code, commit, commit_signing, commit_split, commits, completions, daemon, deploy, deps,
+    diagnose, discover_github, docs, doctor, domains, env, explain_commits, ext, external_cli,
+    failure, find, fish_install, fish_trace, fix, fixup, flow_config, git_guard, git_hooks,
     gitignore_policy, hash, health, help_search, history, hive, home, hub, info, init,
-    init_tracing, install, invariants, jj, latest, lifecycle, log_server, macos, notify, otp,
-    palette, parallel, processes, projects, proxy, publish, push, recipe, registry, release, repos,
-    reviews_todo, seq_rpc, services, setup, skills, ssh_keys, storage, supervisor, sync,
+    init_tracing, install, invariants, jj, latest, lifecycle, log_bundle, log_server, log_tokens,
+    macos, notify, otp, palette, parallel, plugins, processes, project_selector, project_status,
+    project_usage, projects, proxy, publish, push, recipe, registry, release, repos, reviews_todo,
+    runs, seq_rpc, services, setup, skills, source_maps, ssh_keys, storage, supervisor, sync, tail,
     task_match, tasks, todo, tools, traces, undo, updates, upgrade, upstream, url_inspect, usage,
     web,
 };
@@ -44,12 +47,15 @@ impl StartupPolicy {
 fn main() -> Result<()> {
     init_tracing();
 
-    let raw_args: Vec<String> = std::env::args().collect();
+    let mut raw_args: Vec<String> = std::env::args().collect();
+    let project_selected = project_selector::apply(&mut raw_args);
     let analytics_capture = usage::command_capture(&raw_args);
     let is_analytics_command = usage::is_analytics_command(&raw_args);
     let started_at = Instant::now();
 
     let result = (|| -> Result<()> {
+        project_selected?;
+
         // Handle `f ?` for fuzzy help search before clap parsing
         if raw_args.get(1).map(|s| s.as_str()) == Some("?") {
             return help_search::run();
@@ -175,7 +181,19 @@ fn main() -> Result<()> {
                 processes::kill_processes(opts)?;
             }
             Some(Commands::Logs(opts)) => {
-                processes::show_task_logs(opts)?;
+                if let Some(action) = opts.action {
+                    match action {
+                        LogsAction::Export(opts) => log_bundle::export(opts)?,
+                        LogsAction::Import(opts) => log_bundle::import(opts)?,
+                        LogsAction::Token(cmd) => log_tokens::run(cmd)?,
+                        LogsAction::Errors(opts) => source_maps::show_errors(opts)?,
+                    }
+                } else {
+                    processes::show_task_logs(opts)?;
+                }
+            }
+            Some(Commands::Tail(opts)) => {
+                tail::run(opts)?;
             }
             Some(Commands::Trace(cmd)) => {
                 if let Some(action) = cmd.action {
@@ -191,9 +209,22 @@ fn main() -> Result<()> {
             Some(Commands::Failure(cmd)) => {
                 failure::run_cli(cmd)?;
             }
-            Some(Commands::Projects) => {
-                projects::show_projects()?;
+            Some(Commands::Diagnose(cmd)) => {
+                diagnose::run(cmd)?;
             }
+            Some(Commands::Runs(cmd)) => {
+                runs::run(cmd)?;
+            }
+            Some(Commands::Find(opts)) => {
+                find::run(opts)?;
+            }
+            Some(Commands::Completions(opts)) => {
+                completions::run(opts)?;
+            }
+            Some(Commands::Projects(cmd)) => match cmd.action {
+                Some(ProjectsAction::Usage(opts)) => project_usage::show_usage(opts)?,
+                None => projects::show_projects()?,
+            },
             Some(Commands::Sessions(opts)) => {
                 ai::run_sessions(&opts)?;
             }
@@ -219,15 +250,32 @@ fn main() -> Result<()> {
                     args: opts.query,
                     model: opts.model,
                     url: opts.url,
+                    use_index: !opts.no_index,
+                    reindex: opts.reindex,
+                    embed_model: opts.embed_model,
+                    lmstudio_port: Some(opts.lmstudio_port),
                 })?;
             }
             Some(Commands::Branches(cmd)) => {
                 branches::run(cmd)?;
             }
             Some(Commands::Status(opts)) => {
-                jj::run_workflow_status(opts.raw, opts.compact)?;
+                if opts.project {
+                    project_status::run(opts.json, opts.watch)?;
+                } else {
+                    jj::run_workflow_status(opts.raw, opts.compact)?;
+                }
             }
             Some(Commands::Commit(opts)) => {
+                if opts.verify_secrets {
+                    unsafe {
+                        std::env::set_var("FLOW_VERIFY_SECRETS", "1");
+                    }
+                }
+                if let Some(action) = opts.action.clone() {
+                    run_commit_action(action)?;
+                    return Ok(());
+                }
                 if let Some(hash) = opts.commit_lookup_hash() {
                     commit::open_commit_in_cursor(hash)?;
                     return Ok(());
@@ -363,7 +411,17 @@ fn main() -> Result<()> {
             Some(Commands::Jj(cmd)) => {
                 jj::run(cmd)?;
             }
+            Some(Commands::CommitMsgHook(opts)) => {
+                commit::run_commit_msg_hook(opts)?;
+            }
+            Some(Commands::PreCommitHook) => {
+                commit::run_pre_commit_hook()?;
+            }
             Some(Commands::CommitSimple(opts)) => {
+                if let Some(action) = opts.action.clone() {
+                    run_commit_action(action)?;
+                    return Ok(());
+                }
                 // Simple commit without review - always sync (fast, no hub)
                 let mut force = opts.force || opts.approved;
                 let mut open_review = opts.review;
@@ -387,6 +445,10 @@ fn main() -> Result<()> {
                 commit::run_sync(push, queue, opts.hashed, &opts.paths)?;
             }
             Some(Commands::CommitWithCheck(opts)) => {
+                if let Some(action) = opts.action.clone() {
+                    run_commit_action(action)?;
+                    return Ok(());
+                }
                 // Review but no gitedit sync
                 let mut force = opts.force || opts.approved;
                 let mut open_review = opts.review;
@@ -570,6 +632,9 @@ fn main() -> Result<()> {
             Some(Commands::Push(cmd)) => {
                 push::run(cmd)?;
             }
+            Some(Commands::Hooks(cmd)) => {
+                git_hooks::run(cmd)?;
+            }
             Some(Commands::Info) => {
                 info::run()?;
             }
@@ -591,6 +656,9 @@ fn main() -> Result<()> {
             Some(Commands::Repos(cmd)) => {
                 repos::run(cmd)?;
             }
+            Some(Commands::Discover(cmd)) => {
+                discover_github::run(cmd)?;
+            }
             Some(Commands::Code(cmd)) => {
                 code::run(cmd)?;
             }
@@ -706,7 +774,8 @@ fn startup_policy_for(command: Option<&Commands>) -> StartupPolicy {
         Some(Commands::Doctor(_)) => StartupPolicy::NONE,
         Some(Commands::Health(_)) => StartupPolicy::NONE,
         Some(Commands::Invariants(_)) => StartupPolicy::NONE,
-        Some(Commands::Projects) => StartupPolicy::NONE,
+        Some(Commands::Projects(_)) => StartupPolicy::NONE,
+        Some(Commands::Completions(_)) => StartupPolicy::NONE,
         Some(Commands::Active(_)) => StartupPolicy::NONE,
         Some(Commands::LastCmd) => StartupPolicy::NONE,
         Some(Commands::LastCmdFull) => StartupPolicy::NONE,
@@ -714,9 +783,16 @@ fn startup_policy_for(command: Option<&Commands>) -> StartupPolicy {
         Some(Commands::FishLastFull) => StartupPolicy::NONE,
         Some(Commands::FishInstall(_)) => StartupPolicy::NONE,
         Some(Commands::Ps(_)) => StartupPolicy::NONE,
+        Some(Commands::CommitMsgHook(_)) => StartupPolicy::NONE,
+        Some(Commands::PreCommitHook) => StartupPolicy::NONE,
+        Some(Commands::Hooks(_)) => StartupPolicy::NONE,
         Some(Commands::Logs(_)) => StartupPolicy::NONE,
+        Some(Commands::Tail(_)) => StartupPolicy::NONE,
         Some(Commands::Trace(_)) => StartupPolicy::NONE,
         Some(Commands::Failure(_)) => StartupPolicy::NONE,
+        Some(Commands::Diagnose(_)) => StartupPolicy::NONE,
+        Some(Commands::Runs(_)) => StartupPolicy::NONE,
+        Some(Commands::Find(_)) => StartupPolicy::NONE,
         Some(Commands::Branches(_)) => StartupPolicy::NONE,
         Some(Commands::Status(_)) => StartupPolicy::NONE,
         Some(Commands::Changes(_)) => StartupPolicy::NONE,
@@ -753,7 +829,11 @@ fn startup_policy_for(command: Option<&Commands>) -> StartupPolicy {
             | Some(TasksAction::List(_))
             | Some(TasksAction::Dupes(_))
             | Some(TasksAction::InitAi(_))
-            | Some(TasksAction::Daemon(_)) => StartupPolicy::NONE,
+            | Some(TasksAction::Daemon(_))
+            | Some(TasksAction::ClearCache(_))
+            | Some(TasksAction::Graph(_))
+            | Some(TasksAction::Stats(_))
+            | Some(TasksAction::Serve(_)) => StartupPolicy::NONE,
             Some(TasksAction::BuildAi(_)) | Some(TasksAction::RunAi(_)) => {
                 StartupPolicy::SECRETS_ONLY
             }
@@ -774,9 +854,11 @@ fn startup_policy_for(command: Option<&Commands>) -> StartupPolicy {
         Some(Commands::Proxy(cmd)) => match &cmd.action {
             ProxyAction::Trace(_)
             | ProxyAction::Last(_)
+            | ProxyAction::Export(_)
             | ProxyAction::Add(_)
             | ProxyAction::List
-            | ProxyAction::Stop => StartupPolicy::NONE,
+            | ProxyAction::Stop
+            | ProxyAction::Domains(_) => StartupPolicy::NONE,
             ProxyAction::Start(_) => StartupPolicy::SECRETS_ONLY,
         },
         Some(Commands::Repos(cmd)) => match cmd.action.as_ref() {
@@ -840,6 +922,7 @@ fn startup_policy_for(command: Option<&Commands>) -> StartupPolicy {
         | Some(Commands::Prod(_))
         | Some(Commands::Publish(_))
         | Some(Commands::Clone(_))
+        | Some(Commands::Discover(_))
         | Some(Commands::TaskShortcut(_))
         | Some(Commands::Agents(_))
         | Some(Commands::Hive(_)) => StartupPolicy::SECRETS_ONLY,
@@ -847,6 +930,13 @@ fn startup_policy_for(command: Option<&Commands>) -> StartupPolicy {
     }
 }
 
+fn run_commit_action(action: CommitAction) -> Result<()> {
+    match action {
+        CommitAction::Signing(cmd) => commit_signing::run(cmd),
+        CommitAction::Split(opts) => commit_split::run(opts),
+    }
+}
+
 fn rerun(opts: RerunOpts) -> Result<()> {
     let project_root = if opts.config.is_absolute() {
         opts.config.parent().unwrap_or(Path::new(".")).to_path_buf()
@@ -871,6 +961,9 @@ fn rerun(opts: RerunOpts) -> Result<()> {
         delegate_to_hub: false,
         hub_host: IpAddr::from([127, 0, 0, 1]),
         hub_port: 9050,
+        priority: 0,
+        print_env: false,
+        yes: false,
         name: task_name,
         args,
     })
@@ -881,18 +974,40 @@ fn is_task_not_found(err: &anyhow::Error) -> bool {
     msg.contains("task '") && msg.contains("not found")
 }
 
-fn run_cli_frontdoor(args: Vec<String>) -> Result<()> {
+fn is_no_tasks(err: &anyhow::Error) -> bool {
+    err.to_string().starts_with("No tasks defined")
+}
+
+/// Tasks first, then `flow-<name>` plugins. An unknown name fails with suggestions; only
+/// `--force-task` falls through to implicit task matching, which may run a different task.
+fn run_cli_frontdoor(mut args: Vec<String>) -> Result<()> {
+    let force_task = take_force_task_flag(&mut args);
     let Some(task_name) = args.first() else {
         bail!("no task name provided");
     };
 
     match tasks::run_with_discovery(task_name, args[1..].to_vec()) {
         Ok(()) => Ok(()),
-        Err(err) if is_task_not_found(&err) && args.len() > 1 => task_match::run_implicit(args),
+        Err(err) if is_task_not_found(&err) || is_no_tasks(&err) => {
+            if let Some(plugin) = plugins::find_executable(task_name) {
+                return plugins::run_external(task_name, &plugin, &args[1..]);
+            }
+            if force_task && is_task_not_found(&err) && args.len() > 1 {
+                return task_match::run_implicit(args);
+            }
+            Err(err)
+        }
         Err(err) => Err(err),
     }
 }
 
+/// Remove `--force-task` from fallback arguments, returning whether it was present.
+fn take_force_task_flag(args: &mut Vec<String>) -> bool {
+    let before = args.len();
+    args.retain(|arg| arg != "--force-task");
+    args.len() != before
+}
+
 fn shell_command(cmd: ShellCommand) {
     match cmd.action.unwrap_or(ShellAction::Reset) {
         ShellAction::Reset => {
@@ -1045,7 +1160,7 @@ fn proxy_command(cmd: ProxyCommand) -> Result<()> {
             let proxy_config = config.proxy.unwrap_or_default();
             let targets = config.proxies;
 
-            if targets.is_empty() {
+            if targets.is_empty() && !proxy_config.auto_register {
                 bail!("No proxy targets configured. Add [[proxies]] to flow.toml");
             }
 
@@ -1069,6 +1184,13 @@ fn proxy_command(cmd: ProxyCommand) -> Result<()> {
         ProxyAction::Last(_opts) => {
             proxy::trace_last(1)?;
         }
+        ProxyAction::Export(opts) => {
+            // Target names are optional here; without a config the HAR uses target indexes.
+            let targets = load_project_config()
+                .map(|config| config.proxies)
+                .unwrap_or_default();
+            proxy::har::export(opts, &targets)?;
+        }
         ProxyAction::Add(opts) => {
             println!("To add a proxy, edit flow.toml:");
             println!();
@@ -1086,6 +1208,17 @@ fn proxy_command(cmd: ProxyCommand) -> Result<()> {
             }
         }
         ProxyAction::List => {
+            let socket = proxy::control::default_socket_path();
+            if let Ok(live) = proxy::control::send(&socket, &proxy::control::ControlRequest::List)
+                && !live.routes.is_empty()
+            {
+                println!("{:<25} {:<35} {:<15}", "NAME", "HOST", "TARGET");
+                println!("{}", "-".repeat(75));
+                for route in &live.routes {
+                    println!("{:<25} {:<35} {:<15}", route.name, route.host, route.addr);
+                }
+                println!();
+            }
             let config = load_project_config()?;
             if config.proxies.is_empty() {
                 println!("No proxy targets configured.");
@@ -1110,6 +1243,9 @@ fn proxy_command(cmd: ProxyCommand) -> Result<()> {
         ProxyAction::Stop => {
             println!("Proxy stop not implemented yet. Use Ctrl+C or kill the process.");
         }
+        ProxyAction::Domains(cmd) => {
+            proxy::hosts::run(cmd)?;
+        }
     }
     Ok(())
 }
@@ -1118,7 +1254,7 @@ fn proxy_command(cmd: ProxyCommand) -> Result<()> {
 mod tests {
     use std::path::PathBuf;
 
-    use super::{StartupPolicy, is_task_not_found, startup_policy_for};
+    use super::{StartupPolicy, is_task_not_found, startup_policy_for, take_force_task_flag};
     use flowd::cli::{
         AiAction, AiCommand, AnalyticsCommand, Commands, GlobalAction, GlobalCommand,
         RepoAliasAction, RepoAliasCommand, RepoCapsuleOpts, ReposAction, ReposCommand,
@@ -1257,6 +1393,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn force_task_flag_is_stripped_from_fallback_args() {
+        let mut args = vec![
+            "--force-task".to_string(),
+            "deploy".to_string(),
+            "prod".to_string(),
+        ];
+        assert!(take_force_task_flag(&mut args));
+        assert_eq!(args, vec!["deploy".to_string(), "prod".to_string()]);
+        assert!(!take_force_task_flag(&mut args));
+    }
+
     #[test]
     fn task_not_found_detection_is_specific() {
         assert!(is_task_not_found(&anyhow::anyhow!(
@@ -0,0 +1,585 @@
+//! Monorepo workspace detection for `f setup` and `f init`.
+//!
+//! Detects Cargo workspace members and `pnpm-workspace.yaml` packages and renders per-package
+//! tasks named `<package>:<action>` (e.g. `web:dev`, `api:test`) plus root aggregate tasks.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::setup;
+
+/// Max directory depth walked when expanding `**` member globs.
+const MAX_GLOB_DEPTH: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageKind {
+    Cargo,
+    Pnpm,
+}
+
+/// A member package of a detected workspace.
+#[derive(Debug, Clone)]
+pub struct WorkspacePackage {
+    /// Task prefix (package name without npm scope).
+    pub name: String,
+    /// Directory relative to the workspace root, `/`-separated.
+    pub dir: String,
+    pub kind: PackageKind,
+    /// Actions available for this package (`dev`, `build`, `test`).
+    pub actions: Vec<&'static str>,
+}
+
+/// A detected Cargo and/or pnpm workspace.
+#[derive(Debug, Clone, Default)]
+pub struct Workspace {
+    pub cargo: bool,
+    pub pnpm: bool,
+    pub packages: Vec<WorkspacePackage>,
+}
+
+impl Workspace {
+    fn has_action(&self, kind: PackageKind, action: &str) -> bool {
+        self.packages
+            .iter()
+            .any(|pkg| pkg.kind == kind && pkg.actions.contains(&action))
+    }
+}
+
+/// Detect a Cargo or pnpm workspace at `root`. Returns `None` when neither has members.
+pub fn detect(root: &Path) -> Option<Workspace> {
+    let mut workspace = Workspace::default();
+
+    if let Some(patterns) = cargo_workspace_members(root) {
+        workspace.cargo = true;
+        for dir in expand_members(root, &patterns, "Cargo.toml") {
+            if let Some(pkg) = cargo_package(root, &dir) {
+                workspace.packages.push(pkg);
+            }
+        }
+    }
+
+    if let Some(patterns) = pnpm_workspace_packages(root) {
+        workspace.pnpm = true;
+        for dir in expand_members(root, &patterns, "package.json") {
+            if let Some(pkg) = pnpm_package(root, &dir) {
+                workspace.packages.push(pkg);
+            }
+        }
+    }
+
+    if workspace.packages.is_empty() {
+        return None;
+    }
+    dedupe_names(&mut workspace.packages);
+    Some(workspace)
+}
+
+/// Render a flow.toml body (version, tasks, `[deps]`) for the workspace.
+pub fn render_flow_toml(workspace: &Workspace) -> String {
+    let mut out = String::from("version = 1\n\n");
+    out.push_str(&render_tasks(workspace));
+    out.push_str("\n[deps]\n");
+    for dep in tool_deps(workspace) {
+        out.push_str(&format!("{dep} = \"{dep}\"\n"));
+    }
+    out
+}
+
+/// Render `[[tasks]]` blocks for the workspace: root `setup`/`dev`/`build`/`test` aggregates
+/// followed by per-package `<package>:<action>` tasks.
+fn render_tasks(workspace: &Workspace) -> String {
+    let mut out = String::new();
+
+    let mut setup = Vec::new();
+    if workspace.pnpm {
+        setup.push("pnpm install".to_string());
+    }
+    if workspace.cargo {
+        setup.push("cargo build --workspace --locked".to_string());
+    }
+    push_task(
+        &mut out,
+        "setup",
+        &setup.join(" && "),
+        "Install workspace dependencies",
+        &tool_deps(workspace),
+        &["s"],
+    );
+
+    if workspace.has_action(PackageKind::Pnpm, "dev") {
+        push_task(
+            &mut out,
+            "dev",
+            "pnpm -r --parallel run dev",
+            "Run dev servers for all workspace packages",
+            &["setup"],
+            &["d"],
+        );
+    } else if let Some(pkg) = workspace
+        .packages
+        .iter()
+        .find(|p| p.actions.contains(&"dev"))
+    {
+        push_task(
+            &mut out,
+            "dev",
+            &package_command(pkg, "dev"),
+            &format!("Run {} (see <package>:dev tasks for others)", pkg.name),
+            &["setup"],
+            &["d"],
+        );
+    }
+
+    for (action, description) in [
+        ("build", "Build all workspace packages"),
+        ("test", "Test all workspace packages"),
+    ] {
+        let mut commands = Vec::new();
+        if workspace.has_action(PackageKind::Pnpm, action) {
+            commands.push(format!("pnpm -r run {action}"));
+        }
+        if workspace.cargo {
+            commands.push(format!("cargo {action} --workspace"));
+        }
+        if !commands.is_empty() {
+            push_task(
+                &mut out,
+                action,
+                &commands.join(" && "),
+                description,
+                &[],
+                &[],
+            );
+        }
+    }
+
+    let mut verify = Vec::new();
+    if workspace.pnpm {
+        verify.push(setup::PNPM_LOCK_CHECK);
+    }
+    if workspace.cargo {
+        verify.push(setup::CARGO_LOCK_CHECK);
+    }
+    push_task(
+        &mut out,
+        "verify",
+        &verify.join(" && "),
+        "Check lockfiles are in sync with their manifests",
+        &[],
+        &[],
+    );
+
+    for pkg in &workspace.packages {
+        for action in &pkg.actions {
+            push_task(
+                &mut out,
+                &format!("{}:{action}", pkg.name),
+                &package_command(pkg, action),
+                &format!("{} {} ({})", action_verb(action), pkg.name, pkg.dir),
+                &[],
+                &[],
+            );
+        }
+    }
+
+    out
+}
+
+fn tool_deps(workspace: &Workspace) -> Vec<&'static str> {
+    let mut deps = Vec::new();
+    if workspace.pnpm {
+        deps.push("pnpm");
+    }
+    if workspace.cargo {
+        deps.push("cargo");
+    }
+    deps
+}
+
+fn package_command(pkg: &WorkspacePackage, action: &str) -> String {
+    match pkg.kind {
+        PackageKind::Cargo => {
+            let verb = if action == "dev" { "run" } else { action };
+            format!("cargo {verb} -p {}", pkg.name)
+        }
+        PackageKind::Pnpm => format!("cd {} && pnpm run {action}", pkg.dir),
+    }
+}
+
+fn action_verb(action: &str) -> &'static str {
+    match action {
+        "dev" => "Run",
+        "build" => "Build",
+        _ => "Test",
+    }
+}
+
+fn push_task(
+    out: &mut String,
+    name: &str,
+    command: &str,
+    description: &str,
+    dependencies: &[&str],
+    shortcuts: &[&str],
+) {
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push_str("[[tasks]]\n");
+    out.push_str(&format!("name = \"{}\"\n", toml_escape(name)));
+    out.push_str(&format!("command = \"{}\"\n", toml_escape(command)));
+    out.push_str(&format!("description = \"{}\"\n", toml_escape(description)));
+    if !dependencies.is_empty() {
+        out.push_str(&format!("dependencies = [{}]\n", quoted_list(dependencies)));
+    }
+    if !shortcuts.is_empty() {
+        out.push_str(&format!("shortcuts = [{}]\n", quoted_list(shortcuts)));
+    }
+}
+
+fn quoted_list(values: &[&str]) -> String {
+    values
+        .iter()
+        .map(|v| format!("\"{}\"", toml_escape(v)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn toml_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn cargo_workspace_members(root: &Path) -> Option<Vec<String>> {
+    let content = fs::read_to_string(root.join("Cargo.toml")).ok()?;
+    let value: toml::Value = toml::from_str(&content).ok()?;
+    let workspace = value.get("workspace")?.as_table()?;
+    let list = |key: &str| -> Vec<String> {
+        workspace
+            .get(key)
+            .and_then(toml::Value::as_array)
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(toml::Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let mut patterns = list("members");
+    patterns.extend(list("exclude").into_iter().map(|p| format!("!{p}")));
+    Some(patterns)
+}
+
+/// Read the `packages:` list from pnpm-workspace.yaml (block or inline list form).
+fn pnpm_workspace_packages(root: &Path) -> Option<Vec<String>> {
+    let content = fs::read_to_string(root.join("pnpm-workspace.yaml")).ok()?;
+    Some(parse_pnpm_workspace(&content))
+}
+
+fn parse_pnpm_workspace(content: &str) -> Vec<String> {
+    let unquote = |s: &str| s.trim().trim_matches(|c| c == '"' || c == '\'').to_string();
+    let mut patterns = Vec::new();
+    let mut in_packages = false;
+    for line in content.lines() {
+        let without_comment = line.split(" #").next().unwrap_or(line);
+        let trimmed = without_comment.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indented = line.starts_with(' ') || line.starts_with('\t');
+        if !indented {
+            in_packages = false;
+            if let Some(rest) = trimmed.strip_prefix("packages:") {
+                let rest = rest.trim();
+                if let Some(inline) = rest.strip_prefix('[').and_then(|r| r.strip_suffix(']')) {
+                    patterns.extend(
+                        inline
+                            .split(',')
+                            .map(unquote)
+                            .filter(|item| !item.is_empty()),
+                    );
+                } else {
+                    in_packages = true;
+                }
+            }
+            continue;
+        }
+        if in_packages && let Some(item) = trimmed.strip_prefix('-') {
+            let item = unquote(item);
+            if !item.is_empty() {
+                patterns.push(item);
+            }
+        }
+    }
+    patterns
+}
+
+/// Expand member globs (`*`, `**`, `!exclude`) into directories containing `manifest`.
+fn expand_members(root: &Path, patterns: &[String], manifest: &str) -> Vec<String> {
+    let mut excluded = HashSet::new();
+    for pattern in patterns.iter().filter_map(|p| p.strip_prefix('!')) {
+        excluded.extend(expand_pattern(root, pattern));
+    }
+
+    let mut seen = HashSet::new();
+    let mut dirs = Vec::new();
+    for pattern in patterns.iter().filter(|p| !p.starts_with('!')) {
+        let mut matches = expand_pattern(root, pattern);
+        matches.sort();
+        for dir in matches {
+            if dir.is_empty()
+                || excluded.contains(&dir)
+                || !root.join(&dir).join(manifest).is_file()
+            {
+                continue;
+            }
+            if seen.insert(dir.clone()) {
+                dirs.push(dir);
+            }
+        }
+    }
+    dirs
+}
+
+fn expand_pattern(root: &Path, pattern: &str) -> Vec<String> {
+    let segments: Vec<&str> = pattern
+        .trim_start_matches("./")
+        .trim_end_matches('/')
+        .split('/')
+        .filter(|s| !s.is_empty() && *s != ".")
+        .collect();
+    let mut current = vec![String::new()];
+    for segment in segments {
+        let mut next = Vec::new();
+        for base in &current {
+            if segment == "**" {
+                next.push(base.clone());
+                collect_descendants(root, base, MAX_GLOB_DEPTH, &mut next);
+            } else if segment.contains('*') {
+                next.extend(
+                    child_dirs(root, base)
+                        .into_iter()
+                        .filter(|name| wildcard_match(segment, name))
+                        .map(|name| join_rel(base, &name)),
+                );
+            } else {
+                next.push(join_rel(base, segment));
+            }
+        }
+        current = next;
+    }
+    current
+}
+
+fn collect_descendants(root: &Path, base: &str, depth: usize, out: &mut Vec<String>) {
+    if depth == 0 {
+        return;
+    }
+    for name in child_dirs(root, base) {
+        let rel = join_rel(base, &name);
+        out.push(rel.clone());
+        collect_descendants(root, &rel, depth - 1, out);
+    }
+}
+
+fn child_dirs(root: &Path, base: &str) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(root.join(base)) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+        .filter(|name| !name.starts_with('.') && name != "node_modules" && name != "target")
+        .collect()
+}
+
+fn join_rel(base: &str, name: &str) -> String {
+    if base.is_empty() {
+        name.to_string()
+    } else {
+        format!("{base}/{name}")
+    }
+}
+
+/// Match a single path segment against a pattern where `*` matches any run of characters.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, rest) = parts.split_first().expect("split yields at least one part");
+    let Some(mut remaining) = name.strip_prefix(first) else {
+        return false;
+    };
+    let Some((last, middle)) = rest.split_last() else {
+        return remaining.is_empty();
+    };
+    for part in middle {
+        match remaining.find(part) {
+            Some(idx) => remaining = &remaining[idx + part.len()..],
+            None => return false,
+        }
+    }
+    remaining.len() >= last.len() && remaining.ends_with(last)
+}
+
+fn cargo_package(root: &Path, dir: &str) -> Option<WorkspacePackage> {
+    let path = root.join(dir);
+    let content = fs::read_to_string(path.join("Cargo.toml")).ok()?;
+    let value: toml::Value = toml::from_str(&content).ok()?;
+    let name = value
+        .get("package")
+        .and_then(|pkg| pkg.get("name"))
+        .and_then(toml::Value::as_str)?
+        .to_string();
+    let has_bin = path.join("src/main.rs").is_file()
+        || value
+            .get("bin")
+            .and_then(toml::Value::as_array)
+            .is_some_and(|bins| !bins.is_empty());
+    let mut actions = Vec::new();
+    if has_bin {
+        actions.push("dev");
+    }
+    actions.extend(["build", "test"]);
+    Some(WorkspacePackage {
+        name,
+        dir: dir.to_string(),
+        kind: PackageKind::Cargo,
+        actions,
+    })
+}
+
+fn pnpm_package(root: &Path, dir: &str) -> Option<WorkspacePackage> {
+    let content = fs::read_to_string(root.join(dir).join("package.json")).ok()?;
+    let value: serde_json::Value = serde_json::from_str(&content).ok()?;
+    let name = value
+        .get("name")
+        .and_then(serde_json::Value::as_str)
+        .map(|name| name.rsplit('/').next().unwrap_or(name).to_string())
+        .unwrap_or_else(|| dir.rsplit('/').next().unwrap_or(dir).to_string());
+    let scripts = value.get("scripts").and_then(serde_json::Value::as_object);
+    let actions: Vec<&'static str> = ["dev", "build", "test"]
+        .into_iter()
+        .filter(|action| scripts.is_some_and(|s| s.contains_key(*action)))
+        .collect();
+    if actions.is_empty() {
+        return None;
+    }
+    Some(WorkspacePackage {
+        name,
+        dir: dir.to_string(),
+        kind: PackageKind::Pnpm,
+        actions,
+    })
+}
+
+/// Task prefixes must be unique; fall back to the directory path for clashing names.
+fn dedupe_names(packages: &mut [WorkspacePackage]) {
+    let mut counts = std::collections::HashMap::new();
+    for pkg in packages.iter() {
+        *counts.entry(pkg.name.clone()).or_insert(0usize) += 1;
+    }
+    for pkg in packages.iter_mut() {
+        if counts.get(&pkg.name).copied().unwrap_or(0) > 1 {
+            pkg.name = pkg.dir.replace('/', "-");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write(root: &Path, rel: &str, content: &str) {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn parses_pnpm_workspace_block_and_inline_lists() {
+        let block = "packages:\n  - 'apps/*'\n  - \"packages/**\" # libs\n  - '!**/test/**'\ncatalog:\n  react: ^19\n";
+        assert_eq!(
+            parse_pnpm_workspace(block),
+            vec!["apps/*", "packages/**", "!**/test/**"]
+        );
+        assert_eq!(
+            parse_pnpm_workspace("packages: [apps/*, 'tools/cli']\n"),
+            vec!["apps/*", "tools/cli"]
+        );
+    }
+
+    #[test]
+    fn wildcard_matches_segments() {
+        assert!(wildcard_match("*", "web"));
+        assert!(wildcard_match("svc-*", "svc-api"));
+        assert!(wildcard_match("*-app", "web-app"));
+        assert!(!wildcard_match("svc-*", "web"));
+        assert!(wildcard_match("a*c*e", "abcde"));
+    }
+
+    #[test]
+    fn detects_mixed_workspace_and_renders_package_tasks() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/scratch\"]\n",
+        );
+        write(root, "crates/api/Cargo.toml", "[package]\nname = \"api\"\n");
+        write(root, "crates/api/src/main.rs", "fn main() {}\n");
+        write(
+            root,
+            "crates/core/Cargo.toml",
+            "[package]\nname = \"core\"\n",
+        );
+        write(
+            root,
+            "crates/scratch/Cargo.toml",
+            "[package]\nname = \"scratch\"\n",
+        );
+        write(root, "pnpm-workspace.yaml", "packages:\n  - apps/*\n");
+        write(
+            root,
+            "apps/web/package.json",
+            r#"{"name": "@acme/web", "scripts": {"dev": "vite", "test": "vitest"}}"#,
+        );
+
+        let workspace = detect(root).expect("workspace detected");
+        let names: Vec<_> = workspace.packages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["api", "core", "web"]);
+
+        let tasks = render_flow_toml(&workspace);
+        assert!(tasks.contains("name = \"web:dev\"\ncommand = \"cd apps/web && pnpm run dev\""));
+        assert!(tasks.contains("name = \"api:test\"\ncommand = \"cargo test -p api\""));
+        assert!(tasks.contains("name = \"api:dev\"\ncommand = \"cargo run -p api\""));
+        assert!(!tasks.contains("core:dev"));
+        assert!(!tasks.contains("scratch"));
+        assert!(tasks.contains("command = \"pnpm install && cargo build --workspace --locked\""));
+        assert!(tasks.contains("command = \"pnpm -r --parallel run dev\""));
+        assert!(tasks.contains("command = \"pnpm -r run test && cargo test --workspace\""));
+        assert!(!tasks.contains("pnpm -r run build"));
+        assert!(tasks.contains(
+            "name = \"verify\"\ncommand = \"pnpm install --frozen-lockfile --lockfile-only && cargo metadata --locked --format-version 1 > /dev/null\""
+        ));
+
+        let parsed: toml::Value = toml::from_str(&tasks).expect("rendered flow.toml is valid");
+        assert!(
+            parsed
+                .get("tasks")
+                .and_then(toml::Value::as_array)
+                .is_some()
+        );
+        assert!(parsed.get("deps").and_then(|d| d.get("pnpm")).is_some());
+    }
+
+    #[test]
+    fn plain_crate_is_not_a_workspace() {
+        let dir = tempdir().unwrap();
+        write(dir.path(), "Cargo.toml", "[package]\nname = \"solo\"\n");
+        assert!(detect(dir.path()).is_none());
+    }
+}
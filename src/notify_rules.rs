@@ -0,0 +1,293 @@
+//! `[[notify.rules]]` dispatch for task, deploy, and service events.
+//!
+//! Rules come from the project flow.toml plus the global one. A matching rule posts to
+//! macOS Notification Center, a Slack incoming webhook, and/or runs a shell command.
+//! Runs delegated to the hub dispatch through `f hub notify` from the run's exit trap,
+//! so background tasks alert without a terminal attached.
+
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use reqwest::blocking::Client;
+use serde_json::json;
+
+use crate::cli::HubNotifyOpts;
+use crate::config::{self, Config, NotifyEvent, NotifyRule};
+
+const SLACK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Something that happened and may be worth an alert.
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub event: NotifyEvent,
+    pub project: String,
+    /// Task, deploy label, or service name the event is about.
+    pub subject: String,
+    pub detail: String,
+}
+
+impl Notification {
+    pub fn title(&self) -> String {
+        let what = match self.event {
+            NotifyEvent::TaskFailure => "failed",
+            NotifyEvent::TaskSuccess => "succeeded",
+            NotifyEvent::DeploySuccess => "deployed",
+            NotifyEvent::DeployFailure => "deploy failed",
+            NotifyEvent::ServiceDown => "is down",
+            NotifyEvent::ServiceRecovered => "recovered",
+        };
+        format!("{}/{} {}", self.project, self.subject, what)
+    }
+
+    fn text(&self) -> String {
+        if self.detail.is_empty() {
+            self.title()
+        } else {
+            format!("{}: {}", self.title(), self.detail)
+        }
+    }
+}
+
+/// Handle `f hub notify`, run by delegated commands when they exit.
+pub fn run_hub_notify(opts: HubNotifyOpts) -> Result<()> {
+    let event = if opts.status == 0 {
+        NotifyEvent::TaskSuccess
+    } else {
+        NotifyEvent::TaskFailure
+    };
+    let cfg = load_config(&opts.config);
+    let note = Notification {
+        event,
+        project: project_label(cfg.as_ref(), &opts.cwd),
+        subject: opts.task,
+        detail: format!("exit status {} (hub)", opts.status),
+    };
+    dispatch(cfg.as_ref(), &opts.cwd, &note);
+    Ok(())
+}
+
+/// Send `note` to every rule in the project and global config that matches it.
+/// Failures are logged, never returned: an alert must not fail the run it reports on.
+pub fn dispatch(project_config: Option<&Config>, cwd: &Path, note: &Notification) {
+    let mut rules: Vec<NotifyRule> = project_config
+        .and_then(|cfg| cfg.notify.as_ref())
+        .map(|notify| notify.rules.clone())
+        .unwrap_or_default();
+    rules.extend(global_rules());
+
+    for rule in rules.iter().filter(|rule| rule_matches(rule, note)) {
+        if let Err(err) = send(rule, cwd, note) {
+            tracing::warn!(?err, event = note.event.as_str(), "notification failed");
+        }
+    }
+}
+
+/// Like [`dispatch`], loading the project config from `config_path`.
+pub fn dispatch_from(config_path: &Path, cwd: &Path, note: &Notification) {
+    let cfg = load_config(config_path);
+    dispatch(cfg.as_ref(), cwd, note);
+}
+
+/// Project name from flow.toml, falling back to the directory name.
+pub fn project_label(cfg: Option<&Config>, root: &Path) -> String {
+    cfg.and_then(|cfg| cfg.project_name.clone())
+        .or_else(|| {
+            root.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "flow".to_string())
+}
+
+fn load_config(path: &Path) -> Option<Config> {
+    if !path.exists() {
+        return None;
+    }
+    config::load(path).ok()
+}
+
+fn global_rules() -> Vec<NotifyRule> {
+    let path = config::default_config_path();
+    if !path.exists() {
+        return Vec::new();
+    }
+    config::load(&path)
+        .ok()
+        .and_then(|cfg| cfg.notify)
+        .map(|notify| notify.rules)
+        .unwrap_or_default()
+}
+
+fn rule_matches(rule: &NotifyRule, note: &Notification) -> bool {
+    rule.on == note.event && (rule.tasks.is_empty() || rule.tasks.contains(&note.subject))
+}
+
+fn send(rule: &NotifyRule, cwd: &Path, note: &Notification) -> Result<()> {
+    if rule.macos {
+        send_macos(note)?;
+    }
+    if let Some(webhook) = rule.slack_webhook.as_deref() {
+        send_slack(webhook, note)?;
+    }
+    if let Some(command) = rule.command.as_deref() {
+        run_command(command, cwd, note)?;
+    }
+    Ok(())
+}
+
+fn send_macos(note: &Notification) -> Result<()> {
+    if !cfg!(target_os = "macos") {
+        return Ok(());
+    }
+    let script = format!(
+        "display notification {} with title {}",
+        applescript_string(&note.detail),
+        applescript_string(&note.title())
+    );
+    let status = Command::new("osascript")
+        .args(["-e", &script])
+        .status()
+        .context("failed to run osascript")?;
+    if !status.success() {
+        bail!("osascript exited with {}", status);
+    }
+    Ok(())
+}
+
+fn applescript_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Resolve `$VAR` / `${VAR}` to the environment variable's value.
+fn resolve_webhook(raw: &str) -> Result<String> {
+    let Some(name) = raw.strip_prefix('$') else {
+        return Ok(raw.to_string());
+    };
+    let name = name
+        .strip_prefix('{')
+        .and_then(|n| n.strip_suffix('}'))
+        .unwrap_or(name);
+    std::env::var(name).with_context(|| format!("slack webhook env var {name} is not set"))
+}
+
+fn send_slack(webhook: &str, note: &Notification) -> Result<()> {
+    let url = resolve_webhook(webhook)?;
+    let client = Client::builder()
+        .timeout(SLACK_TIMEOUT)
+        .build()
+        .context("failed to build HTTP client")?;
+    let resp = client
+        .post(&url)
+        .json(&json!({ "text": note.text() }))
+        .send()
+        .context("failed to post Slack notification")?;
+    if !resp.status().is_success() {
+        bail!("Slack webhook returned {}", resp.status());
+    }
+    Ok(())
+}
+
+fn run_command(command: &str, cwd: &Path, note: &Notification) -> Result<()> {
+    let status = Command::new("/bin/sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(cwd)
+        .env("FLOW_NOTIFY_EVENT", note.event.as_str())
+        .env("FLOW_NOTIFY_PROJECT", &note.project)
+        .env("FLOW_NOTIFY_SUBJECT", &note.subject)
+        .env("FLOW_NOTIFY_DETAIL", &note.detail)
+        .env("FLOW_NOTIFY_MESSAGE", note.text())
+        .status()
+        .with_context(|| format!("failed to run notify command: {command}"))?;
+    if !status.success() {
+        bail!("notify command exited with {}", status);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(on: NotifyEvent, tasks: &[&str]) -> NotifyRule {
+        NotifyRule {
+            on,
+            tasks: tasks.iter().map(|t| t.to_string()).collect(),
+            macos: false,
+            slack_webhook: None,
+            command: None,
+        }
+    }
+
+    fn note(event: NotifyEvent, subject: &str) -> Notification {
+        Notification {
+            event,
+            project: "myapp".to_string(),
+            subject: subject.to_string(),
+            detail: "exit status 1".to_string(),
+        }
+    }
+
+    #[test]
+    fn rules_match_event_and_optional_task_filter() {
+        let failure = note(NotifyEvent::TaskFailure, "build");
+        assert!(rule_matches(&rule(NotifyEvent::TaskFailure, &[]), &failure));
+        assert!(rule_matches(
+            &rule(NotifyEvent::TaskFailure, &["build"]),
+            &failure
+        ));
+        assert!(!rule_matches(
+            &rule(NotifyEvent::TaskFailure, &["test"]),
+            &failure
+        ));
+        assert!(!rule_matches(
+            &rule(NotifyEvent::TaskSuccess, &[]),
+            &failure
+        ));
+        assert_eq!(failure.text(), "myapp/build failed: exit status 1");
+    }
+
+    #[test]
+    fn notify_rules_parse_from_toml() {
+        let cfg: Config = toml::from_str(
+            r#"
+[[notify.rules]]
+on = "deploy_success"
+slack_webhook = "$SLACK_WEBHOOK_URL"
+
+[[notify.rules]]
+on = "task_failure"
+tasks = ["nightly"]
+macos = true
+command = "say failed"
+"#,
+        )
+        .unwrap();
+        let rules = cfg.notify.unwrap().rules;
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].on, NotifyEvent::DeploySuccess);
+        assert_eq!(rules[1].tasks, vec!["nightly".to_string()]);
+        assert!(rules[1].macos);
+    }
+
+    #[test]
+    fn command_channel_receives_notify_env() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let out = dir.path().join("out.txt");
+        let command = format!(
+            "printf '%s|%s' \"$FLOW_NOTIFY_EVENT\" \"$FLOW_NOTIFY_MESSAGE\" > {}",
+            out.display()
+        );
+        run_command(
+            &command,
+            dir.path(),
+            &note(NotifyEvent::TaskFailure, "build"),
+        )
+        .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap(),
+            "task_failure|myapp/build failed: exit status 1"
+        );
+    }
+}
@@ -3,9 +3,20 @@ use std::io::{self, IsTerminal};
 use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
 
-use opentui_lite::{ATTR_BOLD, BORDER_SIMPLE, Color, OpenTui};
+use opentui_lite::{ATTR_BOLD, BORDER_SIMPLE, Color, OpenTui, ellipsize_to_width, wrap_to_width};
 
 pub fn confirm(title: &str, lines: &[String], default_yes: bool) -> Option<bool> {
+    confirm_with_details(title, lines, &[], default_yes)
+}
+
+/// Like [`confirm`], with `details` shown in a right-hand panel when the terminal is wide
+/// enough.
+pub fn confirm_with_details(
+    title: &str,
+    lines: &[String],
+    details: &[String],
+    default_yes: bool,
+) -> Option<bool> {
     if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
         return None;
     }
@@ -42,10 +53,40 @@ pub fn confirm(title: &str, lines: &[String], default_yes: bool) -> Option<bool>
         Some(title),
     );
 
-    let max_width = width.saturating_sub(4) as usize;
+    let show_details = !details.is_empty() && width >= 60;
+    let panel_x = if show_details {
+        width as u32 * 3 / 5
+    } else {
+        width as u32
+    };
+    if show_details {
+        buffer.draw_box(
+            panel_x as i32,
+            1,
+            (width as u32).saturating_sub(panel_x + 1),
+            (height as u32).saturating_sub(2),
+            &BORDER_SIMPLE,
+            packed_options,
+            border,
+            bg,
+            Some("Details"),
+        );
+        let detail_width = (width as u32).saturating_sub(panel_x + 4) as usize;
+        let lines = details
+            .iter()
+            .flat_map(|line| wrap_to_width(line, detail_width));
+        for (line, y) in lines.zip(3u32..) {
+            if y >= height.saturating_sub(3) as u32 {
+                break;
+            }
+            buffer.draw_text(&line, panel_x + 2, y, muted, None, 0);
+        }
+    }
+
+    let max_width = panel_x.saturating_sub(4) as usize;
     let mut y = 2u32;
 
-    let title_line = truncate_width(title, max_width);
+    let title_line = ellipsize_to_width(title, max_width);
     buffer.draw_text(&title_line, 3, y, text, None, ATTR_BOLD);
     y += 2;
 
@@ -53,7 +94,7 @@ pub fn confirm(title: &str, lines: &[String], default_yes: bool) -> Option<bool>
         if y >= height.saturating_sub(3) as u32 {
             break;
         }
-        let line = truncate_width(line, max_width);
+        let line = ellipsize_to_width(line, max_width);
         buffer.draw_text(&line, 3, y, text, None, 0);
         y += 1;
     }
@@ -63,7 +104,7 @@ pub fn confirm(title: &str, lines: &[String], default_yes: bool) -> Option<bool>
     } else {
         "Enter/N = no, Y = yes"
     };
-    let hint_line = truncate_width(hint, max_width);
+    let hint_line = ellipsize_to_width(hint, max_width);
     let hint_y = height.saturating_sub(2) as u32;
     buffer.draw_text(&hint_line, 3, hint_y, muted, None, 0);
 
@@ -72,7 +113,7 @@ pub fn confirm(title: &str, lines: &[String], default_yes: bool) -> Option<bool>
     } else {
         "[N] Cancel"
     };
-    let action_line = truncate_width(action, max_width);
+    let action_line = ellipsize_to_width(action, max_width);
     buffer.draw_text(
         &action_line,
         3,
@@ -119,10 +160,3 @@ impl Drop for RawModeGuard {
         let _ = disable_raw_mode();
     }
 }
-
-fn truncate_width(input: &str, max: usize) -> String {
-    if input.len() <= max {
-        return input.to_string();
-    }
-    input.chars().take(max).collect::<String>()
-}
@@ -1,28 +1,34 @@
 use std::{
     io::Write,
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::{Command, Stdio},
 };
 
 use anyhow::{Context, Result, bail};
+use rusqlite::Connection;
 
 use crate::{
-    ai_tasks,
+    ai_tasks, branch_watch,
     cli::TasksOpts,
     config::{self, TaskConfig},
+    deploy_watch::format_age,
     discover::DiscoveredTask,
+    opentui_prompt,
+    plugins::{self, PaletteItem},
     project_snapshot::ProjectSnapshot,
+    running::{self, RunningProcess},
+    runs::{self, RunKind},
 };
 
 pub fn run(opts: TasksOpts) -> Result<()> {
-    let entries = build_entries(Some(opts))?;
-    present(entries)
+    let (entries, header) = build_entries(Some(opts))?;
+    present(entries, header)
 }
 
 /// Show global commands/tasks only (no project flow.toml required).
 pub fn run_global() -> Result<()> {
-    let entries = build_entries(None)?;
-    present(entries)
+    let (entries, _) = build_entries(None)?;
+    present(entries, None)
 }
 
 struct FzfResult<'a> {
@@ -30,12 +36,26 @@ struct FzfResult<'a> {
     with_args: bool,
 }
 
-fn run_fzf<'a>(entries: &'a [PaletteEntry]) -> Result<Option<FzfResult<'a>>> {
-    let mut child = Command::new("fzf")
+fn run_fzf<'a>(entries: &'a [PaletteEntry], header: Option<&str>) -> Result<Option<FzfResult<'a>>> {
+    // Each line is "display<TAB>details"; only the display is searchable and the details
+    // (with literal "\n" separators) feed the right-hand preview panel.
+    let mut cmd = Command::new("fzf");
+    if let Some(header) = header {
+        cmd.arg("--header").arg(header);
+    }
+    let mut child = cmd
         .arg("--prompt")
         .arg("f> ")
         .arg("--expect")
         .arg("tab") // tab to run with args prompt
+        .arg("--delimiter")
+        .arg("\t")
+        .arg("--with-nth")
+        .arg("1")
+        .arg("--preview")
+        .arg("printf '%b' {2}")
+        .arg("--preview-window")
+        .arg("right:40%:wrap")
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .spawn()
@@ -44,7 +64,7 @@ fn run_fzf<'a>(entries: &'a [PaletteEntry]) -> Result<Option<FzfResult<'a>>> {
     {
         let stdin = child.stdin.as_mut().context("failed to open fzf stdin")?;
         for entry in entries {
-            writeln!(stdin, "{}", entry.display)?;
+            writeln!(stdin, "{}\t{}", entry.display, entry.preview_text())?;
         }
     }
 
@@ -60,7 +80,13 @@ fn run_fzf<'a>(entries: &'a [PaletteEntry]) -> Result<Option<FzfResult<'a>>> {
     let with_args = key == "tab";
 
     // Second line is the selection
-    let selection = lines.next().unwrap_or("").trim();
+    let selection = lines
+        .next()
+        .unwrap_or("")
+        .split('\t')
+        .next()
+        .unwrap_or("")
+        .trim();
     if selection.is_empty() {
         return Ok(None);
     }
@@ -91,7 +117,7 @@ fn run_entry(entry: &PaletteEntry, extra_args: Vec<String>) -> Result<()> {
     }
 }
 
-fn present(entries: Vec<PaletteEntry>) -> Result<()> {
+fn present(entries: Vec<PaletteEntry>, header: Option<String>) -> Result<()> {
     if entries.is_empty() {
         println!("No commands or tasks available. Add entries to flow.toml or global config.");
         return Ok(());
@@ -106,7 +132,11 @@ fn present(entries: Vec<PaletteEntry>) -> Result<()> {
         return Ok(());
     }
 
-    if let Some(result) = run_fzf(&entries)? {
+    if let Some(result) = run_fzf(&entries, header.as_deref())? {
+        if !confirm_destructive(result.entry)? {
+            println!("Cancelled.");
+            return Ok(());
+        }
         let extra_args = if result.with_args {
             prompt_for_args(&result.entry.display)?
         } else {
@@ -118,6 +148,34 @@ fn present(entries: Vec<PaletteEntry>) -> Result<()> {
     Ok(())
 }
 
+/// Destructive entries need an explicit `y` before they run; non-destructive ones pass through.
+fn confirm_destructive(entry: &PaletteEntry) -> Result<bool> {
+    use std::io::{self, BufRead};
+
+    let Some(reason) = entry.destructive.as_deref() else {
+        return Ok(true);
+    };
+    let lines = vec![
+        entry.display.clone(),
+        String::new(),
+        format!("This action is destructive: {reason}."),
+    ];
+    if let Some(answer) =
+        opentui_prompt::confirm_with_details("Confirm action", &lines, &entry.details, false)
+    {
+        return Ok(answer);
+    }
+
+    println!("{}", entry.display);
+    for line in &entry.details {
+        println!("  {line}");
+    }
+    print!("This action is destructive: {reason}. Continue? [y/N] ");
+    io::stdout().flush()?;
+    let answer = io::stdin().lock().lines().next();
+    Ok(matches!(answer, Some(Ok(s)) if s.trim().eq_ignore_ascii_case("y")))
+}
+
 fn prompt_for_args(task_display: &str) -> Result<Vec<String>> {
     use std::io::{self, BufRead};
 
@@ -147,6 +205,10 @@ fn prompt_for_args(task_display: &str) -> Result<Vec<String>> {
 struct PaletteEntry {
     display: String,
     exec: Vec<String>,
+    /// Why the action is destructive; such entries require confirmation before running.
+    destructive: Option<String>,
+    /// Contextual metadata (last run, exit status, ...) shown in the detail panel.
+    details: Vec<String>,
 }
 
 impl PaletteEntry {
@@ -154,7 +216,54 @@ impl PaletteEntry {
         Self {
             display: display.to_string(),
             exec,
+            destructive: None,
+            details: Vec::new(),
+        }
+    }
+
+    fn destructive(mut self, reason: impl Into<String>) -> Self {
+        self.destructive = Some(reason.into());
+        self
+    }
+
+    fn with_details(mut self, details: Vec<String>) -> Self {
+        self.details = details;
+        self
+    }
+
+    /// Single-line preview payload for fzf: details joined with literal `\n` for `printf %b`.
+    fn preview_text(&self) -> String {
+        let mut lines = Vec::new();
+        if let Some(reason) = &self.destructive {
+            lines.push(format!("⚠ destructive: {reason}"));
         }
+        lines.extend(self.details.iter().cloned());
+        lines
+            .iter()
+            .map(|line| line.replace('\\', "\\\\").replace(['\t', '\n'], " "))
+            .collect::<Vec<_>>()
+            .join("\\n")
+    }
+
+    fn from_process(process: &RunningProcess, config_path: &Path, now_ms: u128) -> Self {
+        let display = format!("[kill] {} (pid {})", process.task_name, process.pid);
+        let exec = vec![
+            "kill".into(),
+            "--config".into(),
+            config_path.display().to_string(),
+            "--pid".into(),
+            process.pid.to_string(),
+        ];
+        Self::new(&display, exec)
+            .destructive(format!("stops pid {} and its process group", process.pid))
+            .with_details(vec![
+                format!("task: {}", process.task_name),
+                format!(
+                    "started: {}",
+                    format_age(now_ms.saturating_sub(process.started_at) as i64)
+                ),
+                format!("command: {}", truncate(&process.command, 120)),
+            ])
     }
 
     fn from_task(task: &TaskConfig, config_arg: &str) -> Self {
@@ -170,7 +279,31 @@ impl PaletteEntry {
             task.name.clone(),
         ];
 
-        Self { display, exec }
+        Self::new(&display, exec).annotate_task(task)
+    }
+
+    /// Tasks marked `confirm_on_match`, `confirm`, or `protected` are treated as destructive here
+    /// too. The palette's own confirmation stands in for the task's, so it runs with `--yes`.
+    fn annotate_task(mut self, task: &TaskConfig) -> Self {
+        if task.confirm_on_match {
+            self.destructive = Some("task is marked confirm_on_match".to_string());
+        }
+        if task.protected || task.confirm.is_some() {
+            let marker = if task.protected {
+                "protected"
+            } else {
+                "confirm"
+            };
+            self.display = format!("{} [{marker}]", self.display);
+            self.destructive = Some(format!("task is marked {marker}"));
+            if let Some(question) = &task.confirm {
+                self.details.push(format!("confirm: {question}"));
+            }
+            self.exec.insert(1, "--yes".to_string());
+        }
+        self.details
+            .push(format!("command: {}", truncate(&task.command, 120)));
+        self
     }
 
     fn from_discovered(discovered: &DiscoveredTask) -> Self {
@@ -202,7 +335,23 @@ impl PaletteEntry {
             discovered.task.name.clone(),
         ];
 
-        Self { display, exec }
+        Self::new(&display, exec).annotate_task(&discovered.task)
+    }
+
+    /// Runs `f <plugin> <args>`, which resolves to the plugin executable.
+    fn from_plugin(item: PaletteItem) -> Self {
+        let display = match item.description.as_deref() {
+            Some(description) => format!(
+                "[{}] {} – {}",
+                item.plugin,
+                item.label,
+                truncate(description, 96)
+            ),
+            None => format!("[{}] {}", item.plugin, item.label),
+        };
+        let mut exec = vec![item.plugin];
+        exec.extend(item.args);
+        Self::new(&display, exec)
     }
 
     fn from_ai_task(task: &ai_tasks::DiscoveredAiTask) -> Self {
@@ -213,34 +362,52 @@ impl PaletteEntry {
         };
         let display = format!("[task] {} – {}", task.id, truncate(&summary, 96));
         let exec = vec![task.id.clone()];
-        Self { display, exec }
+        Self::new(&display, exec)
     }
 }
 
-fn build_entries(project_opts: Option<TasksOpts>) -> Result<Vec<PaletteEntry>> {
+/// Palette entries, plus a header line describing the project's checkout (if any).
+fn build_entries(project_opts: Option<TasksOpts>) -> Result<(Vec<PaletteEntry>, Option<String>)> {
     let mut entries = Vec::new();
     let global_cfg = load_if_exists(config::default_config_path())?;
     let mut has_project = false;
+    let mut header = None;
 
     if let Some(opts) = project_opts {
         let snapshot = ProjectSnapshot::from_task_config(&opts.config, false)?;
 
         if snapshot.has_any_tasks() {
             has_project = true;
+            let runs_db = runs::open_runs_db().ok();
             for discovered in &snapshot.discovery.tasks {
-                entries.push(PaletteEntry::from_discovered(discovered));
+                let entry = PaletteEntry::from_discovered(discovered);
+                entries.push(with_last_run(
+                    entry,
+                    runs_db.as_ref(),
+                    &discovered.task.name,
+                    &snapshot.root,
+                ));
+                entries.extend(rerun_failed_entry(
+                    runs_db.as_ref(),
+                    discovered,
+                    &snapshot.root,
+                ));
             }
             for task in &snapshot.ai_tasks {
                 entries.push(PaletteEntry::from_ai_task(task));
             }
+            entries.extend(process_entries(&snapshot.root));
+            entries.extend(plugin_entries(Some(&snapshot.root)));
+            header = branch_watch::palette_header(&snapshot.root);
         }
     }
 
     if has_project {
-        return Ok(entries);
+        return Ok((entries, header));
     }
 
     entries.extend(builtin_entries());
+    entries.extend(plugin_entries(None));
 
     if let Some((global_path, cfg)) = global_cfg {
         let arg = global_path.display().to_string();
@@ -249,7 +416,92 @@ fn build_entries(project_opts: Option<TasksOpts>) -> Result<Vec<PaletteEntry>> {
         }
     }
 
-    Ok(entries)
+    Ok((entries, None))
+}
+
+/// Prepend last-run metadata (time, exit status, duration) to a task entry's details.
+fn with_last_run(
+    mut entry: PaletteEntry,
+    conn: Option<&Connection>,
+    task: &str,
+    root: &Path,
+) -> PaletteEntry {
+    let Some(conn) = conn else {
+        return entry;
+    };
+    let line = match runs::latest_named_under(conn, RunKind::Task, task, root) {
+        Ok(Some(run)) => {
+            let status = match (run.success, run.status) {
+                (true, _) => "ok".to_string(),
+                (false, Some(code)) => format!("failed (exit {code})"),
+                (false, None) => "failed".to_string(),
+            };
+            let elapsed = running::now_ms() as i64 - run.started_ms;
+            format!(
+                "last run: {} – {} in {}",
+                format_age(elapsed.max(0)),
+                status,
+                runs::format_duration(run.duration_ms)
+            )
+        }
+        Ok(None) => "last run: never".to_string(),
+        Err(_) => return entry,
+    };
+    entry.details.insert(0, line);
+    entry
+}
+
+/// `[rerun]` entry for a task whose last run failed with parsed failing tests, running only
+/// those tests again.
+fn rerun_failed_entry(
+    conn: Option<&Connection>,
+    discovered: &DiscoveredTask,
+    root: &Path,
+) -> Option<PaletteEntry> {
+    let run =
+        runs::latest_named_under(conn?, RunKind::Task, &discovered.task.name, root).ok()??;
+    let results = run
+        .results
+        .filter(|results| !run.success && !results.failures.is_empty())?;
+    let display = format!(
+        "[rerun] {} – {} failed test{}",
+        discovered.task.name,
+        results.failures.len(),
+        if results.failures.len() == 1 { "" } else { "s" }
+    );
+    let mut exec = PaletteEntry::from_discovered(discovered).exec;
+    exec.push("--".to_string());
+    exec.extend(results.rerun_args());
+    let mut details = vec![format!("last run {}: {}", run.id, results.summary())];
+    details.extend(
+        results
+            .failures
+            .iter()
+            .take(20)
+            .map(|name| format!("✗ {name}")),
+    );
+    Some(PaletteEntry::new(&display, exec).with_details(details))
+}
+
+/// One destructive `[kill]` entry per running flow-started process in this project.
+fn process_entries(root: &Path) -> Vec<PaletteEntry> {
+    let config_path = root.join("flow.toml");
+    let Ok(processes) = running::get_project_processes(&config_path) else {
+        return Vec::new();
+    };
+    let now_ms = running::now_ms();
+    processes
+        .iter()
+        .map(|process| PaletteEntry::from_process(process, &config_path, now_ms))
+        .collect()
+}
+
+/// Entries contributed by `palette-provider` plugins.
+fn plugin_entries(root: Option<&Path>) -> Vec<PaletteEntry> {
+    plugins::palette_items(root)
+        .into_iter()
+        .map(PaletteEntry::from_plugin)
+        .collect()
 }
 
 fn builtin_entries() -> Vec<PaletteEntry> {
@@ -287,3 +539,43 @@ fn truncate(input: &str, max: usize) -> String {
     }
     out
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preview_text_is_one_line_for_fzf() {
+        let entry = PaletteEntry::new("[kill] dev (pid 42)", Vec::new())
+            .destructive("stops pid 42")
+            .with_details(vec![
+                "command: a\tb".to_string(),
+                "path: C:\\tmp".to_string(),
+            ]);
+        let preview = entry.preview_text();
+        assert!(!preview.contains('\t') && !preview.contains('\n'));
+        assert_eq!(
+            preview,
+            "⚠ destructive: stops pid 42\\ncommand: a b\\npath: C:\\\\tmp"
+        );
+    }
+
+    #[test]
+    fn protected_tasks_are_marked_and_confirmed_once() {
+        let task: TaskConfig = toml::from_str(
+            "name = \"db-reset\"\ncommand = \"dropdb app\"\nprotected = true\nconfirm = \"Wipe the DB?\"",
+        )
+        .unwrap();
+        let entry = PaletteEntry::from_task(&task, "flow.toml");
+        assert!(entry.display.ends_with("[protected]"));
+        assert_eq!(
+            entry.destructive.as_deref(),
+            Some("task is marked protected")
+        );
+        assert_eq!(entry.details[0], "confirm: Wipe the DB?");
+        assert_eq!(
+            entry.exec,
+            vec!["run", "--yes", "--config", "flow.toml", "db-reset"]
+        );
+    }
+}
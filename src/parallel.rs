@@ -42,6 +42,8 @@ pub enum TaskStatus {
 pub struct Task {
     pub label: String,
     pub command: String,
+    /// Extra environment for the command, kept out of `command` so values aren't printed.
+    pub env: Vec<(String, String)>,
     pub status: TaskStatus,
     pub last_line: String,
     pub exit_code: Option<i32>,
@@ -54,6 +56,7 @@ impl Task {
         Self {
             label: label.into(),
             command: command.into(),
+            env: Vec::new(),
             status: TaskStatus::Pending,
             last_line: String::new(),
             exit_code: None,
@@ -61,6 +64,11 @@ impl Task {
             duration: None,
         }
     }
+
+    pub fn with_env(mut self, env: Vec<(String, String)>) -> Self {
+        self.env = env;
+        self
+    }
 }
 
 pub struct ParallelRunner {
@@ -215,10 +223,10 @@ impl ParallelRunner {
             return;
         }
 
-        let command = {
+        let (command, env) = {
             let mut tasks = self.tasks.lock().await;
             tasks[task_idx].status = TaskStatus::Running;
-            tasks[task_idx].command.clone()
+            (tasks[task_idx].command.clone(), tasks[task_idx].env.clone())
         };
 
         let start = Instant::now();
@@ -226,6 +234,7 @@ impl ParallelRunner {
         let mut child = match Command::new("sh")
             .arg("-c")
             .arg(&command)
+            .envs(env)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()
@@ -322,6 +331,11 @@ impl ParallelRunner {
         }
     }
 
+    /// Current state of every task; after [`run`](Self::run) returns, their final results.
+    pub async fn snapshot(&self) -> Vec<Task> {
+        self.tasks.lock().await.clone()
+    }
+
     pub async fn run(self: Arc<Self>) -> i32 {
         // Hide cursor
         print!("{}", HIDE_CURSOR);
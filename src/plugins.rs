@@ -0,0 +1,412 @@
+//! External plugins.
+//!
+//! `f <name>` runs `flow-<name>` from PATH (git-style) when no builtin command or task has
+//! that name. A plugin can also hook into flow by dropping a manifest in
+//! `~/.config/flow/plugins/<name>.toml`:
+//!
+//! ```toml
+//! description = "Team release checks"
+//! command = "~/bin/release-guard"   # default: flow-<name> on PATH
+//! hooks = ["pre-task", "post-deploy", "palette-provider"]
+//! timeout_secs = 10
+//! ```
+//!
+//! A hook runs `<command> hook <point>` with a JSON request on stdin and reads a JSON response
+//! from stdout (empty output means "no opinion"). A plugin that fails, times out, or prints
+//! invalid JSON is reported on stderr and skipped, except that `pre-task` can stop a task by
+//! answering `{"allow": false}`.
+
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::config;
+
+const DEFAULT_HOOK_TIMEOUT_SECS: u64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookPoint {
+    /// Before a task runs. Response: `{"allow": bool, "message": "..."}`.
+    PreTask,
+    /// After `f deploy` / `f prod` finishes. Response: `{"message": "..."}`.
+    PostDeploy,
+    /// Extra palette entries. Response: `{"entries": [{"label", "args", "description"}]}`.
+    PaletteProvider,
+}
+
+impl HookPoint {
+    fn as_str(self) -> &'static str {
+        match self {
+            HookPoint::PreTask => "pre-task",
+            HookPoint::PostDeploy => "post-deploy",
+            HookPoint::PaletteProvider => "palette-provider",
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PluginManifest {
+    description: Option<String>,
+    command: Option<String>,
+    hooks: Vec<HookPoint>,
+    timeout_secs: Option<u64>,
+}
+
+/// A plugin declared by a manifest.
+#[derive(Debug, Clone)]
+pub struct Plugin {
+    pub name: String,
+    pub description: Option<String>,
+    command: PathBuf,
+    hooks: Vec<HookPoint>,
+    timeout: Duration,
+}
+
+impl Plugin {
+    fn from_manifest(name: &str, manifest: PluginManifest) -> Self {
+        let command = match manifest.command.as_deref().map(str::trim) {
+            Some(command) if !command.is_empty() => config::expand_path(command),
+            _ => PathBuf::from(format!("flow-{name}")),
+        };
+        Self {
+            name: name.to_string(),
+            description: manifest.description,
+            command,
+            hooks: manifest.hooks,
+            timeout: Duration::from_secs(
+                manifest.timeout_secs.unwrap_or(DEFAULT_HOOK_TIMEOUT_SECS),
+            ),
+        }
+    }
+}
+
+fn plugins_dir() -> PathBuf {
+    config::global_config_dir().join("plugins")
+}
+
+/// Plugins with a manifest, sorted by name. Unreadable manifests are reported and skipped.
+pub fn load_plugins() -> Vec<Plugin> {
+    let Ok(entries) = fs::read_dir(plugins_dir()) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?;
+            match read_manifest(path) {
+                Ok(manifest) => Some(Plugin::from_manifest(name, manifest)),
+                Err(err) => {
+                    eprintln!("WARN skipping plugin '{name}': {err:#}");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn read_manifest(path: &Path) -> Result<PluginManifest> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+fn is_plugin_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Executable behind `f <name>`: the manifest's `command`, else `flow-<name>` on PATH.
+pub fn find_executable(name: &str) -> Option<PathBuf> {
+    if !is_plugin_name(name) {
+        return None;
+    }
+    let manifest = plugins_dir().join(format!("{name}.toml"));
+    let command = read_manifest(&manifest)
+        .ok()
+        .map(|manifest| Plugin::from_manifest(name, manifest).command)
+        .unwrap_or_else(|| PathBuf::from(format!("flow-{name}")));
+    which::which(command).ok()
+}
+
+/// Run a plugin as a subcommand, exiting with its status when it fails.
+pub fn run_external(name: &str, executable: &Path, args: &[String]) -> Result<()> {
+    let status = plugin_command(executable)
+        .args(args)
+        .status()
+        .with_context(|| format!("failed to run plugin '{name}'"))?;
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
+
+/// Plugins get `FLOW_BIN` so they can call back into the same flow binary.
+fn plugin_command(program: &Path) -> Command {
+    let mut command = Command::new(program);
+    if let Ok(exe) = std::env::current_exe() {
+        command.env("FLOW_BIN", exe);
+    }
+    command
+}
+
+/// Run every plugin hooked into `point`, returning each one's name and response.
+fn dispatch(point: HookPoint, request: &Value) -> Vec<(String, Value)> {
+    load_plugins()
+        .into_iter()
+        .filter(|plugin| plugin.hooks.contains(&point))
+        .filter_map(|plugin| match run_hook(&plugin, point, request) {
+            Ok(response) => Some((plugin.name, response)),
+            Err(err) => {
+                eprintln!(
+                    "WARN plugin '{}' {} hook failed: {err:#}",
+                    plugin.name,
+                    point.as_str()
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+fn run_hook(plugin: &Plugin, point: HookPoint, request: &Value) -> Result<Value> {
+    let mut child = plugin_command(&plugin.command)
+        .arg("hook")
+        .arg(point.as_str())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .with_context(|| format!("failed to run {}", plugin.command.display()))?;
+
+    let input = serde_json::to_vec(request)?;
+    let mut stdin = child.stdin.take().context("failed to open plugin stdin")?;
+    // A plugin may ignore its input; a broken pipe here is not an error.
+    let writer = thread::spawn(move || {
+        let _ = stdin.write_all(&input);
+    });
+    let mut stdout = child
+        .stdout
+        .take()
+        .context("failed to open plugin stdout")?;
+    let reader = thread::spawn(move || {
+        let mut output = Vec::new();
+        stdout.read_to_end(&mut output).map(|_| output)
+    });
+
+    let deadline = Instant::now() + plugin.timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("timed out after {:?}", plugin.timeout);
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+    let _ = writer.join();
+    let output = reader
+        .join()
+        .map_err(|_| anyhow!("plugin output reader panicked"))?
+        .context("failed to read plugin output")?;
+    if !status.success() {
+        bail!("exited with {status}");
+    }
+    parse_response(&output)
+}
+
+fn parse_response(output: &[u8]) -> Result<Value> {
+    let text = String::from_utf8_lossy(output);
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(Value::Null);
+    }
+    serde_json::from_str(text).context("response is not valid JSON")
+}
+
+fn response_message(response: &Value) -> Option<&str> {
+    response
+        .get("message")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|message| !message.is_empty())
+}
+
+/// What a `pre-task` hook needs to know about the task.
+pub struct PreTask<'a> {
+    pub name: &'a str,
+    pub command: &'a str,
+    pub args: &'a [String],
+    pub config_path: &'a Path,
+    pub project_root: &'a Path,
+    pub project: Option<&'a str>,
+}
+
+/// Run `pre-task` hooks; fails when a plugin answers `{"allow": false}`.
+pub fn run_pre_task(task: &PreTask) -> Result<()> {
+    let request = json!({
+        "hook": HookPoint::PreTask.as_str(),
+        "task": {
+            "name": task.name,
+            "command": task.command,
+            "args": task.args,
+            "config_path": task.config_path.display().to_string(),
+            "project_root": task.project_root.display().to_string(),
+            "project": task.project,
+        },
+    });
+    for (plugin, response) in dispatch(HookPoint::PreTask, &request) {
+        let message = response_message(&response);
+        if response.get("allow").and_then(Value::as_bool) == Some(false) {
+            bail!(
+                "task '{}' blocked by plugin '{plugin}'{}",
+                task.name,
+                message.map(|m| format!(": {m}")).unwrap_or_default()
+            );
+        }
+        if let Some(message) = message {
+            println!("[{plugin}] {message}");
+        }
+    }
+    Ok(())
+}
+
+/// Run `post-deploy` hooks and print any messages they return.
+pub fn run_post_deploy(project_root: &Path, project: &str, label: &str, result: &Result<()>) {
+    let request = json!({
+        "hook": HookPoint::PostDeploy.as_str(),
+        "deploy": {
+            "label": label,
+            "project": project,
+            "project_root": project_root.display().to_string(),
+            "success": result.is_ok(),
+            "error": result.as_ref().err().map(|err| format!("{err:#}")),
+        },
+    });
+    for (plugin, response) in dispatch(HookPoint::PostDeploy, &request) {
+        if let Some(message) = response_message(&response) {
+            println!("[{plugin}] {message}");
+        }
+    }
+}
+
+/// A palette entry contributed by a `palette-provider` hook; it runs `f <plugin> <args>`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct PaletteItem {
+    #[serde(skip)]
+    pub plugin: String,
+    pub label: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Entries from every `palette-provider` plugin.
+pub fn palette_items(project_root: Option<&Path>) -> Vec<PaletteItem> {
+    let request = json!({
+        "hook": HookPoint::PaletteProvider.as_str(),
+        "project_root": project_root.map(|root| root.display().to_string()),
+    });
+    dispatch(HookPoint::PaletteProvider, &request)
+        .into_iter()
+        .flat_map(|(plugin, response)| parse_palette_items(&plugin, &response))
+        .collect()
+}
+
+fn parse_palette_items(plugin: &str, response: &Value) -> Vec<PaletteItem> {
+    let Some(entries) = response.get("entries").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    entries
+        .iter()
+        .filter_map(|entry| serde_json::from_value::<PaletteItem>(entry.clone()).ok())
+        .map(|item| PaletteItem {
+            plugin: plugin.to_string(),
+            ..item
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_defaults_to_flow_prefixed_command() {
+        let manifest: PluginManifest =
+            toml::from_str("hooks = [\"pre-task\", \"palette-provider\"]").unwrap();
+        let plugin = Plugin::from_manifest("audit", manifest);
+        assert_eq!(plugin.command, PathBuf::from("flow-audit"));
+        assert_eq!(
+            plugin.hooks,
+            vec![HookPoint::PreTask, HookPoint::PaletteProvider]
+        );
+        assert_eq!(plugin.timeout, Duration::from_secs(10));
+
+        assert!(toml::from_str::<PluginManifest>("hooks = [\"on-save\"]").is_err());
+        assert!(is_plugin_name("release-guard"));
+        assert!(!is_plugin_name("--help"));
+        assert!(!is_plugin_name("../x"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_hook_exchanges_json_and_enforces_timeout() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("flow-echo");
+        fs::write(
+            &script,
+            "#!/bin/sh\nif [ \"$2\" = \"post-deploy\" ]; then sleep 5; fi\n\
+             read -r line\necho \"{\\\"allow\\\": false, \\\"echo\\\": $line}\"\n",
+        )
+        .unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        let plugin = Plugin {
+            name: "echo".to_string(),
+            description: None,
+            command: script,
+            hooks: vec![HookPoint::PreTask, HookPoint::PostDeploy],
+            timeout: Duration::from_millis(500),
+        };
+
+        let response = run_hook(&plugin, HookPoint::PreTask, &json!({ "n": 1 })).unwrap();
+        assert_eq!(response["allow"], json!(false));
+        assert_eq!(response["echo"], json!({ "n": 1 }));
+        assert!(run_hook(&plugin, HookPoint::PostDeploy, &json!({})).is_err());
+    }
+
+    #[test]
+    fn palette_items_skip_malformed_entries() {
+        let response = json!({ "entries": [
+            { "label": "Open runbook", "args": ["runbook"], "description": "docs" },
+            { "args": ["missing-label"] },
+        ]});
+        let items = parse_palette_items("ops", &response);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].plugin, "ops");
+        assert_eq!(items[0].args, vec!["runbook".to_string()]);
+        assert!(parse_palette_items("ops", &Value::Null).is_empty());
+    }
+}
@@ -9,13 +9,20 @@ use std::time::Duration;
 
 use anyhow::{Context, Result, bail};
 
-use crate::cli::{KillOpts, ProcessOpts, TaskLogsOpts};
+use crate::cli::{KillOpts, ProcessOpts, PsAction, PsAdoptOpts, PsScanOpts, TaskLogsOpts};
+use crate::config::Config;
 use crate::projects;
-use crate::running;
+use crate::proxy;
+use crate::running::{self, RunningProcess};
 use crate::tasks;
 
 /// Show running processes for a project (or all projects)
 pub fn show_project_processes(opts: ProcessOpts) -> Result<()> {
+    match opts.action {
+        Some(PsAction::Adopt(adopt)) => return adopt_process(adopt),
+        Some(PsAction::Scan(scan)) => return scan_processes(scan),
+        None => {}
+    }
     if opts.all {
         show_all_processes()
     } else {
@@ -50,6 +57,9 @@ fn show_processes_for_project(config_path: &Path, project_name: Option<&str>) ->
         if proc.used_flox {
             println!("    (flox environment)");
         }
+        if proc.adopted {
+            println!("    (adopted)");
+        }
     }
 
     Ok(())
@@ -76,7 +86,11 @@ fn show_all_processes() -> Result<()> {
         println!("\n{} ({}):", project_name, project_root);
         for proc in processes {
             let runtime = format_runtime(proc.started_at);
-            println!("  {} [pid: {}] - {}", proc.task_name, proc.pid, runtime);
+            let adopted = if proc.adopted { " (adopted)" } else { "" };
+            println!(
+                "  {} [pid: {}] - {}{}",
+                proc.task_name, proc.pid, runtime, adopted
+            );
         }
     }
 
@@ -125,7 +139,10 @@ fn kill_by_pid(pid: u32, force: bool, timeout: u64) -> Result<()> {
     let pgid = entry.map(|e| e.pgid).unwrap_or(pid);
     let task_name = entry.map(|e| e.task_name.as_str()).unwrap_or("unknown");
 
-    terminate_process_group(pgid, force, timeout)?;
+    match entry {
+        Some(proc) => terminate(proc, force, timeout)?,
+        None => terminate_process_group(pgid, force, timeout)?,
+    }
     running::unregister_process(pid)?;
 
     println!("Killed {} (pid: {}, pgid: {})", task_name, pid, pgid);
@@ -141,7 +158,7 @@ fn kill_by_task(config_path: &Path, task: &str, force: bool, timeout: u64) -> Re
     }
 
     for proc in matching {
-        terminate_process_group(proc.pgid, force, timeout)?;
+        terminate(proc, force, timeout)?;
         running::unregister_process(proc.pid)?;
         println!("Killed {} (pid: {})", proc.task_name, proc.pid);
     }
@@ -158,7 +175,7 @@ fn kill_all_for_project(config_path: &Path, force: bool, timeout: u64) -> Result
     }
 
     for proc in &processes {
-        terminate_process_group(proc.pgid, force, timeout)?;
+        terminate(proc, force, timeout)?;
         running::unregister_process(proc.pid)?;
         println!("Killed {} (pid: {})", proc.task_name, proc.pid);
     }
@@ -166,36 +183,54 @@ fn kill_all_for_project(config_path: &Path, force: bool, timeout: u64) -> Result
     Ok(())
 }
 
+/// Whether killing `proc` should signal its whole process group. An adopted process that
+/// doesn't lead its own group shares it with whatever launched it (a script, a tmux pane),
+/// so only the process itself is signalled then.
+fn signals_group(proc: &RunningProcess) -> bool {
+    !proc.adopted || proc.pgid == proc.pid
+}
+
+fn terminate(proc: &RunningProcess, force: bool, timeout: u64) -> Result<()> {
+    if signals_group(proc) {
+        terminate_process_group(proc.pgid, force, timeout)
+    } else {
+        send_signals(&proc.pid.to_string(), proc.pid, force, timeout)
+    }
+}
+
 fn terminate_process_group(pgid: u32, force: bool, timeout: u64) -> Result<()> {
+    send_signals(&format!("-{}", pgid), pgid, force, timeout)
+}
+
+/// Send SIGTERM (or SIGKILL when `force`) to `target` as passed to `kill`, escalating to
+/// SIGKILL if `pid` is still alive after `timeout` seconds.
+fn send_signals(target: &str, pid: u32, force: bool, timeout: u64) -> Result<()> {
     #[cfg(unix)]
     {
         if force {
-            // Immediate SIGKILL to process group
+            // Immediate SIGKILL
             Command::new("kill")
                 .arg("-KILL")
-                .arg(format!("-{}", pgid))
+                .arg(target)
                 .status()
                 .context("failed to send SIGKILL")?;
         } else {
-            // Graceful SIGTERM to process group
-            let _ = Command::new("kill")
-                .arg("-TERM")
-                .arg(format!("-{}", pgid))
-                .status();
+            // Graceful SIGTERM
+            let _ = Command::new("kill").arg("-TERM").arg(target).status();
 
             // Wait for process to exit
             for _ in 0..timeout {
                 thread::sleep(Duration::from_secs(1));
-                if !running::process_alive(pgid) {
+                if !running::process_alive(pid) {
                     return Ok(());
                 }
             }
 
             // Force kill if still alive
-            if running::process_alive(pgid) {
+            if running::process_alive(pid) {
                 Command::new("kill")
                     .arg("-KILL")
-                    .arg(format!("-{}", pgid))
+                    .arg(target)
                     .status()
                     .context("failed to send SIGKILL after timeout")?;
             }
@@ -204,8 +239,9 @@ fn terminate_process_group(pgid: u32, force: bool, timeout: u64) -> Result<()> {
 
     #[cfg(windows)]
     {
+        let _ = target;
         Command::new("taskkill")
-            .args(["/PID", &pgid.to_string(), "/T", "/F"])
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
             .status()
             .context("failed to kill process tree")?;
     }
@@ -213,6 +249,344 @@ fn terminate_process_group(pgid: u32, force: bool, timeout: u64) -> Result<()> {
     Ok(())
 }
 
+// ============================================================================
+// Adopting externally-started processes
+// ============================================================================
+
+/// A row from the system process table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SystemProcess {
+    pid: u32,
+    pgid: u32,
+    elapsed_secs: u64,
+    args: String,
+}
+
+/// A process that looks like one (or more) of the project's tasks.
+struct Candidate {
+    process: SystemProcess,
+    tasks: Vec<String>,
+    cwd_checked: bool,
+}
+
+fn adopt_process(opts: PsAdoptOpts) -> Result<()> {
+    let (config_path, cfg) = tasks::load_project_config(opts.config)?;
+    let canonical = config_path.canonicalize()?;
+    if !cfg.tasks.iter().any(|task| task.name == opts.task) {
+        bail!(
+            "task '{}' not found in {}",
+            opts.task,
+            config_path.display()
+        );
+    }
+    if !running::process_alive(opts.pid) {
+        bail!("no running process with pid {}", opts.pid);
+    }
+    let tracked = running::load_running_processes()?;
+    if let Some(existing) = tracked
+        .projects
+        .values()
+        .flatten()
+        .find(|p| p.pid == opts.pid)
+    {
+        bail!(
+            "pid {} is already tracked as '{}' ({})",
+            opts.pid,
+            existing.task_name,
+            existing.project_root.display()
+        );
+    }
+    let process = system_processes()?
+        .into_iter()
+        .find(|process| process.pid == opts.pid);
+    register_adopted(&canonical, &cfg, &opts.task, opts.pid, process.as_ref())
+}
+
+fn register_adopted(
+    config_path: &Path,
+    cfg: &Config,
+    task: &str,
+    pid: u32,
+    process: Option<&SystemProcess>,
+) -> Result<()> {
+    let project_root = config_path.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let command = process
+        .map(|process| process.args.clone())
+        .or_else(|| {
+            cfg.tasks
+                .iter()
+                .find(|t| t.name == task)
+                .map(|t| t.command.clone())
+        })
+        .unwrap_or_default();
+    let started_at = process
+        .map(|process| running::now_ms().saturating_sub(u128::from(process.elapsed_secs) * 1000))
+        .unwrap_or_else(running::now_ms);
+    let log_path = process_stdout_file(pid);
+
+    running::register_process(RunningProcess {
+        pid,
+        pgid: process
+            .map(|process| process.pgid)
+            .or_else(|| running::get_pgid(pid))
+            .unwrap_or(pid),
+        task_name: task.to_string(),
+        command,
+        started_at,
+        config_path: config_path.to_path_buf(),
+        project_root,
+        used_flox: false,
+        project_name: cfg.project_name.clone(),
+        adopted: true,
+        log_path: log_path.clone(),
+    })?;
+
+    println!("Adopted pid {} as '{}'.", pid, task);
+    match log_path {
+        Some(path) => println!("  Logs: f logs {} (reads {})", task, path.display()),
+        None => println!(
+            "  stdout isn't a file, so `f logs {}` has nothing to show.",
+            task
+        ),
+    }
+    Ok(())
+}
+
+fn scan_processes(opts: PsScanOpts) -> Result<()> {
+    let (config_path, cfg) = tasks::load_project_config(opts.config)?;
+    let canonical = config_path.canonicalize()?;
+    let project_root = canonical.parent().unwrap_or(Path::new(".")).to_path_buf();
+
+    let tracked = running::load_running_processes()?;
+    let tracked: Vec<&RunningProcess> = tracked.projects.values().flatten().collect();
+    let running_tasks: Vec<&str> = tracked
+        .iter()
+        .filter(|p| p.config_path == canonical)
+        .map(|p| p.task_name.as_str())
+        .collect();
+    let own_pid = std::process::id();
+
+    let processes: Vec<SystemProcess> = system_processes()?
+        .into_iter()
+        .filter(|process| process.pid != own_pid)
+        .filter(|process| {
+            !tracked
+                .iter()
+                .any(|t| t.pid == process.pid || t.pgid == process.pgid)
+        })
+        .collect();
+
+    let mut candidates: Vec<Candidate> = Vec::new();
+    for process in processes {
+        let tasks: Vec<String> = cfg
+            .tasks
+            .iter()
+            .filter(|task| !running_tasks.contains(&task.name.as_str()))
+            .filter(|task| command_matches(&task.command, &process.args))
+            .map(|task| task.name.clone())
+            .collect();
+        if tasks.is_empty() {
+            continue;
+        }
+        let cwd = process_cwd(process.pid);
+        if let Some(cwd) = &cwd
+            && !cwd.starts_with(&project_root)
+        {
+            continue;
+        }
+        candidates.push(Candidate {
+            process,
+            tasks,
+            cwd_checked: cwd.is_some(),
+        });
+    }
+    let candidates = one_per_group(candidates);
+
+    if candidates.is_empty() {
+        println!("No untracked processes look like tasks in this project.");
+        return Ok(());
+    }
+
+    println!("Untracked processes matching project tasks:");
+    for candidate in &candidates {
+        let process = &candidate.process;
+        let started_at = running::now_ms().saturating_sub(u128::from(process.elapsed_secs) * 1000);
+        println!(
+            "  {} [pid: {}] - {}{}",
+            candidate.tasks.join(", "),
+            process.pid,
+            format_runtime(started_at),
+            if candidate.cwd_checked {
+                ""
+            } else {
+                " (working directory unknown)"
+            }
+        );
+        println!("    {}", process.args);
+        if !opts.adopt {
+            match candidate.tasks.as_slice() {
+                [task] => println!("    f ps adopt {} --task {}", process.pid, task),
+                _ => println!("    f ps adopt {} --task <task>", process.pid),
+            }
+        }
+    }
+
+    if opts.adopt {
+        println!();
+        for candidate in &candidates {
+            match candidate.tasks.as_slice() {
+                [task] => register_adopted(
+                    &canonical,
+                    &cfg,
+                    task,
+                    candidate.process.pid,
+                    Some(&candidate.process),
+                )?,
+                _ => println!(
+                    "Skipped pid {}: matches several tasks ({}).",
+                    candidate.process.pid,
+                    candidate.tasks.join(", ")
+                ),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Keep one candidate per process group, preferring the group leader, so a shell wrapper
+/// and the server it started don't show up twice.
+fn one_per_group(mut candidates: Vec<Candidate>) -> Vec<Candidate> {
+    candidates.sort_by_key(|c| {
+        (
+            c.process.pgid,
+            c.process.pid != c.process.pgid,
+            c.process.pid,
+        )
+    });
+    candidates.dedup_by_key(|c| c.process.pgid);
+    candidates.sort_by_key(|c| c.process.pid);
+    candidates
+}
+
+/// Whether a process command line looks like it was started from a task command.
+///
+/// Uses the last `&&`/`;` segment of the task's first line (so `cd web && bun dev` matches
+/// `bun dev`) up to any pipe, skips leading `KEY=value` assignments, and requires the program name followed
+/// by the remaining words in order. Program names are compared without directory or
+/// extension, which covers `node_modules/.bin/vite` and `pnpm.cjs`-style wrappers.
+fn command_matches(task_command: &str, args: &str) -> bool {
+    let first_line = task_command.lines().next().unwrap_or_default();
+    let segment = first_line
+        .rsplit(['&', ';'])
+        .map(str::trim)
+        .find(|segment| !segment.is_empty())
+        .unwrap_or_default();
+    let pipeline_head = segment.split('|').next().unwrap_or_default();
+    let mut words = pipeline_head
+        .split_whitespace()
+        .skip_while(|word| is_env_assignment(word) || *word == "exec");
+    let Some(program) = words.next().map(program_name) else {
+        return false;
+    };
+    let rest: Vec<&str> = words.collect();
+
+    let argv: Vec<&str> = args.split_whitespace().collect();
+    argv.iter()
+        .enumerate()
+        .any(|(idx, arg)| program_name(arg) == program && is_subsequence(&rest, &argv[idx + 1..]))
+}
+
+fn is_env_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(key, _)| {
+        !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+fn program_name(word: &str) -> &str {
+    let base = word.rsplit('/').next().unwrap_or(word);
+    base.split('.')
+        .next()
+        .filter(|s| !s.is_empty())
+        .unwrap_or(base)
+}
+
+fn is_subsequence(needle: &[&str], haystack: &[&str]) -> bool {
+    let mut hay = haystack.iter();
+    needle
+        .iter()
+        .all(|word| hay.any(|candidate| candidate == word))
+}
+
+/// Read the system process table via `ps`.
+fn system_processes() -> Result<Vec<SystemProcess>> {
+    let output = Command::new("ps")
+        .args(["-axww", "-o", "pid=,pgid=,etime=,args="])
+        .output()
+        .context("failed to run ps")?;
+    if !output.status.success() {
+        bail!("ps exited with {}", output.status);
+    }
+    Ok(parse_ps_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn parse_ps_output(output: &str) -> Vec<SystemProcess> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid = fields.next()?.parse().ok()?;
+            let pgid = fields.next()?.parse().ok()?;
+            let elapsed_secs = parse_etime(fields.next()?)?;
+            let args = fields.collect::<Vec<_>>().join(" ");
+            (!args.is_empty()).then_some(SystemProcess {
+                pid,
+                pgid,
+                elapsed_secs,
+                args,
+            })
+        })
+        .collect()
+}
+
+/// Parse ps `etime` (`[[dd-]hh:]mm:ss`) into seconds.
+fn parse_etime(raw: &str) -> Option<u64> {
+    let (days, clock) = match raw.split_once('-') {
+        Some((days, clock)) => (days.parse::<u64>().ok()?, clock),
+        None => (0, raw),
+    };
+    let mut secs = 0u64;
+    for part in clock.split(':') {
+        secs = secs * 60 + part.parse::<u64>().ok()?;
+    }
+    Some(days * 86_400 + secs)
+}
+
+/// Working directory of a process, when the OS lets us read it.
+fn process_cwd(pid: u32) -> Option<PathBuf> {
+    fs::read_link(format!("/proc/{pid}/cwd"))
+        .ok()
+        .or_else(|| lsof_fd_path(pid, "cwd"))
+}
+
+/// The file a process writes stdout to, if it is a regular file (not a terminal or pipe).
+fn process_stdout_file(pid: u32) -> Option<PathBuf> {
+    let path = fs::read_link(format!("/proc/{pid}/fd/1"))
+        .ok()
+        .or_else(|| lsof_fd_path(pid, "1"))?;
+    path.is_file().then_some(path)
+}
+
+fn lsof_fd_path(pid: u32, fd: &str) -> Option<PathBuf> {
+    let output = Command::new("lsof")
+        .args(["-a", "-p", &pid.to_string(), "-d", fd, "-Fn"])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix('n'))
+        .map(PathBuf::from)
+}
+
 // ============================================================================
 // Task Logs
 // ============================================================================
@@ -286,54 +660,29 @@ pub fn show_task_logs(opts: TaskLogsOpts) -> Result<()> {
         return list_available_logs(opts.all);
     }
 
+    if let Some(ref raw) = opts.trace {
+        return show_trace_logs(raw, &opts);
+    }
+
     if opts.all {
         return show_all_logs(opts.lines);
     }
 
-    // Resolve project: --project flag > flow.toml in cwd > active project
-    let (project_root, config_path, project_name) = if let Some(ref name) = opts.project {
-        // Explicit project name
-        match projects::resolve_project(name)? {
-            Some(entry) => (entry.project_root, entry.config_path, Some(entry.name)),
-            None => {
-                bail!(
-                    "Project '{}' not found. Use `f projects` to see registered projects.",
-                    name
-                );
-            }
-        }
-    } else if opts.config.exists() {
-        // flow.toml in current directory
-        let (cfg_path, cfg) = tasks::load_project_config(opts.config.clone())?;
-        let canonical = cfg_path.canonicalize().unwrap_or_else(|_| cfg_path.clone());
-        let root = cfg_path
-            .parent()
-            .unwrap_or(Path::new("."))
-            .canonicalize()
-            .unwrap_or_else(|_| cfg_path.parent().unwrap_or(Path::new(".")).to_path_buf());
-        (root, canonical, cfg.project_name)
-    } else if let Some(active) = projects::get_active_project() {
-        // Fall back to active project
-        match projects::resolve_project(&active)? {
-            Some(entry) => (entry.project_root, entry.config_path, Some(entry.name)),
-            None => {
-                bail!(
-                    "Active project '{}' not found. Use `f projects` to see registered projects.",
-                    active
-                );
-            }
-        }
-    } else {
-        bail!(
-            "No flow.toml in current directory and no active project set.\nRun a task in a project first, or use: f logs -p <project>"
-        );
-    };
+    let (project_root, config_path, project_name) = resolve_logs_project(&opts)?;
+
+    let running = running::get_project_processes(&config_path).unwrap_or_default();
 
     // If no task specified, try to find available logs - prefer running tasks
     let task_name = match opts.task {
         Some(name) => name,
         None => {
-            let logs = get_project_log_files(&project_root, project_name.as_deref());
+            let mut logs = get_project_log_files(&project_root, project_name.as_deref());
+            // Adopted processes log to their own file rather than flow's log dir.
+            for proc in running.iter().filter(|p| p.log_path.is_some()) {
+                if !logs.contains(&proc.task_name) {
+                    logs.push(proc.task_name.clone());
+                }
+            }
 
             if logs.is_empty() {
                 println!("No logs found for this project.");
@@ -341,7 +690,6 @@ pub fn show_task_logs(opts: TaskLogsOpts) -> Result<()> {
             }
 
             // Check for running tasks
-            let running = running::get_project_processes(&config_path).unwrap_or_default();
             let running_tasks: Vec<_> = running.iter().map(|p| p.task_name.clone()).collect();
             let running_logs: Vec<_> = logs
                 .iter()
@@ -373,7 +721,11 @@ pub fn show_task_logs(opts: TaskLogsOpts) -> Result<()> {
         }
     };
 
-    let log_path = get_log_path(&project_root, project_name.as_deref(), &task_name);
+    let log_path = running
+        .iter()
+        .find(|p| p.task_name == task_name)
+        .and_then(|p| p.log_path.clone())
+        .unwrap_or_else(|| get_log_path(&project_root, project_name.as_deref(), &task_name));
 
     if !log_path.exists() {
         bail!(
@@ -392,6 +744,164 @@ pub fn show_task_logs(opts: TaskLogsOpts) -> Result<()> {
     Ok(())
 }
 
+fn resolve_logs_project(opts: &TaskLogsOpts) -> Result<(PathBuf, PathBuf, Option<String>)> {
+    resolve_log_project(&opts.config, opts.project.as_deref())
+}
+
+/// Resolve the project for `f logs` and `f tail`: --project flag > flow.toml in cwd > active
+/// project. Returns the project root, config path, and project name.
+pub(crate) fn resolve_log_project(
+    config: &Path,
+    project: Option<&str>,
+) -> Result<(PathBuf, PathBuf, Option<String>)> {
+    if let Some(name) = project {
+        // Explicit project name
+        match projects::resolve_project(name)? {
+            Some(entry) => Ok((entry.project_root, entry.config_path, Some(entry.name))),
+            None => {
+                bail!(
+                    "Project '{}' not found. Use `f projects` to see registered projects.",
+                    name
+                );
+            }
+        }
+    } else if config.exists() {
+        // flow.toml in current directory
+        let (cfg_path, cfg) = tasks::load_project_config(config.to_path_buf())?;
+        let canonical = cfg_path.canonicalize().unwrap_or_else(|_| cfg_path.clone());
+        let root = cfg_path
+            .parent()
+            .unwrap_or(Path::new("."))
+            .canonicalize()
+            .unwrap_or_else(|_| cfg_path.parent().unwrap_or(Path::new(".")).to_path_buf());
+        Ok((root, canonical, cfg.project_name))
+    } else if let Some(active) = projects::get_active_project() {
+        // Fall back to active project
+        match projects::resolve_project(&active)? {
+            Some(entry) => Ok((entry.project_root, entry.config_path, Some(entry.name))),
+            None => {
+                bail!(
+                    "Active project '{}' not found. Use `f projects` to see registered projects.",
+                    active
+                );
+            }
+        }
+    } else {
+        bail!(
+            "No flow.toml in current directory and no active project set.\nRun a task in a project first, or use: f logs -p <project>"
+        );
+    }
+}
+
+/// Show the proxy request for a trace ID and the task log lines that mention it.
+fn show_trace_logs(raw: &str, opts: &TaskLogsOpts) -> Result<()> {
+    let trace_id = proxy::trace::parse_trace_id(raw).ok_or_else(|| {
+        anyhow::anyhow!("invalid trace ID '{raw}' (expected the hex value of x-flow-trace-id)")
+    })?;
+    let needle = proxy::trace::format_trace_id(trace_id);
+
+    match proxy::trace::find_trace(trace_id) {
+        Some((record, age)) => println!(
+            "Proxy: {} {} -> {} in {}ms (upstream {}ms), {}",
+            format!("{:?}", record.method()).to_uppercase(),
+            record.path(),
+            record.status(),
+            record.latency_us() / 1000,
+            record.upstream_latency_us() / 1000,
+            format_relative_time(age.as_secs())
+        ),
+        None => println!("Proxy: {needle} is not in the current proxy trace buffer"),
+    }
+
+    let files = if opts.all {
+        all_log_files()
+    } else {
+        let (project_root, config_path, project_name) = resolve_logs_project(opts)?;
+        project_log_files(
+            &project_root,
+            &config_path,
+            project_name.as_deref(),
+            opts.task.as_deref(),
+        )
+    };
+
+    let mut matches = 0;
+    for (label, path) in files {
+        let Ok(file) = File::open(&path) else {
+            continue;
+        };
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
+            if line.contains(&needle) {
+                println!("{label}: {line}");
+                matches += 1;
+            }
+        }
+    }
+    if matches == 0 {
+        println!(
+            "No task log lines mention {needle}. Log the `{}` request header as `{}=<id>` to correlate.",
+            proxy::trace::TRACE_HEADER,
+            proxy::trace::TRACE_LOG_KEY
+        );
+    }
+    Ok(())
+}
+
+/// Log files of the project's tasks (or just `task`), labelled by task name, including the
+/// files of adopted processes that log outside flow's log dir.
+pub(crate) fn project_log_files(
+    project_root: &Path,
+    config_path: &Path,
+    project_name: Option<&str>,
+    task: Option<&str>,
+) -> Vec<(String, PathBuf)> {
+    let mut files: Vec<(String, PathBuf)> = match task {
+        Some(task) => vec![(
+            task.to_string(),
+            get_log_path(project_root, project_name, task),
+        )],
+        None => get_project_log_files(project_root, project_name)
+            .into_iter()
+            .map(|task| {
+                let path = get_log_path(project_root, project_name, &task);
+                (task, path)
+            })
+            .collect(),
+    };
+    for proc in running::get_project_processes(config_path).unwrap_or_default() {
+        if let Some(path) = proc.log_path
+            && task.is_none_or(|task| task == proc.task_name)
+            && !files.iter().any(|(_, existing)| *existing == path)
+        {
+            files.push((proc.task_name, path));
+        }
+    }
+    files
+}
+
+/// Every `<project>/<task>.log` under the log dir, labelled `project/task`.
+fn all_log_files() -> Vec<(String, PathBuf)> {
+    let mut files = Vec::new();
+    let Ok(projects) = fs::read_dir(log_dir()) else {
+        return files;
+    };
+    for project in projects.flatten() {
+        let Ok(entries) = fs::read_dir(project.path()) else {
+            continue;
+        };
+        let project_name = project.file_name().to_string_lossy().to_string();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "log")
+                && let Some(task) = path.file_stem().and_then(|stem| stem.to_str())
+            {
+                files.push((format!("{project_name}/{task}"), path));
+            }
+        }
+    }
+    files
+}
+
 fn show_all_logs(lines: usize) -> Result<()> {
     let base = log_dir();
     if !base.exists() {
@@ -696,3 +1206,56 @@ fn show_hub_task_logs(task_id: &str, follow: bool) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ps_rows_and_elapsed_times() {
+        let output = "  101   101       05:07 bun run dev\n 202 101 1-02:03:04 node /app/node_modules/.bin/vite --port 5173\nbogus\n";
+        let rows = parse_ps_output(output);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].elapsed_secs, 307);
+        assert_eq!(rows[1].pgid, 101);
+        assert_eq!(rows[1].elapsed_secs, 86_400 + 2 * 3600 + 3 * 60 + 4);
+        assert_eq!(rows[1].args, "node /app/node_modules/.bin/vite --port 5173");
+    }
+
+    #[test]
+    fn command_matching_follows_task_commands() {
+        assert!(command_matches("bun dev", "bun run dev"));
+        assert!(command_matches(
+            "cd web && PORT=3000 vite --host",
+            "node /w/node_modules/.bin/vite --host 0.0.0.0"
+        ));
+        assert!(command_matches(
+            "pnpm dev | tee dev.log",
+            "node /usr/lib/pnpm.cjs dev"
+        ));
+        assert!(!command_matches("bun dev", "bun test"));
+        assert!(!command_matches("cargo watch -x run", "cargo build"));
+        assert!(!command_matches("", "bun dev"));
+    }
+
+    #[test]
+    fn adopted_processes_outside_their_own_group_are_signalled_alone() {
+        let proc = |pid: u32, pgid: u32, adopted: bool| RunningProcess {
+            pid,
+            pgid,
+            task_name: "dev".to_string(),
+            command: "bun dev".to_string(),
+            started_at: 0,
+            config_path: PathBuf::from("/p/flow.toml"),
+            project_root: PathBuf::from("/p"),
+            used_flox: false,
+            project_name: None,
+            adopted,
+            log_path: None,
+        };
+        assert!(signals_group(&proc(10, 10, false)));
+        assert!(signals_group(&proc(10, 7, false)));
+        assert!(signals_group(&proc(10, 10, true)));
+        assert!(!signals_group(&proc(10, 7, true)));
+    }
+}
@@ -0,0 +1,169 @@
+//! Global `--project <name>` / `-C <path>` selector.
+//!
+//! Both options are taken off the argument list before clap (and the task frontdoor) see it,
+//! and the process switches into the selected project root. Every command then resolves
+//! flow.toml from the cwd as usual, so `f --project api logs` behaves like `cd api && f logs`.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+
+use crate::{config, projects};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProjectSelector {
+    /// A name from the project registry (`f projects`).
+    Name(String),
+    /// A directory, like `git -C`.
+    Dir(String),
+}
+
+/// Strip a leading `--project`/`-C` from `args` (binary first) and chdir into the project.
+pub fn apply(args: &mut Vec<String>) -> Result<()> {
+    let Some(selector) = extract(args)? else {
+        return Ok(());
+    };
+    let root = resolve(&selector)?;
+    std::env::set_current_dir(&root)
+        .with_context(|| format!("failed to switch to {}", root.display()))
+}
+
+/// Boolean top-level flags that may precede a selector. Anything else ends the scan, so an
+/// unknown option never hides a later argument from its subcommand.
+const TOP_LEVEL_FLAGS: &[&str] = &["-h", "--help", "-V", "--version", "--help-full"];
+
+/// Remove selector options that appear before the subcommand and return the last one given.
+fn extract(args: &mut Vec<String>) -> Result<Option<ProjectSelector>> {
+    let mut selector = None;
+    let mut index = 1;
+    while index < args.len() {
+        let arg = args[index].as_str();
+        let (parsed, consumed) = if let Some(name) = arg.strip_prefix("--project=") {
+            (ProjectSelector::Name(name.to_string()), 1)
+        } else if let Some(dir) = arg.strip_prefix("-C").filter(|dir| !dir.is_empty()) {
+            (ProjectSelector::Dir(dir.to_string()), 1)
+        } else if arg == "--project" || arg == "-C" {
+            let Some(value) = args.get(index + 1) else {
+                bail!("{arg} requires a value");
+            };
+            let parsed = if arg == "-C" {
+                ProjectSelector::Dir(value.clone())
+            } else {
+                ProjectSelector::Name(value.clone())
+            };
+            (parsed, 2)
+        } else if TOP_LEVEL_FLAGS.contains(&arg) {
+            // Other top-level flags (e.g. --help) stay in place.
+            index += 1;
+            continue;
+        } else {
+            break;
+        };
+        args.drain(index..index + consumed);
+        selector = Some(parsed);
+    }
+    Ok(selector)
+}
+
+fn resolve(selector: &ProjectSelector) -> Result<PathBuf> {
+    match selector {
+        ProjectSelector::Dir(raw) => {
+            let dir = config::expand_path(raw);
+            if !dir.is_dir() {
+                bail!("-C {}: not a directory", dir.display());
+            }
+            Ok(dir.canonicalize().unwrap_or(dir))
+        }
+        ProjectSelector::Name(name) => {
+            // A registry read error falls through to the directory-name match below.
+            let mut registry_err = None;
+            match projects::resolve_project(name) {
+                Ok(Some(entry)) => return Ok(entry.project_root),
+                Ok(None) => {}
+                Err(err) => registry_err = Some(err),
+            }
+            let known = projects::list_projects().unwrap_or_else(|err| {
+                registry_err.get_or_insert(err);
+                Vec::new()
+            });
+            // Fall back to a registered root whose directory carries the name.
+            if let Some(entry) = known.iter().find(|entry| {
+                entry
+                    .project_root
+                    .file_name()
+                    .is_some_and(|dir| dir == name.as_str())
+            }) {
+                return Ok(entry.project_root.clone());
+            }
+            if let Some(err) = registry_err {
+                bail!(
+                    "project '{name}' could not be resolved: failed to read the project registry ({err:#}); use -C <path>"
+                );
+            }
+            let names: Vec<&str> = known
+                .iter()
+                .take(10)
+                .map(|entry| entry.name.as_str())
+                .collect();
+            if names.is_empty() {
+                bail!(
+                    "project '{name}' is not registered; run a task in it once (with `name` set in flow.toml) or use -C <path>"
+                );
+            }
+            bail!(
+                "project '{name}' is not registered (known: {}); see `f projects` or use -C <path>",
+                names.join(", ")
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn strips_leading_selectors_only() {
+        let mut argv = args(&["f", "--project", "api", "logs", "-C", "web"]);
+        assert_eq!(
+            extract(&mut argv).unwrap(),
+            Some(ProjectSelector::Name("api".to_string()))
+        );
+        assert_eq!(argv, args(&["f", "logs", "-C", "web"]));
+
+        let mut argv = args(&["f", "-C~/code/web", "--project=api", "run", "dev"]);
+        assert_eq!(
+            extract(&mut argv).unwrap(),
+            Some(ProjectSelector::Name("api".to_string()))
+        );
+        assert_eq!(argv, args(&["f", "run", "dev"]));
+    }
+
+    #[test]
+    fn leaves_other_arguments_alone() {
+        let mut argv = args(&["f", "--help"]);
+        assert_eq!(extract(&mut argv).unwrap(), None);
+        assert_eq!(argv, args(&["f", "--help"]));
+
+        let mut argv = args(&["f", "--verbose", "--project", "api", "logs"]);
+        assert_eq!(extract(&mut argv).unwrap(), None);
+        assert_eq!(argv.len(), 5);
+
+        let mut argv = args(&["f", "--help-full", "-C", "web"]);
+        assert_eq!(
+            extract(&mut argv).unwrap(),
+            Some(ProjectSelector::Dir("web".to_string()))
+        );
+        assert_eq!(argv, args(&["f", "--help-full"]));
+
+        let mut argv = args(&["f", "env", "history", "--project", "KEY"]);
+        assert_eq!(extract(&mut argv).unwrap(), None);
+        assert_eq!(argv.len(), 5);
+
+        assert!(extract(&mut args(&["f", "-C"])).is_err());
+    }
+}
@@ -0,0 +1,543 @@
+//! `f status --project`: one panel with the live state of the current project — running
+//! tasks and their ports, the last commit gate, the last deploy and its health, .env drift
+//! against the checked-in example, and the proxy error rate over the last ten minutes.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::config::{self, Config};
+use crate::deploy_watch::{self, format_age};
+use crate::env_template::EnvTemplate;
+use crate::proxy::control::{self, ControlRequest};
+use crate::proxy::trace::{self, TraceBuffer};
+use crate::runs::{self, RunKind, RunRecord};
+use crate::{project_snapshot, running};
+
+/// Window for the proxy error rate.
+const PROXY_WINDOW: Duration = Duration::from_secs(10 * 60);
+/// Upper bound on trace records scanned for the error rate.
+const MAX_PROXY_RECORDS: usize = 100_000;
+/// Example files .env is compared against, in order of preference.
+const ENV_REFERENCES: [&str; 3] = [".env.example", ".env.template", ".env.sample"];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectStatus {
+    pub project: String,
+    pub project_root: PathBuf,
+    pub tasks: Vec<RunningTask>,
+    pub last_commit_gate: Option<RunSummary>,
+    pub last_deploy: Option<RunSummary>,
+    pub services: Vec<ServiceHealth>,
+    pub env_drift: Option<EnvDrift>,
+    pub proxy: Option<ProxyErrors>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunningTask {
+    pub name: String,
+    pub pid: u32,
+    pub started_ms: u128,
+    pub ports: Vec<u16>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunSummary {
+    pub id: String,
+    pub name: String,
+    pub success: bool,
+    pub started_ms: i64,
+    pub duration_ms: i64,
+    pub detail: Option<String>,
+}
+
+impl From<RunRecord> for RunSummary {
+    fn from(record: RunRecord) -> Self {
+        Self {
+            id: record.id,
+            name: record.name,
+            success: record.success,
+            started_ms: record.started_ms,
+            duration_ms: record.duration_ms,
+            detail: record.detail,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceHealth {
+    pub service: String,
+    pub ok: bool,
+    pub detail: String,
+    pub checked_ms: i64,
+    pub uptime_24h: Option<f64>,
+}
+
+/// Keys that differ between `.env` and the checked-in example.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvDrift {
+    pub reference: String,
+    /// Listed in the example but missing from `.env` (or `.env` does not exist).
+    pub missing: Vec<String>,
+    /// Present in `.env` but not listed in the example.
+    pub undocumented: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyErrors {
+    pub requests: u64,
+    pub errors: u64,
+}
+
+/// Gather the status of the project containing `start`.
+pub fn collect(start: &Path) -> Result<ProjectStatus> {
+    let config_path = project_snapshot::find_flow_toml_upwards(start)
+        .context("flow.toml not found; run `f status --project` inside a Flow project")?;
+    let project_root = config_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| start.to_path_buf());
+    let cfg = config::load(&config_path)?;
+    let project = project_name(&cfg, &project_root);
+
+    let tasks = running_tasks(&config_path).unwrap_or_else(|err| {
+        tracing::warn!(?err, "failed to read running tasks");
+        Vec::new()
+    });
+
+    let (last_commit_gate, last_deploy) = match runs::open_runs_db() {
+        Ok(conn) => (
+            runs::latest_for_root(&conn, RunKind::Commit, &project_root)
+                .ok()
+                .flatten()
+                .map(RunSummary::from),
+            runs::latest_for_root(&conn, RunKind::Deploy, &project_root)
+                .ok()
+                .flatten()
+                .map(RunSummary::from),
+        ),
+        Err(err) => {
+            tracing::warn!(?err, "failed to open runs db");
+            (None, None)
+        }
+    };
+
+    Ok(ProjectStatus {
+        services: service_health(&project).unwrap_or_default(),
+        env_drift: env_drift(&project_root),
+        proxy: proxy_errors(
+            &trace::default_trace_dir(),
+            PROXY_WINDOW,
+            &project_backends(&cfg, &project),
+        ),
+        project,
+        project_root,
+        tasks,
+        last_commit_gate,
+        last_deploy,
+    })
+}
+
+fn project_name(cfg: &Config, project_root: &Path) -> String {
+    cfg.project_name.clone().unwrap_or_else(|| {
+        project_root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| project_root.display().to_string())
+    })
+}
+
+fn running_tasks(config_path: &Path) -> Result<Vec<RunningTask>> {
+    let processes = running::get_project_processes(config_path)?;
+    if processes.is_empty() {
+        return Ok(Vec::new());
+    }
+    let listeners = listening_ports();
+    Ok(processes
+        .into_iter()
+        .map(|process| {
+            let ports: BTreeSet<u16> = listeners
+                .iter()
+                .filter(|(pid, _)| {
+                    *pid == process.pid || running::get_pgid(*pid) == Some(process.pgid)
+                })
+                .map(|(_, port)| *port)
+                .collect();
+            RunningTask {
+                name: process.task_name,
+                pid: process.pid,
+                started_ms: process.started_at,
+                ports: ports.into_iter().collect(),
+            }
+        })
+        .collect())
+}
+
+/// `(pid, port)` for every listening TCP socket, via `lsof`. Empty when lsof is unavailable.
+fn listening_ports() -> Vec<(u32, u16)> {
+    let output = Command::new("lsof")
+        .args(["-nP", "-iTCP", "-sTCP:LISTEN", "-Fpn"])
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_lsof_listeners(&String::from_utf8_lossy(&output.stdout))
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Parse `lsof -Fpn` output (`p<pid>` lines followed by `n<addr>:<port>` lines).
+fn parse_lsof_listeners(output: &str) -> Vec<(u32, u16)> {
+    let mut listeners = BTreeSet::new();
+    let mut pid = None;
+    for line in output.lines() {
+        if let Some(value) = line.strip_prefix('p') {
+            pid = value.trim().parse::<u32>().ok();
+        } else if let (Some(value), Some(pid)) = (line.strip_prefix('n'), pid)
+            && let Some(port) = value
+                .rsplit_once(':')
+                .and_then(|(_, port)| port.parse::<u16>().ok())
+        {
+            listeners.insert((pid, port));
+        }
+    }
+    listeners.into_iter().collect()
+}
+
+fn service_health(project: &str) -> Result<Vec<ServiceHealth>> {
+    let conn = crate::db::open_db()?;
+    deploy_watch::init_schema(&conn)?;
+    let now_ms = running::now_ms() as i64;
+    Ok(deploy_watch::latest_statuses(&conn, now_ms)?
+        .into_iter()
+        .filter(|status| status.last.project == project)
+        .map(|status| ServiceHealth {
+            service: status.last.service,
+            ok: status.last.ok,
+            detail: status.last.detail,
+            checked_ms: status.last.checked_ms,
+            uptime_24h: status.uptime_24h,
+        })
+        .collect())
+}
+
+fn env_drift(project_root: &Path) -> Option<EnvDrift> {
+    let (reference, content) = ENV_REFERENCES.iter().find_map(|name| {
+        fs::read_to_string(project_root.join(name))
+            .ok()
+            .map(|content| (name.to_string(), content))
+    })?;
+    let expected: BTreeSet<String> = EnvTemplate::parse(&content).keys().into_iter().collect();
+    let actual: BTreeSet<String> = fs::read_to_string(project_root.join(".env"))
+        .map(|content| EnvTemplate::parse(&content).keys().into_iter().collect())
+        .unwrap_or_default();
+    Some(EnvDrift {
+        reference,
+        missing: expected.difference(&actual).cloned().collect(),
+        undocumented: actual.difference(&expected).cloned().collect(),
+    })
+}
+
+/// Proxy backends that belong to this project: its `[[proxies]]` and the routes its tasks
+/// with a `port` register while running.
+fn project_backends(cfg: &Config, project: &str) -> BTreeSet<String> {
+    cfg.proxies
+        .iter()
+        .map(|target| target.name.clone())
+        .chain(
+            cfg.tasks
+                .iter()
+                .filter(|task| task.port.is_some() && task.host.is_none())
+                .map(|task| control::task_backend_name(&task.name, project)),
+        )
+        .collect()
+}
+
+/// Trace indexes of the running proxy's backends that are in `names`. A/B variants are
+/// recorded as `<target>@<variant>` and count towards their target.
+fn backend_indexes(running: &[String], names: &BTreeSet<String>) -> BTreeSet<u8> {
+    running
+        .iter()
+        .enumerate()
+        .filter(|(_, backend)| {
+            let target = backend.split_once('@').map_or(backend.as_str(), |(t, _)| t);
+            names.contains(target)
+        })
+        .filter_map(|(idx, _)| u8::try_from(idx).ok())
+        .collect()
+}
+
+/// Requests and 4xx/5xx responses the proxy recorded for this project's backends within
+/// `window`. `None` when no proxy is running or none of its backends are the project's.
+fn proxy_errors(
+    trace_dir: &Path,
+    window: Duration,
+    backends: &BTreeSet<String>,
+) -> Option<ProxyErrors> {
+    if backends.is_empty() {
+        return None;
+    }
+    let response = control::send(&control::default_socket_path(), &ControlRequest::Backends)
+        .ok()
+        .filter(|response| response.ok)?;
+    let indexes = backend_indexes(&response.backends, backends);
+    if indexes.is_empty() {
+        return None;
+    }
+    let buffer = trace::latest_trace_file(trace_dir).and_then(|path| TraceBuffer::open(&path))?;
+    // Trace timestamps are monotonic nanoseconds, comparable with `now_ns` on the same boot.
+    let cutoff = trace::now_ns().saturating_sub(window.as_nanos() as u64);
+    let mut stats = ProxyErrors {
+        requests: 0,
+        errors: 0,
+    };
+    for record in buffer.recent(MAX_PROXY_RECORDS) {
+        if record.timestamp() == 0
+            || record.timestamp() < cutoff
+            || !indexes.contains(&record.target_idx())
+        {
+            continue;
+        }
+        stats.requests += 1;
+        if record.is_error() {
+            stats.errors += 1;
+        }
+    }
+    Some(stats)
+}
+
+/// Render the status as a boxed panel.
+pub fn render(status: &ProjectStatus, now_ms: i64) -> String {
+    let mut rows: Vec<(&str, String)> = Vec::new();
+
+    if status.tasks.is_empty() {
+        rows.push(("tasks", "none running".to_string()));
+    }
+    for (idx, task) in status.tasks.iter().enumerate() {
+        let ports = if task.ports.is_empty() {
+            String::new()
+        } else {
+            let ports: Vec<String> = task.ports.iter().map(|port| format!(":{port}")).collect();
+            format!(" {}", ports.join(" "))
+        };
+        rows.push((
+            if idx == 0 { "tasks" } else { "" },
+            format!(
+                "{} (pid {}, up {}){}",
+                task.name,
+                task.pid,
+                format_age(now_ms - task.started_ms as i64).trim_end_matches(" ago"),
+                ports
+            ),
+        ));
+    }
+
+    rows.push(("commit", run_line(status.last_commit_gate.as_ref(), now_ms)));
+    rows.push(("deploy", run_line(status.last_deploy.as_ref(), now_ms)));
+    for service in &status.services {
+        let uptime = service
+            .uptime_24h
+            .map(|uptime| format!(", {uptime:.1}% 24h"))
+            .unwrap_or_default();
+        rows.push((
+            "",
+            format!(
+                "{} {} {} (checked {}{})",
+                mark(service.ok),
+                service.service,
+                service.detail,
+                format_age(now_ms - service.checked_ms),
+                uptime
+            ),
+        ));
+    }
+
+    rows.push((
+        "env",
+        match &status.env_drift {
+            None => "no .env.example".to_string(),
+            Some(drift) if drift.missing.is_empty() && drift.undocumented.is_empty() => {
+                format!("✓ .env matches {}", drift.reference)
+            }
+            Some(drift) => {
+                let mut parts = Vec::new();
+                if !drift.missing.is_empty() {
+                    parts.push(format!("missing {}", drift.missing.join(", ")));
+                }
+                if !drift.undocumented.is_empty() {
+                    parts.push(format!(
+                        "not in {}: {}",
+                        drift.reference,
+                        drift.undocumented.join(", ")
+                    ));
+                }
+                format!("⚠ .env {}", parts.join("; "))
+            }
+        },
+    ));
+
+    rows.push((
+        "proxy",
+        match &status.proxy {
+            None => "no proxy running for this project".to_string(),
+            Some(stats) if stats.requests == 0 => "no requests in 10m".to_string(),
+            Some(stats) => format!(
+                "{} {}/{} errors ({:.1}%) in 10m",
+                mark(stats.errors == 0),
+                stats.errors,
+                stats.requests,
+                stats.errors as f64 * 100.0 / stats.requests as f64
+            ),
+        },
+    ));
+
+    let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    let lines: Vec<String> = rows
+        .iter()
+        .map(|(label, value)| format!("{label:<label_width$}  {value}"))
+        .collect();
+    let title = format!(" {} · {} ", status.project, status.project_root.display());
+    let width = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .chain(std::iter::once(title.chars().count() + 1))
+        .max()
+        .unwrap_or(0);
+
+    let mut out = String::new();
+    out.push_str(&format!(
+        "╭─{}{}╮\n",
+        title,
+        "─".repeat(width + 1 - title.chars().count())
+    ));
+    for line in lines {
+        let pad = width - line.chars().count();
+        out.push_str(&format!("│ {}{} │\n", line, " ".repeat(pad)));
+    }
+    out.push_str(&format!("╰{}╯\n", "─".repeat(width + 2)));
+    out
+}
+
+fn run_line(run: Option<&RunSummary>, now_ms: i64) -> String {
+    match run {
+        None => "no runs recorded".to_string(),
+        Some(run) => {
+            let mut line = format!(
+                "{} {} {} ({})",
+                mark(run.success),
+                run.name,
+                format_age(now_ms - run.started_ms),
+                run.id
+            );
+            if !run.success
+                && let Some(detail) = run.detail.as_deref()
+            {
+                let detail = detail.lines().next().unwrap_or(detail);
+                let short: String = detail.chars().take(60).collect();
+                line.push_str(&format!(": {short}"));
+            }
+            line
+        }
+    }
+}
+
+fn mark(ok: bool) -> &'static str {
+    if ok { "✓" } else { "✗" }
+}
+
+/// Handle `f status --project`.
+pub fn run(json: bool, watch: bool) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    loop {
+        let status = collect(&cwd)?;
+        if json {
+            println!("{}", serde_json::to_string_pretty(&status)?);
+        } else {
+            if watch {
+                print!("\x1b[2J\x1b[H");
+            }
+            print!("{}", render(&status, running::now_ms() as i64));
+        }
+        if !watch {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_secs(2));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_lsof_listeners() {
+        let output = "p101\nf5\nn*:3000\nn[::1]:3000\np202\nn127.0.0.1:5432\n";
+        assert_eq!(parse_lsof_listeners(output), vec![(101, 3000), (202, 5432)]);
+    }
+
+    #[test]
+    fn backend_indexes_match_project_targets_and_variants() {
+        let running = vec![
+            "api".to_string(),
+            "dev.other".to_string(),
+            "api@canary".to_string(),
+            "dev.app".to_string(),
+        ];
+        let names: BTreeSet<String> = ["api".to_string(), "dev.app".to_string()].into();
+        assert_eq!(backend_indexes(&running, &names), BTreeSet::from([0, 2, 3]));
+    }
+
+    #[test]
+    fn renders_panel_with_drift_and_failed_gate() {
+        let status = ProjectStatus {
+            project: "app".to_string(),
+            project_root: PathBuf::from("/code/app"),
+            tasks: vec![RunningTask {
+                name: "dev".to_string(),
+                pid: 42,
+                started_ms: 0,
+                ports: vec![3000],
+            }],
+            last_commit_gate: Some(RunSummary {
+                id: "abc123".to_string(),
+                name: "commit-gate".to_string(),
+                success: false,
+                started_ms: 0,
+                duration_ms: 10,
+                detail: Some("tests failed\nmore".to_string()),
+            }),
+            last_deploy: None,
+            services: Vec::new(),
+            env_drift: Some(EnvDrift {
+                reference: ".env.example".to_string(),
+                missing: vec!["API_KEY".to_string()],
+                undocumented: Vec::new(),
+            }),
+            proxy: Some(ProxyErrors {
+                requests: 50,
+                errors: 1,
+            }),
+        };
+        let panel = render(&status, 120_000);
+        assert!(panel.contains("dev (pid 42, up 2m) :3000"), "{panel}");
+        assert!(
+            panel.contains("✗ commit-gate 2m ago (abc123): tests failed"),
+            "{panel}"
+        );
+        assert!(panel.contains("⚠ .env missing API_KEY"), "{panel}");
+        assert!(panel.contains("1/50 errors (2.0%)"), "{panel}");
+        let widths: BTreeSet<usize> = panel.lines().map(|line| line.chars().count()).collect();
+        assert_eq!(widths.len(), 1, "{panel}");
+    }
+}
@@ -0,0 +1,419 @@
+//! Per-project resource usage of flow-managed processes.
+//!
+//! The hub (`f hub run`) samples the process groups recorded in the running-process
+//! registry once a minute and folds them into one row per project per local day: CPU time
+//! consumed, peak resident memory across all of the project's processes, and total process
+//! runtime. `f projects usage` reports the totals for a lookback window.
+
+use std::collections::HashMap;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use rusqlite::{Connection, params};
+use serde::Serialize;
+
+use crate::cli::ProjectsUsageOpts;
+use crate::{db, running, time_window};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// One sample's worth of usage for a project.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct UsageDelta {
+    cpu_ms: u64,
+    rss_kb: u64,
+    runtime_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ProcessSample {
+    pid: u32,
+    pgid: u32,
+    rss_kb: u64,
+    cpu_ms: u64,
+}
+
+/// Stored usage for one project, summed over the requested days.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ProjectUsage {
+    pub project: String,
+    pub cpu_ms: u64,
+    pub peak_rss_kb: u64,
+    pub runtime_ms: u64,
+    pub days: u32,
+}
+
+/// Tracks cumulative CPU time per pid between samples so only the increase is recorded.
+#[derive(Default)]
+struct Sampler {
+    last_cpu_ms: HashMap<u32, u64>,
+    seeded: bool,
+}
+
+impl Sampler {
+    /// Attribute the current process table to projects.
+    ///
+    /// `groups` maps a tracked process group to its project and the number of flow tasks
+    /// running in it. The first call only records baselines: CPU a process burned before
+    /// the hub started is not known to belong to today.
+    fn sample(
+        &mut self,
+        groups: &HashMap<u32, (String, u64)>,
+        processes: &[ProcessSample],
+        elapsed: Duration,
+    ) -> HashMap<String, UsageDelta> {
+        let mut usage: HashMap<String, UsageDelta> = HashMap::new();
+        let mut seen = HashMap::new();
+        for process in processes {
+            let Some((project, _)) = groups.get(&process.pgid) else {
+                continue;
+            };
+            let entry = usage.entry(project.clone()).or_default();
+            entry.rss_kb += process.rss_kb;
+            let cpu_delta = match self.last_cpu_ms.get(&process.pid) {
+                Some(&last) if process.cpu_ms >= last => process.cpu_ms - last,
+                // First sight of a pid (or a reused one): everything it has used is new.
+                _ => process.cpu_ms,
+            };
+            if self.seeded {
+                entry.cpu_ms += cpu_delta;
+            }
+            seen.insert(process.pid, process.cpu_ms);
+        }
+        if self.seeded {
+            let elapsed_ms = elapsed.as_millis() as u64;
+            for (project, tasks) in groups.values() {
+                if let Some(entry) = usage.get_mut(project) {
+                    entry.runtime_ms += elapsed_ms * tasks;
+                }
+            }
+        }
+        self.last_cpu_ms = seen;
+        self.seeded = true;
+        usage
+    }
+}
+
+/// Start the background sampler. Errors are logged and retried on the next tick.
+pub fn spawn_sampler() -> thread::JoinHandle<()> {
+    thread::spawn(|| {
+        let mut sampler = Sampler::default();
+        let mut last = Instant::now();
+        loop {
+            let elapsed = last.elapsed();
+            last = Instant::now();
+            if let Err(err) = sample_once(&mut sampler, elapsed) {
+                eprintln!("WARN project usage sample failed: {err:#}");
+            }
+            thread::sleep(SAMPLE_INTERVAL);
+        }
+    })
+}
+
+fn sample_once(sampler: &mut Sampler, elapsed: Duration) -> Result<()> {
+    let groups = tracked_groups()?;
+    if groups.is_empty() {
+        sampler.last_cpu_ms.clear();
+        sampler.seeded = true;
+        return Ok(());
+    }
+    let processes = process_table()?;
+    let usage = sampler.sample(&groups, &processes, elapsed);
+    if usage.is_empty() {
+        return Ok(());
+    }
+    let conn = db::open_db()?;
+    create_schema(&conn)?;
+    let day = chrono::Local::now().format("%Y-%m-%d").to_string();
+    for (project, delta) in usage {
+        record(&conn, &day, &project, &delta)?;
+    }
+    Ok(())
+}
+
+/// Process groups of live flow-managed tasks, keyed by pgid.
+fn tracked_groups() -> Result<HashMap<u32, (String, u64)>> {
+    let registry = running::load_running_processes()?;
+    let mut groups: HashMap<u32, (String, u64)> = HashMap::new();
+    for process in registry.projects.values().flatten() {
+        if !running::process_alive(process.pid) {
+            continue;
+        }
+        let project = process.project_name.clone().unwrap_or_else(|| {
+            process
+                .project_root
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| process.project_root.display().to_string())
+        });
+        groups.entry(process.pgid).or_insert((project, 0)).1 += 1;
+    }
+    Ok(groups)
+}
+
+fn process_table() -> Result<Vec<ProcessSample>> {
+    let output = Command::new("ps")
+        .args(["-ax", "-o", "pid=,pgid=,rss=,time="])
+        .output()
+        .context("failed to run ps")?;
+    if !output.status.success() {
+        bail!("ps exited with {}", output.status);
+    }
+    Ok(parse_ps_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn parse_ps_output(output: &str) -> Vec<ProcessSample> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some(ProcessSample {
+                pid: fields.next()?.parse().ok()?,
+                pgid: fields.next()?.parse().ok()?,
+                rss_kb: fields.next()?.parse().ok()?,
+                cpu_ms: parse_cpu_time(fields.next()?)?,
+            })
+        })
+        .collect()
+}
+
+/// Parse ps `time` into milliseconds: `[dd-]hh:mm:ss` on Linux, `m:ss.cc` on macOS.
+fn parse_cpu_time(raw: &str) -> Option<u64> {
+    let (days, clock) = match raw.split_once('-') {
+        Some((days, clock)) => (days.parse::<u64>().ok()?, clock),
+        None => (0, raw),
+    };
+    let mut parts: Vec<&str> = clock.split(':').collect();
+    let seconds = parts.pop()?;
+    let (secs, frac) = seconds.split_once('.').unwrap_or((seconds, ""));
+    let mut total_secs = secs.parse::<u64>().ok()?;
+    let mut scale = 60;
+    for part in parts.iter().rev() {
+        total_secs += part.parse::<u64>().ok()? * scale;
+        scale *= 60;
+    }
+    let frac_ms = match frac.len() {
+        0 => 0,
+        len => {
+            let digits: String = frac.chars().chain("00".chars()).take(3).collect();
+            if len > 3 || !digits.chars().all(|ch| ch.is_ascii_digit()) {
+                return None;
+            }
+            digits.parse::<u64>().ok()?
+        }
+    };
+    Some((days * 86_400 + total_secs) * 1000 + frac_ms)
+}
+
+fn create_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS project_usage (
+            day TEXT NOT NULL,
+            project TEXT NOT NULL,
+            cpu_ms INTEGER NOT NULL DEFAULT 0,
+            peak_rss_kb INTEGER NOT NULL DEFAULT 0,
+            runtime_ms INTEGER NOT NULL DEFAULT 0,
+            samples INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (day, project)
+        );
+        "#,
+    )
+    .context("failed to create project_usage schema")?;
+    Ok(())
+}
+
+fn record(conn: &Connection, day: &str, project: &str, delta: &UsageDelta) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT INTO project_usage (day, project, cpu_ms, peak_rss_kb, runtime_ms, samples)
+        VALUES (?1, ?2, ?3, ?4, ?5, 1)
+        ON CONFLICT(day, project) DO UPDATE SET
+            cpu_ms = cpu_ms + excluded.cpu_ms,
+            peak_rss_kb = MAX(peak_rss_kb, excluded.peak_rss_kb),
+            runtime_ms = runtime_ms + excluded.runtime_ms,
+            samples = samples + 1
+        "#,
+        params![
+            day,
+            project,
+            delta.cpu_ms as i64,
+            delta.rss_kb as i64,
+            delta.runtime_ms as i64
+        ],
+    )
+    .context("failed to record project usage")?;
+    Ok(())
+}
+
+/// Per-project totals for days on or after `since_day` (`YYYY-MM-DD`), heaviest CPU first.
+fn load_usage(
+    conn: &Connection,
+    since_day: &str,
+    project: Option<&str>,
+) -> Result<Vec<ProjectUsage>> {
+    create_schema(conn)?;
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT project, SUM(cpu_ms), MAX(peak_rss_kb), SUM(runtime_ms), COUNT(*)
+        FROM project_usage
+        WHERE day >= ?1 AND (?2 IS NULL OR project = ?2)
+        GROUP BY project
+        ORDER BY SUM(cpu_ms) DESC, project
+        "#,
+    )?;
+    let rows = stmt.query_map(params![since_day, project], |row| {
+        Ok(ProjectUsage {
+            project: row.get(0)?,
+            cpu_ms: row.get::<_, i64>(1)? as u64,
+            peak_rss_kb: row.get::<_, i64>(2)? as u64,
+            runtime_ms: row.get::<_, i64>(3)? as u64,
+            days: row.get::<_, i64>(4)? as u32,
+        })
+    })?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to read project usage")
+}
+
+/// `f projects usage`.
+pub fn show_usage(opts: ProjectsUsageOpts) -> Result<()> {
+    let window_ms = time_window::parse_since(&opts.since)?;
+    let since = chrono::Local::now() - chrono::Duration::milliseconds(window_ms);
+    let since_day = since.format("%Y-%m-%d").to_string();
+
+    let conn = db::open_db()?;
+    let usage = load_usage(&conn, &since_day, opts.project.as_deref())?;
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&usage)?);
+        return Ok(());
+    }
+    if usage.is_empty() {
+        println!("No usage recorded since {since_day}.");
+        println!("Usage is sampled by the hub login service; run `f hub install` to enable it.");
+        return Ok(());
+    }
+
+    let name_width = usage
+        .iter()
+        .map(|entry| entry.project.len())
+        .max()
+        .unwrap_or(0)
+        .max("PROJECT".len());
+    println!("Usage since {since_day}:\n");
+    println!(
+        "{:<name_width$}  {:>10}  {:>9}  {:>10}  {:>4}",
+        "PROJECT", "CPU", "PEAK MEM", "RUNTIME", "DAYS"
+    );
+    for entry in &usage {
+        println!(
+            "{:<name_width$}  {:>10}  {:>9}  {:>10}  {:>4}",
+            entry.project,
+            format_duration(entry.cpu_ms),
+            format_memory(entry.peak_rss_kb),
+            format_duration(entry.runtime_ms),
+            entry.days
+        );
+    }
+    Ok(())
+}
+
+fn format_duration(ms: u64) -> String {
+    let secs = ms / 1000;
+    if secs < 60 {
+        format!("{secs}s")
+    } else if secs < 3600 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+fn format_memory(kb: u64) -> String {
+    if kb < 1024 {
+        format!("{kb} KB")
+    } else if kb < 1024 * 1024 {
+        format!("{:.0} MB", kb as f64 / 1024.0)
+    } else {
+        format!("{:.1} GB", kb as f64 / (1024.0 * 1024.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_linux_and_macos_cpu_times() {
+        assert_eq!(parse_cpu_time("00:01:05"), Some(65_000));
+        assert_eq!(parse_cpu_time("1-02:00:00"), Some(93_600_000));
+        assert_eq!(parse_cpu_time("0:01.23"), Some(1_230));
+        assert_eq!(parse_cpu_time("12:34.5"), Some(754_500));
+        assert_eq!(parse_cpu_time("soon"), None);
+
+        let rows = parse_ps_output("  101   100  20480 00:00:03\n  102 100 1024 0:00.50\nbad\n");
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].cpu_ms, 500);
+        assert_eq!(rows[0].rss_kb, 20_480);
+    }
+
+    #[test]
+    fn attributes_cpu_deltas_and_runtime_after_baseline() {
+        let groups = HashMap::from([(100, ("web".to_string(), 2))]);
+        let proc = |pid, rss_kb, cpu_ms| ProcessSample {
+            pid,
+            pgid: if pid == 900 { 900 } else { 100 },
+            rss_kb,
+            cpu_ms,
+        };
+        let mut sampler = Sampler::default();
+        let minute = Duration::from_secs(60);
+
+        let first = sampler.sample(&groups, &[proc(101, 1000, 5000), proc(900, 1, 1)], minute);
+        assert_eq!(first["web"].cpu_ms, 0);
+        assert_eq!(first["web"].runtime_ms, 0);
+
+        let second = sampler.sample(
+            &groups,
+            &[proc(101, 3000, 5600), proc(102, 500, 200)],
+            minute,
+        );
+        assert_eq!(
+            second["web"],
+            UsageDelta {
+                cpu_ms: 800,
+                rss_kb: 3500,
+                runtime_ms: 120_000,
+            }
+        );
+    }
+
+    #[test]
+    fn aggregates_days_per_project() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_schema(&conn).unwrap();
+        let delta = |cpu_ms, rss_kb| UsageDelta {
+            cpu_ms,
+            rss_kb,
+            runtime_ms: 60_000,
+        };
+        record(&conn, "2026-03-01", "web", &delta(100, 500)).unwrap();
+        record(&conn, "2026-03-02", "web", &delta(300, 900)).unwrap();
+        record(&conn, "2026-03-02", "web", &delta(50, 200)).unwrap();
+        record(&conn, "2026-03-02", "api", &delta(10, 100)).unwrap();
+
+        let usage = load_usage(&conn, "2026-03-01", None).unwrap();
+        assert_eq!(usage[0].project, "web");
+        assert_eq!(usage[0].cpu_ms, 450);
+        assert_eq!(usage[0].peak_rss_kb, 900);
+        assert_eq!(usage[0].runtime_ms, 180_000);
+        assert_eq!(usage[0].days, 2);
+        assert_eq!(usage[1].project, "api");
+
+        let recent = load_usage(&conn, "2026-03-02", Some("web")).unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].cpu_ms, 350);
+    }
+}
@@ -0,0 +1,153 @@
+//! Credential injection for upstream APIs.
+//!
+//! A target with `inject_auth` gets a header whose value comes from the flow env store, so
+//! frontend code can call the proxy without holding the credential itself. Values are read
+//! once when the proxy starts and never printed or written to traces.
+
+use std::fmt;
+
+use anyhow::{Context, Result, bail};
+use axum::http::{HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+
+use super::ProxyTargetConfig;
+use crate::env;
+
+/// `inject_auth = { header = "Authorization", env_key = "API_TOKEN" }`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct InjectAuthConfig {
+    /// Header to set on forwarded requests (replaces any header the client sent).
+    #[serde(default = "default_header")]
+    pub header: String,
+    /// Key to read from the env store.
+    pub env_key: String,
+    /// Prepended to the value, e.g. "Bearer ".
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// Read from this project environment (e.g. "dev") instead of personal env vars.
+    #[serde(default)]
+    pub environment: Option<String>,
+}
+
+fn default_header() -> String {
+    "Authorization".to_string()
+}
+
+/// A resolved credential header for one backend.
+pub struct InjectedAuth {
+    header: String,
+    value: String,
+}
+
+impl fmt::Debug for InjectedAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InjectedAuth")
+            .field("header", &self.header)
+            .field("value", &"[redacted]")
+            .finish()
+    }
+}
+
+impl InjectedAuth {
+    /// Look up the target's credential. Blocking: may call the env store over HTTP.
+    pub fn resolve(target: &ProxyTargetConfig) -> Result<Option<Self>> {
+        let Some(config) = &target.inject_auth else {
+            return Ok(None);
+        };
+        let secret = fetch_secret(config)
+            .with_context(|| format!("proxy '{}': failed to read inject_auth", target.name))?;
+        let Some(secret) = secret else {
+            bail!(
+                "proxy '{}': env key {} not found in the {}; set it with `{}`",
+                target.name,
+                config.env_key,
+                match &config.environment {
+                    Some(environment) => format!("'{environment}' project env"),
+                    None => "personal env store".to_string(),
+                },
+                match &config.environment {
+                    Some(environment) => {
+                        format!("f env project set -e {environment} {}=...", config.env_key)
+                    }
+                    None => format!("f env set {}=...", config.env_key),
+                }
+            );
+        };
+        Self::new(&target.name, config, &secret).map(Some)
+    }
+
+    fn new(target: &str, config: &InjectAuthConfig, secret: &str) -> Result<Self> {
+        HeaderName::from_bytes(config.header.as_bytes())
+            .with_context(|| format!("proxy '{target}': invalid inject_auth header"))?;
+        let value = format!(
+            "{}{}",
+            config.prefix.as_deref().unwrap_or(""),
+            secret.trim()
+        );
+        // Don't echo the value: it is the secret.
+        if HeaderValue::from_str(&value).is_err() {
+            bail!(
+                "proxy '{target}': value of {} is not a valid header value",
+                config.env_key
+            );
+        }
+        Ok(Self {
+            header: config.header.clone(),
+            value,
+        })
+    }
+
+    /// Replace any client-sent header of the same name with the credential.
+    pub fn apply(&self, headers: &mut Vec<(String, String)>) {
+        headers.retain(|(name, _)| !name.eq_ignore_ascii_case(&self.header));
+        headers.push((self.header.clone(), self.value.clone()));
+    }
+}
+
+fn fetch_secret(config: &InjectAuthConfig) -> Result<Option<String>> {
+    let keys = [config.env_key.clone()];
+    match &config.environment {
+        Some(environment) => {
+            Ok(env::fetch_project_env_vars(environment, &keys)?.remove(&config.env_key))
+        }
+        None => env::get_personal_env_var(&config.env_key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_client_header_and_hides_value() {
+        let target: ProxyTargetConfig = toml::from_str(
+            r#"
+            name = "api"
+            target = "localhost:3000"
+            inject_auth = { env_key = "API_TOKEN", prefix = "Bearer " }
+            "#,
+        )
+        .unwrap();
+        let config = target.inject_auth.as_ref().unwrap();
+        assert_eq!(config.header, "Authorization");
+
+        let auth = InjectedAuth::new("api", config, "sk-live-123\n").unwrap();
+        let mut headers = vec![
+            ("authorization".to_string(), "Bearer stale".to_string()),
+            ("accept".to_string(), "application/json".to_string()),
+        ];
+        auth.apply(&mut headers);
+        assert_eq!(
+            headers,
+            vec![
+                ("accept".to_string(), "application/json".to_string()),
+                (
+                    "Authorization".to_string(),
+                    "Bearer sk-live-123".to_string()
+                ),
+            ]
+        );
+        assert!(!format!("{auth:?}").contains("sk-live"));
+        assert!(InjectedAuth::new("api", config, "bad\u{7f}value").is_err());
+    }
+}
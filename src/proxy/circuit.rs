@@ -0,0 +1,258 @@
+//! Circuit breaker for failing backends.
+//!
+//! After `circuit_failures` consecutive upstream failures (connect errors, timeouts, reset
+//! connections) a backend's circuit opens: requests get an immediate 503 with `Retry-After`
+//! instead of each waiting out its own timeout. Once `circuit_cooldown` has passed, a single
+//! request is let through as a probe; a response closes the circuit, another failure keeps it
+//! open for a further cooldown. HTTP error statuses don't count: a backend answering 500 is up.
+//! Open circuits and failure streaks are reported under `circuits` in the summary JSON.
+
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+
+use super::pool::UpstreamOutcome;
+use super::{ProxyConfig, ProxyTargetConfig, parse_duration};
+
+/// Failure threshold and cooldown for one backend.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CircuitSettings {
+    /// Consecutive failures that open the circuit.
+    pub failures: u32,
+    /// How long an open circuit rejects requests before probing the backend.
+    pub cooldown: Duration,
+}
+
+impl CircuitSettings {
+    /// The `[proxy]` defaults, used as-is for routes registered at run time; `None` when
+    /// `circuit_failures = 0` turns the breaker off.
+    pub fn from_proxy(config: &ProxyConfig) -> Option<Self> {
+        (config.circuit_failures > 0).then(|| Self {
+            failures: config.circuit_failures,
+            cooldown: parse_duration(&config.circuit_cooldown),
+        })
+    }
+
+    /// The defaults with a target's own `circuit_failures` / `circuit_cooldown` applied.
+    pub fn for_target(default: Option<&Self>, target: &ProxyTargetConfig) -> Option<Self> {
+        let failures = target
+            .circuit_failures
+            .or(default.map(|settings| settings.failures))
+            .unwrap_or(ProxyConfig::default().circuit_failures);
+        if failures == 0 {
+            return None;
+        }
+        let cooldown = match (&target.circuit_cooldown, default) {
+            (Some(raw), _) => parse_duration(raw),
+            (None, Some(settings)) => settings.cooldown,
+            (None, None) => parse_duration(&ProxyConfig::default().circuit_cooldown),
+        };
+        Some(Self { failures, cooldown })
+    }
+}
+
+/// Whether a request may go to the backend.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Admission {
+    Allowed,
+    /// The cooldown is over; this request tests whether the backend is back.
+    Probe,
+    Open {
+        retry_after: Duration,
+        down_since: DateTime<Utc>,
+    },
+}
+
+#[derive(Debug, Default)]
+struct CircuitState {
+    consecutive_failures: u32,
+    /// Wall-clock time of the first failure in the current streak.
+    failing_since: Option<DateTime<Utc>>,
+    opened_at: Option<Instant>,
+    probe_started: Option<Instant>,
+    trips: u64,
+    rejected: u64,
+}
+
+/// Failure tracking for one backend.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    pub settings: CircuitSettings,
+    state: Mutex<CircuitState>,
+}
+
+/// State of one backend's circuit, as written to the summary JSON.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CircuitSummary {
+    /// "closed", "open", or "probing".
+    pub state: &'static str,
+    pub consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub down_since: Option<String>,
+    /// Times the circuit has opened.
+    pub trips: u64,
+    /// Requests answered with a 503 while open.
+    pub rejected: u64,
+}
+
+impl CircuitBreaker {
+    pub fn new(settings: CircuitSettings) -> Self {
+        Self {
+            settings,
+            state: Mutex::new(CircuitState::default()),
+        }
+    }
+
+    pub fn check(&self, now: Instant) -> Admission {
+        let mut state = self.lock();
+        let Some(opened_at) = state.opened_at else {
+            return Admission::Allowed;
+        };
+        // A probe that never finishes (no read timeout) is replaced after another cooldown.
+        let elapsed = now.duration_since(state.probe_started.unwrap_or(opened_at));
+        if elapsed >= self.settings.cooldown {
+            state.probe_started = Some(now);
+            return Admission::Probe;
+        }
+        state.rejected += 1;
+        Admission::Open {
+            retry_after: self.settings.cooldown - elapsed,
+            down_since: state.failing_since.unwrap_or_else(Utc::now),
+        }
+    }
+
+    /// Count a forwarded request: any response closes the circuit, a failure extends the streak.
+    pub fn record(&self, outcome: UpstreamOutcome, now: Instant) {
+        match outcome {
+            UpstreamOutcome::Completed { .. } => self.record_success(),
+            _ => self.record_failure(now),
+        }
+    }
+
+    /// A response came back: the backend is up.
+    pub fn record_success(&self) {
+        let mut state = self.lock();
+        state.consecutive_failures = 0;
+        state.failing_since = None;
+        state.opened_at = None;
+        state.probe_started = None;
+    }
+
+    /// The request failed before a response arrived.
+    pub fn record_failure(&self, now: Instant) {
+        let mut state = self.lock();
+        state.consecutive_failures += 1;
+        state.failing_since.get_or_insert_with(Utc::now);
+        if state.probe_started.take().is_some() {
+            state.opened_at = Some(now);
+        } else if state.opened_at.is_none() && state.consecutive_failures >= self.settings.failures
+        {
+            state.opened_at = Some(now);
+            state.trips += 1;
+        }
+    }
+
+    /// Close the circuit, e.g. after a registered route moves to a new port.
+    pub fn reset(&self) {
+        self.record_success();
+    }
+
+    pub fn summary(&self) -> CircuitSummary {
+        let state = self.lock();
+        CircuitSummary {
+            state: match (state.opened_at, state.probe_started) {
+                (None, _) => "closed",
+                (Some(_), None) => "open",
+                (Some(_), Some(_)) => "probing",
+            },
+            consecutive_failures: state.consecutive_failures,
+            down_since: state.failing_since.map(format_time),
+            trips: state.trips,
+            rejected: state.rejected,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CircuitState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// RFC 3339 to the second, for the 503 body and the summary.
+pub fn format_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker() -> CircuitBreaker {
+        CircuitBreaker::new(CircuitSettings {
+            failures: 3,
+            cooldown: Duration::from_secs(10),
+        })
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures_and_closes_after_a_probe() {
+        let circuit = breaker();
+        let start = Instant::now();
+        circuit.record_failure(start);
+        circuit.record_failure(start);
+        circuit.record_success();
+        circuit.record_failure(start);
+        circuit.record_failure(start);
+        assert_eq!(circuit.check(start), Admission::Allowed);
+        circuit.record_failure(start);
+
+        let Admission::Open { retry_after, .. } = circuit.check(start + Duration::from_secs(4))
+        else {
+            panic!("third consecutive failure should open the circuit");
+        };
+        assert_eq!(retry_after, Duration::from_secs(6));
+        assert_eq!(circuit.summary().state, "open");
+
+        let later = start + Duration::from_secs(10);
+        assert_eq!(circuit.check(later), Admission::Probe);
+        // Only one probe at a time.
+        assert!(matches!(circuit.check(later), Admission::Open { .. }));
+        circuit.record_success();
+        assert_eq!(circuit.check(later), Admission::Allowed);
+
+        let summary = circuit.summary();
+        assert_eq!(
+            (summary.state, summary.trips, summary.rejected),
+            ("closed", 1, 2)
+        );
+        assert_eq!(summary.down_since, None);
+    }
+
+    #[test]
+    fn failed_probe_waits_another_cooldown() {
+        let circuit = breaker();
+        let start = Instant::now();
+        for _ in 0..3 {
+            circuit.record_failure(start);
+        }
+        let probe_at = start + Duration::from_secs(10);
+        assert_eq!(circuit.check(probe_at), Admission::Probe);
+        assert_eq!(circuit.summary().state, "probing");
+        circuit.record_failure(probe_at);
+
+        assert!(matches!(
+            circuit.check(probe_at + Duration::from_secs(9)),
+            Admission::Open { .. }
+        ));
+        assert_eq!(
+            circuit.check(probe_at + Duration::from_secs(10)),
+            Admission::Probe
+        );
+        let summary = circuit.summary();
+        assert_eq!((summary.trips, summary.consecutive_failures), (1, 4));
+        assert!(summary.down_since.is_some());
+    }
+}
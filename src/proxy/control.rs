@@ -0,0 +1,308 @@
+//! Control socket for registering proxy targets at runtime.
+//!
+//! Tasks that declare a `port` register `<task>.<project>.localhost` here when they
+//! start and unregister when they exit, so running services are routable without
+//! listing each one under `[[proxies]]`. The protocol is one JSON request line per
+//! connection, answered by one JSON response line.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::SocketAddr;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::net::UnixListener;
+
+use super::server::ProxyServer;
+use super::trace;
+
+/// A request sent to the proxy control socket.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ControlRequest {
+    /// Route `host` to `127.0.0.1:<port>` under the backend `name`.
+    Register {
+        name: String,
+        host: String,
+        port: u16,
+    },
+    /// Drop the host route of the backend `name`.
+    Unregister { name: String },
+    /// List current host routes.
+    List,
+    /// List every backend name, in trace index order.
+    Backends,
+}
+
+/// Reply to a [`ControlRequest`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ControlResponse {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub routes: Vec<RouteInfo>,
+    /// Backend names for `backends`; position `i` is the `target_idx` traces record.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub backends: Vec<String>,
+}
+
+/// A host route as reported by `list`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteInfo {
+    pub name: String,
+    pub host: String,
+    pub addr: String,
+}
+
+impl ControlResponse {
+    fn error(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            error: Some(message.into()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Default control socket, next to the trace buffer.
+pub fn default_socket_path() -> PathBuf {
+    trace::default_trace_dir().join("control.sock")
+}
+
+/// Host a task is reachable on through the proxy: `<task>.<project>.localhost`.
+pub fn task_route_host(task: &str, project: &str) -> String {
+    format!("{}.{}.localhost", dns_label(task), dns_label(project))
+}
+
+/// Backend name a running task registers under: `<task>.<project>`.
+pub fn task_backend_name(task: &str, project: &str) -> String {
+    format!("{}.{}", dns_label(task), dns_label(project))
+}
+
+/// Lowercase `s` and collapse anything that is not a DNS label character into `-`.
+fn dns_label(s: &str) -> String {
+    let mut label = String::new();
+    for c in s.chars() {
+        if c.is_ascii_alphanumeric() {
+            label.push(c.to_ascii_lowercase());
+        } else if !label.ends_with('-') {
+            label.push('-');
+        }
+    }
+    let label = label.trim_matches('-');
+    if label.is_empty() {
+        "app".to_string()
+    } else {
+        label.to_string()
+    }
+}
+
+/// Apply a control request to the running proxy's router.
+pub async fn apply(server: &ProxyServer, request: ControlRequest) -> ControlResponse {
+    let mut router = server.router.write().await;
+    match request {
+        ControlRequest::Register { name, host, port } => {
+            let addr = SocketAddr::from(([127, 0, 0, 1], port));
+            let idx = match router.register_host_backend(&name, host.to_lowercase(), addr) {
+                Ok(idx) => idx,
+                Err(err) => {
+                    return ControlResponse::error(format!(
+                        "failed to register '{}': {err:#}",
+                        name
+                    ));
+                }
+            };
+            let backend = &router.backends[idx];
+            server.summary_state.add_pool(&name, backend.pool.clone());
+            if let Some(circuit) = backend.circuit.clone() {
+                server.summary_state.add_circuit(&name, circuit);
+            }
+            if let Some(limiter) = backend.rate_limit.clone() {
+                server.summary_state.add_rate_limit(&name, limiter);
+            }
+            tracing::info!("proxy route registered: {} -> {} ({})", host, addr, name);
+            ControlResponse {
+                ok: true,
+                ..Default::default()
+            }
+        }
+        ControlRequest::Unregister { name } => {
+            if router.unregister_host_backend(&name) {
+                tracing::info!("proxy route removed: {}", name);
+                ControlResponse {
+                    ok: true,
+                    ..Default::default()
+                }
+            } else {
+                ControlResponse::error(format!("no route registered for '{}'", name))
+            }
+        }
+        ControlRequest::List => {
+            let mut routes: Vec<RouteInfo> = router
+                .host_routes
+                .iter()
+                .filter_map(|(host, idx)| {
+                    router.backends.get(*idx).map(|backend| RouteInfo {
+                        name: backend.name.clone(),
+                        host: host.clone(),
+                        addr: backend.addr.to_string(),
+                    })
+                })
+                .collect();
+            routes.sort_by(|a, b| a.host.cmp(&b.host));
+            ControlResponse {
+                ok: true,
+                routes,
+                ..Default::default()
+            }
+        }
+        ControlRequest::Backends => ControlResponse {
+            ok: true,
+            backends: router
+                .backends
+                .iter()
+                .map(|backend| backend.name.clone())
+                .collect(),
+            ..Default::default()
+        },
+    }
+}
+
+/// Accept control connections on `path` until the proxy exits.
+pub async fn serve(path: PathBuf, server: Arc<ProxyServer>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    // A socket left behind by a proxy that was killed would make bind fail.
+    if path.exists() && UnixStream::connect(&path).is_err() {
+        let _ = std::fs::remove_file(&path);
+    }
+    let listener = UnixListener::bind(&path)
+        .with_context(|| format!("failed to bind proxy control socket {}", path.display()))?;
+    tracing::info!("Proxy control socket: {:?}", path);
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let server = server.clone();
+        tokio::spawn(async move {
+            if let Err(err) = handle_connection(stream, &server).await {
+                tracing::debug!(?err, "proxy control connection failed");
+            }
+        });
+    }
+}
+
+async fn handle_connection(stream: tokio::net::UnixStream, server: &ProxyServer) -> Result<()> {
+    let (read, mut write) = stream.into_split();
+    let mut line = String::new();
+    tokio::io::BufReader::new(read).read_line(&mut line).await?;
+    let response = match serde_json::from_str::<ControlRequest>(line.trim()) {
+        Ok(request) => apply(server, request).await,
+        Err(err) => ControlResponse::error(format!("invalid request: {err}")),
+    };
+    let mut payload = serde_json::to_vec(&response)?;
+    payload.push(b'\n');
+    write.write_all(&payload).await?;
+    Ok(())
+}
+
+/// Send one request to the control socket at `path`.
+pub fn send(path: &Path, request: &ControlRequest) -> Result<ControlResponse> {
+    let mut stream = UnixStream::connect(path)
+        .with_context(|| format!("proxy control socket {} not reachable", path.display()))?;
+    stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+    stream.set_write_timeout(Some(Duration::from_secs(2)))?;
+    let mut payload = serde_json::to_vec(request)?;
+    payload.push(b'\n');
+    stream.write_all(&payload)?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    serde_json::from_str(line.trim()).context("invalid proxy control response")
+}
+
+/// A proxy route held for the lifetime of a running task; dropping it unregisters.
+#[derive(Debug)]
+pub struct TaskRoute {
+    name: String,
+    pub host: String,
+    socket: PathBuf,
+}
+
+impl TaskRoute {
+    /// Register `task` (listening on `port`) with the local proxy.
+    /// Returns `None` when no proxy is running or it rejects the route.
+    pub fn register(task: &str, project: &str, port: u16) -> Option<Self> {
+        let socket = default_socket_path();
+        if !socket.exists() {
+            return None;
+        }
+        let name = task_backend_name(task, project);
+        let host = task_route_host(task, project);
+        let request = ControlRequest::Register {
+            name: name.clone(),
+            host: host.clone(),
+            port,
+        };
+        match send(&socket, &request) {
+            Ok(response) if response.ok => Some(Self { name, host, socket }),
+            Ok(response) => {
+                tracing::debug!(error = ?response.error, "proxy rejected task route");
+                None
+            }
+            Err(err) => {
+                tracing::debug!(?err, "proxy control socket unavailable");
+                None
+            }
+        }
+    }
+}
+
+impl Drop for TaskRoute {
+    fn drop(&mut self) {
+        let request = ControlRequest::Unregister {
+            name: self.name.clone(),
+        };
+        if let Err(err) = send(&self.socket, &request) {
+            tracing::debug!(?err, route = %self.name, "failed to unregister proxy route");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn task_route_host_sanitizes_labels() {
+        assert_eq!(task_route_host("dev", "my_app"), "dev.my-app.localhost");
+        assert_eq!(
+            task_route_host("API Server", "Flow"),
+            "api-server.flow.localhost"
+        );
+        assert_eq!(task_route_host("--", "x"), "app.x.localhost");
+    }
+
+    #[test]
+    fn control_request_wire_format() {
+        let request = ControlRequest::Register {
+            name: "dev.web".to_string(),
+            host: "dev.web.localhost".to_string(),
+            port: 3000,
+        };
+        let json = serde_json::to_string(&request).unwrap();
+        assert_eq!(
+            json,
+            r#"{"action":"register","name":"dev.web","host":"dev.web.localhost","port":3000}"#
+        );
+        let list: ControlRequest = serde_json::from_str(r#"{"action":"list"}"#).unwrap();
+        assert_eq!(list, ControlRequest::List);
+        let backends: ControlRequest = serde_json::from_str(r#"{"action":"backends"}"#).unwrap();
+        assert_eq!(backends, ControlRequest::Backends);
+    }
+}
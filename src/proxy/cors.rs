@@ -0,0 +1,302 @@
+//! CORS helper mode for local cross-origin development.
+//!
+//! When a target sets `cors`, the proxy answers preflight (`OPTIONS`) requests itself and
+//! replaces any upstream `Access-Control-*` headers with its own.
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+const ALLOW_METHODS: &str = "GET, POST, PUT, PATCH, DELETE, HEAD, OPTIONS";
+const MAX_AGE_SECS: &str = "86400";
+/// Response headers browsers expose without `Access-Control-Expose-Headers`, plus ones they
+/// never expose (`set-cookie`).
+const NOT_EXPOSED: &[&str] = &[
+    "cache-control",
+    "content-language",
+    "content-length",
+    "content-type",
+    "expires",
+    "last-modified",
+    "pragma",
+    "set-cookie",
+    "vary",
+];
+
+/// `cors` value in a `[[proxies]]` entry.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum CorsConfig {
+    /// `"permissive"` or `"mirror-origin"`
+    Mode(String),
+    /// `{ allow_origins = [...] }`
+    Origins { allow_origins: Vec<String> },
+}
+
+/// Resolved CORS behavior for a backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CorsPolicy {
+    /// `Access-Control-Allow-Origin: *` (no credentials).
+    Permissive,
+    /// Echo the request origin and allow credentials.
+    MirrorOrigin,
+    /// Echo the origin (with credentials) only when it is in the list.
+    AllowList(Vec<String>),
+}
+
+impl CorsPolicy {
+    pub fn from_config(config: &CorsConfig) -> Result<Self> {
+        match config {
+            CorsConfig::Mode(mode) => match mode.trim().to_ascii_lowercase().as_str() {
+                "permissive" | "*" => Ok(CorsPolicy::Permissive),
+                "mirror-origin" | "mirror_origin" | "mirror" => Ok(CorsPolicy::MirrorOrigin),
+                other => {
+                    bail!("unknown cors mode '{other}' (expected permissive or mirror-origin)")
+                }
+            },
+            CorsConfig::Origins { allow_origins } => Ok(CorsPolicy::AllowList(
+                allow_origins
+                    .iter()
+                    .map(|origin| origin.trim().trim_end_matches('/').to_string())
+                    .collect(),
+            )),
+        }
+    }
+
+    /// Whether a request is a CORS preflight the proxy should answer itself.
+    pub fn is_preflight(method: &str, origin: Option<&str>, request_method: Option<&str>) -> bool {
+        method.eq_ignore_ascii_case("OPTIONS") && origin.is_some() && request_method.is_some()
+    }
+
+    /// Status and headers for a preflight response. Disallowed origins get a 403.
+    pub fn preflight(
+        &self,
+        origin: Option<&str>,
+        request_headers: Option<&str>,
+    ) -> (u16, Vec<(String, String)>) {
+        let mut headers = self.response_headers(origin);
+        if headers.is_empty() {
+            return (403, headers);
+        }
+        headers.push((
+            "access-control-allow-methods".to_string(),
+            ALLOW_METHODS.to_string(),
+        ));
+        let allow_headers = request_headers
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .unwrap_or("*");
+        headers.push((
+            "access-control-allow-headers".to_string(),
+            allow_headers.to_string(),
+        ));
+        headers.push((
+            "access-control-max-age".to_string(),
+            MAX_AGE_SECS.to_string(),
+        ));
+        (204, headers)
+    }
+
+    /// CORS headers to attach to a proxied response. Empty when the origin is not allowed.
+    pub fn response_headers(&self, origin: Option<&str>) -> Vec<(String, String)> {
+        let allow_origin = match self {
+            CorsPolicy::Permissive => {
+                return vec![
+                    ("access-control-allow-origin".to_string(), "*".to_string()),
+                    ("access-control-expose-headers".to_string(), "*".to_string()),
+                ];
+            }
+            CorsPolicy::MirrorOrigin => origin,
+            CorsPolicy::AllowList(allowed) => origin.filter(|origin| {
+                let origin = origin.trim_end_matches('/');
+                allowed.iter().any(|entry| entry == "*" || entry == origin)
+            }),
+        };
+        let Some(origin) = allow_origin else {
+            return Vec::new();
+        };
+        vec![
+            (
+                "access-control-allow-origin".to_string(),
+                origin.to_string(),
+            ),
+            (
+                "access-control-allow-credentials".to_string(),
+                "true".to_string(),
+            ),
+            ("vary".to_string(), "Origin".to_string()),
+        ]
+    }
+}
+
+/// Replace upstream CORS headers with the policy's headers.
+///
+/// `Origin` is added to the upstream `Vary` rather than replacing it. With credentials,
+/// browsers read `Access-Control-Expose-Headers: *` as a header literally named `*`, so the
+/// upstream's own header names are listed instead.
+pub fn apply(headers: &mut Vec<(String, String)>, cors: Vec<(String, String)>) {
+    headers.retain(|(name, _)| !name.to_ascii_lowercase().starts_with("access-control-"));
+    let credentials = cors
+        .iter()
+        .any(|(name, _)| name == "access-control-allow-credentials");
+    if credentials {
+        let mut exposed: Vec<String> = Vec::new();
+        for (name, _) in headers.iter() {
+            let name = name.to_ascii_lowercase();
+            if !NOT_EXPOSED.contains(&name.as_str()) && !exposed.contains(&name) {
+                exposed.push(name);
+            }
+        }
+        if !exposed.is_empty() {
+            headers.push((
+                "access-control-expose-headers".to_string(),
+                exposed.join(", "),
+            ));
+        }
+    }
+    for (name, value) in cors {
+        if name == "vary" {
+            add_vary(headers, &value);
+        } else {
+            headers.push((name, value));
+        }
+    }
+}
+
+/// Add `field` to the response's `Vary` unless it is already covered.
+fn add_vary(headers: &mut Vec<(String, String)>, field: &str) {
+    let covered = headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("vary"))
+        .flat_map(|(_, value)| value.split(','))
+        .map(str::trim)
+        .any(|existing| existing == "*" || existing.eq_ignore_ascii_case(field));
+    if covered {
+        return;
+    }
+    match headers
+        .iter_mut()
+        .find(|(name, _)| name.eq_ignore_ascii_case("vary"))
+    {
+        Some((_, value)) if !value.trim().is_empty() => {
+            value.push_str(", ");
+            value.push_str(field);
+        }
+        Some((_, value)) => *value = field.to_string(),
+        None => headers.push(("vary".to_string(), field.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+        headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn parses_modes_and_origin_lists() {
+        let parse = |raw: &str| -> CorsConfig {
+            #[derive(Deserialize)]
+            struct Wrapper {
+                cors: CorsConfig,
+            }
+            toml::from_str::<Wrapper>(raw).unwrap().cors
+        };
+        assert_eq!(
+            CorsPolicy::from_config(&parse("cors = \"permissive\"")).unwrap(),
+            CorsPolicy::Permissive
+        );
+        assert_eq!(
+            CorsPolicy::from_config(&parse("cors = \"mirror-origin\"")).unwrap(),
+            CorsPolicy::MirrorOrigin
+        );
+        assert_eq!(
+            CorsPolicy::from_config(&parse(
+                "cors = { allow_origins = [\"http://localhost:5173/\"] }"
+            ))
+            .unwrap(),
+            CorsPolicy::AllowList(vec!["http://localhost:5173".to_string()])
+        );
+        assert!(CorsPolicy::from_config(&parse("cors = \"open\"")).is_err());
+    }
+
+    #[test]
+    fn preflight_mirrors_allowed_origin_and_rejects_others() {
+        let policy = CorsPolicy::AllowList(vec!["http://localhost:5173".to_string()]);
+        let (status, headers) =
+            policy.preflight(Some("http://localhost:5173"), Some("content-type"));
+        assert_eq!(status, 204);
+        assert_eq!(
+            header(&headers, "access-control-allow-origin"),
+            Some("http://localhost:5173")
+        );
+        assert_eq!(
+            header(&headers, "access-control-allow-headers"),
+            Some("content-type")
+        );
+
+        let (status, headers) = policy.preflight(Some("http://evil.test"), None);
+        assert_eq!(status, 403);
+        assert!(headers.is_empty());
+    }
+
+    #[test]
+    fn apply_replaces_upstream_cors_headers() {
+        let mut headers = vec![
+            (
+                "access-control-allow-origin".to_string(),
+                "https://prod.example".to_string(),
+            ),
+            ("content-type".to_string(), "text/plain".to_string()),
+        ];
+        apply(
+            &mut headers,
+            CorsPolicy::Permissive.response_headers(Some("http://localhost:3000")),
+        );
+        assert_eq!(header(&headers, "access-control-allow-origin"), Some("*"));
+        assert_eq!(header(&headers, "content-type"), Some("text/plain"));
+        assert_eq!(
+            headers
+                .iter()
+                .filter(|(n, _)| n == "access-control-allow-origin")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn apply_appends_to_vary_and_lists_exposed_headers_with_credentials() {
+        let mut headers = vec![
+            ("Vary".to_string(), "Accept-Encoding".to_string()),
+            ("content-type".to_string(), "application/json".to_string()),
+            ("x-request-id".to_string(), "abc".to_string()),
+            ("ETag".to_string(), "\"1\"".to_string()),
+        ];
+        apply(
+            &mut headers,
+            CorsPolicy::MirrorOrigin.response_headers(Some("http://localhost:3000")),
+        );
+        assert_eq!(header(&headers, "Vary"), Some("Accept-Encoding, Origin"));
+        assert_eq!(header(&headers, "vary"), None);
+        assert_eq!(
+            header(&headers, "access-control-expose-headers"),
+            Some("x-request-id, etag")
+        );
+        assert_eq!(
+            header(&headers, "access-control-allow-credentials"),
+            Some("true")
+        );
+
+        let mut headers = vec![("vary".to_string(), "origin".to_string())];
+        apply(
+            &mut headers,
+            CorsPolicy::MirrorOrigin.response_headers(Some("http://localhost:3000")),
+        );
+        assert_eq!(header(&headers, "vary"), Some("origin"));
+        assert_eq!(header(&headers, "access-control-expose-headers"), None);
+    }
+}
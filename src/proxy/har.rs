@@ -0,0 +1,246 @@
+//! HAR (HTTP Archive 1.2) export of proxy traces.
+//!
+//! Trace records only keep what fits in 128 bytes: method, status, the first 64 bytes of the
+//! path, sizes, and timings. Headers are not recorded, so they are left empty; response
+//! bodies are filled in from the agent summary's captured error bodies when present.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{Value, json};
+
+use super::ProxyTargetConfig;
+use super::trace::{self, TraceBuffer, TraceRecord};
+use crate::cli::ProxyExportOpts;
+use crate::secret_redact;
+
+pub fn export(opts: ProxyExportOpts, targets: &[ProxyTargetConfig]) -> Result<()> {
+    let trace_dir = trace::default_trace_dir();
+    let path = trace::latest_trace_file(&trace_dir)
+        .with_context(|| format!("no proxy trace file in {}", trace_dir.display()))?;
+    let buffer = TraceBuffer::open(&path)
+        .with_context(|| format!("failed to open trace buffer {}", path.display()))?;
+
+    let target_idx = match opts.target.as_deref() {
+        Some(name) => match targets.iter().position(|target| target.name == name) {
+            Some(idx) => Some(idx as u8),
+            None => bail!("unknown proxy target '{}'", name),
+        },
+        None => None,
+    };
+    let mut records = buffer.filter(opts.last, |record| {
+        record.timestamp() != 0
+            && (!opts.errors || record.is_error())
+            && target_idx.is_none_or(|idx| record.target_idx() == idx)
+    });
+    // `filter` walks newest-first; HAR entries read oldest-first.
+    records.reverse();
+
+    let bodies = captured_bodies(&trace_dir.join("trace-summary.json"));
+    let har = build_har(
+        &records,
+        targets,
+        &bodies,
+        trace::now_ns(),
+        Utc::now().timestamp_millis(),
+    );
+    fs::write(&opts.har, serde_json::to_string_pretty(&har)?)
+        .with_context(|| format!("failed to write {}", opts.har.display()))?;
+    println!(
+        "Exported {} request(s) to {}",
+        records.len(),
+        opts.har.display()
+    );
+    Ok(())
+}
+
+/// Error bodies from the agent summary, keyed by request ID (redacted before export).
+fn captured_bodies(summary_path: &Path) -> HashMap<u64, String> {
+    let Some(summary) = fs::read_to_string(summary_path)
+        .ok()
+        .and_then(|raw| serde_json::from_str::<Value>(&raw).ok())
+    else {
+        return HashMap::new();
+    };
+    summary["recent_errors"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|error| {
+            let req_id = u64::from_str_radix(error["req_id"].as_str()?, 16).ok()?;
+            let body = error["error_body"].as_str()?;
+            Some((req_id, secret_redact::redact_text(body)))
+        })
+        .collect()
+}
+
+fn build_har(
+    records: &[TraceRecord],
+    targets: &[ProxyTargetConfig],
+    bodies: &HashMap<u64, String>,
+    now_ns: u64,
+    now_ms: i64,
+) -> Value {
+    let entries: Vec<Value> = records
+        .iter()
+        .map(|record| {
+            // Trace timestamps are monotonic; anchor them to the wall clock via "now".
+            let age_ms = (now_ns.saturating_sub(record.timestamp()) / 1_000_000) as i64;
+            har_entry(
+                record,
+                targets.get(record.target_idx() as usize),
+                bodies.get(&record.req_id()),
+                now_ms.saturating_sub(age_ms),
+            )
+        })
+        .collect();
+
+    json!({
+        "log": {
+            "version": "1.2",
+            "creator": { "name": "flow", "version": env!("CARGO_PKG_VERSION") },
+            "pages": [],
+            "entries": entries,
+        }
+    })
+}
+
+fn har_entry(
+    record: &TraceRecord,
+    target: Option<&ProxyTargetConfig>,
+    body: Option<&String>,
+    started_ms: i64,
+) -> Value {
+    let path = record.path();
+    let host = match target {
+        Some(target) => target.target.clone(),
+        None => format!("target-{}", record.target_idx()),
+    };
+    let url = format!("http://{host}{path}");
+    let query: Vec<Value> = path
+        .split_once('?')
+        .map(|(_, query)| {
+            url::form_urlencoded::parse(query.as_bytes())
+                .map(|(name, value)| json!({ "name": name, "value": value }))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let total_ms = f64::from(record.latency_us()) / 1000.0;
+    let wait_ms = (f64::from(record.upstream_latency_us()) / 1000.0).min(total_ms);
+    let status = record.status();
+    let status_text = reqwest::StatusCode::from_u16(status)
+        .ok()
+        .and_then(|code| code.canonical_reason())
+        .unwrap_or("");
+
+    let mut content = json!({
+        "size": record.bytes_out(),
+        "mimeType": "",
+    });
+    if let Some(body) = body {
+        content["text"] = json!(body);
+    }
+
+    let mut entry = json!({
+        "startedDateTime": format_started(started_ms),
+        "time": total_ms,
+        "request": {
+            "method": format!("{:?}", record.method()).to_uppercase(),
+            "url": url,
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": [],
+            "queryString": query,
+            "headersSize": -1,
+            "bodySize": record.bytes_in(),
+        },
+        "response": {
+            "status": status,
+            "statusText": status_text,
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": [],
+            "content": content,
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": record.bytes_out(),
+        },
+        "cache": {},
+        "timings": {
+            "blocked": -1,
+            "dns": -1,
+            "connect": -1,
+            "ssl": -1,
+            "send": 0,
+            "wait": wait_ms,
+            "receive": total_ms - wait_ms,
+        },
+        "_traceId": format!("{:032x}", record.trace_id()),
+        "_reqId": format!("{:x}", record.req_id()),
+        "_target": target.map(|target| target.name.as_str()),
+        "_rewrites": trace::describe_flags(record.flags()),
+    });
+    if record.path_len() as usize >= trace::TRACE_PATH_BYTES {
+        entry["comment"] = json!("path truncated to 64 bytes by the trace buffer");
+    }
+    entry
+}
+
+fn format_started(ms: i64) -> String {
+    DateTime::<Utc>::from_timestamp_millis(ms)
+        .unwrap_or_default()
+        .to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::trace::Method;
+
+    fn target(name: &str, addr: &str) -> ProxyTargetConfig {
+        toml::from_str(&format!("name = \"{name}\"\ntarget = \"{addr}\"")).unwrap()
+    }
+
+    #[test]
+    fn builds_har_entries_with_wall_clock_times_and_bodies() {
+        let mut record = TraceRecord::new();
+        record.set_timestamp(9_000_000_000);
+        record.set_req_id(0x2a);
+        record.set_latency_status(12_500, 502, Method::Post, 0);
+        record.set_bytes(10, 20);
+        record.set_target_and_trace_id(0, 17, 0xabc);
+        record.set_upstream_latency(10_000);
+        record.set_path("/api/users?page=2");
+
+        let bodies = HashMap::from([(0x2a, "{\"error\":\"upstream down\"}".to_string())]);
+        let har = build_har(
+            &[record],
+            &[target("api", "localhost:3000")],
+            &bodies,
+            10_000_000_000,
+            1_700_000_001_000,
+        );
+
+        let entry = &har["log"]["entries"][0];
+        assert_eq!(har["log"]["version"], "1.2");
+        assert_eq!(entry["startedDateTime"], "2023-11-14T22:13:20.000Z");
+        assert_eq!(entry["time"], 12.5);
+        assert_eq!(entry["timings"]["wait"], 10.0);
+        assert_eq!(entry["request"]["method"], "POST");
+        assert_eq!(
+            entry["request"]["url"],
+            "http://localhost:3000/api/users?page=2"
+        );
+        assert_eq!(entry["request"]["queryString"][0]["value"], "2");
+        assert_eq!(entry["response"]["statusText"], "Bad Gateway");
+        assert_eq!(
+            entry["response"]["content"]["text"],
+            "{\"error\":\"upstream down\"}"
+        );
+        assert_eq!(entry["_target"], "api");
+    }
+}
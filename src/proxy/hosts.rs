@@ -0,0 +1,378 @@
+//! Local name resolution for `*.localhost` project domains.
+//!
+//! Browsers resolve `*.localhost` on their own, but `curl`, language runtimes, and
+//! macOS `getaddrinfo` generally do not. `f proxy domains` keeps a marked block in
+//! `/etc/hosts` that points each domain at loopback, where `f proxy start` listens.
+//!
+//! This is the only mechanism, on macOS as well. No `/etc/resolver/<domain>` file is
+//! written: such a file only names a DNS server for the domain, and flow does not run one.
+
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use tempfile::NamedTempFile;
+
+use super::control;
+use crate::cli::{
+    ProxyDomainsAction, ProxyDomainsAddOpts, ProxyDomainsCommand, ProxyDomainsRemoveOpts,
+};
+use crate::config;
+
+const BLOCK_BEGIN: &str = "# >>> flow proxy domains >>>";
+const BLOCK_END: &str = "# <<< flow proxy domains <<<";
+
+pub fn run(cmd: ProxyDomainsCommand) -> Result<()> {
+    match cmd.action {
+        None | Some(ProxyDomainsAction::List) => run_list(),
+        Some(ProxyDomainsAction::Add(opts)) => run_add(opts),
+        Some(ProxyDomainsAction::Remove(opts)) => run_remove(opts),
+    }
+}
+
+/// Hosts file to manage; `FLOW_HOSTS_FILE` overrides `/etc/hosts`.
+fn hosts_path() -> PathBuf {
+    std::env::var_os("FLOW_HOSTS_FILE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/etc/hosts"))
+}
+
+fn run_list() -> Result<()> {
+    let path = hosts_path();
+    let contents = read_hosts(&path)?;
+    let domains = managed_domains(&contents);
+    if domains.is_empty() {
+        println!("No flow-managed domains in {}.", path.display());
+        println!("Add one with: f proxy domains add api.myapp.localhost");
+        return Ok(());
+    }
+    for domain in domains {
+        println!("{domain}");
+    }
+    Ok(())
+}
+
+fn run_add(opts: ProxyDomainsAddOpts) -> Result<()> {
+    let requested = if opts.hosts.is_empty() {
+        project_domains()?
+    } else {
+        opts.hosts
+            .iter()
+            .map(|raw| crate::domains::normalize_host(raw))
+            .collect::<Result<Vec<_>>>()?
+    };
+    if requested.is_empty() {
+        bail!("No domains to add. Pass hosts or declare `port` on tasks in flow.toml.");
+    }
+
+    let path = hosts_path();
+    let contents = read_hosts(&path)?;
+    let mut domains = managed_domains(&contents);
+    let added: Vec<String> = requested
+        .into_iter()
+        .filter(|domain| domains.insert(domain.clone()))
+        .collect();
+    if added.is_empty() {
+        println!("All domains are already in {}.", path.display());
+        return Ok(());
+    }
+
+    println!(
+        "Will point these domains at 127.0.0.1 and ::1 in {}:",
+        path.display()
+    );
+    for domain in &added {
+        println!("  + {domain}");
+    }
+    if !opts.yes && !prompt_yes_no("Update hosts file?", true)? {
+        println!("Aborted.");
+        return Ok(());
+    }
+    write_hosts(&path, &render_hosts(&contents, &domains))?;
+    flush_dns_cache();
+    println!("✓ Added {} domain(s)", added.len());
+    Ok(())
+}
+
+fn run_remove(opts: ProxyDomainsRemoveOpts) -> Result<()> {
+    let path = hosts_path();
+    let contents = read_hosts(&path)?;
+    let mut domains = managed_domains(&contents);
+    let removed: Vec<String> = if opts.all {
+        std::mem::take(&mut domains).into_iter().collect()
+    } else {
+        if opts.hosts.is_empty() {
+            bail!("Pass the domains to remove, or --all");
+        }
+        opts.hosts
+            .iter()
+            .map(|raw| crate::domains::normalize_host(raw))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|domain| domains.remove(domain))
+            .collect()
+    };
+    if removed.is_empty() {
+        println!("Nothing to remove.");
+        return Ok(());
+    }
+
+    println!("Will remove from {}:", path.display());
+    for domain in &removed {
+        println!("  - {domain}");
+    }
+    if !opts.yes && !prompt_yes_no("Update hosts file?", true)? {
+        println!("Aborted.");
+        return Ok(());
+    }
+    write_hosts(&path, &render_hosts(&contents, &domains))?;
+    flush_dns_cache();
+    println!("✓ Removed {} domain(s)", removed.len());
+    Ok(())
+}
+
+/// Domains for the current project: tasks with a `port` plus `[[proxies]]` hosts.
+fn project_domains() -> Result<Vec<String>> {
+    let cwd = std::env::current_dir()?;
+    let flow_toml = cwd.join("flow.toml");
+    if !flow_toml.exists() {
+        bail!("No flow.toml in {}; pass domains explicitly", cwd.display());
+    }
+    let cfg = config::load(&flow_toml)?;
+    let project = cfg
+        .project_name
+        .clone()
+        .or_else(|| {
+            cwd.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "flow".to_string());
+
+    let mut domains: Vec<String> = cfg
+        .tasks
+        .iter()
+        .filter(|task| task.port.is_some() && task.host.is_none())
+        .map(|task| control::task_route_host(&task.name, &project))
+        .collect();
+    domains.extend(
+        cfg.proxies
+            .iter()
+            .filter_map(|target| target.host.as_deref())
+            .filter_map(|host| crate::domains::normalize_host(host).ok()),
+    );
+    domains.sort();
+    domains.dedup();
+    Ok(domains)
+}
+
+fn read_hosts(path: &Path) -> Result<String> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => Ok(contents),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(err) => Err(err).with_context(|| format!("failed to read {}", path.display())),
+    }
+}
+
+/// Replace the hosts file atomically: the new contents go to a temp file in the same
+/// directory, which is then renamed over it, so readers never see a half-written file.
+/// Goes through `sudo` when the directory is not writable directly.
+fn write_hosts(path: &Path, contents: &str) -> Result<()> {
+    // Rename over the target itself, not a symlink pointing at it.
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let dir = path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("/"));
+    let mode = std::fs::metadata(&path)
+        .map(|meta| meta.permissions().mode() & 0o7777)
+        .unwrap_or(0o644);
+
+    match write_hosts_direct(&path, &dir, contents, mode) {
+        Ok(()) => return Ok(()),
+        Err(err) if err.kind() != std::io::ErrorKind::PermissionDenied => {
+            return Err(err).with_context(|| format!("failed to write {}", path.display()));
+        }
+        Err(_) => {}
+    }
+
+    println!("Writing {} requires sudo.", path.display());
+    let mut staged = NamedTempFile::new().context("failed to stage hosts file")?;
+    staged.write_all(contents.as_bytes())?;
+    staged.flush()?;
+    let temp = dir.join(format!(".hosts.flow-{}", std::process::id()));
+    let status = Command::new("sudo")
+        .args([
+            "sh",
+            "-c",
+            r#"install -m "$1" "$2" "$3" && mv -f "$3" "$4" || { rm -f "$3"; exit 1; }"#,
+            "sh",
+        ])
+        .arg(format!("{mode:o}"))
+        .arg(staged.path())
+        .arg(&temp)
+        .arg(&path)
+        .status()
+        .context("failed to run sudo")?;
+    if !status.success() {
+        bail!("sudo failed to replace {}", path.display());
+    }
+    Ok(())
+}
+
+fn write_hosts_direct(path: &Path, dir: &Path, contents: &str, mode: u32) -> std::io::Result<()> {
+    let mut temp = NamedTempFile::new_in(dir)?;
+    temp.write_all(contents.as_bytes())?;
+    temp.as_file().sync_all()?;
+    temp.as_file()
+        .set_permissions(std::fs::Permissions::from_mode(mode))?;
+    temp.persist(path).map_err(|err| err.error)?;
+    Ok(())
+}
+
+/// Drop cached lookups so new entries apply immediately (macOS only).
+fn flush_dns_cache() {
+    if !cfg!(target_os = "macos") {
+        return;
+    }
+    let _ = Command::new("dscacheutil").arg("-flushcache").status();
+    let _ = Command::new("sudo")
+        .args(["killall", "-HUP", "mDNSResponder"])
+        .status();
+}
+
+/// Domains listed inside the flow-managed block.
+fn managed_domains(contents: &str) -> BTreeSet<String> {
+    let mut domains = BTreeSet::new();
+    let mut inside = false;
+    for line in contents.lines() {
+        let trimmed = line.trim();
+        if trimmed == BLOCK_BEGIN {
+            inside = true;
+        } else if trimmed == BLOCK_END {
+            inside = false;
+        } else if inside && !trimmed.starts_with('#') {
+            domains.extend(trimmed.split_whitespace().skip(1).map(str::to_string));
+        }
+    }
+    domains
+}
+
+/// Replace (or append, or drop when `domains` is empty) the flow-managed block,
+/// leaving every other line of the hosts file untouched.
+fn render_hosts(contents: &str, domains: &BTreeSet<String>) -> String {
+    let mut out = String::new();
+    let mut inside = false;
+    let mut placed = false;
+    let block = render_block(domains);
+
+    for line in contents.lines() {
+        let trimmed = line.trim();
+        if trimmed == BLOCK_BEGIN {
+            inside = true;
+            if !placed {
+                out.push_str(&block);
+                placed = true;
+            }
+            continue;
+        }
+        if inside {
+            inside = trimmed != BLOCK_END;
+            continue;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    if !placed && !block.is_empty() {
+        if !out.is_empty() && !out.ends_with("\n\n") {
+            out.push('\n');
+        }
+        out.push_str(&block);
+    }
+    out
+}
+
+fn render_block(domains: &BTreeSet<String>) -> String {
+    if domains.is_empty() {
+        return String::new();
+    }
+    let mut block = format!("{BLOCK_BEGIN}\n");
+    for domain in domains {
+        block.push_str(&format!("127.0.0.1 {domain}\n::1 {domain}\n"));
+    }
+    block.push_str(BLOCK_END);
+    block.push('\n');
+    block
+}
+
+fn prompt_yes_no(message: &str, default_yes: bool) -> Result<bool> {
+    let prompt = if default_yes { "[Y/n]" } else { "[y/N]" };
+    print!("{message} {prompt}: ");
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    let answer = input.trim().to_ascii_lowercase();
+    if answer.is_empty() {
+        return Ok(default_yes);
+    }
+    Ok(answer == "y" || answer == "yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(domains: &[&str]) -> BTreeSet<String> {
+        domains.iter().map(|d| d.to_string()).collect()
+    }
+
+    #[test]
+    fn write_hosts_replaces_the_target_and_keeps_its_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let hosts = dir.path().join("hosts");
+        std::fs::write(&hosts, "127.0.0.1 localhost\n").unwrap();
+        std::fs::set_permissions(&hosts, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let link = dir.path().join("hosts-link");
+        std::os::unix::fs::symlink(&hosts, &link).unwrap();
+
+        write_hosts(&link, "127.0.0.1 app.localhost\n").unwrap();
+
+        assert!(link.is_symlink());
+        assert_eq!(
+            std::fs::read_to_string(&hosts).unwrap(),
+            "127.0.0.1 app.localhost\n"
+        );
+        let mode = std::fs::metadata(&hosts).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o644);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn render_hosts_appends_block_and_round_trips() {
+        let original = "127.0.0.1 localhost\n::1 localhost\n";
+        let rendered = render_hosts(original, &set(&["api.myapp.localhost"]));
+        assert!(rendered.starts_with(original));
+        assert!(rendered.contains("127.0.0.1 api.myapp.localhost\n::1 api.myapp.localhost\n"));
+        assert_eq!(managed_domains(&rendered), set(&["api.myapp.localhost"]));
+
+        let updated = render_hosts(&rendered, &set(&["a.x.localhost", "b.x.localhost"]));
+        assert_eq!(updated.matches(BLOCK_BEGIN).count(), 1);
+        assert_eq!(
+            managed_domains(&updated),
+            set(&["a.x.localhost", "b.x.localhost"])
+        );
+    }
+
+    #[test]
+    fn render_hosts_removes_empty_block_and_keeps_other_lines() {
+        let contents = format!(
+            "127.0.0.1 localhost\n\n{BLOCK_BEGIN}\n127.0.0.1 a.x.localhost\n{BLOCK_END}\n10.0.0.5 nas\n"
+        );
+        let rendered = render_hosts(&contents, &BTreeSet::new());
+        assert_eq!(rendered, "127.0.0.1 localhost\n\n10.0.0.5 nas\n");
+        assert!(managed_domains(&rendered).is_empty());
+    }
+}
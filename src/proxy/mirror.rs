@@ -0,0 +1,282 @@
+//! Request mirroring for shadow-testing a second backend.
+//!
+//! When a target sets `mirror_to`, a sampled share of its requests (`mirror_percent`, default
+//! 100) is also sent to the mirror backend. The client only ever sees the primary response;
+//! the mirror's status and latency are compared against it and reported under `mirrors` in the
+//! summary JSON.
+
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use axum::http::Method;
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+use super::ProxyTargetConfig;
+
+/// Header marking mirrored requests, so the shadow backend can tell them apart.
+pub const MIRROR_HEADER: &str = "x-flow-mirror";
+const MIRROR_TIMEOUT: Duration = Duration::from_secs(30);
+const RECENT_MISMATCHES: usize = 10;
+
+/// Resolved `mirror_to` of a target.
+#[derive(Debug)]
+pub struct Mirror {
+    pub addr: SocketAddr,
+    pub percent: f64,
+    stats: Mutex<MirrorStats>,
+}
+
+/// How the primary backend answered, sent to the mirror task once known.
+#[derive(Debug, Clone, Copy)]
+pub struct PrimaryOutcome {
+    pub status: u16,
+    pub latency_us: u32,
+}
+
+/// The forwarded request, replayed against the mirror.
+pub struct MirrorRequest {
+    pub method: Method,
+    /// Rewritten path plus query string.
+    pub path_and_query: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Default)]
+struct MirrorStats {
+    mirrored: u64,
+    /// Mirror requests that failed to complete (connection refused, timeout).
+    failed: u64,
+    compared: u64,
+    status_mismatches: u64,
+    /// Compared requests where both backends answered.
+    timed: u64,
+    /// Sum of mirror minus primary latency over `timed` requests.
+    latency_delta_us: i64,
+    recent_mismatches: VecDeque<MirrorMismatch>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MirrorMismatch {
+    pub method: String,
+    pub path: String,
+    pub primary_status: u16,
+    /// 0 when the mirror request failed.
+    pub mirror_status: u16,
+}
+
+/// Divergence stats for one mirrored target, as written to the summary JSON.
+#[derive(Debug, Clone, Serialize)]
+pub struct MirrorSummary {
+    pub mirror_to: String,
+    pub percent: f64,
+    pub mirrored: u64,
+    pub failed: u64,
+    pub status_mismatches: u64,
+    pub mismatch_rate: String,
+    /// Average mirror latency minus primary latency; positive means the mirror is slower.
+    pub avg_latency_delta_ms: f64,
+    pub recent_mismatches: Vec<MirrorMismatch>,
+}
+
+impl Mirror {
+    pub fn from_target(target: &ProxyTargetConfig) -> Result<Option<Self>> {
+        let Some(raw) = target.mirror_to.as_deref().map(str::trim) else {
+            return Ok(None);
+        };
+        let addr: SocketAddr = if raw.contains(':') {
+            raw.replacen("localhost", "127.0.0.1", 1).parse()
+        } else {
+            format!("127.0.0.1:{raw}").parse()
+        }
+        .with_context(|| format!("proxy '{}': invalid mirror_to '{raw}'", target.name))?;
+        let percent = target.mirror_percent.unwrap_or(100.0);
+        if !(0.0..=100.0).contains(&percent) {
+            bail!(
+                "proxy '{}': mirror_percent must be between 0 and 100",
+                target.name
+            );
+        }
+        Ok(Some(Self {
+            addr,
+            percent,
+            stats: Mutex::new(MirrorStats::default()),
+        }))
+    }
+
+    /// Whether request `req_id` is mirrored. Hashing the id spreads the sample evenly without
+    /// a random number generator on the request path.
+    pub fn sampled(&self, req_id: u64) -> bool {
+        if self.percent >= 100.0 {
+            return true;
+        }
+        let mut x = req_id.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        x ^= x >> 31;
+        ((x % 10_000) as f64) < self.percent * 100.0
+    }
+
+    /// Send `request` to the mirror in the background. The returned sender takes the primary
+    /// outcome; dropping it (e.g. when the client disconnects) skips the comparison.
+    pub fn spawn(
+        self: &Arc<Self>,
+        client: &reqwest::Client,
+        request: MirrorRequest,
+    ) -> oneshot::Sender<PrimaryOutcome> {
+        let (tx, rx) = oneshot::channel();
+        let mirror = self.clone();
+        let client = client.clone();
+        tokio::spawn(async move {
+            let url = format!("http://{}{}", mirror.addr, request.path_and_query);
+            let mut builder = client
+                .request(request.method.clone(), &url)
+                .timeout(MIRROR_TIMEOUT)
+                .header(MIRROR_HEADER, "1");
+            for (name, value) in &request.headers {
+                builder = builder.header(name.as_str(), value.as_str());
+            }
+            if let Some(body) = request.body {
+                builder = builder.body(body);
+            }
+            let started = Instant::now();
+            let mirror_status = match builder.send().await {
+                Ok(response) => Some(response.status().as_u16()),
+                Err(err) => {
+                    tracing::debug!(?err, mirror = %mirror.addr, "mirror request failed");
+                    None
+                }
+            };
+            // Measured to the response headers, like the primary's upstream latency.
+            let mirror_latency_us = started.elapsed().as_micros() as u32;
+            let primary = rx.await.ok();
+            mirror.record(
+                &request.method,
+                &request.path_and_query,
+                primary,
+                mirror_status,
+                mirror_latency_us,
+            );
+        });
+        tx
+    }
+
+    fn record(
+        &self,
+        method: &Method,
+        path: &str,
+        primary: Option<PrimaryOutcome>,
+        mirror_status: Option<u16>,
+        mirror_latency_us: u32,
+    ) {
+        let mut stats = self.lock_stats();
+        stats.mirrored += 1;
+        if mirror_status.is_none() {
+            stats.failed += 1;
+        }
+        let Some(primary) = primary else {
+            return;
+        };
+        stats.compared += 1;
+        if mirror_status.is_some() {
+            stats.timed += 1;
+            stats.latency_delta_us += mirror_latency_us as i64 - primary.latency_us as i64;
+        }
+        if mirror_status != Some(primary.status) {
+            stats.status_mismatches += 1;
+            if stats.recent_mismatches.len() == RECENT_MISMATCHES {
+                stats.recent_mismatches.pop_front();
+            }
+            stats.recent_mismatches.push_back(MirrorMismatch {
+                method: method.to_string(),
+                path: path.to_string(),
+                primary_status: primary.status,
+                mirror_status: mirror_status.unwrap_or(0),
+            });
+        }
+    }
+
+    pub fn summary(&self) -> MirrorSummary {
+        let stats = self.lock_stats();
+        let mismatch_rate = if stats.compared > 0 {
+            format!(
+                "{:.1}%",
+                stats.status_mismatches as f64 / stats.compared as f64 * 100.0
+            )
+        } else {
+            "0%".to_string()
+        };
+        let avg_latency_delta_ms = if stats.timed > 0 {
+            let ms = stats.latency_delta_us as f64 / stats.timed as f64 / 1000.0;
+            (ms * 10.0).round() / 10.0
+        } else {
+            0.0
+        };
+        MirrorSummary {
+            mirror_to: self.addr.to_string(),
+            percent: self.percent,
+            mirrored: stats.mirrored,
+            failed: stats.failed,
+            status_mismatches: stats.status_mismatches,
+            mismatch_rate,
+            avg_latency_delta_ms,
+            recent_mismatches: stats.recent_mismatches.iter().rev().cloned().collect(),
+        }
+    }
+
+    fn lock_stats(&self) -> MutexGuard<'_, MirrorStats> {
+        self.stats
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mirror(percent: f64) -> Mirror {
+        Mirror {
+            addr: "127.0.0.1:4000".parse().unwrap(),
+            percent,
+            stats: Mutex::new(MirrorStats::default()),
+        }
+    }
+
+    #[test]
+    fn sampling_tracks_the_configured_percentage() {
+        let quarter = mirror(25.0);
+        let sampled = (0..10_000).filter(|id| quarter.sampled(*id)).count();
+        assert!((2_300..2_700).contains(&sampled), "sampled {sampled}");
+        assert!((0..1_000).all(|id| mirror(100.0).sampled(id)));
+        assert!((0..1_000).all(|id| !mirror(0.0).sampled(id)));
+    }
+
+    #[test]
+    fn records_status_mismatches_and_latency_delta() {
+        let mirror = mirror(100.0);
+        let primary = |status| {
+            Some(PrimaryOutcome {
+                status,
+                latency_us: 10_000,
+            })
+        };
+        mirror.record(&Method::GET, "/ok", primary(200), Some(200), 14_000);
+        mirror.record(&Method::POST, "/orders", primary(201), Some(500), 12_000);
+        mirror.record(&Method::GET, "/down", primary(200), None, 30_000);
+
+        let summary = mirror.summary();
+        assert_eq!(summary.mirrored, 3);
+        assert_eq!(summary.failed, 1);
+        assert_eq!(summary.status_mismatches, 2);
+        assert_eq!(summary.mismatch_rate, "66.7%");
+        assert_eq!(summary.avg_latency_delta_ms, 3.0);
+        assert_eq!(summary.recent_mismatches[0].path, "/down");
+        assert_eq!(summary.recent_mismatches[0].mirror_status, 0);
+        assert_eq!(summary.recent_mismatches[1].mirror_status, 500);
+    }
+}
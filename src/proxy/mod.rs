@@ -8,24 +8,44 @@
 //! - **Trace ID propagation** across services
 //! - **Flow integration** via flow.toml configuration
 
+pub mod auth;
+pub mod circuit;
+pub mod control;
+pub mod cors;
+pub mod har;
+pub mod hosts;
+pub mod mirror;
+pub mod pool;
+pub mod ratelimit;
+pub mod rewrite;
 pub mod server;
 pub mod summary;
 pub mod trace;
+pub mod variant;
 
+use std::collections::BTreeMap;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use serde::{Deserialize, Serialize};
 
+use auth::{InjectAuthConfig, InjectedAuth};
+use circuit::{CircuitBreaker, CircuitSettings};
+use cors::{CorsConfig, CorsPolicy};
+use mirror::Mirror;
+use pool::{PoolSettings, UpstreamPool};
+use ratelimit::{RateLimitSpec, RateLimiter};
+use rewrite::RewriteRules;
 use server::{Backend, ProxyRouter, ProxyServer};
 use summary::{SummaryState, SummaryWriter};
 use trace::TraceBuffer;
+use variant::{VARIANT_COOKIE, VARIANT_HEADER, VariantConfig, VariantRoutes};
 
 /// Proxy configuration from flow.toml
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ProxyConfig {
     /// Listen address (e.g., ":8080" or "127.0.0.1:8080")
     #[serde(default = "default_listen")]
@@ -50,6 +70,64 @@ pub struct ProxyConfig {
     /// Slow request threshold in milliseconds
     #[serde(default = "default_slow_threshold")]
     pub slow_threshold_ms: u32,
+
+    /// Accept target registrations from running tasks over the control socket
+    #[serde(default = "default_true")]
+    pub auto_register: bool,
+
+    /// Per-client limit for every target without its own (e.g., "100/min")
+    #[serde(default)]
+    pub rate_limit: Option<String>,
+
+    /// Client key for `rate_limit`: "ip" (default) or "token"
+    #[serde(default)]
+    pub rate_limit_by: Option<String>,
+
+    /// Idle upstream connections kept open per backend
+    #[serde(default = "default_pool_max_idle")]
+    pub pool_max_idle: usize,
+
+    /// How long an idle upstream connection is kept (e.g., "90s")
+    #[serde(default = "default_pool_idle_timeout")]
+    pub pool_idle_timeout: String,
+
+    /// Upstream connect timeout for targets without their own (e.g., "5s")
+    #[serde(default = "default_connect_timeout")]
+    pub connect_timeout: String,
+
+    /// Upstream read timeout for targets without their own (unset: no limit)
+    #[serde(default)]
+    pub read_timeout: Option<String>,
+
+    /// Consecutive upstream failures that open a target's circuit (0 disables)
+    #[serde(default = "default_circuit_failures")]
+    pub circuit_failures: u32,
+
+    /// How long an open circuit answers 503 before probing the backend (e.g., "10s")
+    #[serde(default = "default_circuit_cooldown")]
+    pub circuit_cooldown: String,
+}
+
+impl Default for ProxyConfig {
+    fn default() -> Self {
+        Self {
+            listen: default_listen(),
+            trace_size: default_trace_size(),
+            trace_dir: None,
+            trace_summary: true,
+            summary_interval: default_summary_interval(),
+            slow_threshold_ms: default_slow_threshold(),
+            auto_register: true,
+            rate_limit: None,
+            rate_limit_by: None,
+            pool_max_idle: default_pool_max_idle(),
+            pool_idle_timeout: default_pool_idle_timeout(),
+            connect_timeout: default_connect_timeout(),
+            read_timeout: None,
+            circuit_failures: default_circuit_failures(),
+            circuit_cooldown: default_circuit_cooldown(),
+        }
+    }
 }
 
 fn default_listen() -> String {
@@ -72,6 +150,26 @@ fn default_slow_threshold() -> u32 {
     500
 }
 
+fn default_pool_max_idle() -> usize {
+    32
+}
+
+fn default_pool_idle_timeout() -> String {
+    "90s".to_string()
+}
+
+fn default_connect_timeout() -> String {
+    "5s".to_string()
+}
+
+fn default_circuit_failures() -> u32 {
+    5
+}
+
+fn default_circuit_cooldown() -> String {
+    "10s".to_string()
+}
+
 /// Individual proxy target configuration
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ProxyTargetConfig {
@@ -104,6 +202,83 @@ pub struct ProxyTargetConfig {
     /// Paths to exclude from tracing
     #[serde(default)]
     pub exclude_paths: Vec<String>,
+
+    /// Strip this prefix from the path before forwarding (e.g., "/api")
+    #[serde(default)]
+    pub strip_prefix: Option<String>,
+
+    /// Prepend this prefix to the (stripped) path before forwarding
+    #[serde(default)]
+    pub add_prefix: Option<String>,
+
+    /// Headers to set (or replace) on forwarded requests
+    #[serde(default)]
+    pub set_request_headers: BTreeMap<String, String>,
+
+    /// Headers to drop from forwarded requests
+    #[serde(default)]
+    pub remove_request_headers: Vec<String>,
+
+    /// Headers to set (or replace) on responses
+    #[serde(default)]
+    pub set_response_headers: BTreeMap<String, String>,
+
+    /// Headers to drop from responses
+    #[serde(default)]
+    pub remove_response_headers: Vec<String>,
+
+    /// CORS helper: "permissive", "mirror-origin", or { allow_origins = [...] }
+    #[serde(default)]
+    pub cors: Option<CorsConfig>,
+
+    /// Credential header filled from the flow env store:
+    /// { header = "Authorization", env_key = "API_TOKEN", prefix = "Bearer " }
+    #[serde(default)]
+    pub inject_auth: Option<InjectAuthConfig>,
+
+    /// Shadow backend (e.g., "localhost:4000") that receives a copy of sampled requests
+    #[serde(default)]
+    pub mirror_to: Option<String>,
+
+    /// Percentage of requests mirrored to `mirror_to` (default 100)
+    #[serde(default)]
+    pub mirror_percent: Option<f64>,
+
+    /// Per-client request limit (e.g., "100/min"); excess requests get a 429
+    #[serde(default)]
+    pub rate_limit: Option<String>,
+
+    /// Client key for `rate_limit`: "ip" (default) or "token"
+    #[serde(default)]
+    pub rate_limit_by: Option<String>,
+
+    /// Upstream connect timeout (e.g., "2s"; defaults to `[proxy] connect_timeout`)
+    #[serde(default)]
+    pub connect_timeout: Option<String>,
+
+    /// Upstream read timeout (e.g., "30s"; defaults to `[proxy] read_timeout`)
+    #[serde(default)]
+    pub read_timeout: Option<String>,
+
+    /// Consecutive failures that open the circuit (defaults to `[proxy] circuit_failures`)
+    #[serde(default)]
+    pub circuit_failures: Option<u32>,
+
+    /// Open-circuit cooldown (e.g., "30s"; defaults to `[proxy] circuit_cooldown`)
+    #[serde(default)]
+    pub circuit_cooldown: Option<String>,
+
+    /// Alternate backends selected per request: [{ name = "next", target = "localhost:3001" }]
+    #[serde(default)]
+    pub variants: Vec<VariantConfig>,
+
+    /// Header selecting a variant (default "x-flow-variant")
+    #[serde(default)]
+    pub variant_header: Option<String>,
+
+    /// Cookie selecting a variant (default "flow_variant")
+    #[serde(default)]
+    pub variant_cookie: Option<String>,
 }
 
 fn default_capture_max() -> String {
@@ -163,6 +338,21 @@ pub async fn start(config: ProxyConfig, targets: Vec<ProxyTargetConfig>) -> Resu
         TraceBuffer::init(&trace_dir, trace_size).context("Failed to initialize trace buffer")?;
     let trace_buffer = Arc::new(trace_buffer);
 
+    // Fetch injected credentials up front (env store reads are blocking HTTP/file calls)
+    let auth_targets = targets.clone();
+    let mut credentials = tokio::task::spawn_blocking(move || {
+        auth_targets
+            .iter()
+            .map(InjectedAuth::resolve)
+            .collect::<Result<Vec<_>>>()
+    })
+    .await
+    .context("credential lookup panicked")??;
+
+    let default_rate_limit = RateLimitSpec::from_proxy(&config)?;
+    let default_pool = PoolSettings::from_proxy(&config);
+    let default_circuit = CircuitSettings::from_proxy(&config);
+
     // Build backends
     let mut backends = Vec::new();
     for (idx, target) in targets.iter().enumerate() {
@@ -177,11 +367,76 @@ pub async fn start(config: ProxyConfig, targets: Vec<ProxyTargetConfig>) -> Resu
             name: target.name.clone(),
             addr,
             index: idx as u8,
+            rewrite: Arc::new(RewriteRules::from_target(target)?),
+            cors: target
+                .cors
+                .as_ref()
+                .map(|cors| {
+                    CorsPolicy::from_config(cors)
+                        .map(Arc::new)
+                        .with_context(|| format!("proxy '{}': invalid cors", target.name))
+                })
+                .transpose()?,
+            auth: credentials[idx].take().map(Arc::new),
+            mirror: Mirror::from_target(target)?.map(Arc::new),
+            rate_limit: RateLimiter::from_target(target, default_rate_limit.as_ref())?
+                .map(Arc::new),
+            pool: Arc::new(UpstreamPool::new(default_pool.for_target(target))?),
+            circuit: CircuitSettings::for_target(default_circuit.as_ref(), target)
+                .map(|settings| Arc::new(CircuitBreaker::new(settings))),
+            variants: None,
         });
     }
 
+    // Variants get their own backend slots after the configured targets, so their traces carry
+    // a distinct index; they share the stable target's rewrites, CORS, auth, and rate limit.
+    for (idx, target) in targets.iter().enumerate() {
+        let addrs = variant::variant_addrs(target)?;
+        if addrs.is_empty() {
+            continue;
+        }
+        let mut routes = Vec::new();
+        for (name, addr) in addrs {
+            let slot = backends.len();
+            if slot > u8::MAX as usize {
+                bail!("too many proxy targets and variants (max 256)");
+            }
+            let primary = &backends[idx];
+            backends.push(Backend {
+                name: variant::backend_name(&target.name, &name),
+                addr,
+                index: slot as u8,
+                rewrite: primary.rewrite.clone(),
+                cors: primary.cors.clone(),
+                auth: primary.auth.clone(),
+                mirror: None,
+                rate_limit: primary.rate_limit.clone(),
+                pool: Arc::new(UpstreamPool::new(default_pool.for_target(target))?),
+                circuit: CircuitSettings::for_target(default_circuit.as_ref(), target)
+                    .map(|settings| Arc::new(CircuitBreaker::new(settings))),
+                variants: None,
+            });
+            routes.push((name, slot));
+        }
+        backends[idx].variants = Some(Arc::new(VariantRoutes {
+            header: target
+                .variant_header
+                .clone()
+                .unwrap_or_else(|| VARIANT_HEADER.to_string())
+                .to_ascii_lowercase(),
+            cookie: target
+                .variant_cookie
+                .clone()
+                .unwrap_or_else(|| VARIANT_COOKIE.to_string()),
+            variants: routes,
+        }));
+    }
+
     // Build router
     let mut router = ProxyRouter::new(backends);
+    router.default_rate_limit = default_rate_limit;
+    router.default_pool = default_pool;
+    router.default_circuit = default_circuit;
 
     for (idx, target) in targets.iter().enumerate() {
         if let Some(host) = &target.host {
@@ -194,7 +449,17 @@ pub async fn start(config: ProxyConfig, targets: Vec<ProxyTargetConfig>) -> Resu
 
     // Create summary state
     let target_names = router.backend_names();
-    let summary_state = Arc::new(SummaryState::new(target_names, config.slow_threshold_ms));
+    let mirrors = router.backend_mirrors();
+    let rate_limits = router.backend_rate_limits();
+    let pools = router.backend_pools();
+    let circuits = router.backend_circuits();
+    let summary_state = Arc::new(
+        SummaryState::new(target_names, config.slow_threshold_ms)
+            .with_mirrors(mirrors)
+            .with_rate_limits(rate_limits)
+            .with_pools(pools)
+            .with_circuits(circuits),
+    );
 
     // Create server
     let server = Arc::new(ProxyServer::new(
@@ -203,6 +468,17 @@ pub async fn start(config: ProxyConfig, targets: Vec<ProxyTargetConfig>) -> Resu
         summary_state.clone(),
     ));
 
+    // Accept routes from tasks that declare a port
+    if config.auto_register {
+        let socket = control::default_socket_path();
+        let control_server = server.clone();
+        tokio::spawn(async move {
+            if let Err(err) = control::serve(socket, control_server).await {
+                tracing::warn!(?err, "proxy control socket stopped");
+            }
+        });
+    }
+
     // Start summary writer if enabled
     if config.trace_summary {
         let summary_path = trace_dir.join("trace-summary.json");
@@ -222,7 +498,48 @@ pub async fn start(config: ProxyConfig, targets: Vec<ProxyTargetConfig>) -> Resu
     println!("Trace buffer: {:?} ({} bytes)", trace_dir, trace_size);
     println!("Targets:");
     for target in &targets {
-        println!("  {} -> {}", target.name, target.target);
+        match &target.inject_auth {
+            Some(auth) => println!(
+                "  {} -> {} ({} from env {})",
+                target.name, target.target, auth.header, auth.env_key
+            ),
+            None => println!("  {} -> {}", target.name, target.target),
+        }
+        if let Some(mirror_to) = &target.mirror_to {
+            println!(
+                "    mirrored to {} ({}%)",
+                mirror_to,
+                target.mirror_percent.unwrap_or(100.0)
+            );
+        }
+        for variant in &target.variants {
+            println!(
+                "    variant {} -> {} ({}: {} or cookie {}={})",
+                variant.name,
+                variant.target,
+                target.variant_header.as_deref().unwrap_or(VARIANT_HEADER),
+                variant.name,
+                target.variant_cookie.as_deref().unwrap_or(VARIANT_COOKIE),
+                variant.name
+            );
+        }
+        let rate_limit = match &target.rate_limit {
+            Some(limit) => Some((limit, &target.rate_limit_by)),
+            None => config
+                .rate_limit
+                .as_ref()
+                .map(|l| (l, &config.rate_limit_by)),
+        };
+        if let Some((limit, by)) = rate_limit {
+            println!(
+                "    rate limited to {} per {}",
+                limit,
+                by.as_deref().unwrap_or("ip")
+            );
+        }
+    }
+    if config.auto_register {
+        println!("  (tasks with a port register as <task>.<project>.localhost)");
     }
 
     // Run server
@@ -254,17 +571,17 @@ pub fn trace_last(count: usize) -> Result<()> {
     let records = buffer.recent(count);
 
     println!(
-        "{:<12} {:<8} {:<6} {:<40} {:<6} {:<10} {:<10}",
-        "TIME", "REQ_ID", "METHOD", "PATH", "STATUS", "LATENCY", "TARGET"
+        "{:<12} {:<8} {:<6} {:<40} {:<6} {:<10} {:<10} {:<10}",
+        "TIME", "REQ_ID", "METHOD", "PATH", "STATUS", "LATENCY", "TARGET", "REWRITES"
     );
-    println!("{}", "-".repeat(100));
+    println!("{}", "-".repeat(110));
 
     for record in records {
         if record.timestamp() == 0 {
             continue;
         }
         println!(
-            "{:<12} {:<8x} {:<6} {:<40} {:<6} {:<10} {:<10}",
+            "{:<12} {:<8x} {:<6} {:<40} {:<6} {:<10} {:<10} {:<10}",
             format!("{}ms ago", record.timestamp() / 1_000_000),
             record.req_id(),
             format!("{:?}", record.method()),
@@ -272,6 +589,7 @@ pub fn trace_last(count: usize) -> Result<()> {
             record.status(),
             format!("{}ms", record.latency_us() / 1000),
             record.target_idx(),
+            trace::describe_flags(record.flags()),
         );
     }
 
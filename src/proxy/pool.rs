@@ -0,0 +1,276 @@
+//! Upstream connection pools.
+//!
+//! Every backend gets its own HTTP client, so its idle pool size, keep-alive, and connect/read
+//! timeouts can be tuned per target. reqwest doesn't say whether a request reused a pooled
+//! connection, so each pool mirrors the client's idle set (bounded by `pool_max_idle`, expiring
+//! after `pool_idle_timeout`) to count reuse; connect errors and timeouts come from the request
+//! errors. The counters are reported under `upstream_pools` in the summary JSON.
+
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use super::{ProxyConfig, ProxyTargetConfig, parse_duration};
+
+const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
+
+/// Pool and timeout settings for one backend.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolSettings {
+    pub max_idle: usize,
+    pub idle_timeout: Duration,
+    pub connect_timeout: Duration,
+    /// Per read; `None` waits as long as the backend takes (e.g. streaming responses).
+    pub read_timeout: Option<Duration>,
+}
+
+impl PoolSettings {
+    /// The `[proxy]` defaults, used as-is for routes registered at run time.
+    pub fn from_proxy(config: &ProxyConfig) -> Self {
+        Self {
+            max_idle: config.pool_max_idle,
+            idle_timeout: parse_duration(&config.pool_idle_timeout),
+            connect_timeout: parse_duration(&config.connect_timeout),
+            read_timeout: config.read_timeout.as_deref().map(parse_duration),
+        }
+    }
+
+    /// The defaults with a target's own `connect_timeout` / `read_timeout` applied.
+    pub fn for_target(&self, target: &ProxyTargetConfig) -> Self {
+        Self {
+            connect_timeout: target
+                .connect_timeout
+                .as_deref()
+                .map(parse_duration)
+                .unwrap_or(self.connect_timeout),
+            read_timeout: target
+                .read_timeout
+                .as_deref()
+                .map(parse_duration)
+                .or(self.read_timeout),
+            ..self.clone()
+        }
+    }
+}
+
+/// How a forwarded request ended, for the pool counters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpstreamOutcome {
+    /// Response read; `keep_alive` is false when the backend asked to close the connection.
+    Completed {
+        keep_alive: bool,
+    },
+    ConnectError,
+    Timeout,
+    /// Any other failure; the connection is not returned to the pool.
+    Failed,
+}
+
+#[derive(Debug, Default)]
+struct PoolState {
+    /// When each idle connection was returned, oldest first.
+    idle: VecDeque<Instant>,
+    in_flight: usize,
+    requests: u64,
+    reused: u64,
+    connect_errors: u64,
+    timeouts: u64,
+    failures: u64,
+}
+
+/// A backend's HTTP client and its pool counters.
+#[derive(Debug)]
+pub struct UpstreamPool {
+    pub client: reqwest::Client,
+    pub settings: PoolSettings,
+    state: Mutex<PoolState>,
+}
+
+/// Counters for one backend, as written to the summary JSON.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PoolSummary {
+    pub requests: u64,
+    pub reused: u64,
+    /// Share of requests served on an already-open connection.
+    pub reuse_rate: f64,
+    pub new_connections: u64,
+    pub connect_errors: u64,
+    pub timeouts: u64,
+    /// Other failed requests (e.g. the backend reset the connection).
+    pub failures: u64,
+    pub idle: usize,
+    pub in_flight: usize,
+    pub max_idle: usize,
+    pub connect_timeout_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_timeout_ms: Option<u64>,
+}
+
+impl UpstreamPool {
+    pub fn new(settings: PoolSettings) -> Result<Self> {
+        let mut builder = reqwest::Client::builder()
+            .pool_max_idle_per_host(settings.max_idle)
+            .pool_idle_timeout(settings.idle_timeout)
+            .connect_timeout(settings.connect_timeout)
+            .tcp_keepalive(TCP_KEEPALIVE)
+            .tcp_nodelay(true);
+        if let Some(read_timeout) = settings.read_timeout {
+            builder = builder.read_timeout(read_timeout);
+        }
+        let client = builder
+            .build()
+            .context("failed to create upstream HTTP client")?;
+        Ok(Self {
+            client,
+            settings,
+            state: Mutex::new(PoolState::default()),
+        })
+    }
+
+    /// Count a request about to be sent; returns whether it gets a pooled connection.
+    pub fn begin(&self, now: Instant) -> bool {
+        let mut state = self.lock();
+        let idle_timeout = self.settings.idle_timeout;
+        while state
+            .idle
+            .front()
+            .is_some_and(|returned| now.duration_since(*returned) >= idle_timeout)
+        {
+            state.idle.pop_front();
+        }
+        state.requests += 1;
+        state.in_flight += 1;
+        // The client hands out the most recently returned connection.
+        let reused = state.idle.pop_back().is_some();
+        if reused {
+            state.reused += 1;
+        }
+        reused
+    }
+
+    pub fn finish(&self, now: Instant, outcome: UpstreamOutcome) {
+        let mut state = self.lock();
+        state.in_flight = state.in_flight.saturating_sub(1);
+        match outcome {
+            UpstreamOutcome::Completed { keep_alive: true } => {
+                if state.idle.len() < self.settings.max_idle {
+                    state.idle.push_back(now);
+                }
+            }
+            UpstreamOutcome::Completed { keep_alive: false } => {}
+            UpstreamOutcome::ConnectError => state.connect_errors += 1,
+            UpstreamOutcome::Timeout => state.timeouts += 1,
+            UpstreamOutcome::Failed => state.failures += 1,
+        }
+    }
+
+    /// Forget idle connections, e.g. after a registered route moves to a new port.
+    pub fn reset_idle(&self) {
+        self.lock().idle.clear();
+    }
+
+    pub fn summary(&self) -> PoolSummary {
+        let state = self.lock();
+        PoolSummary {
+            requests: state.requests,
+            reused: state.reused,
+            reuse_rate: if state.requests == 0 {
+                0.0
+            } else {
+                state.reused as f64 / state.requests as f64
+            },
+            new_connections: state.requests - state.reused,
+            connect_errors: state.connect_errors,
+            timeouts: state.timeouts,
+            failures: state.failures,
+            idle: state.idle.len(),
+            in_flight: state.in_flight,
+            max_idle: self.settings.max_idle,
+            connect_timeout_ms: self.settings.connect_timeout.as_millis() as u64,
+            read_timeout_ms: self.settings.read_timeout.map(|t| t.as_millis() as u64),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PoolState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Classify a failed upstream request.
+pub fn error_outcome(err: &reqwest::Error) -> UpstreamOutcome {
+    if err.is_timeout() {
+        UpstreamOutcome::Timeout
+    } else if err.is_connect() {
+        UpstreamOutcome::ConnectError
+    } else {
+        UpstreamOutcome::Failed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(max_idle: usize) -> PoolSettings {
+        PoolSettings {
+            max_idle,
+            idle_timeout: Duration::from_secs(90),
+            connect_timeout: Duration::from_secs(5),
+            read_timeout: None,
+        }
+    }
+
+    #[test]
+    fn counts_reuse_within_the_idle_limit() {
+        let pool = UpstreamPool::new(settings(1)).unwrap();
+        let start = Instant::now();
+        let keep = UpstreamOutcome::Completed { keep_alive: true };
+
+        // Two concurrent requests open two connections; only one fits back in the pool.
+        assert!(!pool.begin(start));
+        assert!(!pool.begin(start));
+        pool.finish(start, keep);
+        pool.finish(start, keep);
+        assert!(pool.begin(start));
+        pool.finish(start, UpstreamOutcome::Completed { keep_alive: false });
+        assert!(!pool.begin(start));
+        pool.finish(start, UpstreamOutcome::ConnectError);
+
+        let summary = pool.summary();
+        assert_eq!(
+            (summary.requests, summary.reused, summary.new_connections),
+            (4, 1, 3)
+        );
+        assert_eq!(
+            (summary.connect_errors, summary.idle, summary.in_flight),
+            (1, 0, 0)
+        );
+        assert_eq!(summary.reuse_rate, 0.25);
+    }
+
+    #[test]
+    fn idle_connections_expire() {
+        let pool = UpstreamPool::new(settings(4)).unwrap();
+        let start = Instant::now();
+        pool.begin(start);
+        pool.finish(start, UpstreamOutcome::Completed { keep_alive: true });
+        assert!(!pool.begin(start + Duration::from_secs(91)));
+    }
+
+    #[test]
+    fn targets_override_timeouts() {
+        let defaults = settings(8);
+        let target: ProxyTargetConfig =
+            toml::from_str("name = \"api\"\ntarget = \"localhost:3000\"\nread_timeout = \"30s\"")
+                .unwrap();
+        let resolved = defaults.for_target(&target);
+        assert_eq!(resolved.read_timeout, Some(Duration::from_secs(30)));
+        assert_eq!(resolved.connect_timeout, Duration::from_secs(5));
+        assert_eq!(resolved.max_idle, 8);
+    }
+}
@@ -0,0 +1,359 @@
+//! Per-client rate limiting for shared proxies.
+//!
+//! A target with `rate_limit = "100/min"` (or every target, via `[proxy] rate_limit`) gets a
+//! token bucket per client: per IP by default, or per bearer/API token with
+//! `rate_limit_by = "token"`. Requests over the limit get a 429 without reaching the backend,
+//! and per-target counters are reported under `rate_limits` in the summary JSON.
+
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::net::SocketAddr;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use axum::http::HeaderMap;
+use serde::Serialize;
+
+use super::{ProxyConfig, ProxyTargetConfig};
+
+/// Buckets kept before idle (refilled) ones are pruned.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+const TOP_LIMITED_CLIENTS: usize = 5;
+
+/// What identifies a client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitKey {
+    Ip,
+    /// `Authorization` or `x-api-key` header, falling back to the IP without one.
+    Token,
+}
+
+/// Parsed `rate_limit` / `rate_limit_by`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitSpec {
+    pub limit: u32,
+    pub period: Duration,
+    pub key: RateLimitKey,
+    /// As written in flow.toml, for headers and the summary.
+    pub label: String,
+}
+
+impl RateLimitSpec {
+    /// `"100/min"`, `"10/s"`, `"1000/hour"`, or `"30/10s"`.
+    pub fn parse(raw: &str, by: Option<&str>) -> Result<Self> {
+        let raw = raw.trim();
+        let (count, window) = raw
+            .split_once('/')
+            .with_context(|| format!("invalid rate_limit '{raw}' (expected e.g. \"100/min\")"))?;
+        let limit: u32 = count
+            .trim()
+            .parse()
+            .ok()
+            .filter(|limit| *limit > 0)
+            .with_context(|| format!("invalid rate_limit '{raw}': count must be positive"))?;
+        let window = window.trim().to_ascii_lowercase();
+        let split = window
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(window.len());
+        let (multiplier, unit) = window.split_at(split);
+        let multiplier: u64 = if multiplier.is_empty() {
+            1
+        } else {
+            multiplier
+                .parse()
+                .with_context(|| format!("invalid rate_limit '{raw}'"))?
+        };
+        let unit_secs = match unit {
+            "s" | "sec" | "second" => 1,
+            "m" | "min" | "minute" => 60,
+            "h" | "hr" | "hour" => 3600,
+            _ => bail!("invalid rate_limit '{raw}': unit must be s, min, or hour"),
+        };
+        if multiplier == 0 {
+            bail!("invalid rate_limit '{raw}': window must be positive");
+        }
+        let key = match by.map(|by| by.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("ip") => RateLimitKey::Ip,
+            Some("token") => RateLimitKey::Token,
+            Some(other) => bail!("invalid rate_limit_by '{other}' (use \"ip\" or \"token\")"),
+        };
+        Ok(Self {
+            limit,
+            period: Duration::from_secs(multiplier * unit_secs),
+            key,
+            label: raw.to_string(),
+        })
+    }
+
+    /// The proxy-wide default from `[proxy] rate_limit`.
+    pub fn from_proxy(config: &ProxyConfig) -> Result<Option<Self>> {
+        config
+            .rate_limit
+            .as_deref()
+            .map(|raw| Self::parse(raw, config.rate_limit_by.as_deref()))
+            .transpose()
+            .context("[proxy] rate_limit")
+    }
+
+    fn refill_per_sec(&self) -> f64 {
+        f64::from(self.limit) / self.period.as_secs_f64()
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+#[derive(Debug, Default)]
+struct RateLimitStats {
+    allowed: u64,
+    limited: u64,
+    limited_by_client: HashMap<String, u64>,
+}
+
+/// Outcome of one request against the limiter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decision {
+    Allowed { remaining: u32 },
+    Limited { retry_after: Duration },
+}
+
+/// Token buckets for one target.
+#[derive(Debug)]
+pub struct RateLimiter {
+    pub spec: RateLimitSpec,
+    buckets: Mutex<HashMap<String, Bucket>>,
+    stats: Mutex<RateLimitStats>,
+}
+
+/// Counters for one rate-limited target, as written to the summary JSON.
+#[derive(Debug, Clone, Serialize)]
+pub struct RateLimitSummary {
+    pub limit: String,
+    pub by: &'static str,
+    pub allowed: u64,
+    pub limited: u64,
+    pub clients: usize,
+    /// Clients with the most 429s (IPs, or hashed tokens).
+    pub top_limited: Vec<LimitedClient>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LimitedClient {
+    pub client: String,
+    pub limited: u64,
+}
+
+impl RateLimiter {
+    pub fn new(spec: RateLimitSpec) -> Self {
+        Self {
+            spec,
+            buckets: Mutex::new(HashMap::new()),
+            stats: Mutex::new(RateLimitStats::default()),
+        }
+    }
+
+    /// The target's own `rate_limit`, else the proxy-wide default.
+    pub fn from_target(
+        target: &ProxyTargetConfig,
+        default: Option<&RateLimitSpec>,
+    ) -> Result<Option<Self>> {
+        let spec = match target.rate_limit.as_deref() {
+            Some(raw) => RateLimitSpec::parse(raw, target.rate_limit_by.as_deref())
+                .with_context(|| format!("proxy '{}'", target.name))?,
+            None => match default {
+                Some(spec) => spec.clone(),
+                None => return Ok(None),
+            },
+        };
+        Ok(Some(Self::new(spec)))
+    }
+
+    pub fn check(&self, client: &str, now: Instant) -> Decision {
+        let capacity = f64::from(self.spec.limit);
+        let rate = self.spec.refill_per_sec();
+        let decision = {
+            let mut buckets = lock(&self.buckets);
+            if buckets.len() >= MAX_TRACKED_CLIENTS {
+                let period = self.spec.period;
+                buckets.retain(|_, bucket| now.duration_since(bucket.updated) < period);
+            }
+            let bucket = buckets.entry(client.to_string()).or_insert(Bucket {
+                tokens: capacity,
+                updated: now,
+            });
+            let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+            bucket.updated = now;
+            if bucket.tokens >= 1.0 {
+                bucket.tokens -= 1.0;
+                Decision::Allowed {
+                    remaining: bucket.tokens.floor() as u32,
+                }
+            } else {
+                Decision::Limited {
+                    retry_after: Duration::from_secs_f64((1.0 - bucket.tokens) / rate),
+                }
+            }
+        };
+        let mut stats = lock(&self.stats);
+        match decision {
+            Decision::Allowed { .. } => stats.allowed += 1,
+            Decision::Limited { .. } => {
+                stats.limited += 1;
+                *stats
+                    .limited_by_client
+                    .entry(client.to_string())
+                    .or_insert(0) += 1;
+            }
+        }
+        decision
+    }
+
+    /// Bucket key for a request: `ip:<addr>` or `token:<hash>`.
+    pub fn client_key(&self, peer: SocketAddr, headers: &HeaderMap) -> String {
+        if self.spec.key == RateLimitKey::Token
+            && let Some(token) = ["authorization", "x-api-key"]
+                .iter()
+                .find_map(|name| headers.get(*name)?.to_str().ok())
+                .filter(|token| !token.trim().is_empty())
+        {
+            // Never keep the credential itself, only enough to tell clients apart.
+            let mut hasher = DefaultHasher::new();
+            token.trim().hash(&mut hasher);
+            return format!("token:{:08x}", hasher.finish() as u32);
+        }
+        format!("ip:{}", client_ip(peer, headers))
+    }
+
+    pub fn summary(&self) -> RateLimitSummary {
+        let clients = lock(&self.buckets).len();
+        let stats = lock(&self.stats);
+        let mut top: Vec<LimitedClient> = stats
+            .limited_by_client
+            .iter()
+            .map(|(client, limited)| LimitedClient {
+                client: client.clone(),
+                limited: *limited,
+            })
+            .collect();
+        top.sort_by(|a, b| b.limited.cmp(&a.limited).then(a.client.cmp(&b.client)));
+        top.truncate(TOP_LIMITED_CLIENTS);
+        RateLimitSummary {
+            limit: self.spec.label.clone(),
+            by: match self.spec.key {
+                RateLimitKey::Ip => "ip",
+                RateLimitKey::Token => "token",
+            },
+            allowed: stats.allowed,
+            limited: stats.limited,
+            clients,
+            top_limited: top,
+        }
+    }
+}
+
+/// The peer address, or the first `x-forwarded-for` / `cf-connecting-ip` hop when the peer is
+/// a local tunnel (cloudflared, ngrok) forwarding for someone else.
+fn client_ip(peer: SocketAddr, headers: &HeaderMap) -> String {
+    if peer.ip().is_loopback() {
+        let forwarded = headers
+            .get("cf-connecting-ip")
+            .or_else(|| headers.get("x-forwarded-for"))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .map(str::trim)
+            .filter(|value| !value.is_empty());
+        if let Some(forwarded) = forwarded {
+            return forwarded.to_string();
+        }
+    }
+    peer.ip().to_string()
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_limits() {
+        let spec = RateLimitSpec::parse("100/min", None).unwrap();
+        assert_eq!(
+            (spec.limit, spec.period, spec.key),
+            (100, Duration::from_secs(60), RateLimitKey::Ip)
+        );
+        let spec = RateLimitSpec::parse("30/10s", Some("token")).unwrap();
+        assert_eq!(
+            (spec.limit, spec.period, spec.key),
+            (30, Duration::from_secs(10), RateLimitKey::Token)
+        );
+        assert_eq!(
+            RateLimitSpec::parse("5/hour", None).unwrap().period,
+            Duration::from_secs(3600)
+        );
+        for bad in ["100", "0/min", "10/fortnight", "10/0s"] {
+            assert!(RateLimitSpec::parse(bad, None).is_err(), "{bad}");
+        }
+        assert!(RateLimitSpec::parse("10/s", Some("cookie")).is_err());
+    }
+
+    #[test]
+    fn limits_each_client_and_refills() {
+        let limiter = RateLimiter::new(RateLimitSpec::parse("2/s", None).unwrap());
+        let start = Instant::now();
+        assert_eq!(
+            limiter.check("ip:a", start),
+            Decision::Allowed { remaining: 1 }
+        );
+        assert_eq!(
+            limiter.check("ip:a", start),
+            Decision::Allowed { remaining: 0 }
+        );
+        let Decision::Limited { retry_after } = limiter.check("ip:a", start) else {
+            panic!("third request in the same instant should be limited");
+        };
+        assert_eq!(retry_after, Duration::from_millis(500));
+        assert!(matches!(
+            limiter.check("ip:b", start),
+            Decision::Allowed { .. }
+        ));
+        assert!(matches!(
+            limiter.check("ip:a", start + Duration::from_millis(500)),
+            Decision::Allowed { .. }
+        ));
+
+        let summary = limiter.summary();
+        assert_eq!(
+            (summary.allowed, summary.limited, summary.clients),
+            (4, 1, 2)
+        );
+        assert_eq!(summary.top_limited[0].client, "ip:a");
+    }
+
+    #[test]
+    fn keys_by_token_or_forwarded_ip() {
+        let local: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+        let remote: SocketAddr = "10.0.0.7:50000".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "203.0.113.9, 10.0.0.1".parse().unwrap());
+
+        let by_ip = RateLimiter::new(RateLimitSpec::parse("1/s", None).unwrap());
+        assert_eq!(by_ip.client_key(local, &headers), "ip:203.0.113.9");
+        assert_eq!(by_ip.client_key(remote, &headers), "ip:10.0.0.7");
+
+        let by_token = RateLimiter::new(RateLimitSpec::parse("1/s", Some("token")).unwrap());
+        assert_eq!(by_token.client_key(remote, &headers), "ip:10.0.0.7");
+        headers.insert("authorization", "Bearer sk-123".parse().unwrap());
+        let key = by_token.client_key(remote, &headers);
+        assert!(key.starts_with("token:") && !key.contains("sk-123"));
+    }
+}
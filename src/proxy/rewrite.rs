@@ -0,0 +1,204 @@
+//! Per-target rewrite rules.
+//!
+//! Applied by the proxy handler before forwarding (path prefix + request headers) and before
+//! responding (response headers). Applied rewrites are recorded as trace flags.
+
+use anyhow::{Context, Result, bail};
+use axum::http::{HeaderName, HeaderValue};
+
+use super::ProxyTargetConfig;
+
+/// Rewrite rules for a single backend.
+#[derive(Debug, Clone, Default)]
+pub struct RewriteRules {
+    pub strip_prefix: Option<String>,
+    pub add_prefix: Option<String>,
+    pub set_request_headers: Vec<(String, String)>,
+    /// Lowercased header names.
+    pub remove_request_headers: Vec<String>,
+    pub set_response_headers: Vec<(String, String)>,
+    /// Lowercased header names.
+    pub remove_response_headers: Vec<String>,
+}
+
+impl RewriteRules {
+    /// Build and validate rules from a `[[proxies]]` entry.
+    pub fn from_target(target: &ProxyTargetConfig) -> Result<Self> {
+        let context = |field: &str| format!("proxy '{}': invalid {}", target.name, field);
+        Ok(Self {
+            strip_prefix: normalize_prefix(target.strip_prefix.as_deref())
+                .with_context(|| context("strip_prefix"))?,
+            add_prefix: normalize_prefix(target.add_prefix.as_deref())
+                .with_context(|| context("add_prefix"))?,
+            set_request_headers: validate_set(&target.set_request_headers)
+                .with_context(|| context("set_request_headers"))?,
+            remove_request_headers: validate_names(&target.remove_request_headers)
+                .with_context(|| context("remove_request_headers"))?,
+            set_response_headers: validate_set(&target.set_response_headers)
+                .with_context(|| context("set_response_headers"))?,
+            remove_response_headers: validate_names(&target.remove_response_headers)
+                .with_context(|| context("remove_response_headers"))?,
+        })
+    }
+
+    /// Rewrite the upstream path. Returns `None` when no rule applies.
+    pub fn rewrite_path(&self, path: &str) -> Option<String> {
+        let mut rest = path;
+        let mut changed = false;
+        if let Some(prefix) = self.strip_prefix.as_deref()
+            && let Some(stripped) = path.strip_prefix(prefix)
+            && (stripped.is_empty() || stripped.starts_with('/'))
+        {
+            rest = stripped;
+            changed = true;
+        }
+        if let Some(prefix) = self.add_prefix.as_deref() {
+            let joined = format!("{prefix}{}", if rest.is_empty() { "/" } else { rest });
+            return Some(joined);
+        }
+        if !changed {
+            return None;
+        }
+        Some(if rest.is_empty() {
+            "/".to_string()
+        } else {
+            rest.to_string()
+        })
+    }
+
+    /// Apply request header rules. Returns true when any header was added, replaced, or removed.
+    pub fn apply_request_headers(&self, headers: &mut Vec<(String, String)>) -> bool {
+        apply_headers(
+            headers,
+            &self.set_request_headers,
+            &self.remove_request_headers,
+        )
+    }
+
+    /// Apply response header rules. Returns true when any header was added, replaced, or removed.
+    pub fn apply_response_headers(&self, headers: &mut Vec<(String, String)>) -> bool {
+        apply_headers(
+            headers,
+            &self.set_response_headers,
+            &self.remove_response_headers,
+        )
+    }
+}
+
+fn apply_headers(
+    headers: &mut Vec<(String, String)>,
+    set: &[(String, String)],
+    remove: &[String],
+) -> bool {
+    let before = headers.len();
+    headers.retain(|(name, _)| {
+        !remove.iter().any(|r| name.eq_ignore_ascii_case(r))
+            && !set.iter().any(|(s, _)| name.eq_ignore_ascii_case(s))
+    });
+    let removed = headers.len() != before;
+    headers.extend(set.iter().cloned());
+    removed || !set.is_empty()
+}
+
+/// Prefixes must start with `/`; a trailing slash is dropped so `/api/` behaves like `/api`.
+fn normalize_prefix(prefix: Option<&str>) -> Result<Option<String>> {
+    let Some(prefix) = prefix.map(str::trim).filter(|p| !p.is_empty()) else {
+        return Ok(None);
+    };
+    if !prefix.starts_with('/') {
+        bail!("prefix must start with '/': {prefix}");
+    }
+    let trimmed = prefix.trim_end_matches('/');
+    Ok((!trimmed.is_empty()).then(|| trimmed.to_string()))
+}
+
+fn validate_set(
+    headers: &std::collections::BTreeMap<String, String>,
+) -> Result<Vec<(String, String)>> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            HeaderName::from_bytes(name.as_bytes())
+                .with_context(|| format!("header name {name:?}"))?;
+            HeaderValue::from_str(value).with_context(|| format!("value for header {name}"))?;
+            Ok((name.clone(), value.clone()))
+        })
+        .collect()
+}
+
+fn validate_names(names: &[String]) -> Result<Vec<String>> {
+    names
+        .iter()
+        .map(|name| {
+            HeaderName::from_bytes(name.as_bytes())
+                .with_context(|| format!("header name {name:?}"))?;
+            Ok(name.to_ascii_lowercase())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(strip: Option<&str>, add: Option<&str>) -> RewriteRules {
+        RewriteRules {
+            strip_prefix: normalize_prefix(strip).unwrap(),
+            add_prefix: normalize_prefix(add).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn strip_prefix_respects_segment_boundaries() {
+        let rules = rules(Some("/api/"), None);
+        assert_eq!(rules.rewrite_path("/api/users"), Some("/users".to_string()));
+        assert_eq!(rules.rewrite_path("/api"), Some("/".to_string()));
+        assert_eq!(rules.rewrite_path("/apiary"), None);
+        assert_eq!(rules.rewrite_path("/other"), None);
+    }
+
+    #[test]
+    fn add_prefix_applies_after_strip() {
+        let rules = rules(Some("/api"), Some("/v2"));
+        assert_eq!(
+            rules.rewrite_path("/api/users"),
+            Some("/v2/users".to_string())
+        );
+        assert_eq!(
+            rules.rewrite_path("/health"),
+            Some("/v2/health".to_string())
+        );
+    }
+
+    #[test]
+    fn header_rules_replace_and_remove() {
+        let rules = RewriteRules {
+            set_request_headers: vec![("X-Api-Key".to_string(), "dev".to_string())],
+            remove_request_headers: vec!["cookie".to_string()],
+            ..Default::default()
+        };
+        let mut headers = vec![
+            ("cookie".to_string(), "a=1".to_string()),
+            ("x-api-key".to_string(), "old".to_string()),
+            ("accept".to_string(), "*/*".to_string()),
+        ];
+        assert!(rules.apply_request_headers(&mut headers));
+        assert_eq!(
+            headers,
+            vec![
+                ("accept".to_string(), "*/*".to_string()),
+                ("X-Api-Key".to_string(), "dev".to_string()),
+            ]
+        );
+
+        let mut untouched = vec![("accept".to_string(), "*/*".to_string())];
+        assert!(!rules.apply_response_headers(&mut untouched));
+    }
+
+    #[test]
+    fn rejects_relative_prefix() {
+        assert!(normalize_prefix(Some("api")).is_err());
+        assert_eq!(normalize_prefix(Some("/")).unwrap(), None);
+    }
+}
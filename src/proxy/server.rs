@@ -5,19 +5,32 @@
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use axum::Router;
 use axum::body::Body;
-use axum::extract::State;
-use axum::http::{Request, Response, StatusCode};
+use axum::extract::{ConnectInfo, State};
+use axum::http::{HeaderMap, Request, Response, StatusCode};
 use axum::routing::any;
 use tokio::sync::RwLock;
 
+use super::ProxyConfig;
+use super::auth::InjectedAuth;
+use super::circuit::{self, Admission, CircuitBreaker, CircuitSettings};
+use super::cors::{self, CorsPolicy};
+use super::mirror::{Mirror, MirrorRequest, PrimaryOutcome};
+use super::pool::{self, PoolSettings, UpstreamOutcome, UpstreamPool};
+use super::ratelimit::{Decision, RateLimitSpec, RateLimiter};
+use super::rewrite::RewriteRules;
 use super::summary::SummaryState;
-use super::trace::{TraceBuffer, TraceRecord, hash_path, now_ns};
+use super::trace::{
+    FLAG_AUTH_INJECTED, FLAG_CIRCUIT_OPEN, FLAG_CORS_HANDLED, FLAG_PATH_REWRITTEN,
+    FLAG_RATE_LIMITED, FLAG_REQUEST_HEADERS_REWRITTEN, FLAG_RESPONSE_HEADERS_REWRITTEN,
+    FLAG_VARIANT, LEGACY_TRACE_HEADER, TRACE_HEADER, TraceBuffer, TraceRecord, format_trace_id,
+    hash_path, new_trace_id, now_ns, parse_trace_id,
+};
+use super::variant::{VARIANT_HEADER, VariantRoutes};
 
 /// A backend target
 #[derive(Debug, Clone)]
@@ -25,6 +38,14 @@ pub struct Backend {
     pub name: String,
     pub addr: SocketAddr,
     pub index: u8,
+    pub rewrite: Arc<RewriteRules>,
+    pub cors: Option<Arc<CorsPolicy>>,
+    pub auth: Option<Arc<InjectedAuth>>,
+    pub mirror: Option<Arc<Mirror>>,
+    pub rate_limit: Option<Arc<RateLimiter>>,
+    pub pool: Arc<UpstreamPool>,
+    pub circuit: Option<Arc<CircuitBreaker>>,
+    pub variants: Option<Arc<VariantRoutes>>,
 }
 
 /// Routing configuration
@@ -37,6 +58,12 @@ pub struct ProxyRouter {
     pub default: Option<usize>,
     /// All backends
     pub backends: Vec<Backend>,
+    /// `[proxy] rate_limit`, applied to backends registered at run time
+    pub default_rate_limit: Option<RateLimitSpec>,
+    /// `[proxy]` pool and timeout settings, applied to backends registered at run time
+    pub default_pool: PoolSettings,
+    /// `[proxy]` circuit breaker settings, applied to backends registered at run time
+    pub default_circuit: Option<CircuitSettings>,
 }
 
 impl ProxyRouter {
@@ -46,6 +73,9 @@ impl ProxyRouter {
             path_routes: Vec::new(),
             default: if backends.is_empty() { None } else { Some(0) },
             backends,
+            default_rate_limit: None,
+            default_pool: PoolSettings::from_proxy(&ProxyConfig::default()),
+            default_circuit: CircuitSettings::from_proxy(&ProxyConfig::default()),
         }
     }
 
@@ -78,9 +108,100 @@ impl ProxyRouter {
         self.default.and_then(|idx| self.backends.get(idx))
     }
 
+    /// Route `host` to `addr` under the backend `name`, reusing the slot of an earlier
+    /// registration with the same name so its trace index stays stable.
+    pub fn register_host_backend(
+        &mut self,
+        name: &str,
+        host: String,
+        addr: SocketAddr,
+    ) -> Result<usize> {
+        let idx = match self.backends.iter().position(|b| b.name == name) {
+            Some(idx) => {
+                if self.backends[idx].addr != addr {
+                    self.backends[idx].addr = addr;
+                    self.backends[idx].pool.reset_idle();
+                    if let Some(circuit) = &self.backends[idx].circuit {
+                        circuit.reset();
+                    }
+                }
+                idx
+            }
+            None => {
+                let idx = self.backends.len();
+                self.backends.push(Backend {
+                    name: name.to_string(),
+                    addr,
+                    index: idx.min(u8::MAX as usize) as u8,
+                    rewrite: Arc::new(RewriteRules::default()),
+                    cors: None,
+                    auth: None,
+                    mirror: None,
+                    rate_limit: self
+                        .default_rate_limit
+                        .clone()
+                        .map(|spec| Arc::new(RateLimiter::new(spec))),
+                    pool: Arc::new(UpstreamPool::new(self.default_pool.clone())?),
+                    circuit: self
+                        .default_circuit
+                        .clone()
+                        .map(|settings| Arc::new(CircuitBreaker::new(settings))),
+                    variants: None,
+                });
+                idx
+            }
+        };
+        self.host_routes.retain(|_, routed| *routed != idx);
+        self.host_routes.insert(host, idx);
+        Ok(idx)
+    }
+
+    /// Drop the host routes of the backend `name`. The backend slot is kept so
+    /// indices recorded in earlier traces still resolve to its name.
+    pub fn unregister_host_backend(&mut self, name: &str) -> bool {
+        let Some(idx) = self.backends.iter().position(|b| b.name == name) else {
+            return false;
+        };
+        let before = self.host_routes.len();
+        self.host_routes.retain(|_, routed| *routed != idx);
+        self.host_routes.len() != before
+    }
+
     pub fn backend_names(&self) -> Vec<String> {
         self.backends.iter().map(|b| b.name.clone()).collect()
     }
+
+    /// Backends with `mirror_to` set, by name.
+    pub fn backend_mirrors(&self) -> Vec<(String, Arc<Mirror>)> {
+        self.backends
+            .iter()
+            .filter_map(|b| Some((b.name.clone(), b.mirror.clone()?)))
+            .collect()
+    }
+
+    /// Backends with a rate limit, by name.
+    pub fn backend_rate_limits(&self) -> Vec<(String, Arc<RateLimiter>)> {
+        self.backends
+            .iter()
+            .filter_map(|b| Some((b.name.clone(), b.rate_limit.clone()?)))
+            .collect()
+    }
+
+    /// Every backend's upstream pool, by name.
+    pub fn backend_pools(&self) -> Vec<(String, Arc<UpstreamPool>)> {
+        self.backends
+            .iter()
+            .map(|b| (b.name.clone(), b.pool.clone()))
+            .collect()
+    }
+
+    /// Backends with a circuit breaker, by name.
+    pub fn backend_circuits(&self) -> Vec<(String, Arc<CircuitBreaker>)> {
+        self.backends
+            .iter()
+            .filter_map(|b| Some((b.name.clone(), b.circuit.clone()?)))
+            .collect()
+    }
 }
 
 /// Proxy server state
@@ -89,7 +210,6 @@ pub struct ProxyServer {
     pub trace_buffer: Arc<TraceBuffer>,
     pub summary_state: Arc<SummaryState>,
     pub client: reqwest::Client,
-    pub trace_id_counter: AtomicU64,
 }
 
 impl ProxyServer {
@@ -108,32 +228,39 @@ impl ProxyServer {
             trace_buffer,
             summary_state,
             client,
-            trace_id_counter: AtomicU64::new(1),
         }
     }
+}
 
-    /// Generate a new trace ID
-    pub fn next_trace_id(&self) -> u128 {
-        self.trace_id_counter.fetch_add(1, Ordering::Relaxed) as u128
-    }
+/// Trace ID sent by the client: `x-flow-trace-id` (hex) or the legacy decimal `x-trace-id`.
+fn incoming_trace_id(headers: &HeaderMap) -> Option<u128> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    header(TRACE_HEADER)
+        .and_then(parse_trace_id)
+        .or_else(|| header(LEGACY_TRACE_HEADER).and_then(|v| v.trim().parse::<u128>().ok()))
+}
+
+/// Value of header `name` as an owned string, if present and valid UTF-8.
+fn header_str(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
 }
 
 /// Handle proxied requests
 async fn proxy_handler(
     State(server): State<Arc<ProxyServer>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
     req: Request<Body>,
 ) -> Response<Body> {
     let start = Instant::now();
     let start_ns = now_ns();
     let req_id = server.trace_buffer.next_req_id();
 
-    // Get or generate trace ID
-    let trace_id = req
-        .headers()
-        .get("x-trace-id")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|s| s.parse::<u128>().ok())
-        .unwrap_or_else(|| server.next_trace_id());
+    // Keep the client's trace ID or start a new one; backends see it in `x-flow-trace-id`.
+    let trace_id = incoming_trace_id(req.headers()).unwrap_or_else(new_trace_id);
+    let trace_header = format_trace_id(trace_id);
 
     let method = req.method().clone();
     let uri = req.uri().clone();
@@ -147,8 +274,16 @@ async fn proxy_handler(
 
     // Route to backend
     let router = server.router.read().await;
+    let mut variant = None;
     let backend = match router.route(host.as_deref(), &path) {
-        Some(b) => b.clone(),
+        // Requests asking for a variant go to its backend instead of the stable one
+        Some(b) => match b.variants.as_deref().and_then(|v| v.select(req.headers())) {
+            Some((name, idx)) if idx < router.backends.len() => {
+                variant = Some(name.to_string());
+                router.backends[idx].clone()
+            }
+            _ => b.clone(),
+        },
         None => {
             drop(router);
             // No route found
@@ -173,29 +308,140 @@ async fn proxy_handler(
     };
     drop(router);
 
+    let origin = header_str(req.headers(), "origin");
+
+    // Answer CORS preflights without hitting the backend
+    if let Some(policy) = backend.cors.as_deref() {
+        let request_method = header_str(req.headers(), "access-control-request-method");
+        if CorsPolicy::is_preflight(method_str, origin.as_deref(), request_method.as_deref()) {
+            let request_headers = header_str(req.headers(), "access-control-request-headers");
+            let (status, headers) = policy.preflight(origin.as_deref(), request_headers.as_deref());
+
+            let mut record = TraceRecord::new();
+            record.set_timestamp(start_ns);
+            record.set_req_id(req_id);
+            record.set_latency_status(
+                start.elapsed().as_micros() as u32,
+                status,
+                method_str.into(),
+                FLAG_CORS_HANDLED,
+            );
+            record.set_target_and_trace_id(backend.index, path.len().min(255) as u8, trace_id);
+            record.set_path(&path);
+            record.set_path_hash(hash_path(&path));
+            server.trace_buffer.record(&record);
+
+            let mut response = Response::builder()
+                .status(StatusCode::from_u16(status).unwrap_or(StatusCode::NO_CONTENT))
+                .header(TRACE_HEADER, trace_header.as_str())
+                .header(LEGACY_TRACE_HEADER, trace_id.to_string());
+            for (name, value) in &headers {
+                response = response.header(name.as_str(), value.as_str());
+            }
+            return response.body(Body::empty()).unwrap();
+        }
+    }
+
+    // Turn away clients over their rate limit before touching the backend
+    let mut rate_limit_remaining = None;
+    if let Some(limiter) = backend.rate_limit.as_deref() {
+        let client = limiter.client_key(peer, req.headers());
+        match limiter.check(&client, Instant::now()) {
+            Decision::Allowed { remaining } => rate_limit_remaining = Some(remaining),
+            Decision::Limited { retry_after } => {
+                let mut record = TraceRecord::new();
+                record.set_timestamp(start_ns);
+                record.set_req_id(req_id);
+                record.set_latency_status(
+                    start.elapsed().as_micros() as u32,
+                    429,
+                    method_str.into(),
+                    FLAG_RATE_LIMITED,
+                );
+                record.set_target_and_trace_id(backend.index, path.len().min(255) as u8, trace_id);
+                record.set_path(&path);
+                record.set_path_hash(hash_path(&path));
+                server.trace_buffer.record(&record);
+
+                return rate_limited_response(limiter, retry_after, &trace_header, trace_id);
+            }
+        }
+    }
+
+    // Fail fast while the backend is known to be down, instead of waiting out a timeout
+    if let Some(breaker) = backend.circuit.as_deref()
+        && let Admission::Open {
+            retry_after,
+            down_since,
+        } = breaker.check(Instant::now())
+    {
+        let mut record = TraceRecord::new();
+        record.set_timestamp(start_ns);
+        record.set_req_id(req_id);
+        record.set_latency_status(
+            start.elapsed().as_micros() as u32,
+            503,
+            method_str.into(),
+            FLAG_CIRCUIT_OPEN,
+        );
+        record.set_target_and_trace_id(backend.index, path.len().min(255) as u8, trace_id);
+        record.set_path(&path);
+        record.set_path_hash(hash_path(&path));
+        server.trace_buffer.record(&record);
+
+        let down_since = circuit::format_time(down_since);
+        return circuit_open_response(
+            &backend.name,
+            &down_since,
+            retry_after,
+            &trace_header,
+            trace_id,
+        );
+    }
+
+    // Apply path rewrite rules
+    let mut flags = if variant.is_some() { FLAG_VARIANT } else { 0 };
+    let upstream_path = match backend.rewrite.rewrite_path(&path) {
+        Some(rewritten) => {
+            flags |= FLAG_PATH_REWRITTEN;
+            rewritten
+        }
+        None => path.clone(),
+    };
+
     // Build upstream URL
     let upstream_url = format!(
         "http://{}{}{}",
         backend.addr,
-        path,
+        upstream_path,
         uri.query().map(|q| format!("?{}", q)).unwrap_or_default()
     );
 
     // Forward request headers
     let upstream_start = Instant::now();
-    let mut upstream_req = server.client.request(method.clone(), &upstream_url);
+    let mut upstream_req = backend.pool.client.request(method.clone(), &upstream_url);
 
-    // Copy headers (except host)
-    for (name, value) in req.headers() {
-        if name != "host" {
-            if let Ok(v) = value.to_str() {
-                upstream_req = upstream_req.header(name.as_str(), v);
-            }
-        }
+    // Copy headers (except host and the client's connection handling, so a client sending
+    // `Connection: close` doesn't cost a new upstream connection per request), then apply
+    // header rewrite rules
+    let mut request_headers = end_to_end_headers(req.headers(), |name| {
+        name != "host" && name != TRACE_HEADER && name != LEGACY_TRACE_HEADER
+    });
+    if backend.rewrite.apply_request_headers(&mut request_headers) {
+        flags |= FLAG_REQUEST_HEADERS_REWRITTEN;
+    }
+    if let Some(auth) = backend.auth.as_deref() {
+        auth.apply(&mut request_headers);
+        flags |= FLAG_AUTH_INJECTED;
+    }
+    for (name, value) in &request_headers {
+        upstream_req = upstream_req.header(name.as_str(), value.as_str());
     }
 
-    // Add trace ID header
-    upstream_req = upstream_req.header("x-trace-id", trace_id.to_string());
+    // Add trace ID headers
+    upstream_req = upstream_req
+        .header(TRACE_HEADER, trace_header.as_str())
+        .header(LEGACY_TRACE_HEADER, trace_id.to_string());
 
     // Get request body
     let body_bytes = axum::body::to_bytes(req.into_body(), 10 * 1024 * 1024)
@@ -203,21 +449,63 @@ async fn proxy_handler(
         .ok();
     let bytes_in = body_bytes.as_ref().map(|b| b.len()).unwrap_or(0) as u32;
 
+    let body = body_bytes
+        .filter(|body| !body.is_empty())
+        .map(|body| body.to_vec());
+
+    // Copy sampled requests to the shadow backend; the client only sees the primary response
+    let mirror_outcome = backend
+        .mirror
+        .as_ref()
+        .filter(|mirror| mirror.sampled(req_id))
+        .map(|mirror| {
+            let mut headers = request_headers.clone();
+            headers.push((TRACE_HEADER.to_string(), trace_header.clone()));
+            mirror.spawn(
+                &server.client,
+                MirrorRequest {
+                    method: method.clone(),
+                    path_and_query: format!(
+                        "{}{}",
+                        upstream_path,
+                        uri.query().map(|q| format!("?{}", q)).unwrap_or_default()
+                    ),
+                    headers,
+                    body: body.clone(),
+                },
+            )
+        });
+
     // Send body if present
-    if let Some(body) = body_bytes {
-        if !body.is_empty() {
-            upstream_req = upstream_req.body(body.to_vec());
-        }
+    if let Some(body) = body {
+        upstream_req = upstream_req.body(body);
     }
 
     // Execute request
+    backend.pool.begin(upstream_start);
     let result = upstream_req.send().await;
     let upstream_latency_us = upstream_start.elapsed().as_micros() as u32;
 
+    let mut response_headers: Vec<(String, String)> = Vec::new();
     let (status, body, bytes_out) = match result {
         Ok(resp) => {
             let status = resp.status().as_u16();
-            let body = resp.text().await.unwrap_or_default();
+            let keep_alive = !resp
+                .headers()
+                .get("connection")
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|v| v.eq_ignore_ascii_case("close"));
+            response_headers = end_to_end_headers(resp.headers(), |name| {
+                name != TRACE_HEADER && name != LEGACY_TRACE_HEADER && name != "x-proxy-latency-ms"
+            });
+            let (body, outcome) = match resp.text().await {
+                Ok(body) => (body, UpstreamOutcome::Completed { keep_alive }),
+                Err(err) => (String::new(), pool::error_outcome(&err)),
+            };
+            backend.pool.finish(Instant::now(), outcome);
+            if let Some(circuit) = backend.circuit.as_deref() {
+                circuit.record(outcome, Instant::now());
+            }
             let bytes_out = body.len() as u32;
 
             // Store error body for AI analysis
@@ -228,21 +516,60 @@ async fn proxy_handler(
             (status, body, bytes_out)
         }
         Err(e) => {
+            let outcome = pool::error_outcome(&e);
+            backend.pool.finish(Instant::now(), outcome);
+            if let Some(circuit) = backend.circuit.as_deref() {
+                circuit.record(outcome, Instant::now());
+            }
             let error_body = format!("{{\"error\": \"{}\"}}", e);
             server
                 .summary_state
                 .store_error_body(req_id, error_body.clone());
-            (502, error_body, 0)
+            let status = if outcome == UpstreamOutcome::Timeout {
+                504
+            } else {
+                502
+            };
+            (status, error_body, 0)
         }
     };
 
+    if let Some(outcome) = mirror_outcome {
+        let _ = outcome.send(PrimaryOutcome {
+            status,
+            latency_us: upstream_latency_us,
+        });
+    }
+
+    if !response_headers
+        .iter()
+        .any(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+    {
+        response_headers.push(("content-type".to_string(), "application/json".to_string()));
+    }
+    if backend
+        .rewrite
+        .apply_response_headers(&mut response_headers)
+    {
+        flags |= FLAG_RESPONSE_HEADERS_REWRITTEN;
+    }
+    // Rewrite rules can't reintroduce connection-level headers.
+    response_headers.retain(|(name, _)| !is_hop_by_hop(&name.to_ascii_lowercase()));
+    if let Some(policy) = backend.cors.as_deref() {
+        cors::apply(
+            &mut response_headers,
+            policy.response_headers(origin.as_deref()),
+        );
+        flags |= FLAG_CORS_HANDLED;
+    }
+
     let total_latency_us = start.elapsed().as_micros() as u32;
 
     // Record trace
     let mut record = TraceRecord::new();
     record.set_timestamp(start_ns);
     record.set_req_id(req_id);
-    record.set_latency_status(total_latency_us, status, method_str.into(), 0);
+    record.set_latency_status(total_latency_us, status, method_str.into(), flags);
     record.set_bytes(bytes_in, bytes_out);
     record.set_target_and_trace_id(backend.index, path.len().min(255) as u8, trace_id);
     record.set_path_hash(hash_path(&path));
@@ -251,15 +578,119 @@ async fn proxy_handler(
     server.trace_buffer.record(&record);
 
     // Build response
-    Response::builder()
+    let mut response = Response::builder()
         .status(StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))
-        .header("x-trace-id", trace_id.to_string())
-        .header("x-proxy-latency-ms", (total_latency_us / 1000).to_string())
+        .header(TRACE_HEADER, trace_header.as_str())
+        .header(LEGACY_TRACE_HEADER, trace_id.to_string())
+        .header("x-proxy-latency-ms", (total_latency_us / 1000).to_string());
+    if let Some(variant) = &variant {
+        response = response.header(VARIANT_HEADER, variant.as_str());
+    }
+    if let (Some(limiter), Some(remaining)) = (backend.rate_limit.as_deref(), rate_limit_remaining)
+    {
+        response = response
+            .header("x-ratelimit-limit", limiter.spec.limit.to_string())
+            .header("x-ratelimit-remaining", remaining.to_string());
+    }
+    for (name, value) in &response_headers {
+        response = response.header(name.as_str(), value.as_str());
+    }
+    response.body(Body::from(body)).unwrap()
+}
+
+/// 429 for a client over its limit, with `Retry-After` rounded up to whole seconds.
+fn rate_limited_response(
+    limiter: &RateLimiter,
+    retry_after: Duration,
+    trace_header: &str,
+    trace_id: u128,
+) -> Response<Body> {
+    let retry_secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+    let body = serde_json::json!({
+        "error": "rate limit exceeded",
+        "limit": limiter.spec.label,
+        "retry_after_secs": retry_secs,
+    });
+    Response::builder()
+        .status(StatusCode::TOO_MANY_REQUESTS)
         .header("content-type", "application/json")
-        .body(Body::from(body))
+        .header("retry-after", retry_secs.to_string())
+        .header("x-ratelimit-limit", limiter.spec.limit.to_string())
+        .header("x-ratelimit-remaining", "0")
+        .header(TRACE_HEADER, trace_header)
+        .header(LEGACY_TRACE_HEADER, trace_id.to_string())
+        .body(Body::from(body.to_string()))
         .unwrap()
 }
 
+/// 503 for a backend whose circuit is open, with `Retry-After` rounded up to whole seconds.
+fn circuit_open_response(
+    backend: &str,
+    down_since: &str,
+    retry_after: Duration,
+    trace_header: &str,
+    trace_id: u128,
+) -> Response<Body> {
+    let retry_secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+    let body = serde_json::json!({
+        "error": format!("backend {backend} down since {down_since}"),
+        "backend": backend,
+        "down_since": down_since,
+        "retry_after_secs": retry_secs,
+    });
+    Response::builder()
+        .status(StatusCode::SERVICE_UNAVAILABLE)
+        .header("content-type", "application/json")
+        .header("retry-after", retry_secs.to_string())
+        .header(TRACE_HEADER, trace_header)
+        .header(LEGACY_TRACE_HEADER, trace_id.to_string())
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+/// Headers that describe the upstream connection rather than the payload.
+fn is_hop_by_hop(name: &str) -> bool {
+    matches!(
+        name,
+        "connection"
+            | "keep-alive"
+            | "transfer-encoding"
+            | "content-length"
+            | "upgrade"
+            | "proxy-connection"
+            | "proxy-authenticate"
+            | "proxy-authorization"
+            | "te"
+            | "trailer"
+    )
+}
+
+/// Headers to forward to the other side: those `keep` accepts, minus hop-by-hop headers and
+/// any header the sender lists in `Connection`. Values that aren't visible ASCII are dropped.
+fn end_to_end_headers(headers: &HeaderMap, keep: impl Fn(&str) -> bool) -> Vec<(String, String)> {
+    let listed: Vec<String> = headers
+        .get_all("connection")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|token| token.trim().to_ascii_lowercase())
+        .filter(|token| !token.is_empty())
+        .collect();
+    headers
+        .iter()
+        .filter(|(name, _)| {
+            let name = name.as_str();
+            keep(name) && !is_hop_by_hop(name) && !listed.iter().any(|token| token == name)
+        })
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|v| (name.as_str().to_string(), v.to_string()))
+        })
+        .collect()
+}
+
 /// Health check endpoint
 async fn health_handler(State(server): State<Arc<ProxyServer>>) -> Response<Body> {
     let router = server.router.read().await;
@@ -270,6 +701,7 @@ async fn health_handler(State(server): State<Arc<ProxyServer>>) -> Response<Body
             serde_json::json!({
                 "name": b.name,
                 "addr": b.addr.to_string(),
+                "circuit": b.circuit.as_ref().map(|circuit| circuit.summary().state),
             })
         })
         .collect();
@@ -305,9 +737,77 @@ pub async fn run_server(addr: SocketAddr, server: Arc<ProxyServer>) -> Result<()
 
     tracing::info!("Proxy server listening on {}", addr);
 
-    axum::serve(listener, app)
-        .await
-        .context("Proxy server error")?;
+    // Peer addresses key the per-client rate limits
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .context("Proxy server error")?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registered_host_backends_route_and_unregister() {
+        let mut router = ProxyRouter::new(Vec::new());
+        let addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+        let idx = router
+            .register_host_backend("dev.web", "dev.web.localhost".into(), addr)
+            .unwrap();
+        assert_eq!(idx, 0);
+        let backend = router.route(Some("dev.web.localhost:8080"), "/").unwrap();
+        assert_eq!(backend.addr, addr);
+
+        let moved: SocketAddr = "127.0.0.1:3001".parse().unwrap();
+        assert_eq!(
+            router
+                .register_host_backend("dev.web", "dev.web.localhost".into(), moved)
+                .unwrap(),
+            0
+        );
+        assert_eq!(router.backends.len(), 1);
+        assert_eq!(
+            router.route(Some("dev.web.localhost"), "/").unwrap().addr,
+            moved
+        );
+
+        assert!(router.unregister_host_backend("dev.web"));
+        assert!(router.route(Some("dev.web.localhost"), "/").is_none());
+        assert!(!router.unregister_host_backend("dev.web"));
+    }
+
+    #[test]
+    fn incoming_trace_id_prefers_the_flow_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(incoming_trace_id(&headers), None);
+        headers.insert(LEGACY_TRACE_HEADER, "42".parse().unwrap());
+        assert_eq!(incoming_trace_id(&headers), Some(42));
+        headers.insert(
+            TRACE_HEADER,
+            "00000000000000000000000000000abc".parse().unwrap(),
+        );
+        assert_eq!(incoming_trace_id(&headers), Some(0xabc));
+    }
+
+    #[test]
+    fn hop_by_hop_and_connection_listed_headers_are_not_forwarded() {
+        let mut headers = HeaderMap::new();
+        headers.insert("connection", "keep-alive, X-Session-Hint".parse().unwrap());
+        headers.insert("keep-alive", "timeout=5".parse().unwrap());
+        headers.insert("transfer-encoding", "chunked".parse().unwrap());
+        headers.insert("x-session-hint", "abc".parse().unwrap());
+        headers.insert("content-type", "text/plain".parse().unwrap());
+        headers.insert("server", "upstream".parse().unwrap());
+
+        let forwarded = end_to_end_headers(&headers, |name| name != "server");
+        assert_eq!(
+            forwarded,
+            vec![("content-type".to_string(), "text/plain".to_string())]
+        );
+    }
+}
@@ -11,6 +11,10 @@ use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use serde::Serialize;
 
+use super::circuit::{CircuitBreaker, CircuitSummary};
+use super::mirror::{Mirror, MirrorSummary};
+use super::pool::{PoolSummary, UpstreamPool};
+use super::ratelimit::{RateLimitSummary, RateLimiter};
 use super::trace::{TraceBuffer, TraceRecord};
 
 /// Summary of a single error for AI consumption
@@ -83,6 +87,18 @@ pub struct TraceSummary {
     pub slow_requests: Vec<SlowRequestSummary>,
     pub target_health: HashMap<String, TargetHealth>,
     pub request_patterns: HashMap<String, u64>,
+    /// Divergence between each mirrored target and its `mirror_to` backend
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub mirrors: HashMap<String, MirrorSummary>,
+    /// Allowed/limited counters for each rate-limited target
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub rate_limits: HashMap<String, RateLimitSummary>,
+    /// Connection reuse, connect errors, and timeouts for each backend that saw traffic
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub upstream_pools: HashMap<String, PoolSummary>,
+    /// Circuit breaker state for each backend that is failing or has been cut off
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub circuits: HashMap<String, CircuitSummary>,
 }
 
 /// State for computing summaries
@@ -92,6 +108,10 @@ pub struct SummaryState {
     pub slow_threshold_ms: u32,
     pub session_start: Instant,
     pub session_start_unix: u64,
+    pub mirrors: Vec<(String, Arc<Mirror>)>,
+    pub rate_limits: RwLock<Vec<(String, Arc<RateLimiter>)>>,
+    pub pools: RwLock<Vec<(String, Arc<UpstreamPool>)>>,
+    pub circuits: RwLock<Vec<(String, Arc<CircuitBreaker>)>>,
 }
 
 impl SummaryState {
@@ -107,6 +127,73 @@ impl SummaryState {
             slow_threshold_ms,
             session_start: Instant::now(),
             session_start_unix: now,
+            mirrors: Vec::new(),
+            rate_limits: RwLock::new(Vec::new()),
+            pools: RwLock::new(Vec::new()),
+            circuits: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Report divergence stats for these mirrored targets
+    pub fn with_mirrors(mut self, mirrors: Vec<(String, Arc<Mirror>)>) -> Self {
+        self.mirrors = mirrors;
+        self
+    }
+
+    /// Report counters for these rate-limited targets
+    pub fn with_rate_limits(self, rate_limits: Vec<(String, Arc<RateLimiter>)>) -> Self {
+        Self {
+            rate_limits: RwLock::new(rate_limits),
+            ..self
+        }
+    }
+
+    /// Report connection stats for these backends
+    pub fn with_pools(self, pools: Vec<(String, Arc<UpstreamPool>)>) -> Self {
+        Self {
+            pools: RwLock::new(pools),
+            ..self
+        }
+    }
+
+    /// Report circuit breaker state for these backends
+    pub fn with_circuits(self, circuits: Vec<(String, Arc<CircuitBreaker>)>) -> Self {
+        Self {
+            circuits: RwLock::new(circuits),
+            ..self
+        }
+    }
+
+    /// Track the pool of a target registered while the proxy runs
+    pub fn add_pool(&self, name: &str, pool: Arc<UpstreamPool>) {
+        if let Ok(mut pools) = self.pools.write() {
+            match pools.iter_mut().find(|(existing, _)| existing == name) {
+                Some(entry) => entry.1 = pool,
+                None => pools.push((name.to_string(), pool)),
+            }
+        }
+    }
+
+    /// Track the circuit breaker of a target registered while the proxy runs
+    pub fn add_circuit(&self, name: &str, circuit: Arc<CircuitBreaker>) {
+        if let Ok(mut circuits) = self.circuits.write() {
+            match circuits.iter_mut().find(|(existing, _)| existing == name) {
+                Some(entry) => entry.1 = circuit,
+                None => circuits.push((name.to_string(), circuit)),
+            }
+        }
+    }
+
+    /// Track the limiter of a target registered while the proxy runs
+    pub fn add_rate_limit(&self, name: &str, limiter: Arc<RateLimiter>) {
+        if let Ok(mut rate_limits) = self.rate_limits.write() {
+            match rate_limits
+                .iter_mut()
+                .find(|(existing, _)| existing == name)
+            {
+                Some(entry) => entry.1 = limiter,
+                None => rate_limits.push((name.to_string(), limiter)),
+            }
         }
     }
 
@@ -314,6 +401,26 @@ pub fn compute_summary(buffer: &TraceBuffer, state: &SummaryState) -> TraceSumma
         }
     }
 
+    // Circuits with a failure streak; an open one marks its target unhealthy
+    let circuits: HashMap<String, CircuitSummary> = state
+        .circuits
+        .read()
+        .map(|circuits| {
+            circuits
+                .iter()
+                .map(|(name, circuit)| (name.clone(), circuit.summary()))
+                .filter(|(_, summary)| summary.consecutive_failures > 0)
+                .collect()
+        })
+        .unwrap_or_default();
+    for (name, circuit) in &circuits {
+        if circuit.state != "closed"
+            && let Some(health) = target_health.get_mut(name)
+        {
+            health.healthy = false;
+        }
+    }
+
     // Request patterns (path -> count)
     let mut request_patterns: HashMap<String, u64> = HashMap::new();
     for r in &records {
@@ -335,6 +442,33 @@ pub fn compute_summary(buffer: &TraceBuffer, state: &SummaryState) -> TraceSumma
         slow_requests,
         target_health,
         request_patterns,
+        mirrors: state
+            .mirrors
+            .iter()
+            .map(|(name, mirror)| (name.clone(), mirror.summary()))
+            .collect(),
+        rate_limits: state
+            .rate_limits
+            .read()
+            .map(|rate_limits| {
+                rate_limits
+                    .iter()
+                    .map(|(name, limiter)| (name.clone(), limiter.summary()))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        upstream_pools: state
+            .pools
+            .read()
+            .map(|pools| {
+                pools
+                    .iter()
+                    .map(|(name, pool)| (name.clone(), pool.summary()))
+                    .filter(|(_, summary)| summary.requests > 0)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        circuits,
     }
 }
 
@@ -410,6 +544,7 @@ fn suggest_fix(record: &TraceRecord, error_body: Option<&str>) -> Option<String>
         401 => Some("Unauthorized - check authentication".to_string()),
         403 => Some("Forbidden - check permissions".to_string()),
         404 => Some(format!("Not found - verify endpoint '{}' exists", path)),
+        429 => Some("Too many requests - back off or raise the rate limit".to_string()),
         500 => Some("Internal server error - check server logs".to_string()),
         502 => Some("Bad gateway - upstream service may be down".to_string()),
         503 => Some("Service unavailable - service may be overloaded".to_string()),
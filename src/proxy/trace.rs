@@ -6,11 +6,11 @@
 use std::fs::OpenOptions;
 use std::os::fd::AsRawFd;
 use std::os::unix::fs::OpenOptionsExt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::ptr::{null_mut, write_unaligned};
 use std::sync::OnceLock;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use libc::{CLOCK_MONOTONIC, MAP_SHARED, PROT_READ, PROT_WRITE};
 
@@ -19,7 +19,7 @@ const TRACE_MAGIC: &[u8; 8] = b"PROXYTRC";
 const TRACE_VERSION: u32 = 1;
 
 // Record layout - 128 bytes per request
-const TRACE_PATH_BYTES: usize = 64;
+pub(crate) const TRACE_PATH_BYTES: usize = 64;
 const TRACE_RECORD_SIZE: usize = 128;
 const TRACE_HEADER_SIZE: usize = 64;
 const TRACE_DEFAULT_SIZE: usize = 16 * 1024 * 1024; // 16MB default
@@ -35,6 +35,39 @@ const IDX_PATH_HASH: usize = 6;
 const IDX_UPSTREAM_LATENCY: usize = 7; // upstream_latency_us (32) | reserved (32)
 // Remaining 64 bytes = path prefix
 
+// Bits in the flags byte of IDX_LATENCY_STATUS
+pub const FLAG_PATH_REWRITTEN: u8 = 1 << 0;
+pub const FLAG_REQUEST_HEADERS_REWRITTEN: u8 = 1 << 1;
+pub const FLAG_RESPONSE_HEADERS_REWRITTEN: u8 = 1 << 2;
+pub const FLAG_CORS_HANDLED: u8 = 1 << 3;
+pub const FLAG_AUTH_INJECTED: u8 = 1 << 4;
+pub const FLAG_RATE_LIMITED: u8 = 1 << 5;
+pub const FLAG_VARIANT: u8 = 1 << 6;
+pub const FLAG_CIRCUIT_OPEN: u8 = 1 << 7;
+
+/// Short label for the rewrite/CORS flags of a record (e.g. "path,req").
+pub fn describe_flags(flags: u8) -> String {
+    let labels: Vec<&str> = [
+        (FLAG_PATH_REWRITTEN, "path"),
+        (FLAG_REQUEST_HEADERS_REWRITTEN, "req"),
+        (FLAG_RESPONSE_HEADERS_REWRITTEN, "resp"),
+        (FLAG_CORS_HANDLED, "cors"),
+        (FLAG_AUTH_INJECTED, "auth"),
+        (FLAG_RATE_LIMITED, "limited"),
+        (FLAG_VARIANT, "variant"),
+        (FLAG_CIRCUIT_OPEN, "circuit"),
+    ]
+    .into_iter()
+    .filter(|(bit, _)| flags & bit != 0)
+    .map(|(_, label)| label)
+    .collect();
+    if labels.is_empty() {
+        "-".to_string()
+    } else {
+        labels.join(",")
+    }
+}
+
 /// HTTP methods encoded as u8
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -355,6 +388,45 @@ impl TraceBuffer {
         })
     }
 
+    /// Map an existing trace file for reading (e.g. one written by a running proxy).
+    pub fn open(path: &Path) -> Option<Self> {
+        let file = OpenOptions::new().read(true).open(path).ok()?;
+        let size = file.metadata().ok()?.len() as usize;
+        if size <= TRACE_HEADER_SIZE {
+            return None;
+        }
+
+        let map =
+            unsafe { libc::mmap(null_mut(), size, PROT_READ, MAP_SHARED, file.as_raw_fd(), 0) };
+        if map == libc::MAP_FAILED {
+            return None;
+        }
+
+        let header = map as *mut TraceHeader;
+        let capacity = ((size - TRACE_HEADER_SIZE) / TRACE_RECORD_SIZE) as u64;
+        let valid = unsafe {
+            (*header).magic == *TRACE_MAGIC
+                && (*header).version == TRACE_VERSION
+                && (*header).record_size == TRACE_RECORD_SIZE as u32
+                && (*header).capacity == capacity
+        };
+        if !valid {
+            unsafe {
+                libc::munmap(map, size);
+            }
+            return None;
+        }
+
+        Some(TraceBuffer {
+            _map: map as *mut u8,
+            _map_len: size,
+            header,
+            records: unsafe { (map as *mut u8).add(TRACE_HEADER_SIZE) },
+            capacity,
+            start_time: Instant::now(),
+        })
+    }
+
     /// Record a completed request (zero allocations)
     #[inline]
     pub fn record(&self, record: &TraceRecord) {
@@ -505,6 +577,51 @@ fn set_file_len(file: &std::fs::File, size: usize) -> std::io::Result<()> {
     }
 }
 
+/// Header that carries the trace ID from clients through the proxy to backends.
+pub const TRACE_HEADER: &str = "x-flow-trace-id";
+/// Decimal trace ID header used by earlier proxy versions; still accepted and sent.
+pub const LEGACY_TRACE_HEADER: &str = "x-trace-id";
+/// Key backends use for the trace ID in log lines (`flow_trace_id=<id>` or a JSON field).
+pub const TRACE_LOG_KEY: &str = "flow_trace_id";
+
+/// A record stores 112 bits of trace ID (48 high + 64 low).
+const TRACE_ID_MASK: u128 = (1 << 112) - 1;
+
+/// Random trace ID, unique across proxy restarts so old task logs never collide.
+pub fn new_trace_id() -> u128 {
+    uuid::Uuid::new_v4().as_u128() & TRACE_ID_MASK
+}
+
+/// Canonical form of a trace ID: 32 lowercase hex digits.
+pub fn format_trace_id(trace_id: u128) -> String {
+    format!("{:032x}", trace_id & TRACE_ID_MASK)
+}
+
+/// Parse a hex trace ID (as sent in `x-flow-trace-id` or passed to `f logs --trace`).
+pub fn parse_trace_id(raw: &str) -> Option<u128> {
+    let raw = raw.trim();
+    if raw.is_empty() || raw.len() > 32 {
+        return None;
+    }
+    u128::from_str_radix(raw, 16)
+        .ok()
+        .map(|trace_id| trace_id & TRACE_ID_MASK)
+}
+
+/// The newest record with `trace_id` in the most recent trace buffer, with its age.
+pub fn find_trace(trace_id: u128) -> Option<(TraceRecord, Duration)> {
+    let buffer =
+        latest_trace_file(&default_trace_dir()).and_then(|path| TraceBuffer::open(&path))?;
+    let record = buffer
+        .filter(1, |record| {
+            record.timestamp() != 0 && record.trace_id() == trace_id & TRACE_ID_MASK
+        })
+        .into_iter()
+        .next()?;
+    let age = Duration::from_nanos(now_ns().saturating_sub(record.timestamp()));
+    Some((record, age))
+}
+
 /// Get the default trace directory
 pub fn default_trace_dir() -> PathBuf {
     dirs::config_dir()
@@ -518,6 +635,25 @@ pub fn default_trace_size() -> usize {
     TRACE_DEFAULT_SIZE
 }
 
+/// Most recently written `trace.<pid>.bin` in `dir`.
+pub fn latest_trace_file(dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.starts_with("trace.") && name.ends_with(".bin"))
+        })
+        .filter_map(|entry| {
+            let modified = entry.metadata().and_then(|meta| meta.modified()).ok()?;
+            Some((modified, entry.path()))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -545,4 +681,19 @@ mod tests {
         assert_eq!(record.upstream_latency_us(), 1200);
         assert_eq!(record.path(), "/api/users");
     }
+
+    #[test]
+    fn test_trace_ids_fit_the_record() {
+        let trace_id = new_trace_id();
+        let mut record = TraceRecord::new();
+        record.set_target_and_trace_id(0, 1, trace_id);
+        assert_eq!(record.trace_id(), trace_id);
+
+        let formatted = format_trace_id(trace_id);
+        assert_eq!(formatted.len(), 32);
+        assert_eq!(parse_trace_id(&formatted), Some(trace_id));
+        assert_eq!(parse_trace_id("ABC"), Some(0xabc));
+        assert_eq!(parse_trace_id("not-hex"), None);
+        assert_eq!(parse_trace_id(&"f".repeat(33)), None);
+    }
 }
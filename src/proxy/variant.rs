@@ -0,0 +1,150 @@
+//! Per-request variant routing for A/B backend switching.
+//!
+//! A target can declare `variants`, alternate backends (e.g. a rewrite in progress) picked per
+//! request by the `x-flow-variant` header or the `flow_variant` cookie. Everything else keeps
+//! hitting the stable target. Each variant is traced as its own backend named
+//! `<target>@<variant>`, so the summary reports it next to the stable one.
+
+use std::net::SocketAddr;
+
+use anyhow::{Context, Result, bail};
+use axum::http::HeaderMap;
+use serde::{Deserialize, Serialize};
+
+use super::ProxyTargetConfig;
+
+/// Request header selecting a variant (e.g. `x-flow-variant: next`); echoed on responses.
+pub const VARIANT_HEADER: &str = "x-flow-variant";
+/// Cookie selecting a variant, for browsers (`flow_variant=next`).
+pub const VARIANT_COOKIE: &str = "flow_variant";
+
+/// One `[[proxies.variants]]` entry.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct VariantConfig {
+    /// Value of the header or cookie that selects this variant
+    pub name: String,
+    /// Alternate backend address (e.g., "localhost:3001")
+    pub target: String,
+}
+
+/// Resolved variants of a target: selector names and the backend index each one routes to.
+#[derive(Debug)]
+pub struct VariantRoutes {
+    pub header: String,
+    pub cookie: String,
+    pub variants: Vec<(String, usize)>,
+}
+
+impl VariantRoutes {
+    /// The backend index of the variant selected by `headers`, if any. The header wins over the
+    /// cookie; names match case-insensitively.
+    pub fn select(&self, headers: &HeaderMap) -> Option<(&str, usize)> {
+        let requested = headers
+            .get(self.header.as_str())
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+            .or_else(|| cookie_value(headers, &self.cookie))?;
+        self.variants
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&requested))
+            .map(|(name, idx)| (name.as_str(), *idx))
+    }
+}
+
+/// Addresses of `target`'s variants, in declaration order.
+pub fn variant_addrs(target: &ProxyTargetConfig) -> Result<Vec<(String, SocketAddr)>> {
+    let mut seen = Vec::new();
+    for variant in &target.variants {
+        let name = variant.name.trim();
+        if name.is_empty() {
+            bail!("proxy '{}': variant name cannot be empty", target.name);
+        }
+        if seen
+            .iter()
+            .any(|(s, _): &(String, _)| s.eq_ignore_ascii_case(name))
+        {
+            bail!("proxy '{}': duplicate variant '{}'", target.name, name);
+        }
+        let raw = variant.target.trim();
+        let addr: SocketAddr = if raw.contains(':') {
+            raw.replacen("localhost", "127.0.0.1", 1).parse()
+        } else {
+            format!("127.0.0.1:{raw}").parse()
+        }
+        .with_context(|| {
+            format!(
+                "proxy '{}': invalid target '{raw}' for variant '{name}'",
+                target.name
+            )
+        })?;
+        seen.push((name.to_string(), addr));
+    }
+    Ok(seen)
+}
+
+/// Backend name a variant is traced under.
+pub fn backend_name(target: &str, variant: &str) -> String {
+    format!("{target}@{variant}")
+}
+
+fn cookie_value(headers: &HeaderMap, cookie: &str) -> Option<String> {
+    headers
+        .get_all("cookie")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == cookie)
+        .map(|(_, value)| value.trim().trim_matches('"').to_string())
+        .filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn routes() -> VariantRoutes {
+        VariantRoutes {
+            header: VARIANT_HEADER.to_string(),
+            cookie: VARIANT_COOKIE.to_string(),
+            variants: vec![("next".to_string(), 2)],
+        }
+    }
+
+    #[test]
+    fn selects_variants_by_header_then_cookie() {
+        let routes = routes();
+        let mut headers = HeaderMap::new();
+        assert_eq!(routes.select(&headers), None);
+
+        headers.insert("cookie", "theme=dark; flow_variant=next".parse().unwrap());
+        assert_eq!(routes.select(&headers), Some(("next", 2)));
+
+        headers.insert(VARIANT_HEADER, "stable".parse().unwrap());
+        assert_eq!(routes.select(&headers), None);
+        headers.insert(VARIANT_HEADER, "NEXT".parse().unwrap());
+        assert_eq!(routes.select(&headers), Some(("next", 2)));
+    }
+
+    #[test]
+    fn rejects_duplicate_and_invalid_variants() {
+        let parse = |variants: &str| -> Result<Vec<(String, SocketAddr)>> {
+            let target: ProxyTargetConfig = toml::from_str(&format!(
+                "name = \"api\"\ntarget = \"localhost:3000\"\nvariants = {variants}"
+            ))
+            .unwrap();
+            variant_addrs(&target)
+        };
+        let addrs = parse("[{ name = \"next\", target = \"localhost:3001\" }]").unwrap();
+        assert_eq!(addrs[0].1, "127.0.0.1:3001".parse::<SocketAddr>().unwrap());
+        assert!(
+            parse(
+                "[{ name = \"next\", target = \"3001\" }, { name = \"Next\", target = \"3002\" }]"
+            )
+            .is_err()
+        );
+        assert!(parse("[{ name = \"next\", target = \"nowhere:x\" }]").is_err());
+    }
+}
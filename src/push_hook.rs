@@ -11,6 +11,50 @@ use crate::config;
 use crate::push_policy::{self, PushContext};
 
 const FLOW_PRE_PUSH_HOOK_MARKER: &str = "flow-global-pre-push-hook-v1";
+const FLOW_COMMIT_MSG_HOOK_MARKER: &str = "flow-global-commit-msg-hook-v1";
+
+/// Shell helpers shared by the Flow-managed hook scripts.
+pub(crate) const FLOW_HOOK_HELPERS: &str = r#"resolve_flow_bin() {
+  if [[ -n "${FLOW_PUSH_FLOW_BIN:-}" && -x "${FLOW_PUSH_FLOW_BIN}" ]]; then
+    printf '%s\n' "${FLOW_PUSH_FLOW_BIN}"
+    return 0
+  fi
+
+  local candidate=""
+  candidate="$(command -v f 2>/dev/null || true)"
+  if [[ -n "$candidate" && -x "$candidate" ]]; then
+    printf '%s\n' "$candidate"
+    return 0
+  fi
+
+  for candidate in "$HOME/.flow/bin/f" "$HOME/bin/f" "$HOME/bin/f-bin"; do
+    if [[ -x "$candidate" ]]; then
+      printf '%s\n' "$candidate"
+      return 0
+    fi
+  done
+
+  candidate="$(command -v flow 2>/dev/null || true)"
+  if [[ -n "$candidate" && -x "$candidate" ]]; then
+    printf '%s\n' "$candidate"
+    return 0
+  fi
+
+  echo "Flow git hook could not find the flow binary. Set FLOW_PUSH_FLOW_BIN or install f on PATH." >&2
+  return 1
+}
+
+same_path() {
+  if [[ -z "${1:-}" || -z "${2:-}" ]]; then
+    return 1
+  fi
+
+  local left_dir=""
+  local right_dir=""
+  left_dir="$(cd "$(dirname "$1")" 2>/dev/null && pwd -P)" || return 1
+  right_dir="$(cd "$(dirname "$2")" 2>/dev/null && pwd -P)" || return 1
+  [[ "$left_dir/$(basename "$1")" == "$right_dir/$(basename "$2")" ]]
+}"#;
 
 pub fn run_hooks_command(cmd: PushHooksCommand) -> Result<()> {
     match cmd.action.unwrap_or(PushHooksAction::Status) {
@@ -83,46 +127,72 @@ pub fn install_hooks(force: bool) -> Result<()> {
 
     fs::create_dir_all(&hooks_path)
         .with_context(|| format!("failed to create {}", hooks_path.display()))?;
-    let hook_path = hooks_path.join("pre-push");
-    if hook_path.exists() && !is_flow_managed_hook(&hook_path)? && !force {
-        bail!(
-            "Refusing to overwrite non-Flow hook at {}.\nRe-run with `f push hooks install --force` to replace it.",
-            hook_path.display()
-        );
+    let hooks = flow_hooks();
+    for (name, marker, _) in &hooks {
+        let hook_path = hooks_path.join(name);
+        if hook_path.exists() && !is_flow_managed_hook(&hook_path, marker)? && !force {
+            bail!(
+                "Refusing to overwrite non-Flow hook at {}.\nRe-run with `f push hooks install --force` to replace it.",
+                hook_path.display()
+            );
+        }
     }
 
-    fs::write(&hook_path, render_pre_push_hook_script())
-        .with_context(|| format!("failed to write {}", hook_path.display()))?;
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = fs::metadata(&hook_path)?.permissions();
-        perms.set_mode(0o755);
-        fs::set_permissions(&hook_path, perms)?;
+    for (name, _, render) in &hooks {
+        let hook_path = hooks_path.join(name);
+        fs::write(&hook_path, render())
+            .with_context(|| format!("failed to write {}", hook_path.display()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = fs::metadata(&hook_path)?.permissions();
+            perms.set_mode(0o755);
+            fs::set_permissions(&hook_path, perms)?;
+        }
+        println!("Installed Flow {} hook at {}", name, hook_path.display());
     }
 
     git_config_global_set("core.hooksPath", &hooks_path)?;
-    println!("Installed Flow pre-push hook at {}", hook_path.display());
     println!("Global core.hooksPath -> {}", hooks_path.display());
     Ok(())
 }
 
+/// A Flow-managed hook: file name, ownership marker, and script renderer.
+type FlowHook = (&'static str, &'static str, fn() -> String);
+
+fn flow_hooks() -> [FlowHook; 2] {
+    [
+        (
+            "pre-push",
+            FLOW_PRE_PUSH_HOOK_MARKER,
+            render_pre_push_hook_script,
+        ),
+        (
+            "commit-msg",
+            FLOW_COMMIT_MSG_HOOK_MARKER,
+            render_commit_msg_hook_script,
+        ),
+    ]
+}
+
 pub fn uninstall_hooks() -> Result<()> {
     let hooks_path = push_policy::effective_global_hooks_path()?;
-    let hook_path = hooks_path.join("pre-push");
     let current_hooks_path = current_global_hooks_path()?;
 
-    if hook_path.exists() && is_flow_managed_hook(&hook_path)? {
-        fs::remove_file(&hook_path)
-            .with_context(|| format!("failed to remove {}", hook_path.display()))?;
-        println!("Removed Flow pre-push hook at {}", hook_path.display());
-    } else if hook_path.exists() {
-        bail!(
-            "Refusing to remove non-Flow hook at {}.",
-            hook_path.display()
-        );
-    } else {
-        println!("No Flow pre-push hook found at {}", hook_path.display());
+    for (name, marker, _) in flow_hooks() {
+        let hook_path = hooks_path.join(name);
+        if hook_path.exists() && is_flow_managed_hook(&hook_path, marker)? {
+            fs::remove_file(&hook_path)
+                .with_context(|| format!("failed to remove {}", hook_path.display()))?;
+            println!("Removed Flow {} hook at {}", name, hook_path.display());
+        } else if hook_path.exists() {
+            bail!(
+                "Refusing to remove non-Flow hook at {}.",
+                hook_path.display()
+            );
+        } else {
+            println!("No Flow {} hook found at {}", name, hook_path.display());
+        }
     }
 
     if let Some(current) = current_hooks_path
@@ -137,18 +207,11 @@ pub fn uninstall_hooks() -> Result<()> {
 
 pub fn print_hook_status() -> Result<()> {
     let hooks_path = push_policy::effective_global_hooks_path()?;
-    let hook_path = hooks_path.join("pre-push");
     let current_hooks_path = current_global_hooks_path()?;
     let current_matches = current_hooks_path
         .as_ref()
         .map(|path| normalize_path(path) == normalize_path(&hooks_path))
         .unwrap_or(false);
-    let hook_exists = hook_path.exists();
-    let flow_managed = if hook_exists {
-        is_flow_managed_hook(&hook_path)?
-    } else {
-        false
-    };
 
     println!("Flow push hook status");
     println!("Expected hooks path: {}", hooks_path.display());
@@ -156,15 +219,25 @@ pub fn print_hook_status() -> Result<()> {
         Some(path) => println!("Global core.hooksPath: {}", path.display()),
         None => println!("Global core.hooksPath: <unset>"),
     }
-    println!(
-        "Flow pre-push hook: {}",
-        if hook_exists {
-            hook_path.display().to_string()
+    for (name, marker, _) in flow_hooks() {
+        let hook_path = hooks_path.join(name);
+        let hook_exists = hook_path.exists();
+        let flow_managed = if hook_exists {
+            is_flow_managed_hook(&hook_path, marker)?
         } else {
-            format!("missing ({})", hook_path.display())
-        }
-    );
-    println!("Flow-managed hook file: {}", yes_no(flow_managed));
+            false
+        };
+        println!(
+            "Flow {} hook: {}",
+            name,
+            if hook_exists {
+                hook_path.display().to_string()
+            } else {
+                format!("missing ({})", hook_path.display())
+            }
+        );
+        println!("Flow-managed {} hook file: {}", name, yes_no(flow_managed));
+    }
     println!("Flow hooks path active: {}", yes_no(current_matches));
     Ok(())
 }
@@ -240,10 +313,10 @@ fn current_branch(repo_root: &Path) -> Result<Option<String>> {
     }
 }
 
-fn is_flow_managed_hook(path: &Path) -> Result<bool> {
+fn is_flow_managed_hook(path: &Path, marker: &str) -> Result<bool> {
     let content = fs::read_to_string(path)
         .with_context(|| format!("failed to read hook {}", path.display()))?;
-    Ok(content.contains(FLOW_PRE_PUSH_HOOK_MARKER))
+    Ok(content.contains(marker))
 }
 
 fn normalize_path(path: &Path) -> PathBuf {
@@ -257,47 +330,7 @@ set -euo pipefail
 
 # {marker}
 
-resolve_flow_bin() {{
-  if [[ -n "${{FLOW_PUSH_FLOW_BIN:-}}" && -x "${{FLOW_PUSH_FLOW_BIN}}" ]]; then
-    printf '%s\n' "${{FLOW_PUSH_FLOW_BIN}}"
-    return 0
-  fi
-
-  local candidate=""
-  candidate="$(command -v f 2>/dev/null || true)"
-  if [[ -n "$candidate" && -x "$candidate" ]]; then
-    printf '%s\n' "$candidate"
-    return 0
-  fi
-
-  for candidate in "$HOME/.flow/bin/f" "$HOME/bin/f" "$HOME/bin/f-bin"; do
-    if [[ -x "$candidate" ]]; then
-      printf '%s\n' "$candidate"
-      return 0
-    fi
-  done
-
-  candidate="$(command -v flow 2>/dev/null || true)"
-  if [[ -n "$candidate" && -x "$candidate" ]]; then
-    printf '%s\n' "$candidate"
-    return 0
-  fi
-
-  echo "Flow push hook could not find the flow binary. Set FLOW_PUSH_FLOW_BIN or install f on PATH." >&2
-  return 1
-}}
-
-same_path() {{
-  if [[ -z "${{1:-}}" || -z "${{2:-}}" ]]; then
-    return 1
-  fi
-
-  local left_dir=""
-  local right_dir=""
-  left_dir="$(cd "$(dirname "$1")" 2>/dev/null && pwd -P)" || return 1
-  right_dir="$(cd "$(dirname "$2")" 2>/dev/null && pwd -P)" || return 1
-  [[ "$left_dir/$(basename "$1")" == "$right_dir/$(basename "$2")" ]]
-}}
+{helpers}
 
 payload_file="$(mktemp "${{TMPDIR:-/tmp}}/flow-pre-push.XXXXXX")"
 cleanup() {{
@@ -325,6 +358,38 @@ if [[ -n "${{legacy_hook:-}}" && -x "$legacy_hook" && "${{FLOW_PUSH_HOOK_CHAINED
 fi
 "#,
         marker = FLOW_PRE_PUSH_HOOK_MARKER,
+        helpers = FLOW_HOOK_HELPERS,
+    )
+}
+
+fn render_commit_msg_hook_script() -> String {
+    format!(
+        r#"#!/usr/bin/env bash
+set -euo pipefail
+
+# {marker}
+
+{helpers}
+
+flow_bin="$(resolve_flow_bin)"
+"$flow_bin" commit-msg-hook "$1"
+
+git_dir="$(git rev-parse --absolute-git-dir 2>/dev/null || true)"
+legacy_hook=""
+if [[ -n "$git_dir" ]]; then
+  legacy_hook="$git_dir/hooks/commit-msg"
+fi
+
+if same_path "${{legacy_hook:-}}" "$0"; then
+  legacy_hook=""
+fi
+
+if [[ -n "${{legacy_hook:-}}" && -x "$legacy_hook" && "${{FLOW_COMMIT_MSG_HOOK_CHAINED:-0}}" != "1" ]]; then
+  FLOW_COMMIT_MSG_HOOK_CHAINED=1 "$legacy_hook" "$@"
+fi
+"#,
+        marker = FLOW_COMMIT_MSG_HOOK_MARKER,
+        helpers = FLOW_HOOK_HELPERS,
     )
 }
 
@@ -404,4 +469,13 @@ mod tests {
         assert!(script.contains("legacy_hook"));
         assert!(script.contains("same_path"));
     }
+
+    #[test]
+    fn rendered_commit_msg_hook_runs_message_check_and_chains() {
+        let script = render_commit_msg_hook_script();
+        assert!(script.contains("commit-msg-hook \"$1\""));
+        assert!(script.contains(FLOW_COMMIT_MSG_HOOK_MARKER));
+        assert!(script.contains("resolve_flow_bin() {\n"));
+        assert!(script.contains("$git_dir/hooks/commit-msg"));
+    }
 }
@@ -76,6 +76,9 @@ pub fn run_task(opts: ReleaseOpts) -> Result<()> {
         delegate_to_hub: false,
         hub_host: std::net::IpAddr::from([127, 0, 0, 1]),
         hub_port: 9050,
+        priority: 0,
+        print_env: false,
+        yes: false,
         name: task_name,
         args: opts.args,
     })
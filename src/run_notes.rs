@@ -0,0 +1,300 @@
+//! Task run summaries stored as git notes under `refs/notes/flow`.
+//!
+//! Tasks with `git_note = true` that finish on a clean tree attach one line to HEAD, replacing
+//! the line an earlier run of the same task left there:
+//!
+//! ```text
+//! test: passed duration=12.4s exit=0 coverage=83.2% run=m2x9k4-1a2b at=2026-10-16T09:30:00Z
+//! ```
+//!
+//! `git log --notes=flow` shows them next to the commit; `f runs note <commit>` reads them back.
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result, bail};
+use regex::Regex;
+use serde::Serialize;
+
+use crate::cli::RunsNoteOpts;
+use crate::runs;
+
+pub const NOTES_REF: &str = "refs/notes/flow";
+
+/// One task's entry in a commit's note.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunNote {
+    pub task: String,
+    pub passed: bool,
+    pub duration_ms: i64,
+    pub exit: Option<i32>,
+    /// Line coverage percentage, when the output had a recognizable summary.
+    pub coverage: Option<f64>,
+    pub run_id: String,
+    /// RFC 3339 UTC time the run finished.
+    pub at: String,
+}
+
+impl RunNote {
+    fn to_line(&self) -> String {
+        let mut line = format!(
+            "{}: {} duration={}",
+            self.task,
+            if self.passed { "passed" } else { "failed" },
+            runs::format_duration(self.duration_ms)
+        );
+        if let Some(exit) = self.exit {
+            line.push_str(&format!(" exit={exit}"));
+        }
+        if let Some(coverage) = self.coverage {
+            line.push_str(&format!(" coverage={coverage}%"));
+        }
+        line.push_str(&format!(" run={} at={}", self.run_id, self.at));
+        line
+    }
+
+    fn parse_line(line: &str) -> Option<Self> {
+        let (task, rest) = line.split_once(": ")?;
+        let mut tokens = rest.split_whitespace();
+        let passed = match tokens.next()? {
+            "passed" => true,
+            "failed" => false,
+            _ => return None,
+        };
+        let mut note = RunNote {
+            task: task.to_string(),
+            passed,
+            duration_ms: 0,
+            exit: None,
+            coverage: None,
+            run_id: String::new(),
+            at: String::new(),
+        };
+        for token in tokens {
+            let Some((key, value)) = token.split_once('=') else {
+                continue;
+            };
+            match key {
+                "duration" => note.duration_ms = parse_duration_ms(value).unwrap_or(0),
+                "exit" => note.exit = value.parse().ok(),
+                "coverage" => note.coverage = value.trim_end_matches('%').parse().ok(),
+                "run" => note.run_id = value.to_string(),
+                "at" => note.at = value.to_string(),
+                _ => {}
+            }
+        }
+        Some(note)
+    }
+}
+
+/// Parse what `runs::format_duration` prints: `850ms`, `12.4s`, or `3m5s`.
+fn parse_duration_ms(value: &str) -> Option<i64> {
+    if let Some(ms) = value.strip_suffix("ms") {
+        return ms.parse().ok();
+    }
+    if let Some((minutes, seconds)) = value.split_once('m') {
+        let minutes: i64 = minutes.parse().ok()?;
+        let seconds: i64 = seconds.strip_suffix('s')?.parse().ok()?;
+        return Some(minutes * 60_000 + seconds * 1000);
+    }
+    let seconds: f64 = value.strip_suffix('s')?.parse().ok()?;
+    Some((seconds * 1000.0).round() as i64)
+}
+
+/// Attach `note` to HEAD of the repo containing `workdir`. Returns the short commit it went
+/// on, or `None` (with the reason printed) when the run can't vouch for HEAD because tracked
+/// files differ from it.
+pub fn attach(workdir: &Path, note: &RunNote) -> Result<Option<String>> {
+    let status = git(workdir, &["status", "--porcelain", "--untracked-files=no"])?;
+    if !status.trim().is_empty() {
+        println!(
+            "Not noting '{}' on HEAD: the working tree has uncommitted changes.",
+            note.task
+        );
+        return Ok(None);
+    }
+    let commit = git(workdir, &["rev-parse", "HEAD"])?.trim().to_string();
+    let existing =
+        git(workdir, &["notes", "--ref", NOTES_REF, "show", &commit]).unwrap_or_default();
+    let body = merge_note(&existing, note);
+
+    let mut child = Command::new("git")
+        .current_dir(workdir)
+        .args(["notes", "--ref", NOTES_REF, "add", "-f", "-F", "-", &commit])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to run git notes")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(body.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "git notes add failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(Some(commit.chars().take(12).collect()))
+}
+
+/// `existing` with the line for `note.task` replaced (or appended).
+fn merge_note(existing: &str, note: &RunNote) -> String {
+    let prefix = format!("{}: ", note.task);
+    let mut lines: Vec<String> = existing
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with(&prefix))
+        .map(str::to_string)
+        .collect();
+    lines.push(note.to_line());
+    lines.join("\n") + "\n"
+}
+
+/// Last coverage summary in `output`: coverage.py / llvm-cov `TOTAL` rows, istanbul tables
+/// (jest, vitest, bun), `go test -cover`, and tarpaulin.
+pub fn parse_coverage(output: &str) -> Option<f64> {
+    static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+    static ANSI: OnceLock<Regex> = OnceLock::new();
+    let patterns = PATTERNS.get_or_init(|| {
+        [
+            r"(?m)^TOTAL\s.*?(\d+(?:\.\d+)?)%\s*$",
+            r"(?m)^\s*All files\s*\|\s*(\d+(?:\.\d+)?)",
+            r"coverage: (\d+(?:\.\d+)?)% of statements",
+            r"(\d+(?:\.\d+)?)% coverage, \d+/\d+ lines covered",
+        ]
+        .iter()
+        .map(|pattern| Regex::new(pattern).expect("valid coverage regex"))
+        .collect()
+    });
+    let ansi = ANSI.get_or_init(|| Regex::new(r"\x1b\[[0-9;]*m").expect("valid ansi regex"));
+    let plain = ansi.replace_all(output, "");
+    patterns
+        .iter()
+        .filter_map(|pattern| {
+            let captures = pattern.captures_iter(&plain).last()?;
+            let value = captures.get(1)?;
+            Some((value.start(), value.as_str().parse::<f64>().ok()?))
+        })
+        .max_by_key(|(offset, _)| *offset)
+        .map(|(_, coverage)| coverage)
+}
+
+/// `f runs note [<commit>]`.
+pub fn show(opts: RunsNoteOpts) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let commit = git(
+        &cwd,
+        &[
+            "rev-parse",
+            "--verify",
+            &format!("{}^{{commit}}", opts.commit),
+        ],
+    )
+    .with_context(|| format!("unknown commit '{}'", opts.commit))?;
+    let commit = commit.trim();
+    let Ok(body) = git(&cwd, &["notes", "--ref", NOTES_REF, "show", commit]) else {
+        if opts.json {
+            println!("[]");
+        } else {
+            println!(
+                "No flow run notes on {}. Tasks with `git_note = true` add them.",
+                &commit[..commit.len().min(12)]
+            );
+        }
+        return Ok(());
+    };
+    if opts.json {
+        let notes: Vec<RunNote> = body.lines().filter_map(RunNote::parse_line).collect();
+        println!("{}", serde_json::to_string_pretty(&notes)?);
+    } else {
+        println!("{} ({NOTES_REF})", &commit[..commit.len().min(12)]);
+        for line in body.lines().filter(|line| !line.trim().is_empty()) {
+            println!("  {line}");
+        }
+    }
+    Ok(())
+}
+
+fn git(workdir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .current_dir(workdir)
+        .args(args)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(task: &str, passed: bool) -> RunNote {
+        RunNote {
+            task: task.to_string(),
+            passed,
+            duration_ms: 12_400,
+            exit: Some(if passed { 0 } else { 1 }),
+            coverage: passed.then_some(83.2),
+            run_id: "m2x9k4-1a2b".to_string(),
+            at: "2026-10-16T09:30:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn note_lines_round_trip_and_replace_per_task() {
+        let test = note("test", true);
+        assert_eq!(
+            test.to_line(),
+            "test: passed duration=12.4s exit=0 coverage=83.2% run=m2x9k4-1a2b at=2026-10-16T09:30:00Z"
+        );
+        assert_eq!(RunNote::parse_line(&test.to_line()), Some(test.clone()));
+
+        let body = merge_note("", &note("lint", false));
+        let body = merge_note(&body, &note("test", false));
+        let body = merge_note(&body, &test);
+        let tasks: Vec<_> = body
+            .lines()
+            .filter_map(RunNote::parse_line)
+            .map(|note| (note.task, note.passed))
+            .collect();
+        assert_eq!(
+            tasks,
+            [("lint".to_string(), false), ("test".to_string(), true)]
+        );
+    }
+
+    #[test]
+    fn parses_common_coverage_summaries() {
+        assert_eq!(
+            parse_coverage("Name    Stmts   Miss  Cover\nTOTAL     120     20    83%\n"),
+            Some(83.0)
+        );
+        assert_eq!(
+            parse_coverage(
+                "File      | % Stmts | % Branch\n\x1b[32mAll files\x1b[0m |   91.5 |    80\n"
+            ),
+            Some(91.5)
+        );
+        assert_eq!(
+            parse_coverage(
+                "ok  \tpkg/a\t0.2s\tcoverage: 70.1% of statements\nok  \tpkg/b\tcoverage: 64.0% of statements\n"
+            ),
+            Some(64.0)
+        );
+        assert_eq!(
+            parse_coverage("|| 83.21% coverage, 100/120 lines covered"),
+            Some(83.21)
+        );
+        assert_eq!(parse_coverage("test result: ok. 12 passed"), None);
+    }
+}
@@ -30,6 +30,12 @@ pub struct RunningProcess {
     /// Optional project name from flow.toml
     #[serde(default)]
     pub project_name: Option<String>,
+    /// Started outside flow and attached with `f ps adopt`
+    #[serde(default)]
+    pub adopted: bool,
+    /// Where the process writes its output, when it isn't flow's own task log
+    #[serde(default)]
+    pub log_path: Option<PathBuf>,
 }
 
 /// All running processes tracked by flow
@@ -70,7 +76,9 @@ fn open_running_db(path: &Path) -> Result<Connection> {
             config_path TEXT NOT NULL,
             project_root TEXT NOT NULL,
             used_flox INTEGER NOT NULL,
-            project_name TEXT
+            project_name TEXT,
+            adopted INTEGER NOT NULL DEFAULT 0,
+            log_path TEXT
         );
         CREATE INDEX IF NOT EXISTS idx_running_processes_config_path
         ON running_processes(config_path);
@@ -78,9 +86,29 @@ fn open_running_db(path: &Path) -> Result<Connection> {
         ON running_processes(started_at);",
     )
     .context("failed to initialize running-process schema")?;
+    migrate_running_db(&conn)?;
     Ok(conn)
 }
 
+/// Add columns introduced after the table was first created.
+fn migrate_running_db(conn: &Connection) -> Result<()> {
+    let mut stmt = conn
+        .prepare("pragma table_info(running_processes)")
+        .context("failed to prepare running-process schema query")?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))
+        .context("failed to query running-process schema")?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    if !columns.iter().any(|column| column == "adopted") {
+        conn.execute_batch(
+            "ALTER TABLE running_processes ADD COLUMN adopted INTEGER NOT NULL DEFAULT 0;
+             ALTER TABLE running_processes ADD COLUMN log_path TEXT;",
+        )
+        .context("failed to migrate running-process schema")?;
+    }
+    Ok(())
+}
+
 fn read_processes(conn: &Connection, config_path: Option<&Path>) -> Result<Vec<RunningProcess>> {
     let mut processes = Vec::new();
 
@@ -89,7 +117,7 @@ fn read_processes(conn: &Connection, config_path: Option<&Path>) -> Result<Vec<R
         let mut stmt = conn
             .prepare(
                 "SELECT pid, pgid, task_name, command, started_at, config_path, project_root,
-                        used_flox, project_name
+                        used_flox, project_name, adopted, log_path
                  FROM running_processes
                  WHERE config_path = ?1
                  ORDER BY started_at ASC",
@@ -105,7 +133,7 @@ fn read_processes(conn: &Connection, config_path: Option<&Path>) -> Result<Vec<R
         let mut stmt = conn
             .prepare(
                 "SELECT pid, pgid, task_name, command, started_at, config_path, project_root,
-                        used_flox, project_name
+                        used_flox, project_name, adopted, log_path
                  FROM running_processes
                  ORDER BY started_at ASC",
             )
@@ -133,6 +161,8 @@ fn row_to_running_process(row: &rusqlite::Row<'_>) -> rusqlite::Result<RunningPr
         project_root: PathBuf::from(row.get::<_, String>(6)?),
         used_flox: row.get::<_, i64>(7)? != 0,
         project_name: row.get(8)?,
+        adopted: row.get::<_, i64>(9)? != 0,
+        log_path: row.get::<_, Option<String>>(10)?.map(PathBuf::from),
     })
 }
 
@@ -199,8 +229,8 @@ fn register_process_at(path: &Path, entry: RunningProcess) -> Result<()> {
     tx.execute(
         "INSERT INTO running_processes (
             pid, pgid, task_name, command, started_at, config_path, project_root,
-            used_flox, project_name
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            used_flox, project_name, adopted, log_path
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
         ON CONFLICT(pid) DO UPDATE SET
             pgid = excluded.pgid,
             task_name = excluded.task_name,
@@ -209,7 +239,9 @@ fn register_process_at(path: &Path, entry: RunningProcess) -> Result<()> {
             config_path = excluded.config_path,
             project_root = excluded.project_root,
             used_flox = excluded.used_flox,
-            project_name = excluded.project_name",
+            project_name = excluded.project_name,
+            adopted = excluded.adopted,
+            log_path = excluded.log_path",
         params![
             entry.pid,
             entry.pgid,
@@ -220,6 +252,11 @@ fn register_process_at(path: &Path, entry: RunningProcess) -> Result<()> {
             entry.project_root.display().to_string(),
             if entry.used_flox { 1i64 } else { 0i64 },
             entry.project_name,
+            if entry.adopted { 1i64 } else { 0i64 },
+            entry
+                .log_path
+                .as_ref()
+                .map(|path| path.display().to_string()),
         ],
     )
     .with_context(|| format!("failed to register running process {}", entry.task_name))?;
@@ -324,6 +361,8 @@ mod tests {
             project_root: root.to_path_buf(),
             used_flox: false,
             project_name: Some("flow".to_string()),
+            adopted: false,
+            log_path: None,
         }
     }
 
@@ -0,0 +1,881 @@
+//! Unified run history across task runs, deploys, and commit gates.
+//!
+//! Every subsystem that executes something on behalf of the user records a row in the shared
+//! `runs` table so a failed deploy can be traced back to the task run it triggered. Starting
+//! and finishing a run also publishes `<kind>.started` / `<kind>.finished` on the hub event bus.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use anyhow::{Context, Result, bail};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::cli::{RunsAction, RunsCommand, RunsListOpts, RunsShowOpts};
+use crate::hub_events::{self, HubEvent};
+use crate::task_parsers::TestResults;
+use crate::{db, running};
+
+/// Environment variable carrying the id of the enclosing run, so nested runs (including
+/// subprocesses like `f run` invoked from a deploy script) can link to their parent.
+pub const RUN_ID_ENV: &str = "FLOW_RUN_ID";
+
+/// Runs entered in this process, innermost last.
+static ENTERED_RUNS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Id of the run new work belongs to: the innermost run entered in this process, otherwise
+/// the `FLOW_RUN_ID` this process was started with.
+pub fn current_run_id() -> Option<String> {
+    let entered = ENTERED_RUNS
+        .lock()
+        .ok()
+        .and_then(|runs| runs.last().cloned());
+    entered.or_else(|| {
+        std::env::var(RUN_ID_ENV)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    })
+}
+
+/// Pass the current run id to a child process, so runs it records link to it.
+pub fn set_run_env(cmd: &mut Command) {
+    if let Some(id) = current_run_id() {
+        cmd.env(RUN_ID_ENV, id);
+    }
+}
+
+/// What kind of execution a run record describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunKind {
+    Task,
+    Deploy,
+    Commit,
+}
+
+impl RunKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RunKind::Task => "task",
+            RunKind::Deploy => "deploy",
+            RunKind::Commit => "commit",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "task" => Some(RunKind::Task),
+            "deploy" => Some(RunKind::Deploy),
+            "commit" => Some(RunKind::Commit),
+            _ => None,
+        }
+    }
+}
+
+/// Single persisted run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub id: String,
+    pub kind: RunKind,
+    pub name: String,
+    pub project: Option<String>,
+    pub project_root: Option<String>,
+    pub parent_id: Option<String>,
+    pub started_ms: i64,
+    pub duration_ms: i64,
+    pub status: Option<i32>,
+    pub success: bool,
+    pub log_path: Option<String>,
+    pub detail: Option<String>,
+    /// Test results extracted by the task's `parser`, if any.
+    #[serde(default)]
+    pub results: Option<TestResults>,
+    /// Code the run saw: HEAD, plus a hash of uncommitted changes (`<sha>+<hash>`).
+    #[serde(default)]
+    pub code_rev: Option<String>,
+}
+
+/// Filters for listing runs.
+#[derive(Debug, Clone)]
+pub struct RunQuery {
+    pub kind: Option<RunKind>,
+    pub project: Option<String>,
+    pub failed_only: bool,
+    pub limit: usize,
+}
+
+impl Default for RunQuery {
+    fn default() -> Self {
+        Self {
+            kind: None,
+            project: None,
+            failed_only: false,
+            limit: 20,
+        }
+    }
+}
+
+/// In-flight run. Call one of the `finish` methods once the work completes; the record is
+/// written then so a crashed process leaves no half-finished rows behind. A tracker dropped
+/// without finishing (an early `?` return) is recorded as failed.
+pub struct RunTracker {
+    record: RunRecord,
+    started: Instant,
+    entered: bool,
+    persisted: bool,
+}
+
+impl RunTracker {
+    pub fn start(
+        kind: RunKind,
+        name: &str,
+        project: Option<&str>,
+        project_root: Option<&Path>,
+    ) -> Self {
+        let parent_id = current_run_id();
+        let tracker = Self {
+            record: RunRecord {
+                id: new_run_id(),
+                kind,
+                name: name.to_string(),
+                project: project.map(|s| s.to_string()),
+                project_root: project_root.map(|p| p.display().to_string()),
+                parent_id,
+                started_ms: running::now_ms() as i64,
+                duration_ms: 0,
+                status: None,
+                success: false,
+                log_path: None,
+                detail: None,
+                results: None,
+                code_rev: None,
+            },
+            started: Instant::now(),
+            entered: false,
+            persisted: false,
+        };
+        let record = &tracker.record;
+        hub_events::publish(HubEvent::new(
+            format!("{}.started", kind.as_str()),
+            project,
+            json!({
+                "run_id": record.id,
+                "name": record.name,
+                "project_root": record.project_root,
+                "parent_id": record.parent_id,
+            }),
+        ));
+        tracker
+    }
+
+    pub fn id(&self) -> &str {
+        &self.record.id
+    }
+
+    /// Make this the current run until the tracker finishes, so runs started in the meantime
+    /// (in-process, or by child processes given [`set_run_env`]) record it as their parent.
+    pub fn enter(mut self) -> Self {
+        if !self.entered {
+            if let Ok(mut runs) = ENTERED_RUNS.lock() {
+                runs.push(self.record.id.clone());
+            }
+            self.entered = true;
+        }
+        self
+    }
+
+    pub fn set_log_path(&mut self, path: &Path) {
+        self.record.log_path = Some(path.display().to_string());
+    }
+
+    pub fn set_detail(&mut self, detail: impl Into<String>) {
+        self.record.detail = Some(detail.into());
+    }
+
+    pub fn set_results(&mut self, results: TestResults) {
+        self.record.results = Some(results);
+    }
+
+    /// Record the code revision of `dir`, so `f tasks stats` can tell flaky failures from
+    /// failures caused by a change.
+    pub fn set_code_rev_from(&mut self, dir: &Path) {
+        self.record.code_rev = code_rev(dir);
+    }
+
+    /// Finish with an explicit exit status.
+    pub fn finish(mut self, status: Option<i32>, success: bool) {
+        self.record.status = status;
+        self.record.success = success;
+        self.persist();
+    }
+
+    /// Finish from a `Result`, storing the error message as the run detail on failure.
+    pub fn finish_result<T>(mut self, result: &Result<T>) {
+        match result {
+            Ok(_) => {
+                self.record.status = Some(0);
+                self.record.success = true;
+            }
+            Err(err) => {
+                self.record.status = Some(1);
+                self.record.success = false;
+                if self.record.detail.is_none() {
+                    self.record.detail = Some(format!("{err:#}"));
+                }
+            }
+        }
+        self.persist();
+    }
+
+    fn persist(&mut self) {
+        self.persisted = true;
+        self.record.duration_ms = self.started.elapsed().as_millis() as i64;
+        if self.entered
+            && let Ok(mut runs) = ENTERED_RUNS.lock()
+            && let Some(index) = runs.iter().rposition(|id| *id == self.record.id)
+        {
+            runs.remove(index);
+        }
+        let result = open_runs_db().and_then(|conn| insert_run(&conn, &self.record));
+        if let Err(err) = result {
+            tracing::warn!(?err, "failed to write run record");
+        }
+        hub_events::publish(HubEvent::new(
+            format!("{}.finished", self.record.kind.as_str()),
+            self.record.project.as_deref(),
+            serde_json::to_value(&self.record).unwrap_or_default(),
+        ));
+    }
+}
+
+impl Drop for RunTracker {
+    fn drop(&mut self) {
+        if self.persisted {
+            return;
+        }
+        self.record.success = false;
+        if self.record.detail.is_none() {
+            self.record.detail = Some("ended without finishing".to_string());
+        }
+        self.persist();
+    }
+}
+
+/// `HEAD` of the repo containing `dir`, with a short hash of `git diff HEAD` appended when the
+/// tree has uncommitted changes. `None` outside a git repo.
+fn code_rev(dir: &Path) -> Option<String> {
+    let git = |args: &[&str]| {
+        std::process::Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .stderr(std::process::Stdio::null())
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| output.stdout)
+    };
+    let head = String::from_utf8(git(&["rev-parse", "HEAD"])?).ok()?;
+    let head = head.trim().get(..12)?.to_string();
+    let diff = git(&["diff", "HEAD", "--no-ext-diff", "--no-color"]).unwrap_or_default();
+    if diff.is_empty() {
+        return Some(head);
+    }
+    let digest = blake3::hash(&diff).to_hex();
+    Some(format!("{head}+{}", &digest[..8]))
+}
+
+fn new_run_id() -> String {
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    id[..12].to_string()
+}
+
+/// Initialize the runs table schema.
+pub fn init_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS runs (
+            id TEXT PRIMARY KEY,
+            kind TEXT NOT NULL,
+            name TEXT NOT NULL,
+            project TEXT,
+            project_root TEXT,
+            parent_id TEXT,
+            started_ms INTEGER NOT NULL,
+            duration_ms INTEGER NOT NULL,
+            status INTEGER,
+            success INTEGER NOT NULL,
+            log_path TEXT,
+            detail TEXT,
+            results TEXT,
+            code_rev TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_runs_started ON runs(started_ms);
+        CREATE INDEX IF NOT EXISTS idx_runs_project ON runs(project);
+        CREATE INDEX IF NOT EXISTS idx_runs_parent ON runs(parent_id);
+        "#,
+    )
+    .context("failed to create runs schema")?;
+    ensure_added_columns(conn)?;
+    Ok(())
+}
+
+/// Databases created before task output parsers lack `results`; before `f tasks stats`,
+/// `code_rev`.
+fn ensure_added_columns(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("PRAGMA table_info(runs)")?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for column in ["results", "code_rev"] {
+        if !columns.iter().any(|name| name == column) {
+            conn.execute(&format!("ALTER TABLE runs ADD COLUMN {column} TEXT"), [])
+                .with_context(|| format!("failed to add {column} column"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Open database and ensure schema exists.
+pub fn open_runs_db() -> Result<Connection> {
+    let conn = db::open_db()?;
+    init_schema(&conn)?;
+    Ok(conn)
+}
+
+/// Insert a finished run.
+pub fn insert_run(conn: &Connection, record: &RunRecord) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT OR REPLACE INTO runs (
+            id, kind, name, project, project_root, parent_id,
+            started_ms, duration_ms, status, success, log_path, detail, results, code_rev
+        )
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+        "#,
+        params![
+            record.id,
+            record.kind.as_str(),
+            record.name,
+            record.project,
+            record.project_root,
+            record.parent_id,
+            record.started_ms,
+            record.duration_ms,
+            record.status,
+            record.success,
+            record.log_path,
+            record.detail,
+            record
+                .results
+                .as_ref()
+                .and_then(|results| serde_json::to_string(results).ok()),
+            record.code_rev,
+        ],
+    )
+    .context("failed to insert run")?;
+    Ok(())
+}
+
+const RUN_COLUMNS: &str = "id, kind, name, project, project_root, parent_id, started_ms, \
+     duration_ms, status, success, log_path, detail, results, code_rev";
+
+fn row_to_record(row: &rusqlite::Row<'_>) -> rusqlite::Result<RunRecord> {
+    let kind: String = row.get(1)?;
+    Ok(RunRecord {
+        id: row.get(0)?,
+        kind: RunKind::parse(&kind).unwrap_or(RunKind::Task),
+        name: row.get(2)?,
+        project: row.get(3)?,
+        project_root: row.get(4)?,
+        parent_id: row.get(5)?,
+        started_ms: row.get(6)?,
+        duration_ms: row.get(7)?,
+        status: row.get(8)?,
+        success: row.get(9)?,
+        log_path: row.get(10)?,
+        detail: row.get(11)?,
+        results: row
+            .get::<_, Option<String>>(12)?
+            .and_then(|raw| serde_json::from_str(&raw).ok()),
+        code_rev: row.get(13)?,
+    })
+}
+
+/// Query runs, most recent first.
+pub fn query_runs(conn: &Connection, query: &RunQuery) -> Result<Vec<RunRecord>> {
+    let mut sql = format!("SELECT {RUN_COLUMNS} FROM runs WHERE 1=1");
+    let mut values: Vec<String> = Vec::new();
+    if let Some(kind) = query.kind {
+        values.push(kind.as_str().to_string());
+        sql.push_str(&format!(" AND kind = ?{}", values.len()));
+    }
+    if let Some(project) = query.project.as_ref() {
+        values.push(project.clone());
+        sql.push_str(&format!(" AND project = ?{}", values.len()));
+    }
+    if query.failed_only {
+        sql.push_str(" AND success = 0");
+    }
+    sql.push_str(&format!(
+        " ORDER BY started_ms DESC LIMIT {}",
+        query.limit.max(1)
+    ));
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(values.iter()), row_to_record)?;
+    let mut runs = Vec::new();
+    for row in rows {
+        runs.push(row?);
+    }
+    Ok(runs)
+}
+
+/// Resolve a run by id or unique id prefix.
+pub fn find_run(conn: &Connection, id: &str) -> Result<Option<RunRecord>> {
+    let id = id.trim();
+    if id.is_empty() {
+        return Ok(None);
+    }
+    let exact = conn
+        .query_row(
+            &format!("SELECT {RUN_COLUMNS} FROM runs WHERE id = ?1"),
+            [id],
+            row_to_record,
+        )
+        .optional()?;
+    if exact.is_some() {
+        return Ok(exact);
+    }
+
+    let pattern = format!(
+        "{}%",
+        id.replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    );
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {RUN_COLUMNS} FROM runs WHERE id LIKE ?1 ESCAPE '\\' LIMIT 2"
+    ))?;
+    let matches: Vec<RunRecord> = stmt
+        .query_map([pattern], row_to_record)?
+        .collect::<rusqlite::Result<_>>()?;
+    if matches.len() > 1 {
+        bail!("run id prefix '{}' is ambiguous", id);
+    }
+    Ok(matches.into_iter().next())
+}
+
+/// Runs started at or after `since_ms`, oldest first.
+pub fn runs_since(
+    conn: &Connection,
+    project: Option<&str>,
+    since_ms: i64,
+) -> Result<Vec<RunRecord>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {RUN_COLUMNS} FROM runs WHERE started_ms >= ?1 AND (?2 IS NULL OR project = ?2) \
+         ORDER BY started_ms ASC"
+    ))?;
+    let runs = stmt
+        .query_map(params![since_ms, project], row_to_record)?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(runs)
+}
+
+/// Most recent run of `kind` recorded for `project_root` (matched as given or canonicalized).
+pub fn latest_for_root(
+    conn: &Connection,
+    kind: RunKind,
+    project_root: &Path,
+) -> Result<Option<RunRecord>> {
+    let given = project_root.display().to_string();
+    let canonical = project_root
+        .canonicalize()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|_| given.clone());
+    let run = conn
+        .query_row(
+            &format!(
+                "SELECT {RUN_COLUMNS} FROM runs WHERE kind = ?1 AND project_root IN (?2, ?3) \
+                 ORDER BY started_ms DESC LIMIT 1"
+            ),
+            params![kind.as_str(), given, canonical],
+            row_to_record,
+        )
+        .optional()?;
+    Ok(run)
+}
+
+/// Most recent run of `kind` named `name` whose project root is `root` or a directory below it.
+pub fn latest_named_under(
+    conn: &Connection,
+    kind: RunKind,
+    name: &str,
+    root: &Path,
+) -> Result<Option<RunRecord>> {
+    let root = root.display().to_string();
+    let run = conn
+        .query_row(
+            &format!(
+                "SELECT {RUN_COLUMNS} FROM runs WHERE kind = ?1 AND name = ?2 \
+                 AND (project_root = ?3 OR project_root LIKE ?3 || '/%') \
+                 ORDER BY started_ms DESC LIMIT 1"
+            ),
+            params![kind.as_str(), name, root],
+            row_to_record,
+        )
+        .optional()?;
+    Ok(run)
+}
+
+/// The `limit` most recent runs whose project root is `root` or a directory below it, or of
+/// every project when `root` is `None`.
+pub fn recent_runs_under(
+    conn: &Connection,
+    root: Option<&Path>,
+    limit: usize,
+) -> Result<Vec<RunRecord>> {
+    let root = root.map(|root| root.display().to_string());
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {RUN_COLUMNS} FROM runs \
+         WHERE ?1 IS NULL OR project_root = ?1 OR project_root LIKE ?1 || '/%' \
+         ORDER BY started_ms DESC LIMIT ?2"
+    ))?;
+    let runs = stmt
+        .query_map(params![root, limit.max(1) as i64], row_to_record)?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(runs)
+}
+
+/// Task runs under `root` (or a directory below it) started at or after `since_ms`, oldest first.
+pub fn task_runs_under(conn: &Connection, root: &Path, since_ms: i64) -> Result<Vec<RunRecord>> {
+    let root = root.display().to_string();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {RUN_COLUMNS} FROM runs WHERE kind = 'task' AND started_ms >= ?1 \
+         AND (project_root = ?2 OR project_root LIKE ?2 || '/%') ORDER BY started_ms ASC"
+    ))?;
+    let runs = stmt
+        .query_map(params![since_ms, root], row_to_record)?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(runs)
+}
+
+/// Runs started while the given run was active.
+pub fn child_runs(conn: &Connection, parent_id: &str) -> Result<Vec<RunRecord>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {RUN_COLUMNS} FROM runs WHERE parent_id = ?1 ORDER BY started_ms ASC"
+    ))?;
+    let runs = stmt
+        .query_map([parent_id], row_to_record)?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(runs)
+}
+
+/// Handle `f runs`.
+pub fn run(cmd: RunsCommand) -> Result<()> {
+    match cmd.action {
+        Some(RunsAction::List(opts)) => run_list(opts),
+        Some(RunsAction::Show(opts)) => run_show(opts),
+        Some(RunsAction::Note(opts)) => crate::run_notes::show(opts),
+        None => run_list(RunsListOpts {
+            kind: None,
+            project: None,
+            failed: false,
+            limit: 20,
+            json: false,
+        }),
+    }
+}
+
+fn run_list(opts: RunsListOpts) -> Result<()> {
+    let kind = match opts.kind.as_deref() {
+        Some(raw) => Some(
+            RunKind::parse(raw.trim())
+                .with_context(|| format!("unknown run kind '{}' (task|deploy|commit)", raw))?,
+        ),
+        None => None,
+    };
+    let conn = open_runs_db()?;
+    let runs = query_runs(
+        &conn,
+        &RunQuery {
+            kind,
+            project: opts.project.clone(),
+            failed_only: opts.failed,
+            limit: opts.limit,
+        },
+    )?;
+
+    if opts.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&runs).context("failed to encode runs JSON")?
+        );
+        return Ok(());
+    }
+
+    if runs.is_empty() {
+        println!("No runs recorded yet.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<12} {:<7} {:<8} {:>8}  {:<20} NAME",
+        "ID", "KIND", "STATUS", "TIME", "PROJECT"
+    );
+    for record in &runs {
+        println!(
+            "{:<12} {:<7} {:<8} {:>8}  {:<20} {}",
+            record.id,
+            record.kind.as_str(),
+            status_label(record),
+            format_duration(record.duration_ms),
+            record.project.as_deref().unwrap_or("-"),
+            record.name
+        );
+    }
+    Ok(())
+}
+
+fn run_show(opts: RunsShowOpts) -> Result<()> {
+    let conn = open_runs_db()?;
+    let Some(record) = find_run(&conn, &opts.id)? else {
+        bail!(
+            "run '{}' not found. Use `f runs list` to see recent runs.",
+            opts.id
+        );
+    };
+    let children = child_runs(&conn, &record.id)?;
+
+    if opts.json {
+        let payload = serde_json::json!({
+            "run": record,
+            "children": children,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&payload).context("failed to encode run JSON")?
+        );
+        return Ok(());
+    }
+
+    println!("id: {}", record.id);
+    println!("kind: {}", record.kind.as_str());
+    println!("name: {}", record.name);
+    if let Some(project) = record.project.as_deref() {
+        println!("project: {project}");
+    }
+    if let Some(root) = record.project_root.as_deref() {
+        println!("project_root: {root}");
+    }
+    println!("started: {}", format_started(record.started_ms));
+    println!("duration: {}", format_duration(record.duration_ms));
+    println!(
+        "status: {} (code: {})",
+        status_label(&record),
+        record
+            .status
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "unknown".to_string())
+    );
+    if let Some(parent) = record.parent_id.as_deref() {
+        println!("parent: {parent}");
+    }
+    if let Some(log_path) = record.log_path.as_deref() {
+        let exists = PathBuf::from(log_path).exists();
+        println!(
+            "log: {}{}",
+            log_path,
+            if exists { "" } else { " (missing)" }
+        );
+    }
+    if let Some(detail) = record.detail.as_deref() {
+        println!("detail: {detail}");
+    }
+    if let Some(results) = record.results.as_ref() {
+        println!("tests: {} ({})", results.summary(), results.parser);
+        if !results.failures.is_empty() {
+            println!("failed tests:");
+            for name in &results.failures {
+                println!("  {name}");
+            }
+            if record.kind == RunKind::Task {
+                println!(
+                    "rerun failed: f run {} -- {}",
+                    record.name,
+                    shell_words::join(results.rerun_args())
+                );
+            }
+        }
+    }
+    if !children.is_empty() {
+        println!("children:");
+        for child in &children {
+            println!(
+                "  {}  {:<7} {:<8} {}",
+                child.id,
+                child.kind.as_str(),
+                status_label(child),
+                child.name
+            );
+        }
+    }
+    Ok(())
+}
+
+fn status_label(record: &RunRecord) -> &'static str {
+    if record.success { "ok" } else { "failed" }
+}
+
+pub(crate) fn format_duration(ms: i64) -> String {
+    if ms < 1000 {
+        format!("{ms}ms")
+    } else if ms < 60_000 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else {
+        format!("{}m{}s", ms / 60_000, (ms % 60_000) / 1000)
+    }
+}
+
+fn format_started(ms: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(ms)
+        .map(|dt| {
+            dt.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_else(|| ms.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(id: &str, kind: RunKind, started_ms: i64, success: bool) -> RunRecord {
+        RunRecord {
+            id: id.to_string(),
+            kind,
+            name: "dev".to_string(),
+            project: Some("web".to_string()),
+            project_root: None,
+            parent_id: None,
+            started_ms,
+            duration_ms: 10,
+            status: Some(if success { 0 } else { 1 }),
+            success,
+            log_path: None,
+            detail: None,
+            results: None,
+            code_rev: None,
+        }
+    }
+
+    #[test]
+    fn query_filters_by_kind_and_failure() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        insert_run(&conn, &sample("aaa111", RunKind::Task, 1, true)).unwrap();
+        insert_run(&conn, &sample("bbb222", RunKind::Deploy, 2, false)).unwrap();
+        insert_run(&conn, &sample("ccc333", RunKind::Task, 3, false)).unwrap();
+
+        let all = query_runs(&conn, &RunQuery::default()).unwrap();
+        assert_eq!(
+            all.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(),
+            vec!["ccc333", "bbb222", "aaa111"]
+        );
+
+        let failed_tasks = query_runs(
+            &conn,
+            &RunQuery {
+                kind: Some(RunKind::Task),
+                failed_only: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(failed_tasks.len(), 1);
+        assert_eq!(failed_tasks[0].id, "ccc333");
+    }
+
+    #[test]
+    fn find_run_resolves_prefix_and_children() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        insert_run(&conn, &sample("deploy01", RunKind::Deploy, 1, false)).unwrap();
+        let mut child = sample("task0001", RunKind::Task, 2, false);
+        child.parent_id = Some("deploy01".to_string());
+        insert_run(&conn, &child).unwrap();
+
+        let found = find_run(&conn, "depl").unwrap().unwrap();
+        assert_eq!(found.kind, RunKind::Deploy);
+        let children = child_runs(&conn, &found.id).unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].id, "task0001");
+        assert!(find_run(&conn, "zzz").unwrap().is_none());
+        // `%` and `_` in a prefix match literally.
+        assert!(find_run(&conn, "%").unwrap().is_none());
+        assert!(find_run(&conn, "depl_y").unwrap().is_none());
+    }
+
+    #[test]
+    fn results_survive_the_db_including_pre_results_schemas() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE runs (id TEXT PRIMARY KEY, kind TEXT NOT NULL, name TEXT NOT NULL,
+             project TEXT, project_root TEXT, parent_id TEXT, started_ms INTEGER NOT NULL,
+             duration_ms INTEGER NOT NULL, status INTEGER, success INTEGER NOT NULL,
+             log_path TEXT, detail TEXT);",
+        )
+        .unwrap();
+        init_schema(&conn).unwrap();
+
+        let mut run = sample("test0001", RunKind::Task, 1, false);
+        run.results = Some(TestResults {
+            parser: "cargo-test".to_string(),
+            passed: 4,
+            failed: 1,
+            failures: vec!["api::tests::login".to_string()],
+            ..Default::default()
+        });
+        insert_run(&conn, &run).unwrap();
+        insert_run(&conn, &sample("test0002", RunKind::Task, 2, true)).unwrap();
+
+        let found = find_run(&conn, "test0001").unwrap().unwrap();
+        assert_eq!(found.results, run.results);
+        assert!(
+            find_run(&conn, "test0002")
+                .unwrap()
+                .unwrap()
+                .results
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn latest_named_under_matches_root_and_subdirectories() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let mut root_run = sample("run00001", RunKind::Task, 1, true);
+        root_run.project_root = Some("/code/web".to_string());
+        insert_run(&conn, &root_run).unwrap();
+        let mut nested = sample("run00002", RunKind::Task, 2, false);
+        nested.project_root = Some("/code/web/app".to_string());
+        insert_run(&conn, &nested).unwrap();
+        let mut sibling = sample("run00003", RunKind::Task, 3, true);
+        sibling.project_root = Some("/code/website".to_string());
+        insert_run(&conn, &sibling).unwrap();
+
+        let latest = latest_named_under(&conn, RunKind::Task, "dev", Path::new("/code/web"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.id, "run00002");
+        assert!(
+            latest_named_under(&conn, RunKind::Task, "build", Path::new("/code/web"))
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -16,6 +16,7 @@ pub fn run(cmd: SecretsCommand) -> Result<()> {
     match cmd.action {
         SecretsAction::List(opts) => list(opts),
         SecretsAction::Pull(opts) => pull(opts),
+        SecretsAction::Quarantine(opts) => crate::secret_quarantine::run(opts),
     }
 }
 
@@ -0,0 +1,255 @@
+//! File-triggered restarts for supervised services (`restart_on` on `[[daemons]]` and
+//! `[[servers]]`).
+//!
+//! The supervisor starts one watcher per managed service that declares `restart_on`. Changes
+//! are debounced, filtered through the globs, and turned into a stop/start of the service;
+//! restarts are at least `restart_cooldown` apart, and a change that lands during the
+//! cooldown restarts the service once it ends.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use ignore::overrides::{Override, OverrideBuilder};
+use notify::RecursiveMode;
+use notify_debouncer_mini::{DebouncedEvent, new_debouncer};
+
+use crate::config::{self, DaemonConfig};
+use crate::{daemon, time_window};
+
+const DEBOUNCE: Duration = Duration::from_millis(500);
+const DEFAULT_COOLDOWN: Duration = Duration::from_secs(5);
+/// Directories whose churn never restarts a service.
+const IGNORED_DIRS: &[&str] = &[".git", "node_modules", "target", ".flow"];
+
+/// A running watcher; dropping it stops the watch thread.
+pub struct ServiceWatch {
+    /// `restart_on` and root the watch was started with, to notice config changes.
+    spec: (Vec<String>, PathBuf),
+    shutdown: Option<Sender<()>>,
+    join: Option<thread::JoinHandle<()>>,
+}
+
+impl ServiceWatch {
+    /// Start watching for `daemon`, or `None` when it has no `restart_on`.
+    pub fn spawn(daemon: &DaemonConfig, config_path: Option<&Path>) -> Result<Option<Self>> {
+        if daemon.restart_on.is_empty() {
+            return Ok(None);
+        }
+        let root = watch_root(daemon, config_path).with_context(|| {
+            format!(
+                "daemon '{}' sets restart_on but has no working_dir or project flow.toml",
+                daemon.name
+            )
+        })?;
+        // Event paths arrive canonicalized; match against the canonical root.
+        let canonical_root = root.canonicalize().unwrap_or_else(|_| root.clone());
+        let matcher = build_matcher(&canonical_root, &daemon.restart_on)?;
+        let cooldown = match daemon.restart_cooldown.as_deref() {
+            Some(raw) => {
+                let ms = time_window::parse_since(raw).with_context(|| {
+                    format!("daemon '{}': invalid restart_cooldown", daemon.name)
+                })?;
+                Duration::from_millis(ms.max(0) as u64)
+            }
+            None => DEFAULT_COOLDOWN,
+        };
+
+        let (shutdown_tx, shutdown_rx) = mpsc::channel();
+        let name = daemon.name.clone();
+        let config_path = config_path.map(Path::to_path_buf);
+        let join = thread::spawn(move || {
+            let watched = Watched {
+                name,
+                config_path,
+                root: canonical_root,
+                matcher,
+                cooldown,
+            };
+            if let Err(err) = watched.run(shutdown_rx) {
+                eprintln!(
+                    "WARN restart_on watcher for {} stopped: {err:#}",
+                    watched.name
+                );
+            }
+        });
+
+        Ok(Some(Self {
+            spec: (daemon.restart_on.clone(), root),
+            shutdown: Some(shutdown_tx),
+            join: Some(join),
+        }))
+    }
+
+    /// Whether this watch still matches the daemon's current config.
+    pub fn matches(&self, daemon: &DaemonConfig, config_path: Option<&Path>) -> bool {
+        watch_root(daemon, config_path)
+            .is_some_and(|root| self.spec == (daemon.restart_on.clone(), root))
+    }
+}
+
+impl Drop for ServiceWatch {
+    fn drop(&mut self) {
+        if let Some(tx) = self.shutdown.take() {
+            let _ = tx.send(());
+        }
+        if let Some(handle) = self.join.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+struct Watched {
+    name: String,
+    config_path: Option<PathBuf>,
+    root: PathBuf,
+    matcher: Override,
+    cooldown: Duration,
+}
+
+impl Watched {
+    fn run(&self, shutdown: Receiver<()>) -> Result<()> {
+        let (event_tx, event_rx) = mpsc::channel();
+        let mut debouncer =
+            new_debouncer(DEBOUNCE, event_tx).context("failed to initialize file watcher")?;
+        debouncer
+            .watcher()
+            .watch(&self.root, RecursiveMode::Recursive)
+            .with_context(|| format!("failed to watch {}", self.root.display()))?;
+
+        let mut last_restart: Option<Instant> = None;
+        let mut pending: Option<PathBuf> = None;
+        loop {
+            if shutdown.try_recv().is_ok() {
+                return Ok(());
+            }
+            match event_rx.recv_timeout(Duration::from_millis(200)) {
+                Ok(Ok(events)) => {
+                    if let Some(path) = self.first_match(&events) {
+                        pending = Some(path);
+                    }
+                }
+                Ok(Err(err)) => eprintln!("WARN restart_on watcher for {}: {err}", self.name),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+
+            if pending.is_some()
+                && last_restart.is_none_or(|at| at.elapsed() >= self.cooldown)
+                && let Some(path) = pending.take()
+            {
+                let changed = path.strip_prefix(&self.root).unwrap_or(&path);
+                println!(
+                    "supervisor: {} changed, restarting {}",
+                    changed.display(),
+                    self.name
+                );
+                self.restart();
+                last_restart = Some(Instant::now());
+            }
+        }
+    }
+
+    fn first_match(&self, events: &[DebouncedEvent]) -> Option<PathBuf> {
+        events
+            .iter()
+            .find(|event| is_restart_trigger(&self.matcher, &self.root, &event.path))
+            .map(|event| event.path.clone())
+    }
+
+    fn restart(&self) {
+        let config_path = self.config_path.as_deref();
+        daemon::stop_daemon_with_path(&self.name, config_path).ok();
+        thread::sleep(Duration::from_millis(300));
+        if let Err(err) = daemon::start_daemon_with_path(&self.name, config_path) {
+            eprintln!(
+                "WARN failed to restart {} after file change: {err}",
+                self.name
+            );
+        }
+    }
+}
+
+/// `working_dir` when set, else the directory of the project flow.toml.
+fn watch_root(daemon: &DaemonConfig, config_path: Option<&Path>) -> Option<PathBuf> {
+    match &daemon.working_dir {
+        Some(dir) => Some(config::expand_path(dir)),
+        None => config_path?.parent().map(Path::to_path_buf),
+    }
+}
+
+fn build_matcher(root: &Path, globs: &[String]) -> Result<Override> {
+    let mut builder = OverrideBuilder::new(root);
+    for glob in globs {
+        builder
+            .add(glob.trim_start_matches("./"))
+            .with_context(|| format!("invalid restart_on glob '{glob}'"))?;
+    }
+    Ok(builder.build()?)
+}
+
+fn is_restart_trigger(matcher: &Override, root: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return false;
+    };
+    if relative.components().any(|part| {
+        part.as_os_str()
+            .to_str()
+            .is_some_and(|part| IGNORED_DIRS.contains(&part))
+    }) {
+        return false;
+    }
+    matcher.matched(relative, path.is_dir()).is_whitelist()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_globs_relative_to_the_root() {
+        let root = Path::new("/repo/api");
+        let matcher = build_matcher(
+            root,
+            &["src/**/*.rs".to_string(), "./Cargo.toml".to_string()],
+        )
+        .unwrap();
+        assert!(is_restart_trigger(
+            &matcher,
+            root,
+            &root.join("src/http/routes.rs")
+        ));
+        assert!(is_restart_trigger(&matcher, root, &root.join("Cargo.toml")));
+        assert!(!is_restart_trigger(&matcher, root, &root.join("README.md")));
+        assert!(!is_restart_trigger(
+            &matcher,
+            root,
+            &root.join("target/debug/build.rs")
+        ));
+        assert!(!is_restart_trigger(
+            &matcher,
+            root,
+            Path::new("/elsewhere/src/main.rs")
+        ));
+    }
+
+    #[test]
+    fn watch_root_prefers_working_dir() {
+        let mut daemon: DaemonConfig =
+            toml::from_str("name = \"api\"\nbinary = \"cargo\"\nrestart_on = [\"src/**\"]")
+                .unwrap();
+        let flow_toml = Path::new("/repo/flow.toml");
+        assert_eq!(
+            watch_root(&daemon, Some(flow_toml)),
+            Some(PathBuf::from("/repo"))
+        );
+        assert_eq!(watch_root(&daemon, None), None);
+        daemon.working_dir = Some("/srv/api".to_string());
+        assert_eq!(
+            watch_root(&daemon, Some(flow_toml)),
+            Some(PathBuf::from("/srv/api"))
+        );
+    }
+}
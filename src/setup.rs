@@ -2,7 +2,6 @@ use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::io::{self, IsTerminal, Write};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
 
 use anyhow::{Context, Result};
 use crossterm::event::{self, Event as CEvent, KeyCode};
@@ -13,24 +12,24 @@ use serde::Deserialize;
 use crate::{
     agents,
     cli::{SetupOpts, SetupTarget, TaskRunOpts},
-    config, deploy, docs, skills, start,
+    config, config_edit, dep_installer, deploy, docs, flow_toml_advisor, monorepo, skills, start,
     tasks::{self, load_project_config},
 };
 
 pub fn run(opts: SetupOpts) -> Result<()> {
     let (project_root, config_path) = resolve_project_root(&opts.config)?;
+    let no_ai = opts.no_ai || env_flag("FLOW_NO_AI");
     let mut created_flow_toml = false;
-    let mut upgraded_flow_toml = false;
 
     match opts.target {
         Some(SetupTarget::Docs) => {
             return docs::create_docs_scaffold_at(&project_root, false);
         }
         Some(SetupTarget::Deploy) => {
-            return setup_deploy(&project_root, &config_path);
+            return setup_deploy(&project_root, &config_path, no_ai);
         }
         Some(SetupTarget::Release) => {
-            return setup_release(&project_root, &config_path);
+            return setup_release(&project_root, &config_path, no_ai);
         }
         None => {}
     }
@@ -48,14 +47,16 @@ pub fn run(opts: SetupOpts) -> Result<()> {
         created_flow_toml = true;
     }
     if !created_flow_toml {
-        match maybe_upgrade_existing_flow_toml(&project_root, &config_path) {
-            Ok(true) => {
-                upgraded_flow_toml = true;
-                println!("Updated flow.toml with Codex-first baseline sections.");
+        match flow_toml_advisor::analyze_file(&config_path) {
+            Ok(advice) if advice.has_fixes() => {
+                println!(
+                    "flow.toml has {} suggested upgrade(s); preview with `f doctor`, apply with `f doctor --fix`.",
+                    advice.fix_count()
+                );
             }
-            Ok(false) => {}
+            Ok(_) => {}
             Err(err) => {
-                eprintln!("⚠ failed to update flow.toml baseline: {err}");
+                eprintln!("⚠ failed to check flow.toml upgrades: {err:#}");
             }
         }
     }
@@ -80,16 +81,8 @@ pub fn run(opts: SetupOpts) -> Result<()> {
         }
     }
 
-    if upgraded_flow_toml {
-        skills::maybe_reload_codex_skills(
-            &project_root,
-            cfg.skills.as_ref(),
-            "setup baseline upgrade",
-        );
-    }
-
     ensure_bike_gitignore(&project_root)?;
-    ensure_project_dependencies(&cfg)?;
+    ensure_project_dependencies(&cfg, &project_root)?;
     ensure_pnpm_only_built_deps(&project_root)?;
 
     if tasks::find_task(&cfg, "setup").is_some() {
@@ -102,6 +95,9 @@ pub fn run(opts: SetupOpts) -> Result<()> {
             delegate_to_hub: false,
             hub_host: std::net::IpAddr::from([127, 0, 0, 1]),
             hub_port: 9050,
+            priority: 0,
+            print_env: false,
+            yes: false,
             name: "setup".to_string(),
             args: Vec::new(),
         });
@@ -135,6 +131,18 @@ pub fn run(opts: SetupOpts) -> Result<()> {
     Ok(())
 }
 
+fn env_flag(name: &str) -> bool {
+    std::env::var(name)
+        .ok()
+        .map(|value| {
+            matches!(
+                value.trim().to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        })
+        .unwrap_or(false)
+}
+
 fn maybe_run_existing_setup_task(config_path: &Path) -> Result<bool> {
     if !config_path.exists() {
         return Ok(false);
@@ -150,6 +158,9 @@ fn maybe_run_existing_setup_task(config_path: &Path) -> Result<bool> {
         delegate_to_hub: false,
         hub_host: std::net::IpAddr::from([127, 0, 0, 1]),
         hub_port: 9050,
+        priority: 0,
+        print_env: false,
+        yes: false,
         name: "setup".to_string(),
         args: Vec::new(),
     })?;
@@ -187,23 +198,35 @@ fn ensure_bike_gitignore(project_root: &Path) -> Result<()> {
     add_gitignore_entry(project_root, ".ai/review-log.jsonl")
 }
 
-fn ensure_project_dependencies(cfg: &config::Config) -> Result<()> {
+fn ensure_project_dependencies(cfg: &config::Config, project_root: &Path) -> Result<()> {
     if cfg.dependencies.is_empty() {
         return Ok(());
     }
 
-    let mut commands = Vec::new();
-    for spec in cfg.dependencies.values() {
-        spec.extend_commands(&mut commands);
-    }
-
-    let mut missing = std::collections::BTreeSet::new();
-    for command in commands {
-        if which::which(&command).is_err() {
-            missing.insert(command);
+    let preferred = dep_installer::preferred_backend(cfg);
+    let checks = dep_installer::check_dependencies(cfg);
+    for check in &checks {
+        match &check.state {
+            dep_installer::DepState::Outdated { found, min } => println!(
+                "⚠️  {} {} is older than required {} (try: {})",
+                check.command,
+                found,
+                min,
+                dep_installer::installer_hint(&check.command, Some(min), preferred)
+            ),
+            dep_installer::DepState::Unknown { min } => println!(
+                "⚠️  Could not read {} version (need >= {})",
+                check.command, min
+            ),
+            _ => {}
         }
     }
 
+    let missing: std::collections::BTreeSet<String> = checks
+        .into_iter()
+        .filter(|check| check.state == dep_installer::DepState::Missing)
+        .map(|check| check.command)
+        .collect();
     if missing.is_empty() {
         return Ok(());
     }
@@ -213,70 +236,12 @@ fn ensure_project_dependencies(cfg: &config::Config) -> Result<()> {
         missing.iter().cloned().collect::<Vec<_>>().join(", ")
     );
 
-    if !brew_available() {
-        println!("Homebrew not found. Install missing deps manually.");
-        return Ok(());
-    }
-
-    let mut packages = std::collections::BTreeSet::new();
-    for command in &missing {
-        if let Some(pkg) = brew_package_for_command(command) {
-            packages.insert(pkg);
-        } else {
-            println!(
-                "  - No brew mapping for '{}'; install it manually.",
-                command
-            );
-        }
-    }
-
-    if packages.is_empty() {
-        return Ok(());
-    }
-
-    println!(
-        "Installing missing deps with Homebrew: {}",
-        packages.iter().cloned().collect::<Vec<_>>().join(", ")
-    );
-
-    for pkg in packages {
-        let status = Command::new("brew")
-            .args(["install", &pkg])
-            .stdin(Stdio::inherit())
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .status()
-            .with_context(|| format!("failed to run brew install {}", pkg))?;
-        if !status.success() {
-            println!("  - brew install {} failed; install it manually.", pkg);
-        }
-    }
-
-    Ok(())
-}
-
-fn brew_available() -> bool {
-    Command::new("brew")
-        .arg("--version")
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
-        .map(|s| s.success())
-        .unwrap_or(false)
-}
-
-fn brew_package_for_command(command: &str) -> Option<String> {
-    match command {
-        "pnpm" => Some("pnpm".to_string()),
-        "yarn" => Some("yarn".to_string()),
-        "bun" => Some("bun".to_string()),
-        "node" | "npm" => Some("node".to_string()),
-        "python" | "python3" => Some("python".to_string()),
-        "go" => Some("go".to_string()),
-        "rustc" | "cargo" => Some("rust".to_string()),
-        "wasm-pack" => Some("wasm-pack".to_string()),
-        _ => None,
-    }
+    dep_installer::install_missing(
+        project_root,
+        &missing,
+        &cfg.pinned_dependency_versions(),
+        preferred,
+    )
 }
 
 fn ensure_pnpm_only_built_deps(project_root: &Path) -> Result<()> {
@@ -393,7 +358,7 @@ fn resolve_project_root(config_path: &PathBuf) -> Result<(PathBuf, PathBuf)> {
     Ok((root, resolved))
 }
 
-fn setup_deploy(project_root: &Path, config_path: &Path) -> Result<()> {
+fn setup_deploy(project_root: &Path, config_path: &Path, no_ai: bool) -> Result<()> {
     let server_reason = detect_server_project(project_root);
     let auto_mode = server_reason.is_some();
 
@@ -401,11 +366,11 @@ fn setup_deploy(project_root: &Path, config_path: &Path) -> Result<()> {
         if auto_mode {
             create_flow_toml_auto(project_root, config_path)?;
         } else {
-            create_flow_toml_interactive(project_root, config_path)?;
+            create_flow_toml_interactive(project_root, config_path, no_ai)?;
         }
     }
 
-    let mut flow_content = fs::read_to_string(config_path).unwrap_or_default();
+    let flow_content = fs::read_to_string(config_path).unwrap_or_default();
     if has_host_section(&flow_content) {
         if auto_mode {
             repair_existing_host_config(project_root, config_path, &flow_content)?;
@@ -433,7 +398,7 @@ fn setup_deploy(project_root: &Path, config_path: &Path) -> Result<()> {
         }
         apply_server_template(&mut defaults, template.as_ref(), project_root);
 
-        if !auto_mode && is_tty && prompt_yes_no("Use AI to draft host config?", true)? {
+        if !auto_mode && is_tty && !no_ai && prompt_yes_no("Use AI to draft host config?", true)? {
             println!("Generating host config with AI...");
             io::stdout().flush()?;
             let result = generate_host_config_with_agent(project_root, None);
@@ -588,8 +553,7 @@ fn setup_deploy(project_root: &Path, config_path: &Path) -> Result<()> {
         ssl,
     };
 
-    let host_section = render_host_section(&host_cfg);
-    flow_content = append_section(&flow_content, &host_section);
+    let flow_content = write_host_section(&flow_content, &host_cfg)?;
     fs::write(config_path, flow_content)
         .with_context(|| format!("failed to write {}", config_path.display()))?;
 
@@ -598,12 +562,12 @@ fn setup_deploy(project_root: &Path, config_path: &Path) -> Result<()> {
     Ok(())
 }
 
-fn setup_release(project_root: &Path, config_path: &Path) -> Result<()> {
+fn setup_release(project_root: &Path, config_path: &Path, no_ai: bool) -> Result<()> {
     if !config_path.exists() {
-        create_flow_toml_interactive(project_root, config_path)?;
+        create_flow_toml_interactive(project_root, config_path, no_ai)?;
     }
 
-    let mut flow_content = fs::read_to_string(config_path).unwrap_or_default();
+    let flow_content = fs::read_to_string(config_path).unwrap_or_default();
     if has_host_section(&flow_content) {
         println!("flow.toml already includes [host] configuration.");
         return Ok(());
@@ -663,8 +627,7 @@ fn setup_release(project_root: &Path, config_path: &Path) -> Result<()> {
         ssl: defaults.ssl,
     };
 
-    let host_section = render_host_section(&host_cfg);
-    flow_content = append_section(&flow_content, &host_section);
+    let flow_content = write_host_section(&flow_content, &host_cfg)?;
     fs::write(config_path, flow_content)
         .with_context(|| format!("failed to write {}", config_path.display()))?;
 
@@ -673,10 +636,17 @@ fn setup_release(project_root: &Path, config_path: &Path) -> Result<()> {
     Ok(())
 }
 
-fn create_flow_toml_interactive(project_root: &Path, config_path: &Path) -> Result<()> {
+/// Create flow.toml, offering AI generation on a TTY.
+/// With `no_ai` (or without a TTY) the detected-defaults template is written as-is,
+/// so the result only depends on the project files.
+fn create_flow_toml_interactive(
+    project_root: &Path,
+    config_path: &Path,
+    no_ai: bool,
+) -> Result<()> {
     println!("No flow.toml found. Let's create one.");
 
-    if !io::stdin().is_terminal() {
+    if no_ai || !io::stdin().is_terminal() {
         let content = default_flow_template(project_root);
         write_flow_toml(config_path, &content)?;
         return Ok(());
@@ -726,10 +696,7 @@ fn create_flow_toml_interactive(project_root: &Path, config_path: &Path) -> Resu
     }
 
     if content.is_none() {
-        let defaults = suggested_commands(project_root);
-        let setup_cmd = defaults.setup.unwrap_or_default();
-        let dev_cmd = defaults.dev.unwrap_or_default();
-        content = Some(render_flow_toml(&setup_cmd, &dev_cmd, defaults.deps));
+        content = Some(default_flow_template(project_root));
         println!("Using detected defaults. Edit flow.toml if needed.");
     }
 
@@ -758,24 +725,6 @@ fn create_flow_toml_auto(project_root: &Path, config_path: &Path) -> Result<()>
     Ok(())
 }
 
-fn maybe_upgrade_existing_flow_toml(project_root: &Path, config_path: &Path) -> Result<bool> {
-    if !config_path.exists() {
-        return Ok(false);
-    }
-
-    let current = fs::read_to_string(config_path)
-        .with_context(|| format!("failed to read {}", config_path.display()))?;
-    let current = ensure_trailing_newline(current);
-    let enable_bun_testing_gate = detect_bun_context(project_root, &current);
-    let updated = ensure_codex_flow_baseline(&current, enable_bun_testing_gate);
-    if updated == current {
-        return Ok(false);
-    }
-
-    write_flow_toml(config_path, &updated)?;
-    Ok(true)
-}
-
 fn repair_existing_host_config(
     project_root: &Path,
     config_path: &Path,
@@ -871,17 +820,19 @@ fn repair_existing_host_config(
     }
 
     if changed {
-        let host_section = render_host_section(&HostSetupConfig {
-            dest: host_cfg.dest.unwrap_or_else(|| defaults.dest.clone()),
-            setup: host_cfg.setup,
-            run: host_cfg.run,
-            port: host_cfg.port,
-            service: host_cfg.service,
-            env_file: host_cfg.env_file,
-            domain: host_cfg.domain,
-            ssl: host_cfg.ssl,
-        });
-        let updated = replace_host_section(flow_content, &host_section);
+        let updated = write_host_section(
+            flow_content,
+            &HostSetupConfig {
+                dest: host_cfg.dest.unwrap_or_else(|| defaults.dest.clone()),
+                setup: host_cfg.setup,
+                run: host_cfg.run,
+                port: host_cfg.port,
+                service: host_cfg.service,
+                env_file: host_cfg.env_file,
+                domain: host_cfg.domain,
+                ssl: host_cfg.ssl,
+            },
+        )?;
         fs::write(config_path, updated)
             .with_context(|| format!("failed to write {}", config_path.display()))?;
         println!("Updated [host] config in flow.toml.");
@@ -1017,11 +968,34 @@ fn merge_host_config(base: deploy::HostConfig, overlay: deploy::HostConfig) -> d
         } else {
             overlay.env_keys
         },
+        env_template: overlay.env_template.or(base.env_template),
         env_project: overlay.env_project || base.env_project,
         environment: overlay.environment.or(base.environment),
         service_token: overlay.service_token.or(base.service_token),
         domain: overlay.domain.or(base.domain),
         ssl: overlay.ssl || base.ssl,
+        sudo: overlay.sudo || base.sudo,
+        allow_destructive: if overlay.allow_destructive.is_empty() {
+            base.allow_destructive
+        } else {
+            overlay.allow_destructive
+        },
+        strategy: overlay.strategy.or(base.strategy),
+        alt_port: overlay.alt_port.or(base.alt_port),
+        health_path: overlay.health_path.or(base.health_path),
+        health_timeout: overlay.health_timeout.or(base.health_timeout),
+        user: overlay.user.or(base.user),
+        packages: if overlay.packages.is_empty() {
+            base.packages
+        } else {
+            overlay.packages
+        },
+        workers: if overlay.workers.is_empty() {
+            base.workers
+        } else {
+            overlay.workers
+        },
+        ssh: overlay.ssh.or(base.ssh),
     }
 }
 
@@ -1125,93 +1099,31 @@ fn looks_like_inline_script(value: &str) -> bool {
     value.contains('\n') || value.trim_start().starts_with("#!") || value.contains("set -e")
 }
 
-fn render_host_section(cfg: &HostSetupConfig) -> String {
-    let mut out = String::from("[host]\n");
-    out.push_str(&format!("dest = \"{}\"\n", toml_escape(&cfg.dest)));
-    if let Some(setup) = &cfg.setup {
-        out.push_str(&format!("setup = \"{}\"\n", toml_escape(setup)));
-    }
-    if let Some(run) = &cfg.run {
-        out.push_str(&format!("run = \"{}\"\n", toml_escape(run)));
-    }
-    if let Some(port) = cfg.port {
-        out.push_str(&format!("port = {port}\n"));
-    }
-    if let Some(service) = &cfg.service {
-        out.push_str(&format!("service = \"{}\"\n", toml_escape(service)));
-    }
-    if let Some(env_file) = &cfg.env_file {
-        out.push_str(&format!("env_file = \"{}\"\n", toml_escape(env_file)));
-    }
-    if let Some(domain) = &cfg.domain {
-        out.push_str(&format!("domain = \"{}\"\n", toml_escape(domain)));
-    }
-    if cfg.ssl {
-        out.push_str("ssl = true\n");
-    }
-    out
+/// The `[host]` keys setup manages; `None` means the key should not be present.
+fn host_entries(cfg: &HostSetupConfig) -> Vec<(&'static str, Option<toml_edit::Value>)> {
+    let text = |value: &Option<String>| value.as_deref().map(toml_edit::Value::from);
+    vec![
+        ("dest", Some(cfg.dest.as_str().into())),
+        ("setup", text(&cfg.setup)),
+        ("run", text(&cfg.run)),
+        ("port", cfg.port.map(|port| i64::from(port).into())),
+        ("service", text(&cfg.service)),
+        ("env_file", text(&cfg.env_file)),
+        ("domain", text(&cfg.domain)),
+        ("ssl", cfg.ssl.then(|| true.into())),
+    ]
 }
 
 fn has_host_section(content: &str) -> bool {
-    content.lines().any(|line| line.trim() == "[host]")
-}
-
-fn append_section(content: &str, section: &str) -> String {
-    let mut out = content.to_string();
-    if !out.ends_with('\n') {
-        out.push('\n');
-    }
-    if !out.ends_with("\n\n") {
-        out.push('\n');
-    }
-    out.push_str(section.trim_end());
-    out.push('\n');
-    out
-}
-
-fn replace_host_section(content: &str, section: &str) -> String {
-    let mut lines: Vec<String> = content.lines().map(|line| line.to_string()).collect();
-    let had_trailing_newline = content.ends_with('\n');
-    let section_lines: Vec<String> = section
-        .trim_end()
-        .lines()
-        .map(|line| line.to_string())
-        .collect();
-
-    if let Some(start) = lines.iter().position(|line| line.trim() == "[host]") {
-        let end = find_section_end(&lines, start + 1);
-        let mut updated = Vec::new();
-        updated.extend_from_slice(&lines[..start]);
-        updated.extend(section_lines);
-        updated.extend_from_slice(&lines[end..]);
-        lines = updated;
-    } else {
-        if !lines.is_empty()
-            && !lines
-                .last()
-                .map(|line| line.trim().is_empty())
-                .unwrap_or(false)
-        {
-            lines.push(String::new());
-        }
-        lines.extend(section_lines);
-    }
-
-    let mut out = lines.join("\n");
-    if had_trailing_newline {
-        out.push('\n');
-    }
-    out
+    config_edit::parse(content).is_ok_and(|doc| doc.contains_key("host"))
 }
 
-fn find_section_end(lines: &[String], start: usize) -> usize {
-    for (idx, line) in lines.iter().enumerate().skip(start) {
-        let trimmed = line.trim();
-        if trimmed.starts_with('[') && trimmed.ends_with(']') {
-            return idx;
-        }
-    }
-    lines.len()
+/// Write the managed `[host]` keys into flow.toml, keeping any other host keys, comments,
+/// and the rest of the file as they were.
+fn write_host_section(content: &str, cfg: &HostSetupConfig) -> Result<String> {
+    let mut doc = config_edit::parse(content).context("failed to parse flow.toml")?;
+    config_edit::upsert_table(&mut doc, "host", host_entries(cfg))?;
+    Ok(doc.to_string())
 }
 
 fn ensure_setup_script(
@@ -1526,6 +1438,7 @@ fn generate_flow_toml_with_agent(project_root: &Path, hint: Option<&str>) -> Res
     prompt.push_str("- Add descriptions and shortcuts for setup (s) and dev (d).\n");
     prompt.push_str("- Use [deps] for required binaries.\n");
     prompt.push_str("- If a task prompts for input, set interactive = true.\n");
+    prompt.push_str("- If a lockfile exists, add a `verify` task that fails on lockfile drift without installing (cargo metadata --locked --format-version 1 > /dev/null, pnpm install --frozen-lockfile --lockfile-only, yarn/bun install --frozen-lockfile, npm ci --dry-run).\n");
     prompt.push_str("- Include Codex baseline sections: [skills], [skills.codex], [commit.skill_gate], and [commit.skill_gate.min_version].\n");
     prompt.push_str(
         "- Output ONLY the flow.toml content in a ```toml code block, no other commentary.\n\n",
@@ -1630,6 +1543,7 @@ fn generate_flow_toml_with_agent_streaming(
     prompt.push_str("- Add descriptions and shortcuts for setup (s) and dev (d).\n");
     prompt.push_str("- Use [deps] for required binaries.\n");
     prompt.push_str("- If a task prompts for input, set interactive = true.\n");
+    prompt.push_str("- If a lockfile exists, add a `verify` task that fails on lockfile drift without installing (cargo metadata --locked --format-version 1 > /dev/null, pnpm install --frozen-lockfile --lockfile-only, yarn/bun install --frozen-lockfile, npm ci --dry-run).\n");
     prompt.push_str("- Include Codex baseline sections: [skills], [skills.codex], [commit.skill_gate], and [commit.skill_gate.min_version].\n");
     prompt.push_str(
         "- Output ONLY the flow.toml content in a ```toml code block, no other commentary.\n\n",
@@ -2132,7 +2046,93 @@ enum DepSpec {
     Multiple(&'static str, &'static [&'static str]),
 }
 
+/// Reproducible dev environment that setup/dev commands should run inside.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DevEnvironment {
+    Flox,
+    Devenv,
+    NixFlake,
+}
+
+impl DevEnvironment {
+    /// Prefer flox, then devenv (which usually ships a flake.nix too), then a bare flake.
+    fn detect(project_root: &Path) -> Option<Self> {
+        if project_root.join(".flox/env/manifest.toml").exists() {
+            Some(Self::Flox)
+        } else if project_root.join("devenv.nix").exists()
+            || project_root.join("devenv.yaml").exists()
+        {
+            Some(Self::Devenv)
+        } else if project_root.join("flake.nix").exists() {
+            Some(Self::NixFlake)
+        } else {
+            None
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Flox => "flox environment (.flox/)",
+            Self::Devenv => "devenv environment (devenv.nix)",
+            Self::NixFlake => "nix flake (flake.nix)",
+        }
+    }
+
+    /// Binary that provides the environment; the only `[deps]` entry the template needs.
+    fn tool(self) -> &'static str {
+        match self {
+            Self::Flox => "flox",
+            Self::Devenv => "devenv",
+            Self::NixFlake => "nix",
+        }
+    }
+
+    fn prefix(self) -> &'static str {
+        match self {
+            Self::Flox => "flox activate --",
+            Self::Devenv => "devenv shell --",
+            Self::NixFlake => "nix develop --command",
+        }
+    }
+
+    /// Run `command` inside the environment, through `sh -c` when it uses shell syntax.
+    fn wrap(self, command: &str) -> String {
+        if command.contains(['&', '|', ';', '<', '>', '$']) {
+            format!("{} sh -c {}", self.prefix(), shell_words::quote(command))
+        } else {
+            format!("{} {command}", self.prefix())
+        }
+    }
+
+    /// Wrap the detected commands and swap their deps for the environment tool, since the
+    /// toolchain comes from the environment rather than the host PATH.
+    fn apply(self, commands: SuggestedCommands) -> SuggestedCommands {
+        let setup = match commands.setup {
+            Some(setup) => self.wrap(&setup),
+            None => self.wrap(&format!("echo '{} ready'", self.tool())),
+        };
+        let dev = match (commands.dev, self) {
+            (Some(dev), _) => Some(self.wrap(&dev)),
+            (None, Self::Devenv) => Some("devenv up".to_string()),
+            (None, _) => None,
+        };
+        SuggestedCommands {
+            setup: Some(setup),
+            dev,
+            deps: vec![DepSpec::Single(self.tool(), self.tool())],
+        }
+    }
+}
+
 fn suggested_commands(project_root: &Path) -> SuggestedCommands {
+    let commands = suggested_language_commands(project_root);
+    match DevEnvironment::detect(project_root) {
+        Some(env) => env.apply(commands),
+        None => commands,
+    }
+}
+
+fn suggested_language_commands(project_root: &Path) -> SuggestedCommands {
     // Check root level first
     let cargo = project_root.join("Cargo.toml").exists();
     if cargo {
@@ -2430,10 +2430,65 @@ fn has_catalog_protocol(value: &serde_json::Value) -> bool {
 }
 
 fn default_flow_template(project_root: &Path) -> String {
+    if let Some(workspace) = monorepo::detect(project_root) {
+        return ensure_codex_flow_baseline(&monorepo::render_flow_toml(&workspace), false);
+    }
     let defaults = suggested_commands(project_root);
     let setup_cmd = defaults.setup.unwrap_or_default();
     let dev_cmd = defaults.dev.unwrap_or_default();
-    render_flow_toml(&setup_cmd, &dev_cmd, defaults.deps)
+    let verify_cmd = suggested_verify_command(project_root);
+    render_flow_toml(&setup_cmd, &dev_cmd, verify_cmd.as_deref(), defaults.deps)
+}
+
+/// Fails when Cargo.lock no longer matches the manifests, without building anything.
+pub(crate) const CARGO_LOCK_CHECK: &str = "cargo metadata --locked --format-version 1 > /dev/null";
+/// Fails when pnpm-lock.yaml is stale, without touching node_modules.
+pub(crate) const PNPM_LOCK_CHECK: &str = "pnpm install --frozen-lockfile --lockfile-only";
+
+/// Command for the generated `verify` task: a lockfile drift check for the toolchain that
+/// `suggested_commands` picked, or `None` when the project has no lockfile to check.
+fn suggested_verify_command(project_root: &Path) -> Option<String> {
+    let command = if project_root.join("Cargo.toml").exists() {
+        cargo_lock_check(project_root).map(str::to_string)
+    } else if project_root.join("package.json").exists() {
+        node_lock_check(project_root).map(str::to_string)
+    } else {
+        let subdir_projects = find_subdir_projects(project_root);
+        if let Some(subdir) = subdir_projects.cargo {
+            cargo_lock_check(&project_root.join(&subdir))
+                .map(|check| format!("cd {subdir} && {check}"))
+        } else if let Some(subdir) = subdir_projects.package {
+            node_lock_check(&project_root.join(&subdir))
+                .map(|check| format!("cd {subdir} && {check}"))
+        } else {
+            None
+        }
+    }?;
+    Some(match DevEnvironment::detect(project_root) {
+        Some(env) => env.wrap(&command),
+        None => command,
+    })
+}
+
+fn cargo_lock_check(project_path: &Path) -> Option<&'static str> {
+    project_path
+        .join("Cargo.lock")
+        .exists()
+        .then_some(CARGO_LOCK_CHECK)
+}
+
+/// Frozen-lockfile check for whichever package manager's lockfile is present.
+fn node_lock_check(project_path: &Path) -> Option<&'static str> {
+    [
+        ("pnpm-lock.yaml", PNPM_LOCK_CHECK),
+        ("yarn.lock", "yarn install --frozen-lockfile"),
+        ("bun.lock", "bun install --frozen-lockfile"),
+        ("bun.lockb", "bun install --frozen-lockfile"),
+        ("package-lock.json", "npm ci --dry-run"),
+    ]
+    .into_iter()
+    .find(|(lockfile, _)| project_path.join(lockfile).exists())
+    .map(|(_, check)| check)
 }
 
 fn project_hints(project_root: &Path) -> Vec<String> {
@@ -2450,6 +2505,10 @@ fn project_hints(project_root: &Path) -> Vec<String> {
         "Makefile",
         "justfile",
         "Dockerfile",
+        ".flox/env/manifest.toml",
+        "devenv.nix",
+        "devenv.yaml",
+        "flake.nix",
     ];
     for name in candidates {
         if project_root.join(name).exists() {
@@ -2480,6 +2539,35 @@ fn project_hints(project_root: &Path) -> Vec<String> {
 }
 
 fn project_guidance(project_root: &Path) -> Option<String> {
+    let guidance = language_guidance(project_root);
+    let Some(env) = DevEnvironment::detect(project_root) else {
+        return guidance;
+    };
+    let env_note = format!(
+        "Detected {}. Run setup/dev commands inside it ({} <cmd>) and list only {} in [deps].",
+        env.label(),
+        env.prefix(),
+        env.tool()
+    );
+    Some(match guidance {
+        Some(guidance) => format!("{guidance} {env_note}"),
+        None => env_note,
+    })
+}
+
+fn language_guidance(project_root: &Path) -> Option<String> {
+    if let Some(workspace) = monorepo::detect(project_root) {
+        let packages = workspace
+            .packages
+            .iter()
+            .map(|pkg| format!("{} ({})", pkg.name, pkg.dir))
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Some(format!(
+            "Detected monorepo workspace with packages: {packages}. Add per-package tasks named <package>:<action> (e.g. web:dev, api:test) plus root setup/dev/test tasks that cover the whole workspace."
+        ));
+    }
+
     let has_cargo = project_root.join("Cargo.toml").exists();
     let has_package = project_root.join("package.json").exists();
     let has_tex = has_tex_files(project_root);
@@ -2735,7 +2823,12 @@ fn command_mentions_tool(command: &str, tool: &str) -> bool {
     })
 }
 
-fn render_flow_toml(setup_cmd: &str, dev_cmd: &str, deps: Vec<DepSpec>) -> String {
+fn render_flow_toml(
+    setup_cmd: &str,
+    dev_cmd: &str,
+    verify_cmd: Option<&str>,
+    deps: Vec<DepSpec>,
+) -> String {
     let setup_cmd = setup_cmd.trim();
     let dev_cmd = dev_cmd.trim();
     let setup_cmd = if setup_cmd.is_empty() {
@@ -2787,6 +2880,13 @@ fn render_flow_toml(setup_cmd: &str, dev_cmd: &str, deps: Vec<DepSpec>) -> Strin
     if command_needs_interactive(dev_cmd) {
         out.push_str("interactive = true\n");
     }
+    if let Some(verify_cmd) = verify_cmd {
+        out.push('\n');
+        out.push_str("[[tasks]]\n");
+        out.push_str("name = \"verify\"\n");
+        out.push_str(&format!("command = \"{}\"\n", toml_escape(verify_cmd)));
+        out.push_str("description = \"Check lockfiles are in sync with their manifests\"\n");
+    }
 
     if !deps.is_empty() {
         out.push('\n');
@@ -2811,7 +2911,7 @@ fn render_flow_toml(setup_cmd: &str, dev_cmd: &str, deps: Vec<DepSpec>) -> Strin
     ensure_codex_flow_baseline(&out, enable_bun_testing_gate)
 }
 
-fn contains_toml_section(content: &str, section_header: &str) -> bool {
+pub(crate) fn contains_toml_section(content: &str, section_header: &str) -> bool {
     content.lines().any(|line| line.trim() == section_header)
 }
 
@@ -2829,7 +2929,7 @@ fn append_toml_section_if_missing(out: &mut String, section_header: &str, sectio
     out.push('\n');
 }
 
-fn ensure_codex_flow_baseline(content: &str, enable_bun_testing_gate: bool) -> String {
+pub(crate) fn ensure_codex_flow_baseline(content: &str, enable_bun_testing_gate: bool) -> String {
     let mut out = ensure_trailing_newline(content.to_string());
 
     append_toml_section_if_missing(
@@ -2864,6 +2964,16 @@ required = ["quality-bun-feature-delivery"]"#,
 quality-bun-feature-delivery = 2"#,
     );
 
+    if defines_task(&out, "verify") {
+        append_toml_section_if_missing(
+            &mut out,
+            "[commit.verify]",
+            r#"[commit.verify]
+mode = "block"
+tasks = ["verify"]"#,
+        );
+    }
+
     if enable_bun_testing_gate {
         append_toml_section_if_missing(
             &mut out,
@@ -2883,6 +2993,19 @@ max_local_gate_seconds = 20"#,
     ensure_trailing_newline(out)
 }
 
+fn defines_task(content: &str, name: &str) -> bool {
+    toml::from_str::<toml::Value>(content)
+        .ok()
+        .as_ref()
+        .and_then(|value| value.get("tasks"))
+        .and_then(toml::Value::as_array)
+        .is_some_and(|tasks| {
+            tasks
+                .iter()
+                .any(|task| task.get("name").and_then(toml::Value::as_str) == Some(name))
+        })
+}
+
 fn template_uses_bun(setup_cmd: &str, dev_cmd: &str, deps: &[DepSpec]) -> bool {
     if command_mentions_tool(setup_cmd, "bun") || command_mentions_tool(dev_cmd, "bun") {
         return true;
@@ -2898,7 +3021,7 @@ fn template_uses_bun(setup_cmd: &str, dev_cmd: &str, deps: &[DepSpec]) -> bool {
     })
 }
 
-fn detect_bun_context(project_root: &Path, content: &str) -> bool {
+pub(crate) fn detect_bun_context(project_root: &Path, content: &str) -> bool {
     if project_root.join("bun.lock").exists() || project_root.join("bun.lockb").exists() {
         return true;
     }
@@ -3085,12 +3208,44 @@ mod tests {
 
     #[test]
     fn render_flow_toml_includes_codex_skill_baseline() {
-        let toml = render_flow_toml("cargo build --locked", "cargo run", vec![]);
+        let toml = render_flow_toml("cargo build --locked", "cargo run", None, vec![]);
         assert!(toml.contains("[skills]"));
         assert!(toml.contains("[skills.codex]"));
         assert!(toml.contains("[commit.skill_gate]"));
         assert!(toml.contains("[commit.skill_gate.min_version]"));
         assert!(!toml.contains("[commit.testing]"));
+        assert!(!toml.contains("[commit.verify]"));
+    }
+
+    #[test]
+    fn lockfiles_get_a_verify_task_wired_into_the_commit_gate() {
+        let dir = tempdir().expect("tempdir");
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"demo\"\n",
+        )
+        .expect("write Cargo.toml");
+        assert_eq!(suggested_verify_command(dir.path()), None);
+
+        fs::write(dir.path().join("Cargo.lock"), "version = 4\n").expect("write Cargo.lock");
+        let toml = default_flow_template(dir.path());
+        assert!(toml.contains(&format!("command = \"{CARGO_LOCK_CHECK}\"")));
+        let parsed: config::Config = toml::from_str(&toml).expect("template parses");
+        let verify = parsed
+            .commit
+            .and_then(|commit| commit.verify)
+            .expect("commit.verify expected");
+        assert_eq!(verify.mode.as_deref(), Some("block"));
+        assert_eq!(verify.tasks, vec!["verify".to_string()]);
+
+        let web = dir.path().join("web");
+        fs::create_dir_all(&web).expect("create web");
+        fs::write(web.join("package.json"), "{}").expect("write package.json");
+        fs::write(web.join("yarn.lock"), "").expect("write yarn.lock");
+        assert_eq!(
+            suggested_verify_command(&web).as_deref(),
+            Some("yarn install --frozen-lockfile")
+        );
     }
 
     #[test]
@@ -3098,6 +3253,7 @@ mod tests {
         let toml = render_flow_toml(
             "bun install",
             "bun run dev",
+            None,
             vec![DepSpec::Single("bun", "bun")],
         );
         assert!(toml.contains("[commit.testing]"));
@@ -3106,55 +3262,60 @@ mod tests {
     }
 
     #[test]
-    fn upgrade_existing_flow_toml_adds_codex_baseline() {
+    fn dev_environments_wrap_detected_commands() {
         let dir = tempdir().expect("tempdir");
-        let config_path = dir.path().join("flow.toml");
+        fs::create_dir_all(dir.path().join(".flox/env")).expect("create .flox");
+        fs::write(dir.path().join(".flox/env/manifest.toml"), "version = 1\n")
+            .expect("write manifest");
+        fs::write(dir.path().join("flake.nix"), "{}\n").expect("write flake.nix");
         fs::write(
-            &config_path,
-            r#"version = 1
-
-[[tasks]]
-name = "setup"
-command = "echo setup"
-"#,
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"demo\"\n",
         )
-        .expect("write flow.toml");
+        .expect("write Cargo.toml");
 
-        let changed = maybe_upgrade_existing_flow_toml(dir.path(), &config_path)
-            .expect("upgrade should succeed");
-        assert!(changed, "existing file should be upgraded");
+        let commands = suggested_commands(dir.path());
+        assert_eq!(
+            commands.setup.as_deref(),
+            Some("flox activate -- cargo build --locked")
+        );
+        assert_eq!(commands.dev.as_deref(), Some("flox activate -- cargo run"));
+        assert_eq!(
+            commands.deps.iter().map(dep_name).collect::<Vec<_>>(),
+            vec!["flox"]
+        );
 
-        let updated = fs::read_to_string(&config_path).expect("read updated flow.toml");
-        assert!(updated.contains("[skills]"));
-        assert!(updated.contains("[skills.codex]"));
-        assert!(updated.contains("[commit.skill_gate]"));
-        assert!(updated.contains("[commit.skill_gate.min_version]"));
-        assert!(!updated.contains("[commit.testing]"));
+        assert_eq!(
+            DevEnvironment::NixFlake.wrap("cd app && cargo run"),
+            "nix develop --command sh -c 'cd app && cargo run'"
+        );
     }
 
     #[test]
-    fn upgrade_existing_flow_toml_adds_bun_testing_gate_in_bun_context() {
+    fn devenv_without_language_defaults_to_devenv_up() {
         let dir = tempdir().expect("tempdir");
-        let config_path = dir.path().join("flow.toml");
-        fs::write(
-            &config_path,
-            r#"version = 1
+        fs::write(dir.path().join("devenv.nix"), "{ ... }: {}\n").expect("write devenv.nix");
 
-[[tasks]]
-name = "setup"
-command = "bun install"
-"#,
+        let toml = default_flow_template(dir.path());
+        assert!(toml.contains("command = \"devenv shell -- echo 'devenv ready'\""));
+        assert!(toml.contains("command = \"devenv up\""));
+        assert!(toml.contains("devenv = \"devenv\""));
+    }
+
+    #[test]
+    fn create_flow_toml_without_ai_uses_detected_template() {
+        let dir = tempdir().expect("tempdir");
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"demo\"\n",
         )
-        .expect("write flow.toml");
-        fs::write(dir.path().join("bun.lock"), "").expect("write bun.lock");
+        .expect("write Cargo.toml");
+        let config_path = dir.path().join("flow.toml");
 
-        let changed = maybe_upgrade_existing_flow_toml(dir.path(), &config_path)
-            .expect("upgrade should succeed");
-        assert!(changed, "existing file should be upgraded");
+        create_flow_toml_interactive(dir.path(), &config_path, true).expect("create flow.toml");
 
-        let updated = fs::read_to_string(&config_path).expect("read updated flow.toml");
-        assert!(updated.contains("[commit.testing]"));
-        assert!(updated.contains("runner = \"bun\""));
+        let written = fs::read_to_string(&config_path).expect("read flow.toml");
+        assert_eq!(written, default_flow_template(dir.path()));
     }
 
     #[test]
@@ -3175,6 +3336,7 @@ command = "printf ok > setup-ran.txt"
         run(SetupOpts {
             config: config_path.clone(),
             target: None,
+            no_ai: false,
         })
         .expect("setup should delegate to project task");
 
@@ -0,0 +1,405 @@
+//! Git-hosted skills for `[skills] install` and `f skills install`.
+//!
+//! Specs are either the registry shorthand `gh:org/repo/path/to/skill@ref` or a git URL
+//! (`https://...`, `ssh://...`, `git@host:...`, `file://...`) with an optional
+//! `#path/to/skill` and `@ref`. Installed skills record their source in
+//! `.ai/skills/<name>/.flow-source.json` so `f skills update` can re-fetch them.
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Metadata file written next to SKILL.md for git-installed skills.
+pub const SOURCE_FILE: &str = ".flow-source.json";
+
+/// A parsed git skill reference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitSkillSpec {
+    pub url: String,
+    /// Directory inside the repo holding the skill (repo root when `None`).
+    pub subdir: Option<String>,
+    /// Tag, branch, or commit to fetch (remote HEAD when `None`).
+    pub reference: Option<String>,
+    /// Local skill name (last path segment).
+    pub name: String,
+}
+
+impl GitSkillSpec {
+    /// Parse a git skill spec; returns `None` for plain registry names.
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        if let Some(rest) = raw.strip_prefix("gh:") {
+            let (path, reference) = split_reference(rest);
+            let mut parts = path.split('/').filter(|part| !part.is_empty());
+            let org = parts.next()?;
+            let repo = parts.next()?;
+            let subdir: Vec<&str> = parts.collect();
+            let name = subdir.last().copied().unwrap_or(repo).to_string();
+            return Some(Self {
+                url: format!("https://github.com/{org}/{repo}.git"),
+                subdir: (!subdir.is_empty()).then(|| subdir.join("/")),
+                reference,
+                name,
+            });
+        }
+
+        let is_url = ["https://", "http://", "ssh://", "git://", "file://", "git@"]
+            .iter()
+            .any(|prefix| raw.starts_with(prefix))
+            || raw.ends_with(".git");
+        if !is_url {
+            return None;
+        }
+
+        let (url, subdir, reference) = match raw.split_once('#') {
+            Some((url, fragment)) => {
+                let (subdir, reference) = split_reference(fragment);
+                let subdir = subdir.trim_matches('/');
+                (
+                    url.to_string(),
+                    (!subdir.is_empty()).then(|| subdir.to_string()),
+                    reference,
+                )
+            }
+            None => {
+                // `git@host:org/repo.git` carries an `@` before the path; only treat an
+                // `@` in the last path segment as a ref separator.
+                let segment_start = raw.rfind('/').map(|idx| idx + 1).unwrap_or(0);
+                match raw[segment_start..].rfind('@') {
+                    Some(at) if !raw.starts_with("git@") || segment_start > 0 => {
+                        let at = segment_start + at;
+                        (raw[..at].to_string(), None, Some(raw[at + 1..].to_string()))
+                    }
+                    _ => (raw.to_string(), None, None),
+                }
+            }
+        };
+
+        let name = match &subdir {
+            Some(subdir) => subdir.rsplit('/').next().unwrap_or(subdir).to_string(),
+            None => url
+                .trim_end_matches('/')
+                .rsplit(['/', ':'])
+                .next()
+                .unwrap_or(&url)
+                .trim_end_matches(".git")
+                .to_string(),
+        };
+        if name.is_empty() {
+            return None;
+        }
+        Some(Self {
+            url,
+            subdir,
+            reference: reference.filter(|r| !r.is_empty()),
+            name,
+        })
+    }
+
+    /// Canonical spec string (stored in the source metadata).
+    pub fn display(&self) -> String {
+        let mut spec = self.url.clone();
+        if let Some(subdir) = &self.subdir {
+            spec.push('#');
+            spec.push_str(subdir);
+        }
+        if let Some(reference) = &self.reference {
+            spec.push('@');
+            spec.push_str(reference);
+        }
+        spec
+    }
+}
+
+fn split_reference(value: &str) -> (&str, Option<String>) {
+    match value.rsplit_once('@') {
+        Some((path, reference)) => (path, Some(reference.to_string())),
+        None => (value, None),
+    }
+}
+
+/// Where an installed skill came from.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InstalledSource {
+    pub spec: String,
+    pub commit: String,
+    pub checksum: String,
+}
+
+pub fn read_installed(skill_dir: &Path) -> Option<InstalledSource> {
+    let content = fs::read_to_string(skill_dir.join(SOURCE_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// A skill checked out into a temporary directory.
+pub struct FetchedSkill {
+    _checkout: tempfile::TempDir,
+    pub dir: PathBuf,
+    pub commit: String,
+    pub checksum: String,
+}
+
+/// Shallow-fetch `spec` and locate the skill directory inside the checkout.
+pub fn fetch(spec: &GitSkillSpec) -> Result<FetchedSkill> {
+    if let Some(subdir) = &spec.subdir
+        && Path::new(subdir)
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        bail!("invalid skill path '{subdir}': use a plain path inside the repo");
+    }
+    let checkout = tempfile::tempdir().context("failed to create temp dir for skill fetch")?;
+    let root = checkout.path();
+    let reference = spec.reference.as_deref().unwrap_or("HEAD");
+
+    git(root, &["init", "-q"])?;
+    git(root, &["remote", "add", "origin", &spec.url])?;
+    git(root, &["fetch", "-q", "--depth", "1", "origin", reference])
+        .with_context(|| format!("failed to fetch {} at {}", spec.url, reference))?;
+    git(root, &["checkout", "-q", "FETCH_HEAD"])?;
+    let commit = git(root, &["rev-parse", "HEAD"])?;
+
+    let candidates: Vec<PathBuf> = match &spec.subdir {
+        Some(subdir) => vec![
+            root.join(subdir),
+            root.join("skills").join(subdir),
+            root.join(".ai/skills").join(subdir),
+        ],
+        None => vec![root.to_path_buf()],
+    };
+    // A symlinked skill dir could point anywhere on this machine.
+    let Some(dir) = candidates
+        .into_iter()
+        .find(|dir| !is_symlink(dir) && has_skill_file(dir))
+    else {
+        bail!(
+            "no SKILL.md found for '{}' in {} at {}",
+            spec.name,
+            spec.url,
+            reference
+        );
+    };
+    let checksum = checksum_dir(&dir)?;
+
+    Ok(FetchedSkill {
+        _checkout: checkout,
+        dir,
+        commit,
+        checksum,
+    })
+}
+
+/// Replace `dest` with the fetched skill and record its source.
+pub fn install_fetched(
+    spec: &GitSkillSpec,
+    fetched: &FetchedSkill,
+    dest: &Path,
+    expected_checksum: Option<&str>,
+) -> Result<InstalledSource> {
+    if let Some(expected) = expected_checksum
+        && normalize_checksum(expected) != fetched.checksum
+    {
+        bail!(
+            "checksum mismatch for skill '{}': expected {}, got {}",
+            spec.name,
+            normalize_checksum(expected),
+            fetched.checksum
+        );
+    }
+
+    if dest.exists() {
+        fs::remove_dir_all(dest)
+            .with_context(|| format!("failed to replace {}", dest.display()))?;
+    }
+    copy_dir(&fetched.dir, dest)?;
+
+    let source = InstalledSource {
+        spec: spec.display(),
+        commit: fetched.commit.clone(),
+        checksum: fetched.checksum.clone(),
+    };
+    fs::write(
+        dest.join(SOURCE_FILE),
+        serde_json::to_string_pretty(&source)?,
+    )?;
+    Ok(source)
+}
+
+fn normalize_checksum(value: &str) -> String {
+    let value = value.trim().to_ascii_lowercase();
+    if value.starts_with("sha256:") {
+        value
+    } else {
+        format!("sha256:{value}")
+    }
+}
+
+/// `sha256:<hex>` over every file (relative path + contents) in a skill directory.
+/// Symlinks are skipped, as in [`copy_dir`].
+pub fn checksum_dir(dir: &Path) -> Result<String> {
+    let mut files = Vec::new();
+    collect_files(dir, dir, &mut files)?;
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    for relative in files {
+        let content = fs::read(dir.join(&relative))?;
+        hasher.update(relative.as_bytes());
+        hasher.update([0]);
+        hasher.update((content.len() as u64).to_le_bytes());
+        hasher.update(&content);
+    }
+    Ok(format!("sha256:{}", hex::encode(hasher.finalize())))
+}
+
+fn collect_files(root: &Path, dir: &Path, out: &mut Vec<String>) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name();
+        if name == ".git" || name == SOURCE_FILE {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            continue;
+        }
+        if file_type.is_dir() {
+            collect_files(root, &path, out)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            out.push(relative.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    Ok(())
+}
+
+fn has_skill_file(dir: &Path) -> bool {
+    dir.join("SKILL.md").is_file() || dir.join("skill.md").is_file()
+}
+
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_symlink())
+}
+
+/// Copy a fetched skill, skipping symlinks so links to local files (`~/.ssh/...`) are
+/// never pulled into `.ai/skills`.
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        let target = to.join(entry.file_name());
+        let file_type = fs::symlink_metadata(entry.path())?.file_type();
+        if file_type.is_symlink() {
+            continue;
+        }
+        if file_type.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)
+                .with_context(|| format!("failed to copy {}", entry.path().display()))?;
+        }
+    }
+    Ok(())
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("failed to run git {}", args.join(" ")))?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_github_shorthand() {
+        let spec = GitSkillSpec::parse("gh:acme/skills/review/strict@v2").unwrap();
+        assert_eq!(spec.url, "https://github.com/acme/skills.git");
+        assert_eq!(spec.subdir.as_deref(), Some("review/strict"));
+        assert_eq!(spec.reference.as_deref(), Some("v2"));
+        assert_eq!(spec.name, "strict");
+    }
+
+    #[test]
+    fn parses_git_urls() {
+        let spec = GitSkillSpec::parse("https://example.com/team/linear-skill.git@main").unwrap();
+        assert_eq!(spec.url, "https://example.com/team/linear-skill.git");
+        assert_eq!(spec.subdir, None);
+        assert_eq!(spec.reference.as_deref(), Some("main"));
+        assert_eq!(spec.name, "linear-skill");
+
+        let spec = GitSkillSpec::parse("git@github.com:acme/skills.git#deploy@v1.2").unwrap();
+        assert_eq!(spec.url, "git@github.com:acme/skills.git");
+        assert_eq!(spec.subdir.as_deref(), Some("deploy"));
+        assert_eq!(spec.reference.as_deref(), Some("v1.2"));
+        assert_eq!(spec.display(), "git@github.com:acme/skills.git#deploy@v1.2");
+
+        let spec = GitSkillSpec::parse("git@github.com:acme/linear.git").unwrap();
+        assert_eq!(spec.reference, None);
+        assert_eq!(spec.name, "linear");
+
+        assert_eq!(GitSkillSpec::parse("linear"), None);
+    }
+
+    #[test]
+    fn checksum_ignores_source_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("SKILL.md"), "# skill\n").unwrap();
+        let before = checksum_dir(dir.path()).unwrap();
+        fs::write(dir.path().join(SOURCE_FILE), "{}").unwrap();
+        assert_eq!(checksum_dir(dir.path()).unwrap(), before);
+        assert_eq!(normalize_checksum(&before[7..].to_uppercase()), before);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_not_copied_or_hashed() {
+        let outside = tempfile::tempdir().unwrap();
+        fs::write(outside.path().join("id_rsa"), "secret\n").unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("SKILL.md"), "# skill\n").unwrap();
+        let before = checksum_dir(dir.path()).unwrap();
+        std::os::unix::fs::symlink(outside.path().join("id_rsa"), dir.path().join("x")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("linked")).unwrap();
+        assert_eq!(checksum_dir(dir.path()).unwrap(), before);
+
+        let dest = tempfile::tempdir().unwrap();
+        copy_dir(dir.path(), &dest.path().join("skill")).unwrap();
+        assert!(dest.path().join("skill/SKILL.md").is_file());
+        assert!(!dest.path().join("skill/x").exists());
+        assert!(!dest.path().join("skill/linked").exists());
+    }
+
+    #[test]
+    fn fetch_rejects_paths_outside_the_checkout() {
+        for raw in [
+            "gh:acme/skills/../../etc",
+            "file:///tmp/skills.git#./deploy",
+        ] {
+            let spec = GitSkillSpec::parse(raw).unwrap();
+            let err = fetch(&spec).err().unwrap();
+            assert!(
+                err.to_string().contains("invalid skill path"),
+                "{raw}: {err}"
+            );
+        }
+    }
+}
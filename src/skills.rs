@@ -10,10 +10,13 @@ use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result, bail};
 use serde_json::json;
+use sha2::{Digest, Sha256};
 
 use crate::cli::{SkillsAction, SkillsCommand, SkillsFetchAction, SkillsFetchCommand};
 use crate::commit::configured_codex_bin_for_workdir;
 use crate::config;
+use crate::line_diff;
+use crate::skill_source::{self, GitSkillSpec};
 use crate::start;
 
 const DEFAULT_ENV_SKILL: &str = include_str!("../.ai/skills/env/skill.md");
@@ -41,6 +44,8 @@ impl SkillsEnforceSummary {
 struct SkillSyncOptions {
     generate_openai_yaml: bool,
     task_skill_allow_implicit_invocation: bool,
+    /// Overwrite task skills that were edited by hand since the last sync.
+    overwrite_local_edits: bool,
 }
 
 impl Default for SkillSyncOptions {
@@ -48,10 +53,15 @@ impl Default for SkillSyncOptions {
         Self {
             generate_openai_yaml: true,
             task_skill_allow_implicit_invocation: false,
+            overwrite_local_edits: false,
         }
     }
 }
 
+/// Hash of the SKILL.md content flow last wrote for a task skill; lets status and sync tell
+/// stale generated files apart from hand edits.
+const TASK_SKILL_HASH_FILE: &str = ".flow-sync-hash";
+
 /// Run the skills subcommand.
 pub fn run(cmd: SkillsCommand) -> Result<()> {
     let action = cmd.action.unwrap_or(SkillsAction::List);
@@ -63,9 +73,11 @@ pub fn run(cmd: SkillsCommand) -> Result<()> {
         SkillsAction::Edit { name } => edit_skill(&name)?,
         SkillsAction::Remove { name } => remove_skill(&name)?,
         SkillsAction::Install { name } => install_skill(&name)?,
+        SkillsAction::Update { name } => update_skills(name.as_deref())?,
         SkillsAction::Publish { name } => publish_skill(&name)?,
         SkillsAction::Search { query } => list_remote_skills(query.as_deref())?,
         SkillsAction::Sync => sync_skills()?,
+        SkillsAction::Status { diff, sync, force } => skills_status(diff, sync, force)?,
         SkillsAction::Reload => reload_skills()?,
         SkillsAction::Fetch(fetch) => fetch_skills(&fetch)?,
     }
@@ -952,17 +964,28 @@ fn fetch_skills(fetch: &SkillsFetchCommand) -> Result<()> {
     Ok(())
 }
 
-/// Install a skill from the global skills registry.
+fn load_cwd_config(cwd: &Path) -> config::Config {
+    let flow_toml = cwd.join("flow.toml");
+    if flow_toml.exists() {
+        config::load_or_default(&flow_toml)
+    } else {
+        config::Config::default()
+    }
+}
+
+fn configured_checksum<'a>(cfg: &'a config::Config, name: &str) -> Option<&'a str> {
+    cfg.skills
+        .as_ref()
+        .and_then(|skills| skills.checksums.get(name))
+        .map(String::as_str)
+}
+
+/// Install a skill from the global skills registry or a git source.
 fn install_skill(name: &str) -> Result<()> {
     let cwd = std::env::current_dir().context("failed to get current directory")?;
-    let installed = install_skill_inner(&cwd, name, false, false)?;
+    let cfg = load_cwd_config(&cwd);
+    let installed = install_skill_inner(&cwd, name, &cfg, false, false)?;
     if installed {
-        let flow_toml = cwd.join("flow.toml");
-        let cfg = if flow_toml.exists() {
-            config::load_or_default(&flow_toml)
-        } else {
-            config::Config::default()
-        };
         maybe_reload_codex_skills(&cwd, cfg.skills.as_ref(), "skills install");
     }
     Ok(())
@@ -971,9 +994,14 @@ fn install_skill(name: &str) -> Result<()> {
 fn install_skill_inner(
     project_root: &Path,
     name: &str,
+    cfg: &config::Config,
     allow_existing: bool,
     quiet: bool,
 ) -> Result<bool> {
+    if let Some(spec) = GitSkillSpec::parse(name) {
+        return install_git_skill(project_root, &spec, cfg, allow_existing, quiet);
+    }
+
     let skills_dir = get_skills_dir_at(project_root);
     let skill_dir = skills_dir.join(name);
 
@@ -1049,6 +1077,169 @@ fn install_skill_inner(
     Ok(true)
 }
 
+/// Install a skill from git. With `allow_existing`, an installed skill is only replaced when
+/// its recorded source differs from `spec` (e.g. the pinned ref changed in flow.toml).
+fn install_git_skill(
+    project_root: &Path,
+    spec: &GitSkillSpec,
+    cfg: &config::Config,
+    allow_existing: bool,
+    quiet: bool,
+) -> Result<bool> {
+    let skill_dir = get_skills_dir_at(project_root).join(&spec.name);
+    if skill_dir.exists() {
+        let installed = skill_source::read_installed(&skill_dir);
+        let same_source = installed
+            .as_ref()
+            .is_some_and(|source| source.spec == spec.display());
+        if allow_existing && (same_source || installed.is_none()) {
+            return Ok(false);
+        }
+        if !allow_existing {
+            bail!(
+                "Skill '{}' already exists locally. Use `f skills update {}` or remove it first with: f skills remove {}",
+                spec.name,
+                spec.name,
+                spec.name
+            );
+        }
+    }
+
+    if !quiet {
+        println!("Fetching skill '{}' from {}...", spec.name, spec.display());
+    }
+    let fetched = skill_source::fetch(spec)?;
+    let source = skill_source::install_fetched(
+        spec,
+        &fetched,
+        &skill_dir,
+        configured_checksum(cfg, &spec.name),
+    )?;
+    let _ = normalize_single_skill_file(&skill_dir)?;
+    ensure_symlinks_at(project_root)?;
+
+    if !quiet {
+        println!("Installed skill: {}", spec.name);
+        println!("  Source: {}", source.spec);
+        println!("  Commit: {}", short_commit(&source.commit));
+        println!("  Checksum: {}", source.checksum);
+    }
+    Ok(true)
+}
+
+fn short_commit(commit: &str) -> &str {
+    &commit[..commit.len().min(12)]
+}
+
+/// Re-fetch git-installed skills; flow.toml `[skills] install` pins take precedence over the
+/// recorded source so bumping a ref there upgrades the skill.
+fn update_skills(only: Option<&str>) -> Result<()> {
+    let cwd = std::env::current_dir().context("failed to get current directory")?;
+    let cfg = load_cwd_config(&cwd);
+    let skills_dir = get_skills_dir_at(&cwd);
+
+    let configured: Vec<GitSkillSpec> = cfg
+        .skills
+        .as_ref()
+        .map(|skills| {
+            skills
+                .install
+                .iter()
+                .filter_map(|entry| GitSkillSpec::parse(entry))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut specs: Vec<GitSkillSpec> = Vec::new();
+    if skills_dir.exists() {
+        for entry in fs::read_dir(&skills_dir).context("failed to read skills directory")? {
+            let path = entry?.path();
+            let Some(installed) = skill_source::read_installed(&path) else {
+                continue;
+            };
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            let spec = configured
+                .iter()
+                .find(|spec| spec.name == name)
+                .cloned()
+                .or_else(|| GitSkillSpec::parse(&installed.spec));
+            if let Some(spec) = spec {
+                specs.push(spec);
+            }
+        }
+    }
+    for spec in &configured {
+        if !specs.iter().any(|existing| existing.name == spec.name) {
+            specs.push(spec.clone());
+        }
+    }
+    if let Some(only) = only {
+        specs.retain(|spec| spec.name == only);
+        if specs.is_empty() {
+            bail!("Skill '{}' was not installed from git", only);
+        }
+    }
+    specs.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if specs.is_empty() {
+        println!("No git-installed skills to update.");
+        return Ok(());
+    }
+
+    let mut changed = 0;
+    for spec in &specs {
+        let skill_dir = skills_dir.join(&spec.name);
+        let previous = skill_source::read_installed(&skill_dir);
+        let fetched = match skill_source::fetch(spec) {
+            Ok(fetched) => fetched,
+            Err(err) => {
+                eprintln!("⚠ {}: {err:#}", spec.name);
+                continue;
+            }
+        };
+        if previous
+            .as_ref()
+            .is_some_and(|prev| prev.spec == spec.display() && prev.checksum == fetched.checksum)
+        {
+            println!(
+                "  {} up to date ({})",
+                spec.name,
+                short_commit(&fetched.commit)
+            );
+            continue;
+        }
+
+        match skill_source::install_fetched(
+            spec,
+            &fetched,
+            &skill_dir,
+            configured_checksum(&cfg, &spec.name),
+        ) {
+            Ok(source) => {
+                let _ = normalize_single_skill_file(&skill_dir)?;
+                let from = previous
+                    .map(|prev| short_commit(&prev.commit).to_string())
+                    .unwrap_or_else(|| "new".to_string());
+                println!(
+                    "  {} {} -> {} ({})",
+                    spec.name,
+                    from,
+                    short_commit(&source.commit),
+                    source.checksum
+                );
+                changed += 1;
+            }
+            Err(err) => eprintln!("⚠ {}: {err:#}", spec.name),
+        }
+    }
+
+    if changed > 0 {
+        ensure_symlinks_at(&cwd)?;
+        maybe_reload_codex_skills(&cwd, cfg.skills.as_ref(), "skills update");
+    }
+    Ok(())
+}
+
 #[derive(Debug, serde::Deserialize)]
 #[allow(dead_code)]
 struct SkillResponse {
@@ -1290,15 +1481,25 @@ fn sync_tasks_to_skills(
         let normalized = normalize_single_skill_file(&skill_dir)?;
         let skill_file = skill_file_upper(&skill_dir);
         let content = render_task_skill(task);
-        let should_write = match fs::read_to_string(&skill_file) {
-            Ok(existing) => existing != content,
-            Err(err) if err.kind() == std::io::ErrorKind::NotFound => true,
+        let existing = match fs::read_to_string(&skill_file) {
+            Ok(existing) => Some(existing),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
             Err(err) => return Err(err.into()),
         };
+        let should_write = existing.as_deref() != Some(content.as_str());
 
+        if should_write
+            && !options.overwrite_local_edits
+            && existing
+                .as_deref()
+                .is_some_and(|existing| edited_since_sync(&skill_dir, existing))
+        {
+            continue;
+        }
         if should_write {
-            fs::write(&skill_file, content)?;
+            fs::write(&skill_file, &content)?;
         }
+        fs::write(skill_dir.join(TASK_SKILL_HASH_FILE), content_hash(&content))?;
         if !existed {
             created += 1;
         } else if should_write || normalized {
@@ -1311,6 +1512,186 @@ fn sync_tasks_to_skills(
     Ok((created, updated))
 }
 
+fn content_hash(content: &str) -> String {
+    hex::encode(Sha256::digest(content.as_bytes()))
+}
+
+/// Whether `current` differs from what flow last wrote (skills synced before hashes were
+/// recorded count as unedited).
+fn edited_since_sync(skill_dir: &Path, current: &str) -> bool {
+    fs::read_to_string(skill_dir.join(TASK_SKILL_HASH_FILE))
+        .map(|recorded| recorded.trim() != content_hash(current))
+        .unwrap_or(false)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TaskSkillState {
+    InSync,
+    /// Task exists but has no skill.
+    Missing,
+    /// Generated skill is untouched but the task changed.
+    Stale,
+    /// Skill was edited by hand since the last sync.
+    Edited,
+    /// Generated skill whose task is gone from flow.toml.
+    Orphaned,
+}
+
+#[derive(Debug)]
+struct TaskSkillStatus {
+    name: String,
+    state: TaskSkillState,
+    /// Content flow would generate now (`None` for orphans).
+    expected: Option<String>,
+    /// Content on disk (`None` when missing).
+    current: Option<String>,
+}
+
+fn task_skill_statuses(skills_dir: &Path, tasks: &[config::TaskConfig]) -> Vec<TaskSkillStatus> {
+    let mut statuses: Vec<TaskSkillStatus> = tasks
+        .iter()
+        .map(|task| {
+            let skill_dir = skills_dir.join(&task.name);
+            let expected = render_task_skill(task);
+            let current = find_skill_file(&skill_dir).and_then(|f| fs::read_to_string(f).ok());
+            let state = match current.as_deref() {
+                None => TaskSkillState::Missing,
+                Some(current) if current == expected => TaskSkillState::InSync,
+                Some(current) if edited_since_sync(&skill_dir, current) => TaskSkillState::Edited,
+                Some(_) => TaskSkillState::Stale,
+            };
+            TaskSkillStatus {
+                name: task.name.clone(),
+                state,
+                expected: Some(expected),
+                current,
+            }
+        })
+        .collect();
+
+    if let Ok(entries) = fs::read_dir(skills_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if !path.is_dir() || tasks.iter().any(|task| task.name == name) {
+                continue;
+            }
+            let Some(current) = find_skill_file(&path).and_then(|f| fs::read_to_string(f).ok())
+            else {
+                continue;
+            };
+            if parse_frontmatter_field(&current, "source").as_deref() != Some("flow.toml") {
+                continue;
+            }
+            statuses.push(TaskSkillStatus {
+                name: name.to_string(),
+                state: TaskSkillState::Orphaned,
+                expected: None,
+                current: Some(current),
+            });
+        }
+    }
+
+    statuses.sort_by(|a, b| a.name.cmp(&b.name));
+    statuses
+}
+
+fn kept_local_edits(skills_dir: &Path, tasks: &[config::TaskConfig]) -> Vec<String> {
+    task_skill_statuses(skills_dir, tasks)
+        .into_iter()
+        .filter(|status| status.state == TaskSkillState::Edited)
+        .map(|status| status.name)
+        .collect()
+}
+
+/// Report (and optionally repair) drift between flow.toml tasks and `.ai/skills`.
+fn skills_status(show_diff: bool, sync: bool, force: bool) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let flow_toml = cwd.join("flow.toml");
+    if !flow_toml.exists() {
+        bail!("No flow.toml found in current directory");
+    }
+    let cfg = config::load(&flow_toml)?;
+    let skills_dir = get_skills_dir()?;
+    let statuses = task_skill_statuses(&skills_dir, &cfg.tasks);
+
+    println!("Task skills in .ai/skills/:\n");
+    for status in &statuses {
+        let (marker, label) = match status.state {
+            TaskSkillState::InSync => ("✓", "in sync"),
+            TaskSkillState::Missing => ("+", "missing"),
+            TaskSkillState::Stale => ("~", "stale (task changed in flow.toml)"),
+            TaskSkillState::Edited => ("✎", "edited locally"),
+            TaskSkillState::Orphaned => ("-", "orphaned (task removed from flow.toml)"),
+        };
+        println!("  {} {:<24} {}", marker, status.name, label);
+        if show_diff && status.state != TaskSkillState::InSync {
+            let diff = line_diff::render_line_diff(
+                status.current.as_deref().unwrap_or(""),
+                status.expected.as_deref().unwrap_or(""),
+            );
+            for line in diff.lines() {
+                println!("      {}", line);
+            }
+        }
+    }
+
+    let drifted = statuses
+        .iter()
+        .filter(|status| status.state != TaskSkillState::InSync)
+        .count();
+    if drifted == 0 {
+        println!("\nAll task skills match flow.toml.");
+        return Ok(());
+    }
+
+    if !sync {
+        println!("\n{} skill(s) drifted.", drifted);
+        if !show_diff {
+            println!("  Show changes: f skills status --diff");
+        }
+        println!("  Re-sync (keep local edits): f skills status --sync");
+        println!("  Re-sync and overwrite local edits: f skills status --sync --force");
+        return Ok(());
+    }
+
+    let mut removed = 0;
+    for status in &statuses {
+        if status.state != TaskSkillState::Orphaned {
+            continue;
+        }
+        let skill_dir = skills_dir.join(&status.name);
+        let edited = status
+            .current
+            .as_deref()
+            .is_some_and(|current| edited_since_sync(&skill_dir, current));
+        if edited && !force {
+            continue;
+        }
+        fs::remove_dir_all(&skill_dir)
+            .with_context(|| format!("failed to remove {}", skill_dir.display()))?;
+        removed += 1;
+    }
+
+    let mut options = resolve_skill_sync_options(cfg.skills.as_ref());
+    options.overwrite_local_edits = force;
+    let (created, updated) = sync_tasks_to_skills(&skills_dir, &cfg.tasks, options)?;
+    ensure_symlinks()?;
+
+    println!(
+        "\nRe-synced: {} created, {} updated, {} orphaned removed.",
+        created, updated, removed
+    );
+    let kept = kept_local_edits(&skills_dir, &cfg.tasks);
+    if !kept.is_empty() {
+        println!("  Kept local edits: {}", kept.join(", "));
+    }
+    maybe_reload_codex_skills(&cwd, cfg.skills.as_ref(), "skills status --sync");
+    Ok(())
+}
+
 /// Sync flow.toml tasks as skills.
 fn sync_skills() -> Result<()> {
     let cwd = std::env::current_dir()?;
@@ -1341,6 +1722,13 @@ fn sync_skills() -> Result<()> {
     if normalized > 0 {
         println!("  Normalized: {}", normalized);
     }
+    let kept = kept_local_edits(&skills_dir, &cfg.tasks);
+    if !kept.is_empty() {
+        println!(
+            "  Kept local edits: {} (see f skills status --diff)",
+            kept.join(", ")
+        );
+    }
     println!("\nSymlinked to .claude/skills/ and .codex/skills/");
     maybe_reload_codex_skills(&cwd, cfg.skills.as_ref(), "skills sync");
 
@@ -1368,7 +1756,7 @@ pub(crate) fn enforce_skills_from_config(
     }
 
     for name in &skills_cfg.install {
-        let installed = install_skill_inner(project_root, name, true, true)?;
+        let installed = install_skill_inner(project_root, name, cfg, true, true)?;
         if installed {
             summary.installed_skills.push(name.clone());
         }
@@ -1497,8 +1885,34 @@ mod tests {
             shortcuts: Vec::new(),
             interactive: false,
             confirm_on_match: false,
+            confirm: None,
+            protected: false,
             on_cancel: None,
             output_file: None,
+            host: None,
+            remote_dir: None,
+            remote_env: Vec::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            port: None,
+            matrix: Default::default(),
+            matrix_parallel: false,
+            env_files: None,
+            retries: 0,
+            retry_backoff: None,
+            retry_exponential: false,
+            prompt: Vec::new(),
+            cwd: None,
+            umask: None,
+            nice: None,
+            max_memory: None,
+            cpu: None,
+            memory: None,
+            git_note: false,
+            parser: None,
+            before: Vec::new(),
+            after: Vec::new(),
+            on_failure: Vec::new(),
         }
     }
 
@@ -1655,4 +2069,48 @@ mod tests {
             "legacy lowercase filename should be removed"
         );
     }
+
+    #[test]
+    fn task_skill_status_separates_stale_from_edited() {
+        let dir = tempdir().expect("tempdir");
+        let skills_dir = dir.path().join(".ai/skills");
+        let tasks = vec![
+            sample_task("build", Some("Build it")),
+            sample_task("lint", Some("Lint it")),
+            sample_task("gone", None),
+        ];
+        sync_tasks_to_skills(&skills_dir, &tasks, SkillSyncOptions::default())
+            .expect("initial sync");
+
+        let edited_file = skill_file_upper(&skills_dir.join("lint"));
+        fs::write(&edited_file, "---\nsource: flow.toml\n---\nmy notes\n").expect("edit skill");
+        let current = vec![
+            sample_task("build", Some("Build it faster")),
+            sample_task("lint", Some("Lint it strictly")),
+            sample_task("test", None),
+        ];
+
+        let states: Vec<(String, TaskSkillState)> = task_skill_statuses(&skills_dir, &current)
+            .into_iter()
+            .map(|status| (status.name, status.state))
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                ("build".to_string(), TaskSkillState::Stale),
+                ("gone".to_string(), TaskSkillState::Orphaned),
+                ("lint".to_string(), TaskSkillState::Edited),
+                ("test".to_string(), TaskSkillState::Missing),
+            ]
+        );
+
+        sync_tasks_to_skills(&skills_dir, &current, SkillSyncOptions::default()).expect("resync");
+        assert!(
+            fs::read_to_string(&edited_file)
+                .expect("read lint skill")
+                .contains("my notes"),
+            "sync should keep local edits"
+        );
+        assert_eq!(kept_local_edits(&skills_dir, &current), vec!["lint"]);
+    }
 }
@@ -0,0 +1,539 @@
+//! Source-map resolution of minified JS stack traces.
+//!
+//! When a project sets `[logs] source_maps = true`, error entries ingested by the log
+//! server have their stack frames mapped back through the `*.map` files in the project's
+//! build output. The raw stack is kept as-is; the mapped one is stored alongside it in
+//! `resolved_stack` so `f logs errors` can show real file and line info.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{Context, Result, bail};
+use ignore::WalkBuilder;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::cli::LogsErrorsOpts;
+use crate::config;
+use crate::log_store::{self, LogEntry, LogQuery};
+use crate::time_window;
+use crate::{projects, running};
+
+/// How long a project's map index is reused before the build dirs are walked again.
+const INDEX_TTL: Duration = Duration::from_secs(30);
+/// Maps larger than this are skipped rather than parsed on the ingest path.
+const MAX_MAP_BYTES: u64 = 64 * 1024 * 1024;
+
+/// A position in an original source file (1-based line and column).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OriginalPosition {
+    pub source: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    generated_column: u32,
+    source: u32,
+    line: u32,
+    column: u32,
+}
+
+/// A decoded (non-indexed) source map.
+#[derive(Debug)]
+pub struct SourceMap {
+    sources: Vec<String>,
+    lines: Vec<Vec<Segment>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSourceMap {
+    #[serde(default)]
+    source_root: Option<String>,
+    #[serde(default)]
+    sources: Vec<Option<String>>,
+    #[serde(default)]
+    mappings: String,
+    #[serde(default)]
+    sections: Option<serde_json::Value>,
+}
+
+impl SourceMap {
+    pub fn parse(json: &str) -> Result<Self> {
+        let raw: RawSourceMap = serde_json::from_str(json).context("invalid source map JSON")?;
+        if raw.sections.is_some() {
+            bail!("indexed source maps are not supported");
+        }
+        let root = raw
+            .source_root
+            .filter(|root| !root.is_empty())
+            .map(|root| format!("{}/", root.trim_end_matches('/')));
+        let sources = raw
+            .sources
+            .into_iter()
+            .map(|source| {
+                let source = source.unwrap_or_default();
+                match &root {
+                    Some(root) => format!("{root}{source}"),
+                    None => source,
+                }
+            })
+            .collect();
+        Ok(Self {
+            sources,
+            lines: decode_mappings(&raw.mappings)?,
+        })
+    }
+
+    /// Original position for a generated 1-based `line:column`.
+    pub fn lookup(&self, line: u32, column: u32) -> Option<OriginalPosition> {
+        let segments = self.lines.get(line.checked_sub(1)? as usize)?;
+        let column = column.saturating_sub(1);
+        let idx = segments.partition_point(|segment| segment.generated_column <= column);
+        let segment = segments.get(idx.checked_sub(1)?)?;
+        Some(OriginalPosition {
+            source: self.sources.get(segment.source as usize)?.clone(),
+            line: segment.line + 1,
+            column: segment.column + 1,
+        })
+    }
+}
+
+/// Decode the `mappings` field. Segments without a source (1-field) are dropped.
+fn decode_mappings(mappings: &str) -> Result<Vec<Vec<Segment>>> {
+    let mut lines = Vec::new();
+    let (mut source, mut line, mut column) = (0i64, 0i64, 0i64);
+    for encoded_line in mappings.split(';') {
+        let mut generated_column = 0i64;
+        let mut segments = Vec::new();
+        for encoded in encoded_line.split(',').filter(|s| !s.is_empty()) {
+            let fields = decode_vlq(encoded)?;
+            generated_column += fields[0];
+            if fields.len() < 4 {
+                continue;
+            }
+            source += fields[1];
+            line += fields[2];
+            column += fields[3];
+            if generated_column < 0 || source < 0 || line < 0 || column < 0 {
+                bail!("source map mapping out of range");
+            }
+            segments.push(Segment {
+                generated_column: generated_column as u32,
+                source: source as u32,
+                line: line as u32,
+                column: column as u32,
+            });
+        }
+        segments.sort_by_key(|segment| segment.generated_column);
+        lines.push(segments);
+    }
+    Ok(lines)
+}
+
+fn decode_vlq(encoded: &str) -> Result<Vec<i64>> {
+    let mut values = Vec::new();
+    let (mut value, mut shift) = (0i64, 0u32);
+    for byte in encoded.bytes() {
+        let digit = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => bail!("invalid base64 VLQ character '{}'", byte as char),
+        } as i64;
+        if shift > 60 {
+            bail!("base64 VLQ value too large");
+        }
+        value += (digit & 0x1f) << shift;
+        if digit & 0x20 != 0 {
+            shift += 5;
+            continue;
+        }
+        values.push(if value & 1 == 1 {
+            -(value >> 1)
+        } else {
+            value >> 1
+        });
+        value = 0;
+        shift = 0;
+    }
+    if shift != 0 || values.is_empty() {
+        bail!("truncated base64 VLQ segment '{encoded}'");
+    }
+    Ok(values)
+}
+
+/// Location of a JS frame in a V8 (`at fn (url:1:2)`) or Firefox/Safari (`fn@url:1:2`)
+/// stack line.
+fn frame_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?P<url>[^\s()@]+\.[cm]?js)(?:\?[^\s:()]*)?:(?P<line>\d+):(?P<col>\d+)")
+            .expect("valid stack frame regex")
+    })
+}
+
+/// Rewrite each frame that `lookup` can map; `None` when no frame was resolved.
+pub fn resolve_stack(
+    stack: &str,
+    mut lookup: impl FnMut(&str, u32, u32) -> Option<OriginalPosition>,
+) -> Option<String> {
+    let mut resolved_any = false;
+    let lines: Vec<String> = stack
+        .lines()
+        .map(|line| {
+            let Some(caps) = frame_regex().captures_iter(line).last() else {
+                return line.to_string();
+            };
+            let (Ok(gen_line), Ok(gen_col)) =
+                (caps["line"].parse::<u32>(), caps["col"].parse::<u32>())
+            else {
+                return line.to_string();
+            };
+            let Some(position) = lookup(&caps["url"], gen_line, gen_col) else {
+                return line.to_string();
+            };
+            resolved_any = true;
+            let span = caps.get(0).expect("whole match");
+            format!(
+                "{}{}:{}:{}{}",
+                &line[..span.start()],
+                position.source,
+                position.line,
+                position.column,
+                &line[span.end()..]
+            )
+        })
+        .collect();
+    resolved_any.then(|| lines.join("\n"))
+}
+
+/// Resolves frames for one project against the maps under its build directories.
+struct Resolver {
+    root: PathBuf,
+    maps_by_name: Arc<HashMap<String, PathBuf>>,
+}
+
+impl Resolver {
+    fn new(root: &Path, dirs: &[String]) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            maps_by_name: map_index(root, dirs),
+        }
+    }
+
+    fn lookup(&self, url: &str, line: u32, column: u32) -> Option<OriginalPosition> {
+        let name = url.rsplit('/').next()?;
+        let map_path = self.maps_by_name.get(name)?;
+        let map = load_map(map_path)?;
+        let mut position = map.lookup(line, column)?;
+        position.source = clean_source(
+            &position.source,
+            map_path.parent().unwrap_or(&self.root),
+            &self.root,
+        );
+        Some(position)
+    }
+}
+
+/// `bundle.js` -> `dist/assets/bundle.js.map` for every map under the build dirs.
+fn map_index(root: &Path, dirs: &[String]) -> Arc<HashMap<String, PathBuf>> {
+    type IndexCache = HashMap<PathBuf, (Instant, Arc<HashMap<String, PathBuf>>)>;
+    static CACHE: OnceLock<Mutex<IndexCache>> = OnceLock::new();
+    let mut cache = CACHE
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some((built, index)) = cache.get(root)
+        && built.elapsed() < INDEX_TTL
+    {
+        return Arc::clone(index);
+    }
+
+    let mut index = HashMap::new();
+    for dir in dirs {
+        let dir = root.join(dir);
+        if !dir.is_dir() {
+            continue;
+        }
+        // Build output is usually gitignored, so the standard filters must stay off.
+        let walker = WalkBuilder::new(&dir)
+            .standard_filters(false)
+            .filter_entry(|entry| {
+                let name = entry.file_name();
+                name != "node_modules" && name != "cache"
+            })
+            .build();
+        for entry in walker.flatten() {
+            let Some(name) = entry.file_name().to_str() else {
+                continue;
+            };
+            if let Some(generated) = name.strip_suffix(".map")
+                && entry.file_type().is_some_and(|kind| kind.is_file())
+            {
+                index
+                    .entry(generated.to_string())
+                    .or_insert_with(|| entry.path().to_path_buf());
+            }
+        }
+    }
+    let index = Arc::new(index);
+    cache.insert(root.to_path_buf(), (Instant::now(), Arc::clone(&index)));
+    index
+}
+
+/// Parsed maps, reused until the file on disk changes.
+fn load_map(path: &Path) -> Option<Arc<SourceMap>> {
+    type MapCache = HashMap<PathBuf, (SystemTime, Arc<SourceMap>)>;
+    static CACHE: OnceLock<Mutex<MapCache>> = OnceLock::new();
+    let meta = fs::metadata(path).ok()?;
+    if meta.len() > MAX_MAP_BYTES {
+        return None;
+    }
+    let modified = meta.modified().ok()?;
+    let mut cache = CACHE
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some((seen, map)) = cache.get(path)
+        && *seen == modified
+    {
+        return Some(Arc::clone(map));
+    }
+    let parsed = fs::read_to_string(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| SourceMap::parse(&json));
+    match parsed {
+        Ok(map) => {
+            let map = Arc::new(map);
+            cache.insert(path.to_path_buf(), (modified, Arc::clone(&map)));
+            Some(map)
+        }
+        Err(err) => {
+            tracing::debug!(?err, path = %path.display(), "skipping source map");
+            None
+        }
+    }
+}
+
+/// Turn a map `sources` entry into a project-relative path where possible.
+fn clean_source(source: &str, map_dir: &Path, root: &Path) -> String {
+    if let Some(rest) = source.strip_prefix("webpack://") {
+        // `webpack://app-name/./src/x.ts` and `webpack:///./src/x.ts`
+        let path = rest.split_once('/').map_or(rest, |(_, path)| path);
+        return path.trim_start_matches("./").to_string();
+    }
+    let path = match source.strip_prefix("file://") {
+        Some(path) => PathBuf::from(path),
+        None if source.contains("://") => return source.to_string(),
+        None => normalize(&map_dir.join(source)),
+    };
+    path.strip_prefix(root)
+        .unwrap_or(&path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Lexically resolve `.` and `..` without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Fill in `resolved_stack` for entries whose project has `[logs] source_maps` enabled.
+/// Resolution is best-effort: anything that can't be mapped is left untouched.
+pub fn resolve_entries(entries: &mut [LogEntry]) {
+    let mut resolvers: HashMap<String, Option<Resolver>> = HashMap::new();
+    for entry in entries.iter_mut() {
+        if entry.resolved_stack.is_some() {
+            continue;
+        }
+        let Some(stack) = entry.stack.as_deref() else {
+            continue;
+        };
+        let resolver = resolvers
+            .entry(entry.project.clone())
+            .or_insert_with(|| project_resolver(&entry.project));
+        if let Some(resolver) = resolver {
+            entry.resolved_stack =
+                resolve_stack(stack, |url, line, col| resolver.lookup(url, line, col));
+        }
+    }
+}
+
+fn project_resolver(project: &str) -> Option<Resolver> {
+    let entry = projects::resolve_project(project).ok().flatten()?;
+    let cfg = config::load(&entry.config_path).ok()?;
+    let logs = cfg.logs.filter(|logs| logs.source_maps)?;
+    Some(Resolver::new(&entry.project_root, &logs.source_map_dirs))
+}
+
+/// `f logs errors`: recent error clusters (or every entry with `--all`), preferring the
+/// source-mapped stack.
+pub fn show_errors(opts: LogsErrorsOpts) -> Result<()> {
+    let since = match opts.since.as_deref() {
+        Some(raw) => Some(running::now_ms() as i64 - time_window::parse_since(raw)?),
+        None => None,
+    };
+    let conn = log_store::open_log_db()?;
+    if !opts.all {
+        let clusters =
+            log_store::error_clusters(&conn, opts.project.as_deref(), since, opts.limit)?;
+        if clusters.is_empty() {
+            println!("No errors logged.");
+            return Ok(());
+        }
+        for (idx, cluster) in clusters.iter().rev().enumerate() {
+            let entry = &cluster.latest.entry;
+            if idx > 0 {
+                println!();
+            }
+            let seen = if cluster.count == 1 {
+                format!("new, {}", format_timestamp(cluster.last_seen))
+            } else {
+                format!(
+                    "{}x, {} .. {}",
+                    cluster.count,
+                    format_timestamp(cluster.first_seen),
+                    format_timestamp(cluster.last_seen)
+                )
+            };
+            println!(
+                "[{seen}] [{}/{}] {}",
+                entry.project,
+                entry.service,
+                entry.content.trim_end()
+            );
+            print_stack(entry, opts.raw);
+        }
+        return Ok(());
+    }
+
+    let errors = log_store::query_logs(
+        &conn,
+        &LogQuery {
+            project: opts.project.clone(),
+            log_type: Some("error".to_string()),
+            since,
+            limit: opts.limit,
+            ..Default::default()
+        },
+    )?;
+    if errors.is_empty() {
+        println!("No errors logged.");
+        return Ok(());
+    }
+
+    for (idx, stored) in errors.iter().rev().enumerate() {
+        let entry = &stored.entry;
+        if idx > 0 {
+            println!();
+        }
+        println!(
+            "{} [{}/{}] {}",
+            format_timestamp(entry.timestamp),
+            entry.project,
+            entry.service,
+            entry.content.trim_end()
+        );
+        print_stack(entry, opts.raw);
+    }
+    Ok(())
+}
+
+fn print_stack(entry: &LogEntry, raw: bool) {
+    let stack = if raw {
+        entry.stack.as_deref()
+    } else {
+        entry.resolved_stack.as_deref().or(entry.stack.as_deref())
+    };
+    if let Some(stack) = stack {
+        for line in stack.lines() {
+            println!("  {}", line.trim_start());
+        }
+    }
+}
+
+fn format_timestamp(ms: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(ms)
+        .map(|dt| {
+            dt.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_else(|| ms.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_mappings_and_looks_up_positions() {
+        assert_eq!(decode_vlq("AAAA").unwrap(), vec![0, 0, 0, 0]);
+        assert_eq!(decode_vlq("gBDD").unwrap(), vec![16, -1, -1]);
+        assert!(decode_vlq("g").is_err());
+
+        // Line 1: col 0 -> a.ts 1:1, col 10 -> a.ts 3:5. Line 2: col 4 -> b.ts 8:3.
+        let map = SourceMap::parse(
+            r#"{"version":3,"sources":["a.ts","b.ts"],"names":[],"mappings":"AAAA,UAEI;ICKF"}"#,
+        )
+        .unwrap();
+        let at = |line, col| map.lookup(line, col).map(|p| (p.source, p.line, p.column));
+        assert_eq!(at(1, 1), Some(("a.ts".to_string(), 1, 1)));
+        assert_eq!(at(1, 9), Some(("a.ts".to_string(), 1, 1)));
+        assert_eq!(at(1, 40), Some(("a.ts".to_string(), 3, 5)));
+        assert_eq!(at(2, 5), Some(("b.ts".to_string(), 8, 3)));
+        assert_eq!(at(2, 1), None);
+        assert_eq!(at(3, 1), None);
+    }
+
+    #[test]
+    fn rewrites_only_resolvable_frames() {
+        let stack = "TypeError: x is undefined\n    at a (http://localhost:3000/assets/index-3f2a.js?v=1:1:2345)\n    at http://cdn.example.com/vendor.js:1:10\nrender@http://localhost:3000/assets/index-3f2a.js:2:17";
+        let resolved = resolve_stack(stack, |url, line, col| {
+            url.ends_with("index-3f2a.js").then(|| OriginalPosition {
+                source: "src/App.tsx".to_string(),
+                line: line * 10,
+                column: col,
+            })
+        })
+        .unwrap();
+        assert_eq!(
+            resolved,
+            "TypeError: x is undefined\n    at a (src/App.tsx:10:2345)\n    at http://cdn.example.com/vendor.js:1:10\nrender@src/App.tsx:20:17"
+        );
+        assert_eq!(resolve_stack(stack, |_, _, _| None), None);
+    }
+
+    #[test]
+    fn cleans_bundler_source_paths() {
+        let root = Path::new("/repo");
+        let dir = Path::new("/repo/dist/assets");
+        assert_eq!(
+            clean_source("webpack://app/./src/App.tsx", dir, root),
+            "src/App.tsx"
+        );
+        assert_eq!(clean_source("../../src/main.ts", dir, root), "src/main.ts");
+        assert_eq!(
+            clean_source("file:///repo/src/lib.ts", dir, root),
+            "src/lib.ts"
+        );
+    }
+}
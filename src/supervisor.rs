@@ -8,9 +8,12 @@ use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result, bail};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 
 use crate::cli::{DaemonAction, SupervisorAction, SupervisorCommand};
-use crate::{config, daemon, projects, running};
+use crate::hub_events::{self, HubEvent};
+use crate::service_watch::ServiceWatch;
+use crate::{branch_watch, config, daemon, projects, running};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct IpcRequest {
@@ -75,6 +78,8 @@ struct ManagedDaemon {
 #[derive(Default)]
 struct SupervisorState {
     managed: HashMap<String, ManagedDaemon>,
+    /// `restart_on` watchers, keyed like `managed`.
+    watches: HashMap<String, ServiceWatch>,
 }
 
 type SharedState = Arc<Mutex<SupervisorState>>;
@@ -382,6 +387,7 @@ fn run_server(socket_path: &Path, boot: bool) -> Result<()> {
     }
 
     let state = Arc::new(Mutex::new(SupervisorState::default()));
+    branch_watch::spawn();
     let bootstrap_state = Arc::clone(&state);
     let initial_active_path = resolve_active_project_config_path();
     std::thread::spawn(move || {
@@ -888,6 +894,8 @@ fn reconcile_removed(state: &SharedState, active_config_path: &Option<PathBuf>)
             daemon::stop_daemon(&entry.name).ok();
             let mut st = state.lock().expect("lock");
             st.managed.remove(&entry.name);
+            st.watches
+                .remove(&daemon_key(&entry.name, entry.config_path.as_deref()));
         }
     }
 }
@@ -972,6 +980,9 @@ fn monitor_daemons(state: SharedState) -> Result<()> {
             let status = daemon::get_daemon_status(&daemon_cfg);
             if status.running {
                 if status.healthy == Some(false) {
+                    if entry.health_failures == 0 {
+                        publish_health_change(&entry.name, config_path.as_deref(), false);
+                    }
                     let key = daemon_key(&entry.name, config_path.as_deref());
                     let failures = entry.health_failures.saturating_add(1);
                     let should_restart_for_health = failures >= 3;
@@ -1016,6 +1027,9 @@ fn monitor_daemons(state: SharedState) -> Result<()> {
                         ));
                     }
                 } else if entry.health_failures != 0 || entry.restart_attempts != 0 {
+                    if entry.health_failures != 0 {
+                        publish_health_change(&entry.name, config_path.as_deref(), true);
+                    }
                     let key = daemon_key(&entry.name, config_path.as_deref());
                     updates.push((key, entry.retry_remaining, false, 0, 0, None));
                 }
@@ -1077,6 +1091,9 @@ fn monitor_daemons(state: SharedState) -> Result<()> {
                 next_restart_at,
             ) in updates
             {
+                if disabled {
+                    state.watches.remove(&key);
+                }
                 if let Some(entry) = state.managed.get_mut(&key) {
                     entry.retry_remaining = retry_remaining;
                     entry.disabled = disabled;
@@ -1135,10 +1152,42 @@ fn register_managed_daemon(
         restart_attempts: 0,
         next_restart_at: None,
     };
-    state.managed.insert(key, entry);
+    state.managed.insert(key.clone(), entry);
+    if disabled {
+        state.watches.remove(&key);
+    } else {
+        sync_service_watch(&mut state, key, daemon_cfg, config_path);
+    }
     Ok(())
 }
 
+/// Start, replace, or drop the `restart_on` watcher for a managed daemon.
+fn sync_service_watch(
+    state: &mut SupervisorState,
+    key: String,
+    daemon_cfg: &config::DaemonConfig,
+    config_path: Option<&Path>,
+) {
+    if state
+        .watches
+        .get(&key)
+        .is_some_and(|watch| watch.matches(daemon_cfg, config_path))
+    {
+        return;
+    }
+    state.watches.remove(&key);
+    match ServiceWatch::spawn(daemon_cfg, config_path) {
+        Ok(Some(watch)) => {
+            state.watches.insert(key, watch);
+        }
+        Ok(None) => {}
+        Err(err) => eprintln!(
+            "WARN supervisor cannot watch {} for restarts: {err:#}",
+            daemon_cfg.name
+        ),
+    }
+}
+
 fn disable_managed_daemon(
     state: &SharedState,
     name: &str,
@@ -1146,6 +1195,7 @@ fn disable_managed_daemon(
 ) -> Result<()> {
     let mut state = state.lock().expect("supervisor state lock");
     let key = daemon_key(name, config_path);
+    state.watches.remove(&key);
     if let Some(entry) = state.managed.get_mut(&key) {
         entry.disabled = true;
         return Ok(());
@@ -1168,6 +1218,19 @@ fn disable_managed_daemon(
     Ok(())
 }
 
+/// Publish a `service.health` hub event when a supervised daemon turns unhealthy or recovers.
+fn publish_health_change(name: &str, config_path: Option<&Path>, healthy: bool) {
+    hub_events::publish(HubEvent::new(
+        hub_events::SERVICE_HEALTH,
+        None,
+        json!({
+            "name": name,
+            "healthy": healthy,
+            "config_path": config_path.map(|path| path.display().to_string()),
+        }),
+    ));
+}
+
 fn daemon_key(name: &str, config_path: Option<&Path>) -> String {
     match config_path {
         Some(path) => format!("{}::{}", name, path.display()),
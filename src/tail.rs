@@ -0,0 +1,590 @@
+//! `f tail`: one time-ordered stream of a project's task logs, the journal of its deployed
+//! service, and proxy 5xx responses.
+//!
+//! Each source runs on its own thread. A merger holds lines for a short window and releases
+//! them sorted by timestamp, so remote journal entries and local output interleave correctly.
+//! `f tail` prints the merged stream; the log server serves it as SSE at `/logs/tail/stream`.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, IsTerminal, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use chrono::{Local, TimeZone};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::cli::TailOpts;
+use crate::proxy::trace::{self, TraceBuffer};
+use crate::{deploy, processes, tasks};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// How long lines wait so that one stamped earlier but delivered later still sorts first.
+const REORDER_WINDOW: Duration = Duration::from_millis(300);
+
+const RESET: &str = "\x1b[0m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[31m";
+const MAGENTA: &str = "\x1b[35m";
+const CYAN: &str = "\x1b[36m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TailSource {
+    /// Output of the project's tasks.
+    Task,
+    /// `journalctl` of the `[host]` service, over SSH.
+    Journal,
+    /// Proxied requests that returned 5xx.
+    Proxy,
+}
+
+impl TailSource {
+    fn color(self) -> &'static str {
+        match self {
+            TailSource::Task => CYAN,
+            TailSource::Journal => MAGENTA,
+            TailSource::Proxy => RED,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            TailSource::Task => "task",
+            TailSource::Journal => "journal",
+            TailSource::Proxy => "proxy",
+        }
+    }
+}
+
+/// One line of the merged stream.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TailLine {
+    /// Unix time in milliseconds: the journal's own timestamp, the proxy request's start,
+    /// or when flow read a task log line.
+    pub ts_ms: u64,
+    pub source: TailSource,
+    /// Task name, service name, or empty for the proxy.
+    pub label: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TailFilter {
+    /// Sources to include; empty means all.
+    pub sources: Vec<TailSource>,
+    /// Only this task's log.
+    pub task: Option<String>,
+    /// Case-insensitive substring a line must contain.
+    pub grep: Option<String>,
+}
+
+impl TailFilter {
+    fn wants(&self, source: TailSource) -> bool {
+        self.sources.is_empty() || self.sources.contains(&source)
+    }
+
+    fn matches(&self, line: &TailLine) -> bool {
+        self.grep.as_deref().is_none_or(|needle| {
+            let needle = needle.to_lowercase();
+            line.text.to_lowercase().contains(&needle)
+                || line.label.to_lowercase().contains(&needle)
+        })
+    }
+}
+
+/// A running tail. Dropping it stops every source, including the remote `journalctl`.
+pub struct TailStream {
+    pub lines: Receiver<TailLine>,
+    /// What each source is following, or why it was skipped.
+    pub notes: Vec<String>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Drop for TailStream {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Start tailing the project at `config_path`. Sources only report what happens from now on.
+pub fn spawn(
+    project_root: &Path,
+    config_path: &Path,
+    project_name: Option<String>,
+    filter: TailFilter,
+) -> Result<TailStream> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = mpsc::channel();
+    let mut notes = Vec::new();
+
+    if filter.wants(TailSource::Task) {
+        let (root, config) = (project_root.to_path_buf(), config_path.to_path_buf());
+        let task = filter.task.clone();
+        let count =
+            processes::project_log_files(&root, &config, project_name.as_deref(), task.as_deref())
+                .len();
+        notes.push(match &task {
+            Some(task) => format!("task: {task}"),
+            None => format!("task: {count} log file(s), plus tasks started later"),
+        });
+        let (tx, stop) = (tx.clone(), stop.clone());
+        thread::spawn(move || {
+            follow_task_logs(
+                || {
+                    processes::project_log_files(
+                        &root,
+                        &config,
+                        project_name.as_deref(),
+                        task.as_deref(),
+                    )
+                },
+                tx,
+                stop,
+            )
+        });
+    }
+
+    if filter.wants(TailSource::Journal) {
+        let (_, cfg) = tasks::load_project_config(config_path.to_path_buf())?;
+        match deploy::journal_follow_command(&cfg)? {
+            Some((service, command)) => {
+                notes.push(format!("journal: {service}"));
+                let (tx, stop) = (tx.clone(), stop.clone());
+                thread::spawn(move || follow_journal(command, service, tx, stop));
+            }
+            None => notes.push(
+                "journal: skipped (needs [host] service and `f deploy set-host`)".to_string(),
+            ),
+        }
+    }
+
+    if filter.wants(TailSource::Proxy) {
+        notes.push(format!(
+            "proxy: 5xx responses in {}",
+            trace::default_trace_dir().display()
+        ));
+        let (tx, stop) = (tx.clone(), stop.clone());
+        thread::spawn(move || follow_proxy_errors(tx, stop));
+    }
+    drop(tx);
+
+    let (out_tx, out_rx) = mpsc::channel();
+    let merge_stop = stop.clone();
+    thread::spawn(move || merge(rx, out_tx, filter, merge_stop));
+    Ok(TailStream {
+        lines: out_rx,
+        notes,
+        stop,
+    })
+}
+
+pub fn run(opts: TailOpts) -> Result<()> {
+    let (project_root, config_path, project_name) =
+        processes::resolve_log_project(&opts.config, opts.project.as_deref())?;
+    let filter = TailFilter {
+        sources: opts.sources,
+        task: opts.task,
+        grep: opts.grep,
+    };
+    let stream = spawn(&project_root, &config_path, project_name, filter)?;
+    let color = !opts.json && std::io::stdout().is_terminal();
+    if !opts.json {
+        for note in &stream.notes {
+            eprintln!("{note}");
+        }
+        eprintln!("Waiting for output (Ctrl+C to stop)...");
+    }
+    for line in stream.lines.iter() {
+        if opts.json {
+            println!("{}", serde_json::to_string(&line)?);
+        } else {
+            println!("{}", format_line(&line, color));
+        }
+    }
+    Ok(())
+}
+
+fn format_line(line: &TailLine, color: bool) -> String {
+    let time = Local
+        .timestamp_millis_opt(line.ts_ms as i64)
+        .single()
+        .map(|time| time.format("%H:%M:%S%.3f").to_string())
+        .unwrap_or_default();
+    let tag = if line.label.is_empty() {
+        line.source.name().to_string()
+    } else {
+        format!("{}:{}", line.source.name(), line.label)
+    };
+    if color {
+        format!(
+            "{DIM}{time}{RESET} {}[{tag}]{RESET} {}",
+            line.source.color(),
+            line.text
+        )
+    } else {
+        format!("{time} [{tag}] {}", line.text)
+    }
+}
+
+fn merge(rx: Receiver<TailLine>, out: Sender<TailLine>, filter: TailFilter, stop: Arc<AtomicBool>) {
+    let mut reorder = Reorder::new(REORDER_WINDOW);
+    loop {
+        let disconnected = match rx.recv_timeout(reorder.wait(Instant::now())) {
+            Ok(line) => {
+                if filter.matches(&line) {
+                    reorder.push(line, Instant::now());
+                }
+                false
+            }
+            Err(RecvTimeoutError::Timeout) => false,
+            Err(RecvTimeoutError::Disconnected) => true,
+        };
+        let ready = if disconnected {
+            reorder.drain()
+        } else {
+            reorder.ready(Instant::now())
+        };
+        for line in ready {
+            if out.send(line).is_err() {
+                stop.store(true, Ordering::SeqCst);
+                return;
+            }
+        }
+        if disconnected || stop.load(Ordering::SeqCst) {
+            return;
+        }
+    }
+}
+
+/// Holds lines for `window` after they arrive and releases them in timestamp order.
+struct Reorder {
+    window: Duration,
+    pending: Vec<(Instant, TailLine)>,
+}
+
+impl Reorder {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, line: TailLine, now: Instant) {
+        self.pending.push((now, line));
+    }
+
+    /// How long until the oldest pending line is due.
+    fn wait(&self, now: Instant) -> Duration {
+        self.pending
+            .iter()
+            .map(|(arrived, _)| (*arrived + self.window).saturating_duration_since(now))
+            .min()
+            .unwrap_or(POLL_INTERVAL)
+    }
+
+    /// Lines that waited out the window, plus any still waiting that are stamped no later,
+    /// so the output never goes back in time.
+    fn ready(&mut self, now: Instant) -> Vec<TailLine> {
+        let Some(cutoff) = self
+            .pending
+            .iter()
+            .filter(|(arrived, _)| now.duration_since(*arrived) >= self.window)
+            .map(|(_, line)| line.ts_ms)
+            .max()
+        else {
+            return Vec::new();
+        };
+        let (ready, rest) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(_, line)| line.ts_ms <= cutoff);
+        self.pending = rest;
+        sorted(ready)
+    }
+
+    fn drain(&mut self) -> Vec<TailLine> {
+        sorted(std::mem::take(&mut self.pending))
+    }
+}
+
+fn sorted(mut lines: Vec<(Instant, TailLine)>) -> Vec<TailLine> {
+    lines.sort_by_key(|(_, line)| line.ts_ms);
+    lines.into_iter().map(|(_, line)| line).collect()
+}
+
+/// Poll the task log files for appended lines. Files present at the start are read from
+/// their end; files that show up later are read from the beginning.
+fn follow_task_logs(
+    files: impl Fn() -> Vec<(String, PathBuf)>,
+    tx: Sender<TailLine>,
+    stop: Arc<AtomicBool>,
+) {
+    // Offset read so far and the unterminated tail of the last read, per file.
+    let mut state: HashMap<PathBuf, (u64, Vec<u8>)> = HashMap::new();
+    let mut first_pass = true;
+    while !stop.load(Ordering::SeqCst) {
+        for (label, path) in files() {
+            let Ok(len) = fs::metadata(&path).map(|meta| meta.len()) else {
+                continue;
+            };
+            let (offset, partial) = state
+                .entry(path.clone())
+                .or_insert_with(|| (if first_pass { len } else { 0 }, Vec::new()));
+            if len < *offset {
+                // Truncated or replaced: start over.
+                *offset = 0;
+                partial.clear();
+            }
+            if len == *offset {
+                continue;
+            }
+            let Ok(mut file) = File::open(&path) else {
+                continue;
+            };
+            if file.seek(SeekFrom::Start(*offset)).is_err() {
+                continue;
+            }
+            let mut chunk = Vec::new();
+            let Ok(read) = file.take(len - *offset).read_to_end(&mut chunk) else {
+                continue;
+            };
+            *offset += read as u64;
+            partial.extend_from_slice(&chunk);
+            let ts_ms = now_ms();
+            while let Some(end) = partial.iter().position(|byte| *byte == b'\n') {
+                let raw: Vec<u8> = partial.drain(..=end).collect();
+                let text = String::from_utf8_lossy(&raw).trim_end().to_string();
+                let line = TailLine {
+                    ts_ms,
+                    source: TailSource::Task,
+                    label: label.clone(),
+                    text,
+                };
+                if tx.send(line).is_err() {
+                    return;
+                }
+            }
+        }
+        first_pass = false;
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Run `ssh … journalctl -f -o short-unix` until stopped or the connection ends.
+fn follow_journal(
+    mut command: Command,
+    service: String,
+    tx: Sender<TailLine>,
+    stop: Arc<AtomicBool>,
+) {
+    let line = move |text: String, ts_ms: u64| TailLine {
+        ts_ms,
+        source: TailSource::Journal,
+        label: service.clone(),
+        text,
+    };
+    let mut child = match command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(err) => {
+            let _ = tx.send(line(format!("failed to start ssh: {err}"), now_ms()));
+            return;
+        }
+    };
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    for (stream, is_journal) in [
+        (stdout.map(|s| Box::new(s) as Box<dyn Read + Send>), true),
+        (stderr.map(|s| Box::new(s) as Box<dyn Read + Send>), false),
+    ] {
+        let Some(stream) = stream else {
+            continue;
+        };
+        let (tx, make) = (tx.clone(), line.clone());
+        thread::spawn(move || {
+            for raw in BufReader::new(stream).lines().map_while(Result::ok) {
+                let (ts_ms, text) = if is_journal {
+                    parse_journal_line(&raw).unwrap_or_else(|| (now_ms(), raw.clone()))
+                } else {
+                    (now_ms(), format!("ssh: {raw}"))
+                };
+                if tx.send(make(text, ts_ms)).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+
+    loop {
+        if stop.load(Ordering::SeqCst) {
+            let _ = child.kill();
+            break;
+        }
+        if let Ok(Some(status)) = child.try_wait() {
+            let _ = tx.send(line(format!("journal stream ended ({status})"), now_ms()));
+            break;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    let _ = child.wait();
+}
+
+/// Split a `journalctl -o short-unix` line (`1697040000.123456 host unit[42]: message`) into
+/// its timestamp and `unit[42]: message`.
+fn parse_journal_line(raw: &str) -> Option<(u64, String)> {
+    let (stamp, rest) = raw.split_once(' ')?;
+    let seconds: f64 = stamp.parse().ok()?;
+    let message = rest
+        .split_once(' ')
+        .map_or(rest, |(_host, message)| message);
+    Some(((seconds * 1000.0) as u64, message.to_string()))
+}
+
+/// Poll the newest proxy trace buffer for 5xx records written since the tail started.
+fn follow_proxy_errors(tx: Sender<TailLine>, stop: Arc<AtomicBool>) {
+    let mut current: Option<(PathBuf, TraceBuffer)> = None;
+    // Records below `next` were reported; `seen` is the write index of the previous poll.
+    // Slots are claimed before they are written, so a poll only reads up to the last one's
+    // index.
+    let (mut next, mut seen) = (0u64, 0u64);
+    while !stop.load(Ordering::SeqCst) {
+        let latest = trace::latest_trace_file(&trace::default_trace_dir());
+        if latest.as_ref() != current.as_ref().map(|(path, _)| path) {
+            current = latest.and_then(|path| Some((path.clone(), TraceBuffer::open(&path)?)));
+            let start = current
+                .as_ref()
+                .map_or(0, |(_, buffer)| buffer.write_index());
+            (next, seen) = (start, start);
+        }
+        if let Some((_, buffer)) = &current {
+            let end = buffer.write_index();
+            let from = next.max(seen.saturating_sub(buffer.capacity()));
+            for idx in from..seen {
+                let record = buffer.read(idx);
+                if record.timestamp() == 0 || record.status() < 500 {
+                    continue;
+                }
+                let text = format!(
+                    "{} {} -> {} in {}ms trace={}",
+                    format!("{:?}", record.method()).to_uppercase(),
+                    record.path(),
+                    record.status(),
+                    record.latency_us() / 1000,
+                    trace::format_trace_id(record.trace_id()),
+                );
+                let line = TailLine {
+                    ts_ms: monotonic_to_unix_ms(record.timestamp()),
+                    source: TailSource::Proxy,
+                    label: String::new(),
+                    text,
+                };
+                if tx.send(line).is_err() {
+                    return;
+                }
+            }
+            (next, seen) = (seen, end);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Trace records carry `CLOCK_MONOTONIC` timestamps; shift them onto the wall clock.
+fn monotonic_to_unix_ms(ts_ns: u64) -> u64 {
+    let age_ms = trace::now_ns().saturating_sub(ts_ns) / 1_000_000;
+    now_ms().saturating_sub(age_ms)
+}
+
+/// Parse `--source`-style comma-separated names, as used by the log server's query string.
+pub fn parse_sources(raw: &str) -> Result<Vec<TailSource>> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            TailSource::from_str(name, true).map_err(|_| {
+                anyhow::anyhow!("unknown source '{name}' (expected task, journal, or proxy)")
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(ts_ms: u64, text: &str) -> TailLine {
+        TailLine {
+            ts_ms,
+            source: TailSource::Task,
+            label: "web".to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn reorder_releases_lines_in_timestamp_order() {
+        let start = Instant::now();
+        let window = Duration::from_millis(300);
+        let mut reorder = Reorder::new(window);
+        reorder.push(line(2_000, "local"), start);
+        reorder.push(
+            line(1_500, "remote, delivered late"),
+            start + Duration::from_millis(100),
+        );
+        reorder.push(line(9_000, "newest"), start + Duration::from_millis(200));
+
+        assert!(reorder.ready(start + Duration::from_millis(100)).is_empty());
+        let texts: Vec<_> = reorder
+            .ready(start + window)
+            .into_iter()
+            .map(|line| line.text)
+            .collect();
+        assert_eq!(texts, ["remote, delivered late", "local"]);
+        assert_eq!(reorder.drain(), vec![line(9_000, "newest")]);
+    }
+
+    #[test]
+    fn parses_journal_lines_and_filters() {
+        assert_eq!(
+            parse_journal_line("1697040000.123456 web-1 myapp[42]: listening on :3000"),
+            Some((
+                1_697_040_000_123,
+                "myapp[42]: listening on :3000".to_string()
+            ))
+        );
+        assert_eq!(parse_journal_line("-- No entries --"), None);
+
+        let filter = TailFilter {
+            sources: vec![TailSource::Proxy],
+            grep: Some("TIMEOUT".to_string()),
+            ..Default::default()
+        };
+        assert!(!filter.wants(TailSource::Task));
+        assert!(filter.matches(&line(0, "upstream timeout after 30s")));
+        assert!(!filter.matches(&line(0, "ok")));
+        assert_eq!(
+            parse_sources("task, proxy").unwrap(),
+            [TailSource::Task, TailSource::Proxy]
+        );
+        assert!(parse_sources("syslog").is_err());
+    }
+}
@@ -0,0 +1,428 @@
+//! Input-hash caching for tasks that declare `inputs` (and optionally `outputs`).
+//!
+//! After a successful run the content hash of the inputs is stored in the `task_cache` table
+//! of flow.db and the outputs are snapshotted under `~/.config/flow/task-cache`. A later run
+//! with the same input hash is skipped: outputs that still match are reported as up to date,
+//! outputs that changed or disappeared are restored from the snapshot.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use ignore::WalkBuilder;
+use ignore::overrides::OverrideBuilder;
+use rusqlite::{Connection, OptionalExtension, params};
+use sha2::{Digest, Sha256};
+
+use crate::config::{self, TaskConfig};
+use crate::config_interp::ShellCommand;
+use crate::{db, running};
+
+/// Set to `1` to ignore task caches and always run.
+pub const DISABLE_ENV: &str = "FLOW_NO_TASK_CACHE";
+
+/// Snapshots kept per task; older ones are pruned after each store.
+const MAX_ENTRIES_PER_TASK: usize = 3;
+
+/// Outcome of checking the cache before running a task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheStatus {
+    /// Inputs unchanged and outputs already match the cached run.
+    UpToDate,
+    /// Inputs unchanged; these outputs were restored from the snapshot.
+    Restored(Vec<String>),
+    /// No usable entry; the task has to run.
+    Miss,
+}
+
+/// Whether `task` opts into caching (and caching is not disabled).
+pub fn enabled_for(task: &TaskConfig) -> bool {
+    !task.inputs.is_empty()
+        && task.host.is_none()
+        && !std::env::var(DISABLE_ENV)
+            .map(|value| matches!(value.trim(), "1" | "true" | "yes"))
+            .unwrap_or(false)
+}
+
+/// Hash of the interpolated command (including the `${env:..}`/`${envstore:..}` values it
+/// resolved to), its arguments, and every file matched by the input globs.
+pub fn input_hash(
+    project_root: &Path,
+    task: &TaskConfig,
+    command: &ShellCommand,
+    args: &[String],
+) -> Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(command.command.as_bytes());
+    hasher.update([0]);
+    for (key, value) in command.env.iter().chain(&command.inherited) {
+        hasher.update(key.as_bytes());
+        hasher.update([b'=']);
+        hasher.update(value.as_bytes());
+        hasher.update([0]);
+    }
+    for arg in args {
+        hasher.update(arg.as_bytes());
+        hasher.update([0]);
+    }
+    let files = input_files(project_root, &task.inputs)?;
+    for relative in &files {
+        let content = fs::read(project_root.join(relative))
+            .with_context(|| format!("failed to read input {}", relative))?;
+        hash_file(&mut hasher, relative, &content);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Relative paths (sorted) of the files matched by `patterns`. Gitignored files are skipped.
+fn input_files(project_root: &Path, patterns: &[String]) -> Result<Vec<String>> {
+    let mut overrides = OverrideBuilder::new(project_root);
+    for pattern in patterns {
+        overrides
+            .add(pattern.trim_start_matches("./"))
+            .with_context(|| format!("invalid input glob '{}'", pattern))?;
+    }
+    let overrides = overrides.build()?;
+
+    let mut files = Vec::new();
+    let walker = WalkBuilder::new(project_root)
+        .hidden(false)
+        .overrides(overrides)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+    for entry in walker {
+        let entry = entry?;
+        if !entry.file_type().is_some_and(|kind| kind.is_file()) {
+            continue;
+        }
+        if let Ok(relative) = entry.path().strip_prefix(project_root) {
+            files.push(relative.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn hash_file(hasher: &mut Sha256, relative: &str, content: &[u8]) {
+    hasher.update(relative.as_bytes());
+    hasher.update([0]);
+    hasher.update((content.len() as u64).to_le_bytes());
+    hasher.update(content);
+}
+
+/// Hash of the declared outputs as they exist on disk; missing outputs hash as absent.
+fn output_hash(project_root: &Path, outputs: &[String]) -> Result<String> {
+    let mut hasher = Sha256::new();
+    for output in outputs {
+        let path = project_root.join(output);
+        hasher.update(output.as_bytes());
+        if !path.exists() {
+            hasher.update(b"\0missing");
+            continue;
+        }
+        let mut files = Vec::new();
+        collect_files(&path, &path, &mut files)?;
+        files.sort();
+        for relative in files {
+            let content = fs::read(path.join(&relative))?;
+            hash_file(&mut hasher, &relative, &content);
+        }
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn collect_files(root: &Path, path: &Path, out: &mut Vec<String>) -> Result<()> {
+    if path.is_file() {
+        let relative = path.strip_prefix(root).unwrap_or(Path::new(""));
+        out.push(relative.to_string_lossy().replace('\\', "/"));
+        return Ok(());
+    }
+    for entry in fs::read_dir(path).with_context(|| format!("failed to read {}", path.display()))? {
+        collect_files(root, &entry?.path(), out)?;
+    }
+    Ok(())
+}
+
+fn cache_root() -> PathBuf {
+    config::global_config_dir().join("task-cache")
+}
+
+fn entry_dir(project_root: &Path, task: &str, input_hash: &str) -> PathBuf {
+    let mut hasher = Sha256::new();
+    hasher.update(project_root.to_string_lossy().as_bytes());
+    hasher.update([0]);
+    hasher.update(task.as_bytes());
+    let scope = hex::encode(hasher.finalize());
+    cache_root().join(&scope[..16]).join(&input_hash[..16])
+}
+
+fn init_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS task_cache (
+            project_root TEXT NOT NULL,
+            task TEXT NOT NULL,
+            input_hash TEXT NOT NULL,
+            output_hash TEXT NOT NULL,
+            created_ms INTEGER NOT NULL,
+            PRIMARY KEY (project_root, task, input_hash)
+        );
+        "#,
+    )
+    .context("failed to create task_cache schema")?;
+    Ok(())
+}
+
+fn open_cache_db() -> Result<Connection> {
+    let conn = db::open_db()?;
+    init_schema(&conn)?;
+    Ok(conn)
+}
+
+fn project_key(project_root: &Path) -> String {
+    project_root
+        .canonicalize()
+        .unwrap_or_else(|_| project_root.to_path_buf())
+        .display()
+        .to_string()
+}
+
+/// Check for a cached run with `input_hash`, restoring outputs from the snapshot if needed.
+pub fn check(project_root: &Path, task: &TaskConfig, input_hash: &str) -> Result<CacheStatus> {
+    let conn = open_cache_db()?;
+    let root = project_key(project_root);
+    let stored: Option<String> = conn
+        .query_row(
+            "SELECT output_hash FROM task_cache WHERE project_root = ?1 AND task = ?2 AND input_hash = ?3",
+            params![root, task.name, input_hash],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let Some(stored) = stored else {
+        return Ok(CacheStatus::Miss);
+    };
+
+    if output_hash(project_root, &task.outputs)? == stored {
+        return Ok(CacheStatus::UpToDate);
+    }
+
+    let snapshot = entry_dir(Path::new(&root), &task.name, input_hash);
+    if !snapshot.exists() {
+        return Ok(CacheStatus::Miss);
+    }
+    let mut restored = Vec::new();
+    for output in &task.outputs {
+        let cached = snapshot.join(output);
+        if !cached.exists() {
+            continue;
+        }
+        let target = project_root.join(output);
+        if target.is_dir() {
+            fs::remove_dir_all(&target)?;
+        } else if target.exists() {
+            fs::remove_file(&target)?;
+        }
+        copy_path(&cached, &target)?;
+        restored.push(output.clone());
+    }
+    if output_hash(project_root, &task.outputs)? != stored {
+        return Ok(CacheStatus::Miss);
+    }
+    Ok(CacheStatus::Restored(restored))
+}
+
+/// Record a successful run: snapshot its outputs and store the input hash.
+pub fn store(project_root: &Path, task: &TaskConfig, input_hash: &str) -> Result<()> {
+    for output in &task.outputs {
+        if !project_root.join(output).exists() {
+            bail!(
+                "task '{}' declares output '{}' but it was not produced",
+                task.name,
+                output
+            );
+        }
+    }
+
+    let root = project_key(project_root);
+    let snapshot = entry_dir(Path::new(&root), &task.name, input_hash);
+    if snapshot.exists() {
+        fs::remove_dir_all(&snapshot)?;
+    }
+    for output in &task.outputs {
+        copy_path(&project_root.join(output), &snapshot.join(output))?;
+    }
+
+    let conn = open_cache_db()?;
+    conn.execute(
+        "INSERT OR REPLACE INTO task_cache (project_root, task, input_hash, output_hash, created_ms) \
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            root,
+            task.name,
+            input_hash,
+            output_hash(project_root, &task.outputs)?,
+            running::now_ms() as i64
+        ],
+    )?;
+    prune(&conn, &root, &task.name)
+}
+
+fn prune(conn: &Connection, root: &str, task: &str) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT input_hash FROM task_cache WHERE project_root = ?1 AND task = ?2 \
+         ORDER BY created_ms DESC LIMIT -1 OFFSET ?3",
+    )?;
+    let stale: Vec<String> = stmt
+        .query_map(params![root, task, MAX_ENTRIES_PER_TASK as i64], |row| {
+            row.get(0)
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    for input_hash in stale {
+        let _ = fs::remove_dir_all(entry_dir(Path::new(root), task, &input_hash));
+        conn.execute(
+            "DELETE FROM task_cache WHERE project_root = ?1 AND task = ?2 AND input_hash = ?3",
+            params![root, task, input_hash],
+        )?;
+    }
+    Ok(())
+}
+
+/// Drop cached runs for a project (or one task in it). Returns the number of entries removed.
+pub fn clear(project_root: &Path, task: Option<&str>) -> Result<usize> {
+    let conn = open_cache_db()?;
+    let root = project_key(project_root);
+    let mut stmt = conn.prepare(
+        "SELECT task, input_hash FROM task_cache WHERE project_root = ?1 AND (?2 IS NULL OR task = ?2)",
+    )?;
+    let entries: Vec<(String, String)> = stmt
+        .query_map(params![root, task], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    for (name, input_hash) in &entries {
+        let _ = fs::remove_dir_all(entry_dir(Path::new(&root), name, input_hash));
+    }
+    conn.execute(
+        "DELETE FROM task_cache WHERE project_root = ?1 AND (?2 IS NULL OR task = ?2)",
+        params![root, task],
+    )?;
+    Ok(entries.len())
+}
+
+fn copy_path(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_path(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(from, to)
+            .with_context(|| format!("failed to copy {} to {}", from.display(), to.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn task(inputs: &[&str]) -> TaskConfig {
+        TaskConfig {
+            name: "build".to_string(),
+            command: "cargo build".to_string(),
+            delegate_to_hub: false,
+            activate_on_cd_to_root: false,
+            dependencies: Vec::new(),
+            description: None,
+            shortcuts: Vec::new(),
+            interactive: false,
+            confirm_on_match: false,
+            confirm: None,
+            protected: false,
+            on_cancel: None,
+            output_file: None,
+            host: None,
+            remote_dir: None,
+            remote_env: Vec::new(),
+            inputs: inputs.iter().map(|s| s.to_string()).collect(),
+            outputs: Vec::new(),
+            port: None,
+            matrix: Default::default(),
+            matrix_parallel: false,
+            env_files: None,
+            retries: 0,
+            retry_backoff: None,
+            retry_exponential: false,
+            prompt: Vec::new(),
+            cwd: None,
+            umask: None,
+            nice: None,
+            max_memory: None,
+            cpu: None,
+            memory: None,
+            git_note: false,
+            parser: None,
+            before: Vec::new(),
+            after: Vec::new(),
+            on_failure: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn input_hash_tracks_matched_file_contents() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(dir.path().join("README.md"), "docs").unwrap();
+        fs::write(dir.path().join("Cargo.lock"), "lock").unwrap();
+        let task = task(&["src/**", "Cargo.lock"]);
+
+        assert_eq!(
+            input_files(dir.path(), &task.inputs).unwrap(),
+            vec!["Cargo.lock", "src/main.rs"]
+        );
+        let command = ShellCommand {
+            command: task.command.clone(),
+            ..Default::default()
+        };
+        let before = input_hash(dir.path(), &task, &command, &[]).unwrap();
+        fs::write(dir.path().join("README.md"), "changed docs").unwrap();
+        assert_eq!(
+            input_hash(dir.path(), &task, &command, &[]).unwrap(),
+            before
+        );
+        fs::write(dir.path().join("src/main.rs"), "fn main() { }").unwrap();
+        let after = input_hash(dir.path(), &task, &command, &[]).unwrap();
+        assert_ne!(after, before);
+        assert_ne!(
+            input_hash(dir.path(), &task, &command, &["--release".to_string()]).unwrap(),
+            after
+        );
+
+        let with_value = |value: &str| ShellCommand {
+            command: "cargo build --profile ${PROFILE}".to_string(),
+            inherited: vec![("PROFILE".to_string(), value.to_string())],
+            ..Default::default()
+        };
+        assert_ne!(
+            input_hash(dir.path(), &task, &with_value("dev"), &[]).unwrap(),
+            input_hash(dir.path(), &task, &with_value("release"), &[]).unwrap()
+        );
+    }
+
+    #[test]
+    fn output_hash_distinguishes_missing_outputs() {
+        let dir = tempdir().unwrap();
+        let outputs = vec!["dist".to_string()];
+        let missing = output_hash(dir.path(), &outputs).unwrap();
+        fs::create_dir_all(dir.path().join("dist")).unwrap();
+        fs::write(dir.path().join("dist/app.js"), "app").unwrap();
+        let present = output_hash(dir.path(), &outputs).unwrap();
+        assert_ne!(missing, present);
+        fs::write(dir.path().join("dist/app.js"), "app2").unwrap();
+        assert_ne!(output_hash(dir.path(), &outputs).unwrap(), present);
+    }
+}
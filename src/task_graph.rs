@@ -0,0 +1,800 @@
+//! `f tasks graph`: how a project's tasks, entry points, services, and gates connect.
+//!
+//! flow.toml has no explicit task dependencies, so edges come from what flow actually runs:
+//! task commands that call other tasks (`f build && f test`), entry points such as `f up`
+//! (`[lifecycle] up_task`), `f deploy` (`[flow] deploy_task`), or `[flow] on_branch_change`,
+//! `[diagnose]` fixes, and the checks `f commit` applies. Tasks are grouped by the flow.toml that defines them.
+//!
+//! The graph renders as Graphviz DOT or Mermaid for docs, or as a TUI tree where Enter runs
+//! the selected task.
+
+use std::collections::HashSet;
+use std::io::{self, IsTerminal};
+
+use anyhow::{Context, Result, bail};
+use crossterm::{
+    event::{self, Event as CEvent, KeyCode, KeyEvent},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use ratatui::{
+    Terminal,
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+};
+
+use crate::cli::{TaskGraphFormat, TaskRunOpts, TasksGraphOpts};
+use crate::config::{self, Config};
+use crate::discover::DiscoveredTask;
+use crate::project_snapshot::ProjectSnapshot;
+
+/// Deepest nesting shown under a node in the TUI tree.
+const MAX_TREE_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum NodeKind {
+    Entry,
+    Task,
+    Service,
+    Gate,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    kind: NodeKind,
+    label: String,
+    group: String,
+    /// Extra lines for the TUI detail pane (command, description, port, ...).
+    detail: Vec<String>,
+    /// Index into the discovered tasks, for task nodes.
+    task: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Edge {
+    from: usize,
+    to: usize,
+    label: &'static str,
+}
+
+#[derive(Debug, Default)]
+struct Graph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+pub fn run(opts: TasksGraphOpts) -> Result<()> {
+    let snapshot = ProjectSnapshot::from_task_config_tasks_only(&opts.config, true)?;
+    let root_config = snapshot.root.join("flow.toml");
+    let cfg = if root_config.exists() {
+        config::load(&root_config)
+            .with_context(|| format!("failed to load {}", root_config.display()))?
+    } else {
+        Config::default()
+    };
+    let tasks = &snapshot.discovery.tasks;
+    let graph = build(tasks, &cfg);
+    if graph.nodes.is_empty() {
+        println!("No tasks defined in {}", snapshot.root.display());
+        return Ok(());
+    }
+
+    let format = opts.format.unwrap_or(if io::stdout().is_terminal() {
+        TaskGraphFormat::Tui
+    } else {
+        TaskGraphFormat::Mermaid
+    });
+    match format {
+        TaskGraphFormat::Dot => print!("{}", render_dot(&graph)),
+        TaskGraphFormat::Mermaid => print!("{}", render_mermaid(&graph)),
+        TaskGraphFormat::Tui => {
+            if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+                bail!("the graph TUI needs a terminal; use --format dot or --format mermaid");
+            }
+            if let Some(task) = browse(&graph)? {
+                let discovered = &tasks[task];
+                return crate::tasks::run(TaskRunOpts {
+                    config: discovered.config_path.clone(),
+                    delegate_to_hub: false,
+                    hub_host: std::net::IpAddr::from([127, 0, 0, 1]),
+                    hub_port: 9050,
+                    priority: 0,
+                    print_env: false,
+                    yes: false,
+                    name: discovered.task.name.clone(),
+                    args: Vec::new(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn build(tasks: &[DiscoveredTask], cfg: &Config) -> Graph {
+    let mut graph = Graph::default();
+    for (idx, discovered) in tasks.iter().enumerate() {
+        let task = &discovered.task;
+        let mut detail = vec![format!("$ {}", task.command)];
+        detail.extend(task.description.clone());
+        if let Some(port) = task.port {
+            detail.push(format!("serves on port {port}"));
+        }
+        if !task.shortcuts.is_empty() {
+            detail.push(format!("shortcuts: {}", task.shortcuts.join(", ")));
+        }
+        detail.push(discovered.config_path.display().to_string());
+        graph.nodes.push(Node {
+            kind: NodeKind::Task,
+            label: task.name.clone(),
+            group: group_name(discovered),
+            detail,
+            task: Some(idx),
+        });
+    }
+
+    for (idx, discovered) in tasks.iter().enumerate() {
+        for selector in called_tasks(&discovered.task.command) {
+            if let Some(target) = find_task(tasks, &selector, Some(discovered))
+                && target != idx
+            {
+                graph.add_edge(idx, target, "runs");
+            }
+        }
+    }
+
+    let lifecycle = cfg.lifecycle.as_ref();
+    let entries = [
+        (
+            "f up",
+            lifecycle.and_then(|l| l.up_task.clone()),
+            &["up", "dev"][..],
+        ),
+        (
+            "f down",
+            lifecycle.and_then(|l| l.down_task.clone()),
+            &["down"][..],
+        ),
+        ("f deploy", cfg.flow.deploy_task.clone(), &[][..]),
+        ("f release", cfg.flow.release_task.clone(), &[][..]),
+        ("primary task", cfg.flow.primary_task.clone(), &[][..]),
+    ];
+    for (label, configured, fallbacks) in entries {
+        let target = match configured {
+            Some(name) => find_task(tasks, &name, None),
+            None => fallbacks
+                .iter()
+                .find_map(|name| find_task(tasks, name, None)),
+        };
+        if let Some(target) = target {
+            let entry = graph.add_node(NodeKind::Entry, label, "entry points", Vec::new());
+            graph.add_edge(entry, target, "runs");
+        }
+    }
+    if !cfg.flow.on_branch_change.is_empty() {
+        let entry = graph.add_node(NodeKind::Entry, "branch change", "entry points", Vec::new());
+        for name in &cfg.flow.on_branch_change {
+            if let Some(target) = find_task(tasks, name, None) {
+                graph.add_edge(entry, target, "runs");
+            }
+        }
+    }
+    if let Some(diagnose) = &cfg.diagnose {
+        let mut fixes: Vec<_> = diagnose.fixes.iter().collect();
+        fixes.sort();
+        for (signature, task) in fixes {
+            if let Some(target) = find_task(tasks, task, None) {
+                let entry = graph.add_node(
+                    NodeKind::Entry,
+                    &format!("f diagnose: {signature}"),
+                    "entry points",
+                    Vec::new(),
+                );
+                graph.add_edge(entry, target, "fixes");
+            }
+        }
+    }
+
+    for server in &cfg.servers {
+        let command = format!("$ {} {}", server.command, server.args.join(" "));
+        let mut detail = vec![command.trim_end().to_string()];
+        detail.extend(server.port.map(|port| format!("port {port}")));
+        graph.add_node(NodeKind::Service, &server.name, "services", detail);
+    }
+    for daemon in &cfg.daemons {
+        let mut detail = vec![format!("binary: {}", daemon.binary)];
+        detail.extend(daemon.port.map(|port| format!("port {port}")));
+        detail.push(format!("f daemon start {}", daemon.name));
+        graph.add_node(NodeKind::Service, &daemon.name, "services", detail);
+    }
+
+    if let Some(commit) = &cfg.commit {
+        let mut gates: Vec<(String, Vec<String>)> = Vec::new();
+        if let Some(quality) = &commit.quality {
+            let mode = quality.mode.as_deref().unwrap_or("warn");
+            gates.push((format!("quality ({mode})"), Vec::new()));
+        }
+        if let Some(testing) = &commit.testing {
+            let mode = testing.mode.as_deref().unwrap_or("warn");
+            let runner = testing.runner.as_deref().unwrap_or("bun");
+            gates.push((format!("tests ({mode})"), vec![format!("runner: {runner}")]));
+        }
+        if let Some(skills) = &commit.skill_gate {
+            let mode = skills.mode.as_deref().unwrap_or("warn");
+            gates.push((
+                format!("skills ({mode})"),
+                vec![format!("required: {}", skills.required.join(", "))],
+            ));
+        }
+        gates.push(("secret scan".to_string(), Vec::new()));
+        if let Some(pattern) = commit
+            .message
+            .as_ref()
+            .and_then(|message| message.require_ticket.as_ref())
+        {
+            gates.push((
+                "ticket reference".to_string(),
+                vec![format!("pattern: {pattern}")],
+            ));
+        }
+        let entry = graph.add_node(NodeKind::Entry, "f commit", "entry points", Vec::new());
+        for (label, detail) in gates {
+            let gate = graph.add_node(NodeKind::Gate, &label, "commit gates", detail);
+            graph.add_edge(entry, gate, "checks");
+        }
+    }
+
+    graph
+}
+
+impl Graph {
+    fn add_node(&mut self, kind: NodeKind, label: &str, group: &str, detail: Vec<String>) -> usize {
+        self.nodes.push(Node {
+            kind,
+            label: label.to_string(),
+            group: group.to_string(),
+            detail,
+            task: None,
+        });
+        self.nodes.len() - 1
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize, label: &'static str) {
+        let edge = Edge { from, to, label };
+        if !self.edges.contains(&edge) {
+            self.edges.push(edge);
+        }
+    }
+
+    /// Groups in display order (entry points, task scopes, services, gates), each with its
+    /// node indices.
+    fn groups(&self) -> Vec<(&str, Vec<usize>)> {
+        let mut groups: Vec<(&str, NodeKind, Vec<usize>)> = Vec::new();
+        for (idx, node) in self.nodes.iter().enumerate() {
+            match groups.iter_mut().find(|(name, ..)| *name == node.group) {
+                Some((_, _, members)) => members.push(idx),
+                None => groups.push((node.group.as_str(), node.kind, vec![idx])),
+            }
+        }
+        groups.sort_by_key(|(_, kind, _)| *kind);
+        groups
+            .into_iter()
+            .map(|(name, _, members)| (name, members))
+            .collect()
+    }
+
+    fn outgoing(&self, node: usize) -> impl Iterator<Item = &Edge> {
+        self.edges.iter().filter(move |edge| edge.from == node)
+    }
+
+    /// The task an Enter on this node runs: the task itself, or an entry point's target.
+    fn runnable_task(&self, node: usize) -> Option<usize> {
+        match self.nodes[node].kind {
+            NodeKind::Task => self.nodes[node].task,
+            NodeKind::Entry => self
+                .outgoing(node)
+                .find_map(|edge| self.nodes[edge.to].task),
+            NodeKind::Service | NodeKind::Gate => None,
+        }
+    }
+}
+
+fn group_name(discovered: &DiscoveredTask) -> String {
+    if discovered.relative_dir.is_empty() {
+        "flow.toml".to_string()
+    } else {
+        format!("{}/flow.toml", discovered.relative_dir)
+    }
+}
+
+/// Task selectors invoked as `f <task>`, `f run <task>`, or `flow run <task>` in a command.
+fn called_tasks(command: &str) -> Vec<String> {
+    let mut called = Vec::new();
+    for segment in command.split(['\n', ';', '&', '|', '(', ')']) {
+        let mut words = segment
+            .split_whitespace()
+            .skip_while(|word| word.contains('=') && !word.starts_with('-'));
+        if !matches!(words.next(), Some("f" | "flow")) {
+            continue;
+        }
+        let selector = match words.next() {
+            Some("run") => words.next(),
+            other => other,
+        };
+        if let Some(selector) = selector.map(|word| word.trim_matches(['"', '\'']))
+            && !selector.is_empty()
+            && !selector.starts_with('-')
+        {
+            called.push(selector.to_string());
+        }
+    }
+    called
+}
+
+/// Resolve a selector (`name`, shortcut, or `scope:name`), preferring tasks from the
+/// caller's flow.toml, then the root one.
+fn find_task(
+    tasks: &[DiscoveredTask],
+    selector: &str,
+    caller: Option<&DiscoveredTask>,
+) -> Option<usize> {
+    if let Some((scope, name)) = crate::tasks::parse_scoped_selector(selector)
+        && let Some(idx) = tasks.iter().position(|discovered| {
+            discovered.matches_scope(&scope)
+                && crate::tasks::task_matches_selector(discovered, &name)
+        })
+    {
+        return Some(idx);
+    }
+    let matches: Vec<usize> = tasks
+        .iter()
+        .enumerate()
+        .filter(|(_, discovered)| crate::tasks::task_matches_selector(discovered, selector))
+        .map(|(idx, _)| idx)
+        .collect();
+    caller
+        .and_then(|caller| {
+            matches
+                .iter()
+                .copied()
+                .find(|idx| tasks[*idx].config_path == caller.config_path)
+        })
+        .or_else(|| {
+            matches
+                .iter()
+                .copied()
+                .find(|idx| tasks[*idx].relative_dir.is_empty())
+        })
+        .or_else(|| matches.first().copied())
+}
+
+fn render_dot(graph: &Graph) -> String {
+    let mut out = String::from("digraph flow {\n  rankdir=LR;\n  node [fontname=\"Helvetica\"];\n");
+    for (group_idx, (group, members)) in graph.groups().into_iter().enumerate() {
+        out.push_str(&format!(
+            "  subgraph cluster_{group_idx} {{\n    label=\"{}\";\n",
+            dot_escape(group)
+        ));
+        for idx in members {
+            let shape = match graph.nodes[idx].kind {
+                NodeKind::Entry => "oval",
+                NodeKind::Task => "box",
+                NodeKind::Service => "cylinder",
+                NodeKind::Gate => "diamond",
+            };
+            out.push_str(&format!(
+                "    n{idx} [label=\"{}\", shape={shape}];\n",
+                dot_escape(&graph.nodes[idx].label)
+            ));
+        }
+        out.push_str("  }\n");
+    }
+    for edge in &graph.edges {
+        out.push_str(&format!(
+            "  n{} -> n{} [label=\"{}\"];\n",
+            edge.from, edge.to, edge.label
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn render_mermaid(graph: &Graph) -> String {
+    let mut out = String::from("flowchart LR\n");
+    for (group_idx, (group, members)) in graph.groups().into_iter().enumerate() {
+        out.push_str(&format!(
+            "  subgraph g{group_idx}[\"{}\"]\n",
+            mermaid_escape(group)
+        ));
+        for idx in members {
+            let label = mermaid_escape(&graph.nodes[idx].label);
+            let node = match graph.nodes[idx].kind {
+                NodeKind::Entry => format!("n{idx}([\"{label}\"])"),
+                NodeKind::Task => format!("n{idx}[\"{label}\"]"),
+                NodeKind::Service => format!("n{idx}[(\"{label}\")]"),
+                NodeKind::Gate => format!("n{idx}{{\"{label}\"}}"),
+            };
+            out.push_str(&format!("    {node}\n"));
+        }
+        out.push_str("  end\n");
+    }
+    for edge in &graph.edges {
+        out.push_str(&format!(
+            "  n{} -->|{}| n{}\n",
+            edge.from, edge.label, edge.to
+        ));
+    }
+    out
+}
+
+fn dot_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn mermaid_escape(value: &str) -> String {
+    value.replace('"', "#quot;")
+}
+
+// ---------------------------------------------------------------------------
+// TUI
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Row {
+    Group(String),
+    Node {
+        node: usize,
+        depth: usize,
+        via: Option<&'static str>,
+    },
+}
+
+/// Flatten the graph into tree rows: each group's nodes, with the nodes they lead to nested
+/// below them. Cycles stop at the first repeat on a path.
+fn tree_rows(graph: &Graph) -> Vec<Row> {
+    fn walk(
+        graph: &Graph,
+        node: usize,
+        depth: usize,
+        via: Option<&'static str>,
+        path: &mut HashSet<usize>,
+        rows: &mut Vec<Row>,
+    ) {
+        rows.push(Row::Node { node, depth, via });
+        if depth >= MAX_TREE_DEPTH || !path.insert(node) {
+            return;
+        }
+        for edge in graph.outgoing(node) {
+            walk(graph, edge.to, depth + 1, Some(edge.label), path, rows);
+        }
+        path.remove(&node);
+    }
+
+    let mut rows = Vec::new();
+    for (group, members) in graph.groups() {
+        rows.push(Row::Group(group.to_string()));
+        for node in members {
+            walk(graph, node, 1, None, &mut HashSet::new(), &mut rows);
+        }
+    }
+    rows
+}
+
+struct GraphApp<'a> {
+    graph: &'a Graph,
+    rows: Vec<Row>,
+    selected: usize,
+    message: Option<String>,
+}
+
+impl GraphApp<'_> {
+    fn current_node(&self) -> Option<usize> {
+        match self.rows.get(self.selected)? {
+            Row::Node { node, .. } => Some(*node),
+            Row::Group(_) => None,
+        }
+    }
+}
+
+/// Show the tree; returns the task to run when one is picked.
+fn browse(graph: &Graph) -> Result<Option<usize>> {
+    let rows = tree_rows(graph);
+    let mut app = GraphApp {
+        graph,
+        selected: rows
+            .iter()
+            .position(|row| matches!(row, Row::Node { .. }))
+            .unwrap_or(0),
+        rows,
+        message: None,
+    };
+
+    enable_raw_mode().context("failed to enable raw mode")?;
+    let mut stdout = std::io::stdout();
+    execute!(stdout, EnterAlternateScreen).context("failed to enter alternate screen")?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).context("failed to create terminal backend")?;
+
+    let app_result = run_app(&mut terminal, &mut app);
+
+    disable_raw_mode().ok();
+    let _ = terminal.show_cursor();
+    drop(terminal);
+    let mut stdout = std::io::stdout();
+    execute!(stdout, LeaveAlternateScreen).ok();
+
+    app_result
+}
+
+fn run_app<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut GraphApp<'_>,
+) -> Result<Option<usize>> {
+    loop {
+        terminal
+            .draw(|f| draw_ui(f, app))
+            .map_err(|err| anyhow::anyhow!("failed to draw task graph UI: {err}"))?;
+
+        if event::poll(std::time::Duration::from_millis(200))?
+            && let CEvent::Key(key) = event::read()?
+            && let Some(outcome) = handle_key(app, key)
+        {
+            return Ok(outcome);
+        }
+    }
+}
+
+/// `Some(outcome)` ends the TUI.
+fn handle_key(app: &mut GraphApp<'_>, key: KeyEvent) -> Option<Option<usize>> {
+    let last = app.rows.len().saturating_sub(1);
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => return Some(None),
+        KeyCode::Up | KeyCode::Char('k') => app.selected = app.selected.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => app.selected = (app.selected + 1).min(last),
+        KeyCode::Home | KeyCode::Char('g') => app.selected = 0,
+        KeyCode::End | KeyCode::Char('G') => app.selected = last,
+        KeyCode::Enter => {
+            match app
+                .current_node()
+                .and_then(|node| app.graph.runnable_task(node))
+            {
+                Some(task) => return Some(Some(task)),
+                None => app.message = Some("Nothing to run for this node.".to_string()),
+            }
+        }
+        _ => {}
+    }
+    None
+}
+
+fn draw_ui(f: &mut ratatui::Frame<'_>, app: &GraphApp<'_>) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(3)].as_ref())
+        .split(f.area());
+    let body = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)].as_ref())
+        .split(chunks[0]);
+
+    let items = app
+        .rows
+        .iter()
+        .map(|row| match row {
+            Row::Group(name) => ListItem::new(Line::from(Span::styled(
+                name.clone(),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ))),
+            Row::Node { node, depth, via } => {
+                let node = &app.graph.nodes[*node];
+                let mut spans = vec![Span::raw("  ".repeat(*depth))];
+                if let Some(via) = via {
+                    spans.push(Span::styled(
+                        format!("└ {via} "),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+                let color = match node.kind {
+                    NodeKind::Entry => Color::Magenta,
+                    NodeKind::Task => Color::Reset,
+                    NodeKind::Service => Color::Green,
+                    NodeKind::Gate => Color::Cyan,
+                };
+                spans.push(Span::styled(node.label.clone(), Style::default().fg(color)));
+                ListItem::new(Line::from(spans))
+            }
+        })
+        .collect::<Vec<_>>();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title("Task graph"))
+        .highlight_style(
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        );
+    let mut state = ListState::default();
+    state.select(Some(app.selected));
+    f.render_stateful_widget(list, body[0], &mut state);
+
+    let detail_lines: Vec<Line> = match app.current_node() {
+        Some(idx) => {
+            let node = &app.graph.nodes[idx];
+            let kind = match node.kind {
+                NodeKind::Entry => "entry point",
+                NodeKind::Task => "task",
+                NodeKind::Service => "service",
+                NodeKind::Gate => "gate",
+            };
+            let mut lines = vec![
+                Line::from(Span::styled(
+                    node.label.clone(),
+                    Style::default().add_modifier(Modifier::BOLD),
+                )),
+                Line::from(Span::styled(
+                    format!("{kind} · {}", node.group),
+                    Style::default().fg(Color::DarkGray),
+                )),
+                Line::from(""),
+            ];
+            lines.extend(node.detail.iter().map(|line| Line::from(line.clone())));
+            lines
+        }
+        None => Vec::new(),
+    };
+    let detail = Paragraph::new(detail_lines)
+        .block(Block::default().borders(Borders::ALL).title("Details"))
+        .wrap(Wrap { trim: false });
+    f.render_widget(detail, body[1]);
+
+    let footer = app
+        .message
+        .clone()
+        .unwrap_or_else(|| "↑/↓ move · Enter run task · q quit".to_string());
+    let footer = Paragraph::new(Line::from(footer)).block(Block::default().borders(Borders::ALL));
+    f.render_widget(footer, chunks[1]);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn discovered(toml: &str, relative_dir: &str) -> Vec<DiscoveredTask> {
+        let cfg: Config = toml::from_str(toml).expect("flow.toml should parse");
+        let dir = Path::new("/repo").join(relative_dir);
+        cfg.tasks
+            .into_iter()
+            .map(|task| DiscoveredTask {
+                task,
+                config_path: dir.join("flow.toml"),
+                relative_dir: relative_dir.to_string(),
+                depth: usize::from(!relative_dir.is_empty()),
+                scope: if relative_dir.is_empty() {
+                    "root".to_string()
+                } else {
+                    relative_dir.to_string()
+                },
+                scope_aliases: vec![if relative_dir.is_empty() {
+                    "root".to_string()
+                } else {
+                    relative_dir.to_string()
+                }],
+            })
+            .collect()
+    }
+
+    const ROOT: &str = r#"
+[lifecycle]
+up_task = "dev"
+
+[diagnose.fixes]
+module_not_found = "setup"
+
+[[servers]]
+name = "api"
+command = "cargo run"
+port = 8080
+
+[commit.secret_scan]
+ignore_paths = []
+
+[[tasks]]
+name = "setup"
+command = "bun install"
+
+[[tasks]]
+name = "dev"
+command = "f setup && FOO=1 f run web:build; bun dev"
+
+[[tasks]]
+name = "build"
+command = "echo root build"
+"#;
+
+    #[test]
+    fn links_task_calls_entry_points_and_gates() {
+        let mut tasks = discovered(ROOT, "");
+        tasks.extend(discovered(
+            "[[tasks]]\nname = \"build\"\ncommand = \"vite build\"\n",
+            "web",
+        ));
+        let cfg: Config = toml::from_str(ROOT).unwrap();
+        let graph = build(&tasks, &cfg);
+
+        let label = |edge: &Edge| {
+            (
+                graph.nodes[edge.from].label.as_str(),
+                graph.nodes[edge.to].label.as_str(),
+                graph.nodes[edge.to].group.as_str(),
+            )
+        };
+        let edges: Vec<_> = graph.edges.iter().map(label).collect();
+        assert!(edges.contains(&("dev", "setup", "flow.toml")));
+        assert!(edges.contains(&("dev", "build", "web/flow.toml")));
+        assert!(edges.contains(&("f up", "dev", "flow.toml")));
+        assert!(edges.contains(&("f diagnose: module_not_found", "setup", "flow.toml")));
+        assert!(edges.contains(&("f commit", "secret scan", "commit gates")));
+        assert!(
+            graph
+                .nodes
+                .iter()
+                .any(|node| node.kind == NodeKind::Service && node.label == "api")
+        );
+
+        let groups: Vec<_> = graph.groups().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(
+            groups,
+            [
+                "entry points",
+                "flow.toml",
+                "web/flow.toml",
+                "services",
+                "commit gates"
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_dot_and_mermaid() {
+        let tasks = discovered(ROOT, "");
+        let graph = build(&tasks, &Config::default());
+        let mermaid = render_mermaid(&graph);
+        assert!(mermaid.starts_with("flowchart LR\n"));
+        assert!(mermaid.contains("  subgraph g0[\"entry points\"]\n    n3([\"f up\"])\n"));
+        assert!(mermaid.contains("  subgraph g1[\"flow.toml\"]\n    n0[\"setup\"]\n"));
+        assert!(mermaid.contains("  n1 -->|runs| n0\n"));
+        assert!(mermaid.contains("  n3 -->|runs| n1\n"));
+
+        let dot = render_dot(&graph);
+        assert!(dot.contains("n1 [label=\"dev\", shape=box];"));
+        assert!(dot.contains("n1 -> n0 [label=\"runs\"];"));
+    }
+
+    #[test]
+    fn tree_nests_targets_and_stops_cycles() {
+        let tasks = discovered(
+            "[[tasks]]\nname = \"a\"\ncommand = \"f b\"\n\n[[tasks]]\nname = \"b\"\ncommand = \"f a\"\n",
+            "",
+        );
+        let graph = build(&tasks, &Config::default());
+        let rows = tree_rows(&graph);
+        let depths: Vec<_> = rows
+            .iter()
+            .filter_map(|row| match row {
+                Row::Node { node, depth, .. } => Some((graph.nodes[*node].label.as_str(), *depth)),
+                Row::Group(_) => None,
+            })
+            .collect();
+        assert_eq!(
+            depths,
+            [("a", 1), ("b", 2), ("a", 3), ("b", 1), ("a", 2), ("b", 3)]
+        );
+        assert_eq!(graph.runnable_task(0), Some(0));
+    }
+}
@@ -0,0 +1,246 @@
+//! Clean, timestamped task log capture.
+//!
+//! The terminal gets the child's raw output (colors, progress bars); the log file gets one
+//! line per completed output line, with escape sequences and carriage-return redraws
+//! resolved and a timestamp and stream tag in front:
+//!
+//! ```text
+//! 2026-03-02 14:05:11.482 out | ready on http://localhost:3000
+//! 2026-03-02 14:05:11.503 err | warning: unused variable `x`
+//! ```
+//!
+//! stdout and stderr share one writer, so lines land in the order they completed.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Force out a partial line once it grows past this (e.g. a `\r`-only progress bar).
+const MAX_PENDING_BYTES: usize = 64 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum LogStream {
+    Stdout,
+    Stderr,
+    /// Merged output of a pseudo-terminal.
+    Tty,
+}
+
+impl LogStream {
+    fn tag(self) -> &'static str {
+        match self {
+            LogStream::Stdout => "out",
+            LogStream::Stderr => "err",
+            LogStream::Tty => "tty",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+pub(crate) struct TaskLogCapture {
+    state: Mutex<CaptureState>,
+}
+
+struct CaptureState {
+    file: File,
+    pending: [Vec<u8>; 3],
+}
+
+impl TaskLogCapture {
+    /// Open `path` for appending and write a run header.
+    pub(crate) fn open(path: &Path, task_name: &str, command: &str) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(
+            file,
+            "\n--- {} | task:{} | cmd:{} ---",
+            crate::running::now_ms(),
+            task_name,
+            command
+        )?;
+        Ok(Self {
+            state: Mutex::new(CaptureState {
+                file,
+                pending: Default::default(),
+            }),
+        })
+    }
+
+    /// Record a raw chunk of output; complete lines are cleaned and written immediately.
+    pub(crate) fn write(&self, stream: LogStream, bytes: &[u8]) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let state = &mut *state;
+        let pending = &mut state.pending[stream.index()];
+        pending.extend_from_slice(bytes);
+
+        let mut out = String::new();
+        let mut start = 0;
+        while let Some(offset) = pending[start..].iter().position(|&b| b == b'\n') {
+            let end = start + offset;
+            push_entry(&mut out, stream, &pending[start..end]);
+            start = end + 1;
+        }
+        pending.drain(..start);
+        if pending.len() > MAX_PENDING_BYTES {
+            push_entry(&mut out, stream, pending);
+            pending.clear();
+        }
+        if !out.is_empty() {
+            let _ = state.file.write_all(out.as_bytes());
+            let _ = state.file.flush();
+        }
+    }
+
+    /// Write out any unterminated trailing lines.
+    pub(crate) fn finish(&self) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let state = &mut *state;
+        let mut out = String::new();
+        for stream in [LogStream::Stdout, LogStream::Stderr, LogStream::Tty] {
+            let pending = &mut state.pending[stream.index()];
+            if !pending.is_empty() {
+                push_entry(&mut out, stream, pending);
+                pending.clear();
+            }
+        }
+        if !out.is_empty() {
+            let _ = state.file.write_all(out.as_bytes());
+            let _ = state.file.flush();
+        }
+    }
+}
+
+fn push_entry(out: &mut String, stream: LogStream, raw: &[u8]) {
+    let raw = String::from_utf8_lossy(raw);
+    if let Some(line) = clean_line(&raw) {
+        let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
+        out.push_str(&format!("{timestamp} {} | {line}\n", stream.tag()));
+    }
+}
+
+/// What a terminal would leave visible for one output line: escape sequences removed,
+/// `\r` redraws keep only the final text, backspaces applied.
+///
+/// Returns `None` for lines that only carried escape sequences (cursor movement, screen
+/// clears) so they don't show up as blank log lines.
+pub(crate) fn clean_line(raw: &str) -> Option<String> {
+    let raw = raw.strip_suffix('\r').unwrap_or(raw);
+    let mut out = String::with_capacity(raw.len());
+    let mut carriage_return = false;
+    let mut chars = raw.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\x1b' => skip_escape(&mut chars),
+            '\r' => carriage_return = true,
+            '\x08' => {
+                out.pop();
+            }
+            '\t' => {
+                if std::mem::take(&mut carriage_return) {
+                    out.clear();
+                }
+                out.push('\t');
+            }
+            ch if ch.is_control() => {}
+            ch => {
+                if std::mem::take(&mut carriage_return) {
+                    out.clear();
+                }
+                out.push(ch);
+            }
+        }
+    }
+    if out.is_empty() && !raw.is_empty() {
+        return None;
+    }
+    Some(out.trim_end().to_string())
+}
+
+fn skip_escape(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    match chars.next() {
+        // CSI: parameters and intermediates, then one final byte in @..~.
+        Some('[') => {
+            for ch in chars.by_ref() {
+                if ('@'..='~').contains(&ch) {
+                    break;
+                }
+            }
+        }
+        // OSC, DCS, APC, PM: terminated by BEL or ST (ESC \).
+        Some(']' | 'P' | '_' | '^') => {
+            while let Some(ch) = chars.next() {
+                if ch == '\x07' {
+                    break;
+                }
+                if ch == '\x1b' {
+                    if chars.peek() == Some(&'\\') {
+                        chars.next();
+                    }
+                    break;
+                }
+            }
+        }
+        // Character set designation takes one more byte.
+        Some('(' | ')' | '*' | '+') => {
+            chars.next();
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cleans_colors_redraws_and_hyperlinks() {
+        assert_eq!(
+            clean_line("\x1b[1;32m✓\x1b[0m built in 1.2s\r").as_deref(),
+            Some("✓ built in 1.2s")
+        );
+        assert_eq!(
+            clean_line("  10%\r  55%\r 100% done").as_deref(),
+            Some(" 100% done")
+        );
+        assert_eq!(
+            clean_line("\x1b]8;;http://localhost:3000\x1b\\open\x1b]8;;\x07 app").as_deref(),
+            Some("open app")
+        );
+        assert_eq!(clean_line("ab\x08c").as_deref(), Some("ac"));
+        assert_eq!(clean_line("\x1b[2J\x1b[H"), None);
+        assert_eq!(clean_line("").as_deref(), Some(""));
+    }
+
+    #[test]
+    fn writes_timestamped_lines_in_completion_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs/dev.log");
+        let capture = TaskLogCapture::open(&path, "dev", "npm run dev").unwrap();
+
+        capture.write(LogStream::Stdout, b"\x1b[32mstarting");
+        capture.write(LogStream::Stderr, b"warn: slow\n");
+        capture.write(LogStream::Stdout, b"\x1b[0m\nready\n");
+        capture.write(LogStream::Stderr, b"partial");
+        capture.finish();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().skip(2).collect();
+        assert!(contents.contains("| task:dev | cmd:npm run dev ---"));
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].ends_with(" err | warn: slow"));
+        assert!(lines[1].ends_with(" out | starting"));
+        assert!(lines[2].ends_with(" out | ready"));
+        assert!(lines[3].ends_with(" err | partial"));
+        assert_eq!(lines[0].find(" err"), Some(23));
+    }
+}
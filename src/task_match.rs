@@ -273,6 +273,9 @@ fn run_with_tasks(
             delegate_to_hub: false,
             hub_host: "127.0.0.1".parse().unwrap(),
             hub_port: 9050,
+            priority: 0,
+            print_env: false,
+            yes: false,
             name: matched.task.name.clone(),
             args: task_args.clone(),
         };
@@ -486,8 +489,34 @@ mod tests {
                 shortcuts: Vec::new(),
                 interactive: false,
                 confirm_on_match: false,
+                confirm: None,
+                protected: false,
                 on_cancel: None,
                 output_file: None,
+                host: None,
+                remote_dir: None,
+                remote_env: Vec::new(),
+                inputs: Vec::new(),
+                outputs: Vec::new(),
+                port: None,
+                matrix: Default::default(),
+                matrix_parallel: false,
+                env_files: None,
+                retries: 0,
+                retry_backoff: None,
+                retry_exponential: false,
+                prompt: Vec::new(),
+                cwd: None,
+                umask: None,
+                nice: None,
+                max_memory: None,
+                cpu: None,
+                memory: None,
+                git_note: false,
+                parser: None,
+                before: Vec::new(),
+                after: Vec::new(),
+                on_failure: Vec::new(),
             },
             config_path: PathBuf::from("flow.toml"),
             relative_dir: String::new(),
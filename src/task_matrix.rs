@@ -0,0 +1,212 @@
+//! Matrix tasks: one task definition expanded into a run per combination of values.
+//!
+//! ```toml
+//! [[tasks]]
+//! name = "test"
+//! command = "mise exec node@{{matrix.node}} -- npm test"
+//! matrix.node = ["18", "20", "22"]
+//! ```
+//!
+//! Each combination ("cell") runs the command with `{{matrix.<key>}}` replaced by the
+//! cell's value. Results are aggregated into a single pass/fail summary table.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+
+/// One combination of matrix values, in key order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatrixCell {
+    pub values: Vec<(String, String)>,
+}
+
+impl MatrixCell {
+    /// Short label such as `node=20,os=linux`.
+    pub fn label(&self) -> String {
+        self.values
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Replace `{{matrix.<key>}}` (whitespace inside the braces allowed) with this cell's values.
+    pub fn interpolate(&self, command: &str) -> Result<String> {
+        let mut out = String::with_capacity(command.len());
+        let mut rest = command;
+        while let Some(start) = rest.find("{{") {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("}}") else {
+                out.push_str(&rest[start..]);
+                return Ok(out);
+            };
+            let expr = after[..end].trim();
+            match expr.strip_prefix("matrix.") {
+                Some(key) => match self.values.iter().find(|(k, _)| k == key) {
+                    Some((_, value)) => out.push_str(value),
+                    None => bail!("unknown matrix key '{}' in {{{{{}}}}}", key, expr),
+                },
+                // Not ours; leave other templating untouched.
+                None => out.push_str(&rest[start..start + 2 + end + 2]),
+            }
+            rest = &after[end + 2..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+}
+
+/// Expand the matrix into every combination of values; the last key varies fastest.
+pub fn expand(matrix: &BTreeMap<String, Vec<String>>) -> Vec<MatrixCell> {
+    let mut cells = vec![MatrixCell { values: Vec::new() }];
+    for (key, values) in matrix {
+        if values.is_empty() {
+            continue;
+        }
+        cells = cells
+            .into_iter()
+            .flat_map(|cell| {
+                values.iter().map(move |value| {
+                    let mut values = cell.values.clone();
+                    values.push((key.clone(), value.clone()));
+                    MatrixCell { values }
+                })
+            })
+            .collect();
+    }
+    cells.retain(|cell| !cell.values.is_empty());
+    cells
+}
+
+/// Outcome of one matrix cell.
+#[derive(Debug, Clone)]
+pub struct CellResult {
+    pub label: String,
+    pub success: bool,
+    pub status: Option<i32>,
+    pub duration: Duration,
+}
+
+/// Render the pass/fail summary table printed after all cells finish.
+pub fn render_summary(task: &str, results: &[CellResult]) -> String {
+    let width = results
+        .iter()
+        .map(|result| result.label.len())
+        .max()
+        .unwrap_or(0)
+        .max("matrix".len());
+    let mut out = format!("\nMatrix summary for '{task}':\n");
+    out.push_str(&format!(
+        "  {:<width$}  {:<8}  {:>8}\n",
+        "matrix", "result", "time"
+    ));
+    for result in results {
+        let outcome = if result.success {
+            "pass".to_string()
+        } else {
+            match result.status {
+                Some(code) => format!("fail ({code})"),
+                None => "fail".to_string(),
+            }
+        };
+        out.push_str(&format!(
+            "  {:<width$}  {:<8}  {:>7.1}s\n",
+            result.label,
+            outcome,
+            result.duration.as_secs_f64()
+        ));
+    }
+    let passed = results.iter().filter(|result| result.success).count();
+    out.push_str(&format!("  {passed}/{} passed\n", results.len()));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(entries: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        entries
+            .iter()
+            .map(|(key, values)| {
+                (
+                    key.to_string(),
+                    values.iter().map(|v| v.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn expand_builds_cartesian_product() {
+        let cells = expand(&matrix(&[
+            ("node", &["18", "20"]),
+            ("os", &["linux", "mac"]),
+        ]));
+        let labels: Vec<String> = cells.iter().map(MatrixCell::label).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "node=18,os=linux",
+                "node=18,os=mac",
+                "node=20,os=linux",
+                "node=20,os=mac"
+            ]
+        );
+        assert!(expand(&BTreeMap::new()).is_empty());
+    }
+
+    #[test]
+    fn interpolate_replaces_matrix_keys_only() {
+        let cell = &expand(&matrix(&[("node", &["20"])]))[0];
+        assert_eq!(
+            cell.interpolate("nvm exec {{ matrix.node }} npm test -- {{other}}")
+                .unwrap(),
+            "nvm exec 20 npm test -- {{other}}"
+        );
+        assert!(cell.interpolate("echo {{matrix.python}}").is_err());
+    }
+
+    #[test]
+    fn matrix_values_parse_from_task_toml() {
+        let cfg: crate::config::Config = toml::from_str(
+            r#"
+[[tasks]]
+name = "test"
+command = "echo {{matrix.node}}"
+matrix.node = [18, "20"]
+matrix-parallel = true
+"#,
+        )
+        .unwrap();
+        let task = &cfg.tasks[0];
+        assert_eq!(
+            task.matrix["node"],
+            vec!["18".to_string(), "20".to_string()]
+        );
+        assert!(task.matrix_parallel);
+    }
+
+    #[test]
+    fn summary_counts_passes() {
+        let results = vec![
+            CellResult {
+                label: "node=18".to_string(),
+                success: true,
+                status: Some(0),
+                duration: Duration::from_millis(1200),
+            },
+            CellResult {
+                label: "node=20".to_string(),
+                success: false,
+                status: Some(1),
+                duration: Duration::from_millis(800),
+            },
+        ];
+        let summary = render_summary("test", &results);
+        assert!(summary.contains("node=20  fail (1)"));
+        assert!(summary.contains("1/2 passed"));
+    }
+}
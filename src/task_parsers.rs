@@ -0,0 +1,388 @@
+//! Structured test results extracted from task output (`parser = "..."` on a task).
+//!
+//! Built-in parsers understand `cargo test`, Jest, and JUnit XML reports; any other value is a
+//! regex applied per output line. Results are stored on the task's run record, so
+//! `f runs show` can list the failing tests and the palette can offer to re-run just those.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result, bail};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Parsed outcome of one task run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TestResults {
+    /// `cargo-test`, `jest`, `junit-xml`, or `regex`.
+    pub parser: String,
+    pub passed: u32,
+    pub failed: u32,
+    #[serde(default)]
+    pub skipped: u32,
+    /// Time the test runner reported, which excludes build and startup.
+    #[serde(default)]
+    pub duration_ms: Option<i64>,
+    /// Names of failed tests, in the order they were first reported.
+    #[serde(default)]
+    pub failures: Vec<String>,
+}
+
+impl TestResults {
+    fn new(parser: &str) -> Self {
+        Self {
+            parser: parser.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// `12 passed, 2 failed, 1 skipped in 3.4s`.
+    pub fn summary(&self) -> String {
+        let mut summary = format!("{} passed, {} failed", self.passed, self.failed);
+        if self.skipped > 0 {
+            summary.push_str(&format!(", {} skipped", self.skipped));
+        }
+        if let Some(ms) = self.duration_ms {
+            summary.push_str(&format!(" in {}", crate::runs::format_duration(ms)));
+        }
+        summary
+    }
+
+    /// Arguments appended to the task command to run only the failed tests.
+    pub fn rerun_args(&self) -> Vec<String> {
+        if self.failures.is_empty() {
+            return Vec::new();
+        }
+        match self.parser.as_str() {
+            "cargo-test" => ["--", "--exact"]
+                .into_iter()
+                .map(str::to_string)
+                .chain(self.failures.iter().cloned())
+                .collect(),
+            "jest" => {
+                // Jest reports `Suite › test`; `-t` matches the names joined with spaces.
+                let names: Vec<String> = self
+                    .failures
+                    .iter()
+                    .map(|name| regex::escape(&name.replace(" › ", " ")))
+                    .collect();
+                vec!["-t".to_string(), format!("^(?:{})$", names.join("|"))]
+            }
+            _ => self.failures.clone(),
+        }
+    }
+
+    fn push_failure(&mut self, name: &str) {
+        let name = name.trim();
+        if !name.is_empty() && !self.failures.iter().any(|existing| existing == name) {
+            self.failures.push(name.to_string());
+        }
+    }
+}
+
+/// Run the parser named by `spec` over a task's output. `Ok(None)` means the output had nothing
+/// the parser recognized (e.g. the build failed before any test ran).
+pub fn parse(spec: &str, output: &str, workdir: &Path) -> Result<Option<TestResults>> {
+    let plain = strip_ansi(output);
+    let results = match spec.trim() {
+        "cargo-test" => parse_cargo_test(&plain),
+        "jest" => parse_jest(&plain),
+        "junit-xml" => parse_junit_xml(&plain),
+        spec => match spec.strip_prefix("junit-xml:") {
+            Some(report) => {
+                let path = workdir.join(report.trim());
+                let xml = std::fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read JUnit report {}", path.display()))?;
+                parse_junit_xml(&xml)
+            }
+            None => parse_regex(spec, &plain)?,
+        },
+    };
+    Ok(results)
+}
+
+fn strip_ansi(output: &str) -> String {
+    static ANSI: OnceLock<Regex> = OnceLock::new();
+    let ansi = ANSI.get_or_init(|| Regex::new(r"\x1b\[[0-9;]*m").expect("valid ansi regex"));
+    ansi.replace_all(output, "").into_owned()
+}
+
+/// libtest: `test a::b ... FAILED` lines plus one `test result:` summary per test binary.
+fn parse_cargo_test(output: &str) -> Option<TestResults> {
+    static LINE: OnceLock<Regex> = OnceLock::new();
+    static SUMMARY: OnceLock<Regex> = OnceLock::new();
+    let line = LINE.get_or_init(|| {
+        Regex::new(r"(?m)^test (\S+) \.\.\. (ok|FAILED|ignored)").expect("valid cargo test regex")
+    });
+    let summary = SUMMARY.get_or_init(|| {
+        Regex::new(
+            r"(?m)^test result: \w+\. (\d+) passed; (\d+) failed; (\d+) ignored;.*?(?:finished in ([\d.]+)s)?$",
+        )
+        .expect("valid cargo summary regex")
+    });
+
+    let mut results = TestResults::new("cargo-test");
+    let mut seen = false;
+    for captures in summary.captures_iter(output) {
+        seen = true;
+        results.passed += captures[1].parse::<u32>().unwrap_or(0);
+        results.failed += captures[2].parse::<u32>().unwrap_or(0);
+        results.skipped += captures[3].parse::<u32>().unwrap_or(0);
+        if let Some(seconds) = captures.get(4).and_then(|m| m.as_str().parse::<f64>().ok()) {
+            *results.duration_ms.get_or_insert(0) += (seconds * 1000.0).round() as i64;
+        }
+    }
+    let mut counted = TestResults::new("cargo-test");
+    for captures in line.captures_iter(output) {
+        match &captures[2] {
+            "ok" => counted.passed += 1,
+            "ignored" => counted.skipped += 1,
+            _ => {
+                counted.failed += 1;
+                results.push_failure(&captures[1]);
+            }
+        }
+    }
+    if !seen {
+        if counted.passed + counted.failed + counted.skipped == 0 {
+            return None;
+        }
+        results.passed = counted.passed;
+        results.failed = counted.failed;
+        results.skipped = counted.skipped;
+    }
+    Some(results)
+}
+
+/// Jest: the `Tests:` summary, `Time:`, and `● Suite › test` failure headings.
+fn parse_jest(output: &str) -> Option<TestResults> {
+    static SUMMARY: OnceLock<Regex> = OnceLock::new();
+    static COUNT: OnceLock<Regex> = OnceLock::new();
+    static TIME: OnceLock<Regex> = OnceLock::new();
+    static FAILURE: OnceLock<Regex> = OnceLock::new();
+    let summary = SUMMARY
+        .get_or_init(|| Regex::new(r"(?m)^Tests:\s+(.+)$").expect("valid jest summary regex"));
+    let count = COUNT.get_or_init(|| {
+        Regex::new(r"(\d+) (passed|failed|skipped|todo)").expect("valid jest count regex")
+    });
+    let time = TIME.get_or_init(|| {
+        Regex::new(r"(?m)^Time:\s+([\d.]+)\s*(ms|s)\b").expect("valid jest time regex")
+    });
+    let failure = FAILURE
+        .get_or_init(|| Regex::new(r"(?m)^\s*● (.+?)\s*$").expect("valid jest failure regex"));
+
+    let line = summary.captures_iter(output).last()?;
+    let mut results = TestResults::new("jest");
+    for captures in count.captures_iter(&line[1]) {
+        let value = captures[1].parse::<u32>().unwrap_or(0);
+        match &captures[2] {
+            "passed" => results.passed += value,
+            "failed" => results.failed += value,
+            _ => results.skipped += value,
+        }
+    }
+    if let Some(captures) = time.captures_iter(output).last()
+        && let Ok(value) = captures[1].parse::<f64>()
+    {
+        let ms = if &captures[2] == "s" {
+            value * 1000.0
+        } else {
+            value
+        };
+        results.duration_ms = Some(ms.round() as i64);
+    }
+    for captures in failure.captures_iter(output) {
+        if !captures[1].starts_with("Test suite failed to run") {
+            results.push_failure(&captures[1]);
+        }
+    }
+    Some(results)
+}
+
+/// `<testcase>` elements of a JUnit report; a `<failure>` or `<error>` child marks a failure.
+fn parse_junit_xml(xml: &str) -> Option<TestResults> {
+    static TESTCASE: OnceLock<Regex> = OnceLock::new();
+    static ATTR: OnceLock<Regex> = OnceLock::new();
+    let testcase = TESTCASE.get_or_init(|| {
+        Regex::new(r"(?s)<testcase\b([^>]*?)(/>|>(.*?)</testcase>)").expect("valid junit regex")
+    });
+    let attr = ATTR.get_or_init(|| {
+        Regex::new(r#"([\w:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).expect("valid attribute regex")
+    });
+
+    let mut results = TestResults::new("junit-xml");
+    let mut total_ms = 0.0;
+    let mut seen = false;
+    for captures in testcase.captures_iter(xml) {
+        seen = true;
+        let mut name = String::new();
+        for attribute in attr.captures_iter(&captures[1]) {
+            let value = attribute
+                .get(2)
+                .or_else(|| attribute.get(3))
+                .map(|m| unescape_xml(m.as_str()))
+                .unwrap_or_default();
+            match &attribute[1] {
+                "name" => name = value,
+                "time" => total_ms += value.parse::<f64>().unwrap_or(0.0) * 1000.0,
+                _ => {}
+            }
+        }
+        let body = captures.get(3).map(|m| m.as_str()).unwrap_or("");
+        if body.contains("<failure") || body.contains("<error") {
+            results.failed += 1;
+            results.push_failure(&name);
+        } else if body.contains("<skipped") {
+            results.skipped += 1;
+        } else {
+            results.passed += 1;
+        }
+    }
+    if !seen {
+        return None;
+    }
+    results.duration_ms = Some(total_ms.round() as i64);
+    Some(results)
+}
+
+fn unescape_xml(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Custom parser: a regex with a `name` group, matched per line. An optional `status` group
+/// counts `ok`/`pass`/`passed` as passing and `skip`/`skipped`/`ignored` as skipped; without
+/// it every match is a failure.
+fn parse_regex(spec: &str, output: &str) -> Result<Option<TestResults>> {
+    let pattern =
+        Regex::new(spec).with_context(|| format!("invalid task parser regex '{spec}'"))?;
+    if !pattern.capture_names().flatten().any(|name| name == "name") {
+        bail!("task parser regex '{spec}' needs a (?P<name>...) group");
+    }
+    let mut results = TestResults::new("regex");
+    let mut passed = BTreeSet::new();
+    let mut seen = false;
+    for line in output.lines() {
+        let Some(captures) = pattern.captures(line) else {
+            continue;
+        };
+        let Some(name) = captures.name("name").map(|m| m.as_str().trim()) else {
+            continue;
+        };
+        seen = true;
+        let status = captures
+            .name("status")
+            .map(|m| m.as_str().to_ascii_lowercase());
+        match status.as_deref() {
+            Some("ok" | "pass" | "passed") => {
+                passed.insert(name.to_string());
+            }
+            Some("skip" | "skipped" | "ignored") => results.skipped += 1,
+            _ => {
+                results.failed += 1;
+                results.push_failure(name);
+            }
+        }
+    }
+    if !seen {
+        return Ok(None);
+    }
+    results.passed = passed.len() as u32;
+    Ok(Some(results))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cargo_test_output_and_rerun_args() {
+        let output = "\
+running 3 tests
+test config::tests::loads ... ok
+test tasks::tests::runs ... \x1b[31mFAILED\x1b[0m
+test slow ... ignored
+
+failures:
+    tasks::tests::runs
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.52s
+
+running 2 tests
+test it_works ... ok
+test it_also_works ... ok
+
+test result: ok. 2 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 1.00s
+";
+        let results = parse("cargo-test", output, Path::new("."))
+            .unwrap()
+            .unwrap();
+        assert_eq!((results.passed, results.failed, results.skipped), (3, 1, 1));
+        assert_eq!(results.duration_ms, Some(1520));
+        assert_eq!(results.failures, ["tasks::tests::runs"]);
+        assert_eq!(
+            results.rerun_args(),
+            ["--", "--exact", "tasks::tests::runs"]
+        );
+        assert!(
+            parse(
+                "cargo-test",
+                "error[E0425]: cannot find value",
+                Path::new(".")
+            )
+            .unwrap()
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn parses_jest_and_junit_reports() {
+        let jest = "\
+  ● Cart › adds items (1.5 total)
+
+  ● Cart › removes items
+
+Tests:       2 failed, 1 skipped, 10 passed, 13 total
+Time:        2.345 s
+";
+        let results = parse("jest", jest, Path::new(".")).unwrap().unwrap();
+        assert_eq!(
+            (results.passed, results.failed, results.skipped),
+            (10, 2, 1)
+        );
+        assert_eq!(results.duration_ms, Some(2345));
+        assert_eq!(
+            results.rerun_args(),
+            [
+                "-t",
+                r"^(?:Cart adds items \(1\.5 total\)|Cart removes items)$"
+            ]
+        );
+
+        let xml = r#"<testsuite>
+  <testcase classname="api" name="login &amp; logout" time="0.25"/>
+  <testcase classname="api" name="refresh" time="1.5"><failure message="boom"/></testcase>
+  <testcase name="later"><skipped/></testcase>
+</testsuite>"#;
+        let results = parse("junit-xml", xml, Path::new(".")).unwrap().unwrap();
+        assert_eq!((results.passed, results.failed, results.skipped), (1, 1, 1));
+        assert_eq!(results.duration_ms, Some(1750));
+        assert_eq!(results.failures, ["refresh"]);
+    }
+
+    #[test]
+    fn parses_with_custom_regex() {
+        let spec = r"^(?P<status>PASS|FAIL) (?P<name>\S+)";
+        let output = "PASS auth\nFAIL billing\nFAIL billing\nnoise\n";
+        let results = parse(spec, output, Path::new(".")).unwrap().unwrap();
+        assert_eq!(results.parser, "regex");
+        assert_eq!((results.passed, results.failed), (1, 2));
+        assert_eq!(results.failures, ["billing"]);
+        assert!(parse(r"^FAIL (\S+)", output, Path::new(".")).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+//! Process settings for tasks with `cwd`, `umask`, `nice`, or `max_memory` set in flow.toml.
+//!
+//! The settings become a shell preamble that runs before the task command, in the same shell,
+//! so they apply on the host, inside `flox activate`, in a PTY, and to matrix cells alike.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+
+use crate::config::{self, TaskConfig};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct ProcessSettings {
+    pub cwd: Option<PathBuf>,
+    pub umask: Option<u32>,
+    pub nice: Option<i32>,
+    pub max_memory: Option<u64>,
+}
+
+impl ProcessSettings {
+    /// Validate the task's settings; `cwd` is resolved against the flow.toml directory.
+    pub(crate) fn from_task(task: &TaskConfig, workdir: &Path) -> Result<Self> {
+        let cwd = match task.cwd.as_deref().map(str::trim) {
+            Some(raw) if !raw.is_empty() => {
+                let dir = workdir.join(config::expand_path(raw));
+                if !dir.is_dir() {
+                    bail!(
+                        "task '{}': cwd {} is not a directory",
+                        task.name,
+                        dir.display()
+                    );
+                }
+                Some(dir)
+            }
+            _ => None,
+        };
+        let umask = task
+            .umask
+            .as_deref()
+            .map(|raw| {
+                u32::from_str_radix(raw.trim(), 8)
+                    .ok()
+                    .filter(|mask| *mask <= 0o777)
+                    .with_context(|| {
+                        format!(
+                            "task '{}': umask must be octal like \"022\", got '{raw}'",
+                            task.name
+                        )
+                    })
+            })
+            .transpose()?;
+        if let Some(nice) = task.nice
+            && !(-20..=19).contains(&nice)
+        {
+            bail!("task '{}': nice must be between -20 and 19", task.name);
+        }
+        let max_memory = task
+            .max_memory
+            .as_deref()
+            .map(|raw| {
+                parse_memory(raw)
+                    .with_context(|| format!("task '{}': invalid max_memory", task.name))
+            })
+            .transpose()?;
+        Ok(Self {
+            cwd,
+            umask,
+            nice: task.nice,
+            max_memory,
+        })
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Shell lines to run before the command; each one aborts the task if it fails, except the
+    /// memory limit, which only warns where the platform doesn't support it.
+    pub(crate) fn preamble(&self) -> String {
+        let mut lines = Vec::new();
+        if let Some(cwd) = &self.cwd {
+            lines.push(format!(
+                "cd {} || exit $?",
+                shell_words::quote(&cwd.display().to_string())
+            ));
+        }
+        if let Some(umask) = self.umask {
+            lines.push(format!("umask {umask:03o} || exit $?"));
+        }
+        if let Some(nice) = self.nice {
+            lines.push(format!("renice -n {nice} -p $$ >/dev/null || exit $?"));
+        }
+        if let Some(bytes) = self.max_memory {
+            // `ulimit -v` sets RLIMIT_AS, in KiB, for this shell and everything it starts.
+            lines.push(format!(
+                "ulimit -v {} 2>/dev/null || echo 'flow: max_memory is not supported here; running without it' >&2",
+                bytes.div_ceil(1024)
+            ));
+        }
+        lines.into_iter().map(|line| line + "\n").collect()
+    }
+
+    /// `command` with the preamble in front.
+    pub(crate) fn apply(&self, command: &str) -> String {
+        format!("{}{command}", self.preamble())
+    }
+}
+
+/// Parse a size like `2GB`, `512M`, `1.5G`, or a plain byte count. Units are powers of 1024.
+pub(crate) fn parse_memory(raw: &str) -> Result<u64> {
+    let raw = raw.trim();
+    let split = raw
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(raw.len());
+    let (number, unit) = raw.split_at(split);
+    let number: f64 = number
+        .parse()
+        .with_context(|| format!("expected a size like \"2GB\", got '{raw}'"))?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        other => bail!("unknown size unit '{other}' in '{raw}'"),
+    };
+    let bytes = number * multiplier as f64;
+    if bytes < 1.0 {
+        bail!("size must be positive, got '{raw}'");
+    }
+    Ok(bytes as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_memory_sizes() {
+        assert_eq!(parse_memory("2GB").unwrap(), 2 << 30);
+        assert_eq!(parse_memory("512m").unwrap(), 512 << 20);
+        assert_eq!(parse_memory("1.5G").unwrap(), 3 << 29);
+        assert_eq!(parse_memory("4096").unwrap(), 4096);
+        assert!(parse_memory("2 parsecs").is_err());
+        assert!(parse_memory("0").is_err());
+    }
+
+    #[test]
+    fn preamble_quotes_cwd_and_orders_settings() {
+        let settings = ProcessSettings {
+            cwd: Some(PathBuf::from("/repo/packages/my api")),
+            umask: Some(0o27),
+            nice: Some(10),
+            max_memory: Some(2 << 30),
+        };
+        assert_eq!(
+            settings.apply("cargo build"),
+            "cd '/repo/packages/my api' || exit $?\n\
+             umask 027 || exit $?\n\
+             renice -n 10 -p $$ >/dev/null || exit $?\n\
+             ulimit -v 2097152 2>/dev/null || echo 'flow: max_memory is not supported here; running without it' >&2\n\
+             cargo build"
+        );
+        assert!(ProcessSettings::default().is_empty());
+        assert_eq!(ProcessSettings::default().apply("true"), "true");
+    }
+}
@@ -0,0 +1,365 @@
+//! Parameter prompts for tasks.
+//!
+//! ```toml
+//! [[tasks]]
+//! name = "migration"
+//! command = "bunx drizzle-kit generate --name {{migration_name}}"
+//! prompt = [{ name = "migration_name", message = "Migration name?" }]
+//! ```
+//!
+//! Values come from the command line first (`name=value`, or positionally in declaration
+//! order). On a TTY the rest are asked for, offering recent answers; without one they fall back
+//! to `default` or the run fails. Answers are shell-quoted into `{{name}}`.
+//!
+//! Tasks with `confirm = "..."` or `protected = true` are also confirmed here before they run;
+//! `f run <task> --yes` skips the question.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+
+use crate::config::{self, TaskConfig, TaskPromptConfig};
+
+/// Recent answers kept per prompt.
+const HISTORY_LIMIT: usize = 5;
+
+/// A task command with its prompt placeholders filled in.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Prompted {
+    pub command: String,
+    /// Arguments left over after taking prompt values; passed through to the command.
+    pub args: Vec<String>,
+    /// (name, value) in declaration order.
+    pub answers: Vec<(String, String)>,
+}
+
+/// Fill the task's prompts. Returns `None` when the task declares none.
+pub fn resolve(task: &TaskConfig, config_path: &Path, args: &[String]) -> Result<Option<Prompted>> {
+    if task.prompt.is_empty() {
+        return Ok(None);
+    }
+    let (mut values, rest) = take_cli_values(&task.prompt, args);
+
+    let interactive = io::stdin().is_terminal();
+    let mut missing = Vec::new();
+    for prompt in &task.prompt {
+        if values.contains_key(&prompt.name) {
+            continue;
+        }
+        match &prompt.default {
+            Some(default) if !interactive => {
+                values.insert(prompt.name.clone(), default.clone());
+            }
+            _ => missing.push(prompt),
+        }
+    }
+
+    if !missing.is_empty() {
+        if !interactive {
+            let usage = missing
+                .iter()
+                .map(|prompt| format!("{}=<value>", prompt.name))
+                .collect::<Vec<_>>()
+                .join(" ");
+            bail!(
+                "task '{}' needs input and there is no TTY to ask; run `f {} {}`",
+                task.name,
+                task.name,
+                usage
+            );
+        }
+        let history_key = history_scope(config_path, &task.name);
+        let mut history = load_history();
+        for prompt in missing {
+            let key = format!("{history_key}#{}", prompt.name);
+            let recent = history.get(&key).cloned().unwrap_or_default();
+            let value = ask(prompt, &recent)?;
+            remember(&mut history, key, &value);
+            values.insert(prompt.name.clone(), value);
+        }
+        if let Err(err) = save_history(&history) {
+            tracing::warn!(?err, "failed to save task prompt history");
+        }
+    }
+
+    let answers = task
+        .prompt
+        .iter()
+        .filter_map(|prompt| {
+            values
+                .get(&prompt.name)
+                .map(|value| (prompt.name.clone(), value.clone()))
+        })
+        .collect();
+    Ok(Some(Prompted {
+        command: interpolate(&task.command, &values),
+        args: rest,
+        answers,
+    }))
+}
+
+/// What to do before running a `confirm` or `protected` task.
+#[derive(Debug, PartialEq, Eq)]
+enum Confirmation {
+    Run,
+    Ask(String),
+    Refuse,
+}
+
+fn confirmation(task: &TaskConfig, yes: bool, interactive: bool) -> Confirmation {
+    if yes || (task.confirm.is_none() && !task.protected) {
+        return Confirmation::Run;
+    }
+    if !interactive {
+        return if task.protected {
+            Confirmation::Refuse
+        } else {
+            Confirmation::Run
+        };
+    }
+    let question = task
+        .confirm
+        .clone()
+        .unwrap_or_else(|| format!("'{}' is a protected task. Run it?", task.name));
+    Confirmation::Ask(question)
+}
+
+/// Ask before running a task marked `confirm` or `protected`; errors if the run is declined,
+/// or if a protected task runs without a TTY and without `--yes`.
+pub fn confirm(task: &TaskConfig, yes: bool) -> Result<()> {
+    match confirmation(task, yes, io::stdin().is_terminal()) {
+        Confirmation::Run => Ok(()),
+        Confirmation::Refuse => bail!(
+            "task '{}' is protected and there is no TTY to confirm; run `f run {} --yes`",
+            task.name,
+            task.name
+        ),
+        Confirmation::Ask(question) => {
+            print!("{question} [y/N] ");
+            io::stdout().flush()?;
+            let mut input = String::new();
+            io::stdin().read_line(&mut input)?;
+            let input = input.trim();
+            if input.eq_ignore_ascii_case("y") || input.eq_ignore_ascii_case("yes") {
+                Ok(())
+            } else {
+                bail!("task '{}' cancelled", task.name)
+            }
+        }
+    }
+}
+
+/// Take `name=value` arguments for declared prompts, then leading positional arguments for
+/// the prompts still unset, in declaration order.
+fn take_cli_values(
+    prompts: &[TaskPromptConfig],
+    args: &[String],
+) -> (HashMap<String, String>, Vec<String>) {
+    let mut values = HashMap::new();
+    let mut rest = Vec::new();
+    for arg in args {
+        match arg.split_once('=') {
+            Some((name, value)) if prompts.iter().any(|prompt| prompt.name == name) => {
+                values.insert(name.to_string(), value.to_string());
+            }
+            _ => rest.push(arg.clone()),
+        }
+    }
+
+    let mut rest = rest.into_iter().peekable();
+    for prompt in prompts {
+        if values.contains_key(&prompt.name) {
+            continue;
+        }
+        match rest.next_if(|arg| !arg.starts_with('-')) {
+            Some(arg) => {
+                values.insert(prompt.name.clone(), arg);
+            }
+            None => break,
+        }
+    }
+    (values, rest.collect())
+}
+
+/// Ask for one value. Enter keeps the shown default (the last answer, else `default`); a
+/// number picks from the listed recent answers.
+fn ask(prompt: &TaskPromptConfig, recent: &[String]) -> Result<String> {
+    let message = prompt.message.as_deref().unwrap_or(&prompt.name);
+    let default = recent
+        .first()
+        .map(String::as_str)
+        .or(prompt.default.as_deref());
+    if recent.len() > 1 {
+        println!("Recent answers for {}:", prompt.name);
+        for (idx, value) in recent.iter().enumerate() {
+            println!("  {}. {}", idx + 1, value);
+        }
+    }
+
+    loop {
+        match default {
+            Some(default) => print!("{message} [{default}]: "),
+            None => print!("{message}: "),
+        }
+        io::stdout().flush()?;
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            bail!("no value entered for '{}'", prompt.name);
+        }
+        let input = input.trim();
+        if input.is_empty() {
+            match default {
+                Some(default) => return Ok(default.to_string()),
+                None => continue,
+            }
+        }
+        if recent.len() > 1
+            && let Ok(choice) = input.parse::<usize>()
+            && (1..=recent.len()).contains(&choice)
+        {
+            return Ok(recent[choice - 1].clone());
+        }
+        return Ok(input.to_string());
+    }
+}
+
+/// Replace `{{name}}` (whitespace inside the braces allowed) for each answered prompt with the
+/// shell-quoted value. Other placeholders, such as `{{matrix.node}}`, are left alone.
+fn interpolate(command: &str, values: &HashMap<String, String>) -> String {
+    let mut out = String::with_capacity(command.len());
+    let mut rest = command;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        match values.get(after[..end].trim()) {
+            Some(value) => out.push_str(&shell_words::quote(value)),
+            None => out.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn history_scope(config_path: &Path, task: &str) -> String {
+    let config_path = config_path
+        .canonicalize()
+        .unwrap_or_else(|_| config_path.to_path_buf());
+    format!("{}#{task}", config_path.display())
+}
+
+fn history_path() -> PathBuf {
+    config::global_state_dir().join("task-prompts.json")
+}
+
+fn load_history() -> BTreeMap<String, Vec<String>> {
+    fs::read_to_string(history_path())
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save_history(history: &BTreeMap<String, Vec<String>>) -> Result<()> {
+    let path = history_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(history)?)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Move `value` to the front of the key's answers, keeping at most `HISTORY_LIMIT`.
+fn remember(history: &mut BTreeMap<String, Vec<String>>, key: String, value: &str) {
+    let recent = history.entry(key).or_default();
+    recent.retain(|existing| existing != value);
+    recent.insert(0, value.to_string());
+    recent.truncate(HISTORY_LIMIT);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompts(names: &[&str]) -> Vec<TaskPromptConfig> {
+        names
+            .iter()
+            .map(|name| TaskPromptConfig {
+                name: name.to_string(),
+                message: None,
+                default: None,
+            })
+            .collect()
+    }
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn takes_named_then_positional_values() {
+        let (values, rest) = take_cli_values(
+            &prompts(&["table", "name"]),
+            &strings(&["name=add users", "posts", "--dry-run", "extra"]),
+        );
+        assert_eq!(values["name"], "add users");
+        assert_eq!(values["table"], "posts");
+        assert_eq!(rest, strings(&["--dry-run", "extra"]));
+
+        let (values, rest) =
+            take_cli_values(&prompts(&["table"]), &strings(&["--verbose", "posts"]));
+        assert!(values.is_empty());
+        assert_eq!(rest, strings(&["--verbose", "posts"]));
+    }
+
+    #[test]
+    fn interpolates_quoted_values_and_keeps_other_placeholders() {
+        let values = HashMap::from([("migration_name".to_string(), "add users".to_string())]);
+        assert_eq!(
+            interpolate(
+                "generate --name {{ migration_name }} --node {{matrix.node}}",
+                &values
+            ),
+            "generate --name 'add users' --node {{matrix.node}}"
+        );
+    }
+
+    #[test]
+    fn protected_tasks_need_yes_without_a_tty() {
+        let mut task: TaskConfig =
+            toml::from_str("name = \"db-reset\"\ncommand = \"dropdb app\"").unwrap();
+        assert_eq!(confirmation(&task, false, false), Confirmation::Run);
+
+        task.confirm = Some("This wipes the local DB. Continue?".to_string());
+        assert_eq!(
+            confirmation(&task, false, true),
+            Confirmation::Ask("This wipes the local DB. Continue?".to_string())
+        );
+        assert_eq!(confirmation(&task, false, false), Confirmation::Run);
+
+        task.protected = true;
+        assert_eq!(confirmation(&task, false, false), Confirmation::Refuse);
+        assert_eq!(confirmation(&task, true, false), Confirmation::Run);
+        task.confirm = None;
+        assert_eq!(
+            confirmation(&task, false, true),
+            Confirmation::Ask("'db-reset' is a protected task. Run it?".to_string())
+        );
+    }
+
+    #[test]
+    fn history_keeps_recent_unique_answers() {
+        let mut history = BTreeMap::new();
+        for value in ["a", "b", "c", "d", "e", "f", "b"] {
+            remember(&mut history, "k".to_string(), value);
+        }
+        assert_eq!(history["k"], strings(&["b", "f", "e", "d", "c"]));
+    }
+}
@@ -0,0 +1,228 @@
+//! Retry policy for tasks with `retries` set in flow.toml.
+
+use std::process::ExitStatus;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::config::TaskConfig;
+use crate::time_window;
+
+const DEFAULT_BACKOFF: Duration = Duration::from_secs(5);
+/// Exponential backoff never waits longer than this between attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RetryPolicy {
+    pub retries: u32,
+    pub backoff: Duration,
+    pub exponential: bool,
+}
+
+impl RetryPolicy {
+    pub(crate) fn from_task(task: &TaskConfig) -> Result<Self> {
+        let backoff = match task.retry_backoff.as_deref() {
+            Some(raw) => {
+                let ms = time_window::parse_since(raw)
+                    .with_context(|| format!("task '{}': invalid retry_backoff", task.name))?;
+                Duration::from_millis(ms.max(0) as u64)
+            }
+            None => DEFAULT_BACKOFF,
+        };
+        Ok(Self {
+            retries: task.retries,
+            backoff,
+            exponential: task.retry_exponential,
+        })
+    }
+
+    /// Total number of attempts, counting the first run.
+    pub(crate) fn attempts(&self) -> u32 {
+        self.retries.saturating_add(1)
+    }
+
+    /// How long to wait after failed attempt `attempt` (1-based) before the next one.
+    pub(crate) fn delay_after(&self, attempt: u32) -> Duration {
+        if !self.exponential {
+            return self.backoff;
+        }
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.backoff.saturating_mul(factor).min(MAX_BACKOFF)
+    }
+}
+
+/// Result of running a task under its retry policy.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RetryOutcome {
+    pub status: ExitStatus,
+    /// 1-based number of the attempt that produced `status`.
+    pub attempt: u32,
+    /// Byte offset in the captured output where the final attempt begins.
+    pub final_start: usize,
+}
+
+impl RetryOutcome {
+    /// Output of the final attempt, without earlier attempts or retry notices.
+    pub(crate) fn final_output<'a>(&self, output: &'a str) -> &'a str {
+        output.get(self.final_start..).unwrap_or_default()
+    }
+}
+
+impl RetryPolicy {
+    /// Run `run_attempt` until it succeeds or the attempts run out.
+    ///
+    /// Every attempt appends to `output`; `log` records the attempt and retry
+    /// notices there as well.
+    pub(crate) fn run_attempts(
+        &self,
+        task_name: &str,
+        output: &mut String,
+        log: impl Fn(&mut String, &str),
+        mut run_attempt: impl FnMut(&mut String) -> Result<ExitStatus>,
+    ) -> Result<RetryOutcome> {
+        let attempts = self.attempts();
+        let mut attempt = 1;
+        loop {
+            if attempts > 1 {
+                log(
+                    output,
+                    &format!("Attempt {}/{} of '{}'", attempt, attempts, task_name),
+                );
+            }
+            let final_start = output.len();
+            let status = run_attempt(output)?;
+            if status.success() || attempt >= attempts {
+                return Ok(RetryOutcome {
+                    status,
+                    attempt,
+                    final_start,
+                });
+            }
+            let delay = self.delay_after(attempt);
+            log(
+                output,
+                &format!(
+                    "Attempt {}/{} of '{}' failed with status {}; retrying in {}",
+                    attempt,
+                    attempts,
+                    task_name,
+                    status.code().unwrap_or(-1),
+                    format_delay(delay)
+                ),
+            );
+            std::thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+}
+
+/// Compact human form of a backoff delay (`500ms`, `5s`, `2m 30s`).
+pub(crate) fn format_delay(delay: Duration) -> String {
+    let ms = delay.as_millis();
+    if ms < 1000 {
+        format!("{ms}ms")
+    } else if ms < 60_000 {
+        let secs = delay.as_secs_f64();
+        if ms.is_multiple_of(1000) {
+            format!("{}s", ms / 1000)
+        } else {
+            format!("{secs:.1}s")
+        }
+    } else {
+        let secs = delay.as_secs();
+        match secs % 60 {
+            0 => format!("{}m", secs / 60),
+            rest => format!("{}m {}s", secs / 60, rest),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn task(retries: u32, backoff: Option<&str>, exponential: bool) -> TaskConfig {
+        let mut task: TaskConfig =
+            toml::from_str("name = \"publish\"\ncommand = \"npm publish\"").unwrap();
+        task.retries = retries;
+        task.retry_backoff = backoff.map(str::to_string);
+        task.retry_exponential = exponential;
+        task
+    }
+
+    #[test]
+    fn fixed_and_exponential_delays() {
+        let fixed = RetryPolicy::from_task(&task(3, Some("5s"), false)).unwrap();
+        assert_eq!(fixed.attempts(), 4);
+        assert_eq!(fixed.delay_after(1), Duration::from_secs(5));
+        assert_eq!(fixed.delay_after(3), Duration::from_secs(5));
+
+        let exp = RetryPolicy::from_task(&task(5, Some("500ms"), true)).unwrap();
+        assert_eq!(exp.delay_after(1), Duration::from_millis(500));
+        assert_eq!(exp.delay_after(2), Duration::from_secs(1));
+        assert_eq!(exp.delay_after(4), Duration::from_secs(4));
+        assert_eq!(exp.delay_after(40), MAX_BACKOFF);
+
+        let default = RetryPolicy::from_task(&task(0, None, false)).unwrap();
+        assert_eq!(default.attempts(), 1);
+        assert_eq!(default.backoff, DEFAULT_BACKOFF);
+        assert!(RetryPolicy::from_task(&task(1, Some("soon"), false)).is_err());
+    }
+
+    #[test]
+    fn parses_only_the_final_attempt() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let policy = RetryPolicy {
+            retries: 2,
+            backoff: Duration::ZERO,
+            exponential: false,
+        };
+        let mut output = String::new();
+        let mut runs = 0;
+        let outcome = policy
+            .run_attempts(
+                "test",
+                &mut output,
+                |out, line| {
+                    out.push_str(line);
+                    out.push('\n');
+                },
+                |out| {
+                    runs += 1;
+                    if runs == 1 {
+                        out.push_str("test a ... ok\ntest b ... FAILED\n");
+                        out.push_str("test result: FAILED. 1 passed; 1 failed; 0 ignored;\n");
+                        Ok(ExitStatus::from_raw(1 << 8))
+                    } else {
+                        out.push_str("test a ... ok\ntest b ... ok\n");
+                        out.push_str("test result: ok. 2 passed; 0 failed; 0 ignored;\n");
+                        Ok(ExitStatus::from_raw(0))
+                    }
+                },
+            )
+            .unwrap();
+
+        assert!(outcome.status.success());
+        assert_eq!(outcome.attempt, 2);
+        assert!(output.contains("Attempt 1/3 of 'test' failed"));
+        let final_output = outcome.final_output(&output);
+        assert!(!final_output.contains("FAILED"));
+        let results = crate::task_parsers::parse("cargo-test", final_output, Path::new("."))
+            .unwrap()
+            .unwrap();
+        assert_eq!((results.passed, results.failed), (2, 0));
+        assert!(results.failures.is_empty());
+    }
+
+    #[test]
+    fn formats_delays() {
+        assert_eq!(format_delay(Duration::from_millis(250)), "250ms");
+        assert_eq!(format_delay(Duration::from_secs(5)), "5s");
+        assert_eq!(format_delay(Duration::from_millis(1500)), "1.5s");
+        assert_eq!(format_delay(Duration::from_secs(150)), "2m 30s");
+        assert_eq!(format_delay(Duration::from_secs(300)), "5m");
+    }
+}
@@ -0,0 +1,330 @@
+//! `f tasks stats`: failure rates, durations, streaks, and flakiness from the run history.
+//!
+//! A task is flaky when its outcome flips between runs of the same code (same `HEAD` and
+//! uncommitted diff), and its duration regressed when the median of its latest passing runs is
+//! well above the median of the passing runs before them.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::cli::TasksStatsOpts;
+use crate::runs::{self, RunRecord, format_duration};
+use crate::tasks::load_project_config;
+
+/// Same-code outcome flips needed before a task is flagged flaky.
+const FLAKY_MIN_FLIPS: usize = 2;
+/// Latest passing runs compared against the baseline before them.
+const RECENT_RUNS: usize = 5;
+const BASELINE_RUNS: usize = 20;
+/// A regression needs the recent median to be 50% and 500ms above the baseline median.
+const REGRESSION_RATIO: f64 = 1.5;
+const REGRESSION_MIN_MS: i64 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Streak {
+    pub success: bool,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DurationRegression {
+    pub baseline_median_ms: i64,
+    pub recent_median_ms: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TaskStats {
+    pub task: String,
+    pub runs: usize,
+    pub failures: usize,
+    pub failure_rate: f64,
+    pub avg_duration_ms: i64,
+    /// Median of passing runs (failed runs often stop early).
+    pub median_duration_ms: Option<i64>,
+    pub streak: Streak,
+    /// Share of consecutive same-code runs whose outcome differed (0 to 1).
+    pub flakiness: f64,
+    pub flaky: bool,
+    pub regression: Option<DurationRegression>,
+    pub last_run_ms: i64,
+}
+
+pub fn run(opts: TasksStatsOpts) -> Result<()> {
+    let (config_path, _cfg) = load_project_config(opts.config)?;
+    let root = config_path.parent().unwrap_or(Path::new("."));
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let since_ms = crate::running::now_ms() as i64 - i64::from(opts.days) * 86_400_000;
+
+    let conn = runs::open_runs_db()?;
+    let mut history = runs::task_runs_under(&conn, &root, since_ms)?;
+    if let Some(task) = opts.task.as_deref() {
+        history.retain(|run| run.name == task);
+    }
+    let stats = compute(&history);
+
+    if opts.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&stats).context("failed to encode task stats")?
+        );
+        return Ok(());
+    }
+    if stats.is_empty() {
+        println!(
+            "No task runs in the last {} day(s) under {}.",
+            opts.days,
+            root.display()
+        );
+        return Ok(());
+    }
+    print_table(&stats);
+    Ok(())
+}
+
+/// Per-task stats from runs ordered oldest first, most failure-prone tasks first.
+pub fn compute(history: &[RunRecord]) -> Vec<TaskStats> {
+    let mut names: Vec<&str> = Vec::new();
+    for run in history {
+        if !names.contains(&run.name.as_str()) {
+            names.push(&run.name);
+        }
+    }
+    let mut stats: Vec<TaskStats> = names
+        .into_iter()
+        .filter_map(|name| {
+            // Cache hits didn't run anything, so they say nothing about the task.
+            let runs: Vec<&RunRecord> = history
+                .iter()
+                .filter(|run| run.name == name && run.detail.as_deref() != Some("cached"))
+                .collect();
+            task_stats(name, &runs)
+        })
+        .collect();
+    stats.sort_by(|a, b| {
+        b.flaky
+            .cmp(&a.flaky)
+            .then(b.failure_rate.total_cmp(&a.failure_rate))
+            .then(a.task.cmp(&b.task))
+    });
+    stats
+}
+
+fn task_stats(name: &str, runs: &[&RunRecord]) -> Option<TaskStats> {
+    let last = runs.last()?;
+    let failures = runs.iter().filter(|run| !run.success).count();
+    let count = runs
+        .iter()
+        .rev()
+        .take_while(|run| run.success == last.success)
+        .count();
+    let passing: Vec<i64> = runs
+        .iter()
+        .filter(|run| run.success)
+        .map(|run| run.duration_ms)
+        .collect();
+    let (flips, pairs) = same_code_flips(runs);
+    Some(TaskStats {
+        task: name.to_string(),
+        runs: runs.len(),
+        failures,
+        failure_rate: failures as f64 / runs.len() as f64,
+        avg_duration_ms: runs.iter().map(|run| run.duration_ms).sum::<i64>() / runs.len() as i64,
+        median_duration_ms: median(&passing),
+        streak: Streak {
+            success: last.success,
+            count,
+        },
+        flakiness: if pairs == 0 {
+            0.0
+        } else {
+            flips as f64 / pairs as f64
+        },
+        flaky: flips >= FLAKY_MIN_FLIPS,
+        regression: duration_regression(&passing),
+        last_run_ms: last.started_ms,
+    })
+}
+
+/// Outcome changes between consecutive runs of the same code revision, and how many such
+/// consecutive pairs there were. Runs recorded before `code_rev` existed are ignored.
+fn same_code_flips(runs: &[&RunRecord]) -> (usize, usize) {
+    let mut last_by_rev: Vec<(&str, bool)> = Vec::new();
+    let (mut flips, mut pairs) = (0, 0);
+    for run in runs {
+        let Some(rev) = run.code_rev.as_deref() else {
+            continue;
+        };
+        match last_by_rev.iter_mut().find(|(seen, _)| *seen == rev) {
+            Some((_, previous)) => {
+                pairs += 1;
+                if *previous != run.success {
+                    flips += 1;
+                }
+                *previous = run.success;
+            }
+            None => last_by_rev.push((rev, run.success)),
+        }
+    }
+    (flips, pairs)
+}
+
+fn duration_regression(passing: &[i64]) -> Option<DurationRegression> {
+    if passing.len() < RECENT_RUNS * 2 {
+        return None;
+    }
+    let (before, recent) = passing.split_at(passing.len() - RECENT_RUNS);
+    let baseline = &before[before.len().saturating_sub(BASELINE_RUNS)..];
+    let baseline_median_ms = median(baseline)?;
+    let recent_median_ms = median(recent)?;
+    let regressed = recent_median_ms as f64 >= baseline_median_ms as f64 * REGRESSION_RATIO
+        && recent_median_ms - baseline_median_ms >= REGRESSION_MIN_MS;
+    regressed.then_some(DurationRegression {
+        baseline_median_ms,
+        recent_median_ms,
+    })
+}
+
+fn median(values: &[i64]) -> Option<i64> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let mid = sorted.len() / 2;
+    Some(if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2
+    } else {
+        sorted[mid]
+    })
+}
+
+fn print_table(stats: &[TaskStats]) {
+    let width = stats.iter().map(|s| s.task.len()).max().unwrap_or(4).max(4);
+    println!(
+        "{:<width$}  {:>5}  {:>5}  {:>7}  {:>7}  {:>6}  FLAKY",
+        "TASK", "RUNS", "FAIL%", "AVG", "MEDIAN", "STREAK"
+    );
+    for s in stats {
+        let streak = format!(
+            "{}{}",
+            if s.streak.success { "✓" } else { "✗" },
+            s.streak.count
+        );
+        let flaky = if s.flaky {
+            format!("{:.2} flaky", s.flakiness)
+        } else if s.flakiness > 0.0 {
+            format!("{:.2}", s.flakiness)
+        } else {
+            "-".to_string()
+        };
+        println!(
+            "{:<width$}  {:>5}  {:>4.0}%  {:>7}  {:>7}  {:>6}  {}",
+            s.task,
+            s.runs,
+            s.failure_rate * 100.0,
+            format_duration(s.avg_duration_ms),
+            s.median_duration_ms
+                .map(format_duration)
+                .unwrap_or_else(|| "-".to_string()),
+            streak,
+            flaky
+        );
+    }
+
+    let regressions: Vec<_> = stats
+        .iter()
+        .filter_map(|s| Some((s, s.regression?)))
+        .collect();
+    if !regressions.is_empty() {
+        println!();
+        for (s, regression) in regressions {
+            println!(
+                "⚠ {} got slower: median {} over the last {} passing runs, up from {}",
+                s.task,
+                format_duration(regression.recent_median_ms),
+                RECENT_RUNS,
+                format_duration(regression.baseline_median_ms)
+            );
+        }
+    }
+    if stats.iter().any(|s| s.flaky) {
+        println!();
+        println!(
+            "Flaky tasks failed and passed on the same code; `f runs list --failed` shows their runs."
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runs::RunKind;
+
+    fn run(name: &str, started_ms: i64, success: bool, rev: &str, duration_ms: i64) -> RunRecord {
+        RunRecord {
+            id: format!("{name}{started_ms}"),
+            kind: RunKind::Task,
+            name: name.to_string(),
+            project: None,
+            project_root: None,
+            parent_id: None,
+            started_ms,
+            duration_ms,
+            status: Some(if success { 0 } else { 1 }),
+            success,
+            log_path: None,
+            detail: None,
+            results: None,
+            code_rev: Some(rev.to_string()),
+        }
+    }
+
+    #[test]
+    fn flags_flips_on_the_same_code_as_flaky() {
+        let history = vec![
+            run("test", 1, true, "aaa", 100),
+            run("test", 2, false, "aaa", 100),
+            run("test", 3, true, "aaa", 100),
+            run("build", 4, true, "aaa", 100),
+            // A failure after a code change, then a fix: not flaky.
+            run("build", 5, false, "bbb", 100),
+            run("build", 6, true, "ccc", 100),
+            run("test", 7, true, "ccc", 100),
+        ];
+        let stats = compute(&history);
+        assert_eq!(stats[0].task, "test");
+        assert!(stats[0].flaky);
+        assert_eq!(stats[0].flakiness, 1.0);
+        assert_eq!(
+            stats[0].streak,
+            Streak {
+                success: true,
+                count: 2
+            }
+        );
+        assert_eq!(stats[1].task, "build");
+        assert!(!stats[1].flaky);
+        assert_eq!(stats[1].failures, 1);
+    }
+
+    #[test]
+    fn detects_median_duration_regressions() {
+        let mut durations = vec![1_000; 10];
+        durations.extend([2_000, 2_100, 1_900, 2_000, 2_200]);
+        assert_eq!(
+            duration_regression(&durations),
+            Some(DurationRegression {
+                baseline_median_ms: 1_000,
+                recent_median_ms: 2_000
+            })
+        );
+        // Relative jumps on fast tasks stay under the absolute floor.
+        assert_eq!(
+            duration_regression(&[10, 10, 10, 10, 10, 40, 40, 40, 40, 40]),
+            None
+        );
+        assert_eq!(duration_regression(&[1_000; 4]), None);
+    }
+}
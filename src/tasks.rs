@@ -1,8 +1,7 @@
 use std::{
     borrow::Cow,
-    collections::{BTreeMap, HashMap, hash_map::DefaultHasher},
-    env,
-    fs::{self, File, OpenOptions},
+    collections::{BTreeMap, HashMap, HashSet, hash_map::DefaultHasher},
+    env, fs,
     hash::{Hash, Hasher},
     io::{IsTerminal, Read, Write},
     net::IpAddr,
@@ -28,19 +27,25 @@ use crate::{
     ai_taskd, ai_tasks,
     cli::{
         FastRunOpts, GlobalAction, GlobalCommand, HubAction, HubCommand, HubOpts, TaskActivateOpts,
-        TaskRunOpts, TasksAction, TasksBuildAiOpts, TasksCommand, TasksDaemonAction,
-        TasksDaemonCommand, TasksDupesOpts, TasksInitAiOpts, TasksListOpts, TasksOpts,
-        TasksRunAiOpts,
+        TaskRunOpts, TasksAction, TasksBuildAiOpts, TasksClearCacheOpts, TasksCommand,
+        TasksDaemonAction, TasksDaemonCommand, TasksDupesOpts, TasksInitAiOpts, TasksListOpts,
+        TasksOpts, TasksRunAiOpts,
     },
-    config::{self, Config, FloxInstallSpec, TaskConfig, TaskResolutionConfig},
-    discover, failure,
+    config::{self, Config, FloxInstallSpec, NotifyEvent, TaskConfig, TaskResolutionConfig},
+    config_interp, deploy, diagnose, discover, editor_api, failure,
     flox::{self, FloxEnv},
     history::{self, InvocationRecord},
-    hub, init, jazz_state,
+    hub, hub_queue, init, jazz_state, notify_rules, parallel, plugins,
     project_snapshot::{self, AiTaskSnapshot, ProjectSnapshot},
-    projects,
+    projects, proxy, run_notes,
     running::{self, RunningProcess},
-    secret_redact, task_failure_agents, task_match,
+    runs, secret_redact,
+    task_cache::{self, CacheStatus},
+    task_failure_agents, task_graph,
+    task_log::{self, LogStream, TaskLogCapture},
+    task_match,
+    task_matrix::{self, CellResult},
+    task_parsers, task_process, task_prompt, task_retry, task_stats,
 };
 
 /// Fire-and-forget log ingester that batches output lines and POSTs them to the
@@ -59,6 +64,18 @@ impl LogIngester {
                 Ok(c) => c,
                 Err(_) => return,
             };
+            // Projects with ingest tokens reject unauthenticated logs; see `f logs token`.
+            let token = std::env::var("FLOW_LOG_TOKEN")
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty());
+            let post = |batch: &[serde_json::Value]| {
+                let mut request = client.post("http://127.0.0.1:9050/logs/ingest").json(batch);
+                if let Some(token) = &token {
+                    request = request.bearer_auth(token);
+                }
+                let _ = request.send();
+            };
             let mut batch: Vec<serde_json::Value> = Vec::new();
             let flush_interval = Duration::from_millis(500);
             let mut last_flush = Instant::now();
@@ -76,30 +93,21 @@ impl LogIngester {
                         }));
                         // Flush if batch is large enough or interval has passed
                         if batch.len() >= 50 || last_flush.elapsed() >= flush_interval {
-                            let _ = client
-                                .post("http://127.0.0.1:9050/logs/ingest")
-                                .json(&batch)
-                                .send();
+                            post(&batch);
                             batch.clear();
                             last_flush = Instant::now();
                         }
                     }
                     Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
                         if !batch.is_empty() {
-                            let _ = client
-                                .post("http://127.0.0.1:9050/logs/ingest")
-                                .json(&batch)
-                                .send();
+                            post(&batch);
                             batch.clear();
                             last_flush = Instant::now();
                         }
                     }
                     Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
                         if !batch.is_empty() {
-                            let _ = client
-                                .post("http://127.0.0.1:9050/logs/ingest")
-                                .json(&batch)
-                                .send();
+                            post(&batch);
                         }
                         break;
                     }
@@ -286,6 +294,10 @@ pub struct TaskContext {
     pub project_name: Option<String>,
     pub log_path: Option<PathBuf>,
     pub interactive: bool,
+    /// Variables from the task's `env_files` that the process environment doesn't already set.
+    pub env_file_vars: Vec<(String, String)>,
+    /// Values for `${envstore:KEY}` references in the command (and `${env:VAR}` for SSH runs).
+    pub interpolated_env: Vec<(String, String)>,
 }
 
 /// Check if a command needs interactive mode (TTY passthrough).
@@ -375,6 +387,10 @@ pub fn run_tasks_command(cmd: TasksCommand) -> Result<()> {
         Some(TasksAction::BuildAi(opts)) => build_ai_task(opts),
         Some(TasksAction::RunAi(opts)) => run_ai_task(opts),
         Some(TasksAction::Daemon(cmd)) => run_ai_task_daemon_command(cmd),
+        Some(TasksAction::ClearCache(opts)) => clear_task_cache(opts),
+        Some(TasksAction::Graph(opts)) => task_graph::run(opts),
+        Some(TasksAction::Stats(opts)) => task_stats::run(opts),
+        Some(TasksAction::Serve(opts)) => editor_api::run(opts),
         None => fuzzy_search_task_history(),
     }
 }
@@ -790,6 +806,9 @@ fn fuzzy_search_task_history() -> Result<()> {
         delegate_to_hub: false,
         hub_host: std::net::IpAddr::from([127, 0, 0, 1]),
         hub_port: 9050,
+        priority: 0,
+        print_env: false,
+        yes: false,
         name: task_name.to_string(),
         args: vec![],
     })
@@ -818,6 +837,17 @@ fn list_tasks(opts: TasksListOpts) -> Result<()> {
     Ok(())
 }
 
+fn clear_task_cache(opts: TasksClearCacheOpts) -> Result<()> {
+    let (config_path, _cfg) = load_project_config(opts.config)?;
+    let workdir = config_path.parent().unwrap_or(Path::new("."));
+    let removed = task_cache::clear(workdir, opts.task.as_deref())?;
+    match opts.task {
+        Some(task) => println!("Cleared {} cached run(s) for '{}'", removed, task),
+        None => println!("Cleared {} cached run(s)", removed),
+    }
+    Ok(())
+}
+
 fn list_task_duplicates(opts: TasksDupesOpts) -> Result<()> {
     let snapshot = ProjectSnapshot::from_task_config_tasks_only(&opts.config, true)?;
     print_duplicate_tasks(&snapshot.discovery.tasks)
@@ -886,6 +916,9 @@ pub fn run_global(opts: GlobalCommand) -> Result<()> {
                     delegate_to_hub: false,
                     hub_host: std::net::IpAddr::from([127, 0, 0, 1]),
                     hub_port: 9050,
+                    priority: 0,
+                    print_env: false,
+                    yes: false,
                     name: task,
                     args,
                 });
@@ -913,6 +946,9 @@ pub fn run_global(opts: GlobalCommand) -> Result<()> {
             delegate_to_hub: false,
             hub_host: std::net::IpAddr::from([127, 0, 0, 1]),
             hub_port: 9050,
+            priority: 0,
+            print_env: false,
+            yes: false,
             name: task,
             args: opts.args,
         });
@@ -940,6 +976,9 @@ pub fn run_with_discovery(task_name: &str, args: Vec<String>) -> Result<()> {
             delegate_to_hub: false,
             hub_host: std::net::IpAddr::from([127, 0, 0, 1]),
             hub_port: 9050,
+            priority: 0,
+            print_env: false,
+            yes: false,
             name: discovered.task.name.clone(),
             args,
         });
@@ -951,6 +990,9 @@ pub fn run_with_discovery(task_name: &str, args: Vec<String>) -> Result<()> {
             delegate_to_hub: false,
             hub_host: std::net::IpAddr::from([127, 0, 0, 1]),
             hub_port: 9050,
+            priority: 0,
+            print_env: false,
+            yes: false,
             name: discovered.task.name.clone(),
             args,
         });
@@ -970,13 +1012,89 @@ pub fn run_with_discovery(task_name: &str, args: Vec<String>) -> Result<()> {
         .collect();
     let mut available_all = available;
     available_all.extend(snapshot.ai_tasks.iter().map(ai_tasks::task_reference));
+    let suggestions = suggest_task_names(
+        task_name,
+        snapshot
+            .discovery
+            .tasks
+            .iter()
+            .map(|d| d.task.name.as_str()),
+    );
+    let hint = match suggestions.as_slice() {
+        [] => String::new(),
+        names => format!(
+            " Did you mean {}?",
+            names
+                .iter()
+                .map(|name| format!("`{name}`"))
+                .collect::<Vec<_>>()
+                .join(" or ")
+        ),
+    };
     bail!(
-        "task '{}' not found.\nAvailable tasks: {}",
+        "task '{}' not found.{}\nAvailable tasks: {}",
         task_name,
+        hint,
         available_all.join(", ")
     );
 }
 
+/// Up to three task names within a small edit distance of `needle` (or starting with it),
+/// closest first.
+pub(crate) fn suggest_task_names<'a>(
+    needle: &str,
+    names: impl IntoIterator<Item = &'a str>,
+) -> Vec<String> {
+    let needle = needle.to_ascii_lowercase();
+    let max_distance = (needle.chars().count() / 3).max(1);
+    let mut scored: Vec<(usize, &str)> = names
+        .into_iter()
+        .filter_map(|name| {
+            let lower = name.to_ascii_lowercase();
+            let distance = edit_distance(&needle, &lower);
+            if distance <= max_distance {
+                Some((distance, name))
+            } else if needle.len() >= 2 && lower.starts_with(&needle) {
+                Some((max_distance + 1, name))
+            } else {
+                None
+            }
+        })
+        .collect();
+    scored.sort();
+    scored.dedup_by(|a, b| a.1 == b.1);
+    scored
+        .into_iter()
+        .take(3)
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+/// Edit distance counting a swap of adjacent characters as one edit (`dve` → `dev`).
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
 fn select_discovered_task<'a>(
     discovery: &'a discover::DiscoveryResult,
     task_name: &str,
@@ -1118,7 +1236,7 @@ fn ancestor_flow_roots(start_root: &Path) -> Vec<PathBuf> {
     roots
 }
 
-fn parse_scoped_selector(selector: &str) -> Option<(String, String)> {
+pub(crate) fn parse_scoped_selector(selector: &str) -> Option<(String, String)> {
     let trimmed = selector.trim();
     if let Some((scope, task)) = trimmed.split_once(':') {
         let scope = scope.trim();
@@ -1137,7 +1255,7 @@ fn parse_scoped_selector(selector: &str) -> Option<(String, String)> {
     None
 }
 
-fn task_matches_selector(task: &discover::DiscoveredTask, needle: &str) -> bool {
+pub(crate) fn task_matches_selector(task: &discover::DiscoveredTask, needle: &str) -> bool {
     task.task.name.eq_ignore_ascii_case(needle)
         || task
             .task
@@ -1238,6 +1356,7 @@ fn resolve_ambiguous_task_match<'a>(
 }
 
 pub fn run(opts: TaskRunOpts) -> Result<()> {
+    let hook = take_hook_env();
     let config_path_for_deps = opts.config.clone();
     let (config_path, cfg) = load_project_config(opts.config)?;
     let project_name = cfg.project_name.clone();
@@ -1264,19 +1383,55 @@ pub fn run(opts: TaskRunOpts) -> Result<()> {
         );
     };
 
+    let task_with_default_env_files;
+    let task = if task.env_files.is_none() && cfg.env_files.is_some() {
+        task_with_default_env_files = TaskConfig {
+            env_files: cfg.env_files.clone(),
+            ..task.clone()
+        };
+        &task_with_default_env_files
+    } else {
+        task
+    };
+    if opts.print_env {
+        return print_task_env(task, &config_path, workdir);
+    }
+    task_prompt::confirm(task, opts.yes)?;
+
+    // Fill `prompt` placeholders from the command line or by asking; answers are recorded as
+    // `name=value` so a replay doesn't ask again.
+    let task_with_answers;
+    let (task, args, input_args) = match task_prompt::resolve(task, &config_path, &opts.args)? {
+        Some(prompted) => {
+            task_with_answers = TaskConfig {
+                command: prompted.command,
+                ..task.clone()
+            };
+            let input_args = prompted
+                .answers
+                .iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .chain(prompted.args.iter().cloned())
+                .collect();
+            (&task_with_answers, prompted.args, input_args)
+        }
+        None => (task, opts.args.clone(), opts.args.clone()),
+    };
+
     // Build user_input early so we can record failures
-    let quoted_args: Vec<String> = opts
-        .args
-        .iter()
-        .map(|arg| shell_words::quote(arg).into_owned())
-        .collect();
-    let user_input = if opts.args.is_empty() {
+    let quote_all = |args: &[String]| -> Vec<String> {
+        args.iter()
+            .map(|arg| shell_words::quote(arg).into_owned())
+            .collect()
+    };
+    let quoted_args = quote_all(&args);
+    let user_input = if input_args.is_empty() {
         task.name.clone()
     } else {
-        format!("{} {}", task.name, quoted_args.join(" "))
+        format!("{} {}", task.name, quote_all(&input_args).join(" "))
     };
     let base_command = task.command.trim().to_string();
-    let display_command = if opts.args.is_empty() {
+    let display_command = if args.is_empty() {
         base_command.clone()
     } else {
         format!("{} {}", base_command, quoted_args.join(" "))
@@ -1320,6 +1475,22 @@ pub fn run(opts: TaskRunOpts) -> Result<()> {
         }
     };
 
+    if let Err(err) = validate_hooks(task, &cfg) {
+        record_failure(&err.to_string());
+        return Err(err);
+    }
+    let hook_opts = |name: &str| TaskRunOpts {
+        config: config_path_for_deps.clone(),
+        delegate_to_hub: false,
+        hub_host: opts.hub_host,
+        hub_port: opts.hub_port,
+        priority: opts.priority,
+        print_env: false,
+        yes: opts.yes,
+        name: name.to_string(),
+        args: vec![],
+    };
+
     // Run task dependencies first (tasks that must complete before this one)
     if !resolved.task_deps.is_empty() {
         for dep_task_name in &resolved.task_deps {
@@ -1329,6 +1500,9 @@ pub fn run(opts: TaskRunOpts) -> Result<()> {
                 delegate_to_hub: false,
                 hub_host: opts.hub_host,
                 hub_port: opts.hub_port,
+                priority: opts.priority,
+                print_env: false,
+                yes: opts.yes,
                 name: dep_task_name.clone(),
                 args: vec![],
             };
@@ -1345,14 +1519,20 @@ pub fn run(opts: TaskRunOpts) -> Result<()> {
 
     let should_delegate = opts.delegate_to_hub || task.delegate_to_hub;
     if should_delegate {
-        match delegate_task_to_hub(
+        let queue_project = project_name
+            .clone()
+            .unwrap_or_else(|| workdir.display().to_string());
+        match delegate_task_to_hub(&HubDelegation {
             task,
-            &resolved,
+            deps: &resolved,
+            config_path: &config_path,
             workdir,
-            opts.hub_host,
-            opts.hub_port,
-            &display_command,
-        ) {
+            host: opts.hub_host,
+            port: opts.hub_port,
+            command: &display_command,
+            project: &queue_project,
+            priority: opts.priority,
+        }) {
             Ok(()) => {
                 let mut record = InvocationRecord::new(
                     workdir.display().to_string(),
@@ -1410,18 +1590,437 @@ pub fn run(opts: TaskRunOpts) -> Result<()> {
             return Err(err);
         }
     }
-    execute_task(
-        task,
-        &config_path,
+
+    if !task.matrix.is_empty() {
+        return run_with_hooks(task, &hook_opts, || {
+            run_matrix(
+                task,
+                MatrixRun {
+                    config_path: &config_path,
+                    workdir,
+                    preamble,
+                    project_name: project_name.as_deref(),
+                    flox_pkgs: &flox_pkgs,
+                    flox_enabled,
+                    args: &args,
+                    user_input: &user_input,
+                },
+            )
+        });
+    }
+
+    run_with_hooks(task, &hook_opts, || {
+        // Checked inside the hook wrapper so `before`/`after` still run on a cache hit.
+        // Hook runs always execute, so they never consult the cache.
+        let cache_key = if hook.is_none() && task_cache::enabled_for(task) {
+            cache_key(workdir, task, &base_command, &args)
+        } else {
+            None
+        };
+        if let Some(hash) = cache_key.as_deref()
+            && let Some(message) = cached_message(workdir, task, hash)
+        {
+            println!("{}", message);
+            println!("  (set {}=1 to force a run)", task_cache::DISABLE_ENV);
+            let mut run_tracker = runs::RunTracker::start(
+                runs::RunKind::Task,
+                &task.name,
+                project_name.as_deref(),
+                Some(workdir),
+            );
+            run_tracker.set_detail("cached");
+            run_tracker.finish(Some(0), true);
+            let mut record = InvocationRecord::new(
+                workdir.display().to_string(),
+                config_path.display().to_string(),
+                project_name.as_deref(),
+                &task.name,
+                &display_command,
+                &user_input,
+                false,
+            );
+            record.success = true;
+            record.status = Some(0);
+            record.output = message;
+            if let Err(err) = history::record(record) {
+                tracing::warn!(?err, "failed to write task history");
+            }
+            return Ok(());
+        }
+
+        let status = execute_task(
+            task,
+            &config_path,
+            workdir,
+            preamble,
+            project_name.as_deref(),
+            &flox_pkgs,
+            flox_enabled,
+            &base_command,
+            &args,
+            &user_input,
+            hook.as_deref(),
+        )?;
+        ensure_success(&task.name, status)?;
+        if let Some(hash) = cache_key.as_deref()
+            && let Err(err) = task_cache::store(workdir, task, hash)
+        {
+            println!("⚠️  Not caching '{}': {err:#}", task.name);
+        }
+        Ok(())
+    })
+}
+
+/// Input hash for `task`, keyed on the command as `execute_task` will interpolate it.
+fn cache_key(workdir: &Path, task: &TaskConfig, command: &str, args: &[String]) -> Option<String> {
+    let hash = config_interp::prepare_shell_command(command)
+        .and_then(|interpolated| task_cache::input_hash(workdir, task, &interpolated, args));
+    match hash {
+        Ok(hash) => Some(hash),
+        Err(err) => {
+            println!("⚠️  Task cache unavailable for '{}': {err:#}", task.name);
+            None
+        }
+    }
+}
+
+/// What to print when the cache lets `task` skip its run, or `None` on a miss.
+fn cached_message(workdir: &Path, task: &TaskConfig, hash: &str) -> Option<String> {
+    match task_cache::check(workdir, task, hash) {
+        Ok(CacheStatus::UpToDate) => Some(format!(
+            "✓ '{}' is cached: inputs unchanged, outputs up to date",
+            task.name
+        )),
+        Ok(CacheStatus::Restored(outputs)) => Some(format!(
+            "✓ '{}' is cached: inputs unchanged, restored {}",
+            task.name,
+            outputs.join(", ")
+        )),
+        Ok(CacheStatus::Miss) => None,
+        Err(err) => {
+            tracing::warn!(?err, "failed to read task cache");
+            None
+        }
+    }
+}
+
+/// Set while a `before`/`after`/`on_failure` task runs, as `<phase>:<hooked task>`, so its run
+/// record says what it ran for. `run` clears it straight away so nested runs don't inherit it.
+const HOOK_ENV: &str = "FLOW_TASK_HOOK";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HookPhase {
+    Before,
+    After,
+    OnFailure,
+}
+
+impl HookPhase {
+    fn as_str(self) -> &'static str {
+        match self {
+            HookPhase::Before => "before",
+            HookPhase::After => "after",
+            HookPhase::OnFailure => "on_failure",
+        }
+    }
+
+    fn tasks(self, task: &TaskConfig) -> &[String] {
+        match self {
+            HookPhase::Before => &task.before,
+            HookPhase::After => &task.after,
+            HookPhase::OnFailure => &task.on_failure,
+        }
+    }
+}
+
+/// Take `FLOW_TASK_HOOK` out of the environment, as a run detail like "before hook of 'build'".
+fn take_hook_env() -> Option<String> {
+    let value = std::env::var(HOOK_ENV).ok()?;
+    unsafe {
+        std::env::remove_var(HOOK_ENV);
+    }
+    let (phase, task) = value.split_once(':')?;
+    Some(format!("{phase} hook of '{task}'"))
+}
+
+/// Hooks must name other tasks in the same config, and following hooks from `task` must
+/// never lead back to a task already being run.
+fn validate_hooks(task: &TaskConfig, cfg: &Config) -> Result<()> {
+    for phase in [HookPhase::Before, HookPhase::After, HookPhase::OnFailure] {
+        for name in phase.tasks(task) {
+            if *name == task.name {
+                bail!(
+                    "task '{}' lists itself in its {} tasks",
+                    task.name,
+                    phase.as_str()
+                );
+            }
+            if !cfg.tasks.iter().any(|t| t.name == *name) {
+                bail!(
+                    "task '{}': {} task '{}' not found",
+                    task.name,
+                    phase.as_str(),
+                    name
+                );
+            }
+        }
+    }
+    let mut visiting = Vec::new();
+    let mut done = HashSet::new();
+    if let Some(cycle) = find_hook_cycle(task, cfg, &mut visiting, &mut done) {
+        bail!("task '{}': hook cycle {}", task.name, cycle.join(" -> "));
+    }
+    Ok(())
+}
+
+/// Depth-first search over `before`, `after`, and `on_failure` edges. Returns the task
+/// names along the first cycle found, ending with the task that closes it.
+fn find_hook_cycle<'a>(
+    task: &'a TaskConfig,
+    cfg: &'a Config,
+    visiting: &mut Vec<&'a str>,
+    done: &mut HashSet<&'a str>,
+) -> Option<Vec<String>> {
+    if let Some(pos) = visiting.iter().position(|name| *name == task.name) {
+        let mut cycle: Vec<String> = visiting[pos..].iter().map(|s| s.to_string()).collect();
+        cycle.push(task.name.clone());
+        return Some(cycle);
+    }
+    if !done.insert(task.name.as_str()) {
+        return None;
+    }
+    visiting.push(&task.name);
+    for phase in [HookPhase::Before, HookPhase::After, HookPhase::OnFailure] {
+        for name in phase.tasks(task) {
+            let Some(hook) = cfg.tasks.iter().find(|t| t.name == *name) else {
+                continue;
+            };
+            if let Some(cycle) = find_hook_cycle(hook, cfg, visiting, done) {
+                return Some(cycle);
+            }
+        }
+    }
+    visiting.pop();
+    None
+}
+
+/// Run `body` between the task's `before` and `after` tasks. `on_failure` tasks run when a
+/// `before` task or `body` fails; `after` tasks run either way. The first error wins.
+fn run_with_hooks(
+    task: &TaskConfig,
+    hook_opts: &dyn Fn(&str) -> TaskRunOpts,
+    body: impl FnOnce() -> Result<()>,
+) -> Result<()> {
+    let result = HookPhase::Before
+        .tasks(task)
+        .iter()
+        .try_for_each(|name| run_hook(HookPhase::Before, task, name, hook_opts))
+        .and_then(|()| body());
+    if result.is_err() {
+        for name in &task.on_failure {
+            if let Err(err) = run_hook(HookPhase::OnFailure, task, name, hook_opts) {
+                println!("⚠️  {err:#}");
+            }
+        }
+    }
+    let after = HookPhase::After
+        .tasks(task)
+        .iter()
+        .try_for_each(|name| run_hook(HookPhase::After, task, name, hook_opts));
+    result.and(after)
+}
+
+fn run_hook(
+    phase: HookPhase,
+    task: &TaskConfig,
+    name: &str,
+    hook_opts: &dyn Fn(&str) -> TaskRunOpts,
+) -> Result<()> {
+    println!("Running {} task '{}'...", phase.as_str(), name);
+    unsafe {
+        std::env::set_var(HOOK_ENV, format!("{}:{}", phase.as_str(), task.name));
+    }
+    let result = run(hook_opts(name));
+    unsafe {
+        std::env::remove_var(HOOK_ENV);
+    }
+    println!();
+    result.with_context(|| format!("{} task '{}' failed", phase.as_str(), name))
+}
+
+/// Run state shared by every combination of a matrix task.
+struct MatrixRun<'a> {
+    config_path: &'a Path,
+    workdir: &'a Path,
+    preamble: String,
+    project_name: Option<&'a str>,
+    flox_pkgs: &'a [(String, FloxInstallSpec)],
+    flox_enabled: bool,
+    args: &'a [String],
+    user_input: &'a str,
+}
+
+/// Run every combination of a matrix task, then print a pass/fail summary table.
+fn run_matrix(task: &TaskConfig, run: MatrixRun<'_>) -> Result<()> {
+    let MatrixRun {
+        config_path,
         workdir,
         preamble,
-        project_name.as_deref(),
-        &flox_pkgs,
+        project_name,
+        flox_pkgs,
         flox_enabled,
-        &base_command,
-        &opts.args,
-        &user_input,
-    )
+        args,
+        user_input,
+    } = run;
+    let cells = task_matrix::expand(&task.matrix);
+    if cells.is_empty() {
+        bail!("task '{}' has an empty matrix", task.name);
+    }
+    let commands = cells
+        .iter()
+        .map(|cell| Ok((cell.label(), cell.interpolate(task.command.trim())?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    // Parallel cells share one local shell each; remote and interactive tasks stay sequential.
+    let parallel = task.matrix_parallel && task.host.is_none() && !task.interactive;
+    println!(
+        "Matrix task '{}': {} combination(s){}",
+        task.name,
+        commands.len(),
+        if parallel { ", in parallel" } else { "" }
+    );
+
+    let results = if parallel {
+        run_matrix_parallel(task, config_path, workdir, project_name, &commands, args)?
+    } else {
+        let mut results = Vec::new();
+        for (label, command) in &commands {
+            let mut cell_task = task.clone();
+            cell_task.name = format!("{}[{}]", task.name, label);
+            cell_task.command = command.clone();
+            let started = Instant::now();
+            let outcome = execute_task(
+                &cell_task,
+                config_path,
+                workdir,
+                preamble.clone(),
+                project_name,
+                flox_pkgs,
+                flox_enabled,
+                command,
+                args,
+                user_input,
+                None,
+            );
+            let (success, status) = match outcome {
+                Ok(status) => (status.success(), status.code()),
+                Err(err) => {
+                    println!("✗ {}: {err:#}", cell_task.name);
+                    (false, None)
+                }
+            };
+            results.push(CellResult {
+                label: label.clone(),
+                success,
+                status,
+                duration: started.elapsed(),
+            });
+            println!();
+        }
+        results
+    };
+
+    print!("{}", task_matrix::render_summary(&task.name, &results));
+    let failed = results.iter().filter(|result| !result.success).count();
+    if failed > 0 {
+        bail!(
+            "matrix task '{}': {} of {} combination(s) failed",
+            task.name,
+            failed,
+            results.len()
+        );
+    }
+    Ok(())
+}
+
+fn run_matrix_parallel(
+    task: &TaskConfig,
+    config_path: &Path,
+    workdir: &Path,
+    project_name: Option<&str>,
+    commands: &[(String, String)],
+    args: &[String],
+) -> Result<Vec<CellResult>> {
+    let mut prefix = String::new();
+    for (key, value) in task_env_file_vars(task, config_path)? {
+        prefix.push_str(&format!("export {key}={}; ", shell_words::quote(&value)));
+    }
+    let commands = commands
+        .iter()
+        .map(|(label, command)| {
+            let prepared = config_interp::prepare_shell_command(command)
+                .with_context(|| format!("task '{}'", task.name))?;
+            Ok((label.clone(), prepared))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    prefix.push_str(&format!(
+        "cd {} && ",
+        shell_words::quote(&workdir.display().to_string())
+    ));
+    let process = task_process::ProcessSettings::from_task(task, workdir)?;
+    let jobs: Vec<parallel::Task> = commands
+        .iter()
+        .map(|(label, prepared)| {
+            let command = &prepared.command;
+            let command = if args.is_empty() || command_references_args(command) {
+                command.clone()
+            } else {
+                format!("{} {}", command, shell_words::join(args))
+            };
+            parallel::Task::new(
+                label.clone(),
+                format!("{prefix}{}", process.apply(&command)),
+            )
+            .with_env(prepared.env.clone())
+        })
+        .collect();
+    let max_jobs = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4);
+    let runner = Arc::new(parallel::ParallelRunner::new(jobs, max_jobs, false));
+    let finished = tokio::runtime::Runtime::new()
+        .context("failed to start matrix runtime")?
+        .block_on(async {
+            Arc::clone(&runner).run().await;
+            runner.snapshot().await
+        });
+
+    // Keep `f failure last` / `f diagnose last` pointed at the first failing combination.
+    if let Some(failed) = finished
+        .iter()
+        .find(|job| job.status == parallel::TaskStatus::Failure)
+    {
+        failure::record_task_failure(
+            &format!("{}[{}]", task.name, failed.label),
+            &failed.command,
+            workdir,
+            config_path,
+            project_name,
+            &failed.output.join(""),
+            failed.exit_code,
+            fishx_enabled(),
+        );
+    }
+
+    Ok(finished
+        .into_iter()
+        .map(|job| CellResult {
+            success: job.status == parallel::TaskStatus::Success,
+            status: job.exit_code,
+            duration: job.duration.unwrap_or_default(),
+            label: job.label,
+        })
+        .collect())
 }
 
 pub fn activate(opts: TaskActivateOpts) -> Result<()> {
@@ -1469,7 +2068,7 @@ pub fn activate(opts: TaskActivateOpts) -> Result<()> {
         let flox_enabled = !flox_pkgs.is_empty() && !flox_disabled_env && !flox_disabled_marker;
         let command = task.command.trim().to_string();
         let empty_args: Vec<String> = Vec::new();
-        execute_task(
+        let status = execute_task(
             task,
             &config_path,
             workdir,
@@ -1480,7 +2079,9 @@ pub fn activate(opts: TaskActivateOpts) -> Result<()> {
             &command,
             &empty_args,
             &task.name,
+            None,
         )?;
+        ensure_success(&task.name, status)?;
     }
 
     Ok(())
@@ -1592,6 +2193,27 @@ fn task_output_path(raw: &str, workdir: &Path) -> PathBuf {
     }
 }
 
+/// Register a local task that declares a `port` with a running `f proxy`.
+fn register_proxy_route(
+    task: &TaskConfig,
+    project_name: Option<&str>,
+    workdir: &Path,
+) -> Option<proxy::control::TaskRoute> {
+    let port = task.port?;
+    if task.host.is_some() {
+        return None;
+    }
+    let project = project_name
+        .map(str::to_string)
+        .or_else(|| {
+            workdir
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "flow".to_string());
+    proxy::control::TaskRoute::register(&task.name, &project, port)
+}
+
 fn execute_task(
     task: &TaskConfig,
     config_path: &Path,
@@ -1603,15 +2225,30 @@ fn execute_task(
     command: &str,
     args: &[String],
     user_input: &str,
-) -> Result<()> {
+    hook: Option<&str>,
+) -> Result<ExitStatus> {
     if command.is_empty() {
         bail!("task '{}' has an empty command", task.name);
     }
+    let retry = task_retry::RetryPolicy::from_task(task)?;
+    let process = task_process::ProcessSettings::from_task(task, workdir)?;
+    // `${env:..}` / `${envstore:..}` become shell variables; values never enter the command.
+    let interpolated = config_interp::prepare_shell_command(command)
+        .with_context(|| format!("task '{}'", task.name))?;
+    let command = interpolated.command.as_str();
+    // Local runs get the cwd/umask/nice/max_memory preamble; `host` tasks use `remote_dir`.
+    let local_command = process.apply(command);
 
     log_and_capture(
         &mut preamble,
         &format!("Running task '{}': {}", task.name, command),
     );
+    if task.host.is_some() && !process.is_empty() {
+        log_and_capture(
+            &mut preamble,
+            "cwd, umask, nice, and max_memory apply to local runs only; ignored over SSH",
+        );
+    }
 
     // Create context for PID tracking
     let canonical_config = config_path
@@ -1621,6 +2258,15 @@ fn execute_task(
         .canonicalize()
         .unwrap_or_else(|_| workdir.to_path_buf());
 
+    plugins::run_pre_task(&plugins::PreTask {
+        name: &task.name,
+        command,
+        args,
+        config_path: &canonical_config,
+        project_root: &canonical_workdir,
+        project: project_name,
+    })?;
+
     // Auto-detect interactive mode if not explicitly set
     let interactive = task.interactive || needs_interactive_mode(command);
 
@@ -1633,6 +2279,12 @@ fn execute_task(
         project_name: project_name.map(|s| s.to_string()),
         log_path: None,
         interactive,
+        env_file_vars: task_env_file_vars(task, config_path)?,
+        interpolated_env: if task.host.is_some() {
+            [interpolated.inherited.as_slice(), &interpolated.env].concat()
+        } else {
+            interpolated.env.clone()
+        },
     };
 
     // Set up cancel handler if on_cancel is defined
@@ -1647,100 +2299,165 @@ fn execute_task(
         user_input,
         !flox_pkgs.is_empty(),
     );
+    let mut run_tracker = runs::RunTracker::start(
+        runs::RunKind::Task,
+        &task.name,
+        project_name,
+        Some(&canonical_workdir),
+    );
+    run_tracker.set_code_rev_from(&canonical_workdir);
+    if let Some(hook) = hook {
+        run_tracker.set_detail(hook);
+    }
+    let default_log_path = task_log_path(&task_ctx);
     let started = Instant::now();
     let mut combined_output = preamble;
-    let status: ExitStatus;
 
     let flox_disabled = flox_disabled_marker(workdir).exists();
 
-    if flox_pkgs.is_empty() || flox_disabled || !flox_enabled {
-        let (st, out) = run_host_command(workdir, command, args, Some(task_ctx.clone()))?;
-        status = st;
-        combined_output.push_str(&out);
-    } else {
+    // Held until the task exits; dropping it unregisters the proxy route.
+    let proxy_route = register_proxy_route(task, project_name, &canonical_workdir);
+    if let Some(route) = &proxy_route {
         log_and_capture(
             &mut combined_output,
-            &format!(
-                "Skipping host PATH checks; using managed deps [{}]",
-                flox_pkgs
-                    .iter()
-                    .map(|(name, _)| name.as_str())
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            ),
+            &format!("Proxy route: http://{}", route.host),
         );
-        match flox_health_check(workdir, flox_pkgs) {
-            Ok(true) => {
-                match run_flox_with_reset(flox_pkgs, workdir, command, args, Some(task_ctx.clone()))
-                {
-                    Ok(Some((st, out))) => {
-                        combined_output.push_str(&out);
-                        if st.success() {
-                            status = st;
-                        } else {
+    }
+
+    let run_attempt = |combined_output: &mut String,
+                       run_tracker: &mut runs::RunTracker|
+     -> Result<ExitStatus> {
+        let status: ExitStatus;
+        if let Some(host) = task.host.as_deref() {
+            let remote = resolve_remote_host(host, task.remote_dir.as_deref(), config_path)?;
+            log_and_capture(
+                combined_output,
+                &format!("Running on {} over SSH", remote.conn.ssh_target()),
+            );
+            run_tracker.set_detail(format!("host: {}", remote.conn.ssh_target()));
+            let (st, out) =
+                run_remote_command(&remote, task, command, args, Some(task_ctx.clone()))?;
+            status = st;
+            combined_output.push_str(&out);
+        } else if flox_pkgs.is_empty() || flox_disabled || !flox_enabled {
+            let (st, out) =
+                run_host_command(workdir, &local_command, args, Some(task_ctx.clone()))?;
+            status = st;
+            combined_output.push_str(&out);
+        } else {
+            log_and_capture(
+                combined_output,
+                &format!(
+                    "Skipping host PATH checks; using managed deps [{}]",
+                    flox_pkgs
+                        .iter()
+                        .map(|(name, _)| name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            );
+            match flox_health_check(workdir, flox_pkgs) {
+                Ok(true) => {
+                    match run_flox_with_reset(
+                        flox_pkgs,
+                        workdir,
+                        &local_command,
+                        args,
+                        Some(task_ctx.clone()),
+                    ) {
+                        Ok(Some((st, out))) => {
+                            combined_output.push_str(&out);
+                            if st.success() {
+                                status = st;
+                            } else {
+                                log_and_capture(
+                                    combined_output,
+                                    &format!(
+                                        "flox activate failed (status {:?}); retrying on host PATH",
+                                        st.code()
+                                    ),
+                                );
+                                let (host_status, host_out) = run_host_command(
+                                    workdir,
+                                    &local_command,
+                                    args,
+                                    Some(task_ctx.clone()),
+                                )?;
+                                combined_output
+                                    .push_str("\n[flox activate failed; retried on host PATH]\n");
+                                combined_output.push_str(&host_out);
+                                status = host_status;
+                            }
+                        }
+                        Ok(None) => {
                             log_and_capture(
-                                &mut combined_output,
-                                &format!(
-                                    "flox activate failed (status {:?}); retrying on host PATH",
-                                    st.code()
-                                ),
+                                combined_output,
+                                "flox disabled after repeated errors; using host PATH",
                             );
-                            let (host_status, host_out) =
-                                run_host_command(workdir, command, args, Some(task_ctx.clone()))?;
+                            combined_output.push_str("[flox disabled after errors]\n");
+                            let (host_status, host_out) = run_host_command(
+                                workdir,
+                                &local_command,
+                                args,
+                                Some(task_ctx.clone()),
+                            )?;
+                            combined_output.push_str(&host_out);
+                            status = host_status;
+                        }
+                        Err(err) => {
+                            log_and_capture(
+                                combined_output,
+                                &format!("flox activate failed ({err}); retrying on host PATH"),
+                            );
+                            let (host_status, host_out) = run_host_command(
+                                workdir,
+                                &local_command,
+                                args,
+                                Some(task_ctx.clone()),
+                            )?;
                             combined_output
                                 .push_str("\n[flox activate failed; retried on host PATH]\n");
                             combined_output.push_str(&host_out);
                             status = host_status;
                         }
                     }
-                    Ok(None) => {
-                        log_and_capture(
-                            &mut combined_output,
-                            "flox disabled after repeated errors; using host PATH",
-                        );
-                        combined_output.push_str("[flox disabled after errors]\n");
-                        let (host_status, host_out) =
-                            run_host_command(workdir, command, args, Some(task_ctx.clone()))?;
-                        combined_output.push_str(&host_out);
-                        status = host_status;
-                    }
-                    Err(err) => {
-                        log_and_capture(
-                            &mut combined_output,
-                            &format!("flox activate failed ({err}); retrying on host PATH"),
-                        );
-                        let (host_status, host_out) =
-                            run_host_command(workdir, command, args, Some(task_ctx.clone()))?;
-                        combined_output
-                            .push_str("\n[flox activate failed; retried on host PATH]\n");
-                        combined_output.push_str(&host_out);
-                        status = host_status;
-                    }
                 }
-            }
-            Ok(false) => {
-                log_and_capture(
-                    &mut combined_output,
-                    "flox disabled after health check; using host PATH",
-                );
-                combined_output.push_str("[flox disabled after health check]\n");
-                let (host_status, host_out) =
-                    run_host_command(workdir, command, args, Some(task_ctx.clone()))?;
-                combined_output.push_str(&host_out);
-                status = host_status;
-            }
-            Err(err) => {
-                log_and_capture(
-                    &mut combined_output,
-                    &format!("flox health check failed ({err}); using host PATH"),
-                );
-                combined_output.push_str("[flox health check failed; using host PATH]\n");
-                let (host_status, host_out) =
-                    run_host_command(workdir, command, args, Some(task_ctx))?;
-                combined_output.push_str(&host_out);
-                status = host_status;
+                Ok(false) => {
+                    log_and_capture(
+                        combined_output,
+                        "flox disabled after health check; using host PATH",
+                    );
+                    combined_output.push_str("[flox disabled after health check]\n");
+                    let (host_status, host_out) =
+                        run_host_command(workdir, &local_command, args, Some(task_ctx.clone()))?;
+                    combined_output.push_str(&host_out);
+                    status = host_status;
+                }
+                Err(err) => {
+                    log_and_capture(
+                        combined_output,
+                        &format!("flox health check failed ({err}); using host PATH"),
+                    );
+                    combined_output.push_str("[flox health check failed; using host PATH]\n");
+                    let (host_status, host_out) =
+                        run_host_command(workdir, &local_command, args, Some(task_ctx.clone()))?;
+                    combined_output.push_str(&host_out);
+                    status = host_status;
+                }
             }
         }
+        Ok(status)
+    };
+
+    let outcome = retry.run_attempts(
+        &task.name,
+        &mut combined_output,
+        log_and_capture,
+        |output| run_attempt(output, &mut run_tracker),
+    )?;
+    let (status, attempt) = (outcome.status, outcome.attempt);
+    if attempt > 1 {
+        run_tracker.set_detail(format!("attempt {}/{}", attempt, retry.attempts()));
     }
 
     record.duration_ms = started.elapsed().as_millis();
@@ -1748,6 +2465,8 @@ fn execute_task(
     record.success = status.success();
     record.output = combined_output;
     let output = record.output.clone();
+    // Retries append to the same log; results come from the attempt that decided the run.
+    let final_output = outcome.final_output(&output);
 
     if let Some(output_file) = task.output_file.as_deref() {
         let path = task_output_path(output_file, workdir);
@@ -1759,6 +2478,69 @@ fn execute_task(
         }
     }
 
+    if let Some(path) = task.output_file.as_deref() {
+        run_tracker.set_log_path(&task_output_path(path, workdir));
+    } else if let Some(path) = default_log_path.filter(|path| path.exists()) {
+        run_tracker.set_log_path(&path);
+    }
+    if let Some(spec) = task.parser.as_deref() {
+        match task_parsers::parse(spec, final_output, workdir) {
+            Ok(Some(results)) => {
+                println!("Tests: {}", results.summary());
+                if !results.failures.is_empty() {
+                    println!("`f runs show {}` lists the failed tests.", run_tracker.id());
+                }
+                run_tracker.set_results(results);
+            }
+            Ok(None) => {}
+            Err(err) => eprintln!("⚠ task parser for '{}': {err:#}", task.name),
+        }
+    }
+    let run_id = run_tracker.id().to_string();
+    run_tracker.finish(status.code(), status.success());
+
+    if task.git_note && task.host.is_none() {
+        let note = run_notes::RunNote {
+            task: task.name.clone(),
+            passed: status.success(),
+            duration_ms: record.duration_ms as i64,
+            exit: status.code(),
+            coverage: run_notes::parse_coverage(final_output),
+            run_id,
+            at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        };
+        match run_notes::attach(workdir, &note) {
+            Ok(Some(commit)) => println!(
+                "Noted '{}' on {} ({})",
+                task.name,
+                commit,
+                run_notes::NOTES_REF
+            ),
+            Ok(None) => {}
+            Err(err) => tracing::warn!(?err, "failed to attach git note for task run"),
+        }
+    }
+
+    notify_rules::dispatch_from(
+        config_path,
+        workdir,
+        &notify_rules::Notification {
+            event: if status.success() {
+                NotifyEvent::TaskSuccess
+            } else {
+                NotifyEvent::TaskFailure
+            },
+            project: project_name
+                .map(str::to_string)
+                .unwrap_or_else(|| notify_rules::project_label(None, workdir)),
+            subject: task.name.clone(),
+            detail: match status.code() {
+                Some(code) => format!("exit status {code}"),
+                None => "terminated by signal".to_string(),
+            },
+        },
+    );
+
     // Record to jazz2 first (borrows), then history (takes ownership)
     if let Err(err) = jazz_state::record_task_run(&record) {
         tracing::warn!(?err, "failed to write jazz2 task run");
@@ -1770,9 +2552,7 @@ fn execute_task(
     // Clear cancel handler since task completed normally
     clear_cancel_handler();
 
-    if status.success() {
-        Ok(())
-    } else {
+    if !status.success() {
         failure::record_task_failure(
             &task.name,
             command,
@@ -1783,6 +2563,7 @@ fn execute_task(
             status.code(),
             fishx_enabled(),
         );
+        diagnose::report_task_failure(&output, config_path);
         task_failure_agents::maybe_run_task_failure_agents(
             &task.name,
             command,
@@ -1791,12 +2572,20 @@ fn execute_task(
             status.code(),
         );
         maybe_run_task_failure_hook(&task.name, command, workdir, &output, status.code());
+    }
+    Ok(status)
+}
+
+/// Turn a finished run's exit status into the error `f run` reports for a failed task.
+fn ensure_success(task: &str, status: ExitStatus) -> Result<()> {
+    if !status.success() {
         bail!(
             "task '{}' exited with status {}",
-            task.name,
+            task,
             status.code().unwrap_or(-1)
         );
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -2263,7 +3052,220 @@ fn run_host_command(
     }
     cmd.current_dir(workdir);
     inject_global_env(&mut cmd);
-    run_command_with_tee(cmd, ctx).with_context(|| "failed to spawn command without managed env")
+    runs::set_run_env(&mut cmd);
+    run_command_with_tee(cmd, ctx, None)
+        .with_context(|| "failed to spawn command without managed env")
+}
+
+/// SSH target for a task with `host` set.
+struct RemoteHost {
+    conn: deploy::HostConnection,
+    dir: Option<String>,
+}
+
+/// Resolve a task `host` value. `deploy` uses the host from `f deploy setup` and defaults the
+/// remote directory to `[host].dest`.
+fn resolve_remote_host(
+    host: &str,
+    remote_dir: Option<&str>,
+    config_path: &Path,
+) -> Result<RemoteHost> {
+    let host = host.trim();
+    let (conn, default_dir) = if host.eq_ignore_ascii_case("deploy") {
+        let conn = deploy::load_deploy_config()?.host.context(
+            "task host = \"deploy\" but no deploy host is configured (run `f deploy setup`)",
+        )?;
+        let dest = config::load(config_path)
+            .ok()
+            .and_then(|cfg| cfg.host)
+            .and_then(|host| host.dest);
+        (conn, dest)
+    } else {
+        let conn = deploy::HostConnection::parse(host)
+            .with_context(|| format!("invalid task host '{host}'"))?;
+        (conn, None)
+    };
+    Ok(RemoteHost {
+        conn,
+        dir: remote_dir.map(|dir| dir.to_string()).or(default_dir),
+    })
+}
+
+/// Environment passed to the remote command: `remote_env` names that are set locally.
+fn remote_env_pairs(task: &TaskConfig) -> Vec<(String, String)> {
+    task.remote_env
+        .iter()
+        .filter_map(|key| Some((key.clone(), std::env::var(key).ok()?)))
+        .collect()
+}
+
+/// `export` lines for `env`. They reach the remote shell over ssh stdin, never argv, because
+/// `remote_env` and `${envstore:..}` values are usually secrets.
+fn remote_env_preamble(env: &[(String, String)]) -> String {
+    env.iter()
+        .map(|(key, value)| format!("export {key}={}\n", shell_words::quote(value)))
+        .collect()
+}
+
+/// Command with `"$@"` appended when positional args are passed but not referenced.
+fn remote_full_command(command: &str, args: &[String]) -> String {
+    if args.is_empty() || command_references_args(command) {
+        command.to_string()
+    } else {
+        format!("{} \"$@\"", command)
+    }
+}
+
+/// `cd <dir> && env FLOW_TASK_NAME=<task>`: the non-secret prefix of every remote line.
+fn remote_line_prefix(dir: Option<&str>, task_name: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    if let Some(dir) = dir {
+        parts.push(format!("cd {} &&", shell_words::quote(dir)));
+    }
+    parts.push("env".to_string());
+    parts.push(shell_words::quote(&format!("FLOW_TASK_NAME={task_name}")).into_owned());
+    parts
+}
+
+/// Build the shell line executed by `ssh` for a piped run. The remote shell reads the script
+/// from stdin (`sh -s`): the env preamble followed by the command, with positional args as `$@`.
+/// Returns the line and the script to write to ssh's stdin.
+fn remote_shell_command(
+    command: &str,
+    args: &[String],
+    dir: Option<&str>,
+    task_name: &str,
+    env: &[(String, String)],
+) -> (String, String) {
+    let mut parts = remote_line_prefix(dir, task_name);
+    parts.push("/bin/sh -s".to_string());
+    if !args.is_empty() {
+        parts.push("--".to_string());
+        parts.extend(args.iter().map(|arg| shell_words::quote(arg).into_owned()));
+    }
+    let script = format!(
+        "{}{}\n",
+        remote_env_preamble(env),
+        remote_full_command(command, args)
+    );
+    (parts.join(" "), script)
+}
+
+/// Build the shell line for a run under a local PTY, where stdin belongs to the terminal. The
+/// env preamble was uploaded to `env_file` beforehand; the remote shell sources and deletes it.
+fn remote_tty_command(
+    command: &str,
+    args: &[String],
+    dir: Option<&str>,
+    task_name: &str,
+    env_file: Option<&str>,
+) -> String {
+    let full_command = remote_full_command(command, args);
+    let mut parts = remote_line_prefix(dir, task_name);
+    parts.push("/bin/sh -c".to_string());
+    match env_file {
+        Some(path) => {
+            let script = format!(". \"$0\"; rm -f -- \"$0\"; {full_command}");
+            parts.push(shell_words::quote(&script).into_owned());
+            parts.push(shell_words::quote(path).into_owned());
+        }
+        None => {
+            parts.push(shell_words::quote(&full_command).into_owned());
+            if !args.is_empty() {
+                parts.push("sh".to_string());
+            }
+        }
+    }
+    parts.extend(args.iter().map(|arg| shell_words::quote(arg).into_owned()));
+    parts.join(" ")
+}
+
+/// Upload the env preamble into a private temp file on the host and return its path.
+fn upload_remote_env(remote: &RemoteHost, port: &str, preamble: &str) -> Result<String> {
+    let target = remote.conn.ssh_target();
+    let mut child = Command::new("ssh")
+        .args(["-p", port, "-o", "StrictHostKeyChecking=accept-new"])
+        .arg(&target)
+        .arg(r#"umask 077 && f=$(mktemp) && cat > "$f" && echo "$f""#)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .with_context(|| format!("failed to run ssh to {target}"))?;
+    child
+        .stdin
+        .take()
+        .context("ssh has no stdin")?
+        .write_all(preamble.as_bytes())?;
+    let output = child.wait_with_output()?;
+    let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || path.is_empty() {
+        bail!("failed to pass the task environment to {target}");
+    }
+    Ok(path)
+}
+
+fn run_remote_command(
+    remote: &RemoteHost,
+    task: &TaskConfig,
+    command: &str,
+    args: &[String],
+    ctx: Option<TaskContext>,
+) -> Result<(ExitStatus, String)> {
+    let mut env = remote_env_pairs(task);
+    if let Some(ctx) = ctx.as_ref() {
+        env.extend(ctx.interpolated_env.iter().cloned());
+    }
+    let port = remote.conn.port.to_string();
+    let target = remote.conn.ssh_target();
+    let interactive = ctx.as_ref().map(|c| c.interactive).unwrap_or(false);
+
+    if interactive && has_tty_access() {
+        let env_file = if env.is_empty() {
+            None
+        } else {
+            Some(upload_remote_env(
+                remote,
+                &port,
+                &remote_env_preamble(&env),
+            )?)
+        };
+        let remote_line = remote_tty_command(
+            command,
+            args,
+            remote.dir.as_deref(),
+            &task.name,
+            env_file.as_deref(),
+        );
+        let ssh_line = [
+            "ssh",
+            "-t",
+            "-p",
+            &port,
+            "-o",
+            "StrictHostKeyChecking=accept-new",
+            &target,
+            &remote_line,
+        ]
+        .iter()
+        .map(|part| shell_words::quote(part).into_owned())
+        .collect::<Vec<_>>()
+        .join(" ");
+        let workdir = ctx
+            .as_ref()
+            .map(|c| c.project_root.clone())
+            .unwrap_or_else(|| PathBuf::from("."));
+        return run_command_with_pty(&workdir, &ssh_line, &[], ctx);
+    }
+
+    let (remote_line, script) =
+        remote_shell_command(command, args, remote.dir.as_deref(), &task.name, &env);
+    let mut cmd = Command::new("ssh");
+    cmd.args(["-p", &port, "-o", "StrictHostKeyChecking=accept-new"])
+        .arg(&target)
+        .arg(&remote_line);
+    run_command_with_tee(cmd, ctx, Some(script))
+        .with_context(|| format!("failed to run ssh to {target}"))
 }
 
 fn run_flox_with_reset(
@@ -2388,19 +3390,127 @@ fn run_flox_command(
     }
     cmd.current_dir(workdir);
     inject_global_env(&mut cmd);
-    run_command_with_tee(cmd, ctx).with_context(|| "failed to spawn flox activate for task")
+    runs::set_run_env(&mut cmd);
+    run_command_with_tee(cmd, ctx, None).with_context(|| "failed to spawn flox activate for task")
+}
+
+/// A variable loaded from one of a task's `env_files`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct EnvFileVar {
+    key: String,
+    value: String,
+    source: String,
 }
 
+/// Load dotenv files in order, relative to `base_dir`; later files override earlier ones and
+/// missing files are skipped.
+fn load_env_files(files: &[String], base_dir: &Path) -> Result<Vec<EnvFileVar>> {
+    let mut vars: BTreeMap<String, EnvFileVar> = BTreeMap::new();
+    for file in files {
+        let path = base_dir.join(file);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", path.display()));
+            }
+        };
+        for (key, value) in crate::env::parse_env_file(&content) {
+            let key = key.strip_prefix("export ").map(str::trim).unwrap_or(&key);
+            if !is_env_key(key) {
+                continue;
+            }
+            vars.insert(
+                key.to_string(),
+                EnvFileVar {
+                    key: key.to_string(),
+                    value,
+                    source: file.clone(),
+                },
+            );
+        }
+    }
+    Ok(vars.into_values().collect())
+}
+
+fn is_env_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c == '_' || c.is_ascii_alphabetic())
+        && chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+}
+
+/// Env file variables to inject for `task`; the process environment wins over env files.
+fn task_env_file_vars(task: &TaskConfig, config_path: &Path) -> Result<Vec<(String, String)>> {
+    let Some(files) = task.env_files.as_deref() else {
+        return Ok(Vec::new());
+    };
+    let base_dir = config_path.parent().unwrap_or(Path::new("."));
+    Ok(load_env_files(files, base_dir)?
+        .into_iter()
+        .filter(|var| env::var_os(&var.key).is_none())
+        .map(|var| (var.key, var.value))
+        .collect())
+}
+
+/// `f run <task> --print-env`: show the environment flow adds for a task and where each value
+/// comes from, without running it.
+fn print_task_env(task: &TaskConfig, config_path: &Path, workdir: &Path) -> Result<()> {
+    let files = task.env_files.as_deref().unwrap_or_default();
+    let base_dir = config_path.parent().unwrap_or(Path::new("."));
+    let mut rows: Vec<(String, String, String)> = vec![
+        ("FLOW_TASK_NAME".into(), task.name.clone(), "flow".into()),
+        (
+            "FLOW_TASK_CONFIG_PATH".into(),
+            config_path.display().to_string(),
+            "flow".into(),
+        ),
+        (
+            "FLOW_TASK_PROJECT_ROOT".into(),
+            workdir.display().to_string(),
+            "flow".into(),
+        ),
+    ];
+    for var in load_env_files(files, base_dir)? {
+        match env::var(&var.key) {
+            Ok(value) => rows.push((
+                var.key,
+                value,
+                format!("process env (overrides {})", var.source),
+            )),
+            Err(_) => rows.push((var.key, var.value, var.source)),
+        }
+    }
+
+    println!("Environment for task '{}':", task.name);
+    if files.is_empty() {
+        println!("  (no env_files configured)");
+    } else {
+        println!("  env_files: {}", files.join(", "));
+    }
+    let width = rows.iter().map(|(key, _, _)| key.len()).max().unwrap_or(0);
+    for (key, value, source) in rows {
+        println!(
+            "  {key:<width$} = {}  ({source})",
+            secret_redact::redact_text(&value)
+        );
+    }
+    Ok(())
+}
+
+/// Run `cmd` with its output teed to the terminal and logs. `input`, when set, is written to
+/// the command's stdin instead of passing the terminal through.
 fn run_command_with_tee(
     mut cmd: Command,
     ctx: Option<TaskContext>,
+    input: Option<String>,
 ) -> Result<(ExitStatus, String)> {
     inject_global_env(&mut cmd);
+    runs::set_run_env(&mut cmd);
     inject_task_env(&mut cmd, ctx.as_ref());
     // Interactive commands are now caught upstream by run_host_command /
     // run_flox_command and routed through run_command_with_pty, so this
     // always delegates to the pipe-based path.
-    run_command_with_pipes(cmd, ctx)
+    run_command_with_pipes(cmd, ctx, input)
 }
 
 fn inject_global_env(cmd: &mut Command) {
@@ -2456,6 +3566,14 @@ fn inject_task_env(cmd: &mut Command, ctx: Option<&TaskContext>) {
         return;
     };
 
+    for (key, value) in task_ctx
+        .env_file_vars
+        .iter()
+        .chain(&task_ctx.interpolated_env)
+    {
+        cmd.env(key, value);
+    }
+
     cmd.env("FLOW_TASK_NAME", &task_ctx.task_name);
     cmd.env(
         "FLOW_TASK_COMMAND",
@@ -2473,6 +3591,8 @@ fn inject_task_env(cmd: &mut Command, ctx: Option<&TaskContext>) {
         "FLOW_TASK_PROJECT_ROOT",
         task_ctx.project_root.display().to_string(),
     );
+    cmd.env("FLOW_TRACE_HEADER", proxy::trace::TRACE_HEADER);
+    cmd.env("FLOW_TRACE_LOG_KEY", proxy::trace::TRACE_LOG_KEY);
 }
 
 /// Inject global env vars into a `portable_pty::CommandBuilder`.
@@ -2526,6 +3646,14 @@ fn inject_task_env_pty(cmd: &mut CommandBuilder, ctx: Option<&TaskContext>) {
         return;
     };
 
+    for (key, value) in task_ctx
+        .env_file_vars
+        .iter()
+        .chain(&task_ctx.interpolated_env)
+    {
+        cmd.env(key, value);
+    }
+
     cmd.env("FLOW_TASK_NAME", &task_ctx.task_name);
     cmd.env(
         "FLOW_TASK_COMMAND",
@@ -2543,6 +3671,8 @@ fn inject_task_env_pty(cmd: &mut CommandBuilder, ctx: Option<&TaskContext>) {
         "FLOW_TASK_PROJECT_ROOT",
         task_ctx.project_root.display().to_string(),
     );
+    cmd.env("FLOW_TRACE_HEADER", proxy::trace::TRACE_HEADER);
+    cmd.env("FLOW_TRACE_LOG_KEY", proxy::trace::TRACE_LOG_KEY);
 }
 
 /// Run a command inside a PTY with full interactivity, color support, and output
@@ -2599,6 +3729,9 @@ fn run_command_with_pty(
     pty_cmd.env("COLORTERM", "truecolor");
 
     inject_global_env_pty(&mut pty_cmd);
+    if let Some(run_id) = runs::current_run_id() {
+        pty_cmd.env(runs::RUN_ID_ENV, run_id);
+    }
     inject_task_env_pty(&mut pty_cmd, ctx.as_ref());
 
     let mut child = pair
@@ -2624,6 +3757,8 @@ fn run_command_with_pty(
             project_root: task_ctx.project_root.clone(),
             used_flox: task_ctx.used_flox,
             project_name: task_ctx.project_name.clone(),
+            adopted: false,
+            log_path: None,
         };
         if let Err(err) = running::register_process(entry) {
             tracing::warn!(?err, "failed to register running process");
@@ -2650,22 +3785,9 @@ fn run_command_with_pty(
     // Set up optional log file
     let log_file = ctx.as_ref().and_then(|c| {
         let path = task_log_path(c)?;
-        if let Some(parent) = path.parent() {
-            let _ = fs::create_dir_all(parent);
-        }
-        match OpenOptions::new().create(true).append(true).open(&path) {
-            Ok(mut file) => {
-                let header = format!(
-                    "\n--- {} | task:{} | cmd:{} ---\n",
-                    running::now_ms(),
-                    c.task_name,
-                    c.command
-                );
-                let _ = file.write_all(header.as_bytes());
-                Some(Arc::new(Mutex::new(file)))
-            }
-            Err(_) => None,
-        }
+        TaskLogCapture::open(&path, &c.task_name, &c.command)
+            .ok()
+            .map(Arc::new)
     });
 
     // Get reader/writer for PTY master
@@ -2762,11 +3884,8 @@ fn run_command_with_pty(
                     let _ = stdout.write_all(&buf[..n]);
                     let _ = stdout.flush();
 
-                    if let Some(ref file) = log_file_clone {
-                        if let Ok(mut f) = file.lock() {
-                            let _ = f.write_all(&buf[..n]);
-                            let _ = f.flush();
-                        }
+                    if let Some(ref log) = log_file_clone {
+                        log.write(LogStream::Tty, &buf[..n]);
                     }
 
                     let text = String::from_utf8_lossy(&buf[..n]);
@@ -2783,7 +3902,7 @@ fn run_command_with_pty(
                                 line,
                                 &mut preferred_url_hint_emitted,
                             );
-                            ing.send(line);
+                            send_clean_line(ing, line);
                         });
                     } else {
                         line_buf.push_str(&text);
@@ -2813,9 +3932,12 @@ fn run_command_with_pty(
                 &mut preferred_url_hint_emitted,
             );
             if let Some(ref ing) = ingester_clone {
-                ing.send(&line_buf);
+                send_clean_line(ing, &line_buf);
             }
         }
+        if let Some(ref log) = log_file_clone {
+            log.finish();
+        }
     });
 
     // Wait for the child process
@@ -2858,66 +3980,41 @@ fn run_command_with_pty(
 fn run_command_with_pipes(
     mut cmd: Command,
     ctx: Option<TaskContext>,
+    input: Option<String>,
 ) -> Result<(ExitStatus, String)> {
-    let interactive = ctx.as_ref().map(|c| c.interactive).unwrap_or(false);
-
-    // Interactive mode: inherit all stdio for TTY passthrough
+    // Interactive tasks only get here without a TTY (otherwise they run under a PTY), so
+    // they are piped and logged like any other task.
     // NOTE: Do NOT create a new process group for interactive commands.
     // The child must remain in the foreground process group to read from the terminal.
-    if interactive {
-        let mut child = cmd
-            .stdin(Stdio::inherit())
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .spawn()
-            .with_context(|| "failed to spawn interactive command")?;
-
-        let pid = child.id();
-        let pgid = running::get_pgid(pid).unwrap_or(pid);
-        set_cleanup_process(pid, pgid);
-
-        // Register the process
-        if let Some(ref task_ctx) = ctx {
-            let entry = RunningProcess {
-                pid,
-                pgid,
-                task_name: task_ctx.task_name.clone(),
-                command: task_ctx.command.clone(),
-                started_at: running::now_ms(),
-                config_path: task_ctx.config_path.clone(),
-                project_root: task_ctx.project_root.clone(),
-                used_flox: task_ctx.used_flox,
-                project_name: task_ctx.project_name.clone(),
-            };
-            if let Err(err) = running::register_process(entry) {
-                tracing::warn!(?err, "failed to register running process");
-            }
-        }
-
-        let status = child.wait().with_context(|| "failed to wait on child")?;
-
-        // Unregister on exit
-        if let Err(err) = running::unregister_process(pid) {
-            tracing::debug!(?err, "failed to unregister process");
-        }
-
-        return Ok((status, String::new()));
-    }
-
-    // Create new process group on Unix for reliable child process management
-    // (only for non-interactive commands)
+    let interactive = ctx.as_ref().map(|c| c.interactive).unwrap_or(false);
     #[cfg(unix)]
     {
         use std::os::unix::process::CommandExt;
-        cmd.process_group(0);
+        if !interactive {
+            cmd.process_group(0);
+        }
+    }
+
+    if std::io::stdout().is_terminal() {
+        force_color_env(&mut cmd);
     }
 
     let mut child = cmd
-        .stdin(Stdio::inherit()) // Allow user input for prompts
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::inherit() // Allow user input for prompts
+        })
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .spawn()
         .with_context(|| "failed to spawn command")?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        // Written from a thread so a large payload can't block on the child's output pipes.
+        thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes());
+        });
+    }
 
     let pid = child.id();
     let pgid = running::get_pgid(pid).unwrap_or(pid);
@@ -2935,6 +4032,8 @@ fn run_command_with_pipes(
             project_root: task_ctx.project_root.clone(),
             used_flox: task_ctx.used_flox,
             project_name: task_ctx.project_name.clone(),
+            adopted: false,
+            log_path: None,
         };
         if let Err(err) = running::register_process(entry) {
             tracing::warn!(?err, "failed to register running process");
@@ -2947,20 +4046,10 @@ fn run_command_with_pipes(
         Some(mut c) => {
             let path = task_log_path(&c);
             if let Some(path) = path {
-                if let Some(parent) = path.parent() {
-                    let _ = fs::create_dir_all(parent);
-                }
-                match OpenOptions::new().create(true).append(true).open(&path) {
-                    Ok(mut file) => {
-                        let header = format!(
-                            "\n--- {} | task:{} | cmd:{} ---\n",
-                            running::now_ms(),
-                            c.task_name,
-                            c.command
-                        );
-                        let _ = file.write_all(header.as_bytes());
+                match TaskLogCapture::open(&path, &c.task_name, &c.command) {
+                    Ok(capture) => {
                         c.log_path = Some(path.clone());
-                        (Some(c), Some(Arc::new(Mutex::new(file))))
+                        (Some(c), Some(Arc::new(capture)))
                     }
                     Err(err) => {
                         if let Ok(mut buf) = output.lock() {
@@ -2990,6 +4079,7 @@ fn run_command_with_pipes(
         handles.push(tee_stream(
             stdout,
             std::io::stdout(),
+            LogStream::Stdout,
             output.clone(),
             log_file.clone(),
             ingester.clone(),
@@ -2999,6 +4089,7 @@ fn run_command_with_pipes(
         handles.push(tee_stream(
             stderr,
             std::io::stderr(),
+            LogStream::Stderr,
             output.clone(),
             log_file.clone(),
             ingester.clone(),
@@ -3008,6 +4099,9 @@ fn run_command_with_pipes(
     for handle in handles {
         let _ = handle.join();
     }
+    if let Some(log) = &log_file {
+        log.finish();
+    }
 
     let status = child
         .wait()
@@ -3079,8 +4173,9 @@ fn for_each_complete_line(line_buf: &mut String, mut on_line: impl FnMut(&str))
 fn tee_stream<R, W>(
     mut reader: R,
     mut writer: W,
+    stream: LogStream,
     buffer: Arc<Mutex<String>>,
-    log_file: Option<Arc<Mutex<File>>>,
+    log_file: Option<Arc<TaskLogCapture>>,
     ingester: Option<Arc<LogIngester>>,
 ) -> thread::JoinHandle<()>
 where
@@ -3102,11 +4197,8 @@ where
             let _ = writer.write_all(&chunk[..read]);
             let _ = writer.flush();
 
-            if let Some(file) = log_file.as_ref() {
-                if let Ok(mut f) = file.lock() {
-                    let _ = f.write_all(&chunk[..read]);
-                    let _ = f.flush();
-                }
+            if let Some(log) = log_file.as_ref() {
+                log.write(stream, &chunk[..read]);
             }
 
             let text = String::from_utf8_lossy(&chunk[..read]);
@@ -3123,7 +4215,7 @@ where
                     &mut preferred_url_hint_emitted,
                 );
                 if let Some(ref ing) = ingester {
-                    ing.send(line);
+                    send_clean_line(ing, line);
                 }
             });
         }
@@ -3135,12 +4227,33 @@ where
                 &mut preferred_url_hint_emitted,
             );
             if let Some(ref ing) = ingester {
-                ing.send(&line_buf);
+                send_clean_line(ing, &line_buf);
             }
         }
     })
 }
 
+/// Forward one output line to the log store without colors or redraw noise.
+fn send_clean_line(ingester: &LogIngester, line: &str) {
+    if let Some(line) = task_log::clean_line(line) {
+        ingester.send(&line);
+    }
+}
+
+/// Piped children see no TTY and usually drop colors; ask them to keep emitting them when
+/// the user's terminal would have shown them. The log capture strips them again.
+fn force_color_env(cmd: &mut Command) {
+    if env::var_os("NO_COLOR").is_some() {
+        return;
+    }
+    for key in ["FORCE_COLOR", "CLICOLOR_FORCE"] {
+        let already_set = env::var_os(key).is_some() || cmd.get_envs().any(|(name, _)| name == key);
+        if !already_set {
+            cmd.env(key, "1");
+        }
+    }
+}
+
 fn reset_flox_env(project_root: &Path) -> Result<()> {
     let dir = project_root.join(".flox");
     if dir.exists() {
@@ -3195,6 +4308,8 @@ fn resolve_task_dependencies(task: &TaskConfig, cfg: &Config) -> Result<Resolved
                 config::DependencySpec::Flox(pkg) => {
                     resolved.flox.push((dep_name.clone(), pkg.clone()));
                 }
+                config::DependencySpec::Tool(tool) => resolved.commands.push(tool.bin.clone()),
+                config::DependencySpec::Versions(_) => {}
             }
             continue;
         }
@@ -3276,15 +4391,49 @@ fn collect_flox_packages(
     merged.into_iter().collect()
 }
 
-fn delegate_task_to_hub(
-    task: &TaskConfig,
-    deps: &ResolvedDependencies,
-    workdir: &Path,
+/// A task run handed to the hub, queued under `project` at `priority`.
+struct HubDelegation<'a> {
+    task: &'a TaskConfig,
+    deps: &'a ResolvedDependencies,
+    config_path: &'a Path,
+    workdir: &'a Path,
     host: IpAddr,
     port: u16,
-    command: &str,
-) -> Result<()> {
-    ensure_hub_running(host, port)?;
+    command: &'a str,
+    project: &'a str,
+    priority: i32,
+}
+
+fn delegate_task_to_hub(request: &HubDelegation<'_>) -> Result<()> {
+    let weight = hub_queue::Weight::for_task(request.task)?;
+    ensure_hub_running(request.host, request.port)?;
+    let slot = hub_queue::acquire(
+        request.project,
+        &request.task.name,
+        request.priority,
+        weight,
+    )?;
+    let result = submit_task_to_hub(request, slot);
+    if result.is_err() {
+        // Nothing will run the release trap, so free the slot here.
+        if let Err(err) = hub_queue::release(slot) {
+            tracing::warn!(?err, "failed to release hub queue slot");
+        }
+    }
+    result
+}
+
+fn submit_task_to_hub(request: &HubDelegation<'_>, slot: i64) -> Result<()> {
+    let HubDelegation {
+        task,
+        deps,
+        config_path,
+        workdir,
+        host,
+        port,
+        command,
+        ..
+    } = *request;
     let url = format_task_submit_url(host, port);
     let client = Client::builder()
         .timeout(Duration::from_secs(5))
@@ -3300,7 +4449,7 @@ fn delegate_task_to_hub(
     let payload = json!({
         "task": {
             "name": task.name,
-            "command": command,
+            "command": hub_queue::wrap_command(command, slot, &task.name, config_path, workdir),
             "dependencies": {
                 "commands": deps.commands,
                 "flox": flox_specs,
@@ -3372,6 +4521,16 @@ mod tests {
     use std::collections::HashMap;
     use std::path::Path;
 
+    #[test]
+    fn suggests_close_task_names() {
+        let names = ["dev", "deploy", "db-reset", "test"];
+        assert_eq!(suggest_task_names("dve", names), vec!["dev"]);
+        assert_eq!(suggest_task_names("db-rest", names), vec!["db-reset"]);
+        assert_eq!(suggest_task_names("dep", names), vec!["dev", "deploy"]);
+        assert!(suggest_task_names("lint", names).is_empty());
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
     #[test]
     fn detects_legacy_rise_work_failure_hook() {
         assert!(is_legacy_rise_work_hook(
@@ -3382,6 +4541,47 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn remote_shell_command_quotes_dir_and_args() {
+        let (line, script) = remote_shell_command(
+            "cargo test",
+            &["it's".to_string()],
+            Some("/opt/my app"),
+            "test",
+            &[],
+        );
+        assert_eq!(
+            line,
+            "cd '/opt/my app' && env 'FLOW_TASK_NAME=test' /bin/sh -s -- 'it'\\''s'"
+        );
+        assert_eq!(script, "cargo test \"$@\"\n");
+        assert_eq!(
+            remote_shell_command("ls", &[], None, "test", &[]).0,
+            "env 'FLOW_TASK_NAME=test' /bin/sh -s"
+        );
+    }
+
+    #[test]
+    fn remote_commands_keep_env_values_out_of_argv() {
+        let env = vec![
+            ("API_TOKEN".to_string(), "s3cr3t-token".to_string()),
+            ("DB_URL".to_string(), "postgres://u:pw@db/app".to_string()),
+        ];
+        let args = ["--release".to_string()];
+        let (line, script) = remote_shell_command("deploy", &args, Some("/srv"), "ship", &env);
+        let tty_line = remote_tty_command("deploy", &args, Some("/srv"), "ship", Some("/tmp/f.x"));
+        for (_, value) in &env {
+            assert!(!line.contains(value.as_str()), "{line}");
+            assert!(!tty_line.contains(value.as_str()), "{tty_line}");
+        }
+        assert!(script.starts_with("export API_TOKEN=s3cr3t-token\nexport DB_URL="));
+        assert!(script.ends_with("deploy \"$@\"\n"));
+        assert_eq!(
+            tty_line,
+            "cd /srv && env 'FLOW_TASK_NAME=ship' /bin/sh -c '. \"$0\"; rm -f -- \"$0\"; deploy \"$@\"' /tmp/f.x --release"
+        );
+    }
+
     #[test]
     fn truncates_failure_hook_output_on_char_boundaries() {
         let output = format!("prefix\n{}", "░".repeat(20));
@@ -3405,8 +4605,34 @@ mod tests {
                 shortcuts: Vec::new(),
                 interactive: false,
                 confirm_on_match: false,
+                confirm: None,
+                protected: false,
                 on_cancel: None,
                 output_file: None,
+                host: None,
+                remote_dir: None,
+                remote_env: Vec::new(),
+                inputs: Vec::new(),
+                outputs: Vec::new(),
+                port: None,
+                matrix: Default::default(),
+                matrix_parallel: false,
+                env_files: None,
+                retries: 0,
+                retry_backoff: None,
+                retry_exponential: false,
+                prompt: Vec::new(),
+                cwd: None,
+                umask: None,
+                nice: None,
+                max_memory: None,
+                cpu: None,
+                memory: None,
+                git_note: false,
+                parser: None,
+                before: Vec::new(),
+                after: Vec::new(),
+                on_failure: Vec::new(),
             },
             TaskConfig {
                 name: "test".to_string(),
@@ -3418,8 +4644,34 @@ mod tests {
                 shortcuts: Vec::new(),
                 interactive: false,
                 confirm_on_match: false,
+                confirm: None,
+                protected: false,
                 on_cancel: None,
                 output_file: None,
+                host: None,
+                remote_dir: None,
+                remote_env: Vec::new(),
+                inputs: Vec::new(),
+                outputs: Vec::new(),
+                port: None,
+                matrix: Default::default(),
+                matrix_parallel: false,
+                env_files: None,
+                retries: 0,
+                retry_backoff: None,
+                retry_exponential: false,
+                prompt: Vec::new(),
+                cwd: None,
+                umask: None,
+                nice: None,
+                max_memory: None,
+                cpu: None,
+                memory: None,
+                git_note: false,
+                parser: None,
+                before: Vec::new(),
+                after: Vec::new(),
+                on_failure: Vec::new(),
             },
         ];
 
@@ -3446,8 +4698,34 @@ mod tests {
                 shortcuts: Vec::new(),
                 interactive: false,
                 confirm_on_match: false,
+                confirm: None,
+                protected: false,
                 on_cancel: None,
                 output_file: None,
+                host: None,
+                remote_dir: None,
+                remote_env: Vec::new(),
+                inputs: Vec::new(),
+                outputs: Vec::new(),
+                port: None,
+                matrix: Default::default(),
+                matrix_parallel: false,
+                env_files: None,
+                retries: 0,
+                retry_backoff: None,
+                retry_exponential: false,
+                prompt: Vec::new(),
+                cwd: None,
+                umask: None,
+                nice: None,
+                max_memory: None,
+                cpu: None,
+                memory: None,
+                git_note: false,
+                parser: None,
+                before: Vec::new(),
+                after: Vec::new(),
+                on_failure: Vec::new(),
             },
             config_path: PathBuf::from(format!("{}/flow.toml", scope)),
             relative_dir: relative_dir.to_string(),
@@ -3593,8 +4871,34 @@ command = "echo setup"
             shortcuts: Vec::new(),
             interactive: false,
             confirm_on_match: false,
+            confirm: None,
+            protected: false,
             on_cancel: None,
             output_file: None,
+            host: None,
+            remote_dir: None,
+            remote_env: Vec::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            port: None,
+            matrix: Default::default(),
+            matrix_parallel: false,
+            env_files: None,
+            retries: 0,
+            retry_backoff: None,
+            retry_exponential: false,
+            prompt: Vec::new(),
+            cwd: None,
+            umask: None,
+            nice: None,
+            max_memory: None,
+            cpu: None,
+            memory: None,
+            git_note: false,
+            parser: None,
+            before: Vec::new(),
+            after: Vec::new(),
+            on_failure: Vec::new(),
         };
         let empty_args: Vec<String> = Vec::new();
         let err = execute_task(
@@ -3608,6 +4912,7 @@ command = "echo setup"
             "",
             &empty_args,
             &task.name,
+            None,
         )
         .unwrap_err();
         assert!(
@@ -3636,8 +4941,34 @@ command = "echo setup"
             shortcuts: Vec::new(),
             interactive: false,
             confirm_on_match: false,
+            confirm: None,
+            protected: false,
             on_cancel: None,
             output_file: None,
+            host: None,
+            remote_dir: None,
+            remote_env: Vec::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            port: None,
+            matrix: Default::default(),
+            matrix_parallel: false,
+            env_files: None,
+            retries: 0,
+            retry_backoff: None,
+            retry_exponential: false,
+            prompt: Vec::new(),
+            cwd: None,
+            umask: None,
+            nice: None,
+            max_memory: None,
+            cpu: None,
+            memory: None,
+            git_note: false,
+            parser: None,
+            before: Vec::new(),
+            after: Vec::new(),
+            on_failure: Vec::new(),
         };
 
         let resolved = resolve_task_dependencies(&task, &cfg).expect("dependencies should resolve");
@@ -3672,8 +5003,34 @@ command = "echo setup"
             shortcuts: Vec::new(),
             interactive: false,
             confirm_on_match: false,
+            confirm: None,
+            protected: false,
             on_cancel: None,
             output_file: None,
+            host: None,
+            remote_dir: None,
+            remote_env: Vec::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            port: None,
+            matrix: Default::default(),
+            matrix_parallel: false,
+            env_files: None,
+            retries: 0,
+            retry_backoff: None,
+            retry_exponential: false,
+            prompt: Vec::new(),
+            cwd: None,
+            umask: None,
+            nice: None,
+            max_memory: None,
+            cpu: None,
+            memory: None,
+            git_note: false,
+            parser: None,
+            before: Vec::new(),
+            after: Vec::new(),
+            on_failure: Vec::new(),
         };
 
         let resolved = resolve_task_dependencies(&task, &cfg).expect("dependencies should resolve");
@@ -3709,8 +5066,34 @@ command = "echo setup"
             shortcuts: Vec::new(),
             interactive: false,
             confirm_on_match: false,
+            confirm: None,
+            protected: false,
             on_cancel: None,
             output_file: None,
+            host: None,
+            remote_dir: None,
+            remote_env: Vec::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            port: None,
+            matrix: Default::default(),
+            matrix_parallel: false,
+            env_files: None,
+            retries: 0,
+            retry_backoff: None,
+            retry_exponential: false,
+            prompt: Vec::new(),
+            cwd: None,
+            umask: None,
+            nice: None,
+            max_memory: None,
+            cpu: None,
+            memory: None,
+            git_note: false,
+            parser: None,
+            before: Vec::new(),
+            after: Vec::new(),
+            on_failure: Vec::new(),
         };
 
         let resolved = resolve_task_dependencies(&task, &cfg).expect("dependencies should resolve");
@@ -3733,8 +5116,34 @@ command = "echo setup"
             shortcuts: Vec::new(),
             interactive: false,
             confirm_on_match: false,
+            confirm: None,
+            protected: false,
             on_cancel: None,
             output_file: None,
+            host: None,
+            remote_dir: None,
+            remote_env: Vec::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            port: None,
+            matrix: Default::default(),
+            matrix_parallel: false,
+            env_files: None,
+            retries: 0,
+            retry_backoff: None,
+            retry_exponential: false,
+            prompt: Vec::new(),
+            cwd: None,
+            umask: None,
+            nice: None,
+            max_memory: None,
+            cpu: None,
+            memory: None,
+            git_note: false,
+            parser: None,
+            before: Vec::new(),
+            after: Vec::new(),
+            on_failure: Vec::new(),
         };
 
         let err = resolve_task_dependencies(&task, &cfg).unwrap_err();
@@ -3759,8 +5168,34 @@ command = "echo setup"
             shortcuts: Vec::new(),
             interactive: false,
             confirm_on_match: false,
+            confirm: None,
+            protected: false,
             on_cancel: None,
             output_file: None,
+            host: None,
+            remote_dir: None,
+            remote_env: Vec::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            port: None,
+            matrix: Default::default(),
+            matrix_parallel: false,
+            env_files: None,
+            retries: 0,
+            retry_backoff: None,
+            retry_exponential: false,
+            prompt: Vec::new(),
+            cwd: None,
+            umask: None,
+            nice: None,
+            max_memory: None,
+            cpu: None,
+            memory: None,
+            git_note: false,
+            parser: None,
+            before: Vec::new(),
+            after: Vec::new(),
+            on_failure: Vec::new(),
         };
 
         let err = resolve_task_dependencies(&task, &cfg).unwrap_err();
@@ -3784,8 +5219,34 @@ command = "echo setup"
                 shortcuts: vec!["dcr-alias".into()],
                 interactive: false,
                 confirm_on_match: false,
+                confirm: None,
+                protected: false,
                 on_cancel: None,
                 output_file: None,
+                host: None,
+                remote_dir: None,
+                remote_env: Vec::new(),
+                inputs: Vec::new(),
+                outputs: Vec::new(),
+                port: None,
+                matrix: Default::default(),
+                matrix_parallel: false,
+                env_files: None,
+                retries: 0,
+                retry_backoff: None,
+                retry_exponential: false,
+                prompt: Vec::new(),
+                cwd: None,
+                umask: None,
+                nice: None,
+                max_memory: None,
+                cpu: None,
+                memory: None,
+                git_note: false,
+                parser: None,
+                before: Vec::new(),
+                after: Vec::new(),
+                on_failure: Vec::new(),
             },
             TaskConfig {
                 name: "dev-hub".into(),
@@ -3797,8 +5258,34 @@ command = "echo setup"
                 shortcuts: Vec::new(),
                 interactive: false,
                 confirm_on_match: false,
+                confirm: None,
+                protected: false,
                 on_cancel: None,
                 output_file: None,
+                host: None,
+                remote_dir: None,
+                remote_env: Vec::new(),
+                inputs: Vec::new(),
+                outputs: Vec::new(),
+                port: None,
+                matrix: Default::default(),
+                matrix_parallel: false,
+                env_files: None,
+                retries: 0,
+                retry_backoff: None,
+                retry_exponential: false,
+                prompt: Vec::new(),
+                cwd: None,
+                umask: None,
+                nice: None,
+                max_memory: None,
+                cpu: None,
+                memory: None,
+                git_note: false,
+                parser: None,
+                before: Vec::new(),
+                after: Vec::new(),
+                on_failure: Vec::new(),
             },
         ];
 
@@ -3829,8 +5316,34 @@ command = "echo setup"
                 shortcuts: Vec::new(),
                 interactive: false,
                 confirm_on_match: false,
+                confirm: None,
+                protected: false,
                 on_cancel: None,
                 output_file: None,
+                host: None,
+                remote_dir: None,
+                remote_env: Vec::new(),
+                inputs: Vec::new(),
+                outputs: Vec::new(),
+                port: None,
+                matrix: Default::default(),
+                matrix_parallel: false,
+                env_files: None,
+                retries: 0,
+                retry_backoff: None,
+                retry_exponential: false,
+                prompt: Vec::new(),
+                cwd: None,
+                umask: None,
+                nice: None,
+                max_memory: None,
+                cpu: None,
+                memory: None,
+                git_note: false,
+                parser: None,
+                before: Vec::new(),
+                after: Vec::new(),
+                on_failure: Vec::new(),
             },
             TaskConfig {
                 name: "deploy-core-runner".into(),
@@ -3842,8 +5355,34 @@ command = "echo setup"
                 shortcuts: Vec::new(),
                 interactive: false,
                 confirm_on_match: false,
+                confirm: None,
+                protected: false,
                 on_cancel: None,
                 output_file: None,
+                host: None,
+                remote_dir: None,
+                remote_env: Vec::new(),
+                inputs: Vec::new(),
+                outputs: Vec::new(),
+                port: None,
+                matrix: Default::default(),
+                matrix_parallel: false,
+                env_files: None,
+                retries: 0,
+                retry_backoff: None,
+                retry_exponential: false,
+                prompt: Vec::new(),
+                cwd: None,
+                umask: None,
+                nice: None,
+                max_memory: None,
+                cpu: None,
+                memory: None,
+                git_note: false,
+                parser: None,
+                before: Vec::new(),
+                after: Vec::new(),
+                on_failure: Vec::new(),
             },
         ];
 
@@ -3875,4 +5414,97 @@ command = "echo setup"
             "source .env && bun script.ts --delete"
         ));
     }
+
+    #[test]
+    fn env_files_later_files_override_earlier_and_missing_are_skipped() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(
+            tmp.path().join(".env"),
+            "PORT=3000\nAPI_URL=http://localhost\nexport REGION=eu\nnot valid=1\n",
+        )
+        .unwrap();
+        fs::write(tmp.path().join(".env.local"), "PORT=4000\n").unwrap();
+
+        let files = vec![
+            ".env".to_string(),
+            ".env.local".to_string(),
+            ".env.missing".to_string(),
+        ];
+        let vars = load_env_files(&files, tmp.path()).unwrap();
+        let found: Vec<(&str, &str, &str)> = vars
+            .iter()
+            .map(|var| (var.key.as_str(), var.value.as_str(), var.source.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("API_URL", "http://localhost", ".env"),
+                ("PORT", "4000", ".env.local"),
+                ("REGION", "eu", ".env"),
+            ]
+        );
+    }
+
+    #[test]
+    fn hooks_must_name_other_tasks() {
+        let cfg: Config = toml::from_str(
+            r#"
+[[tasks]]
+name = "deploy"
+command = "./deploy.sh"
+before = ["build"]
+after = ["cleanup"]
+on_failure = ["deploy"]
+
+[[tasks]]
+name = "build"
+command = "cargo build"
+after = ["notify"]
+
+[[tasks]]
+name = "cleanup"
+command = "rm -rf tmp"
+"#,
+        )
+        .unwrap();
+        let task = |name: &str| cfg.tasks.iter().find(|t| t.name == name).unwrap();
+
+        assert!(validate_hooks(task("cleanup"), &cfg).is_ok());
+        let err = validate_hooks(task("deploy"), &cfg).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("lists itself in its on_failure tasks")
+        );
+        let err = validate_hooks(task("build"), &cfg).unwrap_err();
+        assert!(err.to_string().contains("after task 'notify' not found"));
+    }
+
+    #[test]
+    fn hooks_must_not_form_a_cycle() {
+        let cfg: Config = toml::from_str(
+            r#"
+[[tasks]]
+name = "a"
+command = "true"
+before = ["b"]
+
+[[tasks]]
+name = "b"
+command = "true"
+after = ["a"]
+
+[[tasks]]
+name = "c"
+command = "true"
+before = ["b"]
+"#,
+        )
+        .unwrap();
+        let task = |name: &str| cfg.tasks.iter().find(|t| t.name == name).unwrap();
+
+        let err = validate_hooks(task("a"), &cfg).unwrap_err();
+        assert_eq!(err.to_string(), "task 'a': hook cycle a -> b -> a");
+        let err = validate_hooks(task("c"), &cfg).unwrap_err();
+        assert_eq!(err.to_string(), "task 'c': hook cycle b -> a -> b");
+    }
 }
@@ -0,0 +1,39 @@
+//! Lookback windows like `30m` or `2d`, shared by the commands that take `--since`-style
+//! durations.
+
+use anyhow::{Context, Result, bail};
+
+/// Parse a lookback window like `30m`, `1h`, `2d`, or `90s` into milliseconds.
+pub fn parse_since(raw: &str) -> Result<i64> {
+    let raw = raw.trim().to_ascii_lowercase();
+    let split = raw
+        .find(|ch: char| !ch.is_ascii_digit())
+        .unwrap_or(raw.len());
+    let (num, unit) = raw.split_at(split);
+    let value: i64 = num
+        .parse()
+        .with_context(|| format!("invalid duration '{raw}' (expected e.g. 30m, 1h, 2d)"))?;
+    let unit_ms = match unit {
+        "ms" => 1,
+        "s" | "" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        "d" => 86_400_000,
+        other => bail!("invalid duration unit '{other}' (use s, m, h, or d)"),
+    };
+    Ok(value.saturating_mul(unit_ms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_since_windows() {
+        assert_eq!(parse_since("90s").unwrap(), 90_000);
+        assert_eq!(parse_since("1h").unwrap(), 3_600_000);
+        assert_eq!(parse_since("2d").unwrap(), 172_800_000);
+        assert!(parse_since("soon").is_err());
+        assert!(parse_since("5w").is_err());
+    }
+}
@@ -431,19 +431,19 @@ fn run_command(command: &str, args: &[&str], cwd: &Path) -> Result<()> {
 }
 
 #[cfg(target_os = "macos")]
-fn open_in_browser(url: &str) -> Result<()> {
+pub(crate) fn open_in_browser(url: &str) -> Result<()> {
     std::process::Command::new("open").arg(url).status()?;
     Ok(())
 }
 
 #[cfg(target_os = "linux")]
-fn open_in_browser(url: &str) -> Result<()> {
+pub(crate) fn open_in_browser(url: &str) -> Result<()> {
     std::process::Command::new("xdg-open").arg(url).status()?;
     Ok(())
 }
 
 #[cfg(not(any(target_os = "macos", target_os = "linux")))]
-fn open_in_browser(url: &str) -> Result<()> {
+pub(crate) fn open_in_browser(url: &str) -> Result<()> {
     println!("Open this URL in your browser: {url}");
     Ok(())
 }